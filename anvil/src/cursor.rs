@@ -1,5 +1,6 @@
 use std::{io::Read, time::Duration};
 
+use smithay::input::pointer::CursorSize;
 use tracing::warn;
 use xcursor::{
     parser::{parse_xcursor, Image},
@@ -10,7 +11,7 @@ static FALLBACK_CURSOR_DATA: &[u8] = include_bytes!("../resources/cursor.rgba");
 
 pub struct Cursor {
     icons: Vec<Image>,
-    size: u32,
+    size: CursorSize,
 }
 
 impl Cursor {
@@ -18,10 +19,7 @@ impl Cursor {
         let name = std::env::var("XCURSOR_THEME")
             .ok()
             .unwrap_or_else(|| "default".into());
-        let size = std::env::var("XCURSOR_SIZE")
-            .ok()
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(24);
+        let size = CursorSize::from_env();
 
         let theme = CursorTheme::load(&name);
         let icons = load_icon(&theme)
@@ -43,7 +41,7 @@ impl Cursor {
     }
 
     pub fn get_image(&self, scale: u32, time: Duration) -> Image {
-        let size = self.size * scale;
+        let size = self.size.for_scale(scale as f64);
         frame(time.as_millis() as u32, size, &self.icons)
     }
 }
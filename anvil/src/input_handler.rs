@@ -26,7 +26,7 @@ use smithay::{
     wayland::{
         compositor::with_states,
         input_method::InputMethodSeat,
-        keyboard_shortcuts_inhibit::KeyboardShortcutsInhibitorSeat,
+        keyboard_shortcuts_inhibit::{EscapeChord, ShortcutFilterResult, ShortcutsInhibitFilter},
         shell::wlr_layer::{KeyboardInteractivity, Layer as WlrLayer, LayerSurfaceCachedState},
     },
 };
@@ -165,18 +165,20 @@ impl<BackendData: Backend> AnvilState<BackendData> {
             }
         }
 
-        let inhibited = self
+        let focus = self
             .space
             .element_under(self.pointer.current_location())
-            .and_then(|(window, _)| {
-                let surface = window.wl_surface()?;
-                self.seat.keyboard_shortcuts_inhibitor_for_surface(&surface)
-            })
-            .map(|inhibitor| inhibitor.is_active())
-            .unwrap_or(false);
+            .and_then(|(window, _)| window.wl_surface().map(|surface| surface.into_owned()));
+        // ctrl+alt+escape always reaches the compositor
+        let shortcuts =
+            ShortcutsInhibitFilter::new(&self.seat, focus.as_ref()).with_escape_chord(EscapeChord {
+                ctrl: true,
+                alt: true,
+                ..EscapeChord::new(Keysym::Escape)
+            });
 
         let action = keyboard
-            .input(self, keycode, state, serial, time, |_, modifiers, handle| {
+            .input(self, keycode, state, serial, time, |data, modifiers, handle| {
                 let keysym = handle.modified_sym();
 
                 debug!(
@@ -192,18 +194,18 @@ impl<BackendData: Backend> AnvilState<BackendData> {
                 // so that we can decide on a release if the key
                 // should be forwarded to the client or not.
                 if let KeyState::Pressed = state {
-                    if !inhibited {
-                        let action = process_keyboard_shortcut(*modifiers, keysym);
-
-                        if action.is_some() {
+                    let shortcut = process_keyboard_shortcut(*modifiers, keysym);
+                    match shortcuts.filter(data, modifiers, &handle, shortcut) {
+                        ShortcutFilterResult::Shortcut(action) => {
                             suppressed_keys.push(keysym);
+                            FilterResult::Intercept(action)
                         }
-
-                        action
-                            .map(FilterResult::Intercept)
-                            .unwrap_or(FilterResult::Forward)
-                    } else {
-                        FilterResult::Forward
+                        ShortcutFilterResult::Escape(inhibitor) => {
+                            inhibitor.inactivate();
+                            suppressed_keys.push(keysym);
+                            FilterResult::Intercept(KeyAction::None)
+                        }
+                        ShortcutFilterResult::Forward => FilterResult::Forward,
                     }
                 } else {
                     let suppressed = suppressed_keys.contains(&keysym);
@@ -349,7 +351,7 @@ impl<BackendData: Backend> AnvilState<BackendData> {
             .and_then(|f| f.get())
             .and_then(|w| w.surface_under(pos - output_geo.loc.to_f64(), WindowSurfaceType::ALL))
         {
-            under = Some((surface, loc + output_geo.loc));
+            under = Some((surface, loc + output_geo.loc.to_f64()));
         } else if let Some(focus) = layers
             .layer_under(WlrLayer::Overlay, pos - output_geo.loc.to_f64())
             .or_else(|| layers.layer_under(WlrLayer::Top, pos - output_geo.loc.to_f64()))
@@ -363,15 +365,15 @@ impl<BackendData: Backend> AnvilState<BackendData> {
                     .map(|(surface, loc)| {
                         (
                             PointerFocusTarget::from(surface),
-                            loc + layer_loc + output_geo.loc,
+                            (loc + layer_loc + output_geo.loc).to_f64(),
                         )
                     })
             })
         {
             under = Some(focus)
-        } else if let Some(focus) = self.space.element_under(pos).and_then(|(window, loc)| {
+        } else if let Some(focus) = self.space.element_under_f64(pos).and_then(|(window, loc)| {
             window
-                .surface_under(pos - loc.to_f64(), WindowSurfaceType::ALL)
+                .surface_under(pos - loc, WindowSurfaceType::ALL)
                 .map(|(surface, surf_loc)| (surface, surf_loc + loc))
         }) {
             under = Some(focus);
@@ -388,14 +390,14 @@ impl<BackendData: Backend> AnvilState<BackendData> {
                     .map(|(surface, loc)| {
                         (
                             PointerFocusTarget::from(surface),
-                            loc + layer_loc + output_geo.loc,
+                            (loc + layer_loc + output_geo.loc).to_f64(),
                         )
                     })
             })
         {
             under = Some(focus)
         };
-        under.map(|(s, l)| (s, l.to_f64()))
+        under
     }
 
     fn on_pointer_axis<B: InputBackend>(&mut self, evt: B::PointerAxisEvent) {
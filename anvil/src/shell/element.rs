@@ -38,18 +38,18 @@ impl WindowElement {
         &self,
         location: Point<f64, Logical>,
         window_type: WindowSurfaceType,
-    ) -> Option<(PointerFocusTarget, Point<i32, Logical>)> {
+    ) -> Option<(PointerFocusTarget, Point<f64, Logical>)> {
         let state = self.decoration_state();
         if state.is_ssd && location.y < HEADER_BAR_HEIGHT as f64 {
             return Some((PointerFocusTarget::SSD(SSD(self.clone())), Point::default()));
         }
         let offset = if state.is_ssd {
-            Point::from((0, HEADER_BAR_HEIGHT))
+            Point::from((0.0, HEADER_BAR_HEIGHT as f64))
         } else {
             Point::default()
         };
 
-        let surface_under = self.0.surface_under(location - offset.to_f64(), window_type);
+        let surface_under = self.0.surface_under(location - offset, window_type);
         let (under, loc) = match self.0.underlying_surface() {
             WindowSurface::Wayland(_) => {
                 surface_under.map(|(surface, loc)| (PointerFocusTarget::WlSurface(surface), loc))
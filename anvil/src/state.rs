@@ -5,7 +5,7 @@ use std::{
     time::Duration,
 };
 
-use tracing::{info, warn};
+use tracing::{debug, info, warn};
 
 use smithay::{
     backend::{
@@ -14,11 +14,12 @@ use smithay::{
             default_primary_scanout_output_compare, utils::select_dmabuf_feedback, RenderElementStates,
         },
     },
-    delegate_compositor, delegate_data_control, delegate_data_device, delegate_fractional_scale,
-    delegate_input_method_manager, delegate_keyboard_shortcuts_inhibit, delegate_layer_shell,
-    delegate_output, delegate_pointer_constraints, delegate_pointer_gestures, delegate_presentation,
-    delegate_primary_selection, delegate_relative_pointer, delegate_seat, delegate_security_context,
-    delegate_shm, delegate_tablet_manager, delegate_text_input_manager, delegate_viewporter,
+    delegate_compositor, delegate_data_control, delegate_data_device, delegate_ext_data_control,
+    delegate_fractional_scale, delegate_input_method_manager, delegate_kde_decoration,
+    delegate_keyboard_shortcuts_inhibit, delegate_layer_shell, delegate_output, delegate_pointer_constraints,
+    delegate_pointer_gestures, delegate_presentation, delegate_primary_selection, delegate_relative_pointer,
+    delegate_seat, delegate_security_context, delegate_shm, delegate_tablet_manager,
+    delegate_text_input_manager, delegate_transient_seat, delegate_viewporter,
     delegate_virtual_keyboard_manager, delegate_xdg_activation, delegate_xdg_decoration, delegate_xdg_shell,
     desktop::{
         space::SpaceElement,
@@ -30,27 +31,36 @@ use smithay::{
     },
     input::{
         keyboard::{Keysym, LedState, XkbConfig},
-        pointer::{CursorImageStatus, CursorImageSurfaceData, PointerHandle},
+        pointer::{CursorImageStatus, CursorImageSurfaceData, MotionEvent, PointerHandle},
         Seat, SeatHandler, SeatState,
     },
     output::Output,
     reexports::{
         calloop::{generic::Generic, Interest, LoopHandle, Mode, PostAction},
-        wayland_protocols::xdg::decoration::{
-            self as xdg_decoration, zv1::server::zxdg_toplevel_decoration_v1::Mode as DecorationMode,
+        wayland_protocols::{
+            wp::color_representation::v1::server::wp_color_representation_surface_v1::{
+                AlphaMode, Coefficients, Range,
+            },
+            xdg::decoration::zv1::server::zxdg_toplevel_decoration_v1::Mode as XdgDecorationMode,
+        },
+        wayland_protocols_misc::server_decoration::server::org_kde_kwin_server_decoration::{
+            Mode as KdeDecorationMode, OrgKdeKwinServerDecoration,
         },
         wayland_server::{
             backend::{ClientData, ClientId, DisconnectReason},
-            protocol::{wl_data_source::WlDataSource, wl_surface::WlSurface},
-            Client, Display, DisplayHandle, Resource,
+            protocol::{
+                wl_data_device_manager::DndAction, wl_data_source::WlDataSource, wl_surface::WlSurface,
+            },
+            Client, Display, DisplayHandle, Resource, WEnum,
         },
     },
-    utils::{Clock, Logical, Monotonic, Point, Rectangle, Time},
+    utils::{Clock, Logical, Monotonic, Point, Rectangle, Time, SERIAL_COUNTER},
     wayland::{
-        commit_timing::{CommitTimerBarrierStateUserData, CommitTimingManagerState},
+        color_representation::ColorRepresentationState,
+        commit_timing::{signal_commit_timers, CommitTimingManagerState},
         compositor::{get_parent, with_states, CompositorClientState, CompositorHandler, CompositorState},
         dmabuf::DmabufFeedback,
-        fifo::{FifoBarrierCachedState, FifoManagerState},
+        fifo::{signal_fifo_barrier, FifoManagerState},
         fractional_scale::{with_fractional_scale, FractionalScaleHandler, FractionalScaleManagerState},
         input_method::{InputMethodHandler, InputMethodManagerState, PopupSurface},
         keyboard_shortcuts_inhibit::{
@@ -59,7 +69,9 @@ use smithay::{
         output::{OutputHandler, OutputManagerState},
         pointer_constraints::{with_pointer_constraint, PointerConstraintsHandler, PointerConstraintsState},
         pointer_gestures::PointerGesturesState,
+        pointer_warp::{PointerWarpHandler, PointerWarpManager},
         presentation::PresentationState,
+        protocol_error::{ProtocolErrorHandler, ProtocolErrorReport},
         relative_pointer::RelativePointerManagerState,
         seat::WaylandFocus,
         security_context::{
@@ -67,14 +79,19 @@ use smithay::{
         },
         selection::{
             data_device::{
-                set_data_device_focus, ClientDndGrabHandler, DataDeviceHandler, DataDeviceState,
-                ServerDndGrabHandler,
+                modifier_action_chooser, set_data_device_focus, ClientDndGrabHandler, DataDeviceHandler,
+                DataDeviceState, ServerDndGrabHandler,
+            },
+            ext_data_control::{
+                DataControlHandler as ExtDataControlHandler, DataControlState as ExtDataControlState,
             },
             primary_selection::{set_primary_focus, PrimarySelectionHandler, PrimarySelectionState},
             wlr_data_control::{DataControlHandler, DataControlState},
             SelectionHandler,
         },
         shell::{
+            decoration::{DecorationMode, DecorationPolicy},
+            kde::decoration::{KdeDecorationHandler, KdeDecorationState},
             wlr_layer::WlrLayerShellState,
             xdg::{
                 decoration::{XdgDecorationHandler, XdgDecorationState},
@@ -85,13 +102,16 @@ use smithay::{
         single_pixel_buffer::SinglePixelBufferState,
         socket::ListeningSocketSource,
         tablet_manager::{TabletManagerState, TabletSeatHandler},
+        tearing_control::TearingControlState,
         text_input::TextInputManagerState,
+        transient_seat::{TransientSeatHandler, TransientSeatManagerState},
         viewporter::ViewporterState,
         virtual_keyboard::VirtualKeyboardManagerState,
         xdg_activation::{
             XdgActivationHandler, XdgActivationState, XdgActivationToken, XdgActivationTokenData,
         },
         xdg_foreign::{XdgForeignHandler, XdgForeignState},
+        xdg_system_bell::{XdgSystemBellHandler, XdgSystemBellState},
     },
 };
 
@@ -99,7 +119,7 @@ use smithay::{
 use crate::cursor::Cursor;
 use crate::{
     focus::{KeyboardFocusTarget, PointerFocusTarget},
-    shell::WindowElement,
+    shell::{ssd::HEADER_BAR_HEIGHT, WindowElement},
 };
 #[cfg(feature = "xwayland")]
 use smithay::{
@@ -142,12 +162,14 @@ pub struct AnvilState<BackendData: Backend + 'static> {
     pub output_manager_state: OutputManagerState,
     pub primary_selection_state: PrimarySelectionState,
     pub data_control_state: DataControlState,
+    pub ext_data_control_state: ExtDataControlState,
     pub seat_state: SeatState<AnvilState<BackendData>>,
     pub keyboard_shortcuts_inhibit_state: KeyboardShortcutsInhibitState,
     pub shm_state: ShmState,
     pub viewporter_state: ViewporterState,
     pub xdg_activation_state: XdgActivationState,
     pub xdg_decoration_state: XdgDecorationState,
+    pub kde_decoration_state: KdeDecorationState,
     pub xdg_shell_state: XdgShellState,
     pub presentation_state: PresentationState,
     pub fractional_scale_manager_state: FractionalScaleManagerState,
@@ -187,10 +209,36 @@ pub struct DndIcon {
 
 delegate_compositor!(@<BackendData: Backend + 'static> AnvilState<BackendData>);
 
+impl<BackendData: Backend> ProtocolErrorHandler for AnvilState<BackendData> {
+    fn protocol_error(&mut self, report: ProtocolErrorReport) {
+        warn!(
+            client = ?report.client,
+            credentials = ?report.credentials,
+            kind = ?report.kind,
+            "Protocol error on {}: {}",
+            report.object,
+            report.message
+        );
+    }
+}
+
 impl<BackendData: Backend> DataDeviceHandler for AnvilState<BackendData> {
     fn data_device_state(&self) -> &DataDeviceState {
         &self.data_device_state
     }
+
+    fn dnd_action_choice(
+        &mut self,
+        seat: &Seat<Self>,
+        available: DndAction,
+        preferred: DndAction,
+    ) -> DndAction {
+        let modifiers = seat
+            .get_keyboard()
+            .map(|keyboard| keyboard.modifier_state())
+            .unwrap_or_default();
+        modifier_action_chooser(available, preferred, &modifiers)
+    }
 }
 
 impl<BackendData: Backend> ClientDndGrabHandler for AnvilState<BackendData> {
@@ -269,6 +317,14 @@ impl<BackendData: Backend> DataControlHandler for AnvilState<BackendData> {
 
 delegate_data_control!(@<BackendData: Backend + 'static> AnvilState<BackendData>);
 
+impl<BackendData: Backend> ExtDataControlHandler for AnvilState<BackendData> {
+    fn data_control_state(&self) -> &ExtDataControlState {
+        &self.ext_data_control_state
+    }
+}
+
+delegate_ext_data_control!(@<BackendData: Backend + 'static> AnvilState<BackendData>);
+
 impl<BackendData: Backend> ShmHandler for AnvilState<BackendData> {
     fn shm_state(&self) -> &ShmState {
         &self.shm_state
@@ -348,12 +404,19 @@ impl<BackendData: Backend> KeyboardShortcutsInhibitHandler for AnvilState<Backen
         // Just grant the wish for everyone
         inhibitor.activate();
     }
+
+    fn shortcut_bypassed(&mut self, inhibitor: KeyboardShortcutsInhibitor) {
+        debug!(surface = ?inhibitor.wl_surface(), "Compositor shortcut passed through to client");
+    }
 }
 
 delegate_keyboard_shortcuts_inhibit!(@<BackendData: Backend + 'static> AnvilState<BackendData>);
 
 delegate_virtual_keyboard_manager!(@<BackendData: Backend + 'static> AnvilState<BackendData>);
 
+impl<BackendData: Backend> TransientSeatHandler for AnvilState<BackendData> {}
+delegate_transient_seat!(@<BackendData: Backend + 'static> AnvilState<BackendData>);
+
 delegate_pointer_gestures!(@<BackendData: Backend + 'static> AnvilState<BackendData>);
 
 delegate_relative_pointer!(@<BackendData: Backend + 'static> AnvilState<BackendData>);
@@ -396,6 +459,56 @@ impl<BackendData: Backend> PointerConstraintsHandler for AnvilState<BackendData>
 }
 delegate_pointer_constraints!(@<BackendData: Backend + 'static> AnvilState<BackendData>);
 
+impl<BackendData: Backend> PointerWarpHandler for AnvilState<BackendData> {
+    fn warp_pointer(
+        &mut self,
+        surface: WlSurface,
+        pointer: PointerHandle<Self>,
+        location: Point<f64, Logical>,
+    ) {
+        let Some(window) = self
+            .space
+            .elements()
+            .find(|window| window.wl_surface().as_deref() == Some(&surface))
+            .cloned()
+        else {
+            return;
+        };
+        let Some(window_loc) = self.space.element_location(&window) else {
+            return;
+        };
+
+        let mut origin = window_loc - window.geometry().loc;
+        if window.decoration_state().is_ssd {
+            origin.y += HEADER_BAR_HEIGHT;
+        }
+        let location = origin.to_f64() + location;
+
+        // Only warp onto visible parts of the surface
+        let under = self.surface_under(location);
+        if under
+            .as_ref()
+            .and_then(|(focus, _)| focus.wl_surface())
+            .as_deref()
+            != Some(&surface)
+        {
+            return;
+        }
+
+        pointer.motion(
+            self,
+            under,
+            &MotionEvent {
+                location,
+                serial: SERIAL_COUNTER.next_serial(),
+                time: self.clock.now().as_millis(),
+            },
+        );
+        pointer.frame(self);
+    }
+}
+smithay::delegate_pointer_warp!(@<BackendData: Backend + 'static> AnvilState<BackendData>);
+
 delegate_viewporter!(@<BackendData: Backend + 'static> AnvilState<BackendData>);
 
 impl<BackendData: Backend> XdgActivationHandler for AnvilState<BackendData> {
@@ -437,40 +550,62 @@ impl<BackendData: Backend> XdgActivationHandler for AnvilState<BackendData> {
 }
 delegate_xdg_activation!(@<BackendData: Backend + 'static> AnvilState<BackendData>);
 
+// Answer both decoration protocols the same way, following the client's preference
+const DECORATION_POLICY: DecorationPolicy = DecorationPolicy::PreferClientSide;
+
+fn set_decoration_mode(toplevel: &ToplevelSurface, mode: DecorationMode) {
+    toplevel.with_pending_state(|state| {
+        state.decoration_mode = Some(mode.into());
+    });
+
+    if toplevel.is_initial_configure_sent() {
+        toplevel.send_pending_configure();
+    }
+}
+
 impl<BackendData: Backend> XdgDecorationHandler for AnvilState<BackendData> {
     fn new_decoration(&mut self, toplevel: ToplevelSurface) {
-        use xdg_decoration::zv1::server::zxdg_toplevel_decoration_v1::Mode;
-        // Set the default to client side
         toplevel.with_pending_state(|state| {
-            state.decoration_mode = Some(Mode::ClientSide);
+            state.decoration_mode = Some(DECORATION_POLICY.default_mode().into());
         });
     }
-    fn request_mode(&mut self, toplevel: ToplevelSurface, mode: DecorationMode) {
-        use xdg_decoration::zv1::server::zxdg_toplevel_decoration_v1::Mode;
-
-        toplevel.with_pending_state(|state| {
-            state.decoration_mode = Some(match mode {
-                DecorationMode::ServerSide => Mode::ServerSide,
-                _ => Mode::ClientSide,
-            });
-        });
-
-        if toplevel.is_initial_configure_sent() {
-            toplevel.send_pending_configure();
-        }
+    fn request_mode(&mut self, toplevel: ToplevelSurface, mode: XdgDecorationMode) {
+        let mode = DECORATION_POLICY.negotiate(DecorationMode::from_xdg(mode));
+        set_decoration_mode(&toplevel, mode);
     }
     fn unset_mode(&mut self, toplevel: ToplevelSurface) {
-        use xdg_decoration::zv1::server::zxdg_toplevel_decoration_v1::Mode;
-        toplevel.with_pending_state(|state| {
-            state.decoration_mode = Some(Mode::ClientSide);
-        });
+        set_decoration_mode(&toplevel, DECORATION_POLICY.negotiate(None));
+    }
+}
+delegate_xdg_decoration!(@<BackendData: Backend + 'static> AnvilState<BackendData>);
 
-        if toplevel.is_initial_configure_sent() {
-            toplevel.send_pending_configure();
+impl<BackendData: Backend> KdeDecorationHandler for AnvilState<BackendData> {
+    fn kde_decoration_state(&self) -> &KdeDecorationState {
+        &self.kde_decoration_state
+    }
+
+    fn request_mode(
+        &mut self,
+        surface: &WlSurface,
+        decoration: &OrgKdeKwinServerDecoration,
+        mode: WEnum<KdeDecorationMode>,
+    ) {
+        let requested = mode.into_result().ok().and_then(DecorationMode::from_kde);
+        let mode = DECORATION_POLICY.negotiate(requested);
+        decoration.mode(mode.into());
+
+        // decorate the window just like an xdg-decoration client
+        if let Some(toplevel) = self
+            .xdg_shell_state
+            .toplevel_surfaces()
+            .iter()
+            .find(|toplevel| toplevel.wl_surface() == surface)
+        {
+            set_decoration_mode(toplevel, mode);
         }
     }
 }
-delegate_xdg_decoration!(@<BackendData: Backend + 'static> AnvilState<BackendData>);
+delegate_kde_decoration!(@<BackendData: Backend + 'static> AnvilState<BackendData>);
 
 delegate_xdg_shell!(@<BackendData: Backend + 'static> AnvilState<BackendData>);
 delegate_layer_shell!(@<BackendData: Backend + 'static> AnvilState<BackendData>);
@@ -547,6 +682,13 @@ delegate_security_context!(@<BackendData: Backend + 'static> AnvilState<BackendD
 
 #[cfg(feature = "xwayland")]
 impl<BackendData: Backend + 'static> XWaylandKeyboardGrabHandler for AnvilState<BackendData> {
+    fn allow_grab(&mut self, surface: &WlSurface, seat: &Seat<Self>) -> bool {
+        // only let the focused window grab the keyboard
+        seat.get_keyboard()
+            .and_then(|keyboard| keyboard.current_focus())
+            .is_some_and(|focus| focus.wl_surface().as_deref() == Some(surface))
+    }
+
     fn keyboard_focus_for_xsurface(&self, surface: &WlSurface) -> Option<KeyboardFocusTarget> {
         let elem = self
             .space
@@ -574,6 +716,16 @@ smithay::delegate_fifo!(@<BackendData: Backend + 'static> AnvilState<BackendData
 
 smithay::delegate_commit_timing!(@<BackendData: Backend + 'static> AnvilState<BackendData>);
 
+impl<BackendData: Backend> XdgSystemBellHandler for AnvilState<BackendData> {
+    fn ring(&mut self, surface: Option<WlSurface>) {
+        info!(?surface, "System bell rang");
+    }
+}
+smithay::delegate_xdg_system_bell!(@<BackendData: Backend + 'static> AnvilState<BackendData>);
+
+smithay::delegate_tearing_control!(@<BackendData: Backend + 'static> AnvilState<BackendData>);
+smithay::delegate_color_representation!(@<BackendData: Backend + 'static> AnvilState<BackendData>);
+
 impl<BackendData: Backend + 'static> AnvilState<BackendData> {
     pub fn init(
         display: Display<AnvilState<BackendData>>,
@@ -626,11 +778,15 @@ impl<BackendData: Backend + 'static> AnvilState<BackendData> {
         let primary_selection_state = PrimarySelectionState::new::<Self>(&dh);
         let data_control_state =
             DataControlState::new::<Self, _>(&dh, Some(&primary_selection_state), |_| true);
+        let ext_data_control_state =
+            ExtDataControlState::new::<Self, _>(&dh, Some(&primary_selection_state), |_| true);
         let mut seat_state = SeatState::new();
         let shm_state = ShmState::new::<Self>(&dh, vec![]);
         let viewporter_state = ViewporterState::new::<Self>(&dh);
         let xdg_activation_state = XdgActivationState::new::<Self>(&dh);
         let xdg_decoration_state = XdgDecorationState::new::<Self>(&dh);
+        let kde_decoration_state =
+            KdeDecorationState::new::<Self>(&dh, DECORATION_POLICY.default_mode().into());
         let xdg_shell_state = XdgShellState::new::<Self>(&dh);
         let presentation_state = PresentationState::new::<Self>(&dh, clock.id() as u32);
         let fractional_scale_manager_state = FractionalScaleManagerState::new::<Self>(&dh);
@@ -641,15 +797,35 @@ impl<BackendData: Backend + 'static> AnvilState<BackendData> {
         TextInputManagerState::new::<Self>(&dh);
         InputMethodManagerState::new::<Self, _>(&dh, |_client| true);
         VirtualKeyboardManagerState::new::<Self, _>(&dh, |_client| true);
+        XdgSystemBellState::new::<Self>(&dh);
+        TearingControlState::new::<Self>(&dh);
+        ColorRepresentationState::new::<Self>(
+            &dh,
+            [AlphaMode::PremultipliedElectrical],
+            [
+                (Coefficients::Bt601, Range::Limited),
+                (Coefficients::Bt601, Range::Full),
+                (Coefficients::Bt709, Range::Limited),
+                (Coefficients::Bt709, Range::Full),
+                (Coefficients::Bt2020, Range::Limited),
+                (Coefficients::Bt2020, Range::Full),
+            ],
+        );
         // Expose global only if backend supports relative motion events
         if BackendData::HAS_RELATIVE_MOTION {
             RelativePointerManagerState::new::<Self>(&dh);
         }
         PointerConstraintsState::new::<Self>(&dh);
+        PointerWarpManager::new::<Self>(&dh);
         if BackendData::HAS_GESTURES {
             PointerGesturesState::new::<Self>(&dh);
         }
         TabletManagerState::new::<Self>(&dh);
+        TransientSeatManagerState::new::<Self, _>(&dh, |client| {
+            client
+                .get_data::<ClientState>()
+                .map_or(true, |client_state| client_state.security_context.is_none())
+        });
         SecurityContextState::new::<Self, _>(&dh, |client| {
             client
                 .get_data::<ClientState>()
@@ -686,12 +862,14 @@ impl<BackendData: Backend + 'static> AnvilState<BackendData> {
             output_manager_state,
             primary_selection_state,
             data_control_state,
+            ext_data_control_state,
             seat_state,
             keyboard_shortcuts_inhibit_state,
             shm_state,
             viewporter_state,
             xdg_activation_state,
             xdg_decoration_state,
+            kde_decoration_state,
             xdg_shell_state,
             presentation_state,
             fractional_scale_manager_state,
@@ -781,12 +959,7 @@ impl<BackendData: Backend + 'static> AnvilState<BackendData> {
         let mut clients: HashMap<ClientId, Client> = HashMap::new();
         self.space.elements().for_each(|window| {
             window.with_surfaces(|surface, states| {
-                if let Some(mut commit_timer_state) = states
-                    .data_map
-                    .get::<CommitTimerBarrierStateUserData>()
-                    .map(|commit_timer| commit_timer.lock().unwrap())
-                {
-                    commit_timer_state.signal_until(frame_target);
+                if signal_commit_timers(states, frame_target) {
                     let client = surface.client().unwrap();
                     clients.insert(client.id(), client);
                 }
@@ -796,12 +969,7 @@ impl<BackendData: Backend + 'static> AnvilState<BackendData> {
         let map = smithay::desktop::layer_map_for_output(output);
         for layer_surface in map.layers() {
             layer_surface.with_surfaces(|surface, states| {
-                if let Some(mut commit_timer_state) = states
-                    .data_map
-                    .get::<CommitTimerBarrierStateUserData>()
-                    .map(|commit_timer| commit_timer.lock().unwrap())
-                {
-                    commit_timer_state.signal_until(frame_target);
+                if signal_commit_timers(states, frame_target) {
                     let client = surface.client().unwrap();
                     clients.insert(client.id(), client);
                 }
@@ -813,12 +981,7 @@ impl<BackendData: Backend + 'static> AnvilState<BackendData> {
 
         if let CursorImageStatus::Surface(ref surface) = self.cursor_status {
             with_surfaces_surface_tree(surface, |surface, states| {
-                if let Some(mut commit_timer_state) = states
-                    .data_map
-                    .get::<CommitTimerBarrierStateUserData>()
-                    .map(|commit_timer| commit_timer.lock().unwrap())
-                {
-                    commit_timer_state.signal_until(frame_target);
+                if signal_commit_timers(states, frame_target) {
                     let client = surface.client().unwrap();
                     clients.insert(client.id(), client);
                 }
@@ -827,12 +990,7 @@ impl<BackendData: Backend + 'static> AnvilState<BackendData> {
 
         if let Some(surface) = self.dnd_icon.as_ref().map(|icon| &icon.surface) {
             with_surfaces_surface_tree(surface, |surface, states| {
-                if let Some(mut commit_timer_state) = states
-                    .data_map
-                    .get::<CommitTimerBarrierStateUserData>()
-                    .map(|commit_timer| commit_timer.lock().unwrap())
-                {
-                    commit_timer_state.signal_until(frame_target);
+                if signal_commit_timers(states, frame_target) {
                     let client = surface.client().unwrap();
                     clients.insert(client.id(), client);
                 }
@@ -872,19 +1030,10 @@ impl<BackendData: Backend + 'static> AnvilState<BackendData> {
                     .as_ref()
                     .map(|o| o == output)
                     .unwrap_or(true)
+                    && signal_fifo_barrier(states)
                 {
-                    let fifo_barrier = states
-                        .cached_state
-                        .get::<FifoBarrierCachedState>()
-                        .current()
-                        .barrier
-                        .take();
-
-                    if let Some(fifo_barrier) = fifo_barrier {
-                        fifo_barrier.signal();
-                        let client = surface.client().unwrap();
-                        clients.insert(client.id(), client);
-                    }
+                    let client = surface.client().unwrap();
+                    clients.insert(client.id(), client);
                 }
             });
 
@@ -917,19 +1066,10 @@ impl<BackendData: Backend + 'static> AnvilState<BackendData> {
                     .as_ref()
                     .map(|o| o == output)
                     .unwrap_or(true)
+                    && signal_fifo_barrier(states)
                 {
-                    let fifo_barrier = states
-                        .cached_state
-                        .get::<FifoBarrierCachedState>()
-                        .current()
-                        .barrier
-                        .take();
-
-                    if let Some(fifo_barrier) = fifo_barrier {
-                        fifo_barrier.signal();
-                        let client = surface.client().unwrap();
-                        clients.insert(client.id(), client);
-                    }
+                    let client = surface.client().unwrap();
+                    clients.insert(client.id(), client);
                 }
             });
 
@@ -963,19 +1103,10 @@ impl<BackendData: Backend + 'static> AnvilState<BackendData> {
                     .as_ref()
                     .map(|o| o == output)
                     .unwrap_or(true)
+                    && signal_fifo_barrier(states)
                 {
-                    let fifo_barrier = states
-                        .cached_state
-                        .get::<FifoBarrierCachedState>()
-                        .current()
-                        .barrier
-                        .take();
-
-                    if let Some(fifo_barrier) = fifo_barrier {
-                        fifo_barrier.signal();
-                        let client = surface.client().unwrap();
-                        clients.insert(client.id(), client);
-                    }
+                    let client = surface.client().unwrap();
+                    clients.insert(client.id(), client);
                 }
             });
         }
@@ -994,19 +1125,10 @@ impl<BackendData: Backend + 'static> AnvilState<BackendData> {
                     .as_ref()
                     .map(|o| o == output)
                     .unwrap_or(true)
+                    && signal_fifo_barrier(states)
                 {
-                    let fifo_barrier = states
-                        .cached_state
-                        .get::<FifoBarrierCachedState>()
-                        .current()
-                        .barrier
-                        .take();
-
-                    if let Some(fifo_barrier) = fifo_barrier {
-                        fifo_barrier.signal();
-                        let client = surface.client().unwrap();
-                        clients.insert(client.id(), client);
-                    }
+                    let client = surface.client().unwrap();
+                    clients.insert(client.id(), client);
                 }
             });
         }
@@ -1,6 +1,5 @@
 use std::{
     collections::hash_map::HashMap,
-    io,
     ops::Not,
     path::Path,
     sync::{atomic::Ordering, Mutex, Once},
@@ -35,12 +34,11 @@ use smithay::{
             compositor::{DrmCompositor, FrameFlags},
             exporter::gbm::GbmFramebufferExporter,
             output::{DrmOutput, DrmOutputManager, DrmOutputRenderElements},
-            CreateDrmNodeError, DrmAccessError, DrmDevice, DrmDeviceFd, DrmError, DrmEvent, DrmEventMetadata,
+            CreateDrmNodeError, DrmDevice, DrmDeviceFd, DrmError, DrmErrorKind, DrmEvent, DrmEventMetadata,
             DrmEventTime, DrmNode, DrmSurface, GbmBufferedSurface, NodeType,
         },
         egl::{self, context::ContextPriority, EGLDevice, EGLDisplay},
-        input::InputEvent,
-        libinput::{LibinputInputBackend, LibinputSessionInterface},
+        libinput::{LibinputInputBackend, LibinputKeyboardLeds, LibinputSessionInterface},
         renderer::{
             damage::Error as OutputDamageTrackerError,
             element::{memory::MemoryRenderBuffer, AsRenderElements, RenderElementStates},
@@ -74,7 +72,7 @@ use smithay::{
             control::{connector, crtc, Device, ModeTypeFlags},
             Device as _,
         },
-        input::{DeviceCapability, Libinput},
+        input::Libinput,
         rustix::fs::OFlags,
         wayland_protocols::wp::{
             linux_dmabuf::zv1::server::zwp_linux_dmabuf_feedback_v1,
@@ -140,7 +138,7 @@ pub struct UdevData {
     fps_texture: Option<MultiTexture>,
     pointer_image: crate::cursor::Cursor,
     debug_flags: DebugFlags,
-    keyboards: Vec<smithay::reexports::input::Device>,
+    keyboard_leds: LibinputKeyboardLeds,
 }
 
 impl UdevData {
@@ -208,9 +206,7 @@ impl Backend for UdevData {
     }
 
     fn update_led_state(&mut self, led_state: LedState) {
-        for keyboard in self.keyboards.iter_mut() {
-            keyboard.led_update(led_state.into());
-        }
+        self.keyboard_leds.update(led_state);
     }
 }
 
@@ -265,7 +261,7 @@ pub fn run_udev() {
         #[cfg(feature = "debug")]
         fps_texture: None,
         debug_flags: DebugFlags::empty(),
-        keyboards: Vec::new(),
+        keyboard_leds: LibinputKeyboardLeds::new(),
     };
     let mut state = AnvilState::init(display, event_loop.handle(), data, true);
 
@@ -294,20 +290,9 @@ pub fn run_udev() {
      */
     event_loop
         .handle()
-        .insert_source(libinput_backend, move |mut event, _, data| {
+        .insert_source(libinput_backend, move |event, _, data| {
             let dh = data.backend_data.dh.clone();
-            if let InputEvent::DeviceAdded { device } = &mut event {
-                if device.has_capability(DeviceCapability::Keyboard) {
-                    if let Some(led_state) = data.seat.get_keyboard().map(|keyboard| keyboard.led_state()) {
-                        device.led_update(led_state.into());
-                    }
-                    data.backend_data.keyboards.push(device.clone());
-                }
-            } else if let InputEvent::DeviceRemoved { ref device } = event {
-                if device.has_capability(DeviceCapability::Keyboard) {
-                    data.backend_data.keyboards.retain(|item| item != device);
-                }
-            }
+            data.backend_data.keyboard_leds.process_event(&event);
 
             data.process_input_event(&dh, event)
         })
@@ -1275,7 +1260,7 @@ impl AnvilState<UdevData> {
         let schedule_render = match submit_result {
             Ok(user_data) => {
                 if let Some(mut feedback) = user_data.flatten() {
-                    feedback.presented(clock, Refresh::fixed(frame_duration), seq as u64, flags);
+                    feedback.presented(clock, Refresh::fixed(frame_duration), seq, flags);
                 }
 
                 true
@@ -1284,19 +1269,11 @@ impl AnvilState<UdevData> {
                 warn!("Error during rendering: {:?}", err);
                 match err {
                     SwapBuffersError::AlreadySwapped => true,
-                    // If the device has been deactivated do not reschedule, this will be done
-                    // by session resume
-                    SwapBuffersError::TemporaryFailure(err)
-                        if matches!(err.downcast_ref::<DrmError>(), Some(&DrmError::DeviceInactive)) =>
-                    {
-                        false
-                    }
+                    // If the device has been deactivated (DrmErrorKind::Inactive) do not reschedule,
+                    // this will be done by session resume
                     SwapBuffersError::TemporaryFailure(err) => matches!(
-                        err.downcast_ref::<DrmError>(),
-                        Some(DrmError::Access(DrmAccessError {
-                            source,
-                            ..
-                        })) if source.kind() == io::ErrorKind::PermissionDenied
+                        err.downcast_ref::<DrmError>().map(DrmError::kind),
+                        Some(DrmErrorKind::PermissionDenied)
                     ),
                     SwapBuffersError::ContextLost(err) => panic!("Rendering loop lost: {}", err),
                 }
@@ -1479,13 +1456,10 @@ impl AnvilState<UdevData> {
                 warn!("Error during rendering: {:#?}", err);
                 match err {
                     SwapBuffersError::AlreadySwapped => false,
-                    SwapBuffersError::TemporaryFailure(err) => match err.downcast_ref::<DrmError>() {
-                        Some(DrmError::DeviceInactive) => true,
-                        Some(DrmError::Access(DrmAccessError { source, .. })) => {
-                            source.kind() == io::ErrorKind::PermissionDenied
-                        }
-                        _ => false,
-                    },
+                    SwapBuffersError::TemporaryFailure(err) => matches!(
+                        err.downcast_ref::<DrmError>().map(DrmError::kind),
+                        Some(DrmErrorKind::Inactive | DrmErrorKind::PermissionDenied)
+                    ),
                     SwapBuffersError::ContextLost(err) => match err.downcast_ref::<DrmError>() {
                         Some(DrmError::TestFailed(_)) => {
                             // reset the complete state, disabling all connectors and planes in case we hit a test failed
@@ -1632,7 +1606,7 @@ fn render_surface<'a>(
     let frame_mode = if surface.disable_direct_scanout {
         FrameFlags::empty()
     } else {
-        FrameFlags::DEFAULT
+        FrameFlags::DEFAULT | FrameFlags::ALLOW_TEARING
     };
     let (rendered, states) = surface
         .drm_output
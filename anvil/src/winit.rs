@@ -292,11 +292,9 @@ pub fn run_winit() {
             #[cfg(feature = "debug")]
             let mut renderdoc = state.renderdoc.as_mut();
 
-            let age = if *full_redraw > 0 {
-                0
-            } else {
-                backend.buffer_age().unwrap_or(0)
-            };
+            // EGL_KHR_partial_update only allows setting the damage region after querying the buffer age
+            let buffer_age = backend.buffer_age().unwrap_or(0);
+            let age = if *full_redraw > 0 { 0 } else { buffer_age };
             #[cfg(feature = "debug")]
             let window_handle = backend
                 .window()
@@ -347,20 +345,22 @@ pub fn run_winit() {
                 #[cfg(feature = "debug")]
                 elements.push(CustomRenderElements::Fps(fps_element.clone()));
 
-                let res = render_output(
-                    &output,
-                    space,
-                    elements,
-                    renderer,
-                    &mut fb,
-                    damage_tracker,
-                    age,
-                    show_window_preview,
-                )
-                .map_err(|err| match err {
-                    OutputDamageTrackerError::Rendering(err) => err.into(),
-                    _ => unreachable!(),
-                });
+                let (elements, clear_color) =
+                    output_elements(&output, space, elements, renderer, show_window_preview);
+                // only redraw the damaged region, if the driver supports partial updates
+                let res = damage_tracker
+                    .render_output_with(
+                        renderer,
+                        &mut fb,
+                        age,
+                        &elements,
+                        clear_color,
+                        |renderer, fb, damage| renderer.set_damage_region(fb, damage),
+                    )
+                    .map_err(|err| match err {
+                        OutputDamageTrackerError::Rendering(err) => err.into(),
+                        _ => unreachable!(),
+                    });
 
                 res
             });
@@ -41,9 +41,11 @@ fn gl_generate() {
                 "EGL_EXT_image_dma_buf_import_modifiers",
                 "EGL_MESA_image_dma_buf_export",
                 "EGL_KHR_gl_image",
+                "EGL_KHR_gl_texture_2D_image",
                 "EGL_EXT_buffer_age",
                 "EGL_EXT_swap_buffers_with_damage",
                 "EGL_KHR_swap_buffers_with_damage",
+                "EGL_KHR_partial_update",
                 "EGL_KHR_fence_sync",
                 "EGL_ANDROID_native_fence_sync",
                 "EGL_IMG_context_priority",
@@ -66,6 +68,7 @@ fn gl_generate() {
                 "GL_EXT_texture_format_BGRA8888",
                 "GL_EXT_unpack_subimage",
                 "GL_OES_EGL_sync",
+                "GL_EXT_disjoint_timer_query",
             ],
         )
         .write_bindings(gl_generator::StructGenerator, &mut file)
@@ -76,3 +76,5 @@ impl AsMut<CompositorState> for App {
 }
 
 delegate_compositor!(App);
+
+impl smithay::wayland::protocol_error::ProtocolErrorHandler for App {}
@@ -299,3 +299,5 @@ delegate_compositor!(App);
 delegate_shm!(App);
 delegate_seat!(App);
 delegate_data_device!(App);
+
+impl smithay::wayland::protocol_error::ProtocolErrorHandler for App {}
@@ -106,3 +106,5 @@ impl CompositorHandler for App {
 
 delegate_compositor!(App);
 delegate_seat!(App);
+
+impl smithay::wayland::protocol_error::ProtocolErrorHandler for App {}
@@ -59,3 +59,5 @@ impl ShmHandler for Smallvil {
 
 delegate_compositor!(Smallvil);
 delegate_shm!(Smallvil);
+
+impl smithay::wayland::protocol_error::ProtocolErrorHandler for Smallvil {}
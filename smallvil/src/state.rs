@@ -146,7 +146,7 @@ impl Smallvil {
         self.space.element_under(pos).and_then(|(window, location)| {
             window
                 .surface_under(pos - location.to_f64(), WindowSurfaceType::ALL)
-                .map(|(s, p)| (s, (p + location).to_f64()))
+                .map(|(s, p)| (s, p + location.to_f64()))
         })
     }
 }
@@ -246,11 +246,10 @@ where
     /// Resetting the buffer age will discard all damage information and force a
     /// full redraw for the next frame.
     pub fn reset_buffer_ages(&mut self) {
-        for slot in &mut self.slots {
-            match Arc::get_mut(slot) {
-                Some(slot) => slot.age = AtomicU8::new(0),
-                None => *slot = Default::default(),
-            }
+        // The age is shared with acquired slots, so buffers still in use
+        // (e.g. for scan-out) can be kept instead of being re-allocated.
+        for slot in &self.slots {
+            slot.age.store(0, Ordering::SeqCst);
         }
     }
 
@@ -264,3 +263,126 @@ where
         &self.modifiers
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use super::*;
+    use crate::{
+        backend::allocator::Format,
+        utils::{Buffer as BufferCoords, Size},
+    };
+
+    struct TestBuffer {
+        size: Size<i32, BufferCoords>,
+        format: Format,
+    }
+
+    impl Buffer for TestBuffer {
+        fn size(&self) -> Size<i32, BufferCoords> {
+            self.size
+        }
+
+        fn format(&self) -> Format {
+            self.format
+        }
+    }
+
+    struct TestAllocator;
+
+    impl Allocator for TestAllocator {
+        type Buffer = TestBuffer;
+        type Error = Infallible;
+
+        fn create_buffer(
+            &mut self,
+            width: u32,
+            height: u32,
+            fourcc: Fourcc,
+            modifiers: &[Modifier],
+        ) -> Result<TestBuffer, Infallible> {
+            Ok(TestBuffer {
+                size: (width as i32, height as i32).into(),
+                format: Format {
+                    code: fourcc,
+                    modifier: modifiers[0],
+                },
+            })
+        }
+    }
+
+    fn swapchain() -> Swapchain<TestAllocator> {
+        Swapchain::new(TestAllocator, 64, 64, Fourcc::Argb8888, vec![Modifier::Linear])
+    }
+
+    #[test]
+    fn buffer_ages() {
+        let mut swapchain = swapchain();
+
+        let a = swapchain.acquire().unwrap().unwrap();
+        assert_eq!(a.age(), 0);
+        swapchain.submitted(&a);
+        let b = swapchain.acquire().unwrap().unwrap();
+        assert_eq!(b.age(), 0);
+        swapchain.submitted(&b);
+        assert_eq!((a.age(), b.age()), (2, 1));
+
+        drop(a);
+        let a = swapchain.acquire().unwrap().unwrap();
+        assert_eq!(a.age(), 2);
+        swapchain.submitted(&a);
+        assert_eq!((a.age(), b.age()), (1, 2));
+    }
+
+    #[test]
+    fn resize_resets_ages() {
+        let mut swapchain = swapchain();
+
+        let a = swapchain.acquire().unwrap().unwrap();
+        swapchain.submitted(&a);
+        let b = swapchain.acquire().unwrap().unwrap();
+        swapchain.submitted(&b);
+
+        // same size keeps the buffers
+        swapchain.resize(64, 64);
+        drop(a);
+        let a = swapchain.acquire().unwrap().unwrap();
+        assert_eq!(a.age(), 2);
+
+        swapchain.resize(32, 32);
+        let c = swapchain.acquire().unwrap().unwrap();
+        assert_eq!(c.age(), 0);
+        assert_eq!(c.size(), Size::from((32, 32)));
+        swapchain.submitted(&c);
+
+        // buffers of the old size, e.g. still being scanned out, don't affect the new ones
+        swapchain.submitted(&b);
+        swapchain.submitted(&a);
+        assert_eq!(c.age(), 1);
+        let d = swapchain.acquire().unwrap().unwrap();
+        assert_eq!(d.age(), 0);
+    }
+
+    #[test]
+    fn reset_ages_keeps_buffers() {
+        let mut swapchain = swapchain();
+
+        let a = swapchain.acquire().unwrap().unwrap();
+        swapchain.submitted(&a);
+        let b = swapchain.acquire().unwrap().unwrap();
+        swapchain.submitted(&b);
+        b.userdata().insert_if_missing(|| "b");
+        drop(a);
+
+        // `b` is still in use, e.g. for scan-out
+        swapchain.reset_buffer_ages();
+        assert_eq!(b.age(), 0);
+        drop(b);
+
+        let a = swapchain.acquire().unwrap().unwrap();
+        let b = swapchain.acquire().unwrap().unwrap();
+        assert_eq!((a.age(), b.age()), (0, 0));
+        assert_eq!(b.userdata().get::<&str>(), Some(&"b"));
+    }
+}
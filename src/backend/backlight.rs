@@ -0,0 +1,370 @@
+//! Discovery and control of backlight devices
+//!
+//! The brightness of built-in panels is controlled by backlight devices in the `backlight` sysfs class.
+//! [`Backlight::for_connector`] finds the device controlling the panel of a drm connector and reads its
+//! brightness.
+//!
+//! Writing the brightness usually requires elevated permissions, so compositors should change it through
+//! systemd-logind's `SetBrightness` method on their session by implementing [`BrightnessControl`] using
+//! their D-Bus library of choice. [`SysfsBrightness`] writes the brightness directly, which requires
+//! write access to the sysfs attribute, e.g. granted by a udev rule.
+//!
+//! The firmware may also change the brightness, e.g. when handling brightness hotkeys itself.
+//! [`BacklightMonitor`] is an event source notifying about these changes.
+//!
+//! ```no_run
+//! use smithay::backend::backlight::{Backlight, BacklightMonitor, SysfsBrightness};
+//!
+//! let backlight = Backlight::for_connector("seat0", "eDP-1")?.expect("No backlight for eDP-1");
+//! let brightness = backlight.brightness()?;
+//! backlight.set_brightness(&mut SysfsBrightness, brightness / 2)?;
+//!
+//! # let event_loop = smithay::reexports::calloop::EventLoop::<()>::try_new().unwrap();
+//! let monitor = BacklightMonitor::new(vec![backlight])?;
+//! event_loop
+//!     .handle()
+//!     .insert_source(monitor, |event, _, _| {
+//!         // update the brightness shown to the user
+//!     })
+//!     .expect("Failed to insert the backlight monitor into the event loop");
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    fmt, fs, io,
+    os::unix::io::{AsFd, BorrowedFd},
+    path::{Path, PathBuf},
+};
+
+use calloop::{EventSource, Interest, Mode, Poll, PostAction, Readiness, Token, TokenFactory};
+use tracing::{debug, warn};
+use udev::{Device, Enumerator, EventType, MonitorBuilder, MonitorSocket};
+
+/// Interfaces of connectors driving built-in panels
+const INTERNAL_CONNECTORS: [&str; 3] = ["eDP", "LVDS", "DSI"];
+
+/// Type of a backlight device, in the order of preference
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum BacklightType {
+    /// Controlled through a standard firmware interface, e.g. ACPI
+    Firmware,
+    /// Controlled through a platform specific interface
+    Platform,
+    /// Controlled by writing to the registers of the gpu directly
+    Raw,
+}
+
+/// A backlight device
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Backlight {
+    name: String,
+    path: PathBuf,
+    kind: BacklightType,
+    connector: Option<String>,
+    max_brightness: u32,
+}
+
+impl Backlight {
+    /// Opens the backlight device at the given sysfs path, e.g. `/sys/class/backlight/intel_backlight`
+    pub fn new(path: impl AsRef<Path>) -> io::Result<Backlight> {
+        Backlight::from_device(&Device::from_syspath(path.as_ref())?)
+    }
+
+    fn from_device(device: &Device) -> io::Result<Backlight> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+
+        let kind = match device.attribute_value("type").and_then(|kind| kind.to_str()) {
+            Some("firmware") => BacklightType::Firmware,
+            Some("platform") => BacklightType::Platform,
+            Some("raw") => BacklightType::Raw,
+            _ => return Err(invalid("unknown backlight type")),
+        };
+        let max_brightness = device
+            .attribute_value("max_brightness")
+            .and_then(|value| value.to_str()?.trim().parse().ok())
+            .ok_or_else(|| invalid("invalid maximum brightness"))?;
+        // raw backlights are usually children of the connector they control, e.g. `card0-eDP-1`
+        let connector = device
+            .parent()
+            .filter(|parent| parent.subsystem().is_some_and(|subsystem| subsystem == "drm"))
+            .and_then(|parent| parent.sysname().to_str().map(String::from));
+
+        Ok(Backlight {
+            name: device.sysname().to_string_lossy().into_owned(),
+            path: device.syspath().to_path_buf(),
+            kind,
+            connector,
+            max_brightness,
+        })
+    }
+
+    /// Returns all backlight devices of the given seat
+    pub fn all<S: AsRef<str>>(seat: S) -> io::Result<Vec<Backlight>> {
+        let mut enumerator = Enumerator::new()?;
+        enumerator.match_subsystem("backlight")?;
+        let mut backlights = enumerator
+            .scan_devices()?
+            .filter(|device| {
+                device
+                    .property_value("ID_SEAT")
+                    .map(|x| x.to_os_string())
+                    .unwrap_or_else(|| OsString::from("seat0"))
+                    == *seat.as_ref()
+            })
+            .filter_map(|device| match Backlight::from_device(&device) {
+                Ok(backlight) => Some(backlight),
+                Err(err) => {
+                    warn!(
+                        "Unable to open backlight {:?}: {}. Skipping",
+                        device.syspath(),
+                        err
+                    );
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+        backlights.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(backlights)
+    }
+
+    /// Returns the backlight device controlling the panel of a drm connector
+    ///
+    /// `connector` is the name of the connector in the form `<interface>-<id>`, e.g. `eDP-1`.
+    /// Raw backlights attached to the connector are preferred, otherwise firmware and platform
+    /// backlights are assumed to control the built-in panel.
+    pub fn for_connector<S: AsRef<str>>(seat: S, connector: &str) -> io::Result<Option<Backlight>> {
+        let mut candidates = Backlight::all(seat)?
+            .into_iter()
+            .filter(|backlight| match backlight.connector.as_deref() {
+                Some(sysname) => connector_matches(sysname, connector),
+                None => backlight.kind != BacklightType::Raw && is_internal(connector),
+            })
+            .collect::<Vec<_>>();
+        candidates.sort_by_key(|backlight| (backlight.connector.is_none(), backlight.kind));
+        Ok(candidates.into_iter().next())
+    }
+
+    /// Returns the sysfs name of the device, e.g. `intel_backlight`
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the sysfs path of the device
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the type of the device
+    pub fn kind(&self) -> BacklightType {
+        self.kind
+    }
+
+    /// Returns the sysfs name of the drm connector the device is attached to, e.g. `card0-eDP-1`
+    pub fn connector(&self) -> Option<&str> {
+        self.connector.as_deref()
+    }
+
+    /// Returns the maximum brightness
+    pub fn max_brightness(&self) -> u32 {
+        self.max_brightness
+    }
+
+    /// Reads the current brightness, between `0` and the [maximum brightness](Self::max_brightness)
+    pub fn brightness(&self) -> io::Result<u32> {
+        fs::read_to_string(self.path.join("actual_brightness"))?
+            .trim()
+            .parse()
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    /// Sets the brightness using the given [`BrightnessControl`]
+    ///
+    /// The brightness is clamped to the [maximum brightness](Self::max_brightness).
+    pub fn set_brightness<C: BrightnessControl>(
+        &self,
+        control: &mut C,
+        brightness: u32,
+    ) -> Result<(), C::Error> {
+        control.set_brightness("backlight", &self.name, brightness.min(self.max_brightness))
+    }
+}
+
+fn connector_matches(sysname: &str, connector: &str) -> bool {
+    // sysnames of connectors are prefixed with the card, e.g. `card0-eDP-1`
+    sysname
+        .split_once('-')
+        .is_some_and(|(card, name)| card.starts_with("card") && name == connector)
+}
+
+fn is_internal(connector: &str) -> bool {
+    connector
+        .rsplit_once('-')
+        .is_some_and(|(interface, _)| INTERNAL_CONNECTORS.contains(&interface))
+}
+
+/// Interface to change the brightness of a device
+///
+/// The signature matches the `SetBrightness` method of systemd-logind sessions, which takes the
+/// subsystem and sysfs name of the device.
+pub trait BrightnessControl {
+    /// Error returned by [`BrightnessControl::set_brightness`]
+    type Error;
+
+    /// Sets the brightness of the device `name` of the `subsystem` class
+    fn set_brightness(&mut self, subsystem: &str, name: &str, brightness: u32) -> Result<(), Self::Error>;
+}
+
+/// [`BrightnessControl`] writing the brightness to sysfs directly
+///
+/// Requires write access to the `brightness` attribute of the device.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SysfsBrightness;
+
+impl BrightnessControl for SysfsBrightness {
+    type Error = io::Error;
+
+    fn set_brightness(&mut self, subsystem: &str, name: &str, brightness: u32) -> io::Result<()> {
+        let path = Path::new("/sys/class")
+            .join(subsystem)
+            .join(name)
+            .join("brightness");
+        fs::write(path, brightness.to_string())
+    }
+}
+
+/// Event source notifying about brightness changes of backlight devices
+///
+/// Only changes reported by the kernel are noticed, which usually excludes changes made
+/// through a [`BrightnessControl`].
+pub struct BacklightMonitor {
+    brightness: HashMap<PathBuf, u32>,
+    monitor: MonitorSocket,
+    token: Option<Token>,
+}
+
+// MonitorSocket does not implement debug, so we have to impl Debug manually
+impl fmt::Debug for BacklightMonitor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use udev::AsRaw;
+        f.debug_struct("BacklightMonitor")
+            .field("brightness", &self.brightness)
+            .field("monitor", &format!("MonitorSocket ({:?})", self.monitor.as_raw()))
+            .finish()
+    }
+}
+
+impl AsFd for BacklightMonitor {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.monitor.as_fd()
+    }
+}
+
+impl BacklightMonitor {
+    /// Creates a new [`BacklightMonitor`] watching the given backlights
+    pub fn new(backlights: impl IntoIterator<Item = Backlight>) -> io::Result<BacklightMonitor> {
+        let brightness = backlights
+            .into_iter()
+            .map(|backlight| Ok((backlight.path.clone(), backlight.brightness()?)))
+            .collect::<io::Result<_>>()?;
+        let monitor = MonitorBuilder::new()?.match_subsystem("backlight")?.listen()?;
+
+        Ok(BacklightMonitor {
+            brightness,
+            monitor,
+            token: None,
+        })
+    }
+}
+
+impl EventSource for BacklightMonitor {
+    type Event = BacklightEvent;
+    type Metadata = ();
+    type Ret = ();
+    type Error = io::Error;
+
+    fn process_events<F>(&mut self, _: Readiness, token: Token, mut callback: F) -> io::Result<PostAction>
+    where
+        F: FnMut(BacklightEvent, &mut ()),
+    {
+        if Some(token) != self.token {
+            return Ok(PostAction::Continue);
+        }
+
+        for event in self.monitor.iter() {
+            if event.event_type() != EventType::Change {
+                continue;
+            }
+            let Some(last) = self.brightness.get_mut(event.syspath()) else {
+                continue;
+            };
+            let brightness = match fs::read_to_string(event.syspath().join("actual_brightness"))
+                .ok()
+                .and_then(|value| value.trim().parse().ok())
+            {
+                Some(brightness) => brightness,
+                None => {
+                    warn!("Unable to read brightness of {:?}", event.syspath());
+                    continue;
+                }
+            };
+            if *last != brightness {
+                debug!("Brightness of {:?} changed to {}", event.syspath(), brightness);
+                *last = brightness;
+                callback(
+                    BacklightEvent::BrightnessChanged {
+                        name: event.sysname().to_string_lossy().into_owned(),
+                        brightness,
+                    },
+                    &mut (),
+                );
+            }
+        }
+        Ok(PostAction::Continue)
+    }
+
+    fn register(&mut self, poll: &mut Poll, factory: &mut TokenFactory) -> calloop::Result<()> {
+        self.token = Some(factory.token());
+        // Safety: the fd is owned by the BacklightMonitor and cannot be closed before it is removed from the event loop
+        unsafe { poll.register(self.as_fd(), Interest::READ, Mode::Level, self.token.unwrap()) }
+    }
+
+    fn reregister(&mut self, poll: &mut Poll, factory: &mut TokenFactory) -> calloop::Result<()> {
+        self.token = Some(factory.token());
+        poll.reregister(self.as_fd(), Interest::READ, Mode::Level, self.token.unwrap())
+    }
+
+    fn unregister(&mut self, poll: &mut Poll) -> calloop::Result<()> {
+        self.token = None;
+        poll.unregister(self.as_fd())
+    }
+}
+
+/// Events generated by the [`BacklightMonitor`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BacklightEvent {
+    /// The brightness of a backlight was changed externally
+    BrightnessChanged {
+        /// Sysfs name of the backlight, see [`Backlight::name`]
+        name: String,
+        /// The new brightness
+        brightness: u32,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn connector_names() {
+        assert!(connector_matches("card0-eDP-1", "eDP-1"));
+        assert!(connector_matches("card1-HDMI-A-1", "HDMI-A-1"));
+        assert!(!connector_matches("card0-eDP-1", "eDP-2"));
+        assert!(is_internal("eDP-1"));
+        assert!(is_internal("DSI-1"));
+        assert!(!is_internal("HDMI-A-1"));
+        assert!(!is_internal("DP-1"));
+    }
+}
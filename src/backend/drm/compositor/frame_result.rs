@@ -448,4 +448,19 @@ impl<B: Buffer, F: Framebuffer> PrimarySwapchainElement<B, F> {
             _ => unreachable!(),
         }
     }
+
+    /// Export the underlying swapchain buffer as a dmabuf
+    ///
+    /// The dmabuf is cached by the swapchain slot, so every slot is exported only once.
+    /// Useful to sample the composited frame, e.g. using a
+    /// [`MirrorSource`](crate::backend::renderer::element::mirror::MirrorSource).
+    pub fn export(&self) -> Result<Dmabuf, <B as AsDmabuf>::Error>
+    where
+        B: AsDmabuf,
+    {
+        match &self.slot.buffer {
+            ScanoutBuffer::Swapchain(slot) => slot.export(),
+            _ => unreachable!(),
+        }
+    }
 }
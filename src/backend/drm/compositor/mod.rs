@@ -130,6 +130,7 @@ use std::{
     os::unix::io::{AsFd, OwnedFd},
     str::FromStr,
     sync::Arc,
+    time::Duration,
 };
 
 use drm::{
@@ -183,9 +184,11 @@ use super::{
 
 mod elements;
 mod frame_result;
+mod refresh_cap;
 
 use elements::*;
 pub use frame_result::*;
+pub use refresh_cap::RefreshCap;
 
 impl RenderElementState {
     pub(crate) fn zero_copy(visible_area: usize) -> Self {
@@ -747,13 +750,16 @@ impl<B: Buffer, F: Framebuffer> FrameState<B, F> {
         surface: &DrmSurface,
         supports_fencing: bool,
         allow_partial_update: bool,
+        async_flip: bool,
         event: bool,
     ) -> Result<(), crate::backend::drm::error::Error> {
         debug_assert!(!self.planes.iter().any(|(_, state)| state.needs_test));
-        surface.page_flip(
-            self.build_planes(surface, supports_fencing, allow_partial_update),
-            event,
-        )
+        let planes = self.build_planes(surface, supports_fencing, allow_partial_update);
+        if async_flip {
+            surface.async_page_flip(planes, event)
+        } else {
+            surface.page_flip(planes, event)
+        }
     }
 
     #[profiling::function]
@@ -990,6 +996,7 @@ enum PreparedFrameKind {
 struct PreparedFrame<A: Allocator, F: ExportFramebuffer<<A as Allocator>::Buffer>> {
     frame: CompositorFrameState<A, F>,
     kind: PreparedFrameKind,
+    async_flip: bool,
 }
 
 impl<A: Allocator, F: ExportFramebuffer<<A as Allocator>::Buffer>> PreparedFrame<A, F> {
@@ -1012,6 +1019,7 @@ where
         f.debug_struct("PreparedFrame")
             .field("frame", &self.frame)
             .field("kind", &self.kind)
+            .field("async_flip", &self.async_flip)
             .finish()
     }
 }
@@ -1032,6 +1040,10 @@ bitflags::bitflags! {
         const ALLOW_CURSOR_PLANE_SCANOUT = 8;
         /// Return `EmptyFrame`, if only the cursor plane would have been updated
         const SKIP_CURSOR_ONLY_UPDATES = 16;
+        /// Allow to present the frame with an asynchronous page flip, if the element scanned out
+        /// on the primary plane [allows tearing](crate::backend::renderer::element::Element::allows_tearing)
+        /// and the driver supports it
+        const ALLOW_TEARING = 32;
         /// Allow to realize the frame by assigning elements on any plane
         const ALLOW_SCANOUT = Self::ALLOW_PRIMARY_PLANE_SCANOUT.bits() | Self::ALLOW_OVERLAY_PLANE_SCANOUT.bits() | Self::ALLOW_CURSOR_PLANE_SCANOUT.bits();
         /// Safe default set of flags
@@ -1059,6 +1071,7 @@ where
     primary_plane_element_id: Id,
     primary_plane_damage_bag: DamageBag<i32, BufferCoords>,
     supports_fencing: bool,
+    supports_async_page_flip: bool,
     reset_pending: bool,
     signaled_fence: Option<Arc<OwnedFd>>,
 
@@ -1080,6 +1093,7 @@ where
     element_opaque_regions_workhouse: Vec<Rectangle<i32, Physical>>,
 
     debug_flags: DebugFlags,
+    refresh_cap: RefreshCap,
     span: tracing::Span,
 }
 
@@ -1151,6 +1165,7 @@ where
         );
 
         let output_mode_source = output_mode_source.into();
+        suggest_panel_orientation(&surface, &output_mode_source);
         let renderer_formats = renderer_formats.into_iter().collect::<Vec<_>>();
 
         let mut error = None;
@@ -1202,6 +1217,7 @@ where
                 })?
             && plane_has_property(&*surface, surface.plane(), "IN_FENCE_FD")?
             && !(is_nvidia && nvidia_drm_version().unwrap_or((0, 0, 0)) < (560, 35, 3));
+        let supports_async_page_flip = surface.supports_async_page_flip();
 
         for format in color_formats {
             debug!("Testing color format: {}", format);
@@ -1265,7 +1281,9 @@ where
                         opaque_regions: Vec::new(),
                         element_opaque_regions_workhouse: Vec::new(),
                         supports_fencing,
+                        supports_async_page_flip,
                         debug_flags: DebugFlags::empty(),
+                        refresh_cap: RefreshCap::default(),
                         span,
                     };
 
@@ -1336,6 +1354,7 @@ where
         );
 
         let output_mode_source = output_mode_source.into();
+        suggest_panel_orientation(&surface, &output_mode_source);
 
         let surface = Arc::new(surface);
         let mut planes = match planes {
@@ -1385,6 +1404,7 @@ where
                 })?
             && plane_has_property(&*surface, surface.plane(), "IN_FENCE_FD")?
             && !(is_nvidia && nvidia_drm_version().unwrap_or((0, 0, 0)) < (560, 35, 3));
+        let supports_async_page_flip = surface.supports_async_page_flip();
 
         let (swapchain, is_opaque) = Self::test_format(
             &surface,
@@ -1447,7 +1467,9 @@ where
             opaque_regions: Vec::new(),
             element_opaque_regions_workhouse: Vec::new(),
             supports_fencing,
+            supports_async_page_flip,
             debug_flags: DebugFlags::empty(),
+            refresh_cap: RefreshCap::default(),
             span,
         };
 
@@ -2385,6 +2407,17 @@ where
             }
         }
 
+        // drivers only allow to change the framebuffer of the primary plane during an async page flip
+        let primary_plane = self.surface.plane();
+        let async_flip = frame_flags.contains(FrameFlags::ALLOW_TEARING)
+            && self.supports_async_page_flip
+            && allow_partial_update
+            && matches!(primary_plane_element, PrimaryPlaneElement::Element(element) if element.allows_tearing())
+            && next_frame_state
+                .planes
+                .iter()
+                .all(|(plane, state)| *plane == primary_plane || state.skip);
+
         let next_frame = PreparedFrame {
             kind: if allow_partial_update {
                 PreparedFrameKind::Partial
@@ -2392,6 +2425,7 @@ where
                 PreparedFrameKind::Full
             },
             frame: next_frame_state,
+            async_flip,
         };
         let frame_reference: RenderFrameResult<'a, A::Buffer, F::Framebuffer, E> = RenderFrameResult {
             is_empty: next_frame.is_empty(),
@@ -2526,6 +2560,20 @@ where
         Ok(())
     }
 
+    /// Discards the frames waiting for presentation and resets the state of the crtc
+    ///
+    /// Use this to recover, if the vblank event of a queued frame never arrives, e.g. because
+    /// of a stuck fence or a driver bug, as no further frames can be submitted otherwise.
+    /// See [`FrameWatchdog`](crate::output::FrameWatchdog) for detecting such stalls.
+    ///
+    /// Returns the user data of the frame, that was waiting for its vblank event.
+    pub fn discard_pending_frame(&mut self) -> Result<Option<U>, DrmError> {
+        self.queued_frame = None;
+        let user_data = self.pending_frame.take().map(|frame| frame.user_data);
+        self.reset_state()?;
+        Ok(user_data)
+    }
+
     #[profiling::function]
     fn submit(&mut self) -> FrameResult<(), A, F> {
         let QueuedFrame {
@@ -2538,10 +2586,35 @@ where
             prepared_frame
                 .frame
                 .commit(&self.surface, self.supports_fencing, allow_partial_update, true)
+        } else if prepared_frame.async_flip {
+            let flip = prepared_frame.frame.page_flip(
+                &self.surface,
+                self.supports_fencing,
+                allow_partial_update,
+                true,
+                true,
+            );
+            // the driver may still reject the async flip, e.g. because of a changed plane property
+            if let Err(err) = flip {
+                debug!("Async page flip failed, falling back to vsync: {}", err);
+                prepared_frame.frame.page_flip(
+                    &self.surface,
+                    self.supports_fencing,
+                    allow_partial_update,
+                    false,
+                    true,
+                )
+            } else {
+                flip
+            }
         } else {
-            prepared_frame
-                .frame
-                .page_flip(&self.surface, self.supports_fencing, allow_partial_update, true)
+            prepared_frame.frame.page_flip(
+                &self.surface,
+                self.supports_fencing,
+                allow_partial_update,
+                false,
+                true,
+            )
         };
 
         self.handle_flip(prepared_frame, Some(user_data), flip)
@@ -2731,6 +2804,42 @@ where
         self.surface.use_vrr(vrr).map_err(FrameError::DrmError)
     }
 
+    /// Caps the frame rate of this compositor below the refresh rate of the mode
+    ///
+    /// `max_frame_rate` is given in millihertz, `None` removes the cap.
+    /// The compositor doesn't delay frames on its own, frames have to be queued no earlier than
+    /// [`DrmCompositor::next_queue_time`]. See [`RefreshCap`] for details.
+    pub fn set_max_frame_rate(&mut self, max_frame_rate: Option<u32>) {
+        self.refresh_cap = RefreshCap::new(max_frame_rate);
+    }
+
+    /// Returns the maximum frame rate in millihertz, see [`DrmCompositor::set_max_frame_rate`]
+    pub fn max_frame_rate(&self) -> Option<u32> {
+        self.refresh_cap.max_frame_rate()
+    }
+
+    /// Returns the minimal duration between two frames of the pending mode, taking the frame rate cap into account
+    ///
+    /// This should be used as the refresh duration in presentation feedback,
+    /// so clients pace their frames to the capped rate.
+    pub fn frame_interval(&self) -> Duration {
+        self.refresh_cap
+            .frame_interval(self.pending_refresh(), self.vrr_enabled())
+    }
+
+    /// Returns the earliest time to queue the next frame to honor the frame rate cap
+    ///
+    /// `last_presentation` is the presentation time of the previous frame, as reported by the vblank event.
+    /// Without a cap this is the time of the presentation itself.
+    pub fn next_queue_time(&self, last_presentation: Duration) -> Duration {
+        self.refresh_cap
+            .next_queue_time(last_presentation, self.pending_refresh(), self.vrr_enabled())
+    }
+
+    fn pending_refresh(&self) -> u32 {
+        crate::output::Mode::from(self.pending_mode()).refresh.max(0) as u32
+    }
+
     /// Set the [`DebugFlags`] to use
     ///
     /// Note: This will reset the primary plane swapchain if
@@ -4076,6 +4185,22 @@ where
 
         Ok(())
     }
+
+    /// Turn the output on or off, e.g. for DPMS
+    ///
+    /// Turning the output off [clears](Self::clear) the surface. As queuing a frame turns the output
+    /// back on, no frames should be queued while it is off.
+    ///
+    /// Turning the output on forces the next [`render_frame`](Self::render_frame) to return a non-empty
+    /// frame, which turns the output on once it is queued.
+    pub fn set_powered(&mut self, powered: bool) -> Result<(), DrmError> {
+        if powered {
+            self.reset_pending = true;
+            Ok(())
+        } else {
+            self.clear()
+        }
+    }
 }
 
 #[inline]
@@ -4402,6 +4527,20 @@ impl<
     }
 }
 
+fn suggest_panel_orientation(surface: &DrmSurface, output_mode_source: &OutputModeSource) {
+    let OutputModeSource::Auto(output) = output_mode_source else {
+        return;
+    };
+    match surface.panel_orientation() {
+        Ok(Some(transform)) => {
+            debug!(?transform, "Suggesting transform for panel orientation");
+            output.set_suggested_transform(transform);
+        }
+        Ok(None) => {}
+        Err(err) => warn!(?err, "Failed to query panel orientation"),
+    }
+}
+
 fn nvidia_drm_version() -> Option<(u32, u32, u32)> {
     let ver = std::fs::read_to_string("/sys/module/nvidia_drm/version").ok()?;
     let mut components = ver.trim().split('.');
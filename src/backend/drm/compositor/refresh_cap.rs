@@ -0,0 +1,103 @@
+use std::time::Duration;
+
+/// Caps the rate at which frames are presented below the refresh rate of the mode
+///
+/// Without variable refresh rate every frame is shown for a whole number of vblanks,
+/// so the frame rate is rounded down to the closest divisor of the refresh rate,
+/// e.g. capping a 144Hz mode at 60Hz presents frames every third vblank at 48Hz.
+/// With variable refresh rate the cap is applied exactly.
+///
+/// Frames are only limited by not queueing them too early, see
+/// [`DrmCompositor::next_queue_time`](super::DrmCompositor::next_queue_time).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct RefreshCap {
+    max_frame_rate: Option<u32>,
+}
+
+impl RefreshCap {
+    /// Create a cap at `max_frame_rate` in millihertz, `None` doesn't limit the frame rate
+    pub fn new(max_frame_rate: Option<u32>) -> Self {
+        RefreshCap {
+            max_frame_rate: max_frame_rate.filter(|rate| *rate > 0),
+        }
+    }
+
+    /// Returns the maximum frame rate in millihertz
+    pub fn max_frame_rate(&self) -> Option<u32> {
+        self.max_frame_rate
+    }
+
+    /// Returns the number of vblanks each frame is shown for with a fixed refresh rate
+    ///
+    /// `refresh` is the refresh rate of the mode in millihertz.
+    pub fn vblanks_per_frame(&self, refresh: u32) -> u32 {
+        match self.max_frame_rate {
+            Some(max) if refresh > max => refresh.div_ceil(max),
+            _ => 1,
+        }
+    }
+
+    /// Returns the minimal duration between two presented frames
+    ///
+    /// `refresh` is the refresh rate of the mode in millihertz.
+    pub fn frame_interval(&self, refresh: u32, vrr: bool) -> Duration {
+        let refresh_interval = refresh_interval(refresh);
+        match self.max_frame_rate {
+            Some(max) if vrr => refresh_interval.max(refresh_interval_of(max)),
+            _ => refresh_interval * self.vblanks_per_frame(refresh),
+        }
+    }
+
+    /// Returns the earliest time to queue the frame following a frame presented at `last_presentation`
+    ///
+    /// A frame queued after this time is presented at `last_presentation` plus the
+    /// [frame interval](Self::frame_interval) at the earliest.
+    pub fn next_queue_time(&self, last_presentation: Duration, refresh: u32, vrr: bool) -> Duration {
+        let frame_interval = self.frame_interval(refresh, vrr);
+        if vrr {
+            // the frame is presented as soon as it is ready
+            last_presentation + frame_interval
+        } else {
+            // the frame is presented on the first vblank after it was queued
+            last_presentation + frame_interval.saturating_sub(refresh_interval(refresh))
+        }
+    }
+}
+
+fn refresh_interval(refresh: u32) -> Duration {
+    if refresh == 0 {
+        Duration::ZERO
+    } else {
+        refresh_interval_of(refresh)
+    }
+}
+
+fn refresh_interval_of(rate: u32) -> Duration {
+    Duration::from_nanos(1_000_000_000_000 / rate as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vblank_divisor() {
+        let cap = RefreshCap::new(Some(60_000));
+        assert_eq!(cap.vblanks_per_frame(144_000), 3);
+        assert_eq!(cap.vblanks_per_frame(120_000), 2);
+        assert_eq!(cap.vblanks_per_frame(60_000), 1);
+        assert_eq!(cap.vblanks_per_frame(50_000), 1);
+        assert_eq!(RefreshCap::default().vblanks_per_frame(144_000), 1);
+
+        let refresh = Duration::from_nanos(1_000_000_000_000 / 120_000);
+        assert_eq!(cap.frame_interval(120_000, false), refresh * 2);
+        assert_eq!(
+            cap.next_queue_time(Duration::from_secs(1), 120_000, false),
+            Duration::from_secs(1) + refresh
+        );
+        assert_eq!(
+            cap.frame_interval(144_000, true),
+            Duration::from_nanos(1_000_000_000_000 / 60_000)
+        );
+    }
+}
@@ -14,15 +14,16 @@ pub(super) mod atomic;
 mod fd;
 pub use self::fd::DrmDeviceFd;
 pub(super) mod legacy;
-use crate::utils::{Buffer, DevPath, Size};
+use crate::utils::{Buffer, Clock, DevPath, Monotonic, Size, Transform};
 
 use super::error::AccessError;
 use super::surface::{atomic::AtomicDrmSurface, legacy::LegacyDrmSurface, DrmSurface, DrmSurfaceInternal};
+use super::writeback::{writeback_formats, WritebackConnector};
 use super::{error::Error, planes, Planes};
 use atomic::AtomicDrmDevice;
 use legacy::LegacyDrmDevice;
 
-use tracing::{debug_span, error, info, instrument, trace};
+use tracing::{debug, debug_span, error, info, instrument, trace};
 
 #[derive(Debug)]
 struct PlaneClaimInner {
@@ -107,13 +108,116 @@ impl PlaneClaimStorage {
     }
 }
 
+/// Functionality supported by a [`DrmDevice`]
+///
+/// Devices using the legacy DRM api lack a lot of functionality available with atomic
+/// modesetting. Checking the capabilities up front allows to pick fallbacks, instead of
+/// discovering missing functionality through failing operations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DrmCapabilities {
+    /// Whether the device uses atomic modesetting
+    pub atomic: bool,
+    /// Whether overlay and cursor planes can be used
+    ///
+    /// Without this only the primary plane is available, see [`DrmDevice::planes`].
+    pub planes: bool,
+    /// Whether variable refresh rate can be controlled
+    ///
+    /// Support still depends on the connector, see [`DrmSurface::vrr_supported`].
+    pub vrr: bool,
+    /// Whether gamma ramps can be set, see [`DrmSurface::set_gamma`]
+    pub gamma: bool,
+    /// Whether asynchronous page flips are supported, see [`DrmSurface::async_page_flip`]
+    pub async_page_flip: bool,
+    /// Whether framebuffers can be created with explicit modifiers
+    pub framebuffer_modifiers: bool,
+    /// Whether the device supports DRM syncobjs, which are required for explicit synchronization
+    pub syncobj: bool,
+    /// Whether page flip events carry timestamps of the monotonic clock
+    pub monotonic_timestamps: bool,
+    /// Maximum supported size of cursor buffers
+    pub cursor_size: Size<u32, Buffer>,
+}
+
+impl DrmCapabilities {
+    fn query(
+        internal: &DrmDeviceInternal,
+        resources: &ResourceHandles,
+        has_universal_planes: bool,
+        monotonic_timestamps: bool,
+        cursor_size: Size<u32, Buffer>,
+    ) -> Self {
+        let driver_capability = |capability| {
+            internal
+                .get_driver_capability(capability)
+                .is_ok_and(|val| val != 0)
+        };
+
+        let (atomic, vrr) = match internal {
+            DrmDeviceInternal::Atomic(dev) => {
+                let mapping = dev.prop_mapping.read().unwrap();
+                let vrr = resources
+                    .crtcs()
+                    .iter()
+                    .any(|crtc| mapping.crtc_prop_handle(*crtc, "VRR_ENABLED").is_ok());
+                (true, vrr)
+            }
+            DrmDeviceInternal::Legacy(_) => (false, false),
+        };
+        let gamma = resources
+            .crtcs()
+            .iter()
+            .any(|crtc| internal.get_crtc(*crtc).is_ok_and(|info| info.gamma_length() > 0));
+
+        Self::new(
+            atomic,
+            has_universal_planes,
+            vrr,
+            gamma,
+            monotonic_timestamps,
+            cursor_size,
+            driver_capability,
+        )
+    }
+
+    fn new(
+        atomic: bool,
+        has_universal_planes: bool,
+        vrr: bool,
+        gamma: bool,
+        monotonic_timestamps: bool,
+        cursor_size: Size<u32, Buffer>,
+        driver_capability: impl Fn(DriverCapability) -> bool,
+    ) -> Self {
+        let async_page_flip = if atomic {
+            driver_capability(DriverCapability::AtomicASyncPageFlip)
+        } else {
+            driver_capability(DriverCapability::ASyncPageFlip)
+        };
+
+        DrmCapabilities {
+            atomic,
+            // planes, vrr and syncobjs are only usable through the atomic api
+            planes: atomic && has_universal_planes,
+            vrr: atomic && vrr,
+            gamma,
+            async_page_flip,
+            framebuffer_modifiers: driver_capability(DriverCapability::AddFB2Modifiers),
+            syncobj: atomic && driver_capability(DriverCapability::SyncObj),
+            monotonic_timestamps,
+            cursor_size,
+        }
+    }
+}
+
 /// An open drm device
 #[derive(Debug)]
 pub struct DrmDevice {
     pub(super) dev_id: dev_t,
     pub(crate) internal: Arc<DrmDeviceInternal>,
     has_universal_planes: bool,
-    cursor_size: Size<u32, Buffer>,
+    capabilities: DrmCapabilities,
     resources: ResourceHandles,
     plane_claim_storage: PlaneClaimStorage,
     surfaces: Vec<Weak<DrmSurfaceInternal>>,
@@ -218,13 +322,21 @@ impl DrmDevice {
         })?;
 
         let internal = Arc::new(DrmDevice::create_internal(fd, active, disable_connectors)?);
+        let capabilities = DrmCapabilities::query(
+            &internal,
+            &resources,
+            has_universal_planes,
+            has_monotonic_timestamps,
+            cursor_size,
+        );
+        info!(?capabilities, "DrmDevice initialized");
 
         Ok((
             DrmDevice {
                 dev_id,
                 internal: internal.clone(),
                 has_universal_planes,
-                cursor_size,
+                capabilities,
                 resources,
                 plane_claim_storage: Default::default(),
                 surfaces: Default::default(),
@@ -232,6 +344,8 @@ impl DrmDevice {
             DrmDeviceNotifier {
                 internal,
                 has_monotonic_timestamps,
+                clock: Clock::new(),
+                sequences: HashMap::new(),
                 token: None,
             },
         ))
@@ -270,6 +384,11 @@ impl DrmDevice {
         }
     }
 
+    /// Returns the functionality supported by this device
+    pub fn capabilities(&self) -> &DrmCapabilities {
+        &self.capabilities
+    }
+
     /// Returns a list of crtcs for this device
     pub fn crtcs(&self) -> &[crtc::Handle] {
         self.resources.crtcs()
@@ -287,13 +406,90 @@ impl DrmDevice {
         self.plane_claim_storage.claim(plane, crtc)
     }
 
+    /// Exposes the writeback connectors of this device.
+    ///
+    /// Writeback connectors are hidden by default, as they do not drive a display.
+    /// After calling this function they will be part of the connectors returned by the device
+    /// and can be queried via [`DrmDevice::writeback_connectors`].
+    /// See the [`writeback`](super::writeback) module for more information.
+    ///
+    /// Returns [`Error::WritebackNotSupported`] if the device does not use atomic modesetting
+    /// or the kernel does not support writeback connectors.
+    pub fn enable_writeback_connectors(&mut self) -> Result<(), Error> {
+        let DrmDeviceInternal::Atomic(dev) = &*self.internal else {
+            return Err(Error::WritebackNotSupported);
+        };
+
+        dev.fd
+            .set_client_capability(ClientCapability::WritebackConnectors, true)
+            .map_err(|_| Error::WritebackNotSupported)?;
+
+        // the set of connectors changed, so make sure we know the properties of the new ones
+        self.resources = dev.fd.resource_handles().map_err(|source| {
+            Error::Access(AccessError {
+                errmsg: "Error loading resource handles",
+                dev: dev.fd.dev_path(),
+                source,
+            })
+        })?;
+        atomic::map_props(
+            &dev.fd,
+            self.resources.connectors(),
+            &mut dev.prop_mapping.write().unwrap().connectors,
+        )?;
+
+        Ok(())
+    }
+
+    /// Returns the writeback connectors of this device
+    ///
+    /// This will always be empty unless [`DrmDevice::enable_writeback_connectors`] was called before.
+    pub fn writeback_connectors(&self) -> Result<Vec<WritebackConnector>, Error> {
+        let resources = self.resource_handles().map_err(|source| {
+            Error::Access(AccessError {
+                errmsg: "Error loading resource handles",
+                dev: self.dev_path(),
+                source,
+            })
+        })?;
+
+        let mut connectors = Vec::new();
+        for conn in resources.connectors() {
+            let info = self.get_connector(*conn, false).map_err(|source| {
+                Error::Access(AccessError {
+                    errmsg: "Error loading connector info",
+                    dev: self.dev_path(),
+                    source,
+                })
+            })?;
+            if info.interface() != connector::Interface::Writeback {
+                continue;
+            }
+            connectors.push(WritebackConnector {
+                handle: *conn,
+                formats: writeback_formats(self, *conn)?,
+            });
+        }
+
+        Ok(connectors)
+    }
+
+    /// Returns the transform compensating the mounting of the panel of a connector
+    ///
+    /// Uses the "panel orientation" property, which is usually only set for built-in panels, e.g. of
+    /// convertible laptops or tablets with panels mounted upside down or rotated.
+    /// Returns `None` if the connector has no known orientation.
+    pub fn panel_orientation(&self, conn: connector::Handle) -> Result<Option<Transform>, Error> {
+        super::panel_orientation(self, conn)
+    }
+
     /// Returns the size of the hardware cursor
     ///
     /// Note: In case of universal planes this is the
     /// maximum size of a buffer that can be used on
     /// the cursor plane.
     pub fn cursor_size(&self) -> Size<u32, Buffer> {
-        self.cursor_size
+        self.capabilities.cursor_size
     }
 
     /// Creates a new rendering surface.
@@ -505,10 +701,18 @@ pub struct EventMetadata {
     /// The time the frame flip happend
     pub time: Time,
     /// The sequence number of the frame
-    pub sequence: u32,
+    ///
+    /// The 32-bit counter of the kernel is extended to 64 bits, so it does not wrap around
+    /// on long-running sessions. The sequence keeps increasing per crtc, even if the counter
+    /// of the kernel is reset, e.g. after the crtc was re-enabled.
+    pub sequence: u64,
 }
 
 /// Either a realtime or monotonic timestamp
+///
+/// Timestamps of page-flip events generated by a [`DrmDevice`] are always
+/// [`Monotonic`](Time::Monotonic). Drivers reporting realtime timestamps are converted
+/// to the monotonic clock and bogus values are replaced by the time the event was received.
 #[derive(Debug, Clone, Copy)]
 pub enum Time {
     /// Monotonic time stamp
@@ -517,11 +721,58 @@ pub enum Time {
     Realtime(SystemTime),
 }
 
+/// Maximum age of a page-flip timestamp, older ones are considered invalid
+const MAX_TIMESTAMP_AGE: Duration = Duration::from_secs(1);
+
+/// Converts a page-flip timestamp of a clock to the monotonic clock
+///
+/// `clock_now` and `monotonic_now` are the current time of the clock the timestamp was taken from
+/// and of the monotonic clock respectively. Returns `None` for zero timestamps, timestamps in the
+/// future and timestamps older than [`MAX_TIMESTAMP_AGE`].
+fn sanitize_timestamp(timestamp: Duration, clock_now: Duration, monotonic_now: Duration) -> Option<Duration> {
+    if timestamp.is_zero() {
+        return None;
+    }
+    let age = clock_now.checked_sub(timestamp)?;
+    if age > MAX_TIMESTAMP_AGE {
+        return None;
+    }
+    monotonic_now.checked_sub(age)
+}
+
+/// Extends the 32-bit vblank sequence of a crtc to 64 bits
+#[derive(Debug, Default)]
+struct SequenceCounter {
+    last: Option<(u32, u64)>,
+}
+
+impl SequenceCounter {
+    fn extend(&mut self, sequence: u32) -> u64 {
+        let extended = match self.last {
+            None => sequence as u64,
+            Some((last, extended)) => {
+                let delta = sequence.wrapping_sub(last);
+                if delta <= i32::MAX as u32 {
+                    extended + delta as u64
+                } else {
+                    // The counter went backwards, most likely because it was reset.
+                    // Treat this as the next frame to keep the sequence increasing.
+                    extended + 1
+                }
+            }
+        };
+        self.last = Some((sequence, extended));
+        extended
+    }
+}
+
 /// Even source of [`DrmDevice`]
 #[derive(Debug)]
 pub struct DrmDeviceNotifier {
     internal: Arc<DrmDeviceInternal>,
     has_monotonic_timestamps: bool,
+    clock: Clock<Monotonic>,
+    sequences: HashMap<crtc::Handle, SequenceCounter>,
     token: Option<Token>,
 }
 
@@ -546,13 +797,25 @@ impl EventSource for DrmDeviceNotifier {
                 for event in events {
                     if let Event::PageFlip(event) = event {
                         trace!("Got a page-flip event for crtc ({:?})", event.crtc);
+                        let monotonic_now = Duration::from(self.clock.now());
+                        let time = if self.has_monotonic_timestamps {
+                            sanitize_timestamp(event.duration, monotonic_now, monotonic_now)
+                        } else {
+                            let realtime_now = SystemTime::now()
+                                .duration_since(SystemTime::UNIX_EPOCH)
+                                .unwrap_or(Duration::ZERO);
+                            sanitize_timestamp(event.duration, realtime_now, monotonic_now)
+                        };
+                        let sequence = self.sequences.entry(event.crtc).or_default().extend(event.frame);
                         let metadata = EventMetadata {
-                            time: if self.has_monotonic_timestamps {
-                                Time::Monotonic(event.duration)
-                            } else {
-                                Time::Realtime(SystemTime::UNIX_EPOCH + event.duration)
-                            },
-                            sequence: event.frame,
+                            time: Time::Monotonic(time.unwrap_or_else(|| {
+                                debug!(
+                                    "Invalid page-flip timestamp {:?} for crtc ({:?}), using current time",
+                                    event.duration, event.crtc
+                                );
+                                monotonic_now
+                            })),
+                            sequence,
                         };
                         callback(DrmEvent::VBlank(event.crtc), &mut Some(metadata));
                     } else {
@@ -605,3 +868,81 @@ impl EventSource for DrmDeviceNotifier {
         poll.unregister(self.internal.as_fd())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn sanitize_timestamps() {
+        assert_eq!(sanitize_timestamp(ms(990), ms(1000), ms(1000)), Some(ms(990)));
+        // realtime timestamps are converted to the monotonic clock
+        assert_eq!(
+            sanitize_timestamp(ms(1_700_000_000_000 - 5), ms(1_700_000_000_000), ms(1000)),
+            Some(ms(995))
+        );
+        assert_eq!(sanitize_timestamp(Duration::ZERO, ms(1000), ms(1000)), None);
+        assert_eq!(sanitize_timestamp(ms(1001), ms(1000), ms(1000)), None);
+        assert_eq!(sanitize_timestamp(ms(10), ms(5000), ms(5000)), None);
+    }
+
+    #[test]
+    fn legacy_capabilities() {
+        let cursor_size = Size::from((64, 64));
+        let capabilities = DrmCapabilities::new(false, true, true, true, true, cursor_size, |_| true);
+        assert_eq!(
+            capabilities,
+            DrmCapabilities {
+                atomic: false,
+                planes: false,
+                vrr: false,
+                gamma: true,
+                async_page_flip: true,
+                framebuffer_modifiers: true,
+                syncobj: false,
+                monotonic_timestamps: true,
+                cursor_size,
+            }
+        );
+
+        // legacy devices use the legacy async page flip capability
+        let capabilities = DrmCapabilities::new(false, false, false, false, false, cursor_size, |cap| {
+            cap == DriverCapability::AtomicASyncPageFlip
+        });
+        assert!(!capabilities.async_page_flip);
+        assert!(!capabilities.framebuffer_modifiers);
+    }
+
+    #[test]
+    fn atomic_capabilities() {
+        let cursor_size = Size::from((256, 256));
+        let capabilities = DrmCapabilities::new(true, true, true, false, true, cursor_size, |cap| {
+            cap != DriverCapability::ASyncPageFlip
+        });
+        assert!(capabilities.atomic && capabilities.planes && capabilities.vrr && capabilities.syncobj);
+        assert!(capabilities.async_page_flip);
+        assert!(!capabilities.gamma);
+
+        // without universal planes only the primary plane is available
+        let capabilities = DrmCapabilities::new(true, false, false, false, true, cursor_size, |_| false);
+        assert!(!capabilities.planes && !capabilities.async_page_flip && !capabilities.syncobj);
+    }
+
+    #[test]
+    fn extend_sequence() {
+        let mut counter = SequenceCounter::default();
+        assert_eq!(counter.extend(u32::MAX - 1), u32::MAX as u64 - 1);
+        assert_eq!(counter.extend(u32::MAX), u32::MAX as u64);
+        // wraparound
+        assert_eq!(counter.extend(0), u32::MAX as u64 + 1);
+        assert_eq!(counter.extend(2), u32::MAX as u64 + 3);
+        // reset of the counter
+        assert_eq!(counter.extend(1), u32::MAX as u64 + 4);
+        assert_eq!(counter.extend(1), u32::MAX as u64 + 4);
+        assert_eq!(counter.extend(3), u32::MAX as u64 + 6);
+    }
+}
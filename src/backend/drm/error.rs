@@ -1,10 +1,72 @@
 use crate::backend::SwapBuffersError;
 use drm::control::{connector, crtc, plane, Mode, RawResourceHandle};
+use rustix::io::Errno;
 use std::{
-    io::{self, ErrorKind},
-    path::PathBuf,
+    io,
+    path::{Path, PathBuf},
 };
 
+/// Category of a DRM error
+///
+/// Allows to decide how to react to an [`Error`] without matching on the underlying
+/// error codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The device is paused, e.g. because the session is inactive
+    ///
+    /// Operations can be retried once the device was activated again.
+    Inactive,
+    /// Access to the device was denied, usually because DRM master was lost
+    ///
+    /// This happens e.g. while switching sessions, before the device was paused.
+    /// Operations can be retried once DRM master was acquired again.
+    PermissionDenied,
+    /// The device is busy, e.g. because a page flip is still pending
+    ///
+    /// Operations can be retried after the pending operation finished,
+    /// e.g. on the next vblank or page flip event.
+    Busy,
+    /// The operation was interrupted and can be retried immediately
+    Interrupted,
+    /// The requested configuration is invalid or not supported by the hardware
+    ///
+    /// Retrying won't help, a different configuration should be tried instead,
+    /// e.g. by falling back to fewer planes or a different mode.
+    InvalidConfiguration,
+    /// The operation or a required property is not supported by the device or driver
+    Unsupported,
+    /// The device or one of its resources is gone, e.g. because it was unplugged
+    DeviceGone,
+    /// Any other error
+    Other,
+}
+
+impl ErrorKind {
+    /// Returns the kind of error represented by an error code returned by the device
+    pub fn from_errno(errno: Errno) -> ErrorKind {
+        match errno {
+            Errno::ACCESS | Errno::PERM => ErrorKind::PermissionDenied,
+            Errno::BUSY => ErrorKind::Busy,
+            Errno::INTR | Errno::AGAIN => ErrorKind::Interrupted,
+            Errno::INVAL | Errno::RANGE | Errno::NOSPC | Errno::FBIG => ErrorKind::InvalidConfiguration,
+            Errno::OPNOTSUPP | Errno::NOSYS | Errno::NOTTY => ErrorKind::Unsupported,
+            Errno::NODEV | Errno::NXIO | Errno::NOENT => ErrorKind::DeviceGone,
+            _ => ErrorKind::Other,
+        }
+    }
+
+    /// Returns whether an operation failing with this kind of error may succeed when retried
+    ///
+    /// See the documentation of the individual kinds about when to retry.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            ErrorKind::Inactive | ErrorKind::PermissionDenied | ErrorKind::Busy | ErrorKind::Interrupted
+        )
+    }
+}
+
 /// DRM access error
 #[derive(Debug, thiserror::Error)]
 #[error("DRM access error: {errmsg} on device `{dev:?}` ({source:})")]
@@ -18,6 +80,28 @@ pub struct AccessError {
     pub source: io::Error,
 }
 
+impl AccessError {
+    /// Message describing the failed operation
+    pub fn message(&self) -> &'static str {
+        self.errmsg
+    }
+
+    /// Path of the device the error was generated on, if known
+    pub fn device(&self) -> Option<&Path> {
+        self.dev.as_deref()
+    }
+
+    /// Error code returned by the device, if any
+    pub fn errno(&self) -> Option<Errno> {
+        Errno::from_io_error(&self.source)
+    }
+
+    /// Returns the kind of this error
+    pub fn kind(&self) -> ErrorKind {
+        self.errno().map_or(ErrorKind::Other, ErrorKind::from_errno)
+    }
+}
+
 /// Errors thrown by the [`DrmDevice`](crate::backend::drm::DrmDevice)
 /// and the [`DrmSurface`](crate::backend::drm::DrmSurface).
 #[derive(thiserror::Error, Debug)]
@@ -86,29 +170,88 @@ pub enum Error {
     /// Atomic Test failed for new properties
     #[error("Atomic Test failed for new properties on crtc ({0:?})")]
     TestFailed(crtc::Handle),
+    /// Writeback connectors are not supported by the device
+    #[error("Writeback connectors are not supported by the device")]
+    WritebackNotSupported,
+}
+
+impl Error {
+    /// Returns the kind of this error
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Error::DeviceInactive => ErrorKind::Inactive,
+            Error::DrmMasterFailed => ErrorKind::PermissionDenied,
+            Error::Access(err) => err.kind(),
+            Error::UnableToGetDeviceId(errno) => ErrorKind::from_errno(*errno),
+            Error::ModeNotSuitable(_)
+            | Error::CrtcAlreadyInUse(_)
+            | Error::SurfaceWithoutConnectors(_)
+            | Error::PlaneNotCompatible(_, _)
+            | Error::NoPlane
+            | Error::NonPrimaryPlane(_)
+            | Error::NoFramebuffer(_)
+            | Error::UnsupportedPlaneConfiguration(_)
+            | Error::NoSuitableEncoder { .. }
+            | Error::UnknownConnector(_)
+            | Error::UnknownCrtc(_)
+            | Error::UnknownPlane(_)
+            | Error::TestFailed(_) => ErrorKind::InvalidConfiguration,
+            Error::UnknownProperty { .. } | Error::WritebackNotSupported => ErrorKind::Unsupported,
+        }
+    }
+
+    /// Returns whether the failed operation may succeed when retried
+    ///
+    /// Shorthand for [`ErrorKind::is_retryable`].
+    pub fn is_retryable(&self) -> bool {
+        self.kind().is_retryable()
+    }
 }
 
 impl From<Error> for SwapBuffersError {
     #[inline]
     fn from(err: Error) -> SwapBuffersError {
-        // FIXME: replace the special handling for EBUSY with ErrorKind::ResourceBusy once
-        // we reach MSRV >= 1.83
-        match err {
-            x @ Error::DeviceInactive => SwapBuffersError::TemporaryFailure(Box::new(x)),
-            Error::Access(AccessError {
-                errmsg, dev, source, ..
-            }) if matches!(
-                source.kind(),
-                ErrorKind::PermissionDenied | ErrorKind::WouldBlock | ErrorKind::Interrupted
-            ) || rustix::io::Errno::from_io_error(&source) == Some(rustix::io::Errno::BUSY) =>
-            {
-                SwapBuffersError::TemporaryFailure(Box::new(Error::Access(AccessError {
-                    errmsg,
-                    dev,
-                    source,
-                })))
-            }
-            x => SwapBuffersError::ContextLost(Box::new(x)),
+        if err.is_retryable() {
+            SwapBuffersError::TemporaryFailure(Box::new(err))
+        } else {
+            SwapBuffersError::ContextLost(Box::new(err))
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn access_error(errno: Errno) -> Error {
+        Error::Access(AccessError {
+            errmsg: "Test",
+            dev: None,
+            source: errno.into(),
+        })
+    }
+
+    #[test]
+    fn error_kinds() {
+        assert_eq!(access_error(Errno::BUSY).kind(), ErrorKind::Busy);
+        assert_eq!(access_error(Errno::ACCESS).kind(), ErrorKind::PermissionDenied);
+        assert_eq!(access_error(Errno::INVAL).kind(), ErrorKind::InvalidConfiguration);
+        assert_eq!(access_error(Errno::NODEV).kind(), ErrorKind::DeviceGone);
+        assert_eq!(Error::DeviceInactive.kind(), ErrorKind::Inactive);
+
+        assert!(matches!(
+            SwapBuffersError::from(access_error(Errno::BUSY)),
+            SwapBuffersError::TemporaryFailure(_)
+        ));
+        assert!(matches!(
+            SwapBuffersError::from(access_error(Errno::NODEV)),
+            SwapBuffersError::ContextLost(_)
+        ));
+        assert!(matches!(
+            SwapBuffersError::from(Error::TestFailed(crtc::Handle::from(
+                RawResourceHandle::new(1).unwrap()
+            ))),
+            SwapBuffersError::ContextLost(_)
+        ));
+    }
+}
@@ -83,25 +83,27 @@ pub mod gbm;
 pub mod output;
 
 mod surface;
+pub mod writeback;
 
 use std::sync::Once;
 
-use crate::utils::{DevPath, Physical, Size};
+use crate::utils::{DevPath, Physical, Size, Transform};
 pub use device::{
-    DrmDevice, DrmDeviceFd, DrmDeviceNotifier, DrmEvent, EventMetadata as DrmEventMetadata, PlaneClaim,
-    Time as DrmEventTime,
+    DrmCapabilities, DrmDevice, DrmDeviceFd, DrmDeviceNotifier, DrmEvent, EventMetadata as DrmEventMetadata,
+    PlaneClaim, Time as DrmEventTime,
 };
 pub use drm::node::{CreateDrmNodeError, DrmNode, NodeType};
 use drm_fourcc::{DrmFormat, DrmFourcc, DrmModifier};
 pub use error::AccessError as DrmAccessError;
 pub use error::Error as DrmError;
+pub use error::ErrorKind as DrmErrorKind;
 use indexmap::IndexSet;
 #[cfg(feature = "backend_gbm")]
 pub use surface::gbm::{Error as GbmBufferedSurfaceError, GbmBufferedSurface};
 pub use surface::{DrmSurface, PlaneConfig, PlaneDamageClips, PlaneState, VrrSupport};
 
 use drm::{
-    control::{crtc, framebuffer, plane, Device as ControlDevice, PlaneType},
+    control::{connector, crtc, framebuffer, plane, property, Device as ControlDevice, PlaneType},
     DriverCapability,
 };
 use tracing::trace;
@@ -244,6 +246,49 @@ fn plane_type(dev: &(impl ControlDevice + DevPath), plane: plane::Handle) -> Res
     unreachable!()
 }
 
+// Transform compensating the mounting of a panel, as reported by the "panel orientation" property
+fn panel_orientation(
+    dev: &(impl ControlDevice + DevPath),
+    conn: connector::Handle,
+) -> Result<Option<Transform>, DrmError> {
+    let props = dev.get_properties(conn).map_err(|source| {
+        DrmError::Access(AccessError {
+            errmsg: "Failed to get properties of connector",
+            dev: dev.dev_path(),
+            source,
+        })
+    })?;
+    let (ids, vals) = props.as_props_and_values();
+    for (&id, &val) in ids.iter().zip(vals.iter()) {
+        let info = dev.get_property(id).map_err(|source| {
+            DrmError::Access(AccessError {
+                errmsg: "Failed to get property info",
+                dev: dev.dev_path(),
+                source,
+            })
+        })?;
+        if info
+            .name()
+            .to_str()
+            .map(|x| x == "panel orientation")
+            .unwrap_or(false)
+        {
+            let value_type = info.value_type();
+            let property::Value::Enum(Some(value)) = value_type.convert_value(val) else {
+                return Ok(None);
+            };
+            return Ok(match value.name().to_str() {
+                Ok("Normal") => Some(Transform::Normal),
+                Ok("Upside Down") => Some(Transform::_180),
+                Ok("Left Side Up") => Some(Transform::_90),
+                Ok("Right Side Up") => Some(Transform::_270),
+                _ => None,
+            });
+        }
+    }
+    Ok(None)
+}
+
 fn plane_zpos(dev: &(impl ControlDevice + DevPath), plane: plane::Handle) -> Result<Option<i32>, DrmError> {
     let props = dev.get_properties(plane).map_err(|source| {
         DrmError::Access(AccessError {
@@ -6,6 +6,7 @@ use std::{
     marker::PhantomData,
     os::fd::AsFd,
     sync::{Arc, Mutex, RwLock, TryLockError},
+    time::Duration,
 };
 
 use drm::control::{self, connector, crtc, Mode};
@@ -57,6 +58,7 @@ where
     compositor: CompositorList<A, F, U, G>,
     color_formats: Vec<DrmFourcc>,
     renderer_formats: Vec<DrmFormat>,
+    max_frame_rate: Option<u32>,
 }
 
 impl<A, F, U, G> fmt::Debug for DrmOutputManager<A, F, U, G>
@@ -77,6 +79,7 @@ where
             .field("compositor", &self.compositor)
             .field("color_formats", &self.color_formats)
             .field("renderer_formats", &self.renderer_formats)
+            .field("max_frame_rate", &self.max_frame_rate)
             .finish()
     }
 }
@@ -102,6 +105,13 @@ where
     pub fn allocator(&self) -> &A {
         &self.allocator
     }
+
+    /// Set the maximum frame rate in millihertz of outputs initialized afterwards
+    ///
+    /// Can be overridden per output using [`DrmOutput::set_max_frame_rate`].
+    pub fn set_max_frame_rate(&mut self, max_frame_rate: Option<u32>) {
+        self.max_frame_rate = max_frame_rate;
+    }
 }
 
 impl<A, F, U, G> DrmOutputManager<A, F, U, G>
@@ -194,6 +204,7 @@ where
             compositor: Default::default(),
             color_formats: color_formats.into_iter().collect(),
             renderer_formats: renderer_formats.into_iter().collect(),
+            max_frame_rate: None,
         }
     }
 
@@ -388,6 +399,7 @@ where
 
         let compositor = write_guard.get_mut(&crtc).unwrap();
         let compositor = compositor.get_mut().unwrap();
+        compositor.set_max_frame_rate(self.max_frame_rate);
         render_elements.submit_composited_frame(&mut *compositor, renderer)?;
 
         Ok(DrmOutput {
@@ -576,6 +588,16 @@ where
         self.with_compositor(|compositor| compositor.set_debug_flags(flags));
     }
 
+    /// Set the maximum frame rate in millihertz, see [`DrmCompositor::set_max_frame_rate`]
+    pub fn set_max_frame_rate(&self, max_frame_rate: Option<u32>) {
+        self.with_compositor(|compositor| compositor.set_max_frame_rate(max_frame_rate));
+    }
+
+    /// Returns the earliest time to queue the next frame, see [`DrmCompositor::next_queue_time`]
+    pub fn next_queue_time(&self, last_presentation: Duration) -> Duration {
+        self.with_compositor(|compositor| compositor.next_queue_time(last_presentation))
+    }
+
     /// Reset the underlying buffers
     pub fn reset_buffers(&self) {
         self.with_compositor(|compositor| compositor.reset_buffers());
@@ -590,6 +612,11 @@ where
         self.with_compositor(|compositor| compositor.frame_submitted())
     }
 
+    /// Discards the frames waiting for presentation, see [`DrmCompositor::discard_pending_frame`]
+    pub fn discard_pending_frame(&self) -> Result<Option<U>, DrmError> {
+        self.with_compositor(|compositor| compositor.discard_pending_frame())
+    }
+
     /// Get the format of the underlying swapchain
     pub fn format(&self) -> DrmFourcc {
         self.with_compositor(|compositor| compositor.format())
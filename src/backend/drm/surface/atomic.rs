@@ -11,7 +11,7 @@ use std::collections::HashMap;
 use std::collections::HashSet;
 #[cfg(debug_assertions)]
 use std::fmt;
-use std::os::unix::io::AsRawFd;
+use std::os::unix::io::{AsRawFd, FromRawFd, OwnedFd, RawFd};
 use std::sync::{
     atomic::{AtomicBool, Ordering},
     Arc, Mutex, RwLock,
@@ -26,7 +26,9 @@ use crate::{
             device::atomic::{map_props, PropMapping},
             device::DrmDeviceInternal,
             error::Error,
-            plane_type, DrmDeviceFd,
+            plane_type,
+            writeback::WritebackJob,
+            DrmDeviceFd,
         },
     },
     utils::DevPath,
@@ -171,6 +173,8 @@ pub struct AtomicDrmSurface {
     prop_mapping: Arc<RwLock<PropMapping>>,
     state: RwLock<State>,
     pending: RwLock<State>,
+    writeback: Mutex<Option<WritebackJob>>,
+    writeback_fence: Mutex<Option<OwnedFd>>,
     pub(super) span: tracing::Span,
 }
 
@@ -218,6 +222,8 @@ impl AtomicDrmSurface {
             prop_mapping,
             state: RwLock::new(state),
             pending: RwLock::new(pending),
+            writeback: Mutex::new(None),
+            writeback_fence: Mutex::new(None),
             span,
         };
 
@@ -333,8 +339,9 @@ impl AtomicDrmSurface {
 
         let mut pending = self.pending.write().unwrap();
 
-        // check if the connector can handle the current mode
-        if info.modes().contains(&pending.mode) {
+        // check if the connector can handle the current mode,
+        // writeback connectors capture whatever mode the crtc is driven with.
+        if info.interface() == Interface::Writeback || info.modes().contains(&pending.mode) {
             let test_buffer = self.create_test_buffer(pending.mode.size(), self.plane)?;
 
             // check if config is supported
@@ -777,10 +784,14 @@ impl AtomicDrmSurface {
 
         trace!("Testing screen config");
 
+        // the kernel writes the out-fence of a writeback job into this during the commit
+        let mut writeback_fence: RawFd = -1;
+        let writeback = self.writeback.lock().unwrap().take();
+
         // test the new config and return the request if it would be accepted by the driver.
         let prop_mapping = self.prop_mapping.read().unwrap();
         let req = {
-            let req = AtomicRequest::build_request(
+            let mut req = AtomicRequest::build_request(
                 &prop_mapping,
                 self.crtc,
                 Some(pending.blob),
@@ -789,6 +800,9 @@ impl AtomicDrmSurface {
                 removed,
                 &*planes,
             )?;
+            if let Some(job) = writeback {
+                req.set_writeback(job.connector, job.fb, std::ptr::addr_of_mut!(writeback_fence))?;
+            }
 
             if let Err(err) = self.fd.atomic_commit(
                 AtomicCommitFlags::ALLOW_MODESET | AtomicCommitFlags::TEST_ONLY,
@@ -839,6 +853,9 @@ impl AtomicDrmSurface {
 
         if result.is_ok() {
             *current = pending.clone();
+            if writeback.is_some() {
+                self.store_writeback_fence(writeback_fence);
+            }
             for plane in planes.iter() {
                 if plane.config.is_some() {
                     used_planes.insert(plane.handle);
@@ -857,6 +874,7 @@ impl AtomicDrmSurface {
         &self,
         planes: impl IntoIterator<Item = PlaneState<'a>>,
         event: bool,
+        async_flip: bool,
     ) -> Result<(), Error> {
         if !self.active.load(Ordering::SeqCst) {
             return Err(Error::DeviceInactive);
@@ -867,7 +885,7 @@ impl AtomicDrmSurface {
 
         // page flips work just like commits with fewer parameters..
         let prop_mapping = self.prop_mapping.read().unwrap();
-        let req = AtomicRequest::build_request(
+        let mut req = AtomicRequest::build_request(
             &prop_mapping,
             self.crtc,
             None,
@@ -877,29 +895,36 @@ impl AtomicDrmSurface {
             &*planes,
         )?;
 
+        // the kernel writes the out-fence of a writeback job into this during the commit
+        let mut writeback_fence: RawFd = -1;
+        let writeback = self.writeback.lock().unwrap().take();
+        if let Some(job) = writeback {
+            req.set_writeback(job.connector, job.fb, std::ptr::addr_of_mut!(writeback_fence))?;
+        }
+
         // .. and without `AtomicCommitFlags::AllowModeset`.
         // If we would set anything here, that would require a modeset, this would fail,
         // indicating a problem in our assumptions.
-        trace!(?planes, "Queueing page flip: {:?}", req);
-        let res = self
-            .fd
-            .atomic_commit(
-                if event {
-                    AtomicCommitFlags::PAGE_FLIP_EVENT | AtomicCommitFlags::NONBLOCK
-                } else {
-                    AtomicCommitFlags::NONBLOCK
-                },
-                req.build()?,
-            )
-            .map_err(|source| {
-                Error::Access(AccessError {
-                    errmsg: "Page flip commit failed",
-                    dev: self.fd.dev_path(),
-                    source,
-                })
-            });
+        trace!(?planes, async_flip, "Queueing page flip: {:?}", req);
+        let mut flags = AtomicCommitFlags::NONBLOCK;
+        if event {
+            flags |= AtomicCommitFlags::PAGE_FLIP_EVENT;
+        }
+        if async_flip {
+            flags |= AtomicCommitFlags::PAGE_FLIP_ASYNC;
+        }
+        let res = self.fd.atomic_commit(flags, req.build()?).map_err(|source| {
+            Error::Access(AccessError {
+                errmsg: "Page flip commit failed",
+                dev: self.fd.dev_path(),
+                source,
+            })
+        });
 
         if res.is_ok() {
+            if writeback.is_some() {
+                self.store_writeback_fence(writeback_fence);
+            }
             for plane in planes.iter() {
                 if plane.config.is_some() {
                     used_planes.insert(plane.handle);
@@ -912,6 +937,39 @@ impl AtomicDrmSurface {
         res
     }
 
+    #[instrument(level = "trace", parent = &self.span, skip(self))]
+    pub fn queue_writeback(&self, conn: connector::Handle, fb: framebuffer::Handle) -> Result<(), Error> {
+        if !self.active.load(Ordering::SeqCst) {
+            return Err(Error::DeviceInactive);
+        }
+
+        if !self.pending.read().unwrap().connectors.contains(&conn) {
+            return Err(Error::UnknownConnector(conn));
+        }
+
+        // make sure this actually is a writeback connector
+        self.prop_mapping
+            .read()
+            .unwrap()
+            .conn_prop_handle(conn, "WRITEBACK_FB_ID")?;
+
+        *self.writeback.lock().unwrap() = Some(WritebackJob { connector: conn, fb });
+        Ok(())
+    }
+
+    pub fn take_writeback_fence(&self) -> Option<OwnedFd> {
+        self.writeback_fence.lock().unwrap().take()
+    }
+
+    fn store_writeback_fence(&self, fence: RawFd) {
+        // SAFETY: the kernel just created this fd for us as part of the commit
+        let fence = (fence >= 0).then(|| unsafe { OwnedFd::from_raw_fd(fence) });
+        if fence.is_none() {
+            warn!("Writeback job was committed without an out-fence");
+        }
+        *self.writeback_fence.lock().unwrap() = fence;
+    }
+
     // this helper function disconnects the plane.
     // this is mostly used to remove the contents quickly, e.g. on tty switch,
     // as other compositors might not make use of other planes,
@@ -1157,6 +1215,21 @@ impl<'a> AtomicRequest<'a> {
         Ok(())
     }
 
+    fn set_writeback(
+        &mut self,
+        conn: connector::Handle,
+        fb: framebuffer::Handle,
+        out_fence: *mut RawFd,
+    ) -> Result<(), Error> {
+        let connector_props = self.connector_props.entry(conn).or_default();
+        connector_props.insert("WRITEBACK_FB_ID", property::Value::Framebuffer(Some(fb)));
+        connector_props.insert(
+            "WRITEBACK_OUT_FENCE_PTR",
+            property::Value::UnsignedRange(out_fence as u64),
+        );
+        Ok(())
+    }
+
     fn set_crtc(
         &mut self,
         crtc: crtc::Handle,
@@ -1359,6 +1432,26 @@ impl<'a> AtomicRequest<'a> {
         Ok(())
     }
 
+    fn set_writeback(
+        &mut self,
+        conn: connector::Handle,
+        fb: framebuffer::Handle,
+        out_fence: *mut RawFd,
+    ) -> Result<(), Error> {
+        self.request.add_property(
+            conn,
+            self.mapping.conn_prop_handle(conn, "WRITEBACK_FB_ID")?,
+            property::Value::Framebuffer(Some(fb)),
+        );
+        // the kernel writes the fd of the out-fence to this address
+        self.request.add_property(
+            conn,
+            self.mapping.conn_prop_handle(conn, "WRITEBACK_OUT_FENCE_PTR")?,
+            property::Value::UnsignedRange(out_fence as u64),
+        );
+        Ok(())
+    }
+
     fn set_crtc(
         &mut self,
         crtc: crtc::Handle,
@@ -333,8 +333,13 @@ impl LegacyDrmSurface {
 
     #[instrument(level = "trace", parent = &self.span, skip(self))]
     #[profiling::function]
-    pub fn page_flip(&self, framebuffer: framebuffer::Handle, event: bool) -> Result<(), Error> {
-        trace!("Queueing Page flip");
+    pub fn page_flip(
+        &self,
+        framebuffer: framebuffer::Handle,
+        event: bool,
+        async_flip: bool,
+    ) -> Result<(), Error> {
+        trace!(async_flip, "Queueing Page flip");
 
         if !self.active.load(Ordering::SeqCst) {
             return Err(Error::DeviceInactive);
@@ -347,18 +352,14 @@ impl LegacyDrmSurface {
             *dpms = true;
         }
 
-        ControlDevice::page_flip(
-            &*self.fd,
-            self.crtc,
-            framebuffer,
-            if event {
-                PageFlipFlags::EVENT
-            } else {
-                PageFlipFlags::empty()
-            },
-            None,
-        )
-        .map_err(|source| {
+        let mut flags = PageFlipFlags::empty();
+        if event {
+            flags |= PageFlipFlags::EVENT;
+        }
+        if async_flip {
+            flags |= PageFlipFlags::ASYNC;
+        }
+        ControlDevice::page_flip(&*self.fd, self.crtc, framebuffer, flags, None).map_err(|source| {
             Error::Access(AccessError {
                 errmsg: "Failed to page flip",
                 dev: self.fd.dev_path(),
@@ -1,10 +1,10 @@
 use std::io;
-use std::os::unix::io::{AsFd, BorrowedFd};
+use std::os::unix::io::{AsFd, BorrowedFd, OwnedFd};
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
 use drm::control::{connector, crtc, framebuffer, plane, Device as ControlDevice, Mode};
-use drm::Device as BasicDevice;
+use drm::{Device as BasicDevice, DriverCapability};
 
 use libc::dev_t;
 
@@ -13,8 +13,9 @@ pub(super) mod atomic;
 pub(super) mod gbm;
 pub(super) mod legacy;
 use super::{
-    device::PlaneClaimStorage, error::Error, plane_type, DrmDeviceFd, PlaneClaim, PlaneInfo, PlaneType,
-    Planes,
+    device::PlaneClaimStorage,
+    error::{AccessError, Error},
+    plane_type, DrmDeviceFd, PlaneClaim, PlaneInfo, PlaneType, Planes,
 };
 use crate::utils::DevPath;
 use crate::utils::{Buffer, Physical, Point, Rectangle, Transform};
@@ -232,6 +233,18 @@ impl DrmSurface {
         }
     }
 
+    /// Returns the panel orientation of the pending connectors
+    ///
+    /// See [`DrmDevice::panel_orientation`](super::DrmDevice::panel_orientation).
+    pub fn panel_orientation(&self) -> Result<Option<Transform>, Error> {
+        for conn in self.pending_connectors() {
+            if let Some(transform) = super::panel_orientation(self, conn)? {
+                return Ok(Some(transform));
+            }
+        }
+        Ok(None)
+    }
+
     /// Tries to add a new [`connector`](drm::control::connector)
     /// to be used after the next commit.
     ///
@@ -431,14 +444,82 @@ impl DrmSurface {
         event: bool,
     ) -> Result<(), Error> {
         match &*self.internal {
-            DrmSurfaceInternal::Atomic(surf) => surf.page_flip(planes, event),
+            DrmSurfaceInternal::Atomic(surf) => surf.page_flip(planes, event, false),
             DrmSurfaceInternal::Legacy(surf) => {
                 let fb = ensure_legacy_planes(self, planes)?;
-                surf.page_flip(fb, event)
+                surf.page_flip(fb, event, false)
             }
         }
     }
 
+    /// Page-flip the underlying [`crtc`](drm::control::crtc)
+    /// to a new given set of [`framebuffer`]s without waiting for the next vblank.
+    ///
+    /// This may result in visible tearing, but reduces latency.
+    /// Drivers usually only allow the framebuffer of the primary plane to change during an
+    /// asynchronous page flip, see [`DrmSurface::supports_async_page_flip`].
+    ///
+    /// This operation is not blocking and will produce a `vblank` event once swapping is done.
+    #[profiling::function]
+    pub fn async_page_flip<'a>(
+        &self,
+        planes: impl IntoIterator<Item = PlaneState<'a>>,
+        event: bool,
+    ) -> Result<(), Error> {
+        match &*self.internal {
+            DrmSurfaceInternal::Atomic(surf) => surf.page_flip(planes, event, true),
+            DrmSurfaceInternal::Legacy(surf) => {
+                let fb = ensure_legacy_planes(self, planes)?;
+                surf.page_flip(fb, event, true)
+            }
+        }
+    }
+
+    /// Returns whether the driver supports [`async_page_flip`](DrmSurface::async_page_flip)
+    pub fn supports_async_page_flip(&self) -> bool {
+        let capability = if self.is_legacy() {
+            DriverCapability::ASyncPageFlip
+        } else {
+            DriverCapability::AtomicASyncPageFlip
+        };
+        self.get_driver_capability(capability)
+            .map(|val| val != 0)
+            .unwrap_or(false)
+    }
+
+    /// Queues a writeback of the next frame into the given framebuffer.
+    ///
+    /// The writeback job is attached to the next [`commit`](DrmSurface::commit) or
+    /// [`page_flip`](DrmSurface::page_flip) and is discarded, if that fails.
+    /// Queueing another framebuffer before that replaces the pending job.
+    ///
+    /// The connector needs to be a writeback connector attached to this surface,
+    /// see the [`writeback`](super::writeback) module for more information.
+    ///
+    /// *Note*: This will always return [`Error::WritebackNotSupported`] if the underlying
+    /// implementation is using the legacy DRM api.
+    pub fn queue_writeback(
+        &self,
+        connector: connector::Handle,
+        fb: framebuffer::Handle,
+    ) -> Result<(), Error> {
+        match &*self.internal {
+            DrmSurfaceInternal::Atomic(surf) => surf.queue_writeback(connector, fb),
+            DrmSurfaceInternal::Legacy(_) => Err(Error::WritebackNotSupported),
+        }
+    }
+
+    /// Takes the out-fence of the last committed writeback job.
+    ///
+    /// The fence signals once the framebuffer passed to [`queue_writeback`](DrmSurface::queue_writeback)
+    /// contains the captured frame.
+    pub fn take_writeback_fence(&self) -> Option<OwnedFd> {
+        match &*self.internal {
+            DrmSurfaceInternal::Atomic(surf) => surf.take_writeback_fence(),
+            DrmSurfaceInternal::Legacy(_) => None,
+        }
+    }
+
     /// Returns a set of available planes for this surface
     pub fn planes(&self) -> &Planes {
         &self.planes
@@ -500,6 +581,36 @@ impl DrmSurface {
             DrmSurfaceInternal::Legacy(surf) => surf.clear(),
         }
     }
+
+    /// Returns the number of entries of the gamma ramps of the underlying [`crtc`](drm::control::crtc)
+    ///
+    /// Returns `0`, if the crtc does not support setting gamma ramps.
+    pub fn gamma_size(&self) -> Result<u32, Error> {
+        let info = self.get_crtc(self.crtc).map_err(|source| AccessError {
+            errmsg: "Error loading crtc info",
+            dev: self.dev_path(),
+            source,
+        })?;
+        Ok(info.gamma_length())
+    }
+
+    /// Sets the gamma ramps of the underlying [`crtc`](drm::control::crtc)
+    ///
+    /// Every ramp needs to have [`DrmSurface::gamma_size`] entries.
+    pub fn set_gamma(&self, red: &[u16], green: &[u16], blue: &[u16]) -> Result<(), Error> {
+        if !self.is_active() {
+            return Err(Error::DeviceInactive);
+        }
+
+        ControlDevice::set_gamma(self, self.crtc, red, green, blue).map_err(|source| {
+            AccessError {
+                errmsg: "Failed to set gamma ramps",
+                dev: self.dev_path(),
+                source,
+            }
+            .into()
+        })
+    }
 }
 
 fn ensure_legacy_planes<'a>(
@@ -0,0 +1,100 @@
+//! Support for DRM writeback connectors
+//!
+//! Writeback connectors are virtual connectors, which instead of driving a display
+//! write the composed image of a crtc back into memory. This allows to capture exactly
+//! what is being scanned out, including the contents of any hardware planes, without an
+//! additional render pass. This makes them useful as an alternative capture source
+//! for screencopy or to validate the output of a compositor on real hardware in CI.
+//!
+//! Writeback connectors require an atomic device and are hidden by the kernel by default.
+//! They need to be exposed explicitly via [`DrmDevice::enable_writeback_connectors`].
+//!
+//! ## Usage
+//!
+//! - Enable writeback connectors and query them via [`DrmDevice::writeback_connectors`].
+//! - Attach a writeback connector to the [`DrmSurface`] of the crtc you want to capture
+//!   via [`DrmSurface::add_connector`] and commit the new state.
+//! - Queue a framebuffer, that should receive the next frame, with [`DrmSurface::queue_writeback`].
+//!   The framebuffer has to match the size of the current mode and use one of the
+//!   [`formats`](WritebackConnector::formats) supported by the connector.
+//!   The writeback job is attached to the next [`commit`](DrmSurface::commit) or
+//!   [`page_flip`](DrmSurface::page_flip) of the surface.
+//! - Retrieve the out-fence of the job via [`DrmSurface::take_writeback_fence`].
+//!   The fence signals once the framebuffer contains the captured frame.
+//!
+//! [`DrmDevice::enable_writeback_connectors`]: super::DrmDevice::enable_writeback_connectors
+//! [`DrmDevice::writeback_connectors`]: super::DrmDevice::writeback_connectors
+//! [`DrmSurface`]: super::DrmSurface
+//! [`DrmSurface::add_connector`]: super::DrmSurface::add_connector
+//! [`DrmSurface::queue_writeback`]: super::DrmSurface::queue_writeback
+//! [`DrmSurface::commit`]: super::DrmSurface::commit
+//! [`DrmSurface::page_flip`]: super::DrmSurface::page_flip
+//! [`DrmSurface::take_writeback_fence`]: super::DrmSurface::take_writeback_fence
+
+use drm::control::{connector, framebuffer, property, Device as ControlDevice};
+use drm_fourcc::DrmFourcc;
+
+use super::{error::AccessError, DrmError};
+use crate::utils::DevPath;
+
+/// Info about a single writeback connector
+#[derive(Debug, Clone)]
+pub struct WritebackConnector {
+    /// Handle of the connector
+    pub handle: connector::Handle,
+    /// Formats supported for framebuffers receiving the writeback
+    pub formats: Vec<DrmFourcc>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(super) struct WritebackJob {
+    pub connector: connector::Handle,
+    pub fb: framebuffer::Handle,
+}
+
+pub(super) fn writeback_formats(
+    dev: &(impl ControlDevice + DevPath),
+    conn: connector::Handle,
+) -> Result<Vec<DrmFourcc>, DrmError> {
+    let props = dev.get_properties(conn).map_err(|source| {
+        DrmError::Access(AccessError {
+            errmsg: "Failed to get properties of connector",
+            dev: dev.dev_path(),
+            source,
+        })
+    })?;
+    let (ids, vals) = props.as_props_and_values();
+    for (&id, &val) in ids.iter().zip(vals.iter()) {
+        let info = dev.get_property(id).map_err(|source| {
+            DrmError::Access(AccessError {
+                errmsg: "Failed to get property info",
+                dev: dev.dev_path(),
+                source,
+            })
+        })?;
+        if info
+            .name()
+            .to_str()
+            .map(|x| x == "WRITEBACK_PIXEL_FORMATS")
+            .unwrap_or(false)
+        {
+            let property::Value::Blob(blob) = info.value_type().convert_value(val) else {
+                tracing::debug!(?conn, "WRITEBACK_PIXEL_FORMATS property has wrong value type");
+                return Ok(Vec::new());
+            };
+            let data = dev.get_property_blob(blob).map_err(|source| {
+                DrmError::Access(AccessError {
+                    errmsg: "Failed to query property blob data",
+                    dev: dev.dev_path(),
+                    source,
+                })
+            })?;
+            // the blob is a plain array of fourcc codes
+            return Ok(data
+                .chunks_exact(std::mem::size_of::<u32>())
+                .filter_map(|code| DrmFourcc::try_from(u32::from_ne_bytes(code.try_into().unwrap())).ok())
+                .collect());
+        }
+    }
+    Ok(Vec::new())
+}
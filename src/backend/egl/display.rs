@@ -30,8 +30,9 @@ use crate::{
             ffi,
             ffi::egl::types::EGLImage,
             native::EGLNativeDisplay,
-            wrap_egl_call_bool, wrap_egl_call_ptr, EGLError, Error,
+            wrap_egl_call_bool, wrap_egl_call_ptr, EGLContext, EGLError, Error,
         },
+        renderer::{ColorRange, MatrixCoefficients, YuvConversion},
     },
     utils::{Buffer as BufferCoords, Size},
 };
@@ -625,6 +626,12 @@ impl EGLDisplay {
         DamageSupport::No
     }
 
+    /// Returns if the display supports `EGL_KHR_partial_update`,
+    /// see [`EGLSurface::set_damage_region`](super::EGLSurface::set_damage_region).
+    pub fn supports_partial_update(&self) -> bool {
+        self.extensions.iter().any(|ext| ext == "EGL_KHR_partial_update")
+    }
+
     /// Exports an [`EGLImage`] as a [`Dmabuf`]
     #[allow(clippy::not_unsafe_ptr_arg_deref)]
     #[instrument(level = "trace", skip(self), parent = &self.span, err)]
@@ -638,7 +645,7 @@ impl EGLDisplay {
         use crate::backend::allocator::dmabuf::DmabufFlags;
 
         if !self.extensions.iter().any(|s| s == "EGL_KHR_image_base")
-            && !self
+            || !self
                 .extensions
                 .iter()
                 .any(|s| s == "EGL_MESA_image_dma_buf_export")
@@ -719,10 +726,56 @@ impl EGLDisplay {
         dma.build().ok_or(Error::DmabufExportFailed(EGLError::BadAlloc))
     }
 
+    /// Creates an [`EGLImage`] from a 2D texture of the given context
+    ///
+    /// The image shares its storage with the texture and can be exported using
+    /// [`EGLDisplay::create_dmabuf_from_image`].
+    ///
+    /// # Safety
+    ///
+    /// `texture` has to be a valid and complete 2D texture of `context`.
+    #[instrument(level = "trace", skip(self, context), parent = &self.span, err)]
+    pub unsafe fn create_image_from_texture(
+        &self,
+        context: &EGLContext,
+        texture: u32,
+    ) -> Result<EGLImage, Error> {
+        if !self.extensions.iter().any(|s| s == "EGL_KHR_gl_texture_2D_image") {
+            return Err(Error::EglExtensionNotSupported(&["EGL_KHR_gl_texture_2D_image"]));
+        }
+
+        let attributes = [ffi::egl::NONE as i32];
+        let image = ffi::egl::CreateImageKHR(
+            **self.display,
+            context.get_context_handle(),
+            ffi::egl::GL_TEXTURE_2D_KHR,
+            texture as usize as ffi::egl::types::EGLClientBuffer,
+            attributes.as_ptr(),
+        );
+
+        if image == ffi::egl::NO_IMAGE_KHR {
+            Err(Error::EGLImageCreationFailed)
+        } else {
+            Ok(image)
+        }
+    }
+
     /// Imports a [`Dmabuf`] as an [`EGLImage`]
+    pub fn create_image_from_dmabuf(&self, dmabuf: &Dmabuf) -> Result<EGLImage, Error> {
+        self.create_image_from_dmabuf_with_conversion(dmabuf, &YuvConversion::default())
+    }
+
+    /// Imports a [`Dmabuf`] as an [`EGLImage`] using the given [`YuvConversion`]
+    ///
+    /// The conversion parameters are passed as hints to the driver,
+    /// values without an EGL equivalent are ignored.
     #[instrument(level = "trace", skip(self), parent = &self.span, err)]
     #[profiling::function]
-    pub fn create_image_from_dmabuf(&self, dmabuf: &Dmabuf) -> Result<EGLImage, Error> {
+    pub fn create_image_from_dmabuf_with_conversion(
+        &self,
+        dmabuf: &Dmabuf,
+        conversion: &YuvConversion,
+    ) -> Result<EGLImage, Error> {
         if !self.extensions.iter().any(|s| s == "EGL_KHR_image_base")
             && !self
                 .extensions
@@ -812,6 +865,48 @@ impl EGLDisplay {
             }
         }
 
+        if let Some(color_space) = conversion
+            .coefficients
+            .and_then(|coefficients| match coefficients {
+                MatrixCoefficients::Bt601 => Some(ffi::egl::ITU_REC601_EXT),
+                MatrixCoefficients::Bt709 => Some(ffi::egl::ITU_REC709_EXT),
+                MatrixCoefficients::Bt2020 => Some(ffi::egl::ITU_REC2020_EXT),
+                _ => None,
+            })
+        {
+            out.extend([ffi::egl::YUV_COLOR_SPACE_HINT_EXT as i32, color_space as i32]);
+        }
+        if let Some(range) = conversion.range {
+            let range = match range {
+                ColorRange::Full => ffi::egl::YUV_FULL_RANGE_EXT,
+                ColorRange::Limited => ffi::egl::YUV_NARROW_RANGE_EXT,
+            };
+            out.extend([ffi::egl::SAMPLE_RANGE_HINT_EXT as i32, range as i32]);
+        }
+        if let Some(location) = conversion.chroma_location {
+            let siting = |offset: f32| {
+                if offset == 0.0 {
+                    Some(ffi::egl::YUV_CHROMA_SITING_0_EXT)
+                } else if offset == 0.5 {
+                    Some(ffi::egl::YUV_CHROMA_SITING_0_5_EXT)
+                } else {
+                    None
+                }
+            };
+            if let Some(siting) = siting(location.horizontal_offset()) {
+                out.extend([
+                    ffi::egl::YUV_CHROMA_HORIZONTAL_SITING_HINT_EXT as i32,
+                    siting as i32,
+                ]);
+            }
+            if let Some(siting) = siting(location.vertical_offset()) {
+                out.extend([
+                    ffi::egl::YUV_CHROMA_VERTICAL_SITING_HINT_EXT as i32,
+                    siting as i32,
+                ]);
+            }
+        }
+
         out.push(ffi::egl::NONE as i32);
 
         unsafe {
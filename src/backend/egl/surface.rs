@@ -10,7 +10,7 @@ use crate::backend::egl::{
     display::{DamageSupport, EGLDisplay, EGLDisplayHandle, PixelFormat},
     ffi,
     native::EGLNativeSurface,
-    EGLError, SwapBuffersError,
+    wrap_egl_call_bool, EGLError, SwapBuffersError,
 };
 use crate::utils::{Physical, Rectangle, Size};
 
@@ -24,6 +24,7 @@ pub struct EGLSurface {
     config_id: ffi::egl::types::EGLConfig,
     pixel_format: PixelFormat,
     damage_impl: DamageSupport,
+    partial_update: bool,
     span: tracing::Span,
 }
 
@@ -86,6 +87,7 @@ impl EGLSurface {
             config_id: config,
             pixel_format,
             damage_impl: display.supports_damage_impl(),
+            partial_update: display.supports_partial_update(),
             span,
         })
     }
@@ -156,6 +158,41 @@ impl EGLSurface {
         }
     }
 
+    /// Returns if the surface supports limiting the region updated by a frame,
+    /// see [`EGLSurface::set_damage_region`].
+    pub fn supports_partial_update(&self) -> bool {
+        self.partial_update
+    }
+
+    /// Limits the region of the back buffer updated by the current frame
+    ///
+    /// Contents outside of `damage` are undefined after rendering, so it has to include every region
+    /// that changed since the back buffer was presented, as indicated by [`EGLSurface::buffer_age`].
+    /// This mostly saves memory bandwidth on tiled GPUs, which otherwise load the whole previous
+    /// contents of the buffer.
+    ///
+    /// Uses the same coordinates as [`EGLSurface::swap_buffers`], with the origin at the bottom left.
+    /// It may only be called once per frame, while the surface is current, after querying the
+    /// buffer age and before rendering. Does nothing, if `EGL_KHR_partial_update` is not supported.
+    #[instrument(level = "trace", parent = &self.span, skip(self), err)]
+    #[profiling::function]
+    pub fn set_damage_region(&self, damage: &mut [Rectangle<i32, Physical>]) -> Result<(), EGLError> {
+        if !self.partial_update {
+            return Ok(());
+        }
+
+        let surface = self.surface.load(Ordering::SeqCst);
+        wrap_egl_call_bool(|| unsafe {
+            ffi::egl::SetDamageRegionKHR(
+                **self.display,
+                surface as *const _,
+                damage.as_mut_ptr() as *mut _,
+                damage.len() as i32,
+            )
+        })
+        .map(|_| ())
+    }
+
     /// Swaps buffers at the end of a frame.
     #[instrument(level = "trace", parent = &self.span, skip(self), err)]
     #[profiling::function]
@@ -0,0 +1,285 @@
+//! Hooks for video encoders consuming captured frames
+//!
+//! This module does not implement any encoder itself. Instead it defines the [`VideoEncoder`] trait,
+//! which can be implemented on top of e.g. VA-API, Vulkan Video or a software encoder, to turn
+//! dmabufs produced by the capture paths of the compositor into an encoded bitstream.
+//!
+//! Frames are usually composed into an offscreen texture and exported using
+//! [`ExportDma`](crate::backend::renderer::ExportDma), or rendered directly into a [`Dmabuf`]
+//! allocated with one of the [`supported_formats`](VideoEncoder::supported_formats) of the encoder.
+//!
+//! [`EncodeSession`] drives an encoder for a single stream. It rebases the timestamps of the frames
+//! onto the start of the stream, drops frames arriving out of order and requests keyframes
+//! whenever the encoder has to start over, e.g. after the size of the captured region changed.
+//!
+//! ```no_run
+//! use smithay::backend::encode::{EncodeSession, EncoderFrame, VideoEncoder};
+//! # use smithay::backend::allocator::dmabuf::Dmabuf;
+//! # use smithay::backend::renderer::sync::SyncPoint;
+//! # use smithay::utils::{Clock, Monotonic};
+//! # fn record<E: VideoEncoder>(encoder: E, clock: &Clock<Monotonic>) -> Result<(), E::Error> {
+//! # let (dmabuf, sync): (Dmabuf, SyncPoint) = todo!();
+//! let mut session = EncodeSession::new(encoder);
+//!
+//! // after rendering a frame into `dmabuf`
+//! session.submit_frame(EncoderFrame {
+//!     dmabuf,
+//!     sync,
+//!     timestamp: clock.now(),
+//!     damage: None,
+//! })?;
+//!
+//! for packet in session.receive_packets()? {
+//!     // mux or stream the packet
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::time::Duration;
+
+use tracing::{debug, trace};
+
+use crate::{
+    backend::{
+        allocator::{dmabuf::Dmabuf, format::FormatSet, Buffer as _},
+        renderer::sync::SyncPoint,
+    },
+    utils::{Buffer, Monotonic, Rectangle, Size, Time},
+};
+
+/// Video codecs produced by a [`VideoEncoder`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Codec {
+    /// H.264 / AVC, encoded as an Annex B byte stream
+    H264,
+    /// H.265 / HEVC, encoded as an Annex B byte stream
+    H265,
+    /// AV1, encoded as a low overhead bitstream
+    Av1,
+}
+
+/// A frame to be encoded
+#[derive(Debug)]
+pub struct EncoderFrame {
+    /// Buffer containing the frame
+    pub dmabuf: Dmabuf,
+    /// Sync point, which has to be reached before reading the buffer
+    pub sync: SyncPoint,
+    /// Time the frame was captured or presented at
+    pub timestamp: Time<Monotonic>,
+    /// Damage since the previously submitted frame, `None` if unknown
+    ///
+    /// Encoders may use this as a hint to speed up encoding static content.
+    pub damage: Option<Vec<Rectangle<i32, Buffer>>>,
+}
+
+/// A packet of the encoded bitstream
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncodedPacket {
+    /// Encoded data
+    pub data: Vec<u8>,
+    /// Presentation timestamp of the packet, relative to the start of the stream
+    pub timestamp: Duration,
+    /// Whether the packet can be decoded without any previous packets
+    pub keyframe: bool,
+}
+
+/// Frame of an [`EncodeSession`] handed to a [`VideoEncoder`]
+#[derive(Debug)]
+pub struct SessionFrame {
+    /// Captured frame
+    pub frame: EncoderFrame,
+    /// Presentation timestamp of the frame, relative to the start of the stream
+    pub timestamp: Duration,
+    /// Whether the frame has to be encoded as a keyframe
+    pub keyframe: bool,
+}
+
+/// Trait for video encoders consuming dmabufs
+///
+/// Implementations may encode frames asynchronously, finished packets are collected using
+/// [`VideoEncoder::receive_packets`].
+pub trait VideoEncoder {
+    /// Error type returned by the encoder
+    type Error: std::error::Error;
+
+    /// Codec produced by this encoder
+    fn codec(&self) -> Codec;
+
+    /// Formats of dmabufs the encoder is able to consume
+    fn supported_formats(&self) -> FormatSet;
+
+    /// Queues a frame for encoding
+    ///
+    /// The encoder is responsible for waiting on the [`SyncPoint`] of the frame, before reading the
+    /// buffer, and has to keep the buffer alive until it is done reading.
+    fn encode(&mut self, frame: SessionFrame) -> Result<(), Self::Error>;
+
+    /// Returns the packets encoded since the last call
+    fn receive_packets(&mut self) -> Result<Vec<EncodedPacket>, Self::Error>;
+
+    /// Finishes encoding all queued frames
+    ///
+    /// The remaining packets are returned by the next call to [`VideoEncoder::receive_packets`].
+    fn flush(&mut self) -> Result<(), Self::Error>;
+}
+
+/// A single encoded stream
+///
+/// See the [module documentation](self) for details.
+#[derive(Debug)]
+pub struct EncodeSession<E> {
+    encoder: E,
+    start: Option<Time<Monotonic>>,
+    last_timestamp: Option<Duration>,
+    size: Option<Size<i32, Buffer>>,
+    keyframe_requested: bool,
+}
+
+impl<E: VideoEncoder> EncodeSession<E> {
+    /// Starts a new stream using the given encoder
+    pub fn new(encoder: E) -> Self {
+        EncodeSession {
+            encoder,
+            start: None,
+            last_timestamp: None,
+            size: None,
+            keyframe_requested: true,
+        }
+    }
+
+    /// Access the underlying encoder
+    pub fn encoder(&self) -> &E {
+        &self.encoder
+    }
+
+    /// Mutably access the underlying encoder
+    pub fn encoder_mut(&mut self) -> &mut E {
+        &mut self.encoder
+    }
+
+    /// Requests the next frame to be encoded as a keyframe
+    ///
+    /// This is useful when a new consumer joins a stream.
+    pub fn request_keyframe(&mut self) {
+        self.keyframe_requested = true;
+    }
+
+    /// Submits a frame to the encoder
+    ///
+    /// Returns `false` if the frame was dropped, because it is not newer than the previous frame.
+    pub fn submit_frame(&mut self, frame: EncoderFrame) -> Result<bool, E::Error> {
+        let start = *self.start.get_or_insert(frame.timestamp);
+        let timestamp = Time::elapsed(&start, frame.timestamp);
+        if self.last_timestamp.is_some_and(|last| timestamp <= last) {
+            trace!(?timestamp, "Dropping out of order frame");
+            return Ok(false);
+        }
+
+        let size = frame.dmabuf.size();
+        if self.size.is_some_and(|previous| previous != size) {
+            debug!(?size, "Size of the stream changed, requesting keyframe");
+            self.keyframe_requested = true;
+        }
+
+        self.encoder.encode(SessionFrame {
+            frame,
+            timestamp,
+            keyframe: self.keyframe_requested,
+        })?;
+
+        self.last_timestamp = Some(timestamp);
+        self.size = Some(size);
+        self.keyframe_requested = false;
+        Ok(true)
+    }
+
+    /// Returns the packets encoded since the last call
+    pub fn receive_packets(&mut self) -> Result<Vec<EncodedPacket>, E::Error> {
+        self.encoder.receive_packets()
+    }
+
+    /// Finishes the stream and returns the remaining packets
+    pub fn finish(mut self) -> Result<Vec<EncodedPacket>, E::Error> {
+        self.encoder.flush()?;
+        self.encoder.receive_packets()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{convert::Infallible, fs::File, os::unix::io::OwnedFd};
+
+    use super::*;
+    use crate::backend::allocator::{dmabuf::DmabufFlags, Fourcc, Modifier};
+
+    #[derive(Default)]
+    struct TestEncoder {
+        frames: Vec<(Duration, bool)>,
+    }
+
+    impl VideoEncoder for TestEncoder {
+        type Error = Infallible;
+
+        fn codec(&self) -> Codec {
+            Codec::H264
+        }
+
+        fn supported_formats(&self) -> FormatSet {
+            FormatSet::default()
+        }
+
+        fn encode(&mut self, frame: SessionFrame) -> Result<(), Self::Error> {
+            self.frames.push((frame.timestamp, frame.keyframe));
+            Ok(())
+        }
+
+        fn receive_packets(&mut self) -> Result<Vec<EncodedPacket>, Self::Error> {
+            Ok(Vec::new())
+        }
+
+        fn flush(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+
+    fn frame(ms: u64, size: (i32, i32)) -> EncoderFrame {
+        let mut builder = Dmabuf::builder(size, Fourcc::Xrgb8888, Modifier::Linear, DmabufFlags::empty());
+        builder.add_plane(OwnedFd::from(File::open("/dev/null").unwrap()), 0, 0, 0);
+        EncoderFrame {
+            dmabuf: builder.build().unwrap(),
+            sync: SyncPoint::signaled(),
+            timestamp: Duration::from_millis(ms).into(),
+            damage: None,
+        }
+    }
+
+    #[test]
+    fn session_timestamps_and_keyframes() {
+        let mut session = EncodeSession::new(TestEncoder::default());
+
+        assert!(session.submit_frame(frame(1000, (64, 64))).unwrap());
+        assert!(session.submit_frame(frame(1016, (64, 64))).unwrap());
+        // out of order frames are dropped
+        assert!(!session.submit_frame(frame(1016, (64, 64))).unwrap());
+        assert!(!session.submit_frame(frame(900, (64, 64))).unwrap());
+        // size changes and explicit requests start with a keyframe
+        assert!(session.submit_frame(frame(1033, (32, 32))).unwrap());
+        session.request_keyframe();
+        assert!(session.submit_frame(frame(1050, (32, 32))).unwrap());
+        assert!(session.submit_frame(frame(1066, (32, 32))).unwrap());
+
+        let ms = Duration::from_millis;
+        assert_eq!(
+            session.encoder().frames,
+            [
+                (ms(0), true),
+                (ms(16), false),
+                (ms(33), true),
+                (ms(50), true),
+                (ms(66), false)
+            ]
+        );
+    }
+}
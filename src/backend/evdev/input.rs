@@ -0,0 +1,330 @@
+use crate::{
+    backend::input::{
+        AbsolutePositionEvent, Axis, AxisRelativeDirection, AxisSource, ButtonState, Event, KeyState,
+        KeyboardKeyEvent, Keycode, PointerAxisEvent, PointerButtonEvent, PointerMotionEvent,
+        TouchCancelEvent, TouchDownEvent, TouchEvent, TouchFrameEvent, TouchMotionEvent, TouchSlot,
+        TouchUpEvent,
+    },
+    utils::{Point, Raw},
+};
+
+use super::{AbsRange, EvdevDevice, EvdevInputBackend};
+
+// Scroll distance of a single wheel click, libinput uses the same value
+const WHEEL_CLICK_DEGREES: f64 = 15.0;
+
+/// Evdev-Backend internal event wrapping a key of a keyboard into a [`KeyboardKeyEvent`]
+#[derive(Debug, Clone)]
+pub struct EvdevKeyboardKeyEvent {
+    pub(crate) time: u64,
+    pub(crate) device: EvdevDevice,
+    pub(crate) key: u32,
+    pub(crate) state: KeyState,
+    pub(crate) count: u32,
+}
+
+impl Event<EvdevInputBackend> for EvdevKeyboardKeyEvent {
+    fn time(&self) -> u64 {
+        self.time
+    }
+
+    fn device(&self) -> EvdevDevice {
+        self.device.clone()
+    }
+}
+
+impl KeyboardKeyEvent<EvdevInputBackend> for EvdevKeyboardKeyEvent {
+    fn key_code(&self) -> Keycode {
+        (self.key + 8).into()
+    }
+
+    fn state(&self) -> KeyState {
+        self.state
+    }
+
+    /// Number of keys pressed on the device after this event
+    fn count(&self) -> u32 {
+        self.count
+    }
+}
+
+/// Evdev-Backend internal event wrapping relative motion into a [`PointerMotionEvent`]
+///
+/// No acceleration is applied to the motion of the device.
+#[derive(Debug, Clone)]
+pub struct EvdevPointerMotionEvent {
+    pub(crate) time: u64,
+    pub(crate) device: EvdevDevice,
+    pub(crate) delta: Point<i32, Raw>,
+}
+
+impl Event<EvdevInputBackend> for EvdevPointerMotionEvent {
+    fn time(&self) -> u64 {
+        self.time
+    }
+
+    fn device(&self) -> EvdevDevice {
+        self.device.clone()
+    }
+}
+
+impl PointerMotionEvent<EvdevInputBackend> for EvdevPointerMotionEvent {
+    fn delta_x(&self) -> f64 {
+        self.delta.x as f64
+    }
+
+    fn delta_y(&self) -> f64 {
+        self.delta.y as f64
+    }
+
+    fn delta_x_unaccel(&self) -> f64 {
+        self.delta.x as f64
+    }
+
+    fn delta_y_unaccel(&self) -> f64 {
+        self.delta.y as f64
+    }
+}
+
+/// Evdev-Backend internal event wrapping a button of a pointer into a [`PointerButtonEvent`]
+#[derive(Debug, Clone)]
+pub struct EvdevPointerButtonEvent {
+    pub(crate) time: u64,
+    pub(crate) device: EvdevDevice,
+    pub(crate) button: u32,
+    pub(crate) state: ButtonState,
+}
+
+impl Event<EvdevInputBackend> for EvdevPointerButtonEvent {
+    fn time(&self) -> u64 {
+        self.time
+    }
+
+    fn device(&self) -> EvdevDevice {
+        self.device.clone()
+    }
+}
+
+impl PointerButtonEvent<EvdevInputBackend> for EvdevPointerButtonEvent {
+    fn button_code(&self) -> u32 {
+        self.button
+    }
+
+    fn state(&self) -> ButtonState {
+        self.state
+    }
+}
+
+/// Evdev-Backend internal event wrapping scroll wheels into a [`PointerAxisEvent`]
+#[derive(Debug, Clone)]
+pub struct EvdevPointerAxisEvent {
+    pub(crate) time: u64,
+    pub(crate) device: EvdevDevice,
+    pub(crate) horizontal_v120: Option<i32>,
+    pub(crate) vertical_v120: Option<i32>,
+}
+
+impl Event<EvdevInputBackend> for EvdevPointerAxisEvent {
+    fn time(&self) -> u64 {
+        self.time
+    }
+
+    fn device(&self) -> EvdevDevice {
+        self.device.clone()
+    }
+}
+
+impl PointerAxisEvent<EvdevInputBackend> for EvdevPointerAxisEvent {
+    fn amount(&self, axis: Axis) -> Option<f64> {
+        self.amount_v120(axis)
+            .map(|v120| v120 / 120.0 * WHEEL_CLICK_DEGREES)
+    }
+
+    fn amount_v120(&self, axis: Axis) -> Option<f64> {
+        match axis {
+            Axis::Horizontal => self.horizontal_v120,
+            Axis::Vertical => self.vertical_v120,
+        }
+        .map(f64::from)
+    }
+
+    fn source(&self) -> AxisSource {
+        AxisSource::Wheel
+    }
+
+    fn relative_direction(&self, _axis: Axis) -> AxisRelativeDirection {
+        AxisRelativeDirection::Identical
+    }
+}
+
+/// Evdev-Backend internal event wrapping a new touch point into a [`TouchDownEvent`]
+#[derive(Debug, Clone)]
+pub struct EvdevTouchDownEvent {
+    pub(crate) time: u64,
+    pub(crate) device: EvdevDevice,
+    pub(crate) slot: TouchSlot,
+    pub(crate) position: Point<i32, Raw>,
+}
+
+impl Event<EvdevInputBackend> for EvdevTouchDownEvent {
+    fn time(&self) -> u64 {
+        self.time
+    }
+
+    fn device(&self) -> EvdevDevice {
+        self.device.clone()
+    }
+}
+
+impl TouchDownEvent<EvdevInputBackend> for EvdevTouchDownEvent {}
+
+impl TouchEvent<EvdevInputBackend> for EvdevTouchDownEvent {
+    fn slot(&self) -> TouchSlot {
+        self.slot
+    }
+}
+
+impl AbsolutePositionEvent<EvdevInputBackend> for EvdevTouchDownEvent {
+    fn x(&self) -> f64 {
+        self.position.x as f64
+    }
+
+    fn y(&self) -> f64 {
+        self.position.y as f64
+    }
+
+    fn x_transformed(&self, width: i32) -> f64 {
+        transform(self.position.x, self.device.abs_x(), width)
+    }
+
+    fn y_transformed(&self, height: i32) -> f64 {
+        transform(self.position.y, self.device.abs_y(), height)
+    }
+}
+
+/// Evdev-Backend internal event wrapping the motion of a touch point into a [`TouchMotionEvent`]
+#[derive(Debug, Clone)]
+pub struct EvdevTouchMotionEvent {
+    pub(crate) time: u64,
+    pub(crate) device: EvdevDevice,
+    pub(crate) slot: TouchSlot,
+    pub(crate) position: Point<i32, Raw>,
+}
+
+impl Event<EvdevInputBackend> for EvdevTouchMotionEvent {
+    fn time(&self) -> u64 {
+        self.time
+    }
+
+    fn device(&self) -> EvdevDevice {
+        self.device.clone()
+    }
+}
+
+impl TouchMotionEvent<EvdevInputBackend> for EvdevTouchMotionEvent {}
+
+impl TouchEvent<EvdevInputBackend> for EvdevTouchMotionEvent {
+    fn slot(&self) -> TouchSlot {
+        self.slot
+    }
+}
+
+impl AbsolutePositionEvent<EvdevInputBackend> for EvdevTouchMotionEvent {
+    fn x(&self) -> f64 {
+        self.position.x as f64
+    }
+
+    fn y(&self) -> f64 {
+        self.position.y as f64
+    }
+
+    fn x_transformed(&self, width: i32) -> f64 {
+        transform(self.position.x, self.device.abs_x(), width)
+    }
+
+    fn y_transformed(&self, height: i32) -> f64 {
+        transform(self.position.y, self.device.abs_y(), height)
+    }
+}
+
+/// Evdev-Backend internal event wrapping a lifted touch point into a [`TouchUpEvent`]
+#[derive(Debug, Clone)]
+pub struct EvdevTouchUpEvent {
+    pub(crate) time: u64,
+    pub(crate) device: EvdevDevice,
+    pub(crate) slot: TouchSlot,
+}
+
+impl Event<EvdevInputBackend> for EvdevTouchUpEvent {
+    fn time(&self) -> u64 {
+        self.time
+    }
+
+    fn device(&self) -> EvdevDevice {
+        self.device.clone()
+    }
+}
+
+impl TouchUpEvent<EvdevInputBackend> for EvdevTouchUpEvent {}
+
+impl TouchEvent<EvdevInputBackend> for EvdevTouchUpEvent {
+    fn slot(&self) -> TouchSlot {
+        self.slot
+    }
+}
+
+/// Evdev-Backend internal event wrapping a cancelled touch point into a [`TouchCancelEvent`]
+///
+/// Touch points are cancelled when the kernel dropped events of the device.
+#[derive(Debug, Clone)]
+pub struct EvdevTouchCancelEvent {
+    pub(crate) time: u64,
+    pub(crate) device: EvdevDevice,
+    pub(crate) slot: TouchSlot,
+}
+
+impl Event<EvdevInputBackend> for EvdevTouchCancelEvent {
+    fn time(&self) -> u64 {
+        self.time
+    }
+
+    fn device(&self) -> EvdevDevice {
+        self.device.clone()
+    }
+}
+
+impl TouchCancelEvent<EvdevInputBackend> for EvdevTouchCancelEvent {}
+
+impl TouchEvent<EvdevInputBackend> for EvdevTouchCancelEvent {
+    fn slot(&self) -> TouchSlot {
+        self.slot
+    }
+}
+
+/// Evdev-Backend internal event grouping the touch events of a report into a [`TouchFrameEvent`]
+#[derive(Debug, Clone)]
+pub struct EvdevTouchFrameEvent {
+    pub(crate) time: u64,
+    pub(crate) device: EvdevDevice,
+}
+
+impl Event<EvdevInputBackend> for EvdevTouchFrameEvent {
+    fn time(&self) -> u64 {
+        self.time
+    }
+
+    fn device(&self) -> EvdevDevice {
+        self.device.clone()
+    }
+}
+
+impl TouchFrameEvent<EvdevInputBackend> for EvdevTouchFrameEvent {}
+
+fn transform(value: i32, range: Option<AbsRange>, size: i32) -> f64 {
+    match range {
+        Some(range) if range.max > range.min => {
+            (value - range.min) as f64 * size as f64 / (range.max - range.min + 1) as f64
+        }
+        _ => value as f64,
+    }
+}
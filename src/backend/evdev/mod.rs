@@ -0,0 +1,1003 @@
+//! Input backend reading evdev devices directly
+//!
+//! Constrained embedded systems may not ship libinput or udev. [`EvdevInput`] reads the events of
+//! a single evdev device node like `/dev/input/event0` and translates them into the generic
+//! [`InputEvent`]s of the [`input`](crate::backend::input) module, so the same input handling
+//! code works with this backend and the [`libinput`](crate::backend::libinput) one.
+//!
+//! Keyboards, touchscreens and relative pointers like mice are supported. Compared to libinput
+//! this backend is deliberately minimal: there is no pointer acceleration, no touchpad, tablet or
+//! gesture support, no device quirks and no hotplugging. Every device has to be opened
+//! explicitly and is its own event source.
+//!
+//! ```no_run
+//! use smithay::backend::{evdev::EvdevInput, input::InputEvent};
+//! # let event_loop = smithay::reexports::calloop::EventLoop::<()>::try_new().unwrap();
+//!
+//! for entry in std::fs::read_dir("/dev/input")? {
+//!     let path = entry?.path();
+//!     if !path.file_name().is_some_and(|name| name.to_string_lossy().starts_with("event")) {
+//!         continue;
+//!     }
+//!
+//!     let input = EvdevInput::open(&path)?;
+//!     event_loop
+//!         .handle()
+//!         .insert_source(input, |event, _, _| match event {
+//!             InputEvent::Keyboard { event } => { /* ... */ }
+//!             _ => {}
+//!         })
+//!         .unwrap();
+//! }
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use std::{
+    ffi::CStr,
+    fs,
+    hash::{Hash, Hasher},
+    io, mem,
+    os::unix::io::{AsFd, BorrowedFd, OwnedFd},
+    path::{Path, PathBuf},
+    ptr,
+    sync::Arc,
+};
+
+use calloop::{EventSource, Interest, Mode, Poll, PostAction, Readiness, Token, TokenFactory};
+use rustix::{
+    fs::OFlags,
+    io::Errno,
+    ioctl::{ioctl, IntegerSetter, ReadOpcode, Setter, Updater, WriteOpcode},
+};
+use tracing::{debug, debug_span, info, trace, warn};
+
+use crate::{
+    backend::{
+        evdev_sys::*,
+        input::{
+            self as backend, ButtonState, DeviceCapability, InputBackend, InputEvent, KeyState, TouchSlot,
+            UnusedEvent,
+        },
+    },
+    utils::{Point, Raw},
+};
+
+mod input;
+
+pub use self::input::*;
+
+// Upper bound of multi-touch slots we track per device
+const MAX_SLOTS: usize = 64;
+// Number of events read from the device at once
+const READ_EVENTS: usize = 64;
+
+// Base of the EVIOCGABS opcodes, the axis is added to it
+const EVIOCGABS: u8 = 0x40;
+
+type EvdevGetId = ReadOpcode<b'E', 0x02, libc::input_id>;
+type EvdevGetName = ReadOpcode<b'E', 0x06, [u8; 256]>;
+type EvdevGetProperties = ReadOpcode<b'E', 0x09, [u8; 4]>;
+type EvdevGetEventBits = ReadOpcode<b'E', 0x20, [u8; 4]>;
+type EvdevGetKeyBits = ReadOpcode<b'E', 0x21, [u8; 96]>;
+type EvdevGetRelBits = ReadOpcode<b'E', 0x22, [u8; 2]>;
+type EvdevGetAbsBits = ReadOpcode<b'E', 0x23, [u8; 8]>;
+type EvdevGrab = WriteOpcode<b'E', 0x90, libc::c_int>;
+type EvdevSetClockId = WriteOpcode<b'E', 0xa0, libc::c_int>;
+
+/// Marker used to define the `InputBackend` types for the evdev backend.
+#[derive(Debug)]
+pub struct EvdevInputBackend;
+
+impl InputBackend for EvdevInputBackend {
+    type Device = EvdevDevice;
+    type KeyboardKeyEvent = EvdevKeyboardKeyEvent;
+    type PointerAxisEvent = EvdevPointerAxisEvent;
+    type PointerButtonEvent = EvdevPointerButtonEvent;
+    type PointerMotionEvent = EvdevPointerMotionEvent;
+    type PointerMotionAbsoluteEvent = UnusedEvent;
+    type GestureSwipeBeginEvent = UnusedEvent;
+    type GestureSwipeUpdateEvent = UnusedEvent;
+    type GestureSwipeEndEvent = UnusedEvent;
+    type GesturePinchBeginEvent = UnusedEvent;
+    type GesturePinchUpdateEvent = UnusedEvent;
+    type GesturePinchEndEvent = UnusedEvent;
+    type GestureHoldBeginEvent = UnusedEvent;
+    type GestureHoldEndEvent = UnusedEvent;
+    type TouchDownEvent = EvdevTouchDownEvent;
+    type TouchUpEvent = EvdevTouchUpEvent;
+    type TouchMotionEvent = EvdevTouchMotionEvent;
+    type TouchCancelEvent = EvdevTouchCancelEvent;
+    type TouchFrameEvent = EvdevTouchFrameEvent;
+    type TabletToolAxisEvent = UnusedEvent;
+    type TabletToolProximityEvent = UnusedEvent;
+    type TabletToolTipEvent = UnusedEvent;
+    type TabletToolButtonEvent = UnusedEvent;
+    type SwitchToggleEvent = UnusedEvent;
+    type SpecialEvent = UnusedEvent;
+}
+
+/// Range of an absolute axis of a device
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct AbsRange {
+    min: i32,
+    max: i32,
+}
+
+impl From<libc::input_absinfo> for AbsRange {
+    #[inline]
+    fn from(info: libc::input_absinfo) -> Self {
+        AbsRange {
+            min: info.minimum,
+            max: info.maximum,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct DeviceInfo {
+    sysname: String,
+    name: String,
+    vendor: u16,
+    product: u16,
+    keyboard: bool,
+    pointer: bool,
+    touch: bool,
+    // Number of slots of multi-touch devices using protocol B
+    slots: Option<usize>,
+    abs_x: Option<AbsRange>,
+    abs_y: Option<AbsRange>,
+    hi_res_wheel: bool,
+    hi_res_hwheel: bool,
+}
+
+impl DeviceInfo {
+    fn query(fd: BorrowedFd<'_>, sysname: String) -> io::Result<DeviceInfo> {
+        let mut name = [0u8; 256];
+        unsafe { ioctl(fd, Updater::<EvdevGetName, _>::new(&mut name)) }?;
+        let name = CStr::from_bytes_until_nul(&name)
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        let mut id: libc::input_id = unsafe { mem::zeroed() };
+        unsafe { ioctl(fd, Updater::<EvdevGetId, _>::new(&mut id)) }?;
+
+        let mut events = [0u8; 4];
+        let mut keys = [0u8; 96];
+        let mut rel = [0u8; 2];
+        let mut abs = [0u8; 8];
+        let mut props = [0u8; 4];
+        unsafe { ioctl(fd, Updater::<EvdevGetEventBits, _>::new(&mut events)) }?;
+        if test_bit(&events, EV_KEY) {
+            unsafe { ioctl(fd, Updater::<EvdevGetKeyBits, _>::new(&mut keys)) }?;
+        }
+        if test_bit(&events, EV_REL) {
+            unsafe { ioctl(fd, Updater::<EvdevGetRelBits, _>::new(&mut rel)) }?;
+        }
+        if test_bit(&events, EV_ABS) {
+            unsafe { ioctl(fd, Updater::<EvdevGetAbsBits, _>::new(&mut abs)) }?;
+        }
+        unsafe { ioctl(fd, Updater::<EvdevGetProperties, _>::new(&mut props)) }?;
+
+        let keyboard = (1..BTN_MISC)
+            .chain(KEY_OK..BTN_TRIGGER_HAPPY)
+            .any(|key| test_bit(&keys, key));
+        let pointer = test_bit(&rel, REL_X) && test_bit(&rel, REL_Y);
+
+        let has_abs = |axis: u16| test_bit(&abs, axis);
+        let multitouch = has_abs(ABS_MT_SLOT)
+            && has_abs(ABS_MT_TRACKING_ID)
+            && has_abs(ABS_MT_POSITION_X)
+            && has_abs(ABS_MT_POSITION_Y);
+        // Touchpads also report absolute positions, but are not directly mapped to the screen.
+        // Some older touchscreens lack the property, so fall back to the tool of touchpads.
+        let direct = test_bit(&props, INPUT_PROP_DIRECT)
+            || (test_bit(&keys, BTN_TOUCH) && !test_bit(&keys, BTN_TOOL_FINGER));
+        let touch = direct && (multitouch || (has_abs(ABS_X) && has_abs(ABS_Y)));
+
+        let (slots, abs_x, abs_y) = if touch && multitouch {
+            let slots = abs_info::<{ EVIOCGABS + ABS_MT_SLOT as u8 }>(fd)?;
+            (
+                Some((slots.maximum.max(0) as usize + 1).min(MAX_SLOTS)),
+                Some(abs_info::<{ EVIOCGABS + ABS_MT_POSITION_X as u8 }>(fd)?.into()),
+                Some(abs_info::<{ EVIOCGABS + ABS_MT_POSITION_Y as u8 }>(fd)?.into()),
+            )
+        } else if touch {
+            (
+                None,
+                Some(abs_info::<{ EVIOCGABS + ABS_X as u8 }>(fd)?.into()),
+                Some(abs_info::<{ EVIOCGABS + ABS_Y as u8 }>(fd)?.into()),
+            )
+        } else {
+            (None, None, None)
+        };
+
+        Ok(DeviceInfo {
+            sysname,
+            name,
+            vendor: id.vendor,
+            product: id.product,
+            keyboard,
+            pointer,
+            touch,
+            slots,
+            abs_x,
+            abs_y,
+            hi_res_wheel: test_bit(&rel, REL_WHEEL_HI_RES),
+            hi_res_hwheel: test_bit(&rel, REL_HWHEEL_HI_RES),
+        })
+    }
+}
+
+fn abs_info<const OPCODE: u8>(fd: BorrowedFd<'_>) -> io::Result<libc::input_absinfo> {
+    let mut info: libc::input_absinfo = unsafe { mem::zeroed() };
+    unsafe {
+        ioctl(
+            fd,
+            Updater::<ReadOpcode<b'E', OPCODE, libc::input_absinfo>, _>::new(&mut info),
+        )
+    }?;
+    Ok(info)
+}
+
+fn test_bit(bits: &[u8], bit: u16) -> bool {
+    bits.get(bit as usize / 8)
+        .is_some_and(|byte| byte & (1 << (bit % 8)) != 0)
+}
+
+/// Input device read by the evdev backend
+///
+/// Cloning the device is cheap, clones compare equal to each other.
+#[derive(Debug, Clone)]
+pub struct EvdevDevice(Arc<DeviceInfo>);
+
+impl EvdevDevice {
+    fn abs_x(&self) -> Option<AbsRange> {
+        self.0.abs_x
+    }
+
+    fn abs_y(&self) -> Option<AbsRange> {
+        self.0.abs_y
+    }
+}
+
+impl PartialEq for EvdevDevice {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for EvdevDevice {}
+
+impl Hash for EvdevDevice {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Arc::as_ptr(&self.0).hash(state);
+    }
+}
+
+impl backend::Device for EvdevDevice {
+    fn id(&self) -> String {
+        self.0.sysname.clone()
+    }
+
+    fn name(&self) -> String {
+        self.0.name.clone()
+    }
+
+    fn has_capability(&self, capability: DeviceCapability) -> bool {
+        match capability {
+            DeviceCapability::Keyboard => self.0.keyboard,
+            DeviceCapability::Pointer => self.0.pointer,
+            DeviceCapability::Touch => self.0.touch,
+            _ => false,
+        }
+    }
+
+    fn usb_id(&self) -> Option<(u32, u32)> {
+        Some((self.0.product as u32, self.0.vendor as u32))
+    }
+
+    fn syspath(&self) -> Option<PathBuf> {
+        fs::canonicalize(Path::new("/sys/class/input").join(&self.0.sysname)).ok()
+    }
+}
+
+/// Evdev based event source for a single input device
+///
+/// Emits [`InputEvent::DeviceAdded`] once it is inserted into the event loop and
+/// [`InputEvent::DeviceRemoved`] once the device is unplugged, after which the source removes itself.
+#[derive(Debug)]
+pub struct EvdevInput {
+    fd: OwnedFd,
+    state: EvdevState,
+    added: bool,
+    token: Option<Token>,
+    span: tracing::Span,
+}
+
+impl EvdevInput {
+    /// Opens the evdev device node at `path`
+    pub fn open(path: impl AsRef<Path>) -> io::Result<EvdevInput> {
+        let fd = rustix::fs::open(
+            path.as_ref(),
+            OFlags::RDONLY | OFlags::NONBLOCK | OFlags::CLOEXEC,
+            rustix::fs::Mode::empty(),
+        )?;
+        EvdevInput::from_fd(fd, path)
+    }
+
+    /// Creates the source from an already opened evdev device node, e.g. using a
+    /// [`Session`](crate::backend::session::Session)
+    ///
+    /// `path` is the path the device node was opened from.
+    pub fn from_fd(fd: OwnedFd, path: impl AsRef<Path>) -> io::Result<EvdevInput> {
+        let path = path.as_ref();
+        let sysname = fs::canonicalize(path)
+            .unwrap_or_else(|_| path.to_owned())
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Not a path to a device node"))?;
+
+        let span = debug_span!("backend_evdev", device = sysname);
+        let _guard = span.enter();
+
+        rustix::io::ioctl_fionbio(&fd, true)?;
+        // Timestamps default to the realtime clock, which may jump
+        if let Err(err) = unsafe { ioctl(&fd, Setter::<EvdevSetClockId, _>::new(libc::CLOCK_MONOTONIC)) } {
+            debug!(?err, "Failed to use monotonic timestamps");
+        }
+
+        let info = DeviceInfo::query(fd.as_fd(), sysname)?;
+        info!(
+            name = info.name,
+            keyboard = info.keyboard,
+            pointer = info.pointer,
+            touch = info.touch,
+            "Opened evdev device"
+        );
+
+        let slot = if info.slots.is_some() {
+            abs_info::<{ EVIOCGABS + ABS_MT_SLOT as u8 }>(fd.as_fd())?.value
+        } else {
+            0
+        };
+        let mut state = EvdevState::new(EvdevDevice(Arc::new(info)));
+        state.slot = slot.max(0) as usize;
+
+        drop(_guard);
+        Ok(EvdevInput {
+            fd,
+            state,
+            added: false,
+            token: None,
+            span,
+        })
+    }
+
+    /// Returns the device read by this source
+    pub fn device(&self) -> &EvdevDevice {
+        &self.state.device
+    }
+
+    /// Grabs the device exclusively, so no other client of the kernel receives its events
+    ///
+    /// This keeps the input from also reaching the virtual terminal, for example.
+    pub fn grab(&self, grab: bool) -> io::Result<()> {
+        unsafe { ioctl(&self.fd, IntegerSetter::<EvdevGrab>::new(grab as usize)) }?;
+        Ok(())
+    }
+}
+
+impl AsFd for EvdevInput {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.fd.as_fd()
+    }
+}
+
+impl EventSource for EvdevInput {
+    type Event = InputEvent<EvdevInputBackend>;
+    type Metadata = ();
+    type Ret = ();
+    type Error = io::Error;
+
+    const NEEDS_EXTRA_LIFECYCLE_EVENTS: bool = true;
+
+    fn process_events<F>(&mut self, _: Readiness, token: Token, mut callback: F) -> io::Result<PostAction>
+    where
+        F: FnMut(Self::Event, &mut ()) -> Self::Ret,
+    {
+        if Some(token) != self.token {
+            return Ok(PostAction::Continue);
+        }
+
+        let _guard = self.span.enter();
+        let mut callback = |event| callback(event, &mut ());
+
+        if !self.added {
+            self.added = true;
+            callback(InputEvent::DeviceAdded {
+                device: self.state.device.clone(),
+            });
+        }
+
+        let mut buffer = [0u8; EVENT_SIZE * READ_EVENTS];
+        loop {
+            match rustix::io::read(&self.fd, &mut buffer) {
+                Ok(len) => {
+                    for chunk in buffer[..len].chunks_exact(EVENT_SIZE) {
+                        // SAFETY: the kernel only writes complete events and every bit pattern is valid
+                        let event = unsafe { ptr::read_unaligned(chunk.as_ptr() as *const RawInputEvent) };
+                        self.state.process(event.into(), &mut callback);
+                    }
+                    if len < buffer.len() {
+                        break;
+                    }
+                }
+                Err(Errno::INTR) => continue,
+                Err(Errno::AGAIN) => break,
+                Err(Errno::NODEV) => {
+                    info!("Device removed");
+                    self.state.reset(&mut callback);
+                    callback(InputEvent::DeviceRemoved {
+                        device: self.state.device.clone(),
+                    });
+                    return Ok(PostAction::Remove);
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        Ok(PostAction::Continue)
+    }
+
+    fn register(&mut self, poll: &mut Poll, factory: &mut TokenFactory) -> calloop::Result<()> {
+        self.token = Some(factory.token());
+        // Safety: the FD cannot be closed without removing the EvdevInput from the event loop
+        unsafe { poll.register(self.as_fd(), Interest::READ, Mode::Level, self.token.unwrap()) }
+    }
+
+    fn reregister(&mut self, poll: &mut Poll, factory: &mut TokenFactory) -> calloop::Result<()> {
+        self.token = Some(factory.token());
+        poll.reregister(self.as_fd(), Interest::READ, Mode::Level, self.token.unwrap())
+    }
+
+    fn unregister(&mut self, poll: &mut Poll) -> calloop::Result<()> {
+        self.token = None;
+        poll.unregister(self.as_fd())
+    }
+
+    fn before_sleep(&mut self) -> calloop::Result<Option<(Readiness, Token)>> {
+        // announce the device without waiting for its first input
+        if self.added {
+            Ok(None)
+        } else {
+            Ok(self.token.map(|token| (Readiness::EMPTY, token)))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct EvdevEvent {
+    time: u64,
+    kind: u16,
+    code: u16,
+    value: i32,
+}
+
+impl From<RawInputEvent> for EvdevEvent {
+    #[inline]
+    // c_ulong is only 32 bits wide on some platforms
+    #[allow(clippy::unnecessary_cast)]
+    fn from(event: RawInputEvent) -> Self {
+        EvdevEvent {
+            time: event.sec as u64 * 1_000_000 + event.usec as u64,
+            kind: event.kind,
+            code: event.code,
+            value: event.value,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct TouchPoint {
+    tracking_id: Option<i32>,
+    // tracking id at the last report
+    reported_id: Option<i32>,
+    position: Point<i32, Raw>,
+    moved: bool,
+}
+
+/// Translation of the raw events of a device into [`InputEvent`]s
+///
+/// Events are accumulated until the device reports a consistent state with `SYN_REPORT`.
+#[derive(Debug)]
+struct EvdevState {
+    device: EvdevDevice,
+    time: u64,
+    // events are discarded until the next report after the kernel dropped some
+    dropped: bool,
+    pressed_keys: Vec<u16>,
+    pressed_buttons: Vec<u16>,
+    motion: Point<i32, Raw>,
+    horizontal_v120: Option<i32>,
+    vertical_v120: Option<i32>,
+    touch_points: Vec<TouchPoint>,
+    slot: usize,
+    // tracking ids of single-touch devices, which do not report them
+    next_tracking_id: i32,
+}
+
+impl EvdevState {
+    fn new(device: EvdevDevice) -> EvdevState {
+        let touch_points = match device.0.slots {
+            Some(slots) => slots,
+            None if device.0.touch => 1,
+            None => 0,
+        };
+        EvdevState {
+            device,
+            time: 0,
+            dropped: false,
+            pressed_keys: Vec::new(),
+            pressed_buttons: Vec::new(),
+            motion: Point::default(),
+            horizontal_v120: None,
+            vertical_v120: None,
+            touch_points: vec![TouchPoint::default(); touch_points],
+            slot: 0,
+            next_tracking_id: 0,
+        }
+    }
+
+    fn process<F>(&mut self, event: EvdevEvent, callback: &mut F)
+    where
+        F: FnMut(InputEvent<EvdevInputBackend>),
+    {
+        self.time = event.time;
+
+        if self.dropped {
+            if event.kind == EV_SYN && event.code == SYN_REPORT {
+                self.dropped = false;
+            }
+            return;
+        }
+
+        match event.kind {
+            EV_SYN if event.code == SYN_REPORT => self.report(callback),
+            EV_SYN if event.code == SYN_DROPPED => {
+                warn!("Input events were dropped");
+                self.dropped = true;
+                self.reset(callback);
+            }
+            EV_KEY => self.key(event.code, event.value, callback),
+            EV_REL => self.relative(event.code, event.value),
+            EV_ABS => self.absolute(event.code, event.value),
+            _ => {}
+        }
+    }
+
+    fn key<F>(&mut self, code: u16, value: i32, callback: &mut F)
+    where
+        F: FnMut(InputEvent<EvdevInputBackend>),
+    {
+        let state = match value {
+            0 => KeyState::Released,
+            1 => KeyState::Pressed,
+            // the compositor implements key repeat itself
+            _ => return,
+        };
+
+        if code == BTN_TOUCH {
+            if self.device.0.slots.is_none() {
+                if let Some(point) = self.touch_points.first_mut() {
+                    point.tracking_id = (state == KeyState::Pressed).then_some(self.next_tracking_id);
+                    self.next_tracking_id = self.next_tracking_id.wrapping_add(1) & i32::MAX;
+                }
+            }
+        } else if (BTN_MOUSE..BTN_JOYSTICK).contains(&code) {
+            if update_pressed(&mut self.pressed_buttons, code, state) {
+                callback(InputEvent::PointerButton {
+                    event: EvdevPointerButtonEvent {
+                        time: self.time,
+                        device: self.device.clone(),
+                        button: code as u32,
+                        state: match state {
+                            KeyState::Pressed => ButtonState::Pressed,
+                            KeyState::Released => ButtonState::Released,
+                        },
+                    },
+                });
+            }
+        } else if code < BTN_MISC || (KEY_OK..BTN_TRIGGER_HAPPY).contains(&code) {
+            if update_pressed(&mut self.pressed_keys, code, state) {
+                callback(InputEvent::Keyboard {
+                    event: EvdevKeyboardKeyEvent {
+                        time: self.time,
+                        device: self.device.clone(),
+                        key: code as u32,
+                        state,
+                        count: self.pressed_keys.len() as u32,
+                    },
+                });
+            }
+        } else {
+            trace!(code, "Ignoring unsupported key");
+        }
+    }
+
+    fn relative(&mut self, code: u16, value: i32) {
+        match code {
+            REL_X => self.motion.x += value,
+            REL_Y => self.motion.y += value,
+            // evdev scrolls up for positive values, wayland down
+            REL_WHEEL if !self.device.0.hi_res_wheel => {
+                *self.vertical_v120.get_or_insert(0) -= value * 120;
+            }
+            REL_WHEEL_HI_RES => *self.vertical_v120.get_or_insert(0) -= value,
+            REL_HWHEEL if !self.device.0.hi_res_hwheel => {
+                *self.horizontal_v120.get_or_insert(0) += value * 120;
+            }
+            REL_HWHEEL_HI_RES => *self.horizontal_v120.get_or_insert(0) += value,
+            _ => {}
+        }
+    }
+
+    fn absolute(&mut self, code: u16, value: i32) {
+        if self.device.0.slots.is_some() {
+            if code == ABS_MT_SLOT {
+                self.slot = usize::try_from(value).unwrap_or(usize::MAX);
+                return;
+            }
+            let Some(point) = self.touch_points.get_mut(self.slot) else {
+                return;
+            };
+            match code {
+                ABS_MT_TRACKING_ID => point.tracking_id = (value >= 0).then_some(value),
+                ABS_MT_POSITION_X => {
+                    point.position.x = value;
+                    point.moved = true;
+                }
+                ABS_MT_POSITION_Y => {
+                    point.position.y = value;
+                    point.moved = true;
+                }
+                _ => {}
+            }
+        } else if let Some(point) = self.touch_points.first_mut() {
+            match code {
+                ABS_X => {
+                    point.position.x = value;
+                    point.moved = true;
+                }
+                ABS_Y => {
+                    point.position.y = value;
+                    point.moved = true;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn report<F>(&mut self, callback: &mut F)
+    where
+        F: FnMut(InputEvent<EvdevInputBackend>),
+    {
+        let mut touched = false;
+        for (slot, point) in self.touch_points.iter_mut().enumerate() {
+            let slot = TouchSlot::from(Some(slot as u32));
+            let changed = point.reported_id != point.tracking_id;
+
+            if point.reported_id.is_some() && changed {
+                touched = true;
+                callback(InputEvent::TouchUp {
+                    event: EvdevTouchUpEvent {
+                        time: self.time,
+                        device: self.device.clone(),
+                        slot,
+                    },
+                });
+            }
+            if point.tracking_id.is_some() && changed {
+                touched = true;
+                callback(InputEvent::TouchDown {
+                    event: EvdevTouchDownEvent {
+                        time: self.time,
+                        device: self.device.clone(),
+                        slot,
+                        position: point.position,
+                    },
+                });
+            } else if point.tracking_id.is_some() && point.moved {
+                touched = true;
+                callback(InputEvent::TouchMotion {
+                    event: EvdevTouchMotionEvent {
+                        time: self.time,
+                        device: self.device.clone(),
+                        slot,
+                        position: point.position,
+                    },
+                });
+            }
+
+            point.reported_id = point.tracking_id;
+            point.moved = false;
+        }
+        if touched {
+            callback(InputEvent::TouchFrame {
+                event: EvdevTouchFrameEvent {
+                    time: self.time,
+                    device: self.device.clone(),
+                },
+            });
+        }
+
+        if self.motion != Point::default() {
+            callback(InputEvent::PointerMotion {
+                event: EvdevPointerMotionEvent {
+                    time: self.time,
+                    device: self.device.clone(),
+                    delta: self.motion,
+                },
+            });
+            self.motion = Point::default();
+        }
+
+        if self.horizontal_v120.is_some() || self.vertical_v120.is_some() {
+            callback(InputEvent::PointerAxis {
+                event: EvdevPointerAxisEvent {
+                    time: self.time,
+                    device: self.device.clone(),
+                    horizontal_v120: self.horizontal_v120.take(),
+                    vertical_v120: self.vertical_v120.take(),
+                },
+            });
+        }
+    }
+
+    /// Cancels all touch points and releases all keys and buttons
+    fn reset<F>(&mut self, callback: &mut F)
+    where
+        F: FnMut(InputEvent<EvdevInputBackend>),
+    {
+        let mut touched = false;
+        for (slot, point) in self.touch_points.iter_mut().enumerate() {
+            if point.reported_id.is_some() {
+                touched = true;
+                callback(InputEvent::TouchCancel {
+                    event: EvdevTouchCancelEvent {
+                        time: self.time,
+                        device: self.device.clone(),
+                        slot: TouchSlot::from(Some(slot as u32)),
+                    },
+                });
+            }
+            *point = TouchPoint {
+                position: point.position,
+                ..Default::default()
+            };
+        }
+        if touched {
+            callback(InputEvent::TouchFrame {
+                event: EvdevTouchFrameEvent {
+                    time: self.time,
+                    device: self.device.clone(),
+                },
+            });
+        }
+
+        while let Some(key) = self.pressed_keys.pop() {
+            callback(InputEvent::Keyboard {
+                event: EvdevKeyboardKeyEvent {
+                    time: self.time,
+                    device: self.device.clone(),
+                    key: key as u32,
+                    state: KeyState::Released,
+                    count: self.pressed_keys.len() as u32,
+                },
+            });
+        }
+        while let Some(button) = self.pressed_buttons.pop() {
+            callback(InputEvent::PointerButton {
+                event: EvdevPointerButtonEvent {
+                    time: self.time,
+                    device: self.device.clone(),
+                    button: button as u32,
+                    state: ButtonState::Released,
+                },
+            });
+        }
+
+        self.motion = Point::default();
+        self.horizontal_v120 = None;
+        self.vertical_v120 = None;
+    }
+}
+
+// Returns whether the state of the key changed
+fn update_pressed(pressed: &mut Vec<u16>, code: u16, state: KeyState) -> bool {
+    let index = pressed.iter().position(|key| *key == code);
+    match (state, index) {
+        (KeyState::Pressed, None) => {
+            pressed.push(code);
+            true
+        }
+        (KeyState::Released, Some(index)) => {
+            pressed.swap_remove(index);
+            true
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::input::{
+        AbsolutePositionEvent, Axis, PointerAxisEvent, PointerMotionEvent, TouchEvent,
+    };
+
+    fn device(slots: Option<usize>) -> EvdevDevice {
+        EvdevDevice(Arc::new(DeviceInfo {
+            sysname: String::from("event0"),
+            name: String::from("test device"),
+            vendor: 0,
+            product: 0,
+            keyboard: true,
+            pointer: true,
+            touch: true,
+            slots,
+            abs_x: Some(AbsRange { min: 0, max: 1023 }),
+            abs_y: Some(AbsRange { min: 0, max: 1023 }),
+            hi_res_wheel: false,
+            hi_res_hwheel: false,
+        }))
+    }
+
+    fn process(state: &mut EvdevState, events: &[(u16, u16, i32)]) -> Vec<InputEvent<EvdevInputBackend>> {
+        let mut output = Vec::new();
+        for &(kind, code, value) in events {
+            let event = EvdevEvent {
+                time: 0,
+                kind,
+                code,
+                value,
+            };
+            state.process(event, &mut |event| output.push(event));
+        }
+        output
+    }
+
+    const REPORT: (u16, u16, i32) = (EV_SYN, SYN_REPORT, 0);
+
+    #[test]
+    fn multitouch() {
+        let mut state = EvdevState::new(device(Some(2)));
+        let abs = |axis, value| (EV_ABS, axis, value);
+
+        let events = process(
+            &mut state,
+            &[
+                abs(ABS_MT_TRACKING_ID, 10),
+                abs(ABS_MT_POSITION_X, 512),
+                abs(ABS_MT_POSITION_Y, 256),
+                REPORT,
+            ],
+        );
+        assert!(matches!(
+            &events[..],
+            [InputEvent::TouchDown { event }, InputEvent::TouchFrame { .. }]
+                if event.slot() == Some(0).into() && event.x_transformed(1024) == 512.0 && event.y() == 256.0
+        ));
+
+        let events = process(
+            &mut state,
+            &[
+                abs(ABS_MT_SLOT, 1),
+                abs(ABS_MT_TRACKING_ID, 11),
+                abs(ABS_MT_POSITION_X, 10),
+                abs(ABS_MT_POSITION_Y, 10),
+                abs(ABS_MT_SLOT, 0),
+                abs(ABS_MT_POSITION_X, 500),
+                REPORT,
+            ],
+        );
+        assert!(matches!(
+            &events[..],
+            [InputEvent::TouchMotion { event: motion }, InputEvent::TouchDown { event: down }, InputEvent::TouchFrame { .. }]
+                if motion.slot() == Some(0).into() && motion.x() == 500.0 && down.slot() == Some(1).into()
+        ));
+
+        // a new tracking id without lifting the previous touch point
+        let events = process(
+            &mut state,
+            &[
+                abs(ABS_MT_TRACKING_ID, 12),
+                abs(ABS_MT_SLOT, 1),
+                abs(ABS_MT_TRACKING_ID, -1),
+                REPORT,
+            ],
+        );
+        assert!(matches!(
+            &events[..],
+            [
+                InputEvent::TouchUp { event: up0 },
+                InputEvent::TouchDown { event: down0 },
+                InputEvent::TouchUp { event: up1 },
+                InputEvent::TouchFrame { .. },
+            ] if up0.slot() == Some(0).into() && down0.slot() == Some(0).into() && up1.slot() == Some(1).into()
+        ));
+    }
+
+    #[test]
+    fn relative_motion_and_scroll() {
+        let mut state = EvdevState::new(device(None));
+
+        let events = process(
+            &mut state,
+            &[
+                (EV_REL, REL_X, 2),
+                (EV_REL, REL_X, 3),
+                (EV_REL, REL_Y, -1),
+                (EV_REL, REL_WHEEL, 1),
+                REPORT,
+            ],
+        );
+        assert!(matches!(
+            &events[..],
+            [InputEvent::PointerMotion { event: motion }, InputEvent::PointerAxis { event: axis }]
+                if motion.delta_x() == 5.0
+                    && motion.delta_y() == -1.0
+                    && axis.amount_v120(Axis::Vertical) == Some(-120.0)
+                    && axis.amount_v120(Axis::Horizontal).is_none()
+        ));
+
+        assert!(process(&mut state, &[REPORT]).is_empty());
+    }
+
+    #[test]
+    fn dropped_events() {
+        let mut state = EvdevState::new(device(None));
+
+        let events = process(
+            &mut state,
+            &[
+                (EV_KEY, 30, 1),
+                (EV_KEY, 30, 2),
+                (EV_KEY, BTN_MOUSE, 1),
+                (EV_KEY, BTN_TOUCH, 1),
+                (EV_ABS, ABS_X, 100),
+                REPORT,
+            ],
+        );
+        assert!(matches!(
+            &events[..],
+            [
+                InputEvent::Keyboard { .. },
+                InputEvent::PointerButton { .. },
+                InputEvent::TouchDown { .. },
+                InputEvent::TouchFrame { .. },
+            ]
+        ));
+
+        let events = process(
+            &mut state,
+            &[
+                (EV_SYN, SYN_DROPPED, 0),
+                (EV_KEY, 31, 1),
+                (EV_REL, REL_X, 10),
+                REPORT,
+            ],
+        );
+        assert!(matches!(
+            &events[..],
+            [
+                InputEvent::TouchCancel { .. },
+                InputEvent::TouchFrame { .. },
+                InputEvent::Keyboard { event: key },
+                InputEvent::PointerButton { event: button },
+            ] if key.state == KeyState::Released && key.count == 0 && button.state == ButtonState::Released
+        ));
+
+        // the release of a key pressed before the drop is not reported twice
+        assert!(process(&mut state, &[(EV_KEY, 30, 0), REPORT]).is_empty());
+    }
+}
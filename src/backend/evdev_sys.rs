@@ -0,0 +1,60 @@
+//! Definitions of the kernel input subsystem shared by the evdev and uinput backends
+// Not every backend uses every definition
+#![allow(dead_code)]
+
+use std::mem;
+
+pub(crate) const EV_SYN: u16 = 0x00;
+pub(crate) const EV_KEY: u16 = 0x01;
+pub(crate) const EV_REL: u16 = 0x02;
+pub(crate) const EV_ABS: u16 = 0x03;
+
+pub(crate) const SYN_REPORT: u16 = 0x00;
+pub(crate) const SYN_DROPPED: u16 = 0x03;
+
+pub(crate) const REL_X: u16 = 0x00;
+pub(crate) const REL_Y: u16 = 0x01;
+pub(crate) const REL_HWHEEL: u16 = 0x06;
+pub(crate) const REL_WHEEL: u16 = 0x08;
+pub(crate) const REL_WHEEL_HI_RES: u16 = 0x0b;
+pub(crate) const REL_HWHEEL_HI_RES: u16 = 0x0c;
+
+pub(crate) const ABS_X: u16 = 0x00;
+pub(crate) const ABS_Y: u16 = 0x01;
+pub(crate) const ABS_MT_SLOT: u16 = 0x2f;
+pub(crate) const ABS_MT_POSITION_X: u16 = 0x35;
+pub(crate) const ABS_MT_POSITION_Y: u16 = 0x36;
+pub(crate) const ABS_MT_TRACKING_ID: u16 = 0x39;
+
+pub(crate) const KEY_ESC: u16 = 0x01;
+pub(crate) const KEY_MICMUTE: u16 = 0xf8;
+pub(crate) const BTN_MISC: u16 = 0x100;
+pub(crate) const BTN_MOUSE: u16 = 0x110;
+pub(crate) const BTN_LEFT: u16 = 0x110;
+pub(crate) const BTN_RIGHT: u16 = 0x111;
+pub(crate) const BTN_MIDDLE: u16 = 0x112;
+pub(crate) const BTN_SIDE: u16 = 0x113;
+pub(crate) const BTN_EXTRA: u16 = 0x114;
+pub(crate) const BTN_JOYSTICK: u16 = 0x120;
+pub(crate) const BTN_TOOL_FINGER: u16 = 0x145;
+pub(crate) const BTN_TOUCH: u16 = 0x14a;
+pub(crate) const KEY_OK: u16 = 0x160;
+pub(crate) const BTN_TRIGGER_HAPPY: u16 = 0x2c0;
+
+pub(crate) const INPUT_PROP_DIRECT: u16 = 0x01;
+
+pub(crate) const BUS_USB: u16 = 0x03;
+pub(crate) const BUS_VIRTUAL: u16 = 0x06;
+
+/// `struct input_event` as defined by the kernel uapi, which does not depend on the libc's time_t
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RawInputEvent {
+    pub(crate) sec: libc::c_ulong,
+    pub(crate) usec: libc::c_ulong,
+    pub(crate) kind: u16,
+    pub(crate) code: u16,
+    pub(crate) value: i32,
+}
+
+pub(crate) const EVENT_SIZE: usize = mem::size_of::<RawInputEvent>();
@@ -0,0 +1,315 @@
+//! Automatic rotation using accelerometers of the industrial I/O subsystem
+//!
+//! Tablets and convertible laptops usually have an accelerometer exposed by the kernel through
+//! the industrial I/O (iio) subsystem. [`Accelerometer`] reads it from sysfs and derives the
+//! [`Orientation`] of the device, [`OrientationSource`] polls it from the event loop and emits
+//! an event whenever the orientation changed.
+//!
+//! To rotate the built-in output accordingly use [`apply_orientation`], which takes the panel
+//! orientation suggested by the drm backend into account (see [`Output::suggested_transform`]).
+//! Touchscreens and tablets mapped to the output using an [`InputMapping`](crate::input::mapping::InputMapping)
+//! follow the transform of the output, so their input is remapped automatically.
+//!
+//! ```no_run
+//! # use std::time::Duration;
+//! # use smithay::backend::iio::{apply_orientation, Accelerometer, OrientationSource};
+//! # use smithay::output::Output;
+//! # let event_loop = smithay::reexports::calloop::EventLoop::<Output>::try_new().unwrap();
+//! if let Some(accelerometer) = Accelerometer::find()? {
+//!     let source = OrientationSource::new(accelerometer, Duration::from_millis(500));
+//!     event_loop
+//!         .handle()
+//!         .insert_source(source, |orientation, _, output: &mut Output| {
+//!             apply_orientation(output, orientation);
+//!         })
+//!         .unwrap();
+//! }
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use calloop::{
+    timer::{TimeoutAction, Timer},
+    EventSource, Poll, PostAction, Readiness, Token, TokenFactory,
+};
+use tracing::{debug, warn};
+
+use crate::{output::Output, utils::Transform};
+
+const IIO_DEVICES: &str = "/sys/bus/iio/devices";
+// Angle in degrees the device has to be tilted towards an orientation
+const TILT_THRESHOLD: f64 = 35.0;
+
+/// Orientation of a device, as reported by its accelerometer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Orientation {
+    /// The device is in its natural orientation
+    Normal,
+    /// The device is upside down
+    BottomUp,
+    /// The device is rotated, so its left side is up
+    LeftUp,
+    /// The device is rotated, so its right side is up
+    RightUp,
+}
+
+impl Orientation {
+    /// Returns the transform to apply to the built-in output of the device
+    ///
+    /// This does not include the panel orientation, see [`apply_orientation`].
+    pub fn transform(&self) -> Transform {
+        match self {
+            Orientation::Normal => Transform::Normal,
+            Orientation::BottomUp => Transform::_180,
+            Orientation::LeftUp => Transform::_90,
+            Orientation::RightUp => Transform::_270,
+        }
+    }
+
+    /// Derives the orientation from an acceleration vector in the coordinate system of the device
+    ///
+    /// Returns `previous` if the device is lying flat or tilted in between two orientations.
+    pub fn from_acceleration(acceleration: [f64; 3], previous: Option<Orientation>) -> Option<Orientation> {
+        let [x, y, z] = acceleration;
+        let portrait = x.atan2((y * y + z * z).sqrt()).to_degrees();
+        let landscape = y.atan2((x * x + z * z).sqrt()).to_degrees();
+
+        let is_portrait = portrait.abs() > TILT_THRESHOLD;
+        let is_landscape = landscape.abs() > TILT_THRESHOLD;
+        if is_portrait == is_landscape {
+            return previous;
+        }
+
+        Some(if is_portrait {
+            if portrait > 0.0 {
+                Orientation::LeftUp
+            } else {
+                Orientation::RightUp
+            }
+        } else if landscape < 0.0 {
+            Orientation::Normal
+        } else {
+            Orientation::BottomUp
+        })
+    }
+}
+
+/// An accelerometer of the industrial I/O subsystem
+#[derive(Debug)]
+pub struct Accelerometer {
+    path: PathBuf,
+    scale: f64,
+    mount_matrix: [[f64; 3]; 3],
+    orientation: Option<Orientation>,
+}
+
+impl Accelerometer {
+    /// Opens the accelerometer at the given sysfs path, e.g. `/sys/bus/iio/devices/iio:device0`
+    pub fn open(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        if !path.join("in_accel_x_raw").exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "iio device has no accelerometer channels",
+            ));
+        }
+
+        let scale = read_value(&path.join("in_accel_scale")).unwrap_or(1.0);
+        let mount_matrix = ["in_accel_mount_matrix", "mount_matrix"]
+            .iter()
+            .find_map(|name| parse_mount_matrix(&fs::read_to_string(path.join(name)).ok()?))
+            .unwrap_or([[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]);
+        debug!(?path, scale, ?mount_matrix, "Opened accelerometer");
+
+        Ok(Accelerometer {
+            path,
+            scale,
+            mount_matrix,
+            orientation: None,
+        })
+    }
+
+    /// Finds the first accelerometer of the system
+    pub fn find() -> io::Result<Option<Self>> {
+        let mut devices = fs::read_dir(IIO_DEVICES)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .collect::<Vec<_>>();
+        devices.sort();
+        Ok(devices
+            .into_iter()
+            .find_map(|path| Accelerometer::open(path).ok()))
+    }
+
+    /// Returns the sysfs path of the accelerometer
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Reads the current acceleration in m/s², in the coordinate system of the device
+    pub fn acceleration(&self) -> io::Result<[f64; 3]> {
+        let mut raw = [0.0; 3];
+        for (value, axis) in raw.iter_mut().zip(["x", "y", "z"]) {
+            *value = read_value(&self.path.join(format!("in_accel_{axis}_raw")))? * self.scale;
+        }
+        Ok(self
+            .mount_matrix
+            .map(|row| row.iter().zip(raw).map(|(m, v)| m * v).sum()))
+    }
+
+    /// Returns the last known orientation
+    pub fn orientation(&self) -> Option<Orientation> {
+        self.orientation
+    }
+
+    /// Reads the accelerometer, returning the new orientation if it changed
+    pub fn update(&mut self) -> io::Result<Option<Orientation>> {
+        let orientation = Orientation::from_acceleration(self.acceleration()?, self.orientation);
+        if orientation == self.orientation {
+            return Ok(None);
+        }
+        self.orientation = orientation;
+        Ok(orientation)
+    }
+}
+
+fn read_value(path: &Path) -> io::Result<f64> {
+    fs::read_to_string(path)?
+        .trim()
+        .parse()
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+// The mount matrix is formatted as "x1, y1, z1; x2, y2, z2; x3, y3, z3"
+fn parse_mount_matrix(data: &str) -> Option<[[f64; 3]; 3]> {
+    let mut matrix = [[0.0; 3]; 3];
+    let mut rows = data.trim().split(';');
+    for row in matrix.iter_mut() {
+        let mut values = rows.next()?.split(',');
+        for value in row.iter_mut() {
+            *value = values.next()?.trim().parse().ok()?;
+        }
+    }
+    Some(matrix)
+}
+
+/// Event source polling an [`Accelerometer`], emitting its [`Orientation`] whenever it changes
+#[derive(Debug)]
+pub struct OrientationSource {
+    accelerometer: Accelerometer,
+    interval: Duration,
+    timer: Timer,
+}
+
+impl OrientationSource {
+    /// Create a source polling the accelerometer every `interval`
+    ///
+    /// The current orientation is emitted once the source is inserted into the event loop.
+    pub fn new(accelerometer: Accelerometer, interval: Duration) -> Self {
+        OrientationSource {
+            accelerometer,
+            interval,
+            timer: Timer::immediate(),
+        }
+    }
+
+    /// Returns the polled accelerometer
+    pub fn accelerometer(&self) -> &Accelerometer {
+        &self.accelerometer
+    }
+}
+
+impl EventSource for OrientationSource {
+    type Event = Orientation;
+    type Metadata = ();
+    type Ret = ();
+    type Error = io::Error;
+
+    fn process_events<F>(
+        &mut self,
+        readiness: Readiness,
+        token: Token,
+        mut callback: F,
+    ) -> Result<PostAction, Self::Error>
+    where
+        F: FnMut(Self::Event, &mut Self::Metadata) -> Self::Ret,
+    {
+        let accelerometer = &mut self.accelerometer;
+        let interval = self.interval;
+        let mut changed = None;
+        self.timer.process_events(readiness, token, |_, _| {
+            match accelerometer.update() {
+                Ok(orientation) => changed = orientation,
+                Err(err) => warn!(?err, path = ?accelerometer.path(), "Failed to read accelerometer"),
+            }
+            TimeoutAction::ToDuration(interval)
+        })?;
+
+        if let Some(orientation) = changed {
+            callback(orientation, &mut ());
+        }
+        Ok(PostAction::Continue)
+    }
+
+    fn register(&mut self, poll: &mut Poll, token_factory: &mut TokenFactory) -> calloop::Result<()> {
+        self.timer.register(poll, token_factory)
+    }
+
+    fn reregister(&mut self, poll: &mut Poll, token_factory: &mut TokenFactory) -> calloop::Result<()> {
+        self.timer.reregister(poll, token_factory)
+    }
+
+    fn unregister(&mut self, poll: &mut Poll) -> calloop::Result<()> {
+        self.timer.unregister(poll)
+    }
+}
+
+/// Rotates the built-in output of a device according to its orientation
+///
+/// The transform of the orientation is combined with the [suggested transform](Output::suggested_transform)
+/// of the output, which compensates the mounting of the panel. Returns the applied transform.
+pub fn apply_orientation(output: &Output, orientation: Orientation) -> Transform {
+    let transform = output.suggested_transform() + orientation.transform();
+    output.change_current_state(None, Some(transform), None, None);
+    transform
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn orientation() {
+        let g = 9.81;
+        assert_eq!(
+            Orientation::from_acceleration([0.0, -g, 0.0], None),
+            Some(Orientation::Normal)
+        );
+        assert_eq!(
+            Orientation::from_acceleration([0.0, g, 0.0], None),
+            Some(Orientation::BottomUp)
+        );
+        assert_eq!(
+            Orientation::from_acceleration([g, 0.0, 0.0], None),
+            Some(Orientation::LeftUp)
+        );
+        assert_eq!(
+            Orientation::from_acceleration([-g, 0.0, 0.0], None),
+            Some(Orientation::RightUp)
+        );
+        // lying flat keeps the previous orientation
+        assert_eq!(
+            Orientation::from_acceleration([0.0, 0.0, g], Some(Orientation::LeftUp)),
+            Some(Orientation::LeftUp)
+        );
+
+        assert_eq!(
+            parse_mount_matrix("0, 1, 0; -1, 0, 0; 0, 0, 1\n"),
+            Some([[0.0, 1.0, 0.0], [-1.0, 0.0, 0.0], [0.0, 0.0, 1.0]])
+        );
+    }
+}
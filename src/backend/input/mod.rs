@@ -34,6 +34,14 @@ pub trait Device: PartialEq + Eq + std::hash::Hash {
     ///
     /// The path is an absolute path and includes the sys mount point.
     fn syspath(&self) -> Option<PathBuf>;
+
+    /// Returns the name of the seat the device is assigned to, e.g. `seat0`
+    ///
+    /// This is the physical seat name assigned by udev using the `ID_SEAT` property.
+    /// Backends without seat assignments return `None`.
+    fn seat_name(&self) -> Option<String> {
+        None
+    }
 }
 
 /// Set of input types a device may provide
@@ -541,6 +549,81 @@ impl From<TouchSlot> for i32 {
     }
 }
 
+/// Remapping of touch tracking ids to stable [`TouchSlot`]s
+///
+/// Some backends only provide tracking ids, which are not reused and may grow unbounded,
+/// or may even be reused while the previous touch point is still active. This maps the ids of
+/// active touch points to the lowest free slot, like the kernel does for multi-touch devices,
+/// so the slots reaching the [`TouchHandle`](crate::input::touch::TouchHandle) stay small and stable.
+#[derive(Debug, Default, Clone)]
+pub struct TouchSlotMap {
+    slots: Vec<Option<u64>>,
+}
+
+impl TouchSlotMap {
+    /// Create an empty map
+    pub fn new() -> Self {
+        TouchSlotMap::default()
+    }
+
+    /// Assign a slot to a new touch point
+    ///
+    /// If the id is already active, the slot of the active touch point is returned.
+    pub fn down(&mut self, id: u64) -> TouchSlot {
+        if let Some(slot) = self.get(id) {
+            return slot;
+        }
+
+        let index = match self.slots.iter().position(Option::is_none) {
+            Some(index) => {
+                self.slots[index] = Some(id);
+                index
+            }
+            None => {
+                self.slots.push(Some(id));
+                self.slots.len() - 1
+            }
+        };
+        Some(index as u32).into()
+    }
+
+    /// Slot of an active touch point
+    pub fn get(&self, id: u64) -> Option<TouchSlot> {
+        self.slots
+            .iter()
+            .position(|slot| *slot == Some(id))
+            .map(|index| Some(index as u32).into())
+    }
+
+    /// Release the slot of a touch point, which was lifted or cancelled
+    ///
+    /// Returns `None`, if the id is not active.
+    pub fn up(&mut self, id: u64) -> Option<TouchSlot> {
+        let slot = self.get(id)?;
+        let index = slot.id.unwrap() as usize;
+        self.slots[index] = None;
+        while self.slots.last().is_some_and(Option::is_none) {
+            self.slots.pop();
+        }
+        Some(slot)
+    }
+
+    /// Release all slots, returning the slots of the active touch points
+    pub fn clear(&mut self) -> Vec<TouchSlot> {
+        self.slots
+            .drain(..)
+            .enumerate()
+            .filter(|(_, id)| id.is_some())
+            .map(|(index, _)| Some(index as u32).into())
+            .collect()
+    }
+
+    /// Returns whether no touch point is active
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+}
+
 /// Trait with functions available for all touch events.
 pub trait TouchEvent<B: InputBackend>: Event<B> {
     /// Multi-touch slot identifier.
@@ -832,3 +915,44 @@ pub(crate) fn xorg_mouse_to_libinput(xorg: u32) -> u32 {
         _ => xorg - 8 + 0x113, // BTN_SIZE
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slot(id: u32) -> TouchSlot {
+        Some(id).into()
+    }
+
+    #[test]
+    fn touch_slot_reuse() {
+        let mut map = TouchSlotMap::new();
+        assert_eq!(map.down(1000), slot(0));
+        assert_eq!(map.down(1001), slot(1));
+        assert_eq!(map.down(1002), slot(2));
+
+        // lifting out of order frees the lowest slot first
+        assert_eq!(map.up(1001), Some(slot(1)));
+        assert_eq!(map.up(1000), Some(slot(0)));
+        assert_eq!(map.down(1003), slot(0));
+        assert_eq!(map.down(1004), slot(1));
+        assert_eq!(map.get(1002), Some(slot(2)));
+
+        assert_eq!(map.clear(), vec![slot(0), slot(1), slot(2)]);
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn touch_slot_unknown_ids() {
+        let mut map = TouchSlotMap::new();
+        assert_eq!(map.up(1), None);
+        assert_eq!(map.get(1), None);
+
+        // repeated down events keep the slot of the active touch point
+        assert_eq!(map.down(1), slot(0));
+        assert_eq!(map.down(1), slot(0));
+        assert_eq!(map.up(1), Some(slot(0)));
+        assert_eq!(map.up(1), None);
+        assert!(map.is_empty());
+    }
+}
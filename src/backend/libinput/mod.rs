@@ -5,6 +5,7 @@ use crate::backend::input::{
 };
 #[cfg(feature = "backend_session")]
 use crate::backend::session::{AsErrno, Session};
+use crate::input::feedback::{Feedback, FeedbackDevice, FeedbackError, FeedbackKind};
 use input as libinput;
 use input::event;
 
@@ -83,6 +84,10 @@ impl backend::Device for libinput::Device {
         #[cfg(not(feature = "udev"))]
         None
     }
+
+    fn seat_name(&self) -> Option<String> {
+        Some(libinput::Device::seat(self).physical_name().into())
+    }
 }
 
 impl From<backend::DeviceCapability> for libinput::DeviceCapability {
@@ -669,6 +674,87 @@ impl From<crate::input::keyboard::LedState> for libinput::Led {
     }
 }
 
+/// libinput supports setting the leds of keyboards.
+impl FeedbackDevice for libinput::Device {
+    fn supports_feedback(&self, kind: FeedbackKind) -> bool {
+        kind == FeedbackKind::KeyboardLeds && self.has_capability(libinput::DeviceCapability::Keyboard)
+    }
+
+    fn feedback(&mut self, feedback: Feedback) -> Result<(), FeedbackError> {
+        if !self.supports_feedback(feedback.kind()) {
+            return Err(FeedbackError::Unsupported(feedback.kind()));
+        }
+        match feedback {
+            Feedback::KeyboardLeds(leds) => self.led_update(leds.into()),
+        }
+        Ok(())
+    }
+}
+
+/// Keeps the leds of libinput keyboards in sync with the xkb state of a seat
+///
+/// Feed device events using [`LibinputKeyboardLeds::process_event`] and led changes reported by
+/// [`SeatHandler::led_state_changed`](crate::input::SeatHandler::led_state_changed) using
+/// [`LibinputKeyboardLeds::update`]. All keyboards show the same led state, so locking caps lock
+/// on one keyboard lights up the caps lock led of every keyboard of the seat.
+#[derive(Debug, Default)]
+pub struct LibinputKeyboardLeds {
+    keyboards: Vec<libinput::Device>,
+    led_state: crate::input::keyboard::LedState,
+}
+
+impl LibinputKeyboardLeds {
+    /// Create a new tracker with all leds turned off
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Tracks added and removed keyboards
+    ///
+    /// Newly added keyboards are initialized with the current led state.
+    pub fn process_event(&mut self, event: &InputEvent<LibinputInputBackend>) {
+        match event {
+            InputEvent::DeviceAdded { device } => self.add_keyboard(device.clone()),
+            InputEvent::DeviceRemoved { device } => self.remove_keyboard(device),
+            _ => {}
+        }
+    }
+
+    /// Adds a keyboard and sets its leds to the current state
+    ///
+    /// Devices without the keyboard capability are ignored.
+    pub fn add_keyboard(&mut self, mut device: libinput::Device) {
+        if !device.has_capability(libinput::DeviceCapability::Keyboard) || self.keyboards.contains(&device) {
+            return;
+        }
+        device.led_update(self.led_state.into());
+        self.keyboards.push(device);
+    }
+
+    /// Stops tracking a keyboard
+    pub fn remove_keyboard(&mut self, device: &libinput::Device) {
+        self.keyboards.retain(|keyboard| keyboard != device);
+    }
+
+    /// Updates the leds of all keyboards
+    pub fn update(&mut self, led_state: crate::input::keyboard::LedState) {
+        self.led_state = led_state;
+        for keyboard in self.keyboards.iter_mut() {
+            keyboard.led_update(led_state.into());
+        }
+    }
+
+    /// Returns the current led state
+    pub fn led_state(&self) -> crate::input::keyboard::LedState {
+        self.led_state
+    }
+
+    /// Returns the tracked keyboards
+    pub fn keyboards(&self) -> &[libinput::Device] {
+        &self.keyboards
+    }
+}
+
 /// Wrapper for types implementing the [`Session`] trait to provide
 /// a [`libinput::LibinputInterface`] implementation.
 #[cfg(feature = "backend_session")]
@@ -19,6 +19,9 @@
 //! feature), which allows the discovery of usable graphics and input devices on the system, using
 //! the udev system daemon.
 //!
+//! Backlight devices controlling the brightness of built-in panels are found and monitored by the
+//! [`backlight`] module, also gated by the `backend_udev` cargo feature.
+//!
 //! ### Input handling
 //!
 //! Input handling consists in discovering the various available input devices, and receiving
@@ -28,6 +31,14 @@
 //! [`libinput`] module, gated by the `backend_libinput` cargo feature. The winit backend
 //! (see below) also provides an input provider.
 //!
+//! Systems without libinput can read evdev devices directly using the minimal [`evdev`] module,
+//! gated by the `backend_evdev` cargo feature. Virtual input devices to inject input for testing
+//! and automation can be created using the [`uinput`] module, gated by the `backend_uinput` cargo
+//! feature.
+//!
+//! Accelerometers used to automatically rotate the outputs of tablets and convertibles are read
+//! by the [`iio`] module, gated by the `backend_iio` cargo feature.
+//!
 //! ### Graphics
 //!
 //! Combining content from the clients and displaying it on the screen is the central role of
@@ -80,19 +91,30 @@
 //!
 
 pub mod allocator;
+pub mod encode;
 pub mod input;
 pub mod renderer;
 
+#[cfg(feature = "backend_udev")]
+pub mod backlight;
 #[cfg(feature = "backend_drm")]
 pub mod drm;
 #[cfg(feature = "backend_egl")]
 pub mod egl;
+#[cfg(feature = "backend_evdev")]
+pub mod evdev;
+#[cfg(any(feature = "backend_evdev", feature = "backend_uinput"))]
+mod evdev_sys;
+#[cfg(feature = "backend_iio")]
+pub mod iio;
 #[cfg(feature = "backend_libinput")]
 pub mod libinput;
 #[cfg(feature = "backend_session")]
 pub mod session;
 #[cfg(feature = "backend_udev")]
 pub mod udev;
+#[cfg(feature = "backend_uinput")]
+pub mod uinput;
 
 #[cfg(feature = "backend_vulkan")]
 pub mod vulkan;
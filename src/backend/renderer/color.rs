@@ -75,3 +75,111 @@ impl Mul<f32> for Color32F {
         Self::new(self.r() * rhs, self.g() * rhs, self.b() * rhs, self.a() * rhs)
     }
 }
+
+/// Matrix coefficients used to convert YCbCr buffer contents to RGB
+///
+/// See Recommendation ITU-T H.273 for the exact definitions.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum MatrixCoefficients {
+    /// The identity matrix, only valid for RGB buffers
+    Identity,
+    /// Rec. ITU-R BT.709
+    Bt709,
+    /// US FCC Title 47
+    Fcc,
+    /// Rec. ITU-R BT.601
+    Bt601,
+    /// SMPTE ST 240
+    Smpte240,
+    /// Rec. ITU-R BT.2020 non-constant luminance
+    Bt2020,
+    /// Rec. ITU-R BT.2020 constant luminance
+    Bt2020ConstantLuminance,
+    /// Rec. ITU-R BT.2100 ICtCp
+    ICtCp,
+}
+
+/// Quantization range of YCbCr buffer contents
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ColorRange {
+    /// Full range, using all available code points
+    Full,
+    /// Limited range, sometimes called narrow or video range
+    Limited,
+}
+
+/// Location of the chroma samples of 4:2:0 subsampled YCbCr buffers
+///
+/// Matches the Chroma420SampleLocType code points of Recommendation ITU-T H.273.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ChromaLocation {
+    /// Horizontal offset of 0, vertical offset of 0.5
+    Type0,
+    /// Horizontal offset of 0.5, vertical offset of 0.5
+    Type1,
+    /// Horizontal offset of 0, vertical offset of 0
+    Type2,
+    /// Horizontal offset of 0.5, vertical offset of 0
+    Type3,
+    /// Horizontal offset of 0, vertical offset of 1
+    Type4,
+    /// Horizontal offset of 0.5, vertical offset of 1
+    Type5,
+}
+
+impl ChromaLocation {
+    /// Horizontal offset of the chroma samples in units of luma samples
+    pub fn horizontal_offset(&self) -> f32 {
+        match self {
+            ChromaLocation::Type0 | ChromaLocation::Type2 | ChromaLocation::Type4 => 0.0,
+            ChromaLocation::Type1 | ChromaLocation::Type3 | ChromaLocation::Type5 => 0.5,
+        }
+    }
+
+    /// Vertical offset of the chroma samples in units of luma samples
+    pub fn vertical_offset(&self) -> f32 {
+        match self {
+            ChromaLocation::Type2 | ChromaLocation::Type3 => 0.0,
+            ChromaLocation::Type0 | ChromaLocation::Type1 => 0.5,
+            ChromaLocation::Type4 | ChromaLocation::Type5 => 1.0,
+        }
+    }
+}
+
+/// Parameters for converting YCbCr buffer contents to RGB
+///
+/// Unset values are chosen by the renderer.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct YuvConversion {
+    /// Matrix coefficients
+    pub coefficients: Option<MatrixCoefficients>,
+    /// Quantization range
+    pub range: Option<ColorRange>,
+    /// Location of the chroma samples
+    pub chroma_location: Option<ChromaLocation>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chroma_location_offsets() {
+        let offsets = |location: ChromaLocation| (location.horizontal_offset(), location.vertical_offset());
+        assert_eq!(offsets(ChromaLocation::Type0), (0.0, 0.5));
+        assert_eq!(offsets(ChromaLocation::Type1), (0.5, 0.5));
+        assert_eq!(offsets(ChromaLocation::Type2), (0.0, 0.0));
+        assert_eq!(offsets(ChromaLocation::Type3), (0.5, 0.0));
+        assert_eq!(offsets(ChromaLocation::Type4), (0.0, 1.0));
+        assert_eq!(offsets(ChromaLocation::Type5), (0.5, 1.0));
+    }
+
+    #[test]
+    fn premultiplied_alpha() {
+        let color = Color32F::new(1.0, 0.5, 0.25, 1.0);
+        assert!(color.is_opaque());
+        let faded = color * 0.5;
+        assert_eq!(faded.components(), [0.5, 0.25, 0.125, 0.5]);
+        assert!(!faded.is_opaque());
+    }
+}
@@ -13,6 +13,7 @@
 //! - Current geometry for elements entering the output
 //! - Current and last known geometry for moved elements (includes z-index changes)
 //! - Last known geometry for elements no longer present
+//! - The whole output, if its size, scale or transform changed
 //!
 //! Elements fully occluded by opaque regions as defined by elements higher in the stack are skipped.
 //! The actual action taken by the damage tracker can be inspected from the returned [`RenderElementStates`].
@@ -176,6 +177,7 @@ impl ElementState {
 struct RendererState {
     transform: Option<Transform>,
     size: Option<Size<i32, Physical>>,
+    scale: Option<Scale<f64>>,
     elements: IndexMap<Id, ElementState>,
     old_damage: VecDeque<Vec<Rectangle<i32, Physical>>>,
     opaque_regions: Vec<Rectangle<i32, Physical>>,
@@ -311,8 +313,6 @@ impl OutputDamageTracker {
     /// Render this output with the provided [`Renderer`]
     ///
     /// - `elements` for this output in front-to-back order
-    #[instrument(level = "trace", parent = &self.span, skip(renderer, framebuffer, elements, clear_color))]
-    #[profiling::function]
     pub fn render_output<E, R>(
         &mut self,
         renderer: &mut R,
@@ -325,6 +325,47 @@ impl OutputDamageTracker {
         E: RenderElement<R>,
         R: Renderer,
         R::TextureId: Texture,
+    {
+        self.render_output_with(
+            renderer,
+            framebuffer,
+            age,
+            elements,
+            clear_color,
+            |_, _, _| Ok(()),
+        )
+    }
+
+    /// Render this output with the provided [`Renderer`], announcing the damage before rendering
+    ///
+    /// Works like [`render_output`](OutputDamageTracker::render_output), but calls `pre_render` with
+    /// the damage of the frame before anything is rendered. This allows to limit the region of the
+    /// framebuffer updated by the renderer, e.g. using `GlesRenderer::set_damage_region`, which
+    /// reduces memory bandwidth on tiled GPUs.
+    ///
+    /// `pre_render` is not called, if there is no damage.
+    ///
+    /// - `elements` for this output in front-to-back order
+    #[instrument(
+        level = "trace",
+        parent = &self.span,
+        skip(renderer, framebuffer, elements, clear_color, pre_render)
+    )]
+    #[profiling::function]
+    pub fn render_output_with<'buffer, E, R, F>(
+        &mut self,
+        renderer: &mut R,
+        framebuffer: &mut R::Framebuffer<'buffer>,
+        age: usize,
+        elements: &[E],
+        clear_color: impl Into<Color32F>,
+        pre_render: F,
+    ) -> Result<RenderOutputResult<'_>, Error<R::Error>>
+    where
+        E: RenderElement<R>,
+        R: Renderer,
+        R::TextureId: Texture,
+        F: FnOnce(&mut R, &mut R::Framebuffer<'buffer>, &[Rectangle<i32, Physical>]) -> Result<(), R::Error>,
     {
         let clear_color = clear_color.into();
         let (output_size, output_scale, output_transform) =
@@ -366,6 +407,7 @@ impl OutputDamageTracker {
             // we have to take the element damage to be able to move it around
             let mut element_damage = std::mem::take(&mut self.element_damage);
             let mut element_opaque_regions = std::mem::take(&mut self.element_opaque_regions);
+            pre_render(renderer, framebuffer, &self.damage)?;
             let mut frame = renderer.render(framebuffer, output_size, output_transform)?;
 
             element_damage.clear();
@@ -374,8 +416,11 @@ impl OutputDamageTracker {
                 Rectangle::subtract_rects_many_in_place(element_damage, self.opaque_regions.iter().copied());
 
             trace!("clearing damage {:?}", element_damage);
+            frame.begin_scope("clear");
             frame.clear(clear_color, &element_damage)?;
+            frame.end_scope();
 
+            frame.begin_scope("elements");
             for (z_index, element) in render_elements.iter().rev().enumerate() {
                 let element_id = element.id();
                 let element_geometry = element.geometry(output_scale);
@@ -435,6 +480,8 @@ impl OutputDamageTracker {
                 )?;
             }
 
+            frame.end_scope();
+
             // return the element damage so that we can re-use the allocation
             std::mem::swap(&mut self.element_damage, &mut element_damage);
             std::mem::swap(&mut self.element_opaque_regions, &mut element_opaque_regions);
@@ -669,18 +716,23 @@ impl OutputDamageTracker {
         std::mem::swap(&mut self.element_damage, &mut element_damage);
 
         if self.last_state.size != Some(output_geo.size)
+            || self.last_state.scale != Some(output_scale)
             || self.last_state.transform != Some(output_transform)
             || self.last_state.clear_color != clear_color
         {
-            // The output geometry or transform changed, so just damage everything
+            // The output geometry, scale or transform changed, so just damage everything.
+            // This is stored as new damage, so buffers rendered before the change get
+            // fully redrawn, regardless of their age.
             trace!(
                 previous_geometry = ?self.last_state.size,
                 current_geometry = ?output_geo.size,
+                previous_scale = ?self.last_state.scale,
+                current_scale = ?output_scale,
                 previous_transform = ?self.last_state.transform,
                 current_transform = ?output_transform,
                 previous_clear_color = ?self.last_state.clear_color,
                 current_clear_color = ?clear_color,
-                "Output geometry, transform or clear color changed, damaging whole output geometry");
+                "Output geometry, scale, transform or clear color changed, damaging whole output geometry");
             self.damage.clear();
             self.damage.push(output_geo);
         }
@@ -773,6 +825,7 @@ impl OutputDamageTracker {
                 });
 
         self.last_state.size = Some(output_geo.size);
+        self.last_state.scale = Some(output_scale);
         self.last_state.transform = Some(output_transform);
         self.last_state.elements = new_elements_state;
         self.last_state.old_damage.push_front(new_damage);
@@ -786,3 +839,61 @@ impl OutputDamageTracker {
         element_render_states
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        backend::renderer::element::{solid::SolidColorRenderElement, Kind},
+        output::{test_output, Mode, Scale as OutputScale},
+    };
+
+    #[test]
+    fn full_damage_after_output_changes() {
+        let output = test_output("test", (100, 100));
+        let mode = |w, h| Mode {
+            size: (w, h).into(),
+            refresh: 60_000,
+        };
+        output.change_current_state(None, None, Some(OutputScale::Integer(1)), None);
+
+        let mut tracker = OutputDamageTracker::from_output(&output);
+        let id = Id::new();
+        let mut commit = CommitCounter::default();
+        let mut frame = |tracker: &mut OutputDamageTracker, age| {
+            // the element changes every frame, so every frame gets submitted
+            commit.increment();
+            let element = SolidColorRenderElement::new(
+                id.clone(),
+                Rectangle::new((10, 10).into(), (20, 20).into()),
+                commit,
+                [1.0, 0.0, 0.0, 1.0],
+                Kind::Unspecified,
+            );
+            tracker.damage_output(age, &[element]).unwrap().0.cloned()
+        };
+
+        let full = Rectangle::from_size((100, 100).into());
+        let element = Rectangle::new((10, 10).into(), (20, 20).into());
+        assert_eq!(frame(&mut tracker, 0), Some(vec![full]));
+        // two buffers in flight
+        assert_eq!(frame(&mut tracker, 0), Some(vec![full]));
+        assert_eq!(frame(&mut tracker, 2), Some(vec![element]));
+        assert_eq!(frame(&mut tracker, 2), Some(vec![element]));
+
+        // Every change has to fully redraw the next two buffers, which still contain
+        // the contents from before the change.
+        let changes = [
+            ((200, 100), Some(mode(200, 100)), None, None),
+            ((100, 200), None, Some(Transform::_90), None),
+            ((100, 200), None, None, Some(OutputScale::Fractional(1.5))),
+        ];
+        for (size, mode, transform, scale) in changes {
+            output.change_current_state(mode, transform, scale, None);
+            let full = Rectangle::from_size(size.into());
+            assert_eq!(frame(&mut tracker, 2), Some(vec![full]));
+            assert_eq!(frame(&mut tracker, 2), Some(vec![full]));
+            assert_eq!(frame(&mut tracker, 2), Some(vec![element]));
+        }
+    }
+}
@@ -0,0 +1,260 @@
+//! Element to mirror the frames composed for another output
+//!
+//! Mirroring an output onto another one with a different refresh rate by rendering the scene twice
+//! is expensive, while forcing both outputs to a common rate is often not possible.
+//! [`MirrorSource`] instead samples the latest frame composed for the source output and provides a
+//! [`MirrorRenderElement`] to show it on the mirroring output at its own cadence.
+//!
+//! The buffers of the source output, e.g. the slots of its swapchain, are imported once and reused
+//! as long as they are alive. The damage of all source frames submitted between two frames of the
+//! mirroring output is accumulated, so only the changed regions are redrawn.
+//!
+//! # How to use it
+//!
+//! After rendering a frame for the source output submit its buffer with [`MirrorSource::submit_frame`].
+//! When using a [`DrmCompositor`](crate::backend::drm::compositor::DrmCompositor), the source output has to
+//! be composited without using overlay or cursor planes, so its primary plane shows the complete frame.
+//! The buffer can then be retrieved using [`PrimarySwapchainElement::export`] and the damage since the
+//! previously submitted frame using [`DamageSnapshot::damage_since`] with the
+//! [`CommitCounter`](crate::backend::renderer::utils::CommitCounter) of the last submitted snapshot:
+//!
+//! ```no_run
+//! # use smithay::backend::{
+//! #     allocator::{dmabuf::Dmabuf, Buffer},
+//! #     renderer::{
+//! #         element::{mirror::MirrorSource, Kind},
+//! #         sync::SyncPoint,
+//! #         test::DummyRenderer,
+//! #         utils::CommitCounter,
+//! #     },
+//! # };
+//! # use smithay::utils::{Rectangle, Transform};
+//! # let mut renderer = DummyRenderer::default();
+//! # let (dmabuf, transform, sync): (Dmabuf, Transform, SyncPoint) = todo!();
+//! let mut mirror = MirrorSource::default();
+//!
+//! // after rendering the source output
+//! let damage = [Rectangle::from_size(dmabuf.size())];
+//! mirror.submit_frame(&mut renderer, &dmabuf, transform, Some(&damage), sync)?;
+//!
+//! // when rendering the mirroring output
+//! if let Some(element) = mirror.render_element((0.0, 0.0), None, None, Kind::Unspecified) {
+//!     // render the element, e.g. using an `OutputDamageTracker`
+//! }
+//! # Ok::<(), smithay::backend::renderer::test::DummyError>(())
+//! ```
+//!
+//! [`PrimarySwapchainElement::export`]: crate::backend::drm::compositor::PrimarySwapchainElement::export
+//! [`DamageSnapshot::damage_since`]: crate::backend::renderer::utils::DamageSnapshot::damage_since
+
+use tracing::{instrument, warn};
+
+use crate::{
+    backend::{
+        allocator::{
+            dmabuf::{Dmabuf, WeakDmabuf},
+            Buffer as _,
+        },
+        renderer::{
+            sync::SyncPoint,
+            utils::{DamageSet, OpaqueRegions},
+            Frame, ImportDma, Renderer, Texture,
+        },
+    },
+    utils::{Buffer, Logical, Physical, Point, Rectangle, Scale, Size, Transform},
+};
+
+use super::{
+    texture::{TextureRenderBuffer, TextureRenderElement},
+    CommitCounter, Element, Id, Kind, RenderElement,
+};
+
+// Enough for the slots of a swapchain
+const MAX_CACHED_TEXTURES: usize = 4;
+
+/// Latest frame composed for a mirrored output
+#[derive(Debug)]
+pub struct MirrorSource<T: Texture> {
+    buffer: Option<TextureRenderBuffer<T>>,
+    textures: Vec<(WeakDmabuf, T)>,
+    size: Size<i32, Buffer>,
+    transform: Transform,
+    sync: SyncPoint,
+}
+
+impl<T: Texture> Default for MirrorSource<T> {
+    fn default() -> Self {
+        MirrorSource {
+            buffer: None,
+            textures: Vec::new(),
+            size: Size::default(),
+            transform: Transform::Normal,
+            sync: SyncPoint::signaled(),
+        }
+    }
+}
+
+impl<T: Texture + Clone> MirrorSource<T> {
+    /// Create a new mirror source without any frame
+    pub fn new() -> Self {
+        MirrorSource::default()
+    }
+
+    /// Submit the buffer of a frame composed for the source output
+    ///
+    /// `transform` is the transform the frame was rendered with, usually the transform of the
+    /// source output. `damage` is the damage since the previously submitted frame, `None`
+    /// damages the whole buffer. The mirroring output waits for `sync` before sampling the buffer.
+    #[instrument(level = "trace", skip_all)]
+    pub fn submit_frame<R>(
+        &mut self,
+        renderer: &mut R,
+        dmabuf: &Dmabuf,
+        transform: Transform,
+        damage: Option<&[Rectangle<i32, Buffer>]>,
+        sync: SyncPoint,
+    ) -> Result<(), R::Error>
+    where
+        R: Renderer<TextureId = T> + ImportDma,
+    {
+        let texture = match self
+            .textures
+            .iter()
+            .find(|(buffer, _)| buffer.upgrade().as_ref() == Some(dmabuf))
+            .map(|(_, texture)| texture.clone())
+        {
+            Some(texture) => texture,
+            None => {
+                let texture = renderer.import_dmabuf(dmabuf, None)?;
+                self.textures.retain(|(buffer, _)| !buffer.is_gone());
+                if self.textures.len() >= MAX_CACHED_TEXTURES {
+                    self.textures.remove(0);
+                }
+                self.textures.push((dmabuf.weak(), texture.clone()));
+                texture
+            }
+        };
+
+        let size = dmabuf.size();
+        match (self.buffer.as_mut(), damage) {
+            (Some(buffer), Some(damage)) if size == self.size && transform == self.transform => {
+                buffer.update_from_texture_with_damage(renderer, texture, damage.iter().copied());
+            }
+            (Some(buffer), _) => {
+                buffer.update_from_texture(renderer, texture, 1, transform, None);
+            }
+            (None, _) => {
+                self.buffer = Some(TextureRenderBuffer::from_texture(
+                    renderer, texture, 1, transform, None,
+                ));
+            }
+        }
+        self.size = size;
+        self.transform = transform;
+        self.sync = sync;
+        Ok(())
+    }
+
+    /// Drop the current frame and the cached textures, e.g. when the source output is disabled
+    pub fn reset(&mut self) {
+        *self = MirrorSource::default();
+    }
+
+    /// Returns the size of the current frame, with the transform of the source output applied
+    pub fn size(&self) -> Option<Size<i32, Logical>> {
+        self.buffer
+            .as_ref()
+            .map(|_| self.size.to_logical(1, self.transform))
+    }
+
+    /// Create a render element showing the current frame
+    ///
+    /// `size` scales the frame to the given size, by default the frame is shown at its original size.
+    /// Returns `None` if no frame was submitted yet.
+    pub fn render_element(
+        &self,
+        location: impl Into<Point<f64, Physical>>,
+        size: Option<Size<i32, Logical>>,
+        alpha: Option<f32>,
+        kind: Kind,
+    ) -> Option<MirrorRenderElement<T>> {
+        let buffer = self.buffer.as_ref()?;
+        Some(MirrorRenderElement {
+            texture: TextureRenderElement::from_texture_render_buffer(
+                location, buffer, alpha, None, size, kind,
+            ),
+            sync: self.sync.clone(),
+        })
+    }
+}
+
+/// A render element showing the frame of a [`MirrorSource`]
+#[derive(Debug)]
+pub struct MirrorRenderElement<T: Texture> {
+    texture: TextureRenderElement<T>,
+    sync: SyncPoint,
+}
+
+impl<T: Texture> Element for MirrorRenderElement<T> {
+    fn id(&self) -> &Id {
+        self.texture.id()
+    }
+
+    fn current_commit(&self) -> CommitCounter {
+        self.texture.current_commit()
+    }
+
+    fn location(&self, scale: Scale<f64>) -> Point<i32, Physical> {
+        self.texture.location(scale)
+    }
+
+    fn src(&self) -> Rectangle<f64, Buffer> {
+        self.texture.src()
+    }
+
+    fn transform(&self) -> Transform {
+        self.texture.transform()
+    }
+
+    fn geometry(&self, scale: Scale<f64>) -> Rectangle<i32, Physical> {
+        self.texture.geometry(scale)
+    }
+
+    fn damage_since(&self, scale: Scale<f64>, commit: Option<CommitCounter>) -> DamageSet<i32, Physical> {
+        self.texture.damage_since(scale, commit)
+    }
+
+    fn opaque_regions(&self, scale: Scale<f64>) -> OpaqueRegions<i32, Physical> {
+        self.texture.opaque_regions(scale)
+    }
+
+    fn alpha(&self) -> f32 {
+        self.texture.alpha()
+    }
+
+    fn kind(&self) -> Kind {
+        self.texture.kind()
+    }
+}
+
+impl<R, T> RenderElement<R> for MirrorRenderElement<T>
+where
+    R: Renderer<TextureId = T>,
+    T: Texture,
+{
+    fn draw(
+        &self,
+        frame: &mut R::Frame<'_, '_>,
+        src: Rectangle<f64, Buffer>,
+        dst: Rectangle<i32, Physical>,
+        damage: &[Rectangle<i32, Physical>],
+        opaque_regions: &[Rectangle<i32, Physical>],
+    ) -> Result<(), R::Error> {
+        if !self.sync.is_reached() {
+            if let Err(err) = frame.wait(&self.sync) {
+                warn!(?err, "Failed to wait for the mirrored frame");
+            }
+        }
+        RenderElement::<R>::draw(&self.texture, frame, src, dst, damage, opaque_regions)
+    }
+}
@@ -37,6 +37,7 @@ use super::{
 };
 
 pub mod memory;
+pub mod mirror;
 pub mod solid;
 #[cfg(feature = "wayland_frontend")]
 pub mod surface;
@@ -54,6 +55,7 @@ enum InnerId {
     #[cfg(feature = "wayland_frontend")]
     WaylandResource(ObjectId),
     External(Arc<ExternalId>),
+    Namespaced(Arc<(Id, Id)>),
 }
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
@@ -88,6 +90,17 @@ impl Id {
     pub fn new() -> Self {
         Id(InnerId::External(Arc::new(ExternalId::new())))
     }
+
+    /// Create an id derived from this id inside of a namespace
+    ///
+    /// Can be used to render the same element multiple times, e.g. in previews,
+    /// without the copies sharing their damage tracking state.
+    ///
+    /// Note: Calling this function with the same id and namespace
+    /// multiple times will return the same id.
+    pub fn namespaced(&self, namespace: &Id) -> Self {
+        Id(InnerId::Namespaced(Arc::new((namespace.clone(), self.clone()))))
+    }
 }
 
 #[cfg(feature = "wayland_frontend")]
@@ -175,6 +188,9 @@ impl PrimaryScanoutOutput {
     /// output. Smithay provides a [`default`](`default_primary_scanout_output_compare`)
     /// compare function for convenience.
     ///
+    /// A current primary scan-out output that is powered off, see [`Output::set_powered`],
+    /// is replaced by the provided output if the element was presented on it.
+    ///
     /// Returns the updated primary scan-out output if any
     pub fn update_from_render_element_states<F>(
         &mut self,
@@ -195,6 +211,12 @@ impl PrimaryScanoutOutput {
             .as_ref()
             .map(|(current, _)| current.is_alive())
             .unwrap_or(false);
+        // A powered off output does not present anything, so any output the element
+        // was presented on replaces it, see `Output::set_powered`
+        let has_powered_output = primary_scanout_output
+            .as_ref()
+            .and_then(|(current, _)| current.upgrade())
+            .is_some_and(|current| current.is_powered());
         let same_output = primary_scanout_output
             .as_ref()
             .map(|(current, _)| current == output)
@@ -213,9 +235,9 @@ impl PrimaryScanoutOutput {
             return None;
         }
 
-        // If the element was presented but we have no current valid output
+        // If the element was presented but we have no current valid (or powered) output
         // just directly update and exit early
-        if element_was_presented && !has_valid_output {
+        if element_was_presented && !has_powered_output {
             *primary_scanout_output = Some((output.downgrade(), element_state.unwrap()));
             return Some(output.clone());
         }
@@ -378,6 +400,13 @@ pub trait Element {
     fn kind(&self) -> Kind {
         Kind::default()
     }
+    /// Returns whether the element may be presented with tearing
+    ///
+    /// Backends may use this as a hint to present an element scanned out directly
+    /// with an asynchronous page flip, trading tearing for lower latency.
+    fn allows_tearing(&self) -> bool {
+        false
+    }
 }
 
 /// A single render element
@@ -460,6 +489,10 @@ where
     fn kind(&self) -> Kind {
         (*self).kind()
     }
+
+    fn allows_tearing(&self) -> bool {
+        (*self).allows_tearing()
+    }
 }
 
 impl<R, E> RenderElement<R> for &E
@@ -754,6 +787,19 @@ macro_rules! render_elements_internal {
                 Self::_GenericCatcher(_) => unreachable!(),
             }
         }
+
+        fn allows_tearing(&self) -> bool {
+            match self {
+                $(
+                    #[allow(unused_doc_comments)]
+                    $(
+                        #[$meta]
+                    )*
+                    Self::$body(x) => $crate::render_elements_internal!(@call allows_tearing; x)
+                ),*,
+                Self::_GenericCatcher(_) => unreachable!(),
+            }
+        }
     };
     (@draw <$renderer:ty>; $($(#[$meta:meta])* $body:ident=$field:ty $(as <$other_renderer:ty>)?),* $(,)?) => {
         fn draw(
@@ -1384,6 +1430,10 @@ where
     fn kind(&self) -> Kind {
         self.0.kind()
     }
+
+    fn allows_tearing(&self) -> bool {
+        self.0.allows_tearing()
+    }
 }
 
 impl<R, C> RenderElement<R> for Wrap<C>
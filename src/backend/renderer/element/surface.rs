@@ -79,10 +79,11 @@ use crate::{
     wayland::{
         alpha_modifier::AlphaModifierSurfaceCachedState,
         compositor::{self, SurfaceData, TraversalAction},
+        tearing_control::TearingControlSurfaceCachedState,
     },
 };
 
-use super::{CommitCounter, Element, Id, Kind, RenderElement, UnderlyingStorage};
+use super::{utils::CropRenderElement, CommitCounter, Element, Id, Kind, RenderElement, UnderlyingStorage};
 
 /// Retrieve the [`WaylandSurfaceRenderElement`]s for a surface tree
 #[instrument(level = "trace", skip(renderer, location, scale))]
@@ -153,6 +154,41 @@ where
     surfaces
 }
 
+/// Retrieve the [`WaylandSurfaceRenderElement`]s for a surface tree clipped to a destination rectangle
+///
+/// Works like [`render_elements_from_surface_tree`], but every (sub-)surface is cropped to `clip`,
+/// which is given in the same coordinate space as `location`. Surfaces fully outside of the clip
+/// are skipped. Damage and opaque regions of the returned elements are restricted to the clip as well.
+///
+/// This can be used e.g. for scrolling views or to clip windows to server-side decorations.
+/// Use [`under_from_surface_tree_clipped`](crate::desktop::utils::under_from_surface_tree_clipped)
+/// to apply the same clip to input.
+#[instrument(level = "trace", skip(renderer, location, scale))]
+#[profiling::function]
+pub fn render_elements_from_surface_tree_clipped<R, E>(
+    renderer: &mut R,
+    surface: &wl_surface::WlSurface,
+    location: impl Into<Point<i32, Physical>>,
+    scale: impl Into<Scale<f64>>,
+    alpha: f32,
+    kind: Kind,
+    clip: Rectangle<i32, Physical>,
+) -> Vec<E>
+where
+    R: Renderer + ImportAll,
+    R::TextureId: Clone + 'static,
+    E: From<CropRenderElement<WaylandSurfaceRenderElement<R>>>,
+{
+    let scale = scale.into();
+    render_elements_from_surface_tree::<R, WaylandSurfaceRenderElement<R>>(
+        renderer, surface, location, scale, alpha, kind,
+    )
+    .into_iter()
+    .filter_map(|element| CropRenderElement::from_element(element, scale, clip))
+    .map(E::from)
+    .collect()
+}
+
 /// Texture used for the [`WaylandSurfaceRenderElement`]
 #[derive(Debug)]
 pub enum WaylandSurfaceTexture<R: Renderer> {
@@ -168,6 +204,7 @@ pub struct WaylandSurfaceRenderElement<R: Renderer> {
     location: Point<f64, Physical>,
     alpha: f32,
     kind: Kind,
+    allows_tearing: bool,
 
     view: SurfaceView,
     buffer: Buffer,
@@ -207,6 +244,11 @@ impl<R: Renderer + ImportAll> WaylandSurfaceRenderElement<R> {
 
         let mut alpha_modifier_state = states.cached_state.get::<AlphaModifierSurfaceCachedState>();
         let alpha_multiplier = alpha_modifier_state.current().multiplier_f32().unwrap_or(1.0);
+        let allows_tearing = states
+            .cached_state
+            .get::<TearingControlSurfaceCachedState>()
+            .current()
+            .allows_tearing();
 
         let Some(data_ref) = states.data_map.get::<RendererSurfaceStateUserData>() else {
             return Ok(None);
@@ -217,6 +259,7 @@ impl<R: Renderer + ImportAll> WaylandSurfaceRenderElement<R> {
             location,
             alpha * alpha_multiplier,
             kind,
+            allows_tearing,
             &data_ref.lock().unwrap(),
         ))
     }
@@ -227,6 +270,7 @@ impl<R: Renderer + ImportAll> WaylandSurfaceRenderElement<R> {
         location: Point<f64, Physical>,
         alpha: f32,
         kind: Kind,
+        allows_tearing: bool,
         data: &RendererSurfaceState,
     ) -> Option<Self>
     where
@@ -245,6 +289,7 @@ impl<R: Renderer + ImportAll> WaylandSurfaceRenderElement<R> {
             location,
             alpha,
             kind,
+            allows_tearing,
             view: data.view()?,
             buffer,
             buffer_scale: data.buffer_scale(),
@@ -366,6 +411,10 @@ impl<R: Renderer + ImportAll> Element for WaylandSurfaceRenderElement<R> {
     fn kind(&self) -> Kind {
         self.kind
     }
+
+    fn allows_tearing(&self) -> bool {
+        self.allows_tearing
+    }
 }
 
 impl<R> RenderElement<R> for WaylandSurfaceRenderElement<R>
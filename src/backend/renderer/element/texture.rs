@@ -343,6 +343,21 @@ impl<T: Texture> TextureRenderBuffer<T> {
         self.damage_tracker.lock().unwrap().reset();
     }
 
+    /// Replace the stored texture, only damaging the given regions
+    ///
+    /// Useful if the new texture only differs from the previous one within `damage`,
+    /// e.g. for consecutive buffers of a swapchain.
+    pub fn update_from_texture_with_damage<R: Renderer<TextureId = T>>(
+        &mut self,
+        renderer: &R,
+        texture: T,
+        damage: impl IntoIterator<Item = Rectangle<i32, Buffer>>,
+    ) {
+        assert_eq!(self.context_id, renderer.context_id());
+        self.texture = texture;
+        self.damage_tracker.lock().unwrap().add(damage);
+    }
+
     /// Update the texture from a chunk of memory
     pub fn update_from_memory<R: Renderer<TextureId = T> + ImportMem>(
         &mut self,
@@ -0,0 +1,228 @@
+use wayland_server::protocol::wl_surface::WlSurface;
+
+use crate::{
+    backend::renderer::{
+        element::{solid::SolidColorRenderElement, Id, Kind},
+        utils::{CommitCounter, RendererSurfaceStateUserData},
+        Color32F,
+    },
+    input::{pointer::PointerHandle, SeatHandler},
+    utils::{Logical, Physical, Point, Rectangle, Scale},
+    wayland::{
+        compositor::{self, RectangleKind, RegionAttributes, SurfaceAttributes, TraversalAction},
+        pointer_constraints::with_pointer_constraint,
+    },
+};
+
+const INPUT_COLOR: Color32F = Color32F::new(0.0, 0.0, 0.25, 0.25);
+const OPAQUE_COLOR: Color32F = Color32F::new(0.0, 0.25, 0.0, 0.25);
+const CONSTRAINT_COLOR: Color32F = Color32F::new(0.4, 0.0, 0.0, 0.4);
+
+/// Debug overlay visualizing the regions of surfaces
+///
+/// Renders translucent [`SolidColorRenderElement`]s over the input regions, opaque regions
+/// and active pointer constraint regions of a surface tree. The elements should be placed
+/// in front of the scene. As the elements are recreated every frame, the damage tracker
+/// redraws the regions on every frame while the overlay is enabled.
+///
+/// The overlay is disabled by default, making [`RegionDebugOverlay::render_elements`] a no-op.
+#[derive(Debug, Clone)]
+pub struct RegionDebugOverlay {
+    enabled: bool,
+    input_color: Color32F,
+    opaque_color: Color32F,
+    constraint_color: Color32F,
+}
+
+impl Default for RegionDebugOverlay {
+    fn default() -> Self {
+        RegionDebugOverlay {
+            enabled: false,
+            input_color: INPUT_COLOR,
+            opaque_color: OPAQUE_COLOR,
+            constraint_color: CONSTRAINT_COLOR,
+        }
+    }
+}
+
+impl RegionDebugOverlay {
+    /// Create a new disabled overlay using the default colors
+    pub fn new() -> Self {
+        RegionDebugOverlay::default()
+    }
+
+    /// Returns whether the overlay is rendered
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Enable or disable the overlay
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// Toggle the overlay, returning whether it is enabled now
+    pub fn toggle(&mut self) -> bool {
+        self.enabled = !self.enabled;
+        self.enabled
+    }
+
+    /// Set the color of input regions
+    pub fn set_input_color(&mut self, color: impl Into<Color32F>) {
+        self.input_color = color.into();
+    }
+
+    /// Set the color of opaque regions
+    pub fn set_opaque_color(&mut self, color: impl Into<Color32F>) {
+        self.opaque_color = color.into();
+    }
+
+    /// Set the color of active pointer constraint regions
+    pub fn set_constraint_color(&mut self, color: impl Into<Color32F>) {
+        self.constraint_color = color.into();
+    }
+
+    /// Retrieve the overlay elements for a surface tree
+    ///
+    /// `location` and `scale` match the arguments used to render the surface tree, e.g. with
+    /// [`render_elements_from_surface_tree`](crate::backend::renderer::element::surface::render_elements_from_surface_tree).
+    /// Pointer constraints are only visualized if a `pointer` is given.
+    pub fn render_elements<D: SeatHandler + 'static>(
+        &self,
+        surface: &WlSurface,
+        pointer: Option<&PointerHandle<D>>,
+        location: impl Into<Point<i32, Physical>>,
+        scale: impl Into<Scale<f64>>,
+    ) -> Vec<SolidColorRenderElement> {
+        if !self.enabled {
+            return Vec::new();
+        }
+
+        let location = location.into().to_f64();
+        let scale = scale.into();
+        let mut elements = Vec::new();
+        let mut push_region = |origin: Point<f64, Physical>, rects: Vec<Rectangle<i32, Logical>>, color| {
+            elements.extend(rects.into_iter().map(|rect| {
+                let geometry = rect.to_f64().to_physical(scale);
+                let geometry = Rectangle::new(origin + geometry.loc, geometry.size).to_i32_round();
+                SolidColorRenderElement::new(
+                    Id::new(),
+                    geometry,
+                    CommitCounter::default(),
+                    color,
+                    Kind::Unspecified,
+                )
+            }));
+        };
+
+        compositor::with_surface_tree_downward(
+            surface,
+            location,
+            |_, states, location| {
+                let data = states.data_map.get::<RendererSurfaceStateUserData>();
+                match data.and_then(|data| data.lock().unwrap().view()) {
+                    Some(view) => {
+                        TraversalAction::DoChildren(*location + view.offset.to_f64().to_physical(scale))
+                    }
+                    None => TraversalAction::SkipChildren,
+                }
+            },
+            |surface, states, location| {
+                let data = states.data_map.get::<RendererSurfaceStateUserData>();
+                let Some(view) = data.and_then(|data| data.lock().unwrap().view()) else {
+                    return;
+                };
+                let origin = *location + view.offset.to_f64().to_physical(scale);
+                let bounds = Rectangle::from_size(view.dst);
+
+                let mut guard = states.cached_state.get::<SurfaceAttributes>();
+                let attributes = guard.current();
+                let input = region_rects(attributes.input_region.as_ref(), bounds);
+                let opaque = attributes
+                    .opaque_region
+                    .as_ref()
+                    .map(|region| region_rects(Some(region), bounds))
+                    .unwrap_or_default();
+                push_region(origin, input, self.input_color);
+                push_region(origin, opaque, self.opaque_color);
+
+                if let Some(pointer) = pointer {
+                    let constraint = with_pointer_constraint(surface, pointer, |constraint| {
+                        constraint
+                            .filter(|constraint| constraint.is_active())
+                            .map(|constraint| region_rects(constraint.region(), bounds))
+                    });
+                    if let Some(constraint) = constraint {
+                        push_region(origin, constraint, self.constraint_color);
+                    }
+                }
+            },
+            |_, _, _| true,
+        );
+
+        // render the topmost surfaces first
+        elements.reverse();
+        elements
+    }
+}
+
+// Resolves a region into non-overlapping rectangles clipped to the bounds, `None` covering the bounds
+fn region_rects(
+    region: Option<&RegionAttributes>,
+    bounds: Rectangle<i32, Logical>,
+) -> Vec<Rectangle<i32, Logical>> {
+    let Some(region) = region else {
+        return vec![bounds];
+    };
+
+    let mut rects: Vec<Rectangle<i32, Logical>> = Vec::new();
+    for (kind, rect) in &region.rects {
+        let Some(rect) = rect.intersection(bounds) else {
+            continue;
+        };
+        match kind {
+            RectangleKind::Add => {
+                let added = rect.subtract_rects(rects.iter().copied());
+                rects.extend(added);
+            }
+            RectangleKind::Subtract => {
+                rects = Rectangle::subtract_rects_many(rects, [rect]);
+            }
+        }
+    }
+    rects
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn region_resolution() {
+        let bounds = Rectangle::from_size((100, 100).into());
+        assert_eq!(region_rects(None, bounds), vec![bounds]);
+
+        let region = RegionAttributes {
+            rects: vec![
+                (
+                    RectangleKind::Add,
+                    Rectangle::new((50, 50).into(), (100, 100).into()),
+                ),
+                (
+                    RectangleKind::Add,
+                    Rectangle::new((50, 50).into(), (10, 10).into()),
+                ),
+                (
+                    RectangleKind::Subtract,
+                    Rectangle::new((50, 50).into(), (50, 25).into()),
+                ),
+            ],
+        };
+        let rects = region_rects(Some(&region), bounds);
+        let area: i32 = rects.iter().map(|rect| rect.size.w * rect.size.h).sum();
+        assert_eq!(area, 50 * 25);
+        assert!(rects
+            .iter()
+            .all(|rect| *rect == Rectangle::new((50, 75).into(), (50, 25).into())));
+    }
+}
@@ -90,6 +90,10 @@ impl<E: Element> Element for RescaleRenderElement<E> {
     fn kind(&self) -> Kind {
         self.element.kind()
     }
+
+    fn allows_tearing(&self) -> bool {
+        self.element.allows_tearing()
+    }
 }
 
 impl<R: Renderer, E: RenderElement<R>> RenderElement<R> for RescaleRenderElement<E> {
@@ -274,6 +278,10 @@ impl<E: Element> Element for CropRenderElement<E> {
     fn kind(&self) -> Kind {
         self.element.kind()
     }
+
+    fn allows_tearing(&self) -> bool {
+        self.element.allows_tearing()
+    }
 }
 
 impl<R: Renderer, E: RenderElement<R>> RenderElement<R> for CropRenderElement<E> {
@@ -376,6 +384,10 @@ impl<E: Element> Element for RelocateRenderElement<E> {
     fn kind(&self) -> Kind {
         self.element.kind()
     }
+
+    fn allows_tearing(&self) -> bool {
+        self.element.allows_tearing()
+    }
 }
 
 impl<R: Renderer, E: RenderElement<R>> RenderElement<R> for RelocateRenderElement<E> {
@@ -396,6 +408,91 @@ impl<R: Renderer, E: RenderElement<R>> RenderElement<R> for RelocateRenderElemen
     }
 }
 
+/// A element replacing the id of an existing element by an id inside of a namespace
+///
+/// See [`Id::namespaced`].
+#[derive(Debug)]
+pub struct NamespacedRenderElement<E> {
+    element: E,
+    id: Id,
+}
+
+impl<E: Element> NamespacedRenderElement<E> {
+    /// Create a namespaced element for an existing element
+    pub fn from_element(element: E, namespace: &Id) -> Self {
+        let id = element.id().namespaced(namespace);
+        NamespacedRenderElement { element, id }
+    }
+}
+
+impl<E: Element> Element for NamespacedRenderElement<E> {
+    fn id(&self) -> &Id {
+        &self.id
+    }
+
+    fn current_commit(&self) -> crate::backend::renderer::utils::CommitCounter {
+        self.element.current_commit()
+    }
+
+    fn src(&self) -> Rectangle<f64, Buffer> {
+        self.element.src()
+    }
+
+    fn geometry(&self, scale: Scale<f64>) -> Rectangle<i32, Physical> {
+        self.element.geometry(scale)
+    }
+
+    fn location(&self, scale: Scale<f64>) -> Point<i32, Physical> {
+        self.element.location(scale)
+    }
+
+    fn transform(&self) -> crate::utils::Transform {
+        self.element.transform()
+    }
+
+    fn damage_since(
+        &self,
+        scale: Scale<f64>,
+        commit: Option<crate::backend::renderer::utils::CommitCounter>,
+    ) -> DamageSet<i32, Physical> {
+        self.element.damage_since(scale, commit)
+    }
+
+    fn opaque_regions(&self, scale: Scale<f64>) -> OpaqueRegions<i32, Physical> {
+        self.element.opaque_regions(scale)
+    }
+
+    fn alpha(&self) -> f32 {
+        self.element.alpha()
+    }
+
+    fn kind(&self) -> Kind {
+        self.element.kind()
+    }
+
+    fn allows_tearing(&self) -> bool {
+        self.element.allows_tearing()
+    }
+}
+
+impl<R: Renderer, E: RenderElement<R>> RenderElement<R> for NamespacedRenderElement<E> {
+    fn draw(
+        &self,
+        frame: &mut R::Frame<'_, '_>,
+        src: Rectangle<f64, Buffer>,
+        dst: Rectangle<i32, Physical>,
+        damage: &[Rectangle<i32, Physical>],
+        opaque_regions: &[Rectangle<i32, Physical>],
+    ) -> Result<(), R::Error> {
+        self.element.draw(frame, src, dst, damage, opaque_regions)
+    }
+
+    #[inline]
+    fn underlying_storage(&self, renderer: &mut R) -> Option<UnderlyingStorage<'_>> {
+        self.element.underlying_storage(renderer)
+    }
+}
+
 /// Defines the scale behavior for the constrain
 #[derive(Debug, Copy, Clone)]
 pub enum ConstrainScaleBehavior {
@@ -568,3 +665,26 @@ where
         .map(move |e| RelocateRenderElement::from_element(e, offset, Relocate::Relative))
         .filter_map(move |e| CropRenderElement::from_element(e, scale, constrain))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::renderer::element::solid::{SolidColorBuffer, SolidColorRenderElement};
+
+    #[test]
+    fn namespaced_ids() {
+        let buffer = SolidColorBuffer::new((10, 10), [1.0, 0.0, 0.0, 1.0]);
+        let element = || SolidColorRenderElement::from_buffer(&buffer, (0, 0), 1.0, 1.0, Kind::Unspecified);
+        let (first, second) = (Id::new(), Id::new());
+
+        let a = NamespacedRenderElement::from_element(element(), &first);
+        let b = NamespacedRenderElement::from_element(element(), &first);
+        let c = NamespacedRenderElement::from_element(element(), &second);
+
+        // stable inside of a namespace, distinct between namespaces and from the element itself
+        assert_eq!(a.id(), b.id());
+        assert_ne!(a.id(), c.id());
+        assert_ne!(a.id(), element().id());
+        assert_eq!(a.current_commit(), element().current_commit());
+    }
+}
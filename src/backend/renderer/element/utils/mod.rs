@@ -1,9 +1,17 @@
 //! Utilities and helpers around the `Element` trait.
 
+#[cfg(feature = "wayland_frontend")]
+mod debug;
 mod elements;
+mod rounded;
 #[cfg(feature = "wayland_frontend")]
 mod wayland;
 
+#[cfg(feature = "wayland_frontend")]
+pub use debug::RegionDebugOverlay;
 pub use elements::*;
+#[cfg(feature = "renderer_gl")]
+pub(crate) use rounded::rounded_rect_spans;
+pub use rounded::{RoundedCornerElement, RoundedCornerRenderer, RoundedCornerState};
 #[cfg(feature = "wayland_frontend")]
 pub use wayland::*;
@@ -0,0 +1,483 @@
+use std::collections::VecDeque;
+
+use crate::{
+    backend::renderer::{
+        element::{Element, Id, Kind, RenderElement, UnderlyingStorage},
+        utils::{CommitCounter, DamageSet, OpaqueRegions},
+        Renderer,
+    },
+    utils::{Buffer, Physical, Rectangle, Scale, Transform},
+};
+
+/// Renderers supporting to clip arbitrary elements to a rounded rectangle
+///
+/// Used by [`RoundedCornerElement`]. The default implementation clips on pixel boundaries
+/// by restricting the damage passed to the element, renderers can provide anti-aliased
+/// clipping instead.
+pub trait RoundedCornerRenderer: Renderer + Sized {
+    /// Draw an element clipped to a rounded rectangle
+    ///
+    /// `clip` is relative to `dst` and `radius` is given in physical pixels.
+    /// The `damage` and `opaque_regions` are already restricted to the clip.
+    #[allow(clippy::too_many_arguments)]
+    fn draw_rounded<E: RenderElement<Self>>(
+        frame: &mut Self::Frame<'_, '_>,
+        element: &E,
+        src: Rectangle<f64, Buffer>,
+        dst: Rectangle<i32, Physical>,
+        damage: &[Rectangle<i32, Physical>],
+        opaque_regions: &[Rectangle<i32, Physical>],
+        clip: Rectangle<i32, Physical>,
+        radius: f64,
+    ) -> Result<(), Self::Error> {
+        let spans = rounded_rect_spans(clip, radius, false);
+        let damage = damage
+            .iter()
+            .flat_map(|rect| spans.iter().filter_map(move |span| span.intersection(*rect)))
+            .collect::<Vec<_>>();
+        element.draw(frame, src, dst, &damage, opaque_regions)
+    }
+}
+
+const MAX_HISTORY: usize = 4;
+
+/// State of a [`RoundedCornerElement`] kept across frames
+///
+/// Provides the [`Id`] of the element and tracks changes of the wrapped element,
+/// the clip and the radius in its own [`CommitCounter`].
+#[derive(Debug)]
+pub struct RoundedCornerState {
+    id: Id,
+    commit: CommitCounter,
+    element: Option<Id>,
+    clip: Rectangle<i32, Physical>,
+    radius: f64,
+    // commit of the wrapped element and the damage caused by the clip per commit, newest first
+    history: VecDeque<(CommitCounter, Vec<Rectangle<i32, Physical>>)>,
+}
+
+impl Default for RoundedCornerState {
+    fn default() -> Self {
+        RoundedCornerState {
+            id: Id::new(),
+            commit: CommitCounter::default(),
+            element: None,
+            clip: Rectangle::zero(),
+            radius: 0.0,
+            history: VecDeque::with_capacity(MAX_HISTORY),
+        }
+    }
+}
+
+impl RoundedCornerState {
+    /// Create a new rounded corner state
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the [`Id`] of elements created from this state
+    pub fn id(&self) -> &Id {
+        &self.id
+    }
+
+    fn update(&mut self, element: &impl Element, clip: Rectangle<i32, Physical>, radius: f64) {
+        let element_commit = element.current_commit();
+        if self.element.as_ref() != Some(element.id()) {
+            // a different element can not be tracked incrementally
+            self.element = Some(element.id().clone());
+            self.history.clear();
+            self.history.push_front((element_commit, Vec::new()));
+            self.commit.increment();
+        } else if self.clip == clip && self.radius == radius {
+            if self.history.front().map(|(commit, _)| *commit) == Some(element_commit) {
+                return;
+            }
+            self.history.push_front((element_commit, Vec::new()));
+            self.commit.increment();
+        } else {
+            let damage = if self.clip != clip {
+                vec![self.clip, clip]
+            } else {
+                rounded_rect_corners(clip, self.radius.max(radius))
+            };
+            self.history.push_front((element_commit, damage));
+            self.commit.increment();
+        }
+        self.history.truncate(MAX_HISTORY + 1);
+        self.clip = clip;
+        self.radius = radius;
+    }
+}
+
+/// A element clipping another element to a rounded rectangle
+///
+/// Damage and opaque regions of the element are adjusted to the clip, so the corners
+/// are neither considered opaque nor get drawn outside of the rounded rectangle.
+///
+/// Clipping is done by the renderer, see [`RoundedCornerRenderer`].
+/// The element is never considered for direct scan-out.
+#[derive(Debug)]
+pub struct RoundedCornerElement<E> {
+    element: E,
+    id: Id,
+    commit: CommitCounter,
+    clip: Rectangle<i32, Physical>,
+    radius: f64,
+    history: Vec<(CommitCounter, Vec<Rectangle<i32, Physical>>)>,
+}
+
+impl<E: Element> RoundedCornerElement<E> {
+    /// Create a rounded corner element for an existing element
+    ///
+    /// The clip is expected to be relative to the same origin the element is relative to.
+    /// `radius` is given in physical pixels and is limited to half of the clip size.
+    ///
+    /// The `state` has to be kept across frames and provides the [`Id`] of the element.
+    /// Changes to the clip or radius damage the affected regions.
+    pub fn from_element(
+        element: E,
+        state: &mut RoundedCornerState,
+        clip: Rectangle<i32, Physical>,
+        radius: f64,
+    ) -> Self {
+        let max_radius = clip.size.w.min(clip.size.h) as f64 / 2.0;
+        let radius = radius.clamp(0.0, max_radius.max(0.0));
+        state.update(&element, clip, radius);
+        RoundedCornerElement {
+            element,
+            id: state.id.clone(),
+            commit: state.commit,
+            clip,
+            radius,
+            history: state.history.iter().cloned().collect(),
+        }
+    }
+
+    /// Returns the clip rectangle of this element
+    pub fn clip(&self) -> Rectangle<i32, Physical> {
+        self.clip
+    }
+
+    /// Returns the corner radius of this element in physical pixels
+    pub fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    /// Returns a reference to the wrapped element
+    pub fn element(&self) -> &E {
+        &self.element
+    }
+
+    fn element_clip(&self, scale: Scale<f64>) -> Rectangle<i32, Physical> {
+        let mut clip = self.clip;
+        clip.loc -= self.element.geometry(scale).loc;
+        clip
+    }
+}
+
+impl<E: Element> Element for RoundedCornerElement<E> {
+    fn id(&self) -> &Id {
+        &self.id
+    }
+
+    fn current_commit(&self) -> CommitCounter {
+        self.commit
+    }
+
+    fn src(&self) -> Rectangle<f64, Buffer> {
+        self.element.src()
+    }
+
+    fn geometry(&self, scale: Scale<f64>) -> Rectangle<i32, Physical> {
+        self.element.geometry(scale)
+    }
+
+    fn transform(&self) -> Transform {
+        self.element.transform()
+    }
+
+    fn damage_since(&self, scale: Scale<f64>, commit: Option<CommitCounter>) -> DamageSet<i32, Physical> {
+        let clip = self.element_clip(scale);
+        let distance = self
+            .commit
+            .distance(commit)
+            .filter(|distance| *distance < self.history.len());
+
+        let (element_commit, clip_damage) = match distance {
+            Some(distance) => (
+                Some(self.history[distance].0),
+                self.history[..distance]
+                    .iter()
+                    .flat_map(|(_, damage)| damage.iter().copied())
+                    .collect::<Vec<_>>(),
+            ),
+            None => (None, Vec::new()),
+        };
+
+        let offset = self.element.geometry(scale).loc;
+        self.element
+            .damage_since(scale, element_commit)
+            .into_iter()
+            .filter_map(|rect| rect.intersection(clip))
+            .chain(clip_damage.into_iter().map(|mut rect| {
+                rect.loc -= offset;
+                rect
+            }))
+            .collect()
+    }
+
+    fn opaque_regions(&self, scale: Scale<f64>) -> OpaqueRegions<i32, Physical> {
+        let clip = self.element_clip(scale);
+        let inner = rounded_rect_inner(clip, self.radius);
+        self.element
+            .opaque_regions(scale)
+            .into_iter()
+            .flat_map(|rect| inner.iter().filter_map(move |inner| inner.intersection(rect)))
+            .collect()
+    }
+
+    fn alpha(&self) -> f32 {
+        self.element.alpha()
+    }
+
+    fn kind(&self) -> Kind {
+        self.element.kind()
+    }
+}
+
+impl<R: RoundedCornerRenderer, E: RenderElement<R>> RenderElement<R> for RoundedCornerElement<E> {
+    fn draw(
+        &self,
+        frame: &mut R::Frame<'_, '_>,
+        src: Rectangle<f64, Buffer>,
+        dst: Rectangle<i32, Physical>,
+        damage: &[Rectangle<i32, Physical>],
+        opaque_regions: &[Rectangle<i32, Physical>],
+    ) -> Result<(), R::Error> {
+        let mut clip = self.clip;
+        clip.loc -= dst.loc;
+
+        let damage = damage
+            .iter()
+            .filter_map(|rect| rect.intersection(clip))
+            .collect::<Vec<_>>();
+        if damage.is_empty() {
+            return Ok(());
+        }
+
+        let inner = rounded_rect_inner(clip, self.radius);
+        let opaque_regions = opaque_regions
+            .iter()
+            .flat_map(|rect| inner.iter().filter_map(move |inner| inner.intersection(*rect)))
+            .collect::<Vec<_>>();
+
+        R::draw_rounded(
+            frame,
+            &self.element,
+            src,
+            dst,
+            &damage,
+            &opaque_regions,
+            clip,
+            self.radius,
+        )
+    }
+
+    #[inline]
+    fn underlying_storage(&self, _renderer: &mut R) -> Option<UnderlyingStorage<'_>> {
+        // the clip can not be applied on scan-out
+        None
+    }
+}
+
+// The squares containing the corners of a rounded rectangle
+fn rounded_rect_corners(clip: Rectangle<i32, Physical>, radius: f64) -> Vec<Rectangle<i32, Physical>> {
+    let r = (radius.ceil() as i32).min(clip.size.w / 2).min(clip.size.h / 2);
+    if r <= 0 {
+        return Vec::new();
+    }
+    let right = clip.loc.x + clip.size.w - r;
+    let bottom = clip.loc.y + clip.size.h - r;
+    [
+        (clip.loc.x, clip.loc.y),
+        (right, clip.loc.y),
+        (clip.loc.x, bottom),
+        (right, bottom),
+    ]
+    .into_iter()
+    .map(|loc| Rectangle::new(loc.into(), (r, r).into()))
+    .collect()
+}
+
+// The parts of a rounded rectangle not affected by the corners
+fn rounded_rect_inner(clip: Rectangle<i32, Physical>, radius: f64) -> Vec<Rectangle<i32, Physical>> {
+    let r = (radius.ceil() as i32).min(clip.size.w / 2).min(clip.size.h / 2);
+    if r <= 0 {
+        return vec![clip];
+    }
+    [
+        Rectangle::new(
+            (clip.loc.x + r, clip.loc.y).into(),
+            (clip.size.w - 2 * r, clip.size.h).into(),
+        ),
+        Rectangle::new(
+            (clip.loc.x, clip.loc.y + r).into(),
+            (r, clip.size.h - 2 * r).into(),
+        ),
+        Rectangle::new(
+            (clip.loc.x + clip.size.w - r, clip.loc.y + r).into(),
+            (r, clip.size.h - 2 * r).into(),
+        ),
+    ]
+    .into_iter()
+    .filter(|rect| !rect.is_empty())
+    .collect()
+}
+
+// Approximates a rounded rectangle by rows of pixels
+//
+// If `inclusive` is set, every pixel touching the shape is included,
+// otherwise only pixels whose centers lie inside the shape.
+pub(crate) fn rounded_rect_spans(
+    clip: Rectangle<i32, Physical>,
+    radius: f64,
+    inclusive: bool,
+) -> Vec<Rectangle<i32, Physical>> {
+    let r = (radius.ceil() as i32).min(clip.size.w / 2).min(clip.size.h / 2);
+    if r <= 0 {
+        return vec![clip];
+    }
+
+    let mut spans = Vec::with_capacity(2 * r as usize + 1);
+    spans.push(Rectangle::new(
+        (clip.loc.x, clip.loc.y + r).into(),
+        (clip.size.w, clip.size.h - 2 * r).into(),
+    ));
+    for row in 0..r {
+        let dy = if inclusive {
+            radius - (row as f64 + 1.0)
+        } else {
+            radius - (row as f64 + 0.5)
+        };
+        let dx = (radius * radius - dy.max(0.0) * dy.max(0.0)).max(0.0).sqrt();
+        let inset = if inclusive {
+            (radius - dx).floor() as i32
+        } else {
+            (radius - dx).round() as i32
+        };
+        let width = clip.size.w - 2 * inset;
+        if width <= 0 {
+            continue;
+        }
+        spans.push(Rectangle::new(
+            (clip.loc.x + inset, clip.loc.y + row).into(),
+            (width, 1).into(),
+        ));
+        spans.push(Rectangle::new(
+            (clip.loc.x + inset, clip.loc.y + clip.size.h - 1 - row).into(),
+            (width, 1).into(),
+        ));
+    }
+    spans.retain(|rect| !rect.is_empty());
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::renderer::element::solid::{SolidColorBuffer, SolidColorRenderElement};
+
+    fn solid(buffer: &SolidColorBuffer) -> SolidColorRenderElement {
+        SolidColorRenderElement::from_buffer(buffer, (10, 10), 1.0, 1.0, Kind::Unspecified)
+    }
+
+    fn area(rects: &[Rectangle<i32, Physical>]) -> i32 {
+        rects.iter().map(|r| r.size.w * r.size.h).sum()
+    }
+
+    #[test]
+    fn spans_cover_rounded_rect() {
+        let clip = Rectangle::new((10, 10).into(), (100, 50).into());
+        assert_eq!(rounded_rect_spans(clip, 0.0, false), vec![clip]);
+
+        let spans = rounded_rect_spans(clip, 10.0, false);
+        for span in &spans {
+            assert!(clip.contains_rect(*span));
+        }
+        // the corners of a circle with radius 10 cover roughly 100 - 25 * pi pixels each
+        let cut = 100 * 50 - area(&spans);
+        assert!((80..=90).contains(&cut), "unexpected corner area {}", cut);
+        // the top left pixel is outside, the center of the top edge inside
+        let contains = |x, y| spans.iter().any(|s| s.contains((x, y)));
+        assert!(!contains(10, 10));
+        assert!(contains(60, 10));
+        assert!(contains(10, 35));
+
+        let inclusive = rounded_rect_spans(clip, 10.0, true);
+        assert!(area(&inclusive) > area(&spans));
+        assert!(!inclusive.iter().any(|s| s.contains((10, 10))));
+    }
+
+    #[test]
+    fn inner_excludes_corners() {
+        let clip = Rectangle::from_size((40, 40).into());
+        let inner = rounded_rect_inner(clip, 4.5);
+        assert_eq!(area(&inner), 40 * 40 - 4 * 5 * 5);
+        assert!(!inner.iter().any(|r| r.contains((0, 0))));
+        assert!(!inner.iter().any(|r| r.contains((39, 39))));
+    }
+
+    #[test]
+    fn element_tracks_own_commits() {
+        let mut buffer = SolidColorBuffer::new((100, 100), [1.0, 0.0, 0.0, 1.0]);
+        let mut state = RoundedCornerState::new();
+        let clip = Rectangle::new((10, 10).into(), (100, 100).into());
+        let scale = Scale::from(1.0);
+
+        let first = RoundedCornerElement::from_element(solid(&buffer), &mut state, clip, 8.0);
+        assert_eq!(first.id(), state.id());
+        assert_ne!(first.id(), first.element().id());
+        let commit = first.current_commit();
+
+        // nothing changed
+        let element = RoundedCornerElement::from_element(solid(&buffer), &mut state, clip, 8.0);
+        assert_eq!(element.id(), first.id());
+        assert_eq!(element.current_commit(), commit);
+        assert!(element.damage_since(scale, Some(commit)).is_empty());
+
+        // only the corners are damaged by a new radius
+        let element = RoundedCornerElement::from_element(solid(&buffer), &mut state, clip, 4.0);
+        assert_ne!(element.current_commit(), commit);
+        assert_eq!(
+            element.damage_since(scale, Some(commit)).to_vec(),
+            rounded_rect_corners(Rectangle::from_size((100, 100).into()), 8.0)
+        );
+        let commit = element.current_commit();
+
+        // changes of the wrapped element are forwarded
+        buffer.set_color([0.0, 1.0, 0.0, 1.0]);
+        let element = RoundedCornerElement::from_element(solid(&buffer), &mut state, clip, 4.0);
+        assert_eq!(
+            element.damage_since(scale, Some(commit)).to_vec(),
+            vec![Rectangle::from_size((100, 100).into())]
+        );
+        let commit = element.current_commit();
+
+        // the old and new clip are damaged
+        let smaller = Rectangle::new((20, 20).into(), (50, 50).into());
+        let element = RoundedCornerElement::from_element(solid(&buffer), &mut state, smaller, 4.0);
+        assert_eq!(
+            element.damage_since(scale, Some(commit)).to_vec(),
+            vec![
+                Rectangle::from_size((100, 100).into()),
+                Rectangle::new((10, 10).into(), (50, 50).into())
+            ]
+        );
+
+        // unknown commits are fully damaged
+        assert_eq!(
+            element.damage_since(scale, None).to_vec(),
+            vec![Rectangle::new((10, 10).into(), (50, 50).into())]
+        );
+    }
+}
@@ -22,6 +22,7 @@ use tracing::{debug, error, info, info_span, instrument, span, span::EnteredSpan
 pub mod element;
 mod error;
 pub mod format;
+mod profiler;
 mod shaders;
 mod texture;
 mod uniform;
@@ -36,8 +37,15 @@ pub use uniform::*;
 use self::version::GlVersion;
 
 use super::{
-    sync::SyncPoint, Bind, Blit, BlitFrame, Color32F, ContextId, DebugFlags, ExportMem, Frame, ImportDma,
-    ImportMem, Offscreen, Renderer, RendererSuper, Texture, TextureFilter, TextureMapping,
+    element::{
+        utils::{rounded_rect_spans, RoundedCornerRenderer},
+        RenderElement,
+    },
+    report::{RenderReport, RenderReporting},
+    sync::SyncPoint,
+    utils::DeferredDestruction,
+    Bind, Blit, BlitFrame, Color32F, ContextId, DebugFlags, ExportDma, ExportMem, Frame, ImportDma,
+    ImportMem, Offscreen, Renderer, RendererSuper, Texture, TextureFilter, TextureMapping, YuvConversion,
 };
 use crate::{
     backend::{
@@ -58,7 +66,7 @@ use crate::{
 #[cfg(all(feature = "wayland_frontend", feature = "use_system_lib"))]
 use super::ImportEgl;
 #[cfg(feature = "wayland_frontend")]
-use super::{ImportDmaWl, ImportMemWl};
+use super::{BindDmaWl, ImportDmaWl, ImportMemWl};
 #[cfg(all(feature = "wayland_frontend", feature = "use_system_lib"))]
 use crate::backend::egl::{display::EGLBufferReader, Format as EGLFormat};
 #[cfg(feature = "wayland_frontend")]
@@ -76,12 +84,61 @@ enum CleanupResource {
     FramebufferObject(ffi::types::GLuint),
     RenderbufferObject(ffi::types::GLuint),
     EGLImage(EGLImage),
-    Mapping(ffi::types::GLuint, *const std::ffi::c_void),
+    Mapping(ffi::types::GLuint, *const std::ffi::c_void, isize),
     Program(ffi::types::GLuint),
     Sync(ffi::types::GLsync),
 }
 unsafe impl Send for CleanupResource {}
 
+/// Amount of released readback buffers kept around for reuse
+const MAX_STAGING_BUFFERS: usize = 4;
+
+/// Released readback buffers and their capacity, kept around for reuse
+#[derive(Debug, Default)]
+struct StagingBuffers(Vec<(ffi::types::GLuint, isize)>);
+
+impl StagingBuffers {
+    /// Takes the smallest cached buffer of at least `size` bytes
+    fn take(&mut self, size: isize) -> Option<(ffi::types::GLuint, isize)> {
+        let idx = self
+            .0
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, capacity))| *capacity >= size)
+            .min_by_key(|(_, (_, capacity))| *capacity)
+            .map(|(idx, _)| idx)?;
+        Some(self.0.swap_remove(idx))
+    }
+
+    /// Caches a released buffer and returns the buffer to delete, if any
+    ///
+    /// Once the cache is full the smallest buffer is evicted, so buffers that became
+    /// too small, e.g. after an output was resized, don't prevent reuse forever.
+    fn release(&mut self, pbo: ffi::types::GLuint, capacity: isize) -> Option<ffi::types::GLuint> {
+        if self.0.len() < MAX_STAGING_BUFFERS {
+            self.0.push((pbo, capacity));
+            return None;
+        }
+
+        let (idx, (_, smallest)) = self
+            .0
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, (_, capacity))| *capacity)
+            .unwrap();
+        if *smallest < capacity {
+            let (evicted, _) = std::mem::replace(&mut self.0[idx], (pbo, capacity));
+            Some(evicted)
+        } else {
+            Some(pbo)
+        }
+    }
+
+    fn drain(&mut self) -> impl Iterator<Item = ffi::types::GLuint> + '_ {
+        self.0.drain(..).map(|(pbo, _)| pbo)
+    }
+}
+
 #[derive(Debug, Clone)]
 struct GlesBuffer {
     dmabuf: WeakDmabuf,
@@ -274,6 +331,8 @@ pub enum Capability {
     ExportFence,
     /// GlesRenderer supports GL debug
     Debug,
+    /// GlesRenderer supports measuring gpu time for [`RenderReport`]s
+    TimerQuery,
 }
 
 /// A renderer utilizing OpenGL ES
@@ -297,18 +356,22 @@ pub struct GlesRenderer {
     // shaders
     tex_program: GlesTexProgram,
     solid_program: GlesSolidProgram,
+    rounded_program: Option<GlesTexProgram>,
 
     // caches
     buffers: Vec<GlesBuffer>,
-    dmabuf_cache: HashMap<WeakDmabuf, GlesTexture>,
+    dmabuf_cache: HashMap<WeakDmabuf, (GlesTexture, YuvConversion)>,
+    staging_buffers: StagingBuffers,
     vbos: [ffi::types::GLuint; 2],
     vertices: Vec<f32>,
+    profiler: profiler::Profiler,
     non_opaque_damage: Vec<Rectangle<i32, Physical>>,
     opaque_damage: Vec<Rectangle<i32, Physical>>,
 
     // cleanup
     destruction_callback: Receiver<CleanupResource>,
     destruction_callback_sender: Sender<CleanupResource>,
+    deferred_destruction: DeferredDestruction<CleanupResource>,
 
     // markers
     _not_send: PhantomData<*mut ()>,
@@ -454,6 +517,16 @@ impl GlesRenderer {
             debug!("GL Debug is supported");
         }
 
+        // timestamps are optional, even if the extension is supported
+        if exts.iter().any(|ext| ext == "GL_EXT_disjoint_timer_query") {
+            let mut bits = 0;
+            gl.GetQueryivEXT(ffi::TIMESTAMP_EXT, ffi::QUERY_COUNTER_BITS_EXT, &mut bits);
+            if bits > 0 {
+                capabilities.push(Capability::TimerQuery);
+                debug!("Timer queries are supported");
+            }
+        }
+
         Ok(capabilities)
     }
 
@@ -517,6 +590,9 @@ impl GlesRenderer {
                 Capability::Renderbuffer => GlesError::GLExtensionNotSupported(&["GL_OES_rgb8_rgba8"]),
                 Capability::ExportFence => GlesError::GLExtensionNotSupported(&["GL_OES_EGL_sync"]),
                 Capability::Debug => GlesError::GLExtensionNotSupported(&["GL_KHR_debug"]),
+                Capability::TimerQuery => {
+                    GlesError::GLExtensionNotSupported(&["GL_EXT_disjoint_timer_query"])
+                }
             };
             return Err(err);
         };
@@ -612,6 +688,8 @@ impl GlesRenderer {
             .user_data()
             .insert_if_missing_threadsafe(ContextId::<GlesTexture>::new);
 
+        let profiler = profiler::Profiler::new(capabilities.contains(&Capability::TimerQuery));
+
         drop(_guard);
 
         let renderer = GlesRenderer {
@@ -626,18 +704,22 @@ impl GlesRenderer {
 
             tex_program,
             solid_program,
+            rounded_program: None,
             vbos,
             min_filter: TextureFilter::Linear,
             max_filter: TextureFilter::Linear,
 
             buffers: Vec::new(),
             dmabuf_cache: std::collections::HashMap::new(),
+            staging_buffers: StagingBuffers::default(),
             vertices: Vec::with_capacity(6 * 16),
+            profiler,
             non_opaque_damage: Vec::with_capacity(16),
             opaque_damage: Vec::with_capacity(16),
 
             destruction_callback: rx,
             destruction_callback_sender: tx,
+            deferred_destruction: DeferredDestruction::new(),
 
             debug_flags: DebugFlags::empty(),
             _not_send: PhantomData,
@@ -720,42 +802,104 @@ impl GlesRenderer {
                 i += 1;
             }
         }
+        // resources might still be in use by frames in flight
         for resource in self.destruction_callback.try_iter() {
-            match resource {
-                CleanupResource::Texture(texture) => unsafe {
-                    self.gl.DeleteTextures(1, &texture);
-                },
-                CleanupResource::EGLImage(image) => unsafe {
-                    ffi_egl::DestroyImageKHR(**self.egl.display().get_display_handle(), image);
-                },
-                CleanupResource::FramebufferObject(fbo) => unsafe {
-                    self.gl.DeleteFramebuffers(1, &fbo);
-                },
-                CleanupResource::RenderbufferObject(rbo) => unsafe {
-                    self.gl.DeleteRenderbuffers(1, &rbo);
-                },
-                CleanupResource::Mapping(pbo, mapping) => unsafe {
-                    if !mapping.is_null() {
-                        self.gl.BindBuffer(ffi::PIXEL_PACK_BUFFER, pbo);
-                        self.gl.UnmapBuffer(ffi::PIXEL_PACK_BUFFER);
-                        self.gl.BindBuffer(ffi::PIXEL_PACK_BUFFER, 0);
-                    }
+            self.deferred_destruction.defer(resource);
+        }
+        let resources = self.deferred_destruction.collect().collect::<Vec<_>>();
+        for resource in resources {
+            self.destroy_resource(resource);
+        }
+    }
+
+    fn destroy_resource(&mut self, resource: CleanupResource) {
+        match resource {
+            CleanupResource::Texture(texture) => unsafe {
+                self.gl.DeleteTextures(1, &texture);
+            },
+            CleanupResource::EGLImage(image) => unsafe {
+                ffi_egl::DestroyImageKHR(**self.egl.display().get_display_handle(), image);
+            },
+            CleanupResource::FramebufferObject(fbo) => unsafe {
+                self.gl.DeleteFramebuffers(1, &fbo);
+            },
+            CleanupResource::RenderbufferObject(rbo) => unsafe {
+                self.gl.DeleteRenderbuffers(1, &rbo);
+            },
+            CleanupResource::Mapping(pbo, mapping, capacity) => unsafe {
+                if !mapping.is_null() {
+                    self.gl.BindBuffer(ffi::PIXEL_PACK_BUFFER, pbo);
+                    self.gl.UnmapBuffer(ffi::PIXEL_PACK_BUFFER);
+                    self.gl.BindBuffer(ffi::PIXEL_PACK_BUFFER, 0);
+                }
+                // keep a few buffers around for the next readback, e.g. while screen recording
+                if let Some(pbo) = self.staging_buffers.release(pbo, capacity) {
                     self.gl.DeleteBuffers(1, &pbo);
-                },
-                CleanupResource::Program(program) => unsafe {
-                    self.gl.DeleteProgram(program);
-                },
-                CleanupResource::Sync(sync) => unsafe {
-                    self.gl.DeleteSync(sync);
-                },
+                }
+            },
+            CleanupResource::Program(program) => unsafe {
+                self.gl.DeleteProgram(program);
+            },
+            CleanupResource::Sync(sync) => unsafe {
+                self.gl.DeleteSync(sync);
+            },
+        }
+    }
+
+    fn create_sync_point(&self) -> SyncPoint {
+        // if we support egl fences we should use it
+        if self.capabilities.contains(&Capability::ExportFence) {
+            if let Ok(fence) = EGLFence::create(self.egl.display()) {
+                unsafe {
+                    self.gl.Flush();
+                }
+                return SyncPoint::from(fence);
             }
         }
+
+        // as a last option we force finish, this is unlikely to happen
+        unsafe {
+            self.gl.Finish();
+        }
+        SyncPoint::signaled()
     }
 
     /// Returns the supported [`Capabilities`](Capability) of this renderer.
     pub fn capabilities(&self) -> &[Capability] {
         &self.capabilities
     }
+
+    /// Binds a pixel pack buffer of at least `size` bytes, reusing a released buffer if possible.
+    ///
+    /// Requires the context to be current.
+    unsafe fn bind_staging_buffer(&mut self, size: isize) -> (ffi::types::GLuint, isize) {
+        if let Some((pbo, capacity)) = self.staging_buffers.take(size) {
+            self.gl.BindBuffer(ffi::PIXEL_PACK_BUFFER, pbo);
+            (pbo, capacity)
+        } else {
+            let mut pbo = 0;
+            self.gl.GenBuffers(1, &mut pbo);
+            self.gl.BindBuffer(ffi::PIXEL_PACK_BUFFER, pbo);
+            self.gl
+                .BufferData(ffi::PIXEL_PACK_BUFFER, size, ptr::null(), ffi::STREAM_READ);
+            (pbo, size)
+        }
+    }
+
+    /// Creates a [`SyncPoint`] for previously issued readback commands.
+    ///
+    /// Falls back to an already signaled sync point, in which case mapping the buffer will block.
+    fn readback_sync_point(&self) -> SyncPoint {
+        if self.capabilities.contains(&Capability::ExportFence) {
+            if let Ok(fence) = EGLFence::create(self.egl.display()) {
+                unsafe {
+                    self.gl.Flush();
+                }
+                return SyncPoint::from(fence);
+            }
+        }
+        SyncPoint::signaled()
+    }
 }
 
 #[cfg(feature = "wayland_frontend")]
@@ -1166,6 +1310,17 @@ impl ImportDma for GlesRenderer {
     fn import_dmabuf(
         &mut self,
         buffer: &Dmabuf,
+        damage: Option<&[Rectangle<i32, BufferCoord>]>,
+    ) -> Result<GlesTexture, GlesError> {
+        self.import_dmabuf_with_conversion(buffer, &YuvConversion::default(), damage)
+    }
+
+    #[instrument(level = "trace", parent = &self.span, skip(self))]
+    #[profiling::function]
+    fn import_dmabuf_with_conversion(
+        &mut self,
+        buffer: &Dmabuf,
+        conversion: &YuvConversion,
         _damage: Option<&[Rectangle<i32, BufferCoord>]>,
     ) -> Result<GlesTexture, GlesError> {
         use crate::backend::allocator::Buffer;
@@ -1173,33 +1328,36 @@ impl ImportDma for GlesRenderer {
             return Err(GlesError::GLExtensionNotSupported(&["GL_OES_EGL_image"]));
         }
 
-        self.existing_dmabuf_texture(buffer)?.map(Ok).unwrap_or_else(|| {
-            let is_external = !self.egl.dmabuf_render_formats().contains(&buffer.format());
-            let image = self
-                .egl
-                .display()
-                .create_image_from_dmabuf(buffer)
-                .map_err(GlesError::BindBufferEGLError)?;
-
-            let tex = self.import_egl_image(image, is_external, None)?;
-            let format = fourcc_to_gl_formats(buffer.format().code)
-                .map(|(internal, _, _)| internal)
-                .unwrap_or(ffi::RGBA8);
-            let has_alpha = has_alpha(buffer.format().code);
-            let texture = GlesTexture(Arc::new(GlesTextureInternal {
-                texture: tex,
-                sync: RwLock::default(),
-                format: Some(format),
-                has_alpha,
-                is_external,
-                y_inverted: buffer.y_inverted(),
-                size: buffer.size(),
-                egl_images: Some(vec![image]),
-                destruction_callback_sender: self.destruction_callback_sender.clone(),
-            }));
-            self.dmabuf_cache.insert(buffer.weak(), texture.clone());
-            Ok(texture)
-        })
+        self.existing_dmabuf_texture(buffer, conversion)?
+            .map(Ok)
+            .unwrap_or_else(|| {
+                let is_external = !self.egl.dmabuf_render_formats().contains(&buffer.format());
+                let image = self
+                    .egl
+                    .display()
+                    .create_image_from_dmabuf_with_conversion(buffer, conversion)
+                    .map_err(GlesError::BindBufferEGLError)?;
+
+                let tex = self.import_egl_image(image, is_external, None)?;
+                let format = fourcc_to_gl_formats(buffer.format().code)
+                    .map(|(internal, _, _)| internal)
+                    .unwrap_or(ffi::RGBA8);
+                let has_alpha = has_alpha(buffer.format().code);
+                let texture = GlesTexture(Arc::new(GlesTextureInternal {
+                    texture: tex,
+                    sync: RwLock::default(),
+                    format: Some(format),
+                    has_alpha,
+                    is_external,
+                    y_inverted: buffer.y_inverted(),
+                    size: buffer.size(),
+                    egl_images: Some(vec![image]),
+                    destruction_callback_sender: self.destruction_callback_sender.clone(),
+                }));
+                self.dmabuf_cache
+                    .insert(buffer.weak(), (texture.clone(), *conversion));
+                Ok(texture)
+            })
     }
 
     fn dmabuf_formats(&self) -> FormatSet {
@@ -1214,10 +1372,46 @@ impl ImportDma for GlesRenderer {
 #[cfg(feature = "wayland_frontend")]
 impl ImportDmaWl for GlesRenderer {}
 
+#[cfg(feature = "wayland_frontend")]
+impl BindDmaWl for GlesRenderer {}
+
+impl RoundedCornerRenderer for GlesRenderer {
+    fn draw_rounded<E: RenderElement<Self>>(
+        frame: &mut GlesFrame<'_, '_>,
+        element: &E,
+        src: Rectangle<f64, BufferCoord>,
+        dst: Rectangle<i32, Physical>,
+        damage: &[Rectangle<i32, Physical>],
+        opaque_regions: &[Rectangle<i32, Physical>],
+        clip: Rectangle<i32, Physical>,
+        radius: f64,
+    ) -> Result<(), GlesError> {
+        // textures are clipped by the shader, restricting the damage to every touched pixel
+        // additionally clips solid colors without cutting off the anti-aliased edges
+        let spans = rounded_rect_spans(clip, radius, true);
+        let damage = damage
+            .iter()
+            .flat_map(|rect| spans.iter().filter_map(move |span| span.intersection(*rect)))
+            .collect::<Vec<_>>();
+        frame.with_rounded_clip(clip, radius, |frame| {
+            element.draw(frame, src, dst, &damage, opaque_regions)
+        })
+    }
+}
+
 impl GlesRenderer {
     #[profiling::function]
-    fn existing_dmabuf_texture(&self, buffer: &Dmabuf) -> Result<Option<GlesTexture>, GlesError> {
-        let Some(texture) = self.dmabuf_cache.get(&buffer.weak()) else {
+    fn existing_dmabuf_texture(
+        &self,
+        buffer: &Dmabuf,
+        conversion: &YuvConversion,
+    ) -> Result<Option<GlesTexture>, GlesError> {
+        // textures imported with different conversion parameters have to be re-imported
+        let Some((texture, _)) = self
+            .dmabuf_cache
+            .get(&buffer.weak())
+            .filter(|(_, cached)| cached == conversion)
+        else {
             return Ok(None);
         };
 
@@ -1278,15 +1472,12 @@ impl ExportMem for GlesRenderer {
         let (_, has_alpha) = target.0.format().ok_or(GlesError::UnknownPixelFormat)?;
         let (_, format, layout) = fourcc_to_gl_formats(fourcc).ok_or(GlesError::UnknownPixelFormat)?;
 
-        let mut pbo = 0;
-        let err = unsafe {
+        let bpp = gl_bpp(format, layout).ok_or(GlesError::UnsupportedPixelLayout)? / 8;
+        let size = (region.size.w * region.size.h * bpp as i32) as isize;
+
+        let (pbo, capacity, err) = unsafe {
             self.gl.GetError(); // clear errors
-            self.gl.GenBuffers(1, &mut pbo);
-            self.gl.BindBuffer(ffi::PIXEL_PACK_BUFFER, pbo);
-            let bpp = gl_bpp(format, layout).ok_or(GlesError::UnsupportedPixelLayout)? / 8;
-            let size = (region.size.w * region.size.h * bpp as i32) as isize;
-            self.gl
-                .BufferData(ffi::PIXEL_PACK_BUFFER, size, ptr::null(), ffi::STREAM_READ);
+            let (pbo, capacity) = self.bind_staging_buffer(size);
             self.gl
                 .ReadBuffer(if matches!(target.0, GlesTargetInternal::Surface { .. }) {
                     ffi::BACK
@@ -1304,15 +1495,17 @@ impl ExportMem for GlesRenderer {
             );
             self.gl.ReadBuffer(ffi::NONE);
             self.gl.BindBuffer(ffi::PIXEL_PACK_BUFFER, 0);
-            self.gl.GetError()
+            (pbo, capacity, self.gl.GetError())
         };
 
         match err {
             ffi::NO_ERROR => Ok(GlesMapping {
                 pbo,
+                capacity,
                 format,
                 layout,
                 has_alpha,
+                sync: self.readback_sync_point(),
                 size: region.size,
                 mapping: AtomicPtr::new(ptr::null_mut()),
                 destruction_callback_sender: self.destruction_callback_sender.clone(),
@@ -1335,23 +1528,17 @@ impl ExportMem for GlesRenderer {
         region: Rectangle<i32, BufferCoord>,
         fourcc: Fourcc,
     ) -> Result<Self::TextureMapping, Self::Error> {
-        let mut pbo = 0;
         let target = self.bind_texture(texture)?;
         target.0.make_current(&self.gl, &self.egl)?;
 
         let (_, format, layout) = fourcc_to_gl_formats(fourcc).ok_or(GlesError::UnknownPixelFormat)?;
         let bpp = gl_bpp(format, layout).expect("We check the format before") / 8;
 
-        let err = unsafe {
+        let size = (region.size.w * region.size.h * bpp as i32) as isize;
+
+        let (pbo, capacity, err) = unsafe {
             self.gl.GetError(); // clear errors
-            self.gl.GenBuffers(1, &mut pbo);
-            self.gl.BindBuffer(ffi::PIXEL_PACK_BUFFER, pbo);
-            self.gl.BufferData(
-                ffi::PIXEL_PACK_BUFFER,
-                (region.size.w * region.size.h * bpp as i32) as isize,
-                ptr::null(),
-                ffi::STREAM_READ,
-            );
+            let (pbo, capacity) = self.bind_staging_buffer(size);
             self.gl.ReadBuffer(ffi::COLOR_ATTACHMENT0);
             self.gl.ReadPixels(
                 region.loc.x,
@@ -1364,15 +1551,17 @@ impl ExportMem for GlesRenderer {
             );
             self.gl.ReadBuffer(ffi::NONE);
             self.gl.BindBuffer(ffi::PIXEL_PACK_BUFFER, 0);
-            self.gl.GetError()
+            (pbo, capacity, self.gl.GetError())
         };
 
         match err {
             ffi::NO_ERROR => Ok(GlesMapping {
                 pbo,
+                capacity,
                 format,
                 layout,
                 has_alpha: texture.0.has_alpha,
+                sync: self.readback_sync_point(),
                 size: region.size,
                 mapping: AtomicPtr::new(ptr::null_mut()),
                 destruction_callback_sender: self.destruction_callback_sender.clone(),
@@ -1419,6 +1608,50 @@ impl ExportMem for GlesRenderer {
     }
 }
 
+impl ExportDma for GlesRenderer {
+    #[instrument(level = "trace", parent = &self.span, skip(self))]
+    #[profiling::function]
+    fn export_texture(&mut self, texture: &GlesTexture) -> Result<(Dmabuf, SyncPoint), GlesError> {
+        unsafe {
+            self.egl.make_current()?;
+        }
+
+        // Textures imported from a dmabuf already have an image, which can be exported again
+        let dmabuf = if let Some([image]) = texture.0.egl_images.as_deref() {
+            self.egl
+                .display()
+                .create_dmabuf_from_image(*image, texture.0.size, texture.0.y_inverted)
+                .map_err(GlesError::BindBufferEGLError)?
+        } else {
+            let image = unsafe {
+                // The texture needs to be complete, which it isn't with the default mipmap filter
+                self.gl.BindTexture(ffi::TEXTURE_2D, texture.0.texture);
+                self.gl
+                    .TexParameteri(ffi::TEXTURE_2D, ffi::TEXTURE_MIN_FILTER, ffi::LINEAR as i32);
+                self.gl.BindTexture(ffi::TEXTURE_2D, 0);
+
+                self.egl
+                    .display()
+                    .create_image_from_texture(&self.egl, texture.0.texture)
+                    .map_err(GlesError::BindBufferEGLError)?
+            };
+
+            let dmabuf = self
+                .egl
+                .display()
+                .create_dmabuf_from_image(image, texture.0.size, texture.0.y_inverted)
+                .map_err(GlesError::BindBufferEGLError);
+            unsafe {
+                ffi_egl::DestroyImageKHR(**self.egl.display().get_display_handle(), image);
+            }
+            dmabuf?
+        };
+
+        // Rendering into the texture may still be in flight
+        Ok((dmabuf, self.create_sync_point()))
+    }
+}
+
 impl Bind<EGLSurface> for GlesRenderer {
     fn bind<'a>(&mut self, surface: &'a mut EGLSurface) -> Result<GlesTarget<'a>, GlesError> {
         Ok(GlesTarget(GlesTargetInternal::Surface { surface }))
@@ -1758,12 +1991,26 @@ impl Blit for GlesRenderer {
 
 impl Drop for GlesRenderer {
     fn drop(&mut self) {
-        let _guard = self.span.enter();
+        let span = self.span.clone();
+        let _guard = span.enter();
         unsafe {
             if self.egl.make_current().is_ok() {
                 self.gl.BindFramebuffer(ffi::FRAMEBUFFER, 0);
                 self.gl.DeleteProgram(self.solid_program.program);
                 self.gl.DeleteBuffers(self.vbos.len() as i32, self.vbos.as_ptr());
+                self.gl.Finish();
+                let resources = self
+                    .destruction_callback
+                    .try_iter()
+                    .chain(self.deferred_destruction.drain())
+                    .collect::<Vec<_>>();
+                for resource in resources {
+                    self.destroy_resource(resource);
+                }
+                for pbo in self.staging_buffers.drain() {
+                    self.gl.DeleteBuffers(1, &pbo);
+                }
+                self.profiler.destroy(&self.gl);
 
                 if self.extensions.iter().any(|ext| ext == "GL_KHR_debug") {
                     self.gl.Disable(ffi::DEBUG_OUTPUT);
@@ -1794,6 +2041,46 @@ impl GlesRenderer {
         &self.egl
     }
 
+    /// Limits the region of `target` updated by the next frame
+    ///
+    /// This uses `EGL_KHR_partial_update` and does nothing for targets other than an [`EGLSurface`]
+    /// or if the extension is not supported. See [`EGLSurface::set_damage_region`] for the requirements.
+    /// `damage` uses the same coordinates as the damage returned by
+    /// [`OutputDamageTracker::render_output`](crate::backend::renderer::damage::OutputDamageTracker::render_output),
+    /// see [`OutputDamageTracker::render_output_with`](crate::backend::renderer::damage::OutputDamageTracker::render_output_with)
+    /// for setting it before rendering.
+    ///
+    /// If the region can't be set, the whole buffer is kept and only a message is logged.
+    #[instrument(level = "trace", parent = &self.span, skip(self, target))]
+    #[profiling::function]
+    pub fn set_damage_region(
+        &mut self,
+        target: &mut GlesTarget<'_>,
+        damage: &[Rectangle<i32, Physical>],
+    ) -> Result<(), GlesError> {
+        let GlesTargetInternal::Surface { surface } = &target.0 else {
+            return Ok(());
+        };
+        if !surface.supports_partial_update() {
+            return Ok(());
+        }
+
+        // EGL expects the origin at the bottom left
+        let height = surface.get_size().map(|size| size.h).unwrap_or_default();
+        let mut damage = damage
+            .iter()
+            .map(|rect| Rectangle::new((rect.loc.x, height - rect.loc.y - rect.size.h).into(), rect.size))
+            .collect::<Vec<_>>();
+
+        unsafe {
+            self.egl.make_current_with_surface(surface)?;
+        }
+        if let Err(err) = surface.set_damage_region(&mut damage) {
+            debug!(?err, "Failed to set damage region");
+        }
+        Ok(())
+    }
+
     /// Run custom code in the GL context owned by this renderer.
     ///
     /// The OpenGL state of the renderer is considered an implementation detail
@@ -1951,6 +2238,7 @@ impl GlesRenderer {
     ///
     /// They receive the following variables:
     /// - *varying* v_coords `vec2` - contains the position from the vertex shader
+    /// - *varying* v_position `vec2` - contains the position relative to the destination rectangle in physical pixels
     /// - *uniform* tex `sample2d` - texture sampler
     /// - *uniform* alpha `float` - for the alpha value passed by the renderer
     /// - *uniform* tint `float` - for the tint passed by the renderer (either 0.0 or 1.0) - only if `DEBUG_FLAGS` was defined
@@ -2050,6 +2338,8 @@ impl Renderer for GlesRenderer {
         target.0.make_current(&self.gl, &self.egl)?;
 
         unsafe {
+            self.profiler.begin_frame(&self.gl);
+
             self.gl.Viewport(0, 0, output_size.w, output_size.h);
 
             self.gl.Scissor(0, 0, output_size.w, output_size.h);
@@ -2146,6 +2436,30 @@ impl Renderer for GlesRenderer {
     }
 }
 
+impl RenderReporting for GlesRenderer {
+    fn set_reporting(&mut self, enabled: bool) {
+        if enabled == self.profiler.is_enabled() {
+            return;
+        }
+        match unsafe { self.egl.make_current() } {
+            Ok(()) => unsafe { self.profiler.set_enabled(&self.gl, enabled) },
+            Err(err) => warn!(?err, "Failed to toggle render reports"),
+        }
+    }
+
+    fn is_reporting(&self) -> bool {
+        self.profiler.is_enabled()
+    }
+
+    #[profiling::function]
+    fn take_reports(&mut self) -> Vec<RenderReport> {
+        if unsafe { self.egl.make_current() }.is_ok() {
+            unsafe { self.profiler.collect(&self.gl) };
+        }
+        self.profiler.take_reports()
+    }
+}
+
 /// Vertices for instanced rendering.
 static INSTANCED_VERTS: [ffi::types::GLfloat; 8] = [
     1.0, 0.0, // top right
@@ -2295,6 +2609,14 @@ impl Frame for GlesFrame<'_, '_> {
         self.renderer.wait(sync)
     }
 
+    fn begin_scope(&mut self, name: &'static str) {
+        unsafe { self.renderer.profiler.begin_scope(&self.renderer.gl, name) }
+    }
+
+    fn end_scope(&mut self) {
+        unsafe { self.renderer.profiler.end_scope(&self.renderer.gl) }
+    }
+
     #[profiling::function]
     fn finish(mut self) -> Result<SyncPoint, Self::Error> {
         self.finish_internal()
@@ -2319,24 +2641,12 @@ impl GlesFrame<'_, '_> {
             sync_lock.update_write(&self.renderer.gl);
         }
 
-        // delayed destruction until the next frame rendering.
+        unsafe { self.renderer.profiler.end_frame(&self.renderer.gl) };
+        let sync = self.renderer.create_sync_point();
+        // resources dropped up to now are released once this frame completed
+        self.renderer.deferred_destruction.submit(sync.clone());
         self.renderer.cleanup();
-
-        // if we support egl fences we should use it
-        if self.renderer.capabilities.contains(&Capability::ExportFence) {
-            if let Ok(fence) = EGLFence::create(self.renderer.egl.display()) {
-                unsafe {
-                    self.renderer.gl.Flush();
-                }
-                return Ok(SyncPoint::from(fence));
-            }
-        }
-
-        // as a last option we force finish, this is unlikely to happen
-        unsafe {
-            self.renderer.gl.Finish();
-        }
-        Ok(SyncPoint::signaled())
+        Ok(sync)
     }
 
     /// Overrides the default texture shader used, if none is specified.
@@ -2358,6 +2668,70 @@ impl GlesFrame<'_, '_> {
         self.tex_program_override = None;
     }
 
+    /// Draw textures clipped to a rounded rectangle with anti-aliased corners
+    ///
+    /// Temporarily overrides the default texture shader for the duration of `func`,
+    /// see [`GlesFrame::override_default_tex_program`].
+    /// `clip` is relative to the destination of the textures drawn by `func`.
+    pub fn with_rounded_clip<T>(
+        &mut self,
+        clip: Rectangle<i32, Physical>,
+        radius: f64,
+        func: impl FnOnce(&mut Self) -> Result<T, GlesError>,
+    ) -> Result<T, GlesError> {
+        let previous = self.push_rounded_clip(clip, radius)?;
+        let res = func(self);
+        self.pop_rounded_clip(previous);
+        res
+    }
+
+    pub(crate) fn push_rounded_clip(
+        &mut self,
+        clip: Rectangle<i32, Physical>,
+        radius: f64,
+    ) -> Result<Option<(GlesTexProgram, Vec<Uniform<'static>>)>, GlesError> {
+        let program = match self.renderer.rounded_program.clone() {
+            Some(program) => program,
+            None => {
+                let program = unsafe {
+                    texture_program(
+                        &self.renderer.gl,
+                        shaders::FRAGMENT_SHADER_ROUNDED,
+                        &[
+                            UniformName::new("clip_rect", UniformType::_4f),
+                            UniformName::new("corner_radius", UniformType::_1f),
+                        ],
+                        self.renderer.destruction_callback_sender.clone(),
+                    )?
+                };
+                self.renderer.rounded_program = Some(program.clone());
+                program
+            }
+        };
+
+        let previous = self.tex_program_override.take();
+        self.override_default_tex_program(
+            program,
+            vec![
+                Uniform::new(
+                    "clip_rect",
+                    (
+                        clip.loc.x as f32,
+                        clip.loc.y as f32,
+                        clip.size.w as f32,
+                        clip.size.h as f32,
+                    ),
+                ),
+                Uniform::new("corner_radius", radius as f32),
+            ],
+        );
+        Ok(previous)
+    }
+
+    pub(crate) fn pop_rounded_clip(&mut self, previous: Option<(GlesTexProgram, Vec<Uniform<'static>>)>) {
+        self.tex_program_override = previous;
+    }
+
     /// Draw a solid color to the current target at the specified destination with the specified color.
     #[instrument(level = "trace", skip(self), parent = &self.span)]
     #[profiling::function]
@@ -3020,10 +3394,79 @@ fn build_texture_mat(
 
 #[cfg(test)]
 mod tests {
-    use super::build_texture_mat;
-    use crate::utils::{Buffer, Physical, Rectangle, Size, Transform};
+    use super::{build_texture_mat, GlesError, GlesRenderer, GlesTexture, StagingBuffers};
+    use crate::{
+        backend::{
+            allocator::Fourcc,
+            egl::{native::EGLSurfacelessDisplay, EGLContext, EGLDisplay, Error as EGLError},
+            renderer::{Bind, Color32F, ExportDma, ExportMem, Frame, ImportDma, Offscreen, Renderer},
+        },
+        utils::{Buffer, Physical, Rectangle, Size, Transform},
+    };
     use cgmath::Vector3;
 
+    #[test]
+    fn staging_buffer_reuse() {
+        let mut buffers = StagingBuffers::default();
+        for (pbo, capacity) in [(1, 100), (2, 400), (3, 200), (4, 300)] {
+            assert_eq!(buffers.release(pbo, capacity), None);
+        }
+
+        // the smallest sufficient buffer is reused
+        assert_eq!(buffers.take(150), Some((3, 200)));
+        assert_eq!(buffers.take(500), None);
+        assert_eq!(buffers.release(3, 200), None);
+
+        // a full cache evicts its smallest buffer for a larger one
+        assert_eq!(buffers.release(5, 800), Some(1));
+        assert_eq!(buffers.take(500), Some((5, 800)));
+        assert_eq!(buffers.release(5, 800), None);
+        // but drops buffers not larger than any cached one
+        assert_eq!(buffers.release(6, 200), Some(6));
+
+        let mut remaining = buffers.drain().collect::<Vec<_>>();
+        remaining.sort();
+        assert_eq!(remaining, [2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn export_texture_contents() {
+        // Requires an EGL implementation supporting dmabuf exports, skip otherwise
+        let Ok(display) = (unsafe { EGLDisplay::new(EGLSurfacelessDisplay) }) else {
+            return;
+        };
+        let context = EGLContext::new(&display).unwrap();
+        let mut renderer = unsafe { GlesRenderer::new(context) }.unwrap();
+
+        let size = Size::<i32, Physical>::from((4, 4));
+        let mut texture: GlesTexture = renderer.create_buffer(Fourcc::Abgr8888, (4, 4).into()).unwrap();
+        {
+            let mut framebuffer = renderer.bind(&mut texture).unwrap();
+            let mut frame = renderer
+                .render(&mut framebuffer, size, Transform::Normal)
+                .unwrap();
+            frame
+                .clear(Color32F::new(1.0, 0.0, 0.0, 1.0), &[Rectangle::from_size(size)])
+                .unwrap();
+            // the sync point of the export has to cover the frame
+            let _ = frame.finish().unwrap();
+        }
+
+        let (dmabuf, sync) = match renderer.export_texture(&texture) {
+            Ok(export) => export,
+            Err(GlesError::BindBufferEGLError(EGLError::EglExtensionNotSupported(_))) => return,
+            Err(err) => panic!("failed to export texture: {err}"),
+        };
+        sync.wait().unwrap();
+
+        let imported = renderer.import_dmabuf(&dmabuf, None).unwrap();
+        let mapping = renderer
+            .copy_texture(&imported, Rectangle::from_size((4, 4).into()), Fourcc::Abgr8888)
+            .unwrap();
+        let data = renderer.map_texture(&mapping).unwrap();
+        assert!(data.chunks_exact(4).all(|pixel| pixel == [255, 0, 0, 255]));
+    }
+
     #[test]
     fn texture_normal_double_size() {
         let src: Rectangle<f64, Buffer> = Rectangle::from_size((1000f64, 500f64).into());
@@ -0,0 +1,265 @@
+use std::{
+    collections::VecDeque,
+    mem,
+    time::{Duration, Instant},
+};
+
+use super::ffi::{self, types::GLuint, Gles2};
+use crate::backend::renderer::report::{RenderReport, ScopeTiming};
+
+/// Amount of frames waiting for their gpu timings before they are reported without them
+const MAX_PENDING_FRAMES: usize = 8;
+/// Amount of reports kept until they are taken
+const MAX_REPORTS: usize = 64;
+
+// Timestamp queries at the start and end of a frame or scope
+type Queries = Option<(GLuint, GLuint)>;
+
+#[derive(Debug)]
+struct ScopeRecording {
+    name: &'static str,
+    depth: usize,
+    start: Instant,
+    start_query: Option<GLuint>,
+    timing: Option<(Duration, Queries)>,
+}
+
+#[derive(Debug)]
+struct FrameRecording {
+    start: Instant,
+    start_query: Option<GLuint>,
+    scopes: Vec<ScopeRecording>,
+    open_scopes: Vec<usize>,
+}
+
+#[derive(Debug)]
+struct PendingReport {
+    report: RenderReport,
+    queries: Queries,
+    scope_queries: Vec<Queries>,
+}
+
+/// Measures the cpu and gpu time of frames using `GL_EXT_disjoint_timer_query`
+#[derive(Debug, Default)]
+pub(super) struct Profiler {
+    enabled: bool,
+    timer_queries: bool,
+    frame: u64,
+    current: Option<FrameRecording>,
+    pending: VecDeque<PendingReport>,
+    reports: VecDeque<RenderReport>,
+    free_queries: Vec<GLuint>,
+}
+
+impl Profiler {
+    pub(super) fn new(timer_queries: bool) -> Self {
+        Profiler {
+            timer_queries,
+            ..Default::default()
+        }
+    }
+
+    pub(super) fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Requires the context to be current, if gpu timings are measured
+    pub(super) unsafe fn set_enabled(&mut self, gl: &Gles2, enabled: bool) {
+        if !enabled {
+            self.destroy(gl);
+        }
+        self.enabled = enabled;
+    }
+
+    unsafe fn timestamp(&mut self, gl: &Gles2) -> Option<GLuint> {
+        if !self.timer_queries {
+            return None;
+        }
+        let query = self.free_queries.pop().unwrap_or_else(|| {
+            let mut query = 0;
+            gl.GenQueriesEXT(1, &mut query);
+            query
+        });
+        gl.QueryCounterEXT(query, ffi::TIMESTAMP_EXT);
+        Some(query)
+    }
+
+    unsafe fn read_duration(&mut self, gl: &Gles2, queries: Queries) -> Option<Duration> {
+        let (start, end) = queries?;
+        let mut start_time = 0;
+        let mut end_time = 0;
+        gl.GetQueryObjectui64vEXT(start, ffi::QUERY_RESULT_EXT, &mut start_time);
+        gl.GetQueryObjectui64vEXT(end, ffi::QUERY_RESULT_EXT, &mut end_time);
+        self.free_queries.extend([start, end]);
+        Some(Duration::from_nanos(end_time.saturating_sub(start_time)))
+    }
+
+    /// Requires the context to be current
+    pub(super) unsafe fn begin_frame(&mut self, gl: &Gles2) {
+        if !self.enabled {
+            return;
+        }
+        // a frame that was never finished, e.g. because it was leaked
+        self.discard_current();
+        let start_query = self.timestamp(gl);
+        self.current = Some(FrameRecording {
+            start: Instant::now(),
+            start_query,
+            scopes: Vec::new(),
+            open_scopes: Vec::new(),
+        });
+    }
+
+    /// Requires the context to be current
+    pub(super) unsafe fn begin_scope(&mut self, gl: &Gles2, name: &'static str) {
+        if self.current.is_none() {
+            return;
+        }
+        let start_query = self.timestamp(gl);
+        let frame = self.current.as_mut().unwrap();
+        frame.open_scopes.push(frame.scopes.len());
+        frame.scopes.push(ScopeRecording {
+            name,
+            depth: frame.open_scopes.len() - 1,
+            start: Instant::now(),
+            start_query,
+            timing: None,
+        });
+    }
+
+    /// Requires the context to be current
+    pub(super) unsafe fn end_scope(&mut self, gl: &Gles2) {
+        let Some(idx) = self.current.as_mut().and_then(|frame| frame.open_scopes.pop()) else {
+            return;
+        };
+        let end_query = self.timestamp(gl);
+        let scope = &mut self.current.as_mut().unwrap().scopes[idx];
+        scope.timing = Some((scope.start.elapsed(), scope.start_query.zip(end_query)));
+    }
+
+    /// Requires the context to be current
+    pub(super) unsafe fn end_frame(&mut self, gl: &Gles2) {
+        while self
+            .current
+            .as_ref()
+            .is_some_and(|frame| !frame.open_scopes.is_empty())
+        {
+            self.end_scope(gl);
+        }
+        let Some(frame) = self.current.take() else {
+            return;
+        };
+        let end_query = self.timestamp(gl);
+
+        let (scopes, scope_queries) = frame
+            .scopes
+            .into_iter()
+            .map(|scope| {
+                let (cpu_time, queries) = scope.timing.unwrap_or_default();
+                let timing = ScopeTiming {
+                    name: scope.name,
+                    depth: scope.depth,
+                    cpu_time,
+                    gpu_time: None,
+                };
+                (timing, queries)
+            })
+            .unzip();
+        self.frame = self.frame.wrapping_add(1);
+        self.pending.push_back(PendingReport {
+            report: RenderReport {
+                frame: self.frame,
+                cpu_time: frame.start.elapsed(),
+                gpu_time: None,
+                scopes,
+            },
+            queries: frame.start_query.zip(end_query),
+            scope_queries,
+        });
+        self.collect(gl);
+    }
+
+    /// Moves frames executed by the gpu to the reports
+    ///
+    /// Requires the context to be current
+    pub(super) unsafe fn collect(&mut self, gl: &Gles2) {
+        let mut disjoint = 0;
+        if self.timer_queries {
+            gl.GetIntegerv(ffi::GPU_DISJOINT_EXT, &mut disjoint);
+        }
+
+        while let Some(pending) = self.pending.front() {
+            let available = match pending.queries {
+                Some((_, end)) if disjoint == 0 && self.pending.len() <= MAX_PENDING_FRAMES => {
+                    let mut available = 0;
+                    gl.GetQueryObjectuivEXT(end, ffi::QUERY_RESULT_AVAILABLE_EXT, &mut available);
+                    if available == 0 {
+                        break;
+                    }
+                    true
+                }
+                _ => false,
+            };
+
+            let mut pending = self.pending.pop_front().unwrap();
+            if available {
+                pending.report.gpu_time = self.read_duration(gl, pending.queries);
+                for (scope, queries) in pending.report.scopes.iter_mut().zip(pending.scope_queries) {
+                    scope.gpu_time = self.read_duration(gl, queries);
+                }
+            } else {
+                // the timings are invalid or were not measured
+                self.release_queries(pending.queries);
+                for queries in mem::take(&mut pending.scope_queries) {
+                    self.release_queries(queries);
+                }
+            }
+
+            if self.reports.len() >= MAX_REPORTS {
+                self.reports.pop_front();
+            }
+            self.reports.push_back(pending.report);
+        }
+    }
+
+    pub(super) fn take_reports(&mut self) -> Vec<RenderReport> {
+        self.reports.drain(..).collect()
+    }
+
+    fn discard_current(&mut self) {
+        let Some(frame) = self.current.take() else {
+            return;
+        };
+        self.free_queries.extend(frame.start_query);
+        for scope in frame.scopes {
+            match scope.timing {
+                Some((_, queries)) => self.release_queries(queries),
+                None => self.free_queries.extend(scope.start_query),
+            }
+        }
+    }
+
+    fn release_queries(&mut self, queries: Queries) {
+        if let Some((start, end)) = queries {
+            self.free_queries.extend([start, end]);
+        }
+    }
+
+    /// Drops all measurements and deletes the queries
+    ///
+    /// Requires the context to be current
+    pub(super) unsafe fn destroy(&mut self, gl: &Gles2) {
+        self.discard_current();
+        for pending in mem::take(&mut self.pending) {
+            self.release_queries(pending.queries);
+            for queries in pending.scope_queries {
+                self.release_queries(queries);
+            }
+        }
+        self.reports.clear();
+        if !self.free_queries.is_empty() {
+            gl.DeleteQueriesEXT(self.free_queries.len() as i32, self.free_queries.as_ptr());
+            self.free_queries.clear();
+        }
+    }
+}
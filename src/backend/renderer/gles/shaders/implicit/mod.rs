@@ -3,6 +3,7 @@ use crate::backend::renderer::gles::*;
 
 pub(in super::super) const VERTEX_SHADER: &str = include_str!("./texture.vert");
 pub(in super::super) const FRAGMENT_SHADER: &str = include_str!("./texture.frag");
+pub(in super::super) const FRAGMENT_SHADER_ROUNDED: &str = include_str!("./rounded.frag");
 
 pub(in super::super) const VERTEX_SHADER_SOLID: &str = include_str!("./solid.vert");
 pub(in super::super) const FRAGMENT_SHADER_SOLID: &str = include_str!("./solid.frag");
@@ -0,0 +1,58 @@
+#version 100
+
+//_DEFINES_
+
+#if defined(EXTERNAL)
+#extension GL_OES_EGL_image_external : require
+#endif
+
+#if defined(GL_FRAGMENT_PRECISION_HIGH)
+precision highp float;
+#else
+precision mediump float;
+#endif
+#if defined(EXTERNAL)
+uniform samplerExternalOES tex;
+#else
+uniform sampler2D tex;
+#endif
+
+uniform float alpha;
+varying vec2 v_coords;
+varying vec2 v_position;
+
+// location and size of the clip relative to the destination
+uniform vec4 clip_rect;
+uniform float corner_radius;
+
+#if defined(DEBUG_FLAGS)
+uniform float tint;
+#endif
+
+float coverage(vec2 position) {
+    vec2 half_size = clip_rect.zw * 0.5;
+    vec2 center = clip_rect.xy + half_size;
+    float radius = min(corner_radius, min(half_size.x, half_size.y));
+    vec2 q = abs(position - center) - half_size + radius;
+    float dist = length(max(q, 0.0)) + min(max(q.x, q.y), 0.0) - radius;
+    return clamp(0.5 - dist, 0.0, 1.0);
+}
+
+void main() {
+    vec4 color = texture2D(tex, v_coords);
+
+#if defined(NO_ALPHA)
+    color = vec4(color.rgb, 1.0) * alpha;
+#else
+    color = color * alpha;
+#endif
+
+    color = color * coverage(v_position);
+
+#if defined(DEBUG_FLAGS)
+    if (tint == 1.0)
+        color = vec4(0.0, 0.2, 0.0, 0.2) + color * 0.8;
+#endif
+
+    gl_FragColor = color;
+}
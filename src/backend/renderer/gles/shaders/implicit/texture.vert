@@ -7,6 +7,7 @@ attribute vec2 vert;
 attribute vec4 vert_position;
 
 varying vec2 v_coords;
+varying vec2 v_position;
 
 mat2 scale(vec2 scale_vec){
     return mat2(
@@ -20,5 +21,6 @@ void main() {
     vec2 vert_transform_scale = vert_position.zw;
     vec3 position = vec3(vert * scale(vert_transform_scale) + vert_transform_translation, 1.0);
     v_coords = (tex_matrix * position).xy;
+    v_position = position.xy;
     gl_Position = vec4(matrix * position, 1.0);
 }
\ No newline at end of file
@@ -182,17 +182,40 @@ impl Texture for GlesTexture {
 }
 
 /// Texture mapping of a GLES2 texture
+///
+/// The pixel data is copied asynchronously into a staging buffer.
+/// Use [`GlesMapping::is_ready`] or [`GlesMapping::sync_point`] to find out when
+/// the copy has finished, to avoid stalling inside [`ExportMem::map_texture`].
+///
+/// [`ExportMem::map_texture`]: crate::backend::renderer::ExportMem::map_texture
 #[derive(Debug)]
 pub struct GlesMapping {
     pub(super) pbo: ffi::types::GLuint,
+    pub(super) capacity: isize,
     pub(super) format: ffi::types::GLenum,
     pub(super) layout: ffi::types::GLenum,
     pub(super) has_alpha: bool,
     pub(super) size: Size<i32, BufferCoord>,
     pub(super) mapping: AtomicPtr<std::ffi::c_void>,
+    pub(super) sync: SyncPoint,
     pub(super) destruction_callback_sender: Sender<CleanupResource>,
 }
 
+impl GlesMapping {
+    /// Returns the [`SyncPoint`] signaling the completion of the copy.
+    ///
+    /// This can be used to asynchronously wait for the data to become available,
+    /// e.g. by exporting a native fence and adding it to an event loop.
+    pub fn sync_point(&self) -> &SyncPoint {
+        &self.sync
+    }
+
+    /// Returns `true` if the copy has finished and mapping the data will not block.
+    pub fn is_ready(&self) -> bool {
+        self.sync.is_reached()
+    }
+}
+
 impl Texture for GlesMapping {
     fn width(&self) -> u32 {
         self.size.w as u32
@@ -226,6 +249,7 @@ impl Drop for GlesMapping {
         let _ = self.destruction_callback_sender.send(CleanupResource::Mapping(
             self.pbo,
             self.mapping.load(Ordering::SeqCst),
+            self.capacity,
         ));
     }
 }
@@ -2,7 +2,7 @@
 use tracing::warn;
 
 #[cfg(feature = "wayland_frontend")]
-use crate::backend::renderer::{ImportDmaWl, ImportMemWl};
+use crate::backend::renderer::{BindDmaWl, ImportDmaWl, ImportMemWl};
 #[cfg(all(
     feature = "wayland_frontend",
     feature = "backend_egl",
@@ -14,10 +14,14 @@ use crate::{
         allocator::{dmabuf::Dmabuf, format::FormatSet, Format, Fourcc},
         egl::EGLContext,
         renderer::{
-            element::UnderlyingStorage,
+            element::{
+                utils::{rounded_rect_spans, RoundedCornerRenderer},
+                UnderlyingStorage,
+            },
             gles::{element::*, *},
-            sync, Bind, Blit, BlitFrame, Color32F, DebugFlags, ExportMem, ImportDma, ImportMem, Offscreen,
-            Renderer, RendererSuper, TextureFilter,
+            report::{RenderReport, RenderReporting},
+            sync, Bind, Blit, BlitFrame, Color32F, DebugFlags, ExportDma, ExportMem, ImportDma, ImportMem,
+            Offscreen, Renderer, RendererSuper, TextureFilter, YuvConversion,
         },
     },
     utils::{Buffer as BufferCoord, Physical, Rectangle, Size, Transform},
@@ -117,6 +121,17 @@ impl GlowRenderer {
         self.gl.egl_context()
     }
 
+    /// Limits the region of `target` updated by the next frame
+    ///
+    /// See [`GlesRenderer::set_damage_region`].
+    pub fn set_damage_region(
+        &mut self,
+        target: &mut GlesTarget<'_>,
+        damage: &[Rectangle<i32, Physical>],
+    ) -> Result<(), GlesError> {
+        self.gl.set_damage_region(target, damage)
+    }
+
     /// Run custom code in the GL context owned by this renderer.
     ///
     /// The OpenGL state of the renderer is considered an implementation detail
@@ -258,6 +273,20 @@ impl Renderer for GlowRenderer {
     }
 }
 
+impl RenderReporting for GlowRenderer {
+    fn set_reporting(&mut self, enabled: bool) {
+        self.gl.set_reporting(enabled)
+    }
+
+    fn is_reporting(&self) -> bool {
+        self.gl.is_reporting()
+    }
+
+    fn take_reports(&mut self) -> Vec<RenderReport> {
+        self.gl.take_reports()
+    }
+}
+
 impl Frame for GlowFrame<'_, '_> {
     type Error = GlesError;
     type TextureId = GlesTexture;
@@ -337,6 +366,14 @@ impl Frame for GlowFrame<'_, '_> {
         self.frame.as_mut().unwrap().wait(sync)
     }
 
+    fn begin_scope(&mut self, name: &'static str) {
+        self.frame.as_mut().unwrap().begin_scope(name)
+    }
+
+    fn end_scope(&mut self) {
+        self.frame.as_mut().unwrap().end_scope()
+    }
+
     #[profiling::function]
     fn finish(mut self) -> Result<sync::SyncPoint, Self::Error> {
         self.finish_internal()
@@ -447,6 +484,15 @@ impl ImportDma for GlowRenderer {
     ) -> Result<GlesTexture, GlesError> {
         self.gl.import_dmabuf(buffer, damage)
     }
+    #[profiling::function]
+    fn import_dmabuf_with_conversion(
+        &mut self,
+        buffer: &Dmabuf,
+        conversion: &YuvConversion,
+        damage: Option<&[Rectangle<i32, BufferCoord>]>,
+    ) -> Result<GlesTexture, GlesError> {
+        self.gl.import_dmabuf_with_conversion(buffer, conversion, damage)
+    }
     fn dmabuf_formats(&self) -> FormatSet {
         self.gl.dmabuf_formats()
     }
@@ -458,6 +504,32 @@ impl ImportDma for GlowRenderer {
 #[cfg(feature = "wayland_frontend")]
 impl ImportDmaWl for GlowRenderer {}
 
+#[cfg(feature = "wayland_frontend")]
+impl BindDmaWl for GlowRenderer {}
+
+impl RoundedCornerRenderer for GlowRenderer {
+    fn draw_rounded<E: RenderElement<Self>>(
+        frame: &mut GlowFrame<'_, '_>,
+        element: &E,
+        src: Rectangle<f64, BufferCoord>,
+        dst: Rectangle<i32, Physical>,
+        damage: &[Rectangle<i32, Physical>],
+        opaque_regions: &[Rectangle<i32, Physical>],
+        clip: Rectangle<i32, Physical>,
+        radius: f64,
+    ) -> Result<(), GlesError> {
+        let spans = rounded_rect_spans(clip, radius, true);
+        let damage = damage
+            .iter()
+            .flat_map(|rect| spans.iter().filter_map(move |span| span.intersection(*rect)))
+            .collect::<Vec<_>>();
+        let previous = BorrowMut::<GlesFrame<'_, '_>>::borrow_mut(frame).push_rounded_clip(clip, radius)?;
+        let res = element.draw(frame, src, dst, &damage, opaque_regions);
+        BorrowMut::<GlesFrame<'_, '_>>::borrow_mut(frame).pop_rounded_clip(previous);
+        res
+    }
+}
+
 impl ExportMem for GlowRenderer {
     type TextureMapping = GlesMapping;
 
@@ -494,6 +566,16 @@ impl ExportMem for GlowRenderer {
     }
 }
 
+impl ExportDma for GlowRenderer {
+    #[profiling::function]
+    fn export_texture(
+        &mut self,
+        texture: &Self::TextureId,
+    ) -> Result<(Dmabuf, sync::SyncPoint), Self::Error> {
+        self.gl.export_texture(texture)
+    }
+}
+
 impl<T> Bind<T> for GlowRenderer
 where
     GlesRenderer: Bind<T>,
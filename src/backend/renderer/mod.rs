@@ -34,7 +34,7 @@ pub mod glow;
 pub mod pixman;
 
 mod color;
-pub use color::Color32F;
+pub use color::{ChromaLocation, Color32F, ColorRange, MatrixCoefficients, YuvConversion};
 
 use crate::backend::allocator::{dmabuf::Dmabuf, Format, Fourcc};
 #[cfg(all(
@@ -60,6 +60,8 @@ pub mod damage;
 
 pub mod sync;
 
+pub mod report;
+
 // Note: This doesn't fully work yet due to <https://github.com/rust-lang/rust/issues/67295>.
 // Use `--features renderer_test` when running doc tests manually.
 #[cfg(any(feature = "renderer_test", test, doctest))]
@@ -345,6 +347,18 @@ pub trait Frame {
     /// Wait for a [`SyncPoint`](sync::SyncPoint) to be signaled
     fn wait(&mut self, sync: &sync::SyncPoint) -> Result<(), Self::Error>;
 
+    /// Begin a profiling scope, e.g. for a batch of elements
+    ///
+    /// Scopes can be nested and are ended by [`Frame::end_scope`]. Their timings are included in
+    /// the [`RenderReport`](report::RenderReport)s of renderers implementing [`RenderReporting`](report::RenderReporting).
+    /// Does nothing by default.
+    fn begin_scope(&mut self, name: &'static str) {
+        let _ = name;
+    }
+
+    /// End the most recently started profiling scope
+    fn end_scope(&mut self) {}
+
     /// Finish this [`Frame`] returning any error that may happen during any cleanup.
     ///
     /// Dropping the frame instead may result in any of the following and is implementation dependent:
@@ -603,12 +617,38 @@ pub trait ImportDmaWl: ImportDma {
     fn import_dma_buffer(
         &mut self,
         buffer: &wl_buffer::WlBuffer,
-        _surface: Option<&crate::wayland::compositor::SurfaceData>,
+        surface: Option<&crate::wayland::compositor::SurfaceData>,
         damage: &[Rectangle<i32, BufferCoord>],
     ) -> Result<Self::TextureId, Self::Error> {
         let dmabuf = crate::wayland::dmabuf::get_dmabuf(buffer)
             .expect("import_dma_buffer without checking buffer type?");
-        self.import_dmabuf(dmabuf, Some(damage))
+        let conversion = surface
+            .map(crate::wayland::color_representation::surface_yuv_conversion)
+            .unwrap_or_default();
+        self.import_dmabuf_with_conversion(dmabuf, &conversion, Some(damage))
+    }
+}
+
+#[cfg(feature = "wayland_frontend")]
+/// Trait for Renderers supporting binding dmabuf-based wl_buffers as rendering targets
+///
+/// This allows rendering directly into client-provided buffers, e.g. to fulfill screencopy requests
+/// without an intermediate blit.
+pub trait BindDmaWl: Bind<Dmabuf> {
+    /// Returns the [`Dmabuf`] backing a given buffer, if it can be bound as a rendering target.
+    ///
+    /// The returned dmabuf can be passed to [`Bind::bind`]. Renderers are free to cache resources
+    /// associated with the dmabuf, so binding the same buffer repeatedly does not need to recreate them.
+    ///
+    /// Returns `None`, if the buffer is not a dmabuf or its format is not supported for rendering.
+    fn dmabuf_target(&self, buffer: &wl_buffer::WlBuffer) -> Option<Dmabuf> {
+        let dmabuf = crate::wayland::dmabuf::get_dmabuf(buffer).ok()?;
+        if let Some(formats) = Bind::<Dmabuf>::supported_formats(self) {
+            if !formats.contains(&crate::backend::allocator::Buffer::format(dmabuf)) {
+                return None;
+            }
+        }
+        Some(dmabuf.clone())
     }
 }
 
@@ -640,6 +680,22 @@ pub trait ImportDma: Renderer {
         dmabuf: &Dmabuf,
         damage: Option<&[Rectangle<i32, BufferCoord>]>,
     ) -> Result<Self::TextureId, Self::Error>;
+
+    /// Import a given raw dmabuf into the renderer using the given [`YuvConversion`].
+    ///
+    /// Behaves like [`ImportDma::import_dmabuf`], but converts YCbCr buffers to RGB
+    /// using the provided parameters where supported.
+    ///
+    /// Defaults to [`ImportDma::import_dmabuf`], ignoring the conversion parameters.
+    fn import_dmabuf_with_conversion(
+        &mut self,
+        dmabuf: &Dmabuf,
+        conversion: &YuvConversion,
+        damage: Option<&[Rectangle<i32, BufferCoord>]>,
+    ) -> Result<Self::TextureId, Self::Error> {
+        let _ = conversion;
+        self.import_dmabuf(dmabuf, damage)
+    }
 }
 
 // TODO: Replace this with a trait_alias, once that is stabilized.
@@ -775,6 +831,32 @@ pub trait ExportMem: Renderer {
         -> Result<&'a [u8], Self::Error>;
 }
 
+/// Trait for renderers supporting exporting textures as dmabufs.
+///
+/// This allows a different process or device, e.g. an out-of-process screencast encoder, to access the
+/// contents of a texture without copying them. Frames can be composed into a texture created with
+/// [`Offscreen::create_buffer`] and be exported once, the dmabuf keeps referencing the storage of the
+/// texture and sees the contents of every subsequent frame rendered into it.
+///
+/// Access to the dmabuf is not implicitly synchronized. The [`SyncPoint`](sync::SyncPoint) returned
+/// by [`ExportDma::export_texture`] covers all rendering into the texture submitted before the export.
+/// For later frames the [`SyncPoint`](sync::SyncPoint) returned by [`Frame::finish`] has to be passed
+/// along instead, e.g. as a native fence fd using [`SyncPoint::export`](sync::SyncPoint::export), or be
+/// waited on, if it is not exportable. Likewise the texture must not be rendered into again, until the
+/// consumer is done reading the frame.
+pub trait ExportDma: Renderer {
+    /// Exports the given texture as a [`Dmabuf`]
+    ///
+    /// Every call returns a new dmabuf referencing the storage of the texture, together with a
+    /// [`SyncPoint`](sync::SyncPoint), which is signaled once the contents of the texture are ready.
+    ///
+    /// This function *may* fail, if (but not limited to):
+    /// - The texture is not backed by memory, which can be shared
+    /// - The underlying implementation does not support exporting textures
+    fn export_texture(&mut self, texture: &Self::TextureId)
+        -> Result<(Dmabuf, sync::SyncPoint), Self::Error>;
+}
+
 /// Trait for renderers supporting blitting contents from one framebuffer to another.
 // We would like to require the following. But we can't because of <https://github.com/rust-lang/rust/issues/100013>.
 // for<'frame, 'buffer> Self::Frame<'frame, 'buffer>: BlitFrame<Self::Framebuffer<'buffer>>,
@@ -856,7 +938,7 @@ where
 #[cfg(feature = "wayland_frontend")]
 #[non_exhaustive]
 /// Buffer type of a given wl_buffer, if managed by smithay
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum BufferType {
     /// Buffer is managed by the [`crate::wayland::shm`] global
     Shm,
@@ -908,6 +990,46 @@ pub fn buffer_type(buffer: &wl_buffer::WlBuffer) -> Option<BufferType> {
     None
 }
 
+/// Metadata of a wl_buffer, if managed by smithay
+///
+/// Returned by [`buffer_info`].
+#[cfg(feature = "wayland_frontend")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BufferInfo {
+    /// Type of the buffer
+    pub kind: BufferType,
+    /// Size of the buffer
+    pub size: Size<i32, BufferCoord>,
+    /// Format of the buffer, if it has a fourcc representation
+    ///
+    /// Single pixel and egl buffers have no associated fourcc.
+    pub format: Option<Fourcc>,
+    /// If the buffer has an alpha channel
+    ///
+    /// See [`buffer_has_alpha`].
+    pub has_alpha: bool,
+    /// If the buffer is y-inverted
+    pub y_inverted: bool,
+    /// Color of a single pixel buffer
+    pub color: Option<Color32F>,
+}
+
+#[cfg(feature = "wayland_frontend")]
+impl BufferInfo {
+    /// Returns if the buffer is known to be fully opaque
+    pub fn is_opaque(&self) -> bool {
+        !self.has_alpha
+    }
+
+    /// Returns the color if the buffer is filled with a single color
+    ///
+    /// Single pixel buffers are usually scaled up using a viewport, in which case the surface
+    /// can be drawn as a solid color instead of importing the buffer.
+    pub fn solid_color(&self) -> Option<Color32F> {
+        self.color
+    }
+}
+
 /// Returns if the buffer has an alpha channel
 ///
 /// Returns `None` if the type is not known to smithay
@@ -991,6 +1113,82 @@ pub fn buffer_dimensions(buffer: &wl_buffer::WlBuffer) -> Option<Size<i32, Buffe
     }
 }
 
+/// Returns the [`BufferInfo`] of a wl_buffer
+///
+/// Returns `None` if the type is not known to smithay
+/// or otherwise not supported (e.g. not initialized using one of smithays [`crate::wayland`]-handlers).
+///
+/// Prefer this over calling [`buffer_type`], [`buffer_dimensions`], [`buffer_has_alpha`]
+/// and [`buffer_y_inverted`] separately, as it only looks up the buffer once.
+#[cfg(feature = "wayland_frontend")]
+#[profiling::function]
+pub fn buffer_info(buffer: &wl_buffer::WlBuffer) -> Option<BufferInfo> {
+    use crate::{
+        backend::allocator::{format::has_alpha, Buffer},
+        wayland::shm::{self, shm_format_to_fourcc, BufferAccessError},
+    };
+
+    if let Ok(dmabuf) = crate::wayland::dmabuf::get_dmabuf(buffer) {
+        let format = dmabuf.format().code;
+        return Some(BufferInfo {
+            kind: BufferType::Dma,
+            size: dmabuf.size(),
+            format: Some(format),
+            has_alpha: has_alpha(format),
+            y_inverted: dmabuf.y_inverted(),
+            color: None,
+        });
+    }
+
+    match shm::with_buffer_contents(buffer, |_, _, data| {
+        let format = shm_format_to_fourcc(data.format);
+        BufferInfo {
+            kind: BufferType::Shm,
+            size: (data.width, data.height).into(),
+            format,
+            has_alpha: format.is_some_and(has_alpha),
+            y_inverted: false,
+            color: None,
+        }
+    }) {
+        Ok(info) => return Some(info),
+        Err(BufferAccessError::NotManaged) => {}
+        Err(_) => return None,
+    }
+
+    if let Ok(spb) = crate::wayland::single_pixel_buffer::get_single_pixel_buffer(buffer) {
+        return Some(BufferInfo {
+            kind: BufferType::SinglePixel,
+            size: (1, 1).into(),
+            format: None,
+            has_alpha: spb.has_alpha(),
+            y_inverted: false,
+            color: Some(spb.rgba32f().into()),
+        });
+    }
+
+    // Not managed, check if this is an EGLBuffer
+    #[cfg(all(feature = "backend_egl", feature = "use_system_lib"))]
+    if let Some(egl_buffer) = BUFFER_READER
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|x| x.upgrade())
+        .and_then(|x| x.egl_buffer_contents(buffer).ok())
+    {
+        return Some(BufferInfo {
+            kind: BufferType::Egl,
+            size: egl_buffer.size,
+            format: None,
+            has_alpha: crate::backend::egl::display::EGLBufferReader::egl_buffer_has_alpha(egl_buffer.format),
+            y_inverted: egl_buffer.y_inverted,
+            color: None,
+        });
+    }
+
+    None
+}
+
 /// Returns if the underlying buffer is y-inverted
 ///
 /// *Note*: This will only return y-inverted for buffer types known to smithay (see [`buffer_type`])
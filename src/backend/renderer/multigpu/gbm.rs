@@ -1,7 +1,7 @@
 //! Implementation of the multi-gpu [`GraphicsApi`] using
 //! user provided GBM devices and OpenGL ES for rendering.
 
-use tracing::warn;
+use tracing::{debug, warn};
 #[cfg(all(feature = "wayland_frontend", feature = "use_system_lib"))]
 use wayland_server::protocol::wl_buffer;
 
@@ -15,7 +15,7 @@ use crate::backend::{
     egl::{context::ContextPriority, EGLContext, EGLDisplay, Error as EGLError},
     renderer::{
         gles::{GlesError, GlesRenderer},
-        multigpu::{ApiDevice, Error as MultiError, GraphicsApi},
+        multigpu::{ApiDevice, Error as MultiError, GraphicsApi, TransferTimeline},
         Renderer, RendererSuper,
     },
     SwapBuffersError,
@@ -187,11 +187,16 @@ impl<R: From<GlesRenderer> + Renderer<Error = GlesError>, A: AsFd + Clone + 'sta
                     unsafe { GlesRenderer::new(context).map_err(Error::Gl)? }.into()
                 };
 
+                let timeline = TransferTimeline::new(gbm.as_fd())
+                    .map_err(|err| debug!(?node, ?err, "No transfer timeline available"))
+                    .ok();
+
                 Ok(GbmGlesDevice {
                     node: *node,
                     _display: display.clone(),
                     renderer,
                     allocator: Box::new(DmabufAllocator(gbm.clone())),
+                    timeline,
                 })
             })
             .flat_map(|x: Result<GbmGlesDevice<R>, Error>| match x {
@@ -236,6 +241,7 @@ pub struct GbmGlesDevice<R> {
     node: DrmNode,
     renderer: R,
     allocator: Box<dyn Allocator<Buffer = Dmabuf, Error = AnyError>>,
+    timeline: Option<TransferTimeline>,
     _display: EGLDisplay,
 }
 
@@ -263,6 +269,9 @@ impl<R: Renderer> ApiDevice for GbmGlesDevice<R> {
     fn node(&self) -> &DrmNode {
         &self.node
     }
+    fn transfer_timeline(&mut self) -> Option<&mut TransferTimeline> {
+        self.timeline.as_mut()
+    }
 }
 
 #[cfg(all(feature = "wayland_frontend", feature = "use_system_lib"))]
@@ -34,6 +34,11 @@
 //! Any `ExportMem`-implementations will originate from the render-gpu, which again
 //! needs to support the requested format directly. No paths across other gpus are tested.
 //!
+//! Copies between gpus are synchronized on the gpus, if the graphics api supports importing fences.
+//! If a device provides a [`TransferTimeline`], like the devices of [`gbm::GbmGlesBackend`]
+//! on drivers supporting DRM timeline syncobjs, copies to it complete on points of its timeline.
+//! Their completion can then be queried cheaply, avoiding to wait for copies that are already done.
+//!
 //! *Note*: This module will not keep you from selecting sub-optimal configurations.
 //! Any heuristics for which render-gpu to use for a given set of client buffers
 //! and desired target-gpu are up to be implemented by the compositor. The module only
@@ -48,11 +53,12 @@ use std::{
 };
 
 use super::{
-    sync::SyncPoint, Bind, Blit, BlitFrame, Color32F, ContextId, DebugFlags, ExportMem, Frame, ImportDma,
-    ImportMem, Offscreen, Renderer, RendererSuper, Texture, TextureFilter, TextureMapping,
+    element::utils::RoundedCornerRenderer, sync::SyncPoint, Bind, Blit, BlitFrame, Color32F, ContextId,
+    DebugFlags, ExportMem, Frame, ImportDma, ImportMem, Offscreen, Renderer, RendererSuper, Texture,
+    TextureFilter, TextureMapping, YuvConversion,
 };
 #[cfg(feature = "wayland_frontend")]
-use super::{ImportDmaWl, ImportMemWl};
+use super::{BindDmaWl, ImportDmaWl, ImportMemWl};
 
 #[cfg(feature = "wayland_frontend")]
 use crate::{
@@ -78,6 +84,9 @@ use wayland_server::protocol::{wl_buffer, wl_shm, wl_surface::WlSurface};
 
 #[cfg(all(feature = "backend_gbm", feature = "backend_egl", feature = "renderer_gl"))]
 pub mod gbm;
+mod timeline;
+
+pub use self::timeline::TransferTimeline;
 
 /// Tracks available gpus from a given [`GraphicsApi`]
 #[derive(Debug)]
@@ -559,7 +568,16 @@ impl<A: GraphicsApi> GpuManager<A> {
 
                 let mut devices = self.devices.iter_mut();
                 let first = devices.next().unwrap();
-                let src_node = import_on_src_node(dmabuf, Some(damage), &mut texture, first, None, devices)?;
+                let conversion = crate::wayland::color_representation::surface_yuv_conversion(surface);
+                let src_node = import_on_src_node(
+                    dmabuf,
+                    &conversion,
+                    Some(damage),
+                    &mut texture,
+                    first,
+                    None,
+                    devices,
+                )?;
 
                 if src_node != target_node {
                     let mut texture_internal = texture.0.lock().unwrap();
@@ -719,6 +737,13 @@ pub trait ApiDevice: fmt::Debug {
 
     /// Returns a [`DrmNode`] representing the graphics device
     fn node(&self) -> &DrmNode;
+
+    /// Returns the [`TransferTimeline`] used to synchronize copies to this device
+    ///
+    /// Without a timeline, the device waits on the fences of the copying device directly.
+    fn transfer_timeline(&mut self) -> Option<&mut TransferTimeline> {
+        None
+    }
 }
 
 /// Renderer, that transparently copies rendering results to another gpu,
@@ -1397,12 +1422,19 @@ where
                         .iter()
                         .map(|rect| rect.to_logical(1, Transform::Normal, &buffer_size).to_physical(1))
                         .collect::<Vec<_>>();
+                    let sync = target
+                        .device
+                        .transfer_timeline()
+                        .and_then(|timeline| timeline.signal(&sync))
+                        .unwrap_or(sync);
                     let mut frame = target
                         .device
                         .renderer_mut()
                         .render(target.framebuffer, self.size, Transform::Normal)
                         .map_err(Error::Target)?;
-                    frame.wait(&sync).map_err(Error::Target)?;
+                    if !sync.is_reached() {
+                        frame.wait(&sync).map_err(Error::Target)?;
+                    }
                     frame
                         .clear(Color32F::TRANSPARENT, &damage)
                         .map_err(Error::Target)?;
@@ -1867,7 +1899,8 @@ where
                 rect.loc += dst.loc;
                 rect
             }));
-            if let Some(sync) = sync {
+            // copies synchronized using a transfer timeline can be queried cheaply
+            if let Some(sync) = sync.filter(|sync| !sync.is_reached()) {
                 if let Err(err) = self.frame.as_mut().unwrap().wait(&sync) {
                     trace!(?err, "Failed to import sync point, blocking");
                     let _ = sync.wait(); // ignore interrupt errors
@@ -1898,6 +1931,14 @@ where
         self.frame.as_mut().unwrap().wait(sync).map_err(Error::Render)
     }
 
+    fn begin_scope(&mut self, name: &'static str) {
+        self.frame.as_mut().unwrap().begin_scope(name)
+    }
+
+    fn end_scope(&mut self) {
+        self.frame.as_mut().unwrap().end_scope()
+    }
+
     #[profiling::function]
     fn finish(mut self) -> Result<sync::SyncPoint, Self::Error> {
         self.finish_internal()
@@ -2013,6 +2054,12 @@ where
     }
 }
 
+#[cfg(feature = "wayland_frontend")]
+impl<R: GraphicsApi, T: GraphicsApi> BindDmaWl for MultiRenderer<'_, '_, R, T> where Self: Bind<Dmabuf> {}
+
+impl<R: GraphicsApi, T: GraphicsApi> RoundedCornerRenderer for MultiRenderer<'_, '_, R, T> where Self: Renderer
+{}
+
 #[cfg(feature = "wayland_frontend")]
 impl<R: GraphicsApi, T: GraphicsApi> ImportDmaWl for MultiRenderer<'_, '_, R, T>
 where
@@ -2040,7 +2087,10 @@ where
         let dmabuf = get_dmabuf(buffer).expect("import_dma_buffer without checking buffer type?");
         let texture = MultiTexture::from_surface(surface, dmabuf.size(), dmabuf.format());
         let texture_ref = texture.0.clone();
-        let res = self.import_dmabuf_internal(dmabuf, texture, Some(damage));
+        let conversion = surface
+            .map(crate::wayland::color_representation::surface_yuv_conversion)
+            .unwrap_or_default();
+        let res = self.import_dmabuf_internal(dmabuf, &conversion, texture, Some(damage));
         if res.is_ok() {
             if let Some(surface) = surface {
                 surface.data_map.insert_if_missing_threadsafe(|| texture_ref);
@@ -2079,14 +2129,28 @@ where
         &mut self,
         dmabuf: &Dmabuf,
         damage: Option<&[Rectangle<i32, BufferCoords>]>,
+    ) -> Result<<Self as RendererSuper>::TextureId, <Self as RendererSuper>::Error> {
+        self.import_dmabuf_with_conversion(dmabuf, &YuvConversion::default(), damage)
+    }
+
+    #[instrument(level = "trace", parent = &self.span, skip(self))]
+    #[profiling::function]
+    fn import_dmabuf_with_conversion(
+        &mut self,
+        dmabuf: &Dmabuf,
+        conversion: &YuvConversion,
+        damage: Option<&[Rectangle<i32, BufferCoords>]>,
     ) -> Result<<Self as RendererSuper>::TextureId, <Self as RendererSuper>::Error> {
         let texture = MultiTexture::new(dmabuf.size(), dmabuf.format());
-        self.import_dmabuf_internal(dmabuf, texture, damage)
+        self.import_dmabuf_internal(dmabuf, conversion, texture, damage)
     }
 }
 
+// Imports the dmabuf on the node it was allocated on, converting YCbCr contents using `conversion`.
+// Copies to other nodes are made from the converted texture.
 fn import_on_src_node<'a, R, T>(
     dmabuf: &Dmabuf,
+    conversion: &YuvConversion,
     damage: Option<&[Rectangle<i32, BufferCoords>]>,
     texture: &mut MultiTexture,
     render: &mut R::Device,
@@ -2107,20 +2171,20 @@ where
             if node == *render.node() {
                 let imported = render
                     .renderer_mut()
-                    .import_dmabuf(dmabuf, damage)
+                    .import_dmabuf_with_conversion(dmabuf, conversion, damage)
                     .map_err(Error::Render)?;
                 texture.insert_texture::<R>(node, imported);
             } else if target.as_ref().is_some_and(|target| node == *target.node()) {
                 let imported = target
                     .unwrap()
                     .renderer_mut()
-                    .import_dmabuf(dmabuf, damage)
+                    .import_dmabuf_with_conversion(dmabuf, conversion, damage)
                     .map_err(Error::Target)?;
                 texture.insert_texture::<T>(node, imported);
             } else if let Some(other) = others.find(|other| node == *other.node()) {
                 let imported = other
                     .renderer_mut()
-                    .import_dmabuf(dmabuf, damage)
+                    .import_dmabuf_with_conversion(dmabuf, conversion, damage)
                     .map_err(Error::Render)?;
                 texture.insert_texture::<R>(node, imported);
             } else {
@@ -2131,21 +2195,26 @@ where
         }
         None => {
             // try them all
-            let node = if let Ok(imported) = render.renderer_mut().import_dmabuf(dmabuf, damage) {
+            let node = if let Ok(imported) = render
+                .renderer_mut()
+                .import_dmabuf_with_conversion(dmabuf, conversion, damage)
+            {
                 let node = *render.node();
                 texture.insert_texture::<R>(node, imported);
                 node
-            } else if let Some(imported) = target
-                .as_mut()
-                .and_then(|target| target.renderer_mut().import_dmabuf(dmabuf, damage).ok())
-            {
+            } else if let Some(imported) = target.as_mut().and_then(|target| {
+                target
+                    .renderer_mut()
+                    .import_dmabuf_with_conversion(dmabuf, conversion, damage)
+                    .ok()
+            }) {
                 let node = *target.as_ref().unwrap().node();
                 texture.insert_texture::<T>(node, imported);
                 node
             } else if let Some((node, imported)) = others.find_map(|other| {
                 other
                     .renderer_mut()
-                    .import_dmabuf(dmabuf, damage)
+                    .import_dmabuf_with_conversion(dmabuf, conversion, damage)
                     .ok()
                     .map(|imported| (*other.node(), imported))
             }) {
@@ -2260,7 +2329,13 @@ where
             1.0,
         )
         .map_err(Error::Render)?;
-    *existing_sync_point = Some(frame.finish().map_err(Error::Render)?);
+    let sync = frame.finish().map_err(Error::Render)?;
+    // let the target wait for the copy on its own timeline, if available
+    let sync = target
+        .and_then(|target| target.transfer_timeline())
+        .and_then(|timeline| timeline.signal(&sync))
+        .unwrap_or(sync);
+    *existing_sync_point = Some(sync);
 
     // shadow buffer contains our copy and is readable by target and the original buffer was never migrated
     Ok(())
@@ -2583,11 +2658,13 @@ where
     fn import_dmabuf_internal(
         &mut self,
         dmabuf: &Dmabuf,
+        conversion: &YuvConversion,
         mut texture: MultiTexture,
         damage: Option<&[Rectangle<i32, BufferCoords>]>,
     ) -> Result<<Self as RendererSuper>::TextureId, <Self as RendererSuper>::Error> {
         let src_node = import_on_src_node::<R, T>(
             dmabuf,
+            conversion,
             damage,
             &mut texture,
             self.render,
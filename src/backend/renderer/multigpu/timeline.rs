@@ -0,0 +1,160 @@
+use std::{
+    io,
+    os::unix::io::{AsFd, AsRawFd, BorrowedFd, OwnedFd},
+    sync::Arc,
+};
+
+use drm::{
+    control::{syncobj, Device as ControlDevice},
+    Device, DriverCapability,
+};
+use drm_ffi::{drm_syncobj_handle, DRM_SYNCOBJ_FD_TO_HANDLE_FLAGS_IMPORT_SYNC_FILE};
+use rustix::ioctl::{ReadWriteOpcode, Updater};
+use tracing::debug;
+
+use crate::backend::renderer::sync::{Fence, Interrupted, SyncPoint};
+
+type SyncobjFdToHandle = ReadWriteOpcode<b'd', 0xC2, drm_syncobj_handle>;
+
+#[derive(Debug)]
+struct SyncobjDevice(OwnedFd);
+
+impl AsFd for SyncobjDevice {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.0.as_fd()
+    }
+}
+
+impl Device for SyncobjDevice {}
+impl ControlDevice for SyncobjDevice {}
+
+#[derive(Debug)]
+struct Syncobj {
+    device: Arc<SyncobjDevice>,
+    handle: syncobj::Handle,
+}
+
+impl Syncobj {
+    fn new(device: &Arc<SyncobjDevice>) -> io::Result<Self> {
+        Ok(Syncobj {
+            device: device.clone(),
+            handle: device.create_syncobj(false)?,
+        })
+    }
+}
+
+impl Drop for Syncobj {
+    fn drop(&mut self) {
+        let _ = self.device.destroy_syncobj(self.handle);
+    }
+}
+
+/// DRM timeline syncobj of a device, used to synchronize transfers to the device
+///
+/// Copies between gpus finish on points of the timeline of the device reading the copy.
+/// Readiness of a copy can then be queried without involving the graphics api of the
+/// gpu that executed it and completed copies don't need to be waited on.
+#[derive(Debug)]
+pub struct TransferTimeline {
+    timeline: Arc<Syncobj>,
+    point: u64,
+}
+
+impl TransferTimeline {
+    /// Create a timeline on the drm device of the given file descriptor
+    ///
+    /// Fails if the device does not support timeline syncobjs.
+    pub fn new(fd: BorrowedFd<'_>) -> io::Result<Self> {
+        let device = Arc::new(SyncobjDevice(fd.try_clone_to_owned()?));
+        if device.get_driver_capability(DriverCapability::TimelineSyncObj)? == 0 {
+            return Err(io::ErrorKind::Unsupported.into());
+        }
+        Ok(TransferTimeline {
+            timeline: Arc::new(Syncobj::new(&device)?),
+            point: 0,
+        })
+    }
+
+    /// Moves the fence of a sync point to the next point of this timeline
+    ///
+    /// Returns `None` if the fence can't be exported as a native fence fd
+    /// or if the sync point was already reached.
+    pub fn signal(&mut self, sync: &SyncPoint) -> Option<SyncPoint> {
+        if sync.is_reached() {
+            return None;
+        }
+        let sync_file = sync.export()?;
+        match self.import(sync_file.as_fd()) {
+            Ok(point) => Some(point.into()),
+            Err(err) => {
+                debug!(?err, "Failed to import fence into transfer timeline");
+                None
+            }
+        }
+    }
+
+    fn import(&mut self, sync_file: BorrowedFd<'_>) -> io::Result<TimelinePoint> {
+        let device = &self.timeline.device;
+        let binary = Syncobj::new(device)?;
+        let mut args = drm_syncobj_handle {
+            handle: binary.handle.into(),
+            flags: DRM_SYNCOBJ_FD_TO_HANDLE_FLAGS_IMPORT_SYNC_FILE,
+            fd: sync_file.as_raw_fd(),
+            pad: 0,
+            point: 0,
+        };
+        unsafe { rustix::ioctl::ioctl(device, Updater::<SyncobjFdToHandle, _>::new(&mut args)) }?;
+
+        let point = self.point + 1;
+        device.syncobj_timeline_transfer(binary.handle, self.timeline.handle, 0, point)?;
+        self.point = point;
+        Ok(TimelinePoint {
+            timeline: self.timeline.clone(),
+            point,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct TimelinePoint {
+    timeline: Arc<Syncobj>,
+    point: u64,
+}
+
+impl Fence for TimelinePoint {
+    fn is_signaled(&self) -> bool {
+        let mut points = [0];
+        self.timeline
+            .device
+            .syncobj_timeline_query(&[self.timeline.handle], &mut points, false)
+            .is_ok_and(|_| points[0] >= self.point)
+    }
+
+    fn wait(&self) -> Result<(), Interrupted> {
+        self.timeline
+            .device
+            .syncobj_timeline_wait(
+                &[self.timeline.handle],
+                &[self.point],
+                i64::MAX,
+                false,
+                false,
+                false,
+            )
+            .map(|_| ())
+            .map_err(|_| Interrupted)
+    }
+
+    fn is_exportable(&self) -> bool {
+        true
+    }
+
+    fn export(&self) -> Option<OwnedFd> {
+        let device = &self.timeline.device;
+        let binary = Syncobj::new(device).ok()?;
+        device
+            .syncobj_timeline_transfer(self.timeline.handle, binary.handle, self.point, 0)
+            .ok()?;
+        device.syncobj_to_fd(binary.handle, true).ok()
+    }
+}
@@ -15,12 +15,12 @@ use crate::{
         format::{has_alpha, FormatSet},
         Buffer,
     },
-    utils::{Buffer as BufferCoords, Physical, Rectangle, Scale, Size, Transform},
+    utils::{Buffer as BufferCoords, Physical, Point, Rectangle, Scale, Size, Transform},
 };
 
 #[cfg(feature = "wayland_frontend")]
 use crate::{
-    backend::renderer::{ImportDmaWl, ImportMemWl},
+    backend::renderer::{BindDmaWl, ImportDmaWl, ImportMemWl},
     wayland::{compositor::SurfaceData, shm},
 };
 #[cfg(feature = "wayland_frontend")]
@@ -33,8 +33,10 @@ use wayland_server::protocol::wl_buffer;
 ))]
 use super::ImportEgl;
 use super::{
-    sync::SyncPoint, Bind, Color32F, ContextId, DebugFlags, ExportMem, Frame, ImportDma, ImportMem,
-    Offscreen, Renderer, RendererSuper, Texture, TextureFilter, TextureMapping,
+    element::{utils::RoundedCornerRenderer, RenderElement},
+    sync::SyncPoint,
+    Bind, Color32F, ContextId, DebugFlags, ExportMem, Frame, ImportDma, ImportMem, Offscreen, Renderer,
+    RendererSuper, Texture, TextureFilter, TextureMapping,
 };
 
 mod error;
@@ -273,11 +275,48 @@ pub struct PixmanFrame<'frame, 'buffer> {
     transform: Transform,
     output_size: Size<i32, Physical>,
     size: Size<i32, Physical>,
+    rounded_clip: Option<(Rectangle<i32, Physical>, f64)>,
 
     finished: AtomicBool,
 }
 
 impl PixmanFrame<'_, '_> {
+    // Creates an alpha mask for the current rounded clip in target coordinates
+    fn rounded_clip_mask(
+        &self,
+        alpha: f32,
+    ) -> Result<Option<(Image<'static, 'static>, Point<i32, Physical>)>, PixmanError> {
+        let Some((clip, radius)) = self.rounded_clip else {
+            return Ok(None);
+        };
+        let clip = self.transform.transform_rect_in(clip, &self.size);
+        if clip.is_empty() {
+            return Ok(None);
+        }
+
+        let mask = Image::new(FormatCode::A8, clip.size.w as usize, clip.size.h as usize, true)
+            .map_err(|_| PixmanError::Unsupported)?;
+        let stride = mask.stride();
+        let data = unsafe { std::slice::from_raw_parts_mut(mask.data() as *mut u8, stride * mask.height()) };
+
+        let half_w = clip.size.w as f64 / 2.0;
+        let half_h = clip.size.h as f64 / 2.0;
+        let radius = radius.min(half_w).min(half_h);
+        for y in 0..clip.size.h as usize {
+            for x in 0..clip.size.w as usize {
+                // signed distance of the pixel center to the rounded rectangle
+                let qx = (x as f64 + 0.5 - half_w).abs() - half_w + radius;
+                let qy = (y as f64 + 0.5 - half_h).abs() - half_h + radius;
+                let outside = (qx.max(0.0).powi(2) + qy.max(0.0).powi(2)).sqrt();
+                let dist = outside + qx.max(qy).min(0.0) - radius;
+                let coverage = (0.5 - dist).clamp(0.0, 1.0) * alpha as f64;
+                data[y * stride + x] = (coverage * 255.0).round() as u8;
+            }
+        }
+
+        Ok(Some((mask, clip.loc)))
+    }
+
     fn draw_solid_color(
         &mut self,
         dst: Rectangle<i32, Physical>,
@@ -286,6 +325,8 @@ impl PixmanFrame<'_, '_> {
         op: Operation,
         debug: DebugFlags,
     ) -> Result<(), PixmanError> {
+        let clip_mask = self.rounded_clip_mask(1.0)?;
+
         let mut binding;
         let target_image = match &mut self.target.0 {
             PixmanTargetInternal::Dmabuf { image, .. } => {
@@ -323,15 +364,27 @@ impl PixmanFrame<'_, '_> {
 
         target_image.set_clip_region32(Some(&clip_region))?;
 
-        target_image.composite32(
-            op,
-            &solid,
-            None,
-            (0, 0),
-            (0, 0),
-            (0, 0),
-            (target_image.width() as i32, target_image.height() as i32),
-        );
+        if let Some((mask, mask_loc)) = clip_mask.as_ref() {
+            target_image.composite32(
+                Operation::Over,
+                &solid,
+                Some(mask),
+                (0, 0),
+                (-mask_loc.x, -mask_loc.y),
+                (0, 0),
+                (target_image.width() as i32, target_image.height() as i32),
+            );
+        } else {
+            target_image.composite32(
+                op,
+                &solid,
+                None,
+                (0, 0),
+                (0, 0),
+                (0, 0),
+                (target_image.width() as i32, target_image.height() as i32),
+            );
+        }
 
         if debug.contains(DebugFlags::TINT) {
             target_image.composite32(
@@ -397,6 +450,8 @@ impl Frame for PixmanFrame<'_, '_> {
         src_transform: Transform,
         alpha: f32,
     ) -> Result<(), Self::Error> {
+        let clip_mask = self.rounded_clip_mask(alpha)?;
+
         let mut binding;
         let target_image = match &mut self.target.0 {
             PixmanTargetInternal::Dmabuf { image, .. } => {
@@ -602,21 +657,37 @@ impl Frame for PixmanFrame<'_, '_> {
                 Operation::Src
             };
 
-            let mask = if alpha != 1f32 {
-                Some(pixman::Solid::new([0f32, 0f32, 0f32, alpha]).map_err(|_| PixmanError::Unsupported)?)
+            if let Some((mask, mask_loc)) = clip_mask.as_ref() {
+                // the clip mask already contains the alpha
+                target_image.composite32(
+                    Operation::Over,
+                    src_image,
+                    Some(mask),
+                    (src_x, src_y),
+                    (dest_x - mask_loc.x, dest_y - mask_loc.y),
+                    (dest_x, dest_y),
+                    (width, height),
+                );
             } else {
-                None
-            };
+                let mask = if alpha != 1f32 {
+                    Some(
+                        pixman::Solid::new([0f32, 0f32, 0f32, alpha])
+                            .map_err(|_| PixmanError::Unsupported)?,
+                    )
+                } else {
+                    None
+                };
 
-            target_image.composite32(
-                op,
-                src_image,
-                mask.as_deref(),
-                (src_x, src_y),
-                (0, 0),
-                (dest_x, dest_y),
-                (width, height),
-            );
+                target_image.composite32(
+                    op,
+                    src_image,
+                    mask.as_deref(),
+                    (src_x, src_y),
+                    (0, 0),
+                    (dest_x, dest_y),
+                    (width, height),
+                );
+            }
 
             src_image.clear_transform()?;
 
@@ -871,6 +942,7 @@ impl Renderer for PixmanRenderer {
             transform: dst_transform,
             output_size,
             size: dst_transform.transform_size(output_size),
+            rounded_clip: None,
 
             finished: AtomicBool::new(false),
         })
@@ -1198,6 +1270,29 @@ impl ImportDma for PixmanRenderer {
 #[cfg(feature = "wayland_frontend")]
 impl ImportDmaWl for PixmanRenderer {}
 
+#[cfg(feature = "wayland_frontend")]
+impl BindDmaWl for PixmanRenderer {}
+
+impl RoundedCornerRenderer for PixmanRenderer {
+    fn draw_rounded<E: RenderElement<Self>>(
+        frame: &mut PixmanFrame<'_, '_>,
+        element: &E,
+        src: Rectangle<f64, BufferCoords>,
+        dst: Rectangle<i32, Physical>,
+        damage: &[Rectangle<i32, Physical>],
+        opaque_regions: &[Rectangle<i32, Physical>],
+        clip: Rectangle<i32, Physical>,
+        radius: f64,
+    ) -> Result<(), PixmanError> {
+        let mut output_clip = clip;
+        output_clip.loc += dst.loc;
+        let previous = frame.rounded_clip.replace((output_clip, radius));
+        let res = element.draw(frame, src, dst, damage, opaque_regions);
+        frame.rounded_clip = previous;
+        res
+    }
+}
+
 impl Bind<Dmabuf> for PixmanRenderer {
     #[profiling::function]
     fn bind<'a>(&mut self, target: &'a mut Dmabuf) -> Result<PixmanTarget<'a>, Self::Error> {
@@ -0,0 +1,87 @@
+//! Timing reports of rendered frames
+//!
+//! Stutter can be caused by the cpu taking too long to record a frame or by the gpu taking too long to
+//! execute it. Renderers implementing [`RenderReporting`] measure both per frame and per profiling scope,
+//! which lets compositors tell these cases apart, e.g. by logging slow frames in the field.
+//!
+//! Scopes are started and ended using [`Frame::begin_scope`](super::Frame::begin_scope) and
+//! [`Frame::end_scope`](super::Frame::end_scope). The [`OutputDamageTracker`](super::damage::OutputDamageTracker)
+//! uses the scopes `"clear"` and `"elements"` for clearing the damaged regions and drawing the elements.
+//!
+//! Gpu timings only become available once the gpu executed the frame, so reports are usually delivered
+//! a few frames late:
+//!
+//! ```no_run
+//! # use smithay::backend::renderer::report::RenderReporting;
+//! # fn example<R: RenderReporting>(renderer: &mut R) {
+//! renderer.set_reporting(true);
+//!
+//! // after rendering a frame
+//! for report in renderer.take_reports() {
+//!     if report.is_gpu_bound() == Some(true) {
+//!         println!("frame {} was gpu bound: {:?}", report.frame, report.gpu_time);
+//!     }
+//! }
+//! # }
+//! ```
+
+use std::time::Duration;
+
+use super::Renderer;
+
+/// Timings of a rendered frame
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderReport {
+    /// Sequence number of the frame, counting the frames rendered while reporting was enabled
+    pub frame: u64,
+    /// Time spent recording the frame on the cpu
+    pub cpu_time: Duration,
+    /// Time the gpu spent executing the frame
+    ///
+    /// `None` if the renderer can't measure gpu time or the measurement was invalidated,
+    /// e.g. because the gpu changed its clock frequency.
+    pub gpu_time: Option<Duration>,
+    /// Timings of the profiling scopes of the frame, in the order they were started
+    pub scopes: Vec<ScopeTiming>,
+}
+
+impl RenderReport {
+    /// Returns whether executing the frame on the gpu took longer than recording it on the cpu
+    ///
+    /// Returns `None` if the gpu time is unknown.
+    pub fn is_gpu_bound(&self) -> Option<bool> {
+        self.gpu_time.map(|gpu_time| gpu_time > self.cpu_time)
+    }
+
+    /// Returns the timing of the first scope with the given name
+    pub fn scope(&self, name: &str) -> Option<&ScopeTiming> {
+        self.scopes.iter().find(|scope| scope.name == name)
+    }
+}
+
+/// Timings of a profiling scope of a frame
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScopeTiming {
+    /// Name of the scope
+    pub name: &'static str,
+    /// Number of scopes enclosing this scope
+    pub depth: usize,
+    /// Time spent recording the scope on the cpu
+    pub cpu_time: Duration,
+    /// Time the gpu spent executing the scope, see [`RenderReport::gpu_time`]
+    pub gpu_time: Option<Duration>,
+}
+
+/// Trait for renderers able to report the timings of rendered frames
+pub trait RenderReporting: Renderer {
+    /// Enable or disable measuring the timings of frames
+    ///
+    /// Disabling drops any reports not yet taken.
+    fn set_reporting(&mut self, enabled: bool);
+    /// Returns whether the timings of frames are measured
+    fn is_reporting(&self) -> bool;
+    /// Returns the reports of the frames measured since the last call
+    ///
+    /// Frames still executing on the gpu are reported by a later call.
+    fn take_reports(&mut self) -> Vec<RenderReport>;
+}
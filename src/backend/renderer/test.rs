@@ -16,8 +16,8 @@ use crate::{
     backend::{
         allocator::{dmabuf::Dmabuf, Fourcc},
         renderer::{
-            sync::SyncPoint, DebugFlags, Frame, ImportDma, ImportMem, Renderer, RendererSuper, Texture,
-            TextureFilter,
+            element::utils::RoundedCornerRenderer, sync::SyncPoint, DebugFlags, Frame, ImportDma, ImportMem,
+            Renderer, RendererSuper, Texture, TextureFilter,
         },
         SwapBuffersError,
     },
@@ -211,6 +211,8 @@ impl ImportEgl for DummyRenderer {
 #[cfg(feature = "wayland_frontend")]
 impl ImportDmaWl for DummyRenderer {}
 
+impl RoundedCornerRenderer for DummyRenderer {}
+
 #[derive(Debug)]
 pub struct DummyFramebuffer;
 
@@ -0,0 +1,369 @@
+#[cfg(feature = "wayland_frontend")]
+use wayland_server::protocol::wl_buffer::WlBuffer;
+
+#[cfg(feature = "wayland_frontend")]
+use crate::{
+    backend::{allocator::Buffer as _, renderer::BindDmaWl},
+    wayland::shm,
+};
+use crate::{
+    backend::{
+        allocator::Fourcc,
+        renderer::{
+            damage::{self, OutputDamageTracker},
+            element::{
+                utils::{Relocate, RelocateRenderElement},
+                RenderElement,
+            },
+            sync::SyncPoint,
+            Color32F, ExportMem, Offscreen, Renderer, Texture, TextureMapping,
+        },
+    },
+    utils::{Buffer as BufferCoord, Physical, Rectangle, Scale, Size, Transform},
+};
+
+/// Renders a region of a set of elements into memory
+///
+/// The `elements` are expected in the coordinate space of the output they were collected for,
+/// `region` selects the captured part of it. The result is always upright, independent of the
+/// transform of the output, and contains non-premultiplied RGBA pixels.
+///
+/// This is intended as a common base for screenshots, e.g. for the `wlr-screencopy` or
+/// `ext-image-copy-capture` protocols or compositor-internal screenshot features.
+///
+/// ```no_run
+/// # use smithay::backend::renderer::{
+/// #     element::solid::SolidColorRenderElement, ExportMem, Offscreen, Renderer, Texture,
+/// # };
+/// use smithay::backend::renderer::utils::capture_elements;
+/// use smithay::utils::Rectangle;
+///
+/// # fn screenshot<R, T>(renderer: &mut R, elements: &[SolidColorRenderElement])
+/// # where
+/// #     R: Renderer + Offscreen<T> + ExportMem,
+/// #     R::TextureId: Texture + 'static,
+/// # {
+/// // capture the top left 100x100 pixels of an output
+/// let image = capture_elements(
+///     renderer,
+///     Rectangle::from_size((100, 100).into()),
+///     1.0,
+///     elements,
+///     [0.0, 0.0, 0.0, 1.0],
+/// )
+/// .expect("Failed to capture");
+/// assert_eq!(image.rgba().len(), 100 * 100 * 4);
+/// # }
+/// ```
+#[profiling::function]
+pub fn capture_elements<R, T, E>(
+    renderer: &mut R,
+    region: Rectangle<i32, Physical>,
+    scale: impl Into<Scale<f64>>,
+    elements: &[E],
+    clear_color: impl Into<Color32F>,
+) -> Result<CapturedImage, CaptureError<R::Error>>
+where
+    R: Renderer + Offscreen<T> + ExportMem,
+    R::TextureId: Texture + 'static,
+    E: RenderElement<R>,
+{
+    if region.is_empty() {
+        return Err(CaptureError::EmptyRegion);
+    }
+
+    let size = Size::<i32, BufferCoord>::from((region.size.w, region.size.h));
+    let mut target = renderer
+        .create_buffer(Fourcc::Abgr8888, size)
+        .map_err(CaptureError::Rendering)?;
+    let mut framebuffer = renderer.bind(&mut target).map_err(CaptureError::Rendering)?;
+
+    let sync = render_region(renderer, &mut framebuffer, region, scale, elements, clear_color)?;
+    renderer.wait(&sync).map_err(CaptureError::Rendering)?;
+
+    let mapping = renderer
+        .copy_framebuffer(&framebuffer, Rectangle::from_size(size), Fourcc::Abgr8888)
+        .map_err(CaptureError::Rendering)?;
+    let data = renderer.map_texture(&mapping).map_err(CaptureError::Rendering)?;
+
+    Ok(CapturedImage {
+        size,
+        data: to_straight_rgba(data, size, mapping.flipped()),
+    })
+}
+
+/// Renders a region of a set of elements directly into a client buffer
+///
+/// Works like [`capture_elements`], but writes into a `wl_buffer`, e.g. the buffer of a
+/// `wlr-screencopy` or `ext-image-copy-capture` frame. The size of the buffer has to match the size
+/// of `region`. The result is always upright, so frames never need to be marked as y-inverted.
+///
+/// Dmabufs, which can be bound by the renderer (see [`BindDmaWl::dmabuf_target`]), are rendered into
+/// directly without any intermediate copy. Shm buffers are rendered offscreen using their format
+/// and copied into the buffer afterwards.
+///
+/// The returned [`SyncPoint`] has to be reached, before the frame is submitted to the client.
+#[cfg(feature = "wayland_frontend")]
+#[profiling::function]
+pub fn capture_elements_into_buffer<R, T, E>(
+    renderer: &mut R,
+    buffer: &WlBuffer,
+    region: Rectangle<i32, Physical>,
+    scale: impl Into<Scale<f64>>,
+    elements: &[E],
+    clear_color: impl Into<Color32F>,
+) -> Result<SyncPoint, CaptureError<R::Error>>
+where
+    R: Renderer + BindDmaWl + Offscreen<T> + ExportMem,
+    R::TextureId: Texture + 'static,
+    E: RenderElement<R>,
+{
+    if region.is_empty() {
+        return Err(CaptureError::EmptyRegion);
+    }
+    let size = Size::<i32, BufferCoord>::from((region.size.w, region.size.h));
+
+    if let Some(mut dmabuf) = renderer.dmabuf_target(buffer) {
+        if dmabuf.size() != size {
+            return Err(CaptureError::SizeMismatch);
+        }
+        let mut framebuffer = renderer.bind(&mut dmabuf).map_err(CaptureError::Rendering)?;
+        return render_region(renderer, &mut framebuffer, region, scale, elements, clear_color);
+    }
+
+    let (format, buffer_size) = shm::with_buffer_contents(buffer, |_, _, data| {
+        (
+            data.format,
+            Size::<i32, BufferCoord>::from((data.width, data.height)),
+        )
+    })
+    .map_err(|_| CaptureError::UnsupportedBuffer)?;
+    let fourcc = shm::shm_format_to_fourcc(format).ok_or(CaptureError::UnsupportedBuffer)?;
+    if buffer_size != size {
+        return Err(CaptureError::SizeMismatch);
+    }
+
+    let mut target = renderer
+        .create_buffer(fourcc, size)
+        .map_err(CaptureError::Rendering)?;
+    let mut framebuffer = renderer.bind(&mut target).map_err(CaptureError::Rendering)?;
+    let sync = render_region(renderer, &mut framebuffer, region, scale, elements, clear_color)?;
+    renderer.wait(&sync).map_err(CaptureError::Rendering)?;
+
+    let mapping = renderer
+        .copy_framebuffer(&framebuffer, Rectangle::from_size(size), fourcc)
+        .map_err(CaptureError::Rendering)?;
+    let flipped = mapping.flipped();
+    let data = renderer.map_texture(&mapping).map_err(CaptureError::Rendering)?;
+
+    shm::with_buffer_contents_mut(buffer, |ptr, len, info| {
+        let row_len = data.len() / size.h as usize;
+        let end = info.offset as usize + info.stride as usize * (size.h as usize - 1) + row_len;
+        if info.stride < 0 || row_len > info.stride as usize || end > len {
+            return Err(CaptureError::UnsupportedBuffer);
+        }
+        // SAFETY: The range was checked to be part of the pool above
+        let dst = unsafe {
+            std::slice::from_raw_parts_mut(ptr.add(info.offset as usize), end - info.offset as usize)
+        };
+        copy_rows(data, row_len, dst, info.stride as usize, flipped);
+        Ok(SyncPoint::signaled())
+    })
+    .map_err(|_| CaptureError::UnsupportedBuffer)?
+}
+
+fn render_region<R, E>(
+    renderer: &mut R,
+    framebuffer: &mut R::Framebuffer<'_>,
+    region: Rectangle<i32, Physical>,
+    scale: impl Into<Scale<f64>>,
+    elements: &[E],
+    clear_color: impl Into<Color32F>,
+) -> Result<SyncPoint, CaptureError<R::Error>>
+where
+    R: Renderer,
+    R::TextureId: Texture + 'static,
+    E: RenderElement<R>,
+{
+    let elements = elements
+        .iter()
+        .map(|element| {
+            RelocateRenderElement::from_element(element, region.loc.upscale(-1), Relocate::Relative)
+        })
+        .collect::<Vec<_>>();
+    let mut damage_tracker = OutputDamageTracker::new(region.size, scale, Transform::Normal);
+    let result = damage_tracker
+        .render_output(renderer, framebuffer, 0, &elements, clear_color)
+        .map_err(|err| match err {
+            damage::Error::Rendering(err) => CaptureError::Rendering(err),
+            damage::Error::OutputNoMode(_) => unreachable!(),
+        })?;
+    Ok(result.sync)
+}
+
+/// Errors happening while capturing elements
+#[derive(Debug, thiserror::Error)]
+pub enum CaptureError<E: std::error::Error> {
+    /// The captured region is empty
+    #[error("The captured region is empty")]
+    EmptyRegion,
+    /// The buffer is neither a dmabuf supported by the renderer nor a valid shm buffer
+    #[error("The buffer is not supported for capturing")]
+    UnsupportedBuffer,
+    /// The size of the buffer does not match the size of the captured region
+    #[error("The buffer size does not match the captured region")]
+    SizeMismatch,
+    /// The provided [`Renderer`] returned an error
+    #[error(transparent)]
+    Rendering(E),
+}
+
+/// Image captured by [`capture_elements`]
+#[derive(Debug, Clone)]
+pub struct CapturedImage {
+    size: Size<i32, BufferCoord>,
+    data: Vec<u8>,
+}
+
+impl CapturedImage {
+    /// Size of the image in pixels
+    pub fn size(&self) -> Size<i32, BufferCoord> {
+        self.size
+    }
+
+    /// Pixels of the image in non-premultiplied RGBA order, row by row starting at the top
+    pub fn rgba(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns the pixels of the image, see [`CapturedImage::rgba`]
+    pub fn into_rgba(self) -> Vec<u8> {
+        self.data
+    }
+
+    /// Encodes the image as PNG
+    #[cfg(feature = "png")]
+    pub fn encode_png(&self) -> Result<Vec<u8>, png::EncodingError> {
+        let mut encoded = Vec::new();
+        let mut encoder = png::Encoder::new(&mut encoded, self.size.w as u32, self.size.h as u32);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.data)?;
+        writer.finish()?;
+        Ok(encoded)
+    }
+
+    /// Encodes the image as PNG on a separate thread
+    ///
+    /// `callback` is called from the event loop with the result, once the encoding finished.
+    #[cfg(feature = "png")]
+    pub fn encode_png_async<'l, D, F>(
+        self,
+        handle: &calloop::LoopHandle<'l, D>,
+        callback: F,
+    ) -> Result<(), calloop::Error>
+    where
+        F: FnOnce(&mut D, Result<Vec<u8>, png::EncodingError>) + 'l,
+    {
+        let (sender, receiver) = calloop::channel::channel();
+        let mut callback = Some(callback);
+        handle
+            .insert_source(receiver, move |event, _, state| {
+                if let calloop::channel::Event::Msg(result) = event {
+                    if let Some(callback) = callback.take() {
+                        callback(state, result);
+                    }
+                }
+            })
+            .map_err(|err| err.error)?;
+
+        std::thread::Builder::new()
+            .name("smithay-png-encoder".into())
+            .spawn(move || {
+                let _ = sender.send(self.encode_png());
+            })?;
+
+        Ok(())
+    }
+}
+
+/// Converts premultiplied RGBA pixels into straight alpha and flips them upright if necessary
+fn to_straight_rgba(data: &[u8], size: Size<i32, BufferCoord>, flipped: bool) -> Vec<u8> {
+    let stride = size.w as usize * 4;
+    let mut rows = data
+        .chunks_exact(stride)
+        .take(size.h as usize)
+        .collect::<Vec<_>>();
+    if flipped {
+        rows.reverse();
+    }
+
+    let mut out = Vec::with_capacity(stride * rows.len());
+    for pixel in rows.into_iter().flat_map(|row| row.chunks_exact(4)) {
+        let alpha = pixel[3] as u32;
+        if alpha == 0 {
+            out.extend([0, 0, 0, 0]);
+        } else {
+            let unpremultiply = |c: u8| ((c as u32 * 255 + alpha / 2) / alpha).min(255) as u8;
+            out.extend([
+                unpremultiply(pixel[0]),
+                unpremultiply(pixel[1]),
+                unpremultiply(pixel[2]),
+                pixel[3],
+            ]);
+        }
+    }
+    out
+}
+
+/// Copies rows of `row_len` bytes into a buffer with the given stride, flipping them if necessary
+#[cfg(feature = "wayland_frontend")]
+fn copy_rows(src: &[u8], row_len: usize, dst: &mut [u8], dst_stride: usize, flipped: bool) {
+    let rows = src.chunks_exact(row_len);
+    let height = rows.len();
+    for (i, row) in rows.enumerate() {
+        let y = if flipped { height - 1 - i } else { i };
+        dst[y * dst_stride..y * dst_stride + row_len].copy_from_slice(row);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn straight_alpha_and_flip() {
+        #[rustfmt::skip]
+        let data = [
+            255, 0, 0, 255,   64, 32, 0, 128,
+            0, 0, 0, 0,       10, 20, 30, 255,
+        ];
+
+        let upright = to_straight_rgba(&data, (2, 2).into(), false);
+        #[rustfmt::skip]
+        assert_eq!(upright, [
+            255, 0, 0, 255,   128, 64, 0, 128,
+            0, 0, 0, 0,       10, 20, 30, 255,
+        ]);
+
+        let flipped = to_straight_rgba(&data, (2, 2).into(), true);
+        assert_eq!(flipped[..8], upright[8..]);
+        assert_eq!(flipped[8..], upright[..8]);
+    }
+
+    #[cfg(feature = "wayland_frontend")]
+    #[test]
+    fn copy_rows_with_stride() {
+        let src = [1, 2, 3, 4, 5, 6];
+
+        // rows are padded to the stride, padding bytes are left untouched
+        let mut dst = [0; 8];
+        copy_rows(&src, 3, &mut dst, 4, false);
+        assert_eq!(dst, [1, 2, 3, 0, 4, 5, 6, 0]);
+
+        let mut dst = [9; 7];
+        copy_rows(&src, 3, &mut dst, 4, true);
+        assert_eq!(dst, [4, 5, 6, 9, 1, 2, 3]);
+    }
+}
@@ -0,0 +1,180 @@
+use std::collections::VecDeque;
+
+use crate::backend::renderer::sync::SyncPoint;
+
+/// Queue delaying the destruction of gpu resources until the gpu is done using them
+///
+/// Resources dropped while frames referencing them are still in flight must not be
+/// destroyed right away, e.g. destroying an `EGLImage` or closing a dmabuf still read by
+/// the gpu might result in glitches or stall the pipeline.
+///
+/// Every submitted frame is registered by its [`SyncPoint`] using [`DeferredDestruction::submit`].
+/// Resources passed to [`DeferredDestruction::defer`] are held back until all frames submitted
+/// up to that point have completed and are afterwards returned by [`DeferredDestruction::collect`].
+/// If no frame is in flight, resources are released right away.
+#[derive(Debug)]
+pub struct DeferredDestruction<T> {
+    // fences of frames in flight with their submission epoch, oldest first
+    fences: VecDeque<(u64, SyncPoint)>,
+    // deferred resources with the epoch they depend on, oldest first
+    pending: VecDeque<(u64, T)>,
+    epoch: u64,
+}
+
+impl<T> Default for DeferredDestruction<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> DeferredDestruction<T> {
+    /// Create an empty queue
+    pub fn new() -> Self {
+        DeferredDestruction {
+            fences: VecDeque::new(),
+            pending: VecDeque::new(),
+            epoch: 0,
+        }
+    }
+
+    /// Registers a submitted frame
+    ///
+    /// Resources deferred afterwards are kept at least until the `sync_point` is reached.
+    pub fn submit(&mut self, sync_point: SyncPoint) {
+        self.epoch += 1;
+        if !sync_point.is_reached() {
+            self.fences.push_back((self.epoch, sync_point));
+        }
+    }
+
+    /// Queues a resource for destruction once all frames submitted so far have completed
+    pub fn defer(&mut self, resource: T) {
+        self.pending.push_back((self.epoch, resource));
+    }
+
+    /// Returns the resources which can be destroyed now
+    ///
+    /// Does not block on any pending frames.
+    pub fn collect(&mut self) -> impl Iterator<Item = T> + '_ {
+        // frames complete in submission order, so stop at the first pending fence
+        while self
+            .fences
+            .front()
+            .map(|(_, sync_point)| sync_point.is_reached())
+            .unwrap_or(false)
+        {
+            self.fences.pop_front();
+        }
+        let completed = self
+            .fences
+            .front()
+            .map(|(epoch, _)| epoch - 1)
+            .unwrap_or(self.epoch);
+
+        let count = self
+            .pending
+            .iter()
+            .take_while(|(epoch, _)| *epoch <= completed)
+            .count();
+        self.pending.drain(..count).map(|(_, resource)| resource)
+    }
+
+    /// Returns all resources regardless of pending frames
+    ///
+    /// Useful on teardown, after waiting for the gpu to become idle.
+    pub fn drain(&mut self) -> impl Iterator<Item = T> + '_ {
+        self.fences.clear();
+        self.pending.drain(..).map(|(_, resource)| resource)
+    }
+
+    /// Returns the number of resources waiting for destruction
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Returns `true` if no resources are waiting for destruction
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        os::unix::io::OwnedFd,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+    };
+
+    use super::*;
+    use crate::backend::renderer::sync::{Fence, Interrupted};
+
+    #[derive(Debug, Clone, Default)]
+    struct TestFence(Arc<AtomicBool>);
+
+    impl TestFence {
+        fn signal(&self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    impl Fence for TestFence {
+        fn is_signaled(&self) -> bool {
+            self.0.load(Ordering::SeqCst)
+        }
+        fn wait(&self) -> Result<(), Interrupted> {
+            Ok(())
+        }
+        fn is_exportable(&self) -> bool {
+            false
+        }
+        fn export(&self) -> Option<OwnedFd> {
+            None
+        }
+    }
+
+    #[test]
+    fn released_after_frames_complete() {
+        let mut queue = DeferredDestruction::new();
+        queue.defer(0);
+        assert_eq!(queue.collect().collect::<Vec<_>>(), [0]);
+
+        let first = TestFence::default();
+        let second = TestFence::default();
+        queue.submit(SyncPoint::from(first.clone()));
+        queue.defer(1);
+        queue.submit(SyncPoint::from(second.clone()));
+        queue.defer(2);
+        assert_eq!(queue.collect().count(), 0);
+        assert_eq!(queue.len(), 2);
+
+        first.signal();
+        assert_eq!(queue.collect().collect::<Vec<_>>(), [1]);
+        second.signal();
+        assert_eq!(queue.collect().collect::<Vec<_>>(), [2]);
+
+        queue.submit(SyncPoint::signaled());
+        queue.defer(3);
+        assert_eq!(queue.collect().collect::<Vec<_>>(), [3]);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn out_of_order_signal() {
+        let mut queue = DeferredDestruction::new();
+        let first = TestFence::default();
+        let second = TestFence::default();
+        queue.submit(SyncPoint::from(first.clone()));
+        queue.defer(1);
+        queue.submit(SyncPoint::from(second.clone()));
+        queue.defer(2);
+
+        second.signal();
+        assert_eq!(queue.collect().count(), 0);
+        first.signal();
+        assert_eq!(queue.collect().collect::<Vec<_>>(), [1, 2]);
+    }
+}
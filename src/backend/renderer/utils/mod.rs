@@ -4,9 +4,15 @@
 use crate::utils::{Buffer as BufferCoord, Coordinate, Logical, Physical, Point, Rectangle, Size};
 use std::{collections::VecDeque, fmt, sync::Arc};
 
+mod capture;
+mod deferred;
 #[cfg(feature = "wayland_frontend")]
 mod wayland;
 #[cfg(feature = "wayland_frontend")]
+pub use self::capture::capture_elements_into_buffer;
+pub use self::capture::{capture_elements, CaptureError, CapturedImage};
+pub use self::deferred::DeferredDestruction;
+#[cfg(feature = "wayland_frontend")]
 pub use self::wayland::*;
 
 /// A simple wrapper for counting commits
@@ -16,6 +22,7 @@ pub use self::wayland::*;
 /// It provides an easy way to obtain the distance
 /// between two instances of a [`CommitCounter`].
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CommitCounter(usize);
 
 impl CommitCounter {
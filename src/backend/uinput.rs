@@ -0,0 +1,422 @@
+//! Virtual input devices using uinput
+//!
+//! [`UinputDevice`] creates a virtual input device in the kernel through `/dev/uinput` and feeds
+//! events into it. These events take the same path as the ones of physical devices: they show up
+//! at an evdev device node, are picked up by libinput (or the [`evdev`](super::evdev) backend) and
+//! reach the compositor as regular [`InputEvent`](super::input::InputEvent)s.
+//!
+//! This allows tests to exercise the full input stack of a compositor and automation tools to
+//! inject input below the compositor, e.g. to reproduce races between devices. Creating devices
+//! requires write access to `/dev/uinput`, which is usually restricted to root.
+//!
+//! ```no_run
+//! use smithay::backend::{input::KeyState, uinput::UinputDeviceBuilder};
+//!
+//! let mut keyboard = UinputDeviceBuilder::new("virtual keyboard").keyboard().build()?;
+//! // e.g. add the device node to a libinput context created with `Libinput::new_from_path`
+//! println!("created {:?}", keyboard.devnode());
+//!
+//! // type an "a"
+//! keyboard.key(30, KeyState::Pressed)?;
+//! keyboard.key(30, KeyState::Released)?;
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use std::{
+    ffi::CStr,
+    fs, io,
+    os::unix::io::OwnedFd,
+    path::{Path, PathBuf},
+    slice,
+};
+
+use rustix::{
+    fs::OFlags,
+    ioctl::{ioctl, IntegerSetter, NoArg, NoneOpcode, ReadOpcode, Setter, Updater, WriteOpcode},
+};
+use tracing::{info, warn};
+
+use crate::{
+    backend::{
+        evdev_sys::*,
+        input::{Axis, ButtonState, KeyState},
+    },
+    utils::{Point, Raw, Size},
+};
+
+const UINPUT_PATH: &str = "/dev/uinput";
+const SYSFS_VIRTUAL_INPUT: &str = "/sys/devices/virtual/input";
+
+type UinputCreate = NoneOpcode<b'U', 1, ()>;
+type UinputDestroy = NoneOpcode<b'U', 2, ()>;
+type UinputSetup = WriteOpcode<b'U', 3, libc::uinput_setup>;
+type UinputAbsSetup = WriteOpcode<b'U', 4, libc::uinput_abs_setup>;
+type UinputGetSysname = ReadOpcode<b'U', 44, [u8; 64]>;
+type UinputSetEvBit = WriteOpcode<b'U', 100, libc::c_int>;
+type UinputSetKeyBit = WriteOpcode<b'U', 101, libc::c_int>;
+type UinputSetRelBit = WriteOpcode<b'U', 102, libc::c_int>;
+type UinputSetAbsBit = WriteOpcode<b'U', 103, libc::c_int>;
+type UinputSetPropBit = WriteOpcode<b'U', 110, libc::c_int>;
+
+#[derive(Debug, Clone, Copy)]
+struct AbsAxis {
+    code: u16,
+    min: i32,
+    max: i32,
+}
+
+/// Builder for [`UinputDevice`]s
+///
+/// Keys, buttons and axes are given as evdev codes, see `linux/input-event-codes.h`.
+#[derive(Debug, Clone)]
+pub struct UinputDeviceBuilder {
+    name: String,
+    usb_id: Option<(u16, u16)>,
+    keys: Vec<u16>,
+    relative_axes: Vec<u16>,
+    absolute_axes: Vec<AbsAxis>,
+    properties: Vec<u16>,
+}
+
+impl UinputDeviceBuilder {
+    /// Starts building a device without any capabilities
+    pub fn new(name: impl Into<String>) -> UinputDeviceBuilder {
+        UinputDeviceBuilder {
+            name: name.into(),
+            usb_id: None,
+            keys: Vec::new(),
+            relative_axes: Vec::new(),
+            absolute_axes: Vec::new(),
+            properties: Vec::new(),
+        }
+    }
+
+    /// Pretends the device to be an usb device with the given ids
+    ///
+    /// This is useful to match device specific configuration, otherwise the device is reported
+    /// as a virtual device.
+    pub fn usb_id(mut self, vendor: u16, product: u16) -> Self {
+        self.usb_id = Some((vendor, product));
+        self
+    }
+
+    /// Adds the keys of a regular keyboard
+    pub fn keyboard(self) -> Self {
+        (KEY_ESC..=KEY_MICMUTE).fold(self, |builder, key| builder.key(key))
+    }
+
+    /// Adds the buttons, motion and wheels of a regular mouse
+    pub fn pointer(self) -> Self {
+        (BTN_LEFT..=BTN_EXTRA)
+            .fold(self, |builder, button| builder.key(button))
+            .relative_axis(REL_X)
+            .relative_axis(REL_Y)
+            .relative_axis(REL_WHEEL)
+            .relative_axis(REL_HWHEEL)
+    }
+
+    /// Adds the axes of a multi-touch touchscreen, tracking up to `slots` touch points
+    ///
+    /// Positions of touch points range from zero up to `size`.
+    pub fn touchscreen(self, size: Size<i32, Raw>, slots: u32) -> Self {
+        self.key(BTN_TOUCH)
+            .absolute_axis(ABS_X, 0, size.w)
+            .absolute_axis(ABS_Y, 0, size.h)
+            .absolute_axis(ABS_MT_SLOT, 0, slots.saturating_sub(1) as i32)
+            .absolute_axis(ABS_MT_TRACKING_ID, 0, u16::MAX as i32)
+            .absolute_axis(ABS_MT_POSITION_X, 0, size.w)
+            .absolute_axis(ABS_MT_POSITION_Y, 0, size.h)
+            .property(INPUT_PROP_DIRECT)
+    }
+
+    /// Adds a key or button
+    pub fn key(mut self, code: u16) -> Self {
+        if !self.keys.contains(&code) {
+            self.keys.push(code);
+        }
+        self
+    }
+
+    /// Adds a relative axis
+    pub fn relative_axis(mut self, code: u16) -> Self {
+        if !self.relative_axes.contains(&code) {
+            self.relative_axes.push(code);
+        }
+        self
+    }
+
+    /// Adds an absolute axis with the given range
+    pub fn absolute_axis(mut self, code: u16, min: i32, max: i32) -> Self {
+        self.absolute_axes.retain(|axis| axis.code != code);
+        self.absolute_axes.push(AbsAxis { code, min, max });
+        self
+    }
+
+    /// Adds an input property, e.g. `INPUT_PROP_DIRECT`
+    pub fn property(mut self, code: u16) -> Self {
+        if !self.properties.contains(&code) {
+            self.properties.push(code);
+        }
+        self
+    }
+
+    /// Creates the device
+    pub fn build(self) -> io::Result<UinputDevice> {
+        let fd = rustix::fs::open(
+            UINPUT_PATH,
+            OFlags::WRONLY | OFlags::CLOEXEC,
+            rustix::fs::Mode::empty(),
+        )?;
+
+        unsafe {
+            if !self.keys.is_empty() {
+                ioctl(&fd, IntegerSetter::<UinputSetEvBit>::new(EV_KEY as usize))?;
+            }
+            for key in &self.keys {
+                ioctl(&fd, IntegerSetter::<UinputSetKeyBit>::new(*key as usize))?;
+            }
+            if !self.relative_axes.is_empty() {
+                ioctl(&fd, IntegerSetter::<UinputSetEvBit>::new(EV_REL as usize))?;
+            }
+            for axis in &self.relative_axes {
+                ioctl(&fd, IntegerSetter::<UinputSetRelBit>::new(*axis as usize))?;
+            }
+            if !self.absolute_axes.is_empty() {
+                ioctl(&fd, IntegerSetter::<UinputSetEvBit>::new(EV_ABS as usize))?;
+            }
+            for axis in &self.absolute_axes {
+                ioctl(&fd, IntegerSetter::<UinputSetAbsBit>::new(axis.code as usize))?;
+                let setup = libc::uinput_abs_setup {
+                    code: axis.code,
+                    absinfo: libc::input_absinfo {
+                        value: 0,
+                        minimum: axis.min,
+                        maximum: axis.max,
+                        fuzz: 0,
+                        flat: 0,
+                        resolution: 0,
+                    },
+                };
+                ioctl(&fd, Setter::<UinputAbsSetup, _>::new(setup))?;
+            }
+            for property in &self.properties {
+                ioctl(&fd, IntegerSetter::<UinputSetPropBit>::new(*property as usize))?;
+            }
+
+            let mut setup: libc::uinput_setup = std::mem::zeroed();
+            setup.id = libc::input_id {
+                bustype: if self.usb_id.is_some() {
+                    BUS_USB
+                } else {
+                    BUS_VIRTUAL
+                },
+                vendor: self.usb_id.map(|(vendor, _)| vendor).unwrap_or(0),
+                product: self.usb_id.map(|(_, product)| product).unwrap_or(0),
+                version: 1,
+            };
+            // keep the terminating nul byte
+            for (dst, src) in setup
+                .name
+                .iter_mut()
+                .zip(self.name.bytes().take(libc::UINPUT_MAX_NAME_SIZE - 1))
+            {
+                *dst = src as libc::c_char;
+            }
+            ioctl(&fd, Setter::<UinputSetup, _>::new(setup))?;
+            ioctl(&fd, NoArg::<UinputCreate>::new())?;
+        }
+
+        let mut sysname = [0u8; 64];
+        unsafe { ioctl(&fd, Updater::<UinputGetSysname, _>::new(&mut sysname)) }?;
+        let sysname = CStr::from_bytes_until_nul(&sysname)
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        info!(name = self.name, sysname, "Created uinput device");
+
+        let slots = self
+            .absolute_axes
+            .iter()
+            .find(|axis| axis.code == ABS_MT_SLOT)
+            .map(|axis| axis.max.max(0) as usize + 1)
+            .unwrap_or(0);
+
+        Ok(UinputDevice {
+            fd,
+            sysname,
+            touch_points: vec![None; slots],
+            next_tracking_id: 0,
+        })
+    }
+}
+
+/// Virtual input device created through uinput
+///
+/// The device is removed, once this is dropped.
+#[derive(Debug)]
+pub struct UinputDevice {
+    fd: OwnedFd,
+    sysname: String,
+    // tracking ids of the multi-touch slots
+    touch_points: Vec<Option<i32>>,
+    next_tracking_id: i32,
+}
+
+impl UinputDevice {
+    /// Returns the syspath of the device, e.g. `/sys/devices/virtual/input/input42`
+    pub fn syspath(&self) -> PathBuf {
+        Path::new(SYSFS_VIRTUAL_INPUT).join(&self.sysname)
+    }
+
+    /// Returns the evdev device node of the device, e.g. `/dev/input/event12`
+    pub fn devnode(&self) -> Option<PathBuf> {
+        fs::read_dir(self.syspath())
+            .ok()?
+            .filter_map(Result::ok)
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .find(|name| name.starts_with("event"))
+            .map(|name| Path::new("/dev/input").join(name))
+    }
+
+    /// Emits raw evdev events, given as `(type, code, value)`, followed by a `SYN_REPORT`
+    pub fn emit(&mut self, events: &[(u16, u16, i32)]) -> io::Result<()> {
+        let events = events
+            .iter()
+            .copied()
+            .chain(std::iter::once((EV_SYN, SYN_REPORT, 0)))
+            .map(|(kind, code, value)| RawInputEvent {
+                // the kernel sets the timestamps of the events
+                sec: 0,
+                usec: 0,
+                kind,
+                code,
+                value,
+            })
+            .collect::<Vec<_>>();
+        // SAFETY: RawInputEvent is a plain old data type without padding
+        let bytes = unsafe { slice::from_raw_parts(events.as_ptr() as *const u8, events.len() * EVENT_SIZE) };
+
+        let written = rustix::io::write(&self.fd, bytes)?;
+        if written != bytes.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::WriteZero,
+                "Failed to write all events",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Presses or releases a key, given as evdev key code
+    ///
+    /// Note that these are the codes without the offset of 8 added by xkb, e.g. `KEY_A` is 30.
+    pub fn key(&mut self, key: u16, state: KeyState) -> io::Result<()> {
+        let value = match state {
+            KeyState::Pressed => 1,
+            KeyState::Released => 0,
+        };
+        self.emit(&[(EV_KEY, key, value)])
+    }
+
+    /// Presses or releases a button, given as evdev code, e.g. `BTN_LEFT` is `0x110`
+    pub fn button(&mut self, button: u16, state: ButtonState) -> io::Result<()> {
+        let value = match state {
+            ButtonState::Pressed => 1,
+            ButtonState::Released => 0,
+        };
+        self.emit(&[(EV_KEY, button, value)])
+    }
+
+    /// Moves the pointer relatively
+    pub fn motion(&mut self, delta: Point<i32, Raw>) -> io::Result<()> {
+        self.emit(&[(EV_REL, REL_X, delta.x), (EV_REL, REL_Y, delta.y)])
+    }
+
+    /// Scrolls the wheel of an axis by a number of clicks
+    ///
+    /// Positive values scroll down or right, like the axis events of wayland.
+    pub fn scroll(&mut self, axis: Axis, clicks: i32) -> io::Result<()> {
+        match axis {
+            // evdev scrolls up for positive values
+            Axis::Vertical => self.emit(&[(EV_REL, REL_WHEEL, -clicks)]),
+            Axis::Horizontal => self.emit(&[(EV_REL, REL_HWHEEL, clicks)]),
+        }
+    }
+
+    /// Puts down a new touch point in a multi-touch slot
+    pub fn touch_down(&mut self, slot: u32, position: Point<i32, Raw>) -> io::Result<()> {
+        let first = self.touch_points.iter().all(Option::is_none);
+        let id = self.next_tracking_id;
+        let point = self.touch_point(slot)?;
+        if point.is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Touch slot is in use",
+            ));
+        }
+        *point = Some(id);
+        self.next_tracking_id = (id + 1) % (u16::MAX as i32 + 1);
+
+        let mut events = vec![
+            (EV_ABS, ABS_MT_SLOT, slot as i32),
+            (EV_ABS, ABS_MT_TRACKING_ID, id),
+            (EV_ABS, ABS_MT_POSITION_X, position.x),
+            (EV_ABS, ABS_MT_POSITION_Y, position.y),
+        ];
+        if first {
+            events.extend([
+                (EV_KEY, BTN_TOUCH, 1),
+                (EV_ABS, ABS_X, position.x),
+                (EV_ABS, ABS_Y, position.y),
+            ]);
+        }
+        self.emit(&events)
+    }
+
+    /// Moves the touch point of a multi-touch slot
+    pub fn touch_motion(&mut self, slot: u32, position: Point<i32, Raw>) -> io::Result<()> {
+        if self.touch_point(slot)?.is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Touch slot is not in use",
+            ));
+        }
+
+        self.emit(&[
+            (EV_ABS, ABS_MT_SLOT, slot as i32),
+            (EV_ABS, ABS_MT_POSITION_X, position.x),
+            (EV_ABS, ABS_MT_POSITION_Y, position.y),
+        ])
+    }
+
+    /// Lifts the touch point of a multi-touch slot
+    pub fn touch_up(&mut self, slot: u32) -> io::Result<()> {
+        if self.touch_point(slot)?.take().is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Touch slot is not in use",
+            ));
+        }
+
+        let mut events = vec![
+            (EV_ABS, ABS_MT_SLOT, slot as i32),
+            (EV_ABS, ABS_MT_TRACKING_ID, -1),
+        ];
+        if self.touch_points.iter().all(Option::is_none) {
+            events.push((EV_KEY, BTN_TOUCH, 0));
+        }
+        self.emit(&events)
+    }
+
+    fn touch_point(&mut self, slot: u32) -> io::Result<&mut Option<i32>> {
+        self.touch_points
+            .get_mut(slot as usize)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Invalid touch slot"))
+    }
+}
+
+impl Drop for UinputDevice {
+    fn drop(&mut self) {
+        if let Err(err) = unsafe { ioctl(&self.fd, NoArg::<UinputDestroy>::new()) } {
+            warn!(?err, sysname = self.sysname, "Failed to destroy uinput device");
+        }
+    }
+}
@@ -211,7 +211,7 @@ pub struct WinitTouchStartedEvent {
     pub(crate) time: u64,
     pub(crate) position: RelativePosition,
     pub(crate) global_position: PhysicalPosition<f64>,
-    pub(crate) id: u64,
+    pub(crate) slot: TouchSlot,
 }
 
 impl Event<WinitInput> for WinitTouchStartedEvent {
@@ -228,7 +228,7 @@ impl TouchDownEvent<WinitInput> for WinitTouchStartedEvent {}
 
 impl TouchEvent<WinitInput> for WinitTouchStartedEvent {
     fn slot(&self) -> TouchSlot {
-        Some(self.id as u32).into()
+        self.slot
     }
 }
 
@@ -256,7 +256,7 @@ pub struct WinitTouchMovedEvent {
     pub(crate) time: u64,
     pub(crate) position: RelativePosition,
     pub(crate) global_position: PhysicalPosition<f64>,
-    pub(crate) id: u64,
+    pub(crate) slot: TouchSlot,
 }
 
 impl Event<WinitInput> for WinitTouchMovedEvent {
@@ -273,7 +273,7 @@ impl TouchMotionEvent<WinitInput> for WinitTouchMovedEvent {}
 
 impl TouchEvent<WinitInput> for WinitTouchMovedEvent {
     fn slot(&self) -> TouchSlot {
-        Some(self.id as u32).into()
+        self.slot
     }
 }
 
@@ -299,7 +299,7 @@ impl AbsolutePositionEvent<WinitInput> for WinitTouchMovedEvent {
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct WinitTouchEndedEvent {
     pub(crate) time: u64,
-    pub(crate) id: u64,
+    pub(crate) slot: TouchSlot,
 }
 
 impl Event<WinitInput> for WinitTouchEndedEvent {
@@ -316,7 +316,7 @@ impl TouchUpEvent<WinitInput> for WinitTouchEndedEvent {}
 
 impl TouchEvent<WinitInput> for WinitTouchEndedEvent {
     fn slot(&self) -> TouchSlot {
-        Some(self.id as u32).into()
+        self.slot
     }
 }
 
@@ -324,7 +324,7 @@ impl TouchEvent<WinitInput> for WinitTouchEndedEvent {
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct WinitTouchCancelledEvent {
     pub(crate) time: u64,
-    pub(crate) id: u64,
+    pub(crate) slot: TouchSlot,
 }
 
 impl Event<WinitInput> for WinitTouchCancelledEvent {
@@ -341,7 +341,7 @@ impl TouchCancelEvent<WinitInput> for WinitTouchCancelledEvent {}
 
 impl TouchEvent<WinitInput> for WinitTouchCancelledEvent {
     fn slot(&self) -> TouchSlot {
-        Some(self.id as u32).into()
+        self.slot
     }
 }
 
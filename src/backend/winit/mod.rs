@@ -45,7 +45,7 @@ use crate::{
             display::EGLDisplay,
             native, EGLContext, EGLSurface, Error as EGLError,
         },
-        input::InputEvent,
+        input::{InputEvent, TouchSlotMap},
         renderer::{
             gles::{GlesError, GlesRenderer},
             Bind,
@@ -204,6 +204,7 @@ where
                 key_counter: 0,
                 window,
                 is_x11,
+                touch_slots: TouchSlotMap::new(),
             },
             fake_token: None,
             event_loop,
@@ -360,6 +361,7 @@ struct WinitEventLoopInner {
     key_counter: u32,
     is_x11: bool,
     scale_factor: f64,
+    touch_slots: TouchSlotMap,
 }
 
 /// Abstracted event loop of a [`WinitWindow`].
@@ -517,6 +519,7 @@ impl<F: FnMut(WinitEvent)> ApplicationHandler for WinitEventLoopApp<'_, F> {
                 id,
                 ..
             }) => {
+                let slot = self.inner.touch_slots.down(id);
                 let size = self.inner.window.inner_size();
                 let x = location.x / size.width as f64;
                 let y = location.y / size.width as f64;
@@ -525,7 +528,7 @@ impl<F: FnMut(WinitEvent)> ApplicationHandler for WinitEventLoopApp<'_, F> {
                         time: self.timestamp(),
                         global_position: location,
                         position: RelativePosition::new(x, y),
-                        id,
+                        slot,
                     },
                 };
 
@@ -537,6 +540,9 @@ impl<F: FnMut(WinitEvent)> ApplicationHandler for WinitEventLoopApp<'_, F> {
                 id,
                 ..
             }) => {
+                let Some(slot) = self.inner.touch_slots.get(id) else {
+                    return;
+                };
                 let size = self.inner.window.inner_size();
                 let x = location.x / size.width as f64;
                 let y = location.y / size.width as f64;
@@ -545,7 +551,7 @@ impl<F: FnMut(WinitEvent)> ApplicationHandler for WinitEventLoopApp<'_, F> {
                         time: self.timestamp(),
                         position: RelativePosition::new(x, y),
                         global_position: location,
-                        id,
+                        slot,
                     },
                 };
 
@@ -558,6 +564,9 @@ impl<F: FnMut(WinitEvent)> ApplicationHandler for WinitEventLoopApp<'_, F> {
                 id,
                 ..
             }) => {
+                let Some(slot) = self.inner.touch_slots.up(id) else {
+                    return;
+                };
                 let size = self.inner.window.inner_size();
                 let x = location.x / size.width as f64;
                 let y = location.y / size.width as f64;
@@ -566,7 +575,7 @@ impl<F: FnMut(WinitEvent)> ApplicationHandler for WinitEventLoopApp<'_, F> {
                         time: self.timestamp(),
                         position: RelativePosition::new(x, y),
                         global_position: location,
-                        id,
+                        slot,
                     },
                 };
                 (self.callback)(WinitEvent::Input(event));
@@ -574,7 +583,7 @@ impl<F: FnMut(WinitEvent)> ApplicationHandler for WinitEventLoopApp<'_, F> {
                 let event = InputEvent::TouchUp {
                     event: WinitTouchEndedEvent {
                         time: self.timestamp(),
-                        id,
+                        slot,
                     },
                 };
 
@@ -586,10 +595,13 @@ impl<F: FnMut(WinitEvent)> ApplicationHandler for WinitEventLoopApp<'_, F> {
                 id,
                 ..
             }) => {
+                let Some(slot) = self.inner.touch_slots.up(id) else {
+                    return;
+                };
                 let event = InputEvent::TouchCancel {
                     event: WinitTouchCancelledEvent {
                         time: self.timestamp(),
-                        id,
+                        slot,
                     },
                 };
                 (self.callback)(WinitEvent::Input(event));
@@ -0,0 +1,694 @@
+//! Dumps of the scene for bug reports
+//!
+//! Layout and damage issues often depend on the exact state of the outputs, the mapped elements and
+//! the surface trees of clients, which is hard to reproduce from a description in a bug report.
+//! A [`SceneDump`] captures this state in plain data structures, which can be serialized and deserialized
+//! with the `serde` feature, e.g. to attach them as json to an issue.
+//!
+//! [`SceneDump::render_elements`] turns a dump back into render elements, drawing every surface as a
+//! translucent colored rectangle. Those can be rendered offline, e.g. with the pixman renderer,
+//! without any clients connected. Replaying a series of dumps with the same [`SceneReplay`]
+//! keeps the ids of the elements, so the damage between the recorded frames is reproduced as well.
+//!
+//! ```no_run
+//! # use smithay::desktop::{Space, Window};
+//! use smithay::{
+//!     desktop::dump::{dump_surface_tree, SceneDump, SceneReplay},
+//!     wayland::seat::WaylandFocus,
+//! };
+//!
+//! # let space: Space<Window> = Space::default();
+//! let mut dump = SceneDump::default();
+//! dump.add_space_with(&space, |window| {
+//!     window
+//!         .wl_surface()
+//!         .map(|surface| dump_surface_tree(&surface))
+//!         .unwrap_or_default()
+//! });
+//!
+//! // later, e.g. in a test reproducing the issue
+//! if let Some(output) = dump.output("eDP-1") {
+//!     let mut damage_tracker = output.damage_tracker();
+//!     let mut replay = SceneReplay::default();
+//!     let elements = dump.render_elements(&mut replay, "eDP-1");
+//!     // damage_tracker.render_output(&mut renderer, &mut framebuffer, 0, &elements, [0.0, 0.0, 0.0, 1.0])
+//! }
+//! ```
+
+use std::collections::{HashMap, HashSet};
+
+#[cfg(feature = "wayland_frontend")]
+use wayland_server::{protocol::wl_surface::WlSurface, Resource};
+
+use crate::{
+    backend::renderer::{
+        damage::OutputDamageTracker,
+        element::{solid::SolidColorRenderElement, Id, Kind},
+        utils::CommitCounter,
+        Color32F,
+    },
+    output::Output,
+    utils::{Buffer as BufferCoord, Logical, Physical, Point, Rectangle, Size, Transform},
+};
+
+#[cfg(feature = "wayland_frontend")]
+use crate::{
+    backend::renderer::{buffer_dimensions, utils::RendererSurfaceStateUserData},
+    wayland::compositor::{
+        with_surface_tree_upward, BufferAssignment, Damage, RectangleKind, RegionAttributes,
+        SubsurfaceCachedState, SurfaceAttributes, SurfaceData, TraversalAction, SUBSURFACE_ROLE,
+    },
+};
+
+use super::{
+    space::{SpaceElement, SpaceElementId},
+    Space,
+};
+
+// Colors of the rendered elements, cycled through in stacking order
+const PALETTE: [Color32F; 6] = [
+    Color32F::new(0.9, 0.3, 0.3, 1.0),
+    Color32F::new(0.3, 0.8, 0.3, 1.0),
+    Color32F::new(0.3, 0.4, 0.9, 1.0),
+    Color32F::new(0.9, 0.8, 0.2, 1.0),
+    Color32F::new(0.8, 0.3, 0.9, 1.0),
+    Color32F::new(0.2, 0.8, 0.9, 1.0),
+];
+const SURFACE_ALPHA: f32 = 0.5;
+
+/// Snapshot of the outputs and spaces of a compositor
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SceneDump {
+    /// Outputs mapped in any of the dumped spaces
+    pub outputs: Vec<OutputDump>,
+    /// Dumped spaces, in the order they were added
+    pub spaces: Vec<SpaceDump>,
+}
+
+/// Snapshot of the state of an [`Output`]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OutputDump {
+    /// Name of the output
+    pub name: String,
+    /// Description of the output
+    pub description: String,
+    /// Current mode of the output
+    pub mode: Option<ModeDump>,
+    /// Current transform of the output
+    pub transform: Transform,
+    /// Current fractional scale of the output
+    pub scale: f64,
+}
+
+/// Snapshot of an output [`Mode`](crate::output::Mode)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ModeDump {
+    /// Size of the mode in pixels
+    pub size: Size<i32, Physical>,
+    /// Refresh rate in millihertz
+    pub refresh: i32,
+}
+
+/// Snapshot of a [`Space`]
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpaceDump {
+    /// Id of the space, see [`Space::id`]
+    pub id: usize,
+    /// Outputs mapped in the space
+    pub outputs: Vec<MappedOutputDump>,
+    /// Elements mapped in the space in z-order back to front
+    pub elements: Vec<ElementDump>,
+}
+
+/// Output mapped in a [`SpaceDump`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MappedOutputDump {
+    /// Name of the output, matching an [`OutputDump`]
+    pub name: String,
+    /// Geometry of the output in the space
+    pub geometry: Rectangle<i32, Logical>,
+}
+
+/// Snapshot of an element mapped in a [`Space`]
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ElementDump {
+    /// Stable id of the element in the space, see [`Space::element_id`]
+    pub id: Option<SpaceElementId>,
+    /// Location of the element in the space
+    pub location: Point<i32, Logical>,
+    /// Location the surfaces of the element are rendered at in the space
+    pub render_location: Point<i32, Logical>,
+    /// Geometry of the element in the space
+    pub geometry: Rectangle<i32, Logical>,
+    /// Bounding box of the element in the space
+    pub bbox: Rectangle<i32, Logical>,
+    /// Z-index of the element
+    pub z_index: u8,
+    /// Surfaces of the element in z-order back to front
+    pub surfaces: Vec<SurfaceDump>,
+}
+
+/// Snapshot of a surface of an element
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SurfaceDump {
+    /// Protocol id of the surface
+    pub id: u32,
+    /// Protocol id of the parent surface, if this surface is a subsurface
+    pub parent: Option<u32>,
+    /// Role of the surface
+    pub role: Option<String>,
+    /// Location of the surface relative to the render location of its element
+    pub location: Point<i32, Logical>,
+    /// Size of the surface, if a buffer is attached
+    pub size: Option<Size<i32, Logical>>,
+    /// Commit counter of the surface contents
+    pub commit: CommitCounter,
+    /// Current state of the surface
+    pub current: SurfaceStateDump,
+    /// Pending state of the surface, which is not yet committed
+    pub pending: SurfaceStateDump,
+}
+
+/// Snapshot of the double-buffered state of a surface
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SurfaceStateDump {
+    /// Buffer attached in this state
+    pub buffer: Option<BufferDump>,
+    /// Offset of the attached buffer
+    pub buffer_delta: Option<Point<i32, Logical>>,
+    /// Scale of the buffer
+    pub buffer_scale: i32,
+    /// Transform of the buffer
+    pub buffer_transform: Transform,
+    /// Opaque region of the surface
+    pub opaque_region: Option<Vec<RegionRectDump>>,
+    /// Input region of the surface
+    pub input_region: Option<Vec<RegionRectDump>>,
+    /// Damage accumulated in this state
+    pub damage: Vec<DamageDump>,
+    /// Number of frame callbacks not yet sent
+    pub frame_callbacks: usize,
+}
+
+/// Snapshot of a buffer attached to a surface
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BufferDump {
+    /// The buffer of the surface was removed
+    Removed,
+    /// A new buffer was attached
+    Attached {
+        /// Dimensions of the buffer, if the buffer type is known
+        size: Option<Size<i32, BufferCoord>>,
+    },
+}
+
+/// Rectangle of a region of a surface
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RegionRectDump {
+    /// The rectangle is subtracted from the region instead of added
+    pub subtract: bool,
+    /// The rectangle in surface coordinates
+    pub rect: Rectangle<i32, Logical>,
+}
+
+/// Damage of a surface
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DamageDump {
+    /// Damage in surface coordinates
+    Surface(Rectangle<i32, Logical>),
+    /// Damage in buffer coordinates
+    Buffer(Rectangle<i32, BufferCoord>),
+}
+
+impl SceneDump {
+    /// Adds a [`Space`] and its outputs to the dump, without recording any surfaces
+    pub fn add_space<E: SpaceElement + PartialEq>(&mut self, space: &Space<E>) {
+        self.add_space_with(space, |_| Vec::new());
+    }
+
+    /// Adds a [`Space`] and its outputs to the dump
+    ///
+    /// The surfaces of every element are recorded by calling `surfaces`,
+    /// usually with [`dump_surface_tree`].
+    pub fn add_space_with<E, F>(&mut self, space: &Space<E>, mut surfaces: F)
+    where
+        E: SpaceElement + PartialEq,
+        F: FnMut(&E) -> Vec<SurfaceDump>,
+    {
+        let mut outputs = Vec::new();
+        for output in space.outputs() {
+            let Some(geometry) = space.output_geometry(output) else {
+                continue;
+            };
+            let name = output.name();
+            if self.output(&name).is_none() {
+                self.outputs.push(OutputDump::from(output));
+            }
+            outputs.push(MappedOutputDump { name, geometry });
+        }
+
+        let elements = space
+            .elements()
+            .filter_map(|element| {
+                let location = space.element_location(element)?;
+                let geometry = space.element_geometry(element)?;
+                let bbox = space.element_bbox(element)?;
+                Some(ElementDump {
+                    id: space.element_id(element),
+                    location,
+                    render_location: location - element.geometry().loc,
+                    geometry,
+                    bbox,
+                    z_index: element.z_index(),
+                    surfaces: surfaces(element),
+                })
+            })
+            .collect();
+
+        self.spaces.push(SpaceDump {
+            id: space.id(),
+            outputs,
+            elements,
+        });
+    }
+
+    /// Returns the dumped output with the given name
+    pub fn output(&self, name: &str) -> Option<&OutputDump> {
+        self.outputs.iter().find(|output| output.name == name)
+    }
+
+    /// Creates render elements for an output of the dump, in z-order front to back
+    ///
+    /// Every surface with a known size is drawn as a translucent rectangle, colored by the element
+    /// it belongs to. Elements without any recorded surfaces are drawn by their geometry instead.
+    /// Spaces added later are drawn above spaces added earlier.
+    ///
+    /// The ids of the elements are kept in `replay`, pass the same [`SceneReplay`] for every dump
+    /// of a recorded series to reproduce the damage between them.
+    pub fn render_elements(&self, replay: &mut SceneReplay, output: &str) -> Vec<SolidColorRenderElement> {
+        let scale = self.output(output).map(|o| o.scale).unwrap_or(1.0);
+
+        let mut render_elements = Vec::new();
+        for space in self.spaces.iter().rev() {
+            let Some(mapped) = space.outputs.iter().find(|o| o.name == output) else {
+                continue;
+            };
+
+            for (index, element) in space.elements.iter().enumerate().rev() {
+                let color = PALETTE[index % PALETTE.len()];
+                let key = ReplayKey::element(space, index, element);
+                let rects: Vec<_> = if element.surfaces.is_empty() {
+                    vec![(key, element.geometry, CommitCounter::default(), color)]
+                } else {
+                    element
+                        .surfaces
+                        .iter()
+                        .rev()
+                        .filter_map(|surface| {
+                            let loc = element.render_location + surface.location;
+                            Some((
+                                ReplayKey {
+                                    surface: Some(surface.id),
+                                    ..key
+                                },
+                                Rectangle::new(loc, surface.size?),
+                                surface.commit,
+                                color * SURFACE_ALPHA,
+                            ))
+                        })
+                        .collect()
+                };
+
+                for (key, rect, commit, color) in rects {
+                    if !rect.overlaps(mapped.geometry) {
+                        continue;
+                    }
+                    let geometry = Rectangle::new(
+                        (rect.loc - mapped.geometry.loc).to_physical_precise_round(scale),
+                        rect.size.to_physical_precise_round(scale),
+                    );
+                    render_elements.push(SolidColorRenderElement::new(
+                        replay.id(key),
+                        geometry,
+                        commit,
+                        color,
+                        Kind::Unspecified,
+                    ));
+                }
+            }
+        }
+        replay.retain(self);
+        render_elements
+    }
+}
+
+/// Element ids used to replay a series of [`SceneDump`]s
+///
+/// See [`SceneDump::render_elements`].
+#[derive(Debug, Default)]
+pub struct SceneReplay {
+    ids: HashMap<ReplayKey, Id>,
+}
+
+// Identifies an element or surface of a dump across dumps
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct ReplayKey {
+    space: usize,
+    element: ElementKey,
+    surface: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum ElementKey {
+    Id(SpaceElementId),
+    // elements dumped without an id are identified by their position in the stack
+    Index(usize),
+}
+
+impl ReplayKey {
+    fn element(space: &SpaceDump, index: usize, element: &ElementDump) -> Self {
+        ReplayKey {
+            space: space.id,
+            element: element.id.map(ElementKey::Id).unwrap_or(ElementKey::Index(index)),
+            surface: None,
+        }
+    }
+}
+
+impl SceneReplay {
+    /// Creates a new replay without any known elements
+    pub fn new() -> Self {
+        SceneReplay::default()
+    }
+
+    fn id(&mut self, key: ReplayKey) -> Id {
+        self.ids.entry(key).or_insert_with(Id::new).clone()
+    }
+
+    // Forgets the ids of elements and surfaces, which are not part of the dump anymore
+    fn retain(&mut self, dump: &SceneDump) {
+        let keys: HashSet<ReplayKey> = dump
+            .spaces
+            .iter()
+            .flat_map(|space| {
+                space
+                    .elements
+                    .iter()
+                    .enumerate()
+                    .flat_map(move |(index, element)| {
+                        let key = ReplayKey::element(space, index, element);
+                        std::iter::once(key).chain(element.surfaces.iter().map(move |surface| ReplayKey {
+                            surface: Some(surface.id),
+                            ..key
+                        }))
+                    })
+            })
+            .collect();
+        self.ids.retain(|key, _| keys.contains(key));
+    }
+}
+
+impl OutputDump {
+    /// Creates a damage tracker matching the mode, transform and scale of the output
+    ///
+    /// Outputs without a mode are treated as having a size of zero.
+    pub fn damage_tracker(&self) -> OutputDamageTracker {
+        let size = self.mode.map(|mode| mode.size).unwrap_or_default();
+        OutputDamageTracker::new(size, self.scale, self.transform)
+    }
+}
+
+impl From<&Output> for OutputDump {
+    #[inline]
+    fn from(output: &Output) -> Self {
+        OutputDump {
+            name: output.name(),
+            description: output.description(),
+            mode: output.current_mode().map(|mode| ModeDump {
+                size: mode.size,
+                refresh: mode.refresh,
+            }),
+            transform: output.current_transform(),
+            scale: output.current_scale().fractional_scale(),
+        }
+    }
+}
+
+/// Records a surface and its subsurfaces in z-order back to front
+#[cfg(feature = "wayland_frontend")]
+pub fn dump_surface_tree(surface: &WlSurface) -> Vec<SurfaceDump> {
+    fn location(states: &SurfaceData, parent: Point<i32, Logical>) -> Point<i32, Logical> {
+        if states.role == Some(SUBSURFACE_ROLE) {
+            parent
+                + states
+                    .cached_state
+                    .get::<SubsurfaceCachedState>()
+                    .current()
+                    .location
+        } else {
+            parent
+        }
+    }
+
+    let mut surfaces = Vec::new();
+    with_surface_tree_upward(
+        surface,
+        (Point::default(), None),
+        |surface, states, (parent_location, _)| {
+            TraversalAction::DoChildren((
+                location(states, *parent_location),
+                Some(surface.id().protocol_id()),
+            ))
+        },
+        |surface, states, (parent_location, parent)| {
+            let (size, commit) = states
+                .data_map
+                .get::<RendererSurfaceStateUserData>()
+                .map(|state| {
+                    let state = state.lock().unwrap();
+                    (state.surface_size(), state.current_commit())
+                })
+                .unwrap_or_default();
+            let mut attributes = states.cached_state.get::<SurfaceAttributes>();
+            let current = SurfaceStateDump::from(&*attributes.current());
+            let pending = SurfaceStateDump::from(&*attributes.pending());
+            surfaces.push(SurfaceDump {
+                id: surface.id().protocol_id(),
+                parent: *parent,
+                role: states.role.map(String::from),
+                location: location(states, *parent_location),
+                size,
+                commit,
+                current,
+                pending,
+            });
+        },
+        |_, _, _| true,
+    );
+    surfaces
+}
+
+#[cfg(feature = "wayland_frontend")]
+impl From<&SurfaceAttributes> for SurfaceStateDump {
+    #[inline]
+    fn from(attributes: &SurfaceAttributes) -> Self {
+        fn region(region: &Option<RegionAttributes>) -> Option<Vec<RegionRectDump>> {
+            region.as_ref().map(|region| {
+                region
+                    .rects
+                    .iter()
+                    .map(|(kind, rect)| RegionRectDump {
+                        subtract: matches!(kind, RectangleKind::Subtract),
+                        rect: *rect,
+                    })
+                    .collect()
+            })
+        }
+
+        SurfaceStateDump {
+            buffer: attributes.buffer.as_ref().map(|buffer| match buffer {
+                BufferAssignment::Removed => BufferDump::Removed,
+                BufferAssignment::NewBuffer(buffer) => BufferDump::Attached {
+                    size: buffer_dimensions(buffer),
+                },
+            }),
+            buffer_delta: attributes.buffer_delta,
+            buffer_scale: attributes.buffer_scale,
+            buffer_transform: attributes.buffer_transform.into(),
+            opaque_region: region(&attributes.opaque_region),
+            input_region: region(&attributes.input_region),
+            damage: attributes
+                .damage
+                .iter()
+                .map(|damage| match damage {
+                    Damage::Surface(rect) => DamageDump::Surface(*rect),
+                    Damage::Buffer(rect) => DamageDump::Buffer(*rect),
+                })
+                .collect(),
+            frame_callbacks: attributes.frame_callbacks.len(),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "renderer_test"))]
+mod tests {
+    use super::*;
+    use crate::backend::renderer::element::Element;
+
+    fn scene() -> SceneDump {
+        SceneDump {
+            outputs: vec![OutputDump {
+                name: "DP-1".into(),
+                description: String::new(),
+                mode: Some(ModeDump {
+                    size: (2000, 1000).into(),
+                    refresh: 60_000,
+                }),
+                transform: Transform::Normal,
+                scale: 2.0,
+            }],
+            spaces: vec![SpaceDump {
+                id: 0,
+                outputs: vec![MappedOutputDump {
+                    name: "DP-1".into(),
+                    geometry: Rectangle::new((100, 0).into(), (1000, 500).into()),
+                }],
+                elements: vec![
+                    ElementDump {
+                        geometry: Rectangle::new((150, 50).into(), (100, 100).into()),
+                        ..Default::default()
+                    },
+                    ElementDump {
+                        location: (300, 100).into(),
+                        render_location: (290, 90).into(),
+                        surfaces: vec![
+                            SurfaceDump {
+                                id: 1,
+                                size: Some((50, 50).into()),
+                                ..Default::default()
+                            },
+                            SurfaceDump {
+                                id: 2,
+                                parent: Some(1),
+                                location: (10, 20).into(),
+                                size: Some((20, 20).into()),
+                                ..Default::default()
+                            },
+                            // not yet mapped
+                            SurfaceDump {
+                                id: 3,
+                                parent: Some(1),
+                                ..Default::default()
+                            },
+                        ],
+                        ..Default::default()
+                    },
+                    // outside of the output
+                    ElementDump {
+                        geometry: Rectangle::new((0, 0).into(), (50, 50).into()),
+                        ..Default::default()
+                    },
+                ],
+            }],
+        }
+    }
+
+    #[test]
+    fn render_scaled_output() {
+        let dump = scene();
+        let mut replay = SceneReplay::new();
+        let elements = dump.render_elements(&mut replay, "DP-1");
+        let geometries: Vec<_> = elements.iter().map(|e| e.geometry(1.0.into())).collect();
+        assert_eq!(
+            geometries,
+            vec![
+                Rectangle::new((400, 220).into(), (40, 40).into()),
+                Rectangle::new((380, 180).into(), (100, 100).into()),
+                Rectangle::new((100, 100).into(), (200, 200).into()),
+            ]
+        );
+        assert!(dump.render_elements(&mut replay, "HDMI-A-1").is_empty());
+
+        let mut damage_tracker = dump.output("DP-1").unwrap().damage_tracker();
+        let mut renderer = crate::backend::renderer::test::DummyRenderer;
+        let mut framebuffer = crate::backend::renderer::test::DummyFramebuffer;
+        let result = damage_tracker
+            .render_output(
+                &mut renderer,
+                &mut framebuffer,
+                0,
+                &elements,
+                [0.0, 0.0, 0.0, 1.0],
+            )
+            .unwrap();
+        assert!(result.damage.is_some());
+    }
+
+    #[test]
+    fn replay_keeps_ids() {
+        let mut dump = scene();
+        for (element, id) in dump.spaces[0].elements.iter_mut().zip(1..) {
+            element.id = Some(SpaceElementId(id));
+        }
+        let mut replay = SceneReplay::new();
+        let mut damage_tracker = dump.output("DP-1").unwrap().damage_tracker();
+        let mut renderer = crate::backend::renderer::test::DummyRenderer;
+        let mut framebuffer = crate::backend::renderer::test::DummyFramebuffer;
+        let mut age = 0;
+        let mut render = |dump: &SceneDump, replay: &mut SceneReplay| {
+            let elements = dump.render_elements(replay, "DP-1");
+            let result = damage_tracker
+                .render_output(
+                    &mut renderer,
+                    &mut framebuffer,
+                    age,
+                    &elements,
+                    [0.0, 0.0, 0.0, 1.0],
+                )
+                .unwrap()
+                .damage
+                .cloned();
+            age = 1;
+            result
+        };
+
+        assert!(render(&dump, &mut replay).is_some());
+        // an unchanged scene does not damage anything
+        assert_eq!(render(&dump, &mut replay), None);
+
+        // a new commit of the subsurface only damages the subsurface
+        let mut committed = dump.clone();
+        committed.spaces[0].elements[1].surfaces[1].commit.increment();
+        assert_eq!(
+            render(&committed, &mut replay),
+            Some(vec![Rectangle::new((400, 220).into(), (40, 40).into())])
+        );
+
+        // restacking does not change the ids of the elements
+        let mut restacked = committed.clone();
+        restacked.spaces[0].elements.swap(0, 2);
+        let ids: Vec<_> = committed
+            .render_elements(&mut replay, "DP-1")
+            .iter()
+            .map(|e| e.id().clone())
+            .collect();
+        let mut restacked_ids: Vec<_> = restacked
+            .render_elements(&mut replay, "DP-1")
+            .iter()
+            .map(|e| e.id().clone())
+            .collect();
+        restacked_ids.sort_by_key(|id| ids.iter().position(|i| i == id));
+        assert_eq!(ids, restacked_ids);
+    }
+}
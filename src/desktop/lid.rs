@@ -0,0 +1,257 @@
+//! Disabling the built-in panel of a laptop while its lid is closed
+//!
+//! [`LidPolicy`] reacts to [`Switch::Lid`] toggles and implements the usual laptop behavior:
+//! closing the lid while external outputs are connected disables the built-in panel,
+//! opening the lid enables it again. Without external outputs the panel is left alone,
+//! so the compositor can suspend the system instead.
+//!
+//! The policy maintains the [`Space`]: the built-in output is unmapped while disabled and elements only
+//! visible on it are migrated to the first external output. When the panel is enabled again, it is
+//! mapped at its previous location and elements, that were not moved in the meantime, are moved back.
+//! Disabling and enabling the panel itself is left to the backend, as indicated by the returned [`LidAction`].
+//!
+//! ```no_run
+//! # use smithay::backend::input::{Switch, SwitchState};
+//! # use smithay::desktop::{lid::{LidAction, LidPolicy}, Space, Window};
+//! # use smithay::output::Output;
+//! # fn example(internal: Output, space: &mut Space<Window>, switch: Option<Switch>, state: SwitchState) {
+//! let mut policy = LidPolicy::new(internal);
+//!
+//! // on a switch toggle event
+//! match policy.switch_toggled(switch, state, space) {
+//!     Some(LidAction::DisableInternal) => { /* disable the connector of the panel */ }
+//!     Some(LidAction::EnableInternal) => { /* enable the connector of the panel */ }
+//!     None => {}
+//! }
+//!
+//! // after connecting or disconnecting an external output
+//! let action = policy.update(space);
+//! # }
+//! ```
+
+use crate::{
+    backend::input::{Switch, SwitchState},
+    output::Output,
+    utils::{Logical, Point, Rectangle},
+};
+
+use super::space::{Space, SpaceElement};
+
+/// Change of the built-in panel requested by the [`LidPolicy`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LidAction {
+    /// The built-in panel was unmapped and should be disabled
+    DisableInternal,
+    /// The built-in panel was mapped again and should be enabled
+    EnableInternal,
+}
+
+#[derive(Debug)]
+struct MigratedElement<E> {
+    element: E,
+    original: Point<f64, Logical>,
+    migrated: Point<f64, Logical>,
+}
+
+#[derive(Debug)]
+struct DisabledPanel<E> {
+    location: Option<Point<i32, Logical>>,
+    migrated: Vec<MigratedElement<E>>,
+}
+
+/// Disables the built-in panel while the lid is closed and external outputs are connected
+///
+/// See the [module docs](self) for details.
+#[derive(Debug)]
+pub struct LidPolicy<E> {
+    internal: Output,
+    lid_closed: bool,
+    disabled: Option<DisabledPanel<E>>,
+}
+
+impl<E: SpaceElement + PartialEq + Clone> LidPolicy<E> {
+    /// Create a policy for the given built-in output, assuming an open lid
+    pub fn new(internal: Output) -> Self {
+        LidPolicy {
+            internal,
+            lid_closed: false,
+            disabled: None,
+        }
+    }
+
+    /// Returns the built-in output
+    pub fn internal_output(&self) -> &Output {
+        &self.internal
+    }
+
+    /// Returns whether the lid is closed
+    pub fn is_lid_closed(&self) -> bool {
+        self.lid_closed
+    }
+
+    /// Returns whether the built-in panel is disabled by the policy
+    pub fn is_internal_disabled(&self) -> bool {
+        self.disabled.is_some()
+    }
+
+    /// Handle a switch toggle event, ignoring switches other than [`Switch::Lid`]
+    pub fn switch_toggled(
+        &mut self,
+        switch: Option<Switch>,
+        state: SwitchState,
+        space: &mut Space<E>,
+    ) -> Option<LidAction> {
+        if switch != Some(Switch::Lid) {
+            return None;
+        }
+        self.lid_closed = state == SwitchState::On;
+        self.update(space)
+    }
+
+    /// Re-evaluate the policy, e.g. after external outputs were mapped or unmapped
+    pub fn update(&mut self, space: &mut Space<E>) -> Option<LidAction> {
+        let has_external = space.outputs().any(|output| output != &self.internal);
+        match (self.disabled.is_some(), self.lid_closed && has_external) {
+            (false, true) => {
+                self.disable(space);
+                Some(LidAction::DisableInternal)
+            }
+            (true, false) => {
+                self.enable(space);
+                Some(LidAction::EnableInternal)
+            }
+            _ => None,
+        }
+    }
+
+    fn disable(&mut self, space: &mut Space<E>) {
+        let internal_geo = space.output_geometry(&self.internal);
+        let external_geo = space
+            .outputs()
+            .filter(|output| *output != &self.internal)
+            .filter_map(|output| space.output_geometry(output))
+            .collect::<Vec<_>>();
+
+        let mut migrated = Vec::new();
+        if let (Some(internal_geo), Some(target_geo)) = (internal_geo, external_geo.first().copied()) {
+            let elements = space.elements().cloned().collect::<Vec<_>>();
+            for element in elements {
+                let (Some(geo), Some(original)) = (
+                    space.element_geometry(&element),
+                    space.element_location_f64(&element),
+                ) else {
+                    continue;
+                };
+                if !geo.overlaps(internal_geo) || external_geo.iter().any(|external| geo.overlaps(*external))
+                {
+                    continue;
+                }
+
+                let target = migrate_location(geo, internal_geo, target_geo);
+                let location = original + (target - geo.loc).to_f64();
+                space.relocate_element(&element, location);
+                migrated.push(MigratedElement {
+                    element,
+                    original,
+                    migrated: location,
+                });
+            }
+        }
+
+        space.unmap_output(&self.internal);
+        self.disabled = Some(DisabledPanel {
+            location: internal_geo.map(|geo| geo.loc),
+            migrated,
+        });
+    }
+
+    fn enable(&mut self, space: &mut Space<E>) {
+        let Some(disabled) = self.disabled.take() else {
+            return;
+        };
+        if let Some(location) = disabled.location {
+            space.map_output(&self.internal, location);
+        }
+        for migrated in disabled.migrated {
+            // leave elements alone, that were moved while the panel was disabled
+            if space.element_location_f64(&migrated.element) == Some(migrated.migrated) {
+                space.relocate_element(&migrated.element, migrated.original);
+            }
+        }
+    }
+}
+
+// Keeps the position relative to the output, while fitting the element into the target
+fn migrate_location(
+    geo: Rectangle<i32, Logical>,
+    from: Rectangle<i32, Logical>,
+    to: Rectangle<i32, Logical>,
+) -> Point<i32, Logical> {
+    let location = to.loc + (geo.loc - from.loc);
+    let max = to.loc + (to.size - geo.size).to_point();
+    (
+        location.x.min(max.x).max(to.loc.x),
+        location.y.min(max.y).max(to.loc.y),
+    )
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{output::test_output, utils::IsAlive};
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct TestElement(u32);
+    impl SpaceElement for TestElement {
+        fn bbox(&self) -> Rectangle<i32, Logical> {
+            Rectangle::from_size((10, 10).into())
+        }
+        fn is_in_input_region(&self, _point: &Point<f64, Logical>) -> bool {
+            true
+        }
+        fn set_activate(&self, _activated: bool) {}
+        fn output_enter(&self, _output: &Output, _overlap: Rectangle<i32, Logical>) {}
+        fn output_leave(&self, _output: &Output) {}
+    }
+    impl IsAlive for TestElement {
+        fn alive(&self) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn migrate_on_lid_close() {
+        let mut space = Space::default();
+        let internal = test_output("eDP-1", (100, 100));
+        let external = test_output("HDMI-A-1", (50, 50));
+        space.map_output(&internal, (0, 0));
+        space.map_element(TestElement(0), (10, 10), false);
+        space.map_element(TestElement(1), (80, 60), false);
+
+        let mut policy = LidPolicy::new(internal.clone());
+        // without external outputs the panel stays enabled
+        assert_eq!(
+            policy.switch_toggled(Some(Switch::Lid), SwitchState::On, &mut space),
+            None
+        );
+
+        space.map_output(&external, (100, 0));
+        assert_eq!(policy.update(&mut space), Some(LidAction::DisableInternal));
+        assert!(!space.outputs().any(|output| output == &internal));
+        assert_eq!(space.element_location(&TestElement(0)), Some((110, 10).into()));
+        assert_eq!(space.element_location(&TestElement(1)), Some((140, 40).into()));
+
+        space.relocate_element(&TestElement(1), (120.0, 20.0));
+        assert_eq!(
+            policy.switch_toggled(Some(Switch::Lid), SwitchState::Off, &mut space),
+            Some(LidAction::EnableInternal)
+        );
+        assert_eq!(
+            space.output_geometry(&internal).map(|geo| geo.loc),
+            Some((0, 0).into())
+        );
+        assert_eq!(space.element_location(&TestElement(0)), Some((10, 10).into()));
+        assert_eq!(space.element_location(&TestElement(1)), Some((120, 20).into()));
+    }
+}
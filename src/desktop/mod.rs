@@ -20,6 +20,12 @@
 //!
 //! Note that a [`Window`] on it's own has no position. For that it needs to be placed inside a [`Space`].
 //!
+//! ### [`WindowGroup`]
+//!
+//! A window group bundles multiple [`Window`]s into a single slot, e.g. for tabbed or stacked layouts.
+//! Only the active window is displayed, with an optional tab bar region above it, that is left for the
+//! compositor to draw as part of its decorations.
+//!
 //! ### [`Space`]
 //!
 //! A space represents a two-dimensional plane of undefined dimensions.
@@ -28,6 +34,26 @@
 //! Elements get a position and stacking order through mapping. Outputs become views of a part of the [`Space`]
 //! and can be rendered via [`render_output`](crate::desktop::space::render_output).
 //!
+//! ### Previews
+//!
+//! A [`WindowPreviewElement`](preview::WindowPreviewElement) renders a scaled-down live view of a [`Window`],
+//! e.g. for dock hover previews or overview modes.
+//!
+//! ### Frame callback backpressure
+//!
+//! A [`FrameCallbackBackpressure`](backpressure::FrameCallbackBackpressure) reduces the rate of frame callbacks
+//! for surfaces, which are not focused, while an output is consistently missing its deadlines.
+//!
+//! ### Scene dumps
+//!
+//! A [`SceneDump`](dump::SceneDump) captures the outputs, spaces and surface trees of a compositor for bug reports
+//! and renders them again offline, so layout and damage issues can be reproduced without the original clients.
+//!
+//! ### Lid switch
+//!
+//! A [`LidPolicy`](lid::LidPolicy) disables the built-in panel of a laptop while its lid is closed and external
+//! outputs are connected, migrating the elements of the [`Space`] between the outputs.
+//!
 //! ### Layer Shell
 //!
 //! A [`LayerSurface`] represents a surface as provided by e.g. the layer-shell protocol.
@@ -49,20 +75,28 @@
 //! to manage client buffers to do so. If you plan to use the provided drawing functions, you need to use
 //! [`on_commit_buffer_handler`](crate::backend::renderer::utils::on_commit_buffer_handler).
 
+pub mod dump;
+pub mod lid;
+pub mod redraw;
 pub mod space;
 pub use self::space::Space;
 
 #[cfg(feature = "wayland_frontend")]
 pub use self::wayland::{
+    backpressure,
+    group::WindowGroup,
     layer::{layer_map_for_output, LayerMap, LayerSurface},
     popup::*,
-    utils,
+    preview, utils,
     window::*,
 };
 #[cfg(feature = "wayland_frontend")]
 mod wayland {
+    pub mod backpressure;
+    pub(crate) mod group;
     pub(crate) mod layer;
     pub mod popup;
+    pub mod preview;
     pub mod utils;
     pub mod window;
 }
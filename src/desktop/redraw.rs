@@ -0,0 +1,203 @@
+//! Scheduling of redraws, pausing rendering while nothing changes
+//!
+//! Rendering an output every vblank keeps the gpu and the display engine busy, even if every frame
+//! is identical. [`RedrawScheduler`] tracks whether an output needs to be redrawn, so compositors only
+//! render after something changed and stop waiting for vblanks once the output is idle.
+//!
+//! - Request a redraw with [`RedrawScheduler::queue_redraw`] whenever something changed, e.g. when a
+//!   surface visible on the output was committed or the cursor moved.
+//! - Render the output while [`RedrawScheduler::needs_redraw`] returns `true`. If the frame was damaged
+//!   and queued call [`RedrawScheduler::frame_queued`], otherwise call [`RedrawScheduler::frame_skipped`].
+//! - Once the vblank of the queued frame arrived call [`RedrawScheduler::vblank`]. The output is only
+//!   rendered again, if a redraw was requested in the meantime or an animation is running.
+//!
+//! Animations driven by the compositor, e.g. window open animations, keep the output rendering every vblank
+//! as long as the [`AnimationGuard`] returned by [`RedrawScheduler::start_animation`] is alive.
+//!
+//! ```no_run
+//! # use smithay::desktop::redraw::RedrawScheduler;
+//! # fn render() -> bool { true }
+//! let mut scheduler = RedrawScheduler::new();
+//!
+//! // a surface on the output was committed
+//! scheduler.queue_redraw();
+//!
+//! // when the event loop is idle
+//! if scheduler.needs_redraw() {
+//!     let damaged = render();
+//!     if damaged {
+//!         scheduler.frame_queued();
+//!     } else {
+//!         // frame callbacks are still due, e.g. send them throttled
+//!         scheduler.frame_skipped();
+//!     }
+//! }
+//!
+//! // on the vblank event of the output
+//! scheduler.vblank();
+//! ```
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+/// Redraw state of an output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RedrawState {
+    /// Nothing changed, the output is neither rendered nor waiting for a vblank
+    Idle,
+    /// The output has to be rendered
+    Queued,
+    /// A frame was queued and the output waits for its vblank
+    WaitingForVBlank {
+        /// A redraw was requested while waiting
+        redraw_needed: bool,
+    },
+}
+
+/// Tracks whether an output needs to be redrawn
+///
+/// See the [module docs](self) for details.
+#[derive(Debug)]
+pub struct RedrawScheduler {
+    state: RedrawState,
+    animations: Arc<AtomicUsize>,
+}
+
+impl Default for RedrawScheduler {
+    fn default() -> Self {
+        RedrawScheduler {
+            // render the initial frame
+            state: RedrawState::Queued,
+            animations: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+}
+
+impl RedrawScheduler {
+    /// Create a new scheduler, requesting the initial frame
+    pub fn new() -> Self {
+        RedrawScheduler::default()
+    }
+
+    /// Returns the current state
+    pub fn state(&self) -> RedrawState {
+        self.state
+    }
+
+    /// Request the output to be redrawn
+    ///
+    /// Returns `true` if the output was idle, i.e. rendering has to be resumed.
+    pub fn queue_redraw(&mut self) -> bool {
+        match self.state {
+            RedrawState::Idle => {
+                self.state = RedrawState::Queued;
+                true
+            }
+            RedrawState::Queued => false,
+            RedrawState::WaitingForVBlank { .. } => {
+                self.state = RedrawState::WaitingForVBlank { redraw_needed: true };
+                false
+            }
+        }
+    }
+
+    /// Returns whether the output has to be rendered
+    pub fn needs_redraw(&self) -> bool {
+        self.state == RedrawState::Queued
+    }
+
+    /// A damaged frame was rendered and queued, wait for its vblank
+    pub fn frame_queued(&mut self) {
+        self.state = RedrawState::WaitingForVBlank { redraw_needed: false };
+    }
+
+    /// The rendered frame had no damage and was not queued
+    ///
+    /// No vblank is awaited, the output stays idle until the next redraw is requested.
+    /// Running animations are expected to cause damage, so they don't keep an output without damage rendering.
+    pub fn frame_skipped(&mut self) {
+        self.state = RedrawState::Idle;
+    }
+
+    /// The vblank of the queued frame arrived
+    ///
+    /// Returns `true` if the output has to be rendered again, because a redraw was requested
+    /// or an animation is running.
+    pub fn vblank(&mut self) -> bool {
+        let redraw_needed = match self.state {
+            RedrawState::WaitingForVBlank { redraw_needed } => redraw_needed,
+            RedrawState::Queued => true,
+            RedrawState::Idle => false,
+        };
+        self.state = if redraw_needed || self.is_animating() {
+            RedrawState::Queued
+        } else {
+            RedrawState::Idle
+        };
+        self.needs_redraw()
+    }
+
+    /// Start an animation, rendering the output every vblank until the returned guard is dropped
+    ///
+    /// Requests a redraw, see [`RedrawScheduler::queue_redraw`].
+    pub fn start_animation(&mut self) -> AnimationGuard {
+        self.animations.fetch_add(1, Ordering::AcqRel);
+        self.queue_redraw();
+        AnimationGuard {
+            animations: self.animations.clone(),
+        }
+    }
+
+    /// Returns whether any animation is running
+    pub fn is_animating(&self) -> bool {
+        self.animations.load(Ordering::Acquire) > 0
+    }
+}
+
+/// Keeps an output rendering every vblank while alive
+///
+/// See [`RedrawScheduler::start_animation`].
+#[derive(Debug)]
+pub struct AnimationGuard {
+    animations: Arc<AtomicUsize>,
+}
+
+impl Drop for AnimationGuard {
+    fn drop(&mut self) {
+        self.animations.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pause_and_resume() {
+        let mut scheduler = RedrawScheduler::new();
+        assert!(scheduler.needs_redraw());
+        scheduler.frame_queued();
+        assert!(!scheduler.vblank());
+        assert_eq!(scheduler.state(), RedrawState::Idle);
+
+        // a commit resumes rendering
+        assert!(scheduler.queue_redraw());
+        scheduler.frame_queued();
+        assert!(!scheduler.queue_redraw());
+        assert!(scheduler.vblank());
+        scheduler.frame_skipped();
+        assert_eq!(scheduler.state(), RedrawState::Idle);
+
+        // animations keep rendering every vblank
+        let animation = scheduler.start_animation();
+        assert!(scheduler.needs_redraw());
+        scheduler.frame_queued();
+        assert!(scheduler.vblank());
+        scheduler.frame_queued();
+        drop(animation);
+        assert!(!scheduler.vblank());
+        assert_eq!(scheduler.state(), RedrawState::Idle);
+    }
+}
@@ -15,9 +15,13 @@ use crate::{
     utils::{IsAlive, Logical, Physical, Point, Rectangle, Scale},
 };
 
+#[cfg(feature = "wayland_frontend")]
+mod snapshot;
 #[cfg(feature = "wayland_frontend")]
 mod wayland;
 #[cfg(feature = "wayland_frontend")]
+pub use self::snapshot::ElementSnapshot;
+#[cfg(feature = "wayland_frontend")]
 pub use self::wayland::SurfaceTree;
 
 /// Indicates default values for some zindexs inside smithay
@@ -62,6 +66,13 @@ pub trait SpaceElement: IsAlive {
     fn bbox(&self) -> Rectangle<i32, Logical>;
     /// Returns whenever a given point inside this element will be able to receive input
     fn is_in_input_region(&self, point: &Point<f64, Logical>) -> bool;
+    /// Returns the opaque regions of this element, in the same coordinate space as [`SpaceElement::bbox`]
+    ///
+    /// Used to determine the visibility of elements below this one,
+    /// see [`Space::element_visibility`](super::Space::element_visibility). Defaults to no opaque regions.
+    fn opaque_regions(&self) -> Vec<Rectangle<i32, Logical>> {
+        Vec::new()
+    }
     /// Gets the z-index of this element
     fn z_index(&self) -> u8 {
         RenderZindex::Overlay as u8
@@ -90,6 +101,9 @@ impl<T: SpaceElement> SpaceElement for &T {
     fn is_in_input_region(&self, point: &Point<f64, Logical>) -> bool {
         SpaceElement::is_in_input_region(*self, point)
     }
+    fn opaque_regions(&self) -> Vec<Rectangle<i32, Logical>> {
+        SpaceElement::opaque_regions(*self)
+    }
     fn z_index(&self) -> u8 {
         SpaceElement::z_index(*self)
     }
@@ -153,11 +167,11 @@ where
         }
     }
 
-    pub(super) fn render_location(&self) -> Point<i32, Logical> {
+    pub(super) fn render_location_f64(&self) -> Point<f64, Logical> {
         match self {
             #[cfg(feature = "wayland_frontend")]
-            SpaceElements::Layer { .. } => self.bbox().loc,
-            SpaceElements::Element(inner) => inner.render_location(),
+            SpaceElements::Layer { .. } => self.bbox().loc.to_f64(),
+            SpaceElements::Element(inner) => inner.render_location_f64(),
         }
     }
 }
@@ -316,6 +330,18 @@ macro_rules! space_elements_internal {
                 Self::_GenericCatcher(_) => unreachable!(),
             }
         }
+        fn opaque_regions(&self) -> Vec<$crate::utils::Rectangle<i32, $crate::utils::Logical>> {
+            match self {
+                $(
+                    #[allow(unused_doc_comments)]
+                    $(
+                        #[$meta]
+                    )*
+                    Self::$body(x) => $crate::space_elements_internal!(@call opaque_regions; x)
+                ),*,
+                Self::_GenericCatcher(_) => unreachable!(),
+            }
+        }
 
         fn z_index(&self) -> u8 {
             match self {
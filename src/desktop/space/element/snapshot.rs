@@ -0,0 +1,204 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex,
+};
+
+use crate::{
+    backend::{
+        allocator::Fourcc,
+        renderer::{
+            element::{
+                texture::{TextureBuffer, TextureRenderElement},
+                AsRenderElements, Kind, RenderElement,
+            },
+            utils::draw_render_elements,
+            Color32F, Frame, Offscreen, Renderer, Texture,
+        },
+    },
+    output::Output,
+    utils::{IsAlive, Logical, Physical, Point, Rectangle, Scale, Transform},
+};
+
+use super::SpaceElement;
+
+crate::utils::ids::id_gen!(element_snapshot_id);
+
+#[derive(Debug)]
+struct SnapshotInner<T: Texture> {
+    id: usize,
+    buffer: TextureBuffer<T>,
+    geometry: Rectangle<i32, Logical>,
+    bbox: Rectangle<i32, Logical>,
+    z_index: u8,
+    alpha: Mutex<f32>,
+    alive: AtomicBool,
+}
+
+impl<T: Texture> Drop for SnapshotInner<T> {
+    fn drop(&mut self) {
+        element_snapshot_id::remove(self.id);
+    }
+}
+
+/// The last frame of a [`SpaceElement`], captured into a texture
+///
+/// Snapshots allow elements to linger in a [`Space`](crate::desktop::Space) after their surfaces
+/// were unmapped or destroyed, e.g. to drive close or minimize animations.
+/// A snapshot behaves like the element it was captured from, but never receives input.
+///
+/// To replace a closing window with its snapshot, map the snapshot at the location of the window,
+/// restack it above the window using [`Space::raise_element_above`](crate::desktop::Space::raise_element_above)
+/// and unmap the window afterwards. Once the animation is done, call [`ElementSnapshot::finish`]
+/// and the snapshot gets removed on the next [`Space::refresh`](crate::desktop::Space::refresh).
+#[derive(Debug)]
+pub struct ElementSnapshot<T: Texture>(Arc<SnapshotInner<T>>);
+
+impl<T: Texture> Clone for ElementSnapshot<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        ElementSnapshot(self.0.clone())
+    }
+}
+
+impl<T: Texture> PartialEq for ElementSnapshot<T> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.0.id == other.0.id
+    }
+}
+
+impl<T: Texture> Eq for ElementSnapshot<T> {}
+
+impl<T: Texture> IsAlive for ElementSnapshot<T> {
+    #[inline]
+    fn alive(&self) -> bool {
+        self.0.alive.load(Ordering::Acquire)
+    }
+}
+
+impl<T: Texture + Clone> ElementSnapshot<T> {
+    /// Captures the current contents of an element
+    ///
+    /// The element is rendered into an offscreen texture covering its bounding box.
+    /// `scale` should match the scale of the output the snapshot will be displayed on.
+    pub fn capture<R, E>(
+        renderer: &mut R,
+        element: &E,
+        scale: impl Into<Scale<f64>>,
+    ) -> Result<Self, R::Error>
+    where
+        R: Renderer<TextureId = T> + Offscreen<T>,
+        T: 'static,
+        E: SpaceElement + AsRenderElements<R>,
+        <E as AsRenderElements<R>>::RenderElement: RenderElement<R>,
+    {
+        let scale = scale.into();
+        let geometry = element.geometry();
+        let bbox = element.bbox();
+        let size = bbox.size.to_physical_precise_ceil(scale);
+
+        let elements = element.render_elements::<<E as AsRenderElements<R>>::RenderElement>(
+            renderer,
+            Point::<i32, Logical>::from((-bbox.loc.x, -bbox.loc.y)).to_physical_precise_round(scale),
+            scale,
+            1.0,
+        );
+
+        let mut texture = renderer.create_buffer(Fourcc::Abgr8888, (size.w, size.h).into())?;
+        {
+            let mut framebuffer = renderer.bind(&mut texture)?;
+            let mut frame = renderer.render(&mut framebuffer, size, Transform::Normal)?;
+            let damage = [Rectangle::from_size(size)];
+            frame.clear(Color32F::TRANSPARENT, &damage)?;
+            draw_render_elements::<R, _, _>(&mut frame, scale, &elements, &damage)?;
+            let sync = frame.finish()?;
+            renderer.wait(&sync)?;
+        }
+
+        Ok(ElementSnapshot(Arc::new(SnapshotInner {
+            id: element_snapshot_id::next(),
+            buffer: TextureBuffer::from_texture(renderer, texture, 1, Transform::Normal, None),
+            geometry,
+            bbox,
+            z_index: element.z_index(),
+            alpha: Mutex::new(1.0),
+            alive: AtomicBool::new(true),
+        })))
+    }
+}
+
+impl<T: Texture> ElementSnapshot<T> {
+    /// Returns the captured texture
+    pub fn texture_buffer(&self) -> &TextureBuffer<T> {
+        &self.0.buffer
+    }
+
+    /// Returns the alpha value the snapshot is rendered with
+    pub fn alpha(&self) -> f32 {
+        *self.0.alpha.lock().unwrap()
+    }
+
+    /// Sets the alpha value the snapshot is rendered with, e.g. to fade it out
+    ///
+    /// The value is multiplied with the alpha passed to [`AsRenderElements::render_elements`].
+    pub fn set_alpha(&self, alpha: f32) {
+        *self.0.alpha.lock().unwrap() = alpha.clamp(0.0, 1.0);
+    }
+
+    /// Marks the snapshot as finished
+    ///
+    /// The snapshot is no longer considered alive and will be removed from any
+    /// [`Space`](crate::desktop::Space) on its next refresh.
+    pub fn finish(&self) {
+        self.0.alive.store(false, Ordering::Release);
+    }
+}
+
+impl<T: Texture> SpaceElement for ElementSnapshot<T> {
+    fn geometry(&self) -> Rectangle<i32, Logical> {
+        self.0.geometry
+    }
+
+    fn bbox(&self) -> Rectangle<i32, Logical> {
+        self.0.bbox
+    }
+
+    fn is_in_input_region(&self, _point: &Point<f64, Logical>) -> bool {
+        false
+    }
+
+    fn z_index(&self) -> u8 {
+        self.0.z_index
+    }
+
+    fn set_activate(&self, _activated: bool) {}
+    fn output_enter(&self, _output: &Output, _overlap: Rectangle<i32, Logical>) {}
+    fn output_leave(&self, _output: &Output) {}
+}
+
+impl<R> AsRenderElements<R> for ElementSnapshot<R::TextureId>
+where
+    R: Renderer,
+    R::TextureId: Texture + Clone + 'static,
+{
+    type RenderElement = TextureRenderElement<R::TextureId>;
+
+    fn render_elements<C: From<Self::RenderElement>>(
+        &self,
+        _renderer: &mut R,
+        location: Point<i32, Physical>,
+        scale: Scale<f64>,
+        alpha: f32,
+    ) -> Vec<C> {
+        let location = location.to_f64() + self.0.bbox.loc.to_f64().to_physical(scale);
+        vec![TextureRenderElement::from_texture_buffer(
+            location,
+            &self.0.buffer,
+            Some(alpha * self.alpha()),
+            None,
+            Some(self.0.bbox.size),
+            Kind::Unspecified,
+        )
+        .into()]
+    }
+}
@@ -1,5 +1,16 @@
 //! This module contains the [`Space`] helper class as well has related
 //! rendering helpers to add custom elements or different clients to a space.
+//!
+//! ## Workspaces
+//!
+//! [`Workspaces`] implements a set of workspaces using one [`Space`] per workspace,
+//! only the active one has the outputs mapped.
+//!
+//! Sticky windows, which should stay visible on all workspaces of an output, and other elements
+//! pinned across workspace switches are moved into an additional [`Space`] by
+//! [`Workspaces::set_sticky`] and [`Workspaces::set_pinned`]. Because the render elements of
+//! such an element keep their ids, switching workspaces only generates damage for the elements
+//! that actually changed.
 
 use crate::{
     backend::renderer::{
@@ -8,13 +19,16 @@ use crate::{
         Color32F, Renderer, Texture,
     },
     output::{Output, OutputModeSource, OutputNoMode},
-    utils::{IsAlive, Logical, Point, Rectangle, Scale, Transform},
+    utils::{IsAlive, Logical, Physical, Point, Rectangle, Scale, Transform},
 };
 #[cfg(feature = "wayland_frontend")]
 use crate::{
     backend::renderer::{element::surface::WaylandSurfaceRenderElement, ImportAll},
     desktop::{layer_map_for_output, LayerSurface, WindowSurfaceType},
-    wayland::shell::wlr_layer::Layer,
+    wayland::{
+        seat::WaylandFocus,
+        shell::{wlr_layer::Layer, xdg::ToplevelSurface},
+    },
 };
 use std::{collections::HashMap, fmt};
 use tracing::{debug, debug_span, instrument};
@@ -24,21 +38,64 @@ use wayland_server::protocol::wl_surface::WlSurface;
 mod element;
 mod output;
 mod utils;
+mod workspaces;
 
 #[cfg(feature = "wayland_frontend")]
 pub(crate) mod wayland;
+#[cfg(feature = "wayland_frontend")]
+pub use self::wayland::{default_preferred_buffer_state, PreferredBufferStatePolicy};
 
 pub use self::element::*;
 use self::output::*;
 pub use self::utils::*;
+pub use self::workspaces::Workspaces;
 
 crate::utils::ids::id_gen!(space_id);
 
+/// Stable identifier of an element mapped in a [`Space`]
+///
+/// The id is assigned when the element is first mapped and stays the same
+/// until the element is unmapped, regardless of its position in the stack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpaceElementId(pub(crate) usize);
+
+/// Group of elements in a [`Space`] moving together in the stacking order
+///
+/// Created by [`Space::create_stacking_group`]. Restacking any member of a group
+/// restacks all members, keeping their relative order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StackingGroup(usize);
+
 #[derive(Debug)]
 struct InnerElement<E> {
     element: E,
-    location: Point<i32, Logical>,
+    id: SpaceElementId,
+    location: Point<f64, Logical>,
     outputs: HashMap<Output, Rectangle<i32, Logical>>,
+    group: Option<StackingGroup>,
+    always_on_top: bool,
+}
+
+/// Visibility of an element mapped in a [`Space`]
+///
+/// Returned by [`Space::element_visibility`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Visibility {
+    /// The element is not visible on any output
+    Occluded,
+    /// Only parts of the element are visible, given as regions in space coordinates
+    Partial(Vec<Rectangle<i32, Logical>>),
+    /// The whole bounding box of the element is visible
+    Full,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum StackPosition {
+    Top,
+    Bottom,
+    Above(usize),
+    Below(usize),
 }
 
 /// Represents two dimensional plane to map windows and outputs upon.
@@ -53,6 +110,9 @@ pub struct Space<E: SpaceElement> {
     // in z-order, back to front
     elements: Vec<InnerElement<E>>,
     outputs: Vec<Output>,
+    next_id: usize,
+    #[cfg(feature = "wayland_frontend")]
+    buffer_state_policy: Option<wayland::BufferStatePolicy>,
     span: tracing::Span,
 }
 
@@ -80,6 +140,9 @@ impl<E: SpaceElement> Default for Space<E> {
             id,
             elements: Default::default(),
             outputs: Default::default(),
+            next_id: 0,
+            #[cfg(feature = "wayland_frontend")]
+            buffer_state_policy: None,
             span,
         }
     }
@@ -103,19 +166,47 @@ impl<E: SpaceElement + PartialEq> Space<E> {
     where
         P: Into<Point<i32, Logical>>,
     {
-        #[allow(clippy::mutable_key_type)]
-        let outputs = if let Some(pos) = self.elements.iter().position(|inner| inner.element == element) {
-            self.elements.remove(pos).outputs
+        let id = if let Some(pos) = self.elements.iter().position(|inner| inner.element == element) {
+            self.elements[pos].location = location.into().to_f64();
+            let id = self.elements[pos].id;
+            self.restack(pos, StackPosition::Top);
+            id
         } else {
-            HashMap::new()
+            let id = SpaceElementId(self.next_id);
+            self.next_id += 1;
+            self.elements.push(InnerElement {
+                element,
+                id,
+                location: location.into().to_f64(),
+                outputs: HashMap::new(),
+                group: None,
+                always_on_top: false,
+            });
+            self.sort_elements();
+            id
         };
+        if activate {
+            if let Some(pos) = self.elements.iter().position(|e| e.id == id) {
+                self.activate(pos);
+            }
+        }
+    }
 
-        let inner = InnerElement {
-            element,
-            location: location.into(),
-            outputs,
-        };
-        self.insert_elem(inner, activate);
+    /// Moves an already mapped [`SpaceElement`] to a fractional location
+    ///
+    /// Unlike [`Space::map_element`] this does not change the stacking order or activation.
+    /// The location is not rounded to integer logical coordinates when rendering, which avoids
+    /// elements snapping between logical pixels during animations on outputs with fractional scales.
+    /// Integer based queries like [`Space::element_location`] return the rounded location.
+    ///
+    /// This function does nothing for unmapped elements.
+    pub fn relocate_element<P>(&mut self, element: &E, location: P)
+    where
+        P: Into<Point<f64, Logical>>,
+    {
+        if let Some(pos) = self.position(element) {
+            self.elements[pos].location = location.into();
+        }
     }
 
     /// Moves an already mapped [`SpaceElement`] to top of the stack
@@ -126,30 +217,194 @@ impl<E: SpaceElement + PartialEq> Space<E> {
     /// to be activate and removes that state from every
     /// other mapped window.
     pub fn raise_element(&mut self, element: &E, activate: bool) {
-        if let Some(pos) = self.elements.iter().position(|inner| &inner.element == element) {
-            let inner = self.elements.remove(pos);
-            self.insert_elem(inner, activate);
+        if let Some(pos) = self.position(element) {
+            self.restack(pos, StackPosition::Top);
+            if activate {
+                if let Some(pos) = self.position(element) {
+                    self.activate(pos);
+                }
+            }
         }
     }
 
-    fn insert_elem(&mut self, elem: InnerElement<E>, activate: bool) {
-        if activate {
-            elem.element.set_activate(true);
-            for e in self.elements.iter() {
+    /// Moves an already mapped [`SpaceElement`] to the bottom of the stack
+    ///
+    /// This function does nothing for unmapped windows.
+    pub fn lower_element(&mut self, element: &E) {
+        if let Some(pos) = self.position(element) {
+            self.restack(pos, StackPosition::Bottom);
+        }
+    }
+
+    /// Moves an already mapped [`SpaceElement`] directly above another mapped element
+    ///
+    /// This function does nothing if either element is unmapped.
+    ///
+    /// *Note:* The stacking order is always sorted by [`SpaceElement::z_index`] and the
+    /// always-on-top flag first, so elements can't be moved above elements of a higher layer.
+    pub fn raise_element_above(&mut self, element: &E, sibling: &E) {
+        if let (Some(pos), Some(sibling)) = (self.position(element), self.position(sibling)) {
+            self.restack(pos, StackPosition::Above(sibling));
+        }
+    }
+
+    /// Moves an already mapped [`SpaceElement`] directly below another mapped element
+    ///
+    /// This function does nothing if either element is unmapped.
+    ///
+    /// *Note:* The stacking order is always sorted by [`SpaceElement::z_index`] and the
+    /// always-on-top flag first, so elements can't be moved below elements of a lower layer.
+    pub fn lower_element_below(&mut self, element: &E, sibling: &E) {
+        if let (Some(pos), Some(sibling)) = (self.position(element), self.position(sibling)) {
+            self.restack(pos, StackPosition::Below(sibling));
+        }
+    }
+
+    /// Marks an already mapped [`SpaceElement`] to be stacked above all other elements
+    /// sharing the same [`SpaceElement::z_index`].
+    ///
+    /// This function does nothing for unmapped windows.
+    pub fn set_always_on_top(&mut self, element: &E, always_on_top: bool) {
+        if let Some(pos) = self.position(element) {
+            self.elements[pos].always_on_top = always_on_top;
+            self.sort_elements();
+        }
+    }
+
+    /// Returns if a [`SpaceElement`] is marked as always-on-top
+    pub fn is_always_on_top(&self, element: &E) -> bool {
+        self.elements
+            .iter()
+            .find(|e| &e.element == element)
+            .map(|e| e.always_on_top)
+            .unwrap_or(false)
+    }
+
+    /// Creates a new [`StackingGroup`] for this space
+    pub fn create_stacking_group(&mut self) -> StackingGroup {
+        let group = StackingGroup(self.next_id);
+        self.next_id += 1;
+        group
+    }
+
+    /// Sets or clears the [`StackingGroup`] of an already mapped [`SpaceElement`]
+    ///
+    /// Adding an element to a group moves it directly above the topmost member of that group.
+    ///
+    /// This function does nothing for unmapped windows.
+    pub fn set_stacking_group(&mut self, element: &E, group: Option<StackingGroup>) {
+        let Some(pos) = self.position(element) else {
+            return;
+        };
+
+        self.elements[pos].group = None;
+        if let Some(top) = group.and_then(|group| self.elements.iter().rposition(|e| e.group == Some(group)))
+        {
+            self.restack(pos, StackPosition::Above(top));
+        }
+        if let Some(pos) = self.position(element) {
+            self.elements[pos].group = group;
+        }
+    }
+
+    /// Returns the [`StackingGroup`] of a [`SpaceElement`], if any
+    pub fn stacking_group(&self, element: &E) -> Option<StackingGroup> {
+        self.elements
+            .iter()
+            .find(|e| &e.element == element)
+            .and_then(|e| e.group)
+    }
+
+    /// Returns the stable id of a mapped [`SpaceElement`]
+    pub fn element_id(&self, element: &E) -> Option<SpaceElementId> {
+        self.elements.iter().find(|e| &e.element == element).map(|e| e.id)
+    }
+
+    /// Returns the mapped [`SpaceElement`] for a given id, if it is still mapped
+    pub fn element_for_id(&self, id: SpaceElementId) -> Option<&E> {
+        self.elements.iter().find(|e| e.id == id).map(|e| &e.element)
+    }
+
+    fn position(&self, element: &E) -> Option<usize> {
+        self.elements.iter().position(|inner| &inner.element == element)
+    }
+
+    fn activate(&mut self, pos: usize) {
+        for (i, e) in self.elements.iter().enumerate() {
+            if i != pos {
                 e.element.set_activate(false);
             }
         }
+        self.elements[pos].element.set_activate(true);
+    }
 
-        self.elements.push(elem);
+    // Moves the element at `pos` together with all other members of its group
+    fn restack(&mut self, pos: usize, position: StackPosition) {
+        let group = self.elements[pos].group;
+        let is_moving = |e: &InnerElement<E>, idx: usize| match group {
+            Some(group) => e.group == Some(group),
+            None => idx == pos,
+        };
+
+        // groups of the sibling are kept together as well
+        let sibling_range = |elements: &[InnerElement<E>], sibling: usize| {
+            let group = elements[sibling].group;
+            let members = elements
+                .iter()
+                .enumerate()
+                .filter(|(idx, e)| *idx == sibling || (group.is_some() && e.group == group))
+                .map(|(idx, _)| idx);
+            (members.clone().min().unwrap(), members.max().unwrap())
+        };
+        let anchor = match position {
+            StackPosition::Above(sibling) | StackPosition::Below(sibling) => {
+                if is_moving(&self.elements[sibling], sibling) {
+                    return;
+                }
+                let (first, last) = sibling_range(&self.elements, sibling);
+                Some(if matches!(position, StackPosition::Above(_)) {
+                    self.elements[last].id
+                } else {
+                    self.elements[first].id
+                })
+            }
+            _ => None,
+        };
+
+        let (moving, rest): (Vec<_>, Vec<_>) = self
+            .elements
+            .drain(..)
+            .enumerate()
+            .partition(|(idx, e)| is_moving(e, *idx));
+        self.elements = rest.into_iter().map(|(_, e)| e).collect();
+
+        let idx = match position {
+            StackPosition::Top => self.elements.len(),
+            StackPosition::Bottom => 0,
+            StackPosition::Above(_) => {
+                let anchor = anchor.unwrap();
+                self.elements.iter().position(|e| e.id == anchor).unwrap() + 1
+            }
+            StackPosition::Below(_) => {
+                let anchor = anchor.unwrap();
+                self.elements.iter().position(|e| e.id == anchor).unwrap()
+            }
+        };
+        self.elements.splice(idx..idx, moving.into_iter().map(|(_, e)| e));
+        self.sort_elements();
+    }
+
+    fn sort_elements(&mut self) {
+        // stable sort, keeps the order inside of each layer
         self.elements
-            .sort_by(|e1, e2| e1.element.z_index().cmp(&e2.element.z_index()));
+            .sort_by_key(|e| (e.element.z_index(), e.always_on_top));
     }
 
     /// Unmap a [`SpaceElement`] from this space.
     ///
     /// This function does nothing for already unmapped windows
     pub fn unmap_elem(&mut self, element: &E) {
-        if let Some(pos) = self.elements.iter().position(|inner| &inner.element == element) {
+        if let Some(pos) = self.position(element) {
             let elem = self.elements.remove(pos);
             for output in elem.outputs.keys() {
                 elem.element.output_leave(output);
@@ -182,21 +437,34 @@ impl<E: SpaceElement + PartialEq> Space<E> {
     ///
     /// Note that [`SpaceElement::is_in_input_region`] expects the point
     /// to be relative to the elements origin.
+    ///
+    /// The returned location is rounded to integer coordinates, see [`Space::element_under_f64`]
+    /// for elements at fractional locations.
     pub fn element_under<P: Into<Point<f64, Logical>>>(&self, point: P) -> Option<(&E, Point<i32, Logical>)> {
-        let point = point.into();
+        self.inner_element_under(point.into())
+            .map(|e| (&e.element, e.render_location()))
+    }
+
+    /// Finds the topmost element under this point if any and returns it
+    /// together with the fractional location of this element relative to this space.
+    ///
+    /// Unlike [`Space::element_under`] this does not round the location, so surface-local
+    /// coordinates derived from it match where elements at fractional locations are rendered.
+    pub fn element_under_f64<P: Into<Point<f64, Logical>>>(
+        &self,
+        point: P,
+    ) -> Option<(&E, Point<f64, Logical>)> {
+        self.inner_element_under(point.into())
+            .map(|e| (&e.element, e.render_location_f64()))
+    }
+
+    fn inner_element_under(&self, point: Point<f64, Logical>) -> Option<&InnerElement<E>> {
         self.elements
             .iter()
             .rev()
             .filter(|e| e.bbox().to_f64().contains(point))
-            .find_map(|e| {
-                // we need to offset the point to the location where the surface is actually drawn
-                let render_location = e.render_location();
-                if e.element.is_in_input_region(&(point - render_location.to_f64())) {
-                    Some((&e.element, render_location))
-                } else {
-                    None
-                }
-            })
+            // we need to offset the point to the location where the surface is actually drawn
+            .find(|e| e.element.is_in_input_region(&(point - e.render_location_f64())))
     }
 
     /// Get a reference to the outputs under a given point
@@ -225,6 +493,16 @@ impl<E: SpaceElement + PartialEq> Space<E> {
 
     /// Returns the location of a [`SpaceElement`] inside the Space.
     pub fn element_location(&self, elem: &E) -> Option<Point<i32, Logical>> {
+        self.elements
+            .iter()
+            .find(|e| &e.element == elem)
+            .map(|e| e.location.to_i32_round())
+    }
+
+    /// Returns the fractional location of a [`SpaceElement`] inside the Space.
+    ///
+    /// See [`Space::relocate_element`].
+    pub fn element_location_f64(&self, elem: &E) -> Option<Point<f64, Logical>> {
         self.elements
             .iter()
             .find(|e| &e.element == elem)
@@ -239,6 +517,14 @@ impl<E: SpaceElement + PartialEq> Space<E> {
             .map(|e| e.bbox())
     }
 
+    /// Returns the bounding box of a [`SpaceElement`] at its fractional location inside the Space.
+    pub fn element_bbox_f64(&self, elem: &E) -> Option<Rectangle<f64, Logical>> {
+        self.elements.iter().find(|e| &e.element == elem).map(|e| {
+            let bbox = e.element.bbox().to_f64();
+            Rectangle::new(bbox.loc + e.render_location_f64(), bbox.size)
+        })
+    }
+
     /// Returns the geometry of a [`SpaceElement`] including its relative position inside the Space.
     ///
     /// This area is usually defined as the contents of the window, excluding decorations.
@@ -249,6 +535,71 @@ impl<E: SpaceElement + PartialEq> Space<E> {
             .map(|e| e.geometry())
     }
 
+    /// Returns the geometry of a [`SpaceElement`] at its fractional location inside the Space.
+    pub fn element_geometry_f64(&self, elem: &E) -> Option<Rectangle<f64, Logical>> {
+        self.elements
+            .iter()
+            .find(|e| &e.element == elem)
+            .map(|e| Rectangle::new(e.location, e.element.geometry().size.to_f64()))
+    }
+
+    /// Returns the physical location a [`SpaceElement`] is rendered at on an output
+    ///
+    /// This is the location passed to [`AsRenderElements::render_elements`] by
+    /// [`Space::render_elements_for_output`], which is rounded in physical coordinates only.
+    /// Use it to render additional elements aligned to a mapped element, e.g. decorations.
+    ///
+    /// Returns `None` if the element or the output is not mapped.
+    pub fn element_render_location(&self, elem: &E, output: &Output) -> Option<Point<i32, Physical>> {
+        let element = self.elements.iter().find(|e| &e.element == elem)?;
+        let output_geo = self.output_geometry(output)?;
+        let scale = output.current_scale().fractional_scale();
+        Some((element.render_location_f64() - output_geo.loc.to_f64()).to_physical_precise_round(scale))
+    }
+
+    /// Returns how much of a [`SpaceElement`] is visible
+    ///
+    /// The visible area is the bounding box of the element restricted to the mapped outputs,
+    /// excluding the [opaque regions](SpaceElement::opaque_regions) of all elements stacked above it.
+    /// Compositors can use this to throttle frame callbacks of occluded windows.
+    ///
+    /// Returns `None` if the element is not mapped.
+    pub fn element_visibility(&self, elem: &E) -> Option<Visibility> {
+        let idx = self.elements.iter().position(|e| &e.element == elem)?;
+        let bbox = self.elements[idx].bbox();
+
+        let output_geometries = self
+            .outputs
+            .iter()
+            .filter_map(|o| self.output_geometry(o))
+            .collect::<Vec<_>>();
+        let offscreen = bbox.subtract_rects(output_geometries);
+        let onscreen = bbox.subtract_rects(offscreen);
+
+        let opaque = self.elements[idx + 1..].iter().flat_map(|e| {
+            let render_location = e.render_location();
+            e.element
+                .opaque_regions()
+                .into_iter()
+                .map(move |rect| Rectangle::new(rect.loc + render_location, rect.size))
+        });
+        let visible = Rectangle::subtract_rects_many(onscreen, opaque);
+
+        let area = |rects: &[Rectangle<i32, Logical>]| {
+            rects
+                .iter()
+                .map(|r| r.size.w as i64 * r.size.h as i64)
+                .sum::<i64>()
+        };
+        Some(if visible.is_empty() {
+            Visibility::Occluded
+        } else if area(&visible) == area(&[bbox]) {
+            Visibility::Full
+        } else {
+            Visibility::Partial(visible)
+        })
+    }
+
     /// Maps an [`Output`] inside the space.
     ///
     /// Can be safely called on an already mapped
@@ -320,10 +671,38 @@ impl<E: SpaceElement + PartialEq> Space<E> {
             .collect()
     }
 
+    /// Sets the policy for the preferred buffer scale and transform of surfaces
+    ///
+    /// Once a policy is set, surfaces receive `wl_surface.preferred_buffer_scale` and
+    /// `wl_surface.preferred_buffer_transform` on every [`Space::refresh`] based on the outputs
+    /// they entered, unless they use fractional-scale.
+    ///
+    /// No preferences are sent by default. Pass [`default_preferred_buffer_state`] to use the
+    /// highest integer scale of the entered outputs.
+    #[cfg(feature = "wayland_frontend")]
+    pub fn set_preferred_buffer_state_policy(
+        &mut self,
+        policy: impl Fn(&WlSurface, &[Output]) -> Option<(i32, Transform)> + Send + Sync + 'static,
+    ) {
+        self.buffer_state_policy = Some(wayland::BufferStatePolicy(Box::new(policy)));
+    }
+
+    /// Stops sending preferred buffer scale and transform to surfaces
+    ///
+    /// Use this if your compositor sends them itself using
+    /// [`send_surface_state`](crate::wayland::compositor::send_surface_state).
+    #[cfg(feature = "wayland_frontend")]
+    pub fn disable_preferred_buffer_state(&mut self) {
+        self.buffer_state_policy = None;
+    }
+
     /// Refresh some internal values and update client state,
     /// meaning this will handle output enter and leave events
     /// for mapped outputs and windows based on their position.
     ///
+    /// If a policy was set using [`Space::set_preferred_buffer_state_policy`], surfaces are also sent
+    /// their preferred buffer scale and transform.
+    ///
     /// Needs to be called periodically, at best before every
     /// wayland socket flush.
     #[profiling::function]
@@ -366,6 +745,10 @@ impl<E: SpaceElement + PartialEq> Space<E> {
         }
 
         self.elements.iter().for_each(|e| e.element.refresh());
+        #[cfg(feature = "wayland_frontend")]
+        if let Some(policy) = self.buffer_state_policy.as_ref() {
+            wayland::update_preferred_buffer_state(&self.outputs, &*policy.0);
+        }
         for (output, _) in outputs {
             output.cleanup();
         }
@@ -400,7 +783,7 @@ impl<E: SpaceElement + PartialEq> Space<E> {
                 region.overlaps(geometry)
             })
             .flat_map(|e| {
-                let location = e.render_location() - region.loc;
+                let location = e.render_location_f64() - region.loc.to_f64();
                 e.element
                     .render_elements::<<E as AsRenderElements<R>>::RenderElement>(
                         renderer,
@@ -461,7 +844,7 @@ impl<E: SpaceElement + PartialEq> Space<E> {
                 output_geo.overlaps(geometry)
             })
             .flat_map(|e| {
-                let location = e.render_location() - output_geo.loc;
+                let location = e.render_location_f64() - output_geo.loc.to_f64();
                 e.render_elements::<SpaceRenderElements<R, <E as AsRenderElements<R>>::RenderElement>>(
                     renderer,
                     location.to_physical_precise_round(output_scale),
@@ -473,6 +856,34 @@ impl<E: SpaceElement + PartialEq> Space<E> {
     }
 }
 
+#[cfg(feature = "wayland_frontend")]
+impl<E: SpaceElement + PartialEq + WaylandFocus> Space<E> {
+    /// Moves the element of a toplevel attached to a drag'n'drop operation
+    ///
+    /// Forward [`ClientDndGrabHandler::toplevel_drag_motion`] to this function to move toplevels
+    /// dragged via [`xdg_toplevel_drag`](crate::wayland::xdg_toplevel_drag) with the cursor.
+    /// Like [`Space::relocate_element`] this does not change the stacking order or activation.
+    ///
+    /// Returns `false` if no element of the toplevel is mapped.
+    ///
+    /// [`ClientDndGrabHandler::toplevel_drag_motion`]: crate::wayland::selection::data_device::ClientDndGrabHandler::toplevel_drag_motion
+    pub fn relocate_dragged_toplevel(
+        &mut self,
+        toplevel: &ToplevelSurface,
+        location: Point<i32, Logical>,
+    ) -> bool {
+        let Some(element) = self
+            .elements
+            .iter_mut()
+            .find(|e| e.element.wl_surface().as_deref() == Some(toplevel.wl_surface()))
+        else {
+            return false;
+        };
+        element.location = location.to_f64();
+        true
+    }
+}
+
 /// Errors thrown by [`Space::elements_for_output`]
 #[derive(thiserror::Error, Debug)]
 pub enum OutputError {
@@ -495,19 +906,23 @@ impl<E: SpaceElement> InnerElement<E> {
     // the inner geometry of the element in space coordinates
     fn geometry(&self) -> Rectangle<i32, Logical> {
         let mut geo = self.element.geometry();
-        geo.loc = self.location;
+        geo.loc = self.location.to_i32_round();
         geo
     }
 
     // the bounding box of the element in space coordinates
     fn bbox(&self) -> Rectangle<i32, Logical> {
         let mut bbox = self.element.bbox();
-        bbox.loc += self.location - self.element.geometry().loc;
+        bbox.loc += self.render_location();
         bbox
     }
 
     fn render_location(&self) -> Point<i32, Logical> {
-        self.location - self.element.geometry().loc
+        self.location.to_i32_round() - self.element.geometry().loc
+    }
+
+    fn render_location_f64(&self) -> Point<f64, Logical> {
+        self.location - self.element.geometry().loc.to_f64()
     }
 }
 
@@ -703,3 +1118,223 @@ where
 
     damage_tracker.render_output(renderer, framebuffer, age, &render_elements, clear_color)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct TestElement(u32);
+    impl SpaceElement for TestElement {
+        fn bbox(&self) -> Rectangle<i32, Logical> {
+            Rectangle::from_size((10, 10).into())
+        }
+        fn is_in_input_region(&self, _point: &Point<f64, Logical>) -> bool {
+            true
+        }
+        fn opaque_regions(&self) -> Vec<Rectangle<i32, Logical>> {
+            vec![self.bbox()]
+        }
+        fn set_activate(&self, _activated: bool) {}
+        fn output_enter(&self, _output: &Output, _overlap: Rectangle<i32, Logical>) {}
+        fn output_leave(&self, _output: &Output) {}
+    }
+    impl IsAlive for TestElement {
+        fn alive(&self) -> bool {
+            true
+        }
+    }
+
+    fn order(space: &Space<TestElement>) -> Vec<u32> {
+        space.elements().map(|e| e.0).collect()
+    }
+
+    fn space_with(count: u32) -> Space<TestElement> {
+        let mut space = Space::default();
+        for i in 0..count {
+            space.map_element(TestElement(i), (0, 0), false);
+        }
+        space
+    }
+
+    #[test]
+    fn restack_above_below() {
+        let mut space = space_with(4);
+        space.raise_element_above(&TestElement(0), &TestElement(2));
+        assert_eq!(order(&space), [1, 2, 0, 3]);
+        space.lower_element_below(&TestElement(3), &TestElement(1));
+        assert_eq!(order(&space), [3, 1, 2, 0]);
+        space.lower_element(&TestElement(0));
+        assert_eq!(order(&space), [0, 3, 1, 2]);
+    }
+
+    #[test]
+    fn stable_ids() {
+        let mut space = space_with(3);
+        let id = space.element_id(&TestElement(0)).unwrap();
+        space.raise_element(&TestElement(0), true);
+        space.map_element(TestElement(0), (5, 5), false);
+        assert_eq!(space.element_id(&TestElement(0)), Some(id));
+        assert_eq!(space.element_for_id(id), Some(&TestElement(0)));
+        space.unmap_elem(&TestElement(0));
+        assert_eq!(space.element_for_id(id), None);
+    }
+
+    #[test]
+    fn groups_move_together() {
+        let mut space = space_with(5);
+        let group = space.create_stacking_group();
+        space.set_stacking_group(&TestElement(0), Some(group));
+        space.set_stacking_group(&TestElement(2), Some(group));
+        assert_eq!(order(&space), [0, 2, 1, 3, 4]);
+
+        space.raise_element(&TestElement(0), false);
+        assert_eq!(order(&space), [1, 3, 4, 0, 2]);
+        space.lower_element_below(&TestElement(2), &TestElement(3));
+        assert_eq!(order(&space), [1, 0, 2, 3, 4]);
+        space.raise_element_above(&TestElement(4), &TestElement(0));
+        assert_eq!(order(&space), [1, 0, 2, 4, 3]);
+    }
+
+    #[test]
+    fn always_on_top() {
+        let mut space = space_with(3);
+        space.set_always_on_top(&TestElement(0), true);
+        assert_eq!(order(&space), [1, 2, 0]);
+        space.raise_element(&TestElement(1), false);
+        assert_eq!(order(&space), [2, 1, 0]);
+        space.set_always_on_top(&TestElement(0), false);
+        assert_eq!(order(&space), [2, 1, 0]);
+        space.raise_element(&TestElement(0), false);
+        assert_eq!(order(&space), [2, 1, 0]);
+        space.lower_element(&TestElement(0));
+        assert_eq!(order(&space), [0, 2, 1]);
+    }
+
+    fn test_output(size: i32, scale: f64) -> Output {
+        let output = crate::output::test_output("test", (size, size));
+        output.change_current_state(None, None, Some(crate::output::Scale::Fractional(scale)), None);
+        output
+    }
+
+    #[test]
+    fn element_under_subpixel() {
+        let mut space = space_with(1);
+        let output = test_output(100, 1.5);
+        space.map_output(&output, (0, 0));
+        space.relocate_element(&TestElement(0), (10.5, 0.25));
+
+        let point = Point::<f64, Logical>::from((12.25, 5.0));
+        let (_, location) = space.element_under_f64(point).unwrap();
+        assert_eq!(location, Point::from((10.5, 0.25)));
+        assert_eq!(point - location, Point::from((1.75, 4.75)));
+
+        // input stays aligned with the rendered element at fractional scales
+        let rendered = space
+            .element_render_location(&TestElement(0), &output)
+            .unwrap()
+            .to_f64();
+        let input = location.to_physical(1.5);
+        assert!((rendered.x - input.x).abs() < 0.5);
+        assert!((rendered.y - input.y).abs() < 0.5);
+    }
+
+    #[test]
+    fn visibility() {
+        let mut space = space_with(0);
+        let output = test_output(20, 1.0);
+        space.map_output(&output, (0, 0));
+
+        space.map_element(TestElement(0), (0, 0), false);
+        space.map_element(TestElement(1), (15, 15), false);
+        space.map_element(TestElement(2), (5, 0), false);
+        space.map_element(TestElement(3), (30, 30), false);
+
+        assert_eq!(
+            space.element_visibility(&TestElement(0)),
+            Some(Visibility::Partial(vec![Rectangle::new(
+                (0, 0).into(),
+                (5, 10).into()
+            )]))
+        );
+        assert_eq!(space.element_visibility(&TestElement(2)), Some(Visibility::Full));
+        assert_eq!(
+            space.element_visibility(&TestElement(1)),
+            Some(Visibility::Partial(vec![Rectangle::new(
+                (15, 15).into(),
+                (5, 5).into()
+            )]))
+        );
+        assert_eq!(
+            space.element_visibility(&TestElement(3)),
+            Some(Visibility::Occluded)
+        );
+        assert_eq!(space.element_visibility(&TestElement(4)), None);
+
+        space.map_element(TestElement(4), (0, 0), false);
+        assert_eq!(
+            space.element_visibility(&TestElement(0)),
+            Some(Visibility::Occluded)
+        );
+    }
+
+    #[test]
+    fn fractional_location() {
+        let mut space = space_with(1);
+        let output = test_output(100, 1.5);
+        space.map_output(&output, (0, 0));
+
+        space.relocate_element(&TestElement(0), (10.4, 20.0));
+        assert_eq!(space.element_location(&TestElement(0)), Some((10, 20).into()));
+        assert_eq!(
+            space.element_location_f64(&TestElement(0)),
+            Some((10.4, 20.0).into())
+        );
+        assert_eq!(
+            space.element_geometry_f64(&TestElement(0)),
+            Some(Rectangle::new((10.4, 20.0).into(), (10.0, 10.0).into()))
+        );
+        // rounded in physical coordinates, not snapped to the logical pixel grid
+        assert_eq!(
+            space.element_render_location(&TestElement(0), &output),
+            Some((16, 30).into())
+        );
+        space.relocate_element(&TestElement(0), (10.0, 20.0));
+        assert_eq!(
+            space.element_render_location(&TestElement(0), &output),
+            Some((15, 30).into())
+        );
+    }
+
+    #[cfg(feature = "wayland_frontend")]
+    #[test]
+    fn preferred_buffer_state_is_opt_in() {
+        use crate::wayland::test_utils::{ClientEvent, Fixture};
+        use wayland_client::Proxy;
+
+        let mut fixture = Fixture::new();
+        let (surface, server) = fixture.create_surface();
+        let output = test_output(100, 2.0);
+        output.enter(&server);
+        let mut space = space_with(0);
+        space.map_output(&output, (0, 0));
+
+        space.refresh();
+        fixture.roundtrip();
+        assert!(fixture.client.events.is_empty());
+
+        space.set_preferred_buffer_state_policy(default_preferred_buffer_state);
+        space.refresh();
+        fixture.roundtrip();
+        assert_eq!(
+            fixture.client.events,
+            [ClientEvent::PreferredBufferScale(surface.id().protocol_id(), 2)]
+        );
+
+        space.disable_preferred_buffer_state();
+        output.change_current_state(None, None, Some(crate::output::Scale::Integer(3)), None);
+        space.refresh();
+        fixture.roundtrip();
+        assert_eq!(fixture.client.events.len(), 1);
+    }
+}
@@ -0,0 +1,91 @@
+use crate::{
+    backend::renderer::{
+        element::{surface::WaylandSurfaceRenderElement, AsRenderElements},
+        ImportAll, Renderer,
+    },
+    desktop::{space::SpaceElement, WindowGroup},
+    output::Output,
+    utils::{Logical, Physical, Point, Rectangle, Scale},
+};
+
+impl SpaceElement for WindowGroup {
+    fn geometry(&self) -> Rectangle<i32, Logical> {
+        self.geometry()
+    }
+
+    fn bbox(&self) -> Rectangle<i32, Logical> {
+        let geometry = self.geometry();
+        match self.active_window() {
+            Some(window) => SpaceElement::bbox(&window).merge(geometry),
+            None => geometry,
+        }
+    }
+
+    fn is_in_input_region(&self, point: &Point<f64, Logical>) -> bool {
+        self.tab_bar_geometry().to_f64().contains(*point)
+            || self
+                .active_window()
+                .map(|window| SpaceElement::is_in_input_region(&window, point))
+                .unwrap_or(false)
+    }
+
+    fn opaque_regions(&self) -> Vec<Rectangle<i32, Logical>> {
+        self.active_window()
+            .map(|window| SpaceElement::opaque_regions(&window))
+            .unwrap_or_default()
+    }
+
+    fn z_index(&self) -> u8 {
+        self.active_window()
+            .map(|window| SpaceElement::z_index(&window))
+            .unwrap_or_else(|| crate::desktop::space::RenderZindex::Shell as u8)
+    }
+
+    fn set_activate(&self, activated: bool) {
+        let active = self.active_window();
+        for window in self.windows() {
+            window.set_activated(activated && Some(&window) == active.as_ref());
+        }
+    }
+
+    fn output_enter(&self, output: &Output, overlap: Rectangle<i32, Logical>) {
+        for window in self.windows() {
+            SpaceElement::output_enter(&window, output, overlap);
+        }
+    }
+
+    fn output_leave(&self, output: &Output) {
+        for window in self.windows() {
+            SpaceElement::output_leave(&window, output);
+        }
+    }
+
+    fn refresh(&self) {
+        WindowGroup::refresh(self);
+        for window in self.windows() {
+            SpaceElement::refresh(&window);
+        }
+    }
+}
+
+impl<R> AsRenderElements<R> for WindowGroup
+where
+    R: Renderer + ImportAll,
+    R::TextureId: Clone + 'static,
+{
+    type RenderElement = WaylandSurfaceRenderElement<R>;
+
+    fn render_elements<C: From<WaylandSurfaceRenderElement<R>>>(
+        &self,
+        renderer: &mut R,
+        location: Point<i32, Physical>,
+        scale: Scale<f64>,
+        alpha: f32,
+    ) -> Vec<C> {
+        // the group shares the coordinate space of the active window,
+        // the tab bar is left to the compositor
+        self.active_window()
+            .map(|window| window.render_elements(renderer, location, scale, alpha))
+            .unwrap_or_default()
+    }
+}
@@ -1,15 +1,19 @@
 use std::{cell::RefCell, collections::HashMap};
 
 use tracing::instrument;
-use wayland_server::protocol::wl_surface::WlSurface;
+use wayland_server::{backend::ObjectId, protocol::wl_surface::WlSurface, Resource};
 
 use crate::{
     backend::renderer::utils::RendererSurfaceStateUserData,
     output::{Output, WeakOutput},
-    utils::{Logical, Point, Rectangle},
-    wayland::compositor::{with_surface_tree_downward, TraversalAction},
+    utils::{Logical, Point, Rectangle, Transform},
+    wayland::{
+        compositor::{self, with_surface_tree_downward, TraversalAction},
+        fractional_scale::FractionalScaleStateUserData,
+    },
 };
 
+mod group;
 mod layer;
 mod window;
 #[cfg(feature = "xwayland")]
@@ -80,8 +84,68 @@ pub fn output_update(output: &Output, output_overlap: Option<Rectangle<i32, Logi
     );
 }
 
+/// Policy choosing the preferred buffer scale and transform of a surface
+///
+/// Called with a surface and all outputs of a [`Space`](crate::desktop::Space) the surface entered.
+/// Returning `None` leaves the last sent preferences untouched.
+pub type PreferredBufferStatePolicy = dyn Fn(&WlSurface, &[Output]) -> Option<(i32, Transform)> + Send + Sync;
+
+/// Default [`PreferredBufferStatePolicy`]
+///
+/// Prefers the highest integer scale of all entered outputs and the transform of that output.
+pub fn default_preferred_buffer_state(_surface: &WlSurface, outputs: &[Output]) -> Option<(i32, Transform)> {
+    outputs
+        .iter()
+        .max_by_key(|output| output.current_scale().integer_scale())
+        .map(|output| (output.current_scale().integer_scale(), output.current_transform()))
+}
+
+pub(crate) struct BufferStatePolicy(pub(crate) Box<PreferredBufferStatePolicy>);
+
+impl std::fmt::Debug for BufferStatePolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BufferStatePolicy").finish_non_exhaustive()
+    }
+}
+
+/// Sends `wl_surface.preferred_buffer_scale` and `wl_surface.preferred_buffer_transform`
+/// to all surfaces entered on the given outputs according to `policy`.
+///
+/// Surfaces using fractional-scale are skipped, as they receive their preferred scale
+/// through [`fractional_scale`](crate::wayland::fractional_scale) instead.
+#[profiling::function]
+pub(crate) fn update_preferred_buffer_state(outputs: &[Output], policy: &PreferredBufferStatePolicy) {
+    let mut surfaces: HashMap<ObjectId, (WlSurface, Vec<Output>)> = HashMap::new();
+    for output in outputs {
+        for surface in output.entered_surfaces() {
+            surfaces
+                .entry(surface.id())
+                .or_insert_with(|| (surface, Vec::new()))
+                .1
+                .push(output.clone());
+        }
+    }
+
+    for (surface, outputs) in surfaces.into_values() {
+        let Some((scale, transform)) = policy(&surface, &outputs) else {
+            continue;
+        };
+        compositor::with_states(&surface, |states| {
+            let fractional_scale_aware = states
+                .data_map
+                .get::<FractionalScaleStateUserData>()
+                .is_some_and(|state| state.borrow().is_fractional_scale_aware());
+            if !fractional_scale_aware {
+                compositor::send_surface_state(&surface, states, scale, transform);
+            }
+        });
+    }
+}
+
 #[derive(Debug, Default)]
 struct WindowOutputState {
     output_overlap: HashMap<WeakOutput, Rectangle<i32, Logical>>,
+    // the window was suspended, because all its outputs are powered off
+    power_suspended: bool,
 }
 type WindowOutputUserData = RefCell<WindowOutputState>;
@@ -6,9 +6,12 @@ use crate::{
         },
         ImportAll, Renderer,
     },
-    desktop::{space::SpaceElement, PopupManager, Window, WindowSurface, WindowSurfaceType},
+    desktop::{
+        space::SpaceElement, utils::opaque_regions_from_surface_tree, PopupManager, Window, WindowSurface,
+        WindowSurfaceType,
+    },
     output::Output,
-    utils::{Logical, Physical, Point, Rectangle, Scale},
+    utils::{Buffer, Logical, Physical, Point, Rectangle, Scale, Size, Transform},
     wayland::seat::WaylandFocus,
 };
 
@@ -27,6 +30,12 @@ impl SpaceElement for Window {
         self.surface_under(*point, WindowSurfaceType::ALL).is_some()
     }
 
+    fn opaque_regions(&self) -> Vec<Rectangle<i32, Logical>> {
+        self.wl_surface()
+            .map(|surface| opaque_regions_from_surface_tree(&surface, (0, 0)))
+            .unwrap_or_default()
+    }
+
     fn z_index(&self) -> u8 {
         self.0.z_index.load(std::sync::atomic::Ordering::SeqCst)
     }
@@ -67,7 +76,11 @@ impl SpaceElement for Window {
     #[profiling::function]
     fn refresh(&self) {
         self.user_data().insert_if_missing(WindowOutputUserData::default);
-        let state = self.user_data().get::<WindowOutputUserData>().unwrap().borrow();
+        let mut state = self
+            .user_data()
+            .get::<WindowOutputUserData>()
+            .unwrap()
+            .borrow_mut();
 
         if let Some(surface) = self.wl_surface() {
             for (weak, overlap) in state.output_overlap.iter() {
@@ -81,6 +94,60 @@ impl SpaceElement for Window {
                 }
             }
         }
+
+        // Suspend windows only shown on powered off outputs, but leave windows
+        // suspended by the compositor alone
+        let mut outputs = state
+            .output_overlap
+            .keys()
+            .filter_map(|weak| weak.upgrade())
+            .peekable();
+        let suspended = outputs.peek().is_some() && outputs.all(|output| !output.is_powered());
+        if suspended != state.power_suspended {
+            if let Some(toplevel) = self.toplevel() {
+                let changed = self.set_suspended(suspended);
+                if changed && toplevel.is_initial_configure_sent() {
+                    toplevel.send_pending_configure();
+                }
+                state.power_suspended = suspended && changed;
+            }
+        }
+    }
+}
+
+impl Window {
+    /// Returns the size of a buffer to capture the window into at the given scale
+    ///
+    /// The size matches the [geometry](Window::geometry) of the window, excluding decorations
+    /// drawn by the client and popups extending beyond it.
+    pub fn capture_size(&self, scale: impl Into<Scale<f64>>) -> Size<i32, Buffer> {
+        self.geometry()
+            .size
+            .to_f64()
+            .to_buffer(scale.into(), Transform::Normal)
+            .to_i32_round()
+    }
+
+    /// Retrieve the render elements to capture the window off-screen
+    ///
+    /// The elements are positioned for a buffer of [`Window::capture_size`], with the
+    /// [geometry](Window::geometry) of the window starting at its origin. They can be rendered
+    /// using a dedicated [`OutputDamageTracker`](crate::backend::renderer::damage::OutputDamageTracker)
+    /// with a static mode of the capture size.
+    pub fn capture_render_elements<R, C>(
+        &self,
+        renderer: &mut R,
+        scale: impl Into<Scale<f64>>,
+        alpha: f32,
+    ) -> Vec<C>
+    where
+        R: Renderer + ImportAll,
+        R::TextureId: Clone + 'static,
+        C: From<WaylandSurfaceRenderElement<R>>,
+    {
+        let scale = scale.into();
+        let location = self.geometry().loc.upscale(-1).to_physical_precise_round(scale);
+        AsRenderElements::render_elements(self, renderer, location, scale, alpha)
     }
 }
 
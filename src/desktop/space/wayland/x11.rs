@@ -10,7 +10,11 @@ use crate::{
         },
         ImportAll, Renderer,
     },
-    desktop::{space::SpaceElement, utils::under_from_surface_tree, WindowSurfaceType},
+    desktop::{
+        space::SpaceElement,
+        utils::{opaque_regions_from_surface_tree, under_from_surface_tree},
+        WindowSurfaceType,
+    },
     utils::{Logical, Physical, Point, Rectangle, Scale},
     wayland::seat::WaylandFocus,
     xwayland::X11Surface,
@@ -40,6 +44,15 @@ impl SpaceElement for X11Surface {
         }
     }
 
+    fn opaque_regions(&self) -> Vec<Rectangle<i32, Logical>> {
+        let state = self.state.lock().unwrap();
+        state
+            .wl_surface
+            .as_ref()
+            .map(|surface| opaque_regions_from_surface_tree(surface, (0, 0)))
+            .unwrap_or_default()
+    }
+
     fn set_activate(&self, activated: bool) {
         let _ = self.set_activated(activated);
     }
@@ -0,0 +1,375 @@
+use crate::{
+    output::Output,
+    utils::{Logical, Point},
+};
+
+use super::{Space, SpaceElement};
+
+/// Set of workspaces sharing the same outputs
+///
+/// Every workspace is represented by its own [`Space`], only the active one has the outputs
+/// mapped. Elements marked as sticky are moved into an additional [`Space`], that always has
+/// the outputs mapped and is thus visible regardless of the active workspace. Sticky elements
+/// are stacked above the elements of the active workspace.
+///
+/// Pass [`Workspaces::spaces`] to [`space_render_elements`](super::space_render_elements) or
+/// [`render_output`](super::render_output) to render the visible elements. Because sticky
+/// elements keep their render elements when switching workspaces, the damage tracker only
+/// damages the regions of elements that appeared or disappeared.
+#[derive(Debug)]
+pub struct Workspaces<E: SpaceElement> {
+    workspaces: Vec<Space<E>>,
+    sticky: Space<E>,
+    active: usize,
+}
+
+impl<E: SpaceElement + PartialEq> Workspaces<E> {
+    /// Creates a new set of workspaces
+    ///
+    /// # Panics
+    ///
+    /// Panics if `count` is zero.
+    pub fn new(count: usize) -> Self {
+        assert!(count > 0, "at least one workspace is required");
+        Workspaces {
+            workspaces: (0..count).map(|_| Space::default()).collect(),
+            sticky: Space::default(),
+            active: 0,
+        }
+    }
+
+    /// Number of workspaces
+    pub fn len(&self) -> usize {
+        self.workspaces.len()
+    }
+
+    /// Always `false`, there is at least one workspace
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Appends a new workspace and returns its index
+    pub fn add_workspace(&mut self) -> usize {
+        self.workspaces.push(Space::default());
+        self.workspaces.len() - 1
+    }
+
+    /// Index of the active workspace
+    pub fn active(&self) -> usize {
+        self.active
+    }
+
+    /// [`Space`] of the active workspace
+    pub fn active_space(&self) -> &Space<E> {
+        &self.workspaces[self.active]
+    }
+
+    /// Mutable [`Space`] of the active workspace
+    pub fn active_space_mut(&mut self) -> &mut Space<E> {
+        &mut self.workspaces[self.active]
+    }
+
+    /// [`Space`] of the workspace at the given index
+    pub fn workspace(&self, idx: usize) -> Option<&Space<E>> {
+        self.workspaces.get(idx)
+    }
+
+    /// Mutable [`Space`] of the workspace at the given index
+    pub fn workspace_mut(&mut self, idx: usize) -> Option<&mut Space<E>> {
+        self.workspaces.get_mut(idx)
+    }
+
+    /// [`Space`] containing the sticky elements
+    pub fn sticky_space(&self) -> &Space<E> {
+        &self.sticky
+    }
+
+    /// Mutable [`Space`] containing the sticky elements
+    ///
+    /// Elements mapped directly into this space are sticky as well.
+    pub fn sticky_space_mut(&mut self) -> &mut Space<E> {
+        &mut self.sticky
+    }
+
+    /// Currently visible spaces, top to bottom
+    ///
+    /// Yields the sticky space followed by the active workspace.
+    pub fn spaces(&self) -> impl Iterator<Item = &Space<E>> {
+        [&self.sticky, &self.workspaces[self.active]].into_iter()
+    }
+
+    /// Maps an [`Output`] into the visible spaces
+    ///
+    /// Can be safely called on an already mapped [`Output`] to update its location.
+    pub fn map_output<P: Into<Point<i32, Logical>>>(&mut self, output: &Output, location: P) {
+        let location = location.into();
+        self.sticky.map_output(output, location);
+        self.workspaces[self.active].map_output(output, location);
+    }
+
+    /// Unmaps an [`Output`] from the visible spaces
+    pub fn unmap_output(&mut self, output: &Output) {
+        self.sticky.unmap_output(output);
+        self.workspaces[self.active].unmap_output(output);
+    }
+
+    /// Switches the active workspace
+    ///
+    /// The outputs are moved from the previous to the new workspace, elements of both receive
+    /// the respective output leave and enter events. Sticky elements are untouched.
+    ///
+    /// Returns `false` if no workspace exists at the given index.
+    pub fn switch_to(&mut self, idx: usize) -> bool {
+        if idx >= self.workspaces.len() {
+            return false;
+        }
+        if idx == self.active {
+            return true;
+        }
+
+        let outputs = self.sticky.outputs().cloned().collect::<Vec<_>>();
+        let previous = &mut self.workspaces[self.active];
+        for output in &outputs {
+            previous.unmap_output(output);
+        }
+        previous.refresh();
+
+        self.active = idx;
+        let next = &mut self.workspaces[idx];
+        for output in &outputs {
+            if let Some(geo) = self.sticky.output_geometry(output) {
+                next.map_output(output, geo.loc);
+            }
+        }
+        next.refresh();
+        true
+    }
+
+    /// Returns whether the element is sticky
+    pub fn is_sticky(&self, element: &E) -> bool {
+        self.sticky.elements().any(|e| e == element)
+    }
+
+    /// Index of the workspace the element is mapped on, `None` for sticky or unmapped elements
+    pub fn workspace_of(&self, element: &E) -> Option<usize> {
+        self.workspaces
+            .iter()
+            .position(|space| space.elements().any(|e| e == element))
+    }
+
+    /// Finds the topmost visible element under this point
+    ///
+    /// See [`Space::element_under`].
+    pub fn element_under<P: Into<Point<f64, Logical>>>(&self, point: P) -> Option<(&E, Point<i32, Logical>)> {
+        let point = point.into();
+        self.sticky
+            .element_under(point)
+            .or_else(|| self.workspaces[self.active].element_under(point))
+    }
+
+    /// Refreshes the sticky space and all workspaces
+    ///
+    /// See [`Space::refresh`].
+    pub fn refresh(&mut self) {
+        self.sticky.refresh();
+        for space in &mut self.workspaces {
+            space.refresh();
+        }
+    }
+}
+
+impl<E: SpaceElement + PartialEq + Clone> Workspaces<E> {
+    /// Marks an element as sticky or moves it back to the active workspace
+    ///
+    /// Sticky elements are visible on all workspaces. The location of the element is kept,
+    /// unsticking places it on top of the active workspace.
+    ///
+    /// Does nothing for unmapped elements.
+    pub fn set_sticky(&mut self, element: &E, sticky: bool) {
+        let (from, to) = if sticky {
+            let Some(idx) = self.workspace_of(element) else {
+                return;
+            };
+            let (workspaces, sticky) = (&mut self.workspaces, &mut self.sticky);
+            (&mut workspaces[idx], sticky)
+        } else {
+            if !self.is_sticky(element) {
+                return;
+            }
+            (&mut self.sticky, &mut self.workspaces[self.active])
+        };
+
+        // the unwrap is safe, the element is mapped in `from`
+        let location = from.element_location_f64(element).unwrap();
+        let always_on_top = from.is_always_on_top(element);
+        from.unmap_elem(element);
+        to.map_element(element.clone(), location.to_i32_round(), false);
+        to.relocate_element(element, location);
+        to.set_always_on_top(element, always_on_top);
+        to.refresh();
+    }
+
+    /// Pins an element above all other elements on all workspaces
+    ///
+    /// Pinned elements are sticky and always on top, see [`Workspaces::set_sticky`] and
+    /// [`Space::set_always_on_top`]. Unpinning keeps the element sticky.
+    pub fn set_pinned(&mut self, element: &E, pinned: bool) {
+        if pinned {
+            self.set_sticky(element, true);
+        }
+        self.sticky.set_always_on_top(element, pinned);
+    }
+}
+
+#[cfg(all(test, feature = "renderer_test"))]
+mod tests {
+    use super::*;
+    use crate::{
+        backend::renderer::{
+            damage::OutputDamageTracker,
+            element::{
+                solid::{SolidColorBuffer, SolidColorRenderElement},
+                AsRenderElements, Kind,
+            },
+            test::{DummyFramebuffer, DummyRenderer},
+        },
+        desktop::space::{render_output, SpaceRenderElements},
+        output::test_output,
+        utils::{IsAlive, Physical, Rectangle, Scale},
+    };
+
+    #[derive(Debug, Clone)]
+    struct TestElement(u32, SolidColorBuffer);
+    impl PartialEq for TestElement {
+        fn eq(&self, other: &Self) -> bool {
+            self.0 == other.0
+        }
+    }
+    impl SpaceElement for TestElement {
+        fn bbox(&self) -> Rectangle<i32, Logical> {
+            Rectangle::from_size((10, 10).into())
+        }
+        fn is_in_input_region(&self, _point: &Point<f64, Logical>) -> bool {
+            true
+        }
+        fn set_activate(&self, _activated: bool) {}
+        fn output_enter(&self, _output: &Output, _overlap: Rectangle<i32, Logical>) {}
+        fn output_leave(&self, _output: &Output) {}
+    }
+    impl IsAlive for TestElement {
+        fn alive(&self) -> bool {
+            true
+        }
+    }
+    impl AsRenderElements<DummyRenderer> for TestElement {
+        type RenderElement = SolidColorRenderElement;
+        fn render_elements<C: From<Self::RenderElement>>(
+            &self,
+            _renderer: &mut DummyRenderer,
+            location: Point<i32, Physical>,
+            scale: Scale<f64>,
+            alpha: f32,
+        ) -> Vec<C> {
+            vec![
+                SolidColorRenderElement::from_buffer(&self.1, location, scale, alpha, Kind::Unspecified)
+                    .into(),
+            ]
+        }
+    }
+
+    fn element(id: u32) -> TestElement {
+        TestElement(id, SolidColorBuffer::new((10, 10), [1.0, 0.0, 0.0, 1.0]))
+    }
+
+    fn damage(
+        workspaces: &Workspaces<TestElement>,
+        output: &Output,
+        tracker: &mut OutputDamageTracker,
+    ) -> Vec<Rectangle<i32, Physical>> {
+        render_output::<_, SpaceRenderElements<DummyRenderer, SolidColorRenderElement>, _, _>(
+            output,
+            &mut DummyRenderer,
+            &mut DummyFramebuffer,
+            1.0,
+            1,
+            workspaces.spaces(),
+            &[],
+            tracker,
+            [0.0, 0.0, 0.0, 1.0],
+        )
+        .unwrap()
+        .damage
+        .cloned()
+        .unwrap_or_default()
+    }
+
+    #[test]
+    fn sticky_elements_survive_switch() {
+        let output = test_output("TEST-1", (100, 100));
+        let mut workspaces = Workspaces::new(2);
+        workspaces.map_output(&output, (0, 0));
+
+        let window = element(0);
+        let sticky = element(1);
+        workspaces
+            .active_space_mut()
+            .map_element(window.clone(), (0, 0), false);
+        workspaces
+            .active_space_mut()
+            .map_element(sticky.clone(), (50, 50), false);
+        workspaces.set_sticky(&sticky, true);
+        assert!(workspaces.is_sticky(&sticky));
+        assert_eq!(workspaces.workspace_of(&window), Some(0));
+        assert_eq!(
+            workspaces.sticky_space().element_location(&sticky),
+            Some((50, 50).into())
+        );
+
+        assert!(workspaces.switch_to(1));
+        assert!(!workspaces.switch_to(2));
+        assert_eq!(workspaces.active_space().outputs().count(), 1);
+        assert_eq!(workspaces.workspace(0).unwrap().outputs().count(), 0);
+        assert_eq!(
+            workspaces.element_under((55.0, 55.0)).map(|(e, _)| e),
+            Some(&sticky)
+        );
+        assert!(workspaces.element_under((5.0, 5.0)).is_none());
+
+        workspaces.set_sticky(&sticky, false);
+        assert_eq!(workspaces.workspace_of(&sticky), Some(1));
+    }
+
+    #[test]
+    fn switch_only_damages_workspace_elements() {
+        let output = test_output("TEST-1", (100, 100));
+        let mut tracker = OutputDamageTracker::from_output(&output);
+        let mut workspaces = Workspaces::new(2);
+        workspaces.map_output(&output, (0, 0));
+
+        let window = element(0);
+        let pinned = element(1);
+        workspaces
+            .active_space_mut()
+            .map_element(window.clone(), (0, 0), false);
+        workspaces
+            .active_space_mut()
+            .map_element(pinned.clone(), (50, 50), false);
+        workspaces.set_pinned(&pinned, true);
+        assert!(workspaces.sticky_space().is_always_on_top(&pinned));
+
+        damage(&workspaces, &output, &mut tracker);
+        assert!(damage(&workspaces, &output, &mut tracker).is_empty());
+
+        workspaces.switch_to(1);
+        assert_eq!(
+            damage(&workspaces, &output, &mut tracker),
+            vec![Rectangle::new((0, 0).into(), (10, 10).into())]
+        );
+
+        workspaces.switch_to(0);
+        assert_eq!(
+            damage(&workspaces, &output, &mut tracker),
+            vec![Rectangle::new((0, 0).into(), (10, 10).into())]
+        );
+    }
+}
@@ -0,0 +1,250 @@
+//! Frame callback backpressure for overloaded outputs
+//!
+//! If the composition of an output consistently misses its deadlines, e.g. because many clients
+//! are animating at the same time, [`FrameCallbackBackpressure`] reduces the rate of frame callbacks
+//! sent to surfaces, which are not focused, to half or a quarter of the refresh rate.
+//! Clients rendering in response to frame callbacks then submit fewer frames, taking load off the
+//! compositor and the gpu, while the focused surface keeps the full rate.
+//!
+//! The rate is reduced step by step after a number of missed deadlines and only restored after
+//! a longer run of frames meeting their deadline, to avoid oscillating between the levels.
+//!
+//! Use one [`FrameCallbackBackpressure`] per output and report every composited frame with
+//! [`FrameCallbackBackpressure::record_frame`]. When sending frame callbacks, wrap the function
+//! returning the primary scan-out output of a surface using [`FrameCallbackBackpressure::primary_scan_out_output`]:
+//!
+//! ```no_run
+//! # use std::time::Duration;
+//! # use smithay::desktop::{utils::surface_primary_scanout_output, Window};
+//! # use smithay::desktop::backpressure::FrameCallbackBackpressure;
+//! # use smithay::output::Output;
+//! # let (window, output, focused): (Window, Output, bool) = todo!();
+//! # let (missed_deadline, time): (bool, Duration) = todo!();
+//! let mut backpressure = FrameCallbackBackpressure::default();
+//!
+//! // after the frame was presented
+//! backpressure.record_frame(missed_deadline);
+//!
+//! window.send_frame(
+//!     &output,
+//!     time,
+//!     Some(Duration::from_secs(1)),
+//!     backpressure.primary_scan_out_output(focused, surface_primary_scanout_output),
+//! );
+//! ```
+
+use std::collections::VecDeque;
+
+use wayland_server::protocol::wl_surface::WlSurface;
+
+use crate::{output::Output, wayland::compositor::SurfaceData};
+
+/// Rate of frame callbacks for surfaces, which are not focused
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum BackpressureLevel {
+    /// Frame callbacks are sent every frame
+    #[default]
+    Full,
+    /// Frame callbacks are sent every second frame
+    Half,
+    /// Frame callbacks are sent every fourth frame
+    Quarter,
+}
+
+impl BackpressureLevel {
+    /// Returns how many frames are composited per frame callback
+    pub fn divisor(&self) -> u64 {
+        match self {
+            BackpressureLevel::Full => 1,
+            BackpressureLevel::Half => 2,
+            BackpressureLevel::Quarter => 4,
+        }
+    }
+
+    fn raise(self) -> Self {
+        match self {
+            BackpressureLevel::Full => BackpressureLevel::Half,
+            BackpressureLevel::Half | BackpressureLevel::Quarter => BackpressureLevel::Quarter,
+        }
+    }
+
+    fn lower(self) -> Self {
+        match self {
+            BackpressureLevel::Full | BackpressureLevel::Half => BackpressureLevel::Full,
+            BackpressureLevel::Quarter => BackpressureLevel::Half,
+        }
+    }
+}
+
+/// Thresholds of the [`FrameCallbackBackpressure`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BackpressureConfig {
+    /// Number of recent frames considered for raising the level
+    pub window: usize,
+    /// Number of missed deadlines within `window` raising the level
+    pub raise_after: usize,
+    /// Number of consecutive frames meeting their deadline lowering the level
+    pub lower_after: usize,
+    /// Highest level to apply
+    pub max_level: BackpressureLevel,
+}
+
+impl Default for BackpressureConfig {
+    fn default() -> Self {
+        BackpressureConfig {
+            window: 30,
+            raise_after: 10,
+            lower_after: 120,
+            max_level: BackpressureLevel::Quarter,
+        }
+    }
+}
+
+/// Tracks missed deadlines of an output to reduce the rate of frame callbacks
+///
+/// See the [module docs](self) for details.
+#[derive(Debug, Default)]
+pub struct FrameCallbackBackpressure {
+    config: BackpressureConfig,
+    level: BackpressureLevel,
+    recent: VecDeque<bool>,
+    missed: usize,
+    on_time: usize,
+    frame: u64,
+}
+
+impl FrameCallbackBackpressure {
+    /// Create a new backpressure tracker with the given thresholds
+    pub fn new(config: BackpressureConfig) -> Self {
+        FrameCallbackBackpressure {
+            config,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the current level
+    pub fn level(&self) -> BackpressureLevel {
+        self.level
+    }
+
+    /// Record a composited frame, returning the level for the next frame
+    ///
+    /// `missed_deadline` should be `true`, if the frame was not ready in time for the
+    /// vblank it was supposed to be presented at.
+    pub fn record_frame(&mut self, missed_deadline: bool) -> BackpressureLevel {
+        self.frame = self.frame.wrapping_add(1);
+
+        self.recent.push_back(missed_deadline);
+        if missed_deadline {
+            self.missed += 1;
+            self.on_time = 0;
+        } else {
+            self.on_time += 1;
+        }
+        while self.recent.len() > self.config.window {
+            if self.recent.pop_front() == Some(true) {
+                self.missed -= 1;
+            }
+        }
+
+        if self.missed >= self.config.raise_after.max(1) && self.level < self.config.max_level {
+            self.level = self.level.raise();
+            // require new evidence before raising further
+            self.recent.clear();
+            self.missed = 0;
+        } else if self.on_time >= self.config.lower_after.max(1) && self.level > BackpressureLevel::Full {
+            self.level = self.level.lower();
+            self.on_time = 0;
+        }
+
+        self.level
+    }
+
+    /// Reset to full rate, e.g. after the output was reconfigured
+    pub fn reset(&mut self) {
+        *self = FrameCallbackBackpressure::new(self.config);
+    }
+
+    /// Returns whether surfaces, which are not focused, should receive frame callbacks this frame
+    pub fn send_unfocused_frames(&self) -> bool {
+        self.frame % self.level.divisor() == 0
+    }
+
+    /// Wraps a function returning the primary scan-out output of a surface
+    ///
+    /// The returned function reports no primary scan-out output for surfaces that are not `focused`
+    /// on frames skipped according to the current level. Frame callbacks for these surfaces are then
+    /// only sent, if they are overdue according to the throttle passed to e.g.
+    /// [`Window::send_frame`](crate::desktop::Window::send_frame).
+    pub fn primary_scan_out_output<F>(
+        &self,
+        focused: bool,
+        mut primary_scan_out_output: F,
+    ) -> impl FnMut(&WlSurface, &SurfaceData) -> Option<Output> + Copy
+    where
+        F: FnMut(&WlSurface, &SurfaceData) -> Option<Output> + Copy,
+    {
+        let send = focused || self.send_unfocused_frames();
+        move |surface, states| {
+            if send {
+                primary_scan_out_output(surface, states)
+            } else {
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hysteresis() {
+        let mut backpressure = FrameCallbackBackpressure::new(BackpressureConfig {
+            window: 10,
+            raise_after: 3,
+            lower_after: 20,
+            max_level: BackpressureLevel::Quarter,
+        });
+
+        // occasional misses are tolerated
+        for i in 0..30 {
+            assert_eq!(backpressure.record_frame(i % 5 == 0), BackpressureLevel::Full);
+        }
+
+        for _ in 0..3 {
+            backpressure.record_frame(true);
+        }
+        assert_eq!(backpressure.level(), BackpressureLevel::Half);
+        for _ in 0..3 {
+            backpressure.record_frame(true);
+        }
+        assert_eq!(backpressure.level(), BackpressureLevel::Quarter);
+        for _ in 0..10 {
+            backpressure.record_frame(true);
+        }
+        assert_eq!(backpressure.level(), BackpressureLevel::Quarter);
+        let mut sent = 0;
+        for _ in 0..4 {
+            backpressure.record_frame(true);
+            if backpressure.send_unfocused_frames() {
+                sent += 1;
+            }
+        }
+        assert_eq!(sent, 1);
+
+        // recovering takes a longer run of frames meeting the deadline
+        for _ in 0..19 {
+            backpressure.record_frame(false);
+        }
+        assert_eq!(backpressure.level(), BackpressureLevel::Quarter);
+        backpressure.record_frame(false);
+        assert_eq!(backpressure.level(), BackpressureLevel::Half);
+        for _ in 0..20 {
+            backpressure.record_frame(false);
+        }
+        assert_eq!(backpressure.level(), BackpressureLevel::Full);
+        assert!(backpressure.send_unfocused_frames());
+    }
+}
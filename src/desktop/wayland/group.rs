@@ -0,0 +1,413 @@
+use crate::{
+    utils::{IsAlive, Logical, Point, Rectangle, Size},
+    wayland::seat::WaylandFocus,
+};
+use std::{
+    borrow::Cow,
+    hash::{Hash, Hasher},
+    sync::{Arc, Mutex},
+};
+use wayland_server::protocol::wl_surface;
+
+use super::window::{Window, WindowSurface};
+
+crate::utils::ids::id_gen!(window_group_id);
+
+#[derive(Debug)]
+struct WindowGroupState {
+    windows: Vec<Window>,
+    active: usize,
+    tab_bar_height: i32,
+}
+
+#[derive(Debug)]
+pub(crate) struct WindowGroupInner {
+    id: usize,
+    state: Mutex<WindowGroupState>,
+}
+
+impl Drop for WindowGroupInner {
+    fn drop(&mut self) {
+        window_group_id::remove(self.id);
+    }
+}
+
+/// A group of [`Window`]s sharing a single geometry slot
+///
+/// Only the active window of a group is displayed, while the remaining windows
+/// are represented by tabs inside a tab bar above the active window.
+/// This can be used to implement tabbed or stacked layouts.
+///
+/// The tab bar is not rendered by the group itself. Compositors are expected to draw it
+/// as part of their decorations using [`WindowGroup::tab_bar_geometry`] and
+/// [`WindowGroup::tab_geometries`].
+///
+/// A group can be mapped onto a [`Space`](crate::desktop::Space) like a single window.
+#[derive(Debug, Clone)]
+pub struct WindowGroup(pub(crate) Arc<WindowGroupInner>);
+
+impl PartialEq for WindowGroup {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.0.id == other.0.id
+    }
+}
+
+impl Eq for WindowGroup {}
+
+impl Hash for WindowGroup {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.id.hash(state);
+    }
+}
+
+impl IsAlive for WindowGroup {
+    #[inline]
+    fn alive(&self) -> bool {
+        self.0.state.lock().unwrap().windows.iter().any(|w| w.alive())
+    }
+}
+
+impl WindowGroup {
+    /// Create a new group containing a single window
+    pub fn new(window: Window) -> WindowGroup {
+        WindowGroup(Arc::new(WindowGroupInner {
+            id: window_group_id::next(),
+            state: Mutex::new(WindowGroupState {
+                windows: vec![window],
+                active: 0,
+                tab_bar_height: 0,
+            }),
+        }))
+    }
+
+    /// Adds a window to the end of this group
+    ///
+    /// Does nothing if the window is already part of the group, besides activating it if requested.
+    pub fn add_window(&self, window: Window, activate: bool) {
+        let mut state = self.0.state.lock().unwrap();
+        let idx = match state.windows.iter().position(|w| w == &window) {
+            Some(idx) => idx,
+            None => {
+                state.windows.push(window);
+                state.windows.len() - 1
+            }
+        };
+        if activate {
+            state.active = idx;
+        }
+    }
+
+    /// Removes a window from this group
+    ///
+    /// Returns `false` if the window was not part of the group.
+    /// If the removed window was active, the next window of the group becomes active.
+    pub fn remove_window(&self, window: &Window) -> bool {
+        let mut state = self.0.state.lock().unwrap();
+        let Some(idx) = state.windows.iter().position(|w| w == window) else {
+            return false;
+        };
+        state.windows.remove(idx);
+        if state.active > idx || state.active >= state.windows.len() {
+            state.active = state.active.saturating_sub(1);
+        }
+        true
+    }
+
+    /// Returns the windows of this group in tab order
+    pub fn windows(&self) -> Vec<Window> {
+        self.0.state.lock().unwrap().windows.clone()
+    }
+
+    /// Returns `true` if the window is part of this group
+    pub fn contains(&self, window: &Window) -> bool {
+        self.0.state.lock().unwrap().windows.contains(window)
+    }
+
+    /// Returns the currently displayed window of this group
+    ///
+    /// Returns `None` only if all windows were removed from the group.
+    pub fn active_window(&self) -> Option<Window> {
+        let state = self.0.state.lock().unwrap();
+        state.windows.get(state.active).cloned()
+    }
+
+    /// Sets the currently displayed window of this group
+    ///
+    /// Returns `false` if the window is not part of the group.
+    ///
+    /// *Note*: This does not change the activated state of the windows,
+    /// see [`SpaceElement::set_activate`](crate::desktop::space::SpaceElement::set_activate).
+    pub fn set_active_window(&self, window: &Window) -> bool {
+        let mut state = self.0.state.lock().unwrap();
+        match state.windows.iter().position(|w| w == window) {
+            Some(idx) => {
+                state.active = idx;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Makes the next window in tab order active, wrapping around at the end
+    ///
+    /// Returns the new active window.
+    pub fn focus_next(&self) -> Option<Window> {
+        let mut state = self.0.state.lock().unwrap();
+        if state.windows.is_empty() {
+            return None;
+        }
+        state.active = (state.active + 1) % state.windows.len();
+        state.windows.get(state.active).cloned()
+    }
+
+    /// Makes the previous window in tab order active, wrapping around at the start
+    ///
+    /// Returns the new active window.
+    pub fn focus_previous(&self) -> Option<Window> {
+        let mut state = self.0.state.lock().unwrap();
+        if state.windows.is_empty() {
+            return None;
+        }
+        state.active = state.active.checked_sub(1).unwrap_or(state.windows.len() - 1);
+        state.windows.get(state.active).cloned()
+    }
+
+    /// Returns the height of the tab bar reserved above the active window
+    pub fn tab_bar_height(&self) -> i32 {
+        self.0.state.lock().unwrap().tab_bar_height
+    }
+
+    /// Sets the height of the tab bar reserved above the active window
+    ///
+    /// Defaults to zero, which hides the tab bar.
+    pub fn set_tab_bar_height(&self, height: i32) {
+        self.0.state.lock().unwrap().tab_bar_height = height.max(0);
+    }
+
+    /// Returns the geometry of this group, made up of the tab bar and the geometry of the active window.
+    pub fn geometry(&self) -> Rectangle<i32, Logical> {
+        let (window, tab_bar_height) = {
+            let state = self.0.state.lock().unwrap();
+            (state.windows.get(state.active).cloned(), state.tab_bar_height)
+        };
+        let Some(window) = window else {
+            return Rectangle::zero();
+        };
+        let mut geometry = window.geometry();
+        geometry.loc.y -= tab_bar_height;
+        geometry.size.h += tab_bar_height;
+        geometry
+    }
+
+    /// Returns the geometry of the tab bar relative to the group
+    pub fn tab_bar_geometry(&self) -> Rectangle<i32, Logical> {
+        let mut geometry = self.geometry();
+        geometry.size.h = self.tab_bar_height();
+        geometry
+    }
+
+    /// Returns the geometry of the tab of every window relative to the group
+    ///
+    /// The tab bar is split evenly between all windows in tab order.
+    pub fn tab_geometries(&self) -> Vec<(Window, Rectangle<i32, Logical>)> {
+        let bar = self.tab_bar_geometry();
+        let windows = self.windows();
+        let count = windows.len() as i32;
+        windows
+            .into_iter()
+            .enumerate()
+            .map(|(idx, window)| {
+                let idx = idx as i32;
+                let x = bar.size.w * idx / count;
+                let width = bar.size.w * (idx + 1) / count - x;
+                let loc = bar.loc + Point::from((x, 0));
+                (window, Rectangle::new(loc, (width, bar.size.h).into()))
+            })
+            .collect()
+    }
+
+    /// Returns the window whose tab is at a given point relative to the group, if any
+    pub fn tab_under<P: Into<Point<f64, Logical>>>(&self, point: P) -> Option<Window> {
+        let point = point.into();
+        self.tab_geometries()
+            .into_iter()
+            .find(|(_, geo)| geo.to_f64().contains(point))
+            .map(|(window, _)| window)
+    }
+
+    /// Configures all windows of the group for a new size of the group
+    ///
+    /// The size includes the tab bar. Every window receives the remaining size,
+    /// so switching between tabs does not require another roundtrip.
+    pub fn configure(&self, size: Size<i32, Logical>) {
+        let content_size = Size::from((size.w, (size.h - self.tab_bar_height()).max(0)));
+        for window in self.windows() {
+            match window.underlying_surface() {
+                WindowSurface::Wayland(toplevel) => {
+                    toplevel.with_pending_state(|state| state.size = Some(content_size));
+                    toplevel.send_pending_configure();
+                }
+                #[cfg(feature = "xwayland")]
+                WindowSurface::X11(surface) => {
+                    let geometry = Rectangle::new(surface.geometry().loc, content_size);
+                    if let Err(err) = surface.configure(geometry) {
+                        tracing::warn!(?err, "Failed to configure X11 window of group");
+                    }
+                }
+            }
+        }
+    }
+
+    /// Removes dead windows from the group
+    pub fn refresh(&self) {
+        let mut state = self.0.state.lock().unwrap();
+        let active = state.windows.get(state.active).cloned();
+        state.windows.retain(|w| w.alive());
+        state.active = active
+            .and_then(|active| state.windows.iter().position(|w| w == &active))
+            .unwrap_or(0)
+            .min(state.windows.len().saturating_sub(1));
+    }
+}
+
+impl WaylandFocus for WindowGroup {
+    #[inline]
+    fn wl_surface(&self) -> Option<Cow<'_, wl_surface::WlSurface>> {
+        self.active_window()
+            .and_then(|window| window.wl_surface().map(Cow::into_owned))
+            .map(Cow::Owned)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wayland_protocols::xdg::shell::client::xdg_wm_base::XdgWmBase;
+
+    use super::*;
+    use crate::wayland::test_utils::Fixture;
+
+    fn window(fixture: &mut Fixture) -> Window {
+        let (surface, toplevel) = fixture.create_toplevel();
+        fixture.attach_buffer(&surface, 100, 50);
+        surface.commit();
+        fixture.roundtrip();
+
+        let window = Window::new_wayland_window(toplevel);
+        window.on_commit();
+        window
+    }
+
+    fn group_of(windows: &[Window]) -> WindowGroup {
+        let group = WindowGroup::new(windows[0].clone());
+        for window in &windows[1..] {
+            group.add_window(window.clone(), false);
+        }
+        group
+    }
+
+    #[test]
+    fn remove_keeps_active_window() {
+        let mut fixture = Fixture::new();
+        let windows = (0..4).map(|_| window(&mut fixture)).collect::<Vec<_>>();
+        let group = group_of(&windows);
+
+        group.set_active_window(&windows[2]);
+        assert!(group.remove_window(&windows[0]));
+        assert!(!group.remove_window(&windows[0]));
+        assert_eq!(group.active_window(), Some(windows[2].clone()));
+
+        // the next window takes over
+        assert!(group.remove_window(&windows[2]));
+        assert_eq!(group.active_window(), Some(windows[3].clone()));
+        // or the previous one, if the last window was removed
+        assert!(group.remove_window(&windows[3]));
+        assert_eq!(group.active_window(), Some(windows[1].clone()));
+
+        assert!(group.remove_window(&windows[1]));
+        assert_eq!(group.active_window(), None);
+        assert_eq!(group.focus_next(), None);
+    }
+
+    #[test]
+    fn focus_wraps_around() {
+        let mut fixture = Fixture::new();
+        let windows = (0..3).map(|_| window(&mut fixture)).collect::<Vec<_>>();
+        let group = group_of(&windows);
+
+        assert_eq!(group.focus_previous(), Some(windows[2].clone()));
+        assert_eq!(group.focus_next(), Some(windows[0].clone()));
+        assert_eq!(group.focus_next(), Some(windows[1].clone()));
+
+        // adding an existing window only activates it
+        group.add_window(windows[2].clone(), true);
+        assert_eq!(group.windows(), windows);
+        assert_eq!(group.focus_next(), Some(windows[0].clone()));
+    }
+
+    #[test]
+    fn tabs_split_the_tab_bar() {
+        let mut fixture = Fixture::new();
+        let windows = (0..3).map(|_| window(&mut fixture)).collect::<Vec<_>>();
+        let group = group_of(&windows);
+        assert_eq!(group.geometry(), Rectangle::from_size((100, 50).into()));
+
+        group.set_tab_bar_height(20);
+        assert_eq!(
+            group.geometry(),
+            Rectangle::new((0, -20).into(), (100, 70).into())
+        );
+        assert_eq!(
+            group
+                .tab_geometries()
+                .into_iter()
+                .map(|(_, geometry)| geometry)
+                .collect::<Vec<_>>(),
+            [
+                Rectangle::new((0, -20).into(), (33, 20).into()),
+                Rectangle::new((33, -20).into(), (33, 20).into()),
+                Rectangle::new((66, -20).into(), (34, 20).into()),
+            ]
+        );
+        assert_eq!(group.tab_under((50.0, -10.0)), Some(windows[1].clone()));
+        assert_eq!(group.tab_under((50.0, 10.0)), None);
+    }
+
+    #[test]
+    fn refresh_removes_dead_windows() {
+        let mut fixture = Fixture::new();
+        let windows = (0..2).map(|_| window(&mut fixture)).collect::<Vec<_>>();
+
+        // a window, whose client destroys it later on
+        let (surface, _) = fixture.create_surface();
+        let wm_base: XdgWmBase = fixture.bind(6);
+        let xdg_surface = wm_base.get_xdg_surface(&surface, &fixture.handle(), ());
+        let xdg_toplevel = xdg_surface.get_toplevel(&fixture.handle(), ());
+        surface.commit();
+        fixture.roundtrip();
+        let dead = Window::new_wayland_window(fixture.state.toplevels.last().unwrap().clone());
+
+        let group = WindowGroup::new(dead.clone());
+        group.add_window(windows[0].clone(), false);
+        group.add_window(windows[1].clone(), true);
+
+        xdg_toplevel.destroy();
+        xdg_surface.destroy();
+        surface.destroy();
+        fixture.roundtrip();
+        assert!(!dead.alive());
+        assert!(group.alive());
+
+        group.refresh();
+        assert_eq!(group.windows(), windows);
+        assert_eq!(group.active_window(), Some(windows[1].clone()));
+
+        // all windows are gone
+        group.remove_window(&windows[0]);
+        group.remove_window(&windows[1]);
+        group.refresh();
+        assert!(!group.alive());
+        assert_eq!(group.geometry(), Rectangle::zero());
+    }
+}
@@ -0,0 +1,138 @@
+//! Live previews of windows
+//!
+//! A [`WindowPreviewElement`] renders a scaled-down view of a [`Window`] into a given area,
+//! e.g. for dock hover previews or overview modes. The preview reuses the textures of the
+//! window surfaces, no copies are made. Damage of the window is forwarded scaled to the preview size.
+//!
+//! Every preview renders its elements with distinct ids, see [`Id::namespaced`], so previews can be
+//! shown alongside the window itself or other previews of the same window.
+
+use crate::{
+    backend::renderer::{
+        element::{
+            surface::{render_elements_from_surface_tree, WaylandSurfaceRenderElement},
+            utils::{
+                constrain_render_elements, ConstrainAlign, ConstrainScaleBehavior, CropRenderElement,
+                NamespacedRenderElement, RelocateRenderElement, RescaleRenderElement,
+            },
+            AsRenderElements, Id, Kind,
+        },
+        ImportAll, Renderer,
+    },
+    utils::{Logical, Physical, Point, Rectangle, Scale, Size},
+    wayland::seat::WaylandFocus,
+};
+
+use super::window::Window;
+
+/// Render element type of a [`WindowPreviewElement`]
+pub type WindowPreviewRenderElement<R> = CropRenderElement<
+    RelocateRenderElement<RescaleRenderElement<NamespacedRenderElement<WaylandSurfaceRenderElement<R>>>>,
+>;
+
+/// A scaled-down live view of a [`Window`]
+///
+/// The window is scaled to fit into the size of the preview keeping its aspect ratio
+/// and centered inside of it. Windows smaller than the preview are not scaled up.
+/// Popups of the window are not part of the preview.
+///
+/// The ids of the render elements are stable for the lifetime of the preview and shared by its clones.
+/// Keep the preview around instead of creating a new one every frame to avoid full damage.
+#[derive(Debug, Clone)]
+pub struct WindowPreviewElement {
+    window: Window,
+    size: Size<i32, Logical>,
+    namespace: Id,
+}
+
+impl WindowPreviewElement {
+    /// Create a new preview of a window with a given size
+    pub fn new(window: Window, size: Size<i32, Logical>) -> Self {
+        WindowPreviewElement {
+            window,
+            size,
+            namespace: Id::new(),
+        }
+    }
+
+    /// Returns the previewed window
+    pub fn window(&self) -> &Window {
+        &self.window
+    }
+
+    /// Returns the size of the preview
+    pub fn size(&self) -> Size<i32, Logical> {
+        self.size
+    }
+
+    /// Sets the size of the preview
+    pub fn set_size(&mut self, size: Size<i32, Logical>) {
+        self.size = size;
+    }
+
+    /// Returns the factor the window is scaled by to fit into the preview
+    pub fn preview_scale(&self) -> f64 {
+        let bbox = self.window.bbox();
+        if bbox.is_empty() {
+            return 1.0;
+        }
+        let scale = self.size.to_f64() / bbox.size.to_f64();
+        f64::min(scale.x, scale.y).min(1.0)
+    }
+}
+
+impl<R> AsRenderElements<R> for WindowPreviewElement
+where
+    R: Renderer + ImportAll,
+    R::TextureId: Clone + 'static,
+{
+    type RenderElement = WindowPreviewRenderElement<R>;
+
+    fn render_elements<C: From<Self::RenderElement>>(
+        &self,
+        renderer: &mut R,
+        location: Point<i32, Physical>,
+        scale: Scale<f64>,
+        alpha: f32,
+    ) -> Vec<C> {
+        let Some(surface) = self.window.wl_surface() else {
+            return Vec::new();
+        };
+        let bbox = self.window.bbox();
+        if bbox.is_empty() || self.size.is_empty() {
+            return Vec::new();
+        }
+
+        // render the window with the top-left corner of its bounding box at the location of the preview
+        let reference = bbox.to_physical_precise_round(scale);
+        let origin = location - reference.loc;
+        let elements = render_elements_from_surface_tree::<R, WaylandSurfaceRenderElement<R>>(
+            renderer,
+            &surface,
+            origin,
+            scale,
+            alpha,
+            Kind::Unspecified,
+        )
+        .into_iter()
+        .map(|element| NamespacedRenderElement::from_element(element, &self.namespace));
+
+        let constrain = Rectangle::new(location, self.size.to_physical_precise_round(scale));
+        let behavior = if self.preview_scale() < 1.0 {
+            ConstrainScaleBehavior::Fit
+        } else {
+            ConstrainScaleBehavior::CutOff
+        };
+        constrain_render_elements(
+            elements,
+            origin,
+            constrain,
+            reference,
+            behavior,
+            ConstrainAlign::CENTER,
+            scale,
+        )
+        .map(C::from)
+        .collect()
+    }
+}
@@ -7,18 +7,19 @@ use crate::{
         },
         utils::{RendererSurfaceState, RendererSurfaceStateUserData},
     },
-    desktop::WindowSurfaceType,
+    desktop::{layer_map_for_output, space::SpaceElement, PopupManager, Space, WindowSurfaceType},
     output::{Output, WeakOutput},
-    utils::{Logical, Point, Rectangle, Time},
+    utils::{Logical, Monotonic, Point, Rectangle, Time},
     wayland::{
         compositor::{with_surface_tree_downward, SurfaceAttributes, SurfaceData, TraversalAction},
         dmabuf::{DmabufFeedback, SurfaceDmabufFeedbackState},
         presentation::{PresentationFeedbackCachedState, PresentationFeedbackCallback, Refresh},
+        seat::WaylandFocus,
     },
 };
-use std::{cell::RefCell, sync::Mutex, time::Duration};
+use std::{cell::RefCell, collections::HashSet, sync::Mutex, time::Duration};
 use wayland_protocols::wp::presentation_time::server::wp_presentation_feedback;
-use wayland_server::protocol::wl_surface;
+use wayland_server::{backend::ObjectId, protocol::wl_surface, Resource};
 
 pub use super::super::space::wayland::output_update;
 
@@ -85,6 +86,46 @@ where
     bounding_box
 }
 
+/// Returns the opaque regions of a whole surface tree
+///
+/// - `location` can be used to offset the returned regions.
+pub fn opaque_regions_from_surface_tree<P>(
+    surface: &wl_surface::WlSurface,
+    location: P,
+) -> Vec<Rectangle<i32, Logical>>
+where
+    P: Into<Point<i32, Logical>>,
+{
+    let mut regions = Vec::new();
+    with_surface_tree_downward(
+        surface,
+        location.into(),
+        |_, states, loc: &Point<i32, Logical>| {
+            let mut loc = *loc;
+            let data = states.data_map.get::<RendererSurfaceStateUserData>();
+
+            if let Some(data) = data {
+                let data = data.lock().unwrap();
+                if let Some(surface_view) = data.surface_view {
+                    loc += surface_view.offset;
+                    regions.extend(
+                        data.opaque_regions()
+                            .into_iter()
+                            .flatten()
+                            .map(|rect| Rectangle::new(rect.loc + loc, rect.size)),
+                    );
+                    return TraversalAction::DoChildren(loc);
+                }
+            }
+            // unmapped surfaces hide their children
+            TraversalAction::SkipChildren
+        },
+        |_, _, _| {},
+        |_, _, _| true,
+    );
+    regions
+}
+
 /// Returns the topmost (sub-)surface under a given position matching the input regions of the surface.
 ///
 /// In case no surface input region matches the point [`None`] is returned.
@@ -147,6 +188,28 @@ where
     found.into_inner()
 }
 
+/// Returns the topmost (sub-)surface under a given position matching the input regions of the surface,
+/// restricted to a clip rectangle.
+///
+/// Input outside of `clip` is ignored, matching the rendering of
+/// [`render_elements_from_surface_tree_clipped`](crate::backend::renderer::element::surface::render_elements_from_surface_tree_clipped).
+/// `clip` is given in the same coordinate space as `point` and `location`.
+pub fn under_from_surface_tree_clipped<P>(
+    surface: &wl_surface::WlSurface,
+    point: Point<f64, Logical>,
+    location: P,
+    surface_type: WindowSurfaceType,
+    clip: Rectangle<i32, Logical>,
+) -> Option<(wl_surface::WlSurface, Point<i32, Logical>)>
+where
+    P: Into<Point<i32, Logical>>,
+{
+    if !clip.to_f64().contains(point) {
+        return None;
+    }
+    under_from_surface_tree(surface, point, location, surface_type)
+}
+
 type SurfacePrimaryScanoutOutput = Mutex<PrimaryScanoutOutput>;
 
 /// Run a closure on all surfaces of a surface tree
@@ -214,6 +277,9 @@ where
 /// throttle threshold. If the threshold is `None` this will never send frame callbacks
 /// for a surface that is not visible. Specifying [`Duration::ZERO`] as the throttle threshold
 /// will always send frame callbacks for non visible surfaces.
+///
+/// No frame callbacks are sent for surfaces whose primary scan-out output is powered off,
+/// see [`Output::set_powered`].
 pub fn send_frames_surface_tree<T, F>(
     surface: &wl_surface::WlSurface,
     output: &Output,
@@ -237,16 +303,25 @@ pub fn send_frames_surface_tree<T, F>(
             let surface_frame_throttling_state =
                 states.data_map.get::<SurfaceFrameThrottlingState>().unwrap();
 
-            let on_primary_scanout_output = primary_scan_out_output(surface, states)
-                .map(|preferred_output| preferred_output == *output)
+            let primary_scanout_output = primary_scan_out_output(surface, states);
+            let on_primary_scanout_output = primary_scanout_output
+                .as_ref()
+                .map(|preferred_output| preferred_output == output)
                 .unwrap_or(false);
+            let primary_scanout_output_powered = primary_scanout_output
+                .as_ref()
+                .map(|preferred_output| preferred_output.is_powered())
+                .unwrap_or(true);
 
             let frame_overdue = surface_frame_throttling_state.update(time, throttle);
 
             // We only want to send frame callbacks on the primary scan-out output
             // or if we have no output and the frame is overdue, this can only
-            // happen if the surface is completely occluded on all outputs
-            let send_frame_callback = on_primary_scanout_output || frame_overdue;
+            // happen if the surface is completely occluded on all outputs.
+            // Surfaces on a powered off output will not be presented, so they
+            // do not get any frame callbacks until the output is powered on again.
+            let send_frame_callback =
+                (on_primary_scanout_output || frame_overdue) && primary_scanout_output_powered;
 
             if send_frame_callback {
                 // the surface may not have any user_data if it is a subsurface and has not
@@ -370,6 +445,7 @@ impl Drop for SurfacePresentationFeedback {
 pub struct OutputPresentationFeedback {
     output: WeakOutput,
     callbacks: Vec<SurfacePresentationFeedback>,
+    surfaces: HashSet<ObjectId>,
 }
 
 impl OutputPresentationFeedback {
@@ -378,6 +454,7 @@ impl OutputPresentationFeedback {
         OutputPresentationFeedback {
             output: output.downgrade(),
             callbacks: Vec::new(),
+            surfaces: HashSet::new(),
         }
     }
 
@@ -419,6 +496,138 @@ impl OutputPresentationFeedback {
             callback.discarded();
         }
     }
+
+    /// Mark all stored [`SurfacePresentationFeedback`]s as presented from the metadata of a drm flip event
+    ///
+    /// Uses the hardware timestamp of the event if available, otherwise falls back to `now`.
+    #[cfg(feature = "backend_drm")]
+    pub fn presented_from_drm_event(
+        &mut self,
+        metadata: Option<&crate::backend::drm::DrmEventMetadata>,
+        now: Time<Monotonic>,
+        refresh: Refresh,
+    ) {
+        use crate::backend::drm::DrmEventTime;
+
+        let hw_time = metadata.and_then(|metadata| match metadata.time {
+            DrmEventTime::Monotonic(time) if !time.is_zero() => Some(time),
+            _ => None,
+        });
+        let seq = metadata.map(|metadata| metadata.sequence).unwrap_or(0);
+        match hw_time {
+            Some(time) => self.presented::<_, Monotonic>(
+                time,
+                refresh,
+                seq,
+                wp_presentation_feedback::Kind::Vsync
+                    | wp_presentation_feedback::Kind::HwClock
+                    | wp_presentation_feedback::Kind::HwCompletion,
+            ),
+            None => self.presented(now, refresh, seq, wp_presentation_feedback::Kind::Vsync),
+        }
+    }
+
+    /// Takes the presentation feedback of a surface tree and its popups
+    ///
+    /// Only surfaces with the output of this feedback as their primary scan-out output are
+    /// considered, see [`surface_primary_scanout_output`]. Surfaces scanned out directly,
+    /// either on the primary plane or an overlay plane, are flagged as
+    /// [`ZeroCopy`](wp_presentation_feedback::Kind::ZeroCopy) according to the `render_element_states`.
+    ///
+    /// Every surface is only taken once, so the same surface can safely be passed multiple times,
+    /// e.g. if it is part of multiple element lists.
+    pub fn take_from_surface(
+        &mut self,
+        surface: &wl_surface::WlSurface,
+        render_element_states: &RenderElementStates,
+    ) {
+        self.take_from_surface_tree(surface, render_element_states);
+        for (popup, _) in PopupManager::popups_for_surface(surface) {
+            self.take_from_surface_tree(popup.wl_surface(), render_element_states);
+        }
+    }
+
+    /// Takes the presentation feedback of all elements of a [`Space`] overlapping the output
+    ///
+    /// See [`OutputPresentationFeedback::take_from_surface`].
+    pub fn take_from_space<E>(&mut self, space: &Space<E>, render_element_states: &RenderElementStates)
+    where
+        E: SpaceElement + PartialEq + WaylandFocus,
+    {
+        let Some(output) = self.output.upgrade() else {
+            return;
+        };
+        for element in space.elements_for_output(&output) {
+            if let Some(surface) = element.wl_surface() {
+                self.take_from_surface(&surface, render_element_states);
+            }
+        }
+    }
+
+    /// Takes the presentation feedback of all layer surfaces of the output
+    ///
+    /// See [`OutputPresentationFeedback::take_from_surface`].
+    pub fn take_from_layer_map(&mut self, render_element_states: &RenderElementStates) {
+        let Some(output) = self.output.upgrade() else {
+            return;
+        };
+        let surfaces = layer_map_for_output(&output)
+            .layers()
+            .map(|layer| layer.wl_surface().clone())
+            .collect::<Vec<_>>();
+        for surface in surfaces {
+            self.take_from_surface(&surface, render_element_states);
+        }
+    }
+
+    fn take_from_surface_tree(
+        &mut self,
+        surface: &wl_surface::WlSurface,
+        render_element_states: &RenderElementStates,
+    ) {
+        with_surface_tree_downward(
+            surface,
+            (),
+            |_, _, &()| TraversalAction::DoChildren(()),
+            |surface, states, &()| {
+                if !self.surfaces.insert(surface.id()) {
+                    return;
+                }
+                let on_primary_scanout_output = surface_primary_scanout_output(surface, states)
+                    .map(|preferred_output| preferred_output == self.output)
+                    .unwrap_or(false);
+                if !on_primary_scanout_output {
+                    return;
+                }
+
+                let flags = surface_presentation_feedback_flags_from_states(surface, render_element_states);
+                if let Some(feedback) = SurfacePresentationFeedback::from_states(states, flags) {
+                    self.callbacks.push(feedback);
+                }
+            },
+            |_, _, &()| true,
+        );
+    }
+}
+
+/// Takes the presentation feedback of all surfaces shown on an output
+///
+/// This includes the elements of the [`Space`] overlapping the output and the
+/// layer surfaces of the output. The returned [`OutputPresentationFeedback`] should be
+/// marked as presented once the frame is shown, e.g. using
+/// [`OutputPresentationFeedback::presented`] from the handler of the flip event.
+pub fn take_presentation_feedback<E>(
+    output: &Output,
+    space: &Space<E>,
+    render_element_states: &RenderElementStates,
+) -> OutputPresentationFeedback
+where
+    E: SpaceElement + PartialEq + WaylandFocus,
+{
+    let mut feedback = OutputPresentationFeedback::new(output);
+    feedback.take_from_space(space, render_element_states);
+    feedback.take_from_layer_map(render_element_states);
+    feedback
 }
 
 /// Takes the [`PresentationFeedbackCallback`]s from the surface tree
@@ -499,3 +708,69 @@ impl SurfaceFrameThrottlingState {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use wayland_client::Proxy;
+
+    use super::*;
+    use crate::{
+        backend::renderer::element::{default_primary_scanout_output_compare, Id},
+        output::test_output,
+        wayland::{
+            compositor::with_states,
+            test_utils::{ClientEvent, Fixture},
+        },
+    };
+
+    #[test]
+    fn frames_for_surface_spanning_powered_off_output() {
+        let mut fixture = Fixture::new();
+        let (client_surface, surface) = fixture.create_surface();
+        let off = test_output("off", (1920, 1080));
+        let on = test_output("on", (1920, 1080));
+
+        let update = |output: &Output, visible_area: usize| {
+            let states = RenderElementStates {
+                states: HashMap::from([(
+                    Id::from_wayland_resource(&surface),
+                    RenderElementState::rendered(visible_area),
+                )]),
+            };
+            with_states(&surface, |data| {
+                update_surface_primary_scanout_output(
+                    &surface,
+                    output,
+                    data,
+                    &states,
+                    default_primary_scanout_output_compare,
+                )
+            })
+        };
+
+        // mostly shown on the output that gets powered off
+        update(&off, 100);
+        assert_eq!(update(&on, 10), Some(off.clone()));
+
+        // only the powered on output is still rendered
+        off.set_powered(false);
+        assert_eq!(update(&on, 10), Some(on.clone()));
+
+        let id = client_surface.id().protocol_id();
+        client_surface.frame(&fixture.handle(), id);
+        client_surface.commit();
+        fixture.roundtrip();
+
+        send_frames_surface_tree(
+            &surface,
+            &on,
+            Duration::ZERO,
+            None,
+            surface_primary_scanout_output,
+        );
+        fixture.roundtrip();
+        assert_eq!(fixture.client.events, vec![ClientEvent::FrameDone(id)]);
+    }
+}
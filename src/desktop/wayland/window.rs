@@ -2,11 +2,13 @@
 use crate::{desktop::space::SpaceElement, xwayland::X11Surface};
 use crate::{
     desktop::{space::RenderZindex, utils::*, PopupManager},
-    output::Output,
+    output::{Output, WeakOutput},
     utils::{user_data::UserDataMap, IsAlive, Logical, Point, Rectangle},
     wayland::{
         compositor::{with_states, SurfaceData},
         dmabuf::DmabufFeedback,
+        foreign_toplevel_list::{ForeignToplevelHandle, ForeignToplevelWeakHandle},
+        image_capture_source::ImageCaptureSource,
         seat::WaylandFocus,
         shell::xdg::{SurfaceCachedState, ToplevelSurface},
     },
@@ -23,7 +25,7 @@ use std::{
 use wayland_protocols::{
     wp::presentation_time::server::wp_presentation_feedback, xdg::shell::server::xdg_toplevel,
 };
-use wayland_server::protocol::wl_surface;
+use wayland_server::{protocol::wl_surface, Resource};
 
 crate::utils::ids::id_gen!(window_id);
 
@@ -43,9 +45,28 @@ pub(crate) struct WindowInner {
     surface: WindowSurface,
     bbox: Mutex<Rectangle<i32, Logical>>,
     pub(crate) z_index: AtomicU8,
+    fullscreen: Mutex<Option<FullscreenState>>,
+    foreign_toplevel: Mutex<Option<ForeignToplevelWeakHandle>>,
     user_data: UserDataMap,
 }
 
+#[derive(Debug)]
+struct FullscreenState {
+    output: WeakOutput,
+    restore: FullscreenRestore,
+}
+
+/// State of a [`Window`] before it was made fullscreen
+///
+/// Returned when leaving fullscreen to restore the previous placement of the window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FullscreenRestore {
+    /// Geometry of the window inside its space before it was made fullscreen
+    pub geometry: Rectangle<i32, Logical>,
+    /// Whether the window was maximized before it was made fullscreen
+    pub maximized: bool,
+}
+
 impl Drop for WindowInner {
     fn drop(&mut self) {
         window_id::remove(self.id);
@@ -119,6 +140,8 @@ impl Window {
             surface: WindowSurface::Wayland(toplevel),
             bbox: Mutex::new(Rectangle::zero()),
             z_index: AtomicU8::new(RenderZindex::Shell as u8),
+            fullscreen: Mutex::new(None),
+            foreign_toplevel: Mutex::new(None),
             user_data: UserDataMap::new(),
         }))
     }
@@ -133,6 +156,8 @@ impl Window {
             surface: WindowSurface::X11(surface),
             bbox: Mutex::new(Rectangle::zero()),
             z_index: AtomicU8::new(RenderZindex::Shell as u8),
+            fullscreen: Mutex::new(None),
+            foreign_toplevel: Mutex::new(None),
             user_data: UserDataMap::new(),
         }))
     }
@@ -218,6 +243,27 @@ impl Window {
         }
     }
 
+    /// Suspend/Resume this window
+    ///
+    /// Returns `true` if the state changed. Has no effect on X11 windows.
+    ///
+    /// Windows mapped in a [`Space`](crate::desktop::Space) are suspended automatically
+    /// by [`Space::refresh`](crate::desktop::Space::refresh), while all outputs they are shown on are
+    /// powered off, see [`Output::set_powered`](crate::output::Output::set_powered).
+    pub fn set_suspended(&self, suspended: bool) -> bool {
+        match &self.0.surface {
+            WindowSurface::Wayland(s) => s.with_pending_state(|state| {
+                if suspended {
+                    state.states.set(xdg_toplevel::State::Suspended)
+                } else {
+                    state.states.unset(xdg_toplevel::State::Suspended)
+                }
+            }),
+            #[cfg(feature = "xwayland")]
+            WindowSurface::X11(_) => false,
+        }
+    }
+
     /// Sends the frame callback to all the subsurfaces in this window that requested it
     ///
     /// See [`send_frames_surface_tree`] for more information
@@ -337,22 +383,23 @@ impl Window {
         &self,
         point: P,
         surface_type: WindowSurfaceType,
-    ) -> Option<(wl_surface::WlSurface, Point<i32, Logical>)> {
+    ) -> Option<(wl_surface::WlSurface, Point<f64, Logical>)> {
         let point = point.into();
         if let Some(surface) = self.wl_surface() {
             if surface_type.contains(WindowSurfaceType::POPUP) {
                 for (popup, location) in PopupManager::popups_for_surface(&surface) {
                     let offset = self.geometry().loc + location - popup.geometry().loc;
-                    if let Some(result) =
+                    if let Some((surface, loc)) =
                         under_from_surface_tree(popup.wl_surface(), point, offset, surface_type)
                     {
-                        return Some(result);
+                        return Some((surface, loc.to_f64()));
                     }
                 }
             }
 
             if surface_type.contains(WindowSurfaceType::TOPLEVEL) {
-                return under_from_surface_tree(&surface, point, (0, 0), surface_type);
+                return under_from_surface_tree(&surface, point, (0, 0), surface_type)
+                    .map(|(surface, loc)| (surface, loc.to_f64()));
             }
         }
 
@@ -393,6 +440,178 @@ impl Window {
     pub fn user_data(&self) -> &UserDataMap {
         &self.0.user_data
     }
+
+    /// Associate the handle advertising this window in the foreign toplevel list
+    ///
+    /// This allows clients to capture the window through an
+    /// [`ImageCaptureSource::Toplevel`], see [`Window::is_capture_source`].
+    pub fn set_foreign_toplevel_handle(&self, handle: Option<&ForeignToplevelHandle>) {
+        *self.0.foreign_toplevel.lock().unwrap() = handle.map(ForeignToplevelHandle::downgrade);
+    }
+
+    /// Returns the handle advertising this window in the foreign toplevel list, if any
+    pub fn foreign_toplevel_handle(&self) -> Option<ForeignToplevelHandle> {
+        self.0
+            .foreign_toplevel
+            .lock()
+            .unwrap()
+            .as_ref()
+            .and_then(ForeignToplevelWeakHandle::upgrade)
+    }
+
+    /// Returns whether the given capture source refers to this window
+    ///
+    /// Only matches toplevel sources created for the [handle](Window::set_foreign_toplevel_handle)
+    /// of this window. Use [`Window::capture_render_elements`] to render the window for a capture.
+    pub fn is_capture_source(&self, source: &ImageCaptureSource) -> bool {
+        match source {
+            ImageCaptureSource::Toplevel(handle) => self.foreign_toplevel_handle().as_ref() == Some(handle),
+            ImageCaptureSource::Output(_) => false,
+        }
+    }
+
+    /// Makes this window fullscreen on a given output and sends the necessary configure
+    ///
+    /// - `output_geometry` is the geometry of the output inside the space of the window,
+    ///   which the window is expected to be moved to.
+    /// - `geometry` is the current geometry of the window inside its space,
+    ///   that is returned by [`Window::unset_fullscreen`] to restore the window later on.
+    ///
+    /// If the window is already fullscreen, it is moved to the new output,
+    /// while the originally stored geometry is kept.
+    ///
+    /// The output requested by a client in [`XdgShellHandler::fullscreen_request`] can be resolved
+    /// with [`Output::from_resource`].
+    ///
+    /// [`XdgShellHandler::fullscreen_request`]: crate::wayland::shell::xdg::XdgShellHandler::fullscreen_request
+    pub fn set_fullscreen(
+        &self,
+        output: &Output,
+        output_geometry: Rectangle<i32, Logical>,
+        geometry: Rectangle<i32, Logical>,
+    ) {
+        let mut fullscreen = self.0.fullscreen.lock().unwrap();
+        let restore = match fullscreen.take() {
+            Some(state) => state.restore,
+            None => FullscreenRestore {
+                geometry,
+                maximized: self.is_maximized(),
+            },
+        };
+        *fullscreen = Some(FullscreenState {
+            output: output.downgrade(),
+            restore,
+        });
+        drop(fullscreen);
+
+        match &self.0.surface {
+            WindowSurface::Wayland(toplevel) => {
+                let wl_output = toplevel
+                    .wl_surface()
+                    .client()
+                    .and_then(|client| output.client_outputs(&client).next());
+                toplevel.with_pending_state(|state| {
+                    state.states.set(xdg_toplevel::State::Fullscreen);
+                    state.states.unset(xdg_toplevel::State::Maximized);
+                    state.size = Some(output_geometry.size);
+                    state.fullscreen_output = wl_output;
+                });
+                toplevel.send_pending_configure();
+            }
+            #[cfg(feature = "xwayland")]
+            WindowSurface::X11(surface) => {
+                if let Err(err) = surface.set_fullscreen(true) {
+                    tracing::warn!(?err, "Failed to set X11 window fullscreen");
+                }
+                if let Err(err) = surface.configure(output_geometry) {
+                    tracing::warn!(?err, "Failed to configure X11 window");
+                }
+            }
+        }
+    }
+
+    /// Leaves fullscreen and sends the necessary configure
+    ///
+    /// Returns the state of the window before it was made fullscreen, which should be used
+    /// to restore its previous location, or `None` if the window was not made fullscreen
+    /// via [`Window::set_fullscreen`].
+    pub fn unset_fullscreen(&self) -> Option<FullscreenRestore> {
+        let restore = self.0.fullscreen.lock().unwrap().take()?.restore;
+
+        match &self.0.surface {
+            WindowSurface::Wayland(toplevel) => {
+                toplevel.with_pending_state(|state| {
+                    state.states.unset(xdg_toplevel::State::Fullscreen);
+                    if restore.maximized {
+                        state.states.set(xdg_toplevel::State::Maximized);
+                    }
+                    state.size = Some(restore.geometry.size);
+                    state.fullscreen_output = None;
+                });
+                toplevel.send_pending_configure();
+            }
+            #[cfg(feature = "xwayland")]
+            WindowSurface::X11(surface) => {
+                if let Err(err) = surface.set_fullscreen(false) {
+                    tracing::warn!(?err, "Failed to unset X11 window fullscreen");
+                }
+                if let Err(err) = surface.configure(restore.geometry) {
+                    tracing::warn!(?err, "Failed to configure X11 window");
+                }
+            }
+        }
+
+        Some(restore)
+    }
+
+    /// Returns `true` if this window was made fullscreen via [`Window::set_fullscreen`]
+    pub fn is_fullscreen(&self) -> bool {
+        self.0.fullscreen.lock().unwrap().is_some()
+    }
+
+    /// Returns the output this window is fullscreen on, if any
+    ///
+    /// Returns `None`, if the output was destroyed in the meantime.
+    pub fn fullscreen_output(&self) -> Option<Output> {
+        self.0
+            .fullscreen
+            .lock()
+            .unwrap()
+            .as_ref()
+            .and_then(|state| state.output.upgrade())
+    }
+
+    /// Handles the removal of an output
+    ///
+    /// If this window is fullscreen on the given output or its fullscreen output was already destroyed,
+    /// it leaves fullscreen and the previous state is returned, see [`Window::unset_fullscreen`].
+    pub fn output_removed(&self, output: &Output) -> Option<FullscreenRestore> {
+        let affected =
+            self.0
+                .fullscreen
+                .lock()
+                .unwrap()
+                .as_ref()
+                .is_some_and(|state| match state.output.upgrade() {
+                    Some(o) => &o == output,
+                    None => true,
+                });
+        if affected {
+            self.unset_fullscreen()
+        } else {
+            None
+        }
+    }
+
+    fn is_maximized(&self) -> bool {
+        match &self.0.surface {
+            WindowSurface::Wayland(toplevel) => {
+                toplevel.with_pending_state(|state| state.states.contains(xdg_toplevel::State::Maximized))
+            }
+            #[cfg(feature = "xwayland")]
+            WindowSurface::X11(surface) => surface.is_maximized(),
+        }
+    }
 }
 
 impl WaylandFocus for Window {
@@ -405,3 +624,163 @@ impl WaylandFocus for Window {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use wayland_protocols::wp::viewporter::client::wp_viewporter::WpViewporter;
+
+    use super::*;
+    use crate::{output::test_output, utils::Size, wayland::test_utils::Fixture};
+
+    fn pending_states(toplevel: &ToplevelSurface) -> (bool, bool, Option<Size<i32, Logical>>) {
+        toplevel.with_pending_state(|state| {
+            (
+                state.states.contains(xdg_toplevel::State::Fullscreen),
+                state.states.contains(xdg_toplevel::State::Maximized),
+                state.size,
+            )
+        })
+    }
+
+    #[test]
+    fn fullscreen_keeps_original_restore_state() {
+        let mut fixture = Fixture::new();
+        let (_, toplevel) = fixture.create_toplevel();
+        let window = Window::new_wayland_window(toplevel.clone());
+        let first = test_output("first", (1920, 1080));
+        let second = test_output("second", (1280, 720));
+
+        let geometry = Rectangle::new((10, 10).into(), (300, 200).into());
+        window.set_fullscreen(&first, Rectangle::from_size((1920, 1080).into()), geometry);
+        assert_eq!(window.fullscreen_output(), Some(first.clone()));
+        assert_eq!(
+            pending_states(&toplevel),
+            (true, false, Some((1920, 1080).into()))
+        );
+
+        // moving to another output keeps the geometry from before the first call
+        window.set_fullscreen(
+            &second,
+            Rectangle::new((1920, 0).into(), (1280, 720).into()),
+            Rectangle::new((1920, 0).into(), (1280, 720).into()),
+        );
+        assert_eq!(window.fullscreen_output(), Some(second));
+        assert_eq!(pending_states(&toplevel), (true, false, Some((1280, 720).into())));
+
+        assert_eq!(
+            window.unset_fullscreen(),
+            Some(FullscreenRestore {
+                geometry,
+                maximized: false,
+            })
+        );
+        assert!(!window.is_fullscreen());
+        assert_eq!(pending_states(&toplevel), (false, false, Some((300, 200).into())));
+        assert_eq!(window.unset_fullscreen(), None);
+    }
+
+    #[test]
+    fn unset_fullscreen_restores_maximized() {
+        let mut fixture = Fixture::new();
+        let (_, toplevel) = fixture.create_toplevel();
+        let window = Window::new_wayland_window(toplevel.clone());
+        let output = test_output("output", (1920, 1080));
+
+        toplevel.with_pending_state(|state| {
+            state.states.set(xdg_toplevel::State::Maximized);
+            state.size = Some((1920, 1050).into());
+        });
+        let geometry = Rectangle::new((0, 30).into(), (1920, 1050).into());
+        window.set_fullscreen(&output, Rectangle::from_size((1920, 1080).into()), geometry);
+        assert_eq!(
+            pending_states(&toplevel),
+            (true, false, Some((1920, 1080).into()))
+        );
+
+        let restore = window.unset_fullscreen().unwrap();
+        assert!(restore.maximized);
+        assert_eq!(restore.geometry, geometry);
+        assert_eq!(
+            pending_states(&toplevel),
+            (false, true, Some((1920, 1050).into()))
+        );
+    }
+
+    #[test]
+    fn output_removed_leaves_fullscreen() {
+        let mut fixture = Fixture::new();
+        let (_, toplevel) = fixture.create_toplevel();
+        let window = Window::new_wayland_window(toplevel.clone());
+        let geometry = Rectangle::new((10, 10).into(), (300, 200).into());
+
+        let output_a = test_output("a", (800, 600));
+        let output_b = test_output("b", (1920, 1080));
+        window.set_fullscreen(&output_a, Rectangle::from_size((800, 600).into()), geometry);
+        // other outputs don't affect the window
+        assert_eq!(window.output_removed(&output_b), None);
+        assert!(window.is_fullscreen());
+
+        // the fullscreen output was dropped before the compositor noticed
+        drop(output_a);
+        assert_eq!(window.fullscreen_output(), None);
+        assert!(window.is_fullscreen());
+        assert_eq!(
+            window.output_removed(&output_b),
+            Some(FullscreenRestore {
+                geometry,
+                maximized: false,
+            })
+        );
+        assert!(!window.is_fullscreen());
+        assert_eq!(pending_states(&toplevel), (false, false, Some((300, 200).into())));
+    }
+
+    #[test]
+    fn surface_under_scaled_surface() {
+        let mut fixture = Fixture::new();
+        let (surface, toplevel) = fixture.create_toplevel();
+        fixture.attach_buffer(&surface, 100, 100);
+        surface.set_buffer_scale(2);
+        surface.commit();
+        fixture.roundtrip();
+
+        let window = Window::new_wayland_window(toplevel.clone());
+        window.on_commit();
+
+        let (under, location) = window
+            .surface_under((49.5, 10.25), WindowSurfaceType::ALL)
+            .unwrap();
+        assert_eq!(&under, toplevel.wl_surface());
+        assert_eq!(location, Point::from((0.0, 0.0)));
+        assert!(window
+            .surface_under((50.5, 10.25), WindowSurfaceType::ALL)
+            .is_none());
+    }
+
+    #[test]
+    fn surface_under_viewported_surface() {
+        let mut fixture = Fixture::new();
+        let (surface, toplevel) = fixture.create_toplevel();
+        let viewporter: WpViewporter = fixture.bind(1);
+        let viewport = viewporter.get_viewport(&surface, &fixture.handle(), ());
+        viewport.set_destination(80, 40);
+        fixture.attach_buffer(&surface, 20, 20);
+        surface.commit();
+        fixture.roundtrip();
+
+        let window = Window::new_wayland_window(toplevel.clone());
+        window.on_commit();
+
+        let (under, location) = window
+            .surface_under((79.5, 39.5), WindowSurfaceType::ALL)
+            .unwrap();
+        assert_eq!(&under, toplevel.wl_surface());
+        assert_eq!(location, Point::from((0.0, 0.0)));
+        assert!(window
+            .surface_under((60.0, 40.5), WindowSurfaceType::ALL)
+            .is_none());
+        assert!(window
+            .surface_under((80.5, 20.0), WindowSurfaceType::ALL)
+            .is_none());
+    }
+}
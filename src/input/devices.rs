@@ -0,0 +1,490 @@
+//! Management of seat capabilities based on connected input devices
+//!
+//! [`SeatDevices`] keeps track of the devices reported by an [`InputBackend`], e.g. libinput,
+//! and adds or removes the pointer, keyboard and touch capabilities of a [`Seat`] accordingly.
+//! Clients then only see the capabilities of devices, which are actually connected.
+//!
+//! ```no_run
+//! # use smithay::backend::input::{InputBackend, InputEvent};
+//! # use smithay::input::{devices::SeatDevices, Seat, SeatHandler};
+//! # fn process<B: InputBackend, D: SeatHandler + 'static>(
+//! #     data: &mut D, seat: &mut Seat<D>, devices: &mut SeatDevices<D>, event: InputEvent<B>,
+//! # ) {
+//! if devices.process_event(&event) {
+//!     // calls `SeatHandler::capabilities_changed`, if the capabilities of the seat changed
+//!     devices.apply(data, seat);
+//! }
+//! # }
+//! ```
+//!
+//! ## Multiple seats
+//!
+//! udev can assign input devices to different seats (`seat0`, `seat1`, ...) using the `ID_SEAT`
+//! property, letting multiple users share a machine with their own keyboards and mice.
+//! [`SeatAssignments`] routes the devices and their events to the [`Seat`] created for each of
+//! these seats. As every [`Seat`] has its own keyboard, pointer and touch handles, focus and
+//! cursor state are not shared between them.
+//!
+//! ```no_run
+//! # use smithay::backend::input::{InputBackend, InputEvent};
+//! # use smithay::input::{devices::SeatAssignments, Seat, SeatHandler};
+//! # fn process<B: InputBackend, D: SeatHandler + 'static>(
+//! #     data: &mut D, seat0: Seat<D>, seat1: Seat<D>, events: Vec<InputEvent<B>>,
+//! # ) {
+//! let mut seats = SeatAssignments::new();
+//! seats.assign_seat(data, "seat0", seat0);
+//! seats.assign_seat(data, "seat1", seat1);
+//!
+//! for event in events {
+//!     if let Some(seat) = seats.process_event(data, &event) {
+//!         // handle the event using the keyboard, pointer or touch of `seat`
+//!     }
+//! }
+//! # }
+//! ```
+
+use std::{collections::HashMap, fmt};
+
+use crate::backend::input::{Device, DeviceCapability, Event, InputBackend, InputEvent};
+
+use super::{keyboard::KeyboardHandle, Seat, SeatCapabilities, SeatHandler};
+
+/// Tracks input devices to manage the capabilities of a seat
+///
+/// The pointer and touch capabilities are added and removed as needed.
+/// Keyboards need a keymap, so the keyboard capability has to be added by the compositor
+/// using [`Seat::add_keyboard`]. It is removed while no keyboard is connected and restored
+/// once a keyboard is connected again.
+pub struct SeatDevices<D: SeatHandler> {
+    devices: HashMap<String, SeatCapabilities>,
+    removed_keyboard: Option<KeyboardHandle<D>>,
+}
+
+impl<D: SeatHandler> fmt::Debug for SeatDevices<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SeatDevices")
+            .field("devices", &self.devices)
+            .field("removed_keyboard", &self.removed_keyboard)
+            .finish()
+    }
+}
+
+impl<D: SeatHandler> Default for SeatDevices<D> {
+    fn default() -> Self {
+        SeatDevices {
+            devices: HashMap::new(),
+            removed_keyboard: None,
+        }
+    }
+}
+
+impl<D: SeatHandler + 'static> SeatDevices<D> {
+    /// Create a new tracker without any devices
+    pub fn new() -> Self {
+        SeatDevices::default()
+    }
+
+    /// Adds a device
+    pub fn add_device<Dev: Device>(&mut self, device: &Dev) {
+        self.devices.insert(device.id(), device_capabilities(device));
+    }
+
+    /// Removes a device
+    pub fn remove_device<Dev: Device>(&mut self, device: &Dev) {
+        self.devices.remove(&device.id());
+    }
+
+    /// Tracks the devices added and removed by an input event
+    ///
+    /// Returns `true` if the event added or removed a device.
+    pub fn process_event<B: InputBackend>(&mut self, event: &InputEvent<B>) -> bool {
+        match event {
+            InputEvent::DeviceAdded { device } => {
+                self.add_device(device);
+                true
+            }
+            InputEvent::DeviceRemoved { device } => {
+                self.remove_device(device);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the capabilities of the connected devices
+    pub fn capabilities(&self) -> SeatCapabilities {
+        self.devices
+            .values()
+            .fold(SeatCapabilities::empty(), |caps, device| caps | *device)
+    }
+
+    /// Adds or removes capabilities of the seat to match the connected devices
+    ///
+    /// Calls [`SeatHandler::capabilities_changed`] if the capabilities of the seat changed.
+    pub fn apply(&mut self, data: &mut D, seat: &mut Seat<D>) {
+        let previous = seat.capabilities();
+        let wanted = self.capabilities();
+
+        if wanted.contains(SeatCapabilities::POINTER) {
+            if seat.get_pointer().is_none() {
+                seat.add_pointer();
+            }
+        } else {
+            seat.remove_pointer();
+        }
+
+        if wanted.contains(SeatCapabilities::KEYBOARD) {
+            if seat.get_keyboard().is_none() {
+                if let Some(keyboard) = self.removed_keyboard.take() {
+                    seat.restore_keyboard(keyboard);
+                }
+            }
+        } else if let Some(keyboard) = seat.get_keyboard() {
+            seat.remove_keyboard();
+            self.removed_keyboard = Some(keyboard);
+        }
+
+        if wanted.contains(SeatCapabilities::TOUCH) {
+            if seat.get_touch().is_none() {
+                seat.add_touch();
+            }
+        } else {
+            seat.remove_touch();
+        }
+
+        let capabilities = seat.capabilities();
+        if capabilities != previous {
+            data.capabilities_changed(seat, capabilities);
+        }
+    }
+}
+
+fn device_capabilities<Dev: Device>(device: &Dev) -> SeatCapabilities {
+    let mut capabilities = SeatCapabilities::empty();
+    if device.has_capability(DeviceCapability::Pointer) {
+        capabilities |= SeatCapabilities::POINTER;
+    }
+    if device.has_capability(DeviceCapability::Keyboard) {
+        capabilities |= SeatCapabilities::KEYBOARD;
+    }
+    if device.has_capability(DeviceCapability::Touch) {
+        capabilities |= SeatCapabilities::TOUCH;
+    }
+    capabilities
+}
+
+/// Name of the seat devices are assigned to, if the backend has no seat assignments
+pub const DEFAULT_SEAT: &str = "seat0";
+
+/// Routes input devices to seats according to their udev seat assignment
+///
+/// Devices are assigned using [`Device::seat_name`], devices without a seat name belong to
+/// [`DEFAULT_SEAT`]. The capabilities of every assigned seat are managed with a [`SeatDevices`].
+///
+/// See the [module docs](self) for details.
+pub struct SeatAssignments<D: SeatHandler> {
+    seats: HashMap<String, Seat<D>>,
+    devices: HashMap<String, SeatDevices<D>>,
+    device_seats: HashMap<String, String>,
+}
+
+impl<D: SeatHandler> fmt::Debug for SeatAssignments<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SeatAssignments")
+            .field("seats", &self.seats)
+            .field("devices", &self.devices)
+            .field("device_seats", &self.device_seats)
+            .finish()
+    }
+}
+
+impl<D: SeatHandler> Default for SeatAssignments<D> {
+    fn default() -> Self {
+        SeatAssignments {
+            seats: HashMap::new(),
+            devices: HashMap::new(),
+            device_seats: HashMap::new(),
+        }
+    }
+}
+
+impl<D: SeatHandler + 'static> SeatAssignments<D> {
+    /// Create a new router without any seats
+    pub fn new() -> Self {
+        SeatAssignments::default()
+    }
+
+    /// Assigns a seat to the devices of the udev seat `name`
+    ///
+    /// The capabilities of the seat are updated to match the devices already connected to it.
+    /// Returns the seat previously assigned to `name`.
+    pub fn assign_seat(
+        &mut self,
+        data: &mut D,
+        name: impl Into<String>,
+        mut seat: Seat<D>,
+    ) -> Option<Seat<D>> {
+        let name = name.into();
+        self.devices
+            .entry(name.clone())
+            .or_default()
+            .apply(data, &mut seat);
+        self.seats.insert(name, seat)
+    }
+
+    /// Removes the seat assigned to the udev seat `name`
+    ///
+    /// Events of its devices are not routed anymore.
+    pub fn unassign_seat(&mut self, name: &str) -> Option<Seat<D>> {
+        self.seats.remove(name)
+    }
+
+    /// Returns the seat assigned to the udev seat `name`
+    pub fn seat(&self, name: &str) -> Option<&Seat<D>> {
+        self.seats.get(name)
+    }
+
+    /// Returns the devices connected to the udev seat `name`
+    pub fn devices(&self, name: &str) -> Option<&SeatDevices<D>> {
+        self.devices.get(name)
+    }
+
+    /// Returns the seat a device is assigned to
+    pub fn seat_of_device<Dev: Device>(&self, device: &Dev) -> Option<&Seat<D>> {
+        match self.device_seats.get(&device.id()) {
+            Some(name) => self.seats.get(name),
+            None => self.seats.get(&seat_name(device)),
+        }
+    }
+
+    /// Tracks devices and returns the seat an input event belongs to
+    ///
+    /// Connecting or disconnecting a device updates the capabilities of its seat,
+    /// see [`SeatDevices::apply`]. Returns `None` if the device of the event is not assigned to any
+    /// seat and for [`InputEvent::Special`].
+    pub fn process_event<B: InputBackend>(&mut self, data: &mut D, event: &InputEvent<B>) -> Option<Seat<D>> {
+        let name = match event {
+            InputEvent::DeviceAdded { device } => {
+                let name = seat_name(device);
+                self.device_seats.insert(device.id(), name.clone());
+                self.devices.entry(name.clone()).or_default().add_device(device);
+                name
+            }
+            InputEvent::DeviceRemoved { device } => {
+                let name = self
+                    .device_seats
+                    .remove(&device.id())
+                    .unwrap_or_else(|| seat_name(device));
+                if let Some(devices) = self.devices.get_mut(&name) {
+                    devices.remove_device(device);
+                }
+                name
+            }
+            event => {
+                let device = event_device(event)?;
+                return self.seat_of_device(&device).cloned();
+            }
+        };
+
+        let seat = self.seats.get_mut(&name)?;
+        if let Some(devices) = self.devices.get_mut(&name) {
+            devices.apply(data, seat);
+        }
+        Some(seat.clone())
+    }
+}
+
+fn seat_name<Dev: Device>(device: &Dev) -> String {
+    device.seat_name().unwrap_or_else(|| DEFAULT_SEAT.to_owned())
+}
+
+fn event_device<B: InputBackend>(event: &InputEvent<B>) -> Option<B::Device> {
+    Some(match event {
+        InputEvent::DeviceAdded { .. } | InputEvent::DeviceRemoved { .. } | InputEvent::Special(_) => {
+            return None
+        }
+        InputEvent::Keyboard { event } => event.device(),
+        InputEvent::PointerMotion { event } => event.device(),
+        InputEvent::PointerMotionAbsolute { event } => event.device(),
+        InputEvent::PointerButton { event } => event.device(),
+        InputEvent::PointerAxis { event } => event.device(),
+        InputEvent::GestureSwipeBegin { event } => event.device(),
+        InputEvent::GestureSwipeUpdate { event } => event.device(),
+        InputEvent::GestureSwipeEnd { event } => event.device(),
+        InputEvent::GesturePinchBegin { event } => event.device(),
+        InputEvent::GesturePinchUpdate { event } => event.device(),
+        InputEvent::GesturePinchEnd { event } => event.device(),
+        InputEvent::GestureHoldBegin { event } => event.device(),
+        InputEvent::GestureHoldEnd { event } => event.device(),
+        InputEvent::TouchDown { event } => event.device(),
+        InputEvent::TouchMotion { event } => event.device(),
+        InputEvent::TouchUp { event } => event.device(),
+        InputEvent::TouchCancel { event } => event.device(),
+        InputEvent::TouchFrame { event } => event.device(),
+        InputEvent::TabletToolAxis { event } => event.device(),
+        InputEvent::TabletToolProximity { event } => event.device(),
+        InputEvent::TabletToolTip { event } => event.device(),
+        InputEvent::TabletToolButton { event } => event.device(),
+        InputEvent::SwitchToggle { event } => event.device(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use wayland_server::protocol::wl_surface::WlSurface;
+
+    use super::*;
+    use crate::input::{keyboard::XkbConfig, pointer::CursorImageStatus, SeatState};
+
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    struct TestDevice {
+        id: &'static str,
+        capabilities: SeatCapabilities,
+        seat: Option<&'static str>,
+    }
+
+    impl Device for TestDevice {
+        fn id(&self) -> String {
+            self.id.to_owned()
+        }
+        fn name(&self) -> String {
+            self.id.to_owned()
+        }
+        fn has_capability(&self, capability: DeviceCapability) -> bool {
+            let capability = match capability {
+                DeviceCapability::Pointer => SeatCapabilities::POINTER,
+                DeviceCapability::Keyboard => SeatCapabilities::KEYBOARD,
+                DeviceCapability::Touch => SeatCapabilities::TOUCH,
+                _ => return false,
+            };
+            self.capabilities.contains(capability)
+        }
+        fn usb_id(&self) -> Option<(u32, u32)> {
+            None
+        }
+        fn syspath(&self) -> Option<PathBuf> {
+            None
+        }
+        fn seat_name(&self) -> Option<String> {
+            self.seat.map(str::to_owned)
+        }
+    }
+
+    struct State {
+        seat_state: SeatState<Self>,
+        changes: Vec<SeatCapabilities>,
+    }
+
+    impl SeatHandler for State {
+        type KeyboardFocus = WlSurface;
+        type PointerFocus = WlSurface;
+        type TouchFocus = WlSurface;
+
+        fn seat_state(&mut self) -> &mut SeatState<Self> {
+            &mut self.seat_state
+        }
+        fn cursor_image(&mut self, _seat: &Seat<Self>, _image: CursorImageStatus) {}
+        fn capabilities_changed(&mut self, _seat: &Seat<Self>, capabilities: SeatCapabilities) {
+            self.changes.push(capabilities);
+        }
+    }
+
+    const MOUSE: TestDevice = TestDevice {
+        id: "mouse",
+        capabilities: SeatCapabilities::POINTER,
+        seat: None,
+    };
+    const KEYBOARD: TestDevice = TestDevice {
+        id: "keyboard",
+        capabilities: SeatCapabilities::KEYBOARD,
+        seat: None,
+    };
+    const TOUCHPAD: TestDevice = TestDevice {
+        id: "touchpad",
+        capabilities: SeatCapabilities::POINTER.union(SeatCapabilities::TOUCH),
+        seat: Some("seat1"),
+    };
+
+    fn state() -> (State, Seat<State>) {
+        let mut seat_state = SeatState::new();
+        let seat = seat_state.new_seat("seat0");
+        let state = State {
+            seat_state,
+            changes: Vec::new(),
+        };
+        (state, seat)
+    }
+
+    #[test]
+    fn capabilities_follow_devices() {
+        let (mut state, mut seat) = state();
+        let mut devices = SeatDevices::new();
+
+        devices.add_device(&MOUSE);
+        devices.add_device(&TOUCHPAD);
+        devices.apply(&mut state, &mut seat);
+        assert_eq!(
+            seat.capabilities(),
+            SeatCapabilities::POINTER | SeatCapabilities::TOUCH
+        );
+        assert_eq!(state.changes, [seat.capabilities()]);
+
+        // the pointer stays as long as any pointer device is connected
+        let pointer = seat.get_pointer().unwrap();
+        devices.remove_device(&TOUCHPAD);
+        devices.apply(&mut state, &mut seat);
+        assert_eq!(seat.capabilities(), SeatCapabilities::POINTER);
+        assert_eq!(seat.get_pointer(), Some(pointer));
+
+        // applying without changes does not notify the handler
+        devices.apply(&mut state, &mut seat);
+        assert_eq!(state.changes.len(), 2);
+
+        devices.remove_device(&MOUSE);
+        devices.apply(&mut state, &mut seat);
+        assert!(seat.capabilities().is_empty());
+        assert_eq!(state.changes.last(), Some(&SeatCapabilities::empty()));
+    }
+
+    #[test]
+    fn keyboard_is_restored() {
+        let (mut state, mut seat) = state();
+        let mut devices = SeatDevices::new();
+
+        // the keyboard is added by the compositor and removed without keyboard devices
+        let keyboard = seat.add_keyboard(XkbConfig::default(), 200, 25).unwrap();
+        devices.apply(&mut state, &mut seat);
+        assert!(seat.get_keyboard().is_none());
+        assert_eq!(state.changes, [SeatCapabilities::empty()]);
+
+        // connecting a keyboard restores the same handle
+        devices.add_device(&KEYBOARD);
+        devices.apply(&mut state, &mut seat);
+        assert_eq!(seat.get_keyboard(), Some(keyboard));
+        assert_eq!(state.changes.last(), Some(&SeatCapabilities::KEYBOARD));
+    }
+
+    #[test]
+    fn devices_are_routed_to_seats() {
+        let (mut state, seat0) = state();
+        let seat1 = state.seat_state.new_seat("seat1");
+        let mut seats = SeatAssignments::new();
+        seats.assign_seat(&mut state, "seat0", seat0.clone());
+        seats.assign_seat(&mut state, "seat1", seat1.clone());
+
+        seats
+            .devices
+            .entry("seat1".into())
+            .or_default()
+            .add_device(&TOUCHPAD);
+        seats.device_seats.insert(TOUCHPAD.id(), "seat1".into());
+        assert_eq!(seats.seat_of_device(&TOUCHPAD), Some(&seat1));
+        assert_eq!(seats.seat_of_device(&MOUSE), Some(&seat0));
+        assert_eq!(
+            seats.devices("seat1").unwrap().capabilities(),
+            device_capabilities(&TOUCHPAD)
+        );
+    }
+}
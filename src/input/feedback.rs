@@ -0,0 +1,118 @@
+//! Feedback through input devices
+//!
+//! Besides reporting input, some devices provide outputs to give feedback to the user,
+//! like the lock leds of keyboards.
+//!
+//! Devices supporting any of these implement [`FeedbackDevice`]. Support depends on the hardware,
+//! the kernel driver and the backend, so check [`FeedbackDevice::supports_feedback`] or handle
+//! [`FeedbackError::Unsupported`].
+//!
+//! ```no_run
+//! use smithay::input::feedback::{Feedback, FeedbackDevice, FeedbackKind};
+//! use smithay::input::keyboard::LedState;
+//!
+//! // e.g. called from `SeatHandler::led_state_changed`
+//! fn show_leds<Dev: FeedbackDevice>(keyboards: &mut [Dev], leds: LedState) {
+//!     for keyboard in keyboards {
+//!         if keyboard.supports_feedback(FeedbackKind::KeyboardLeds) {
+//!             let _ = keyboard.feedback(Feedback::KeyboardLeds(leds));
+//!         }
+//!     }
+//! }
+//! ```
+
+use crate::input::keyboard::LedState;
+
+/// Feedback to show on a device
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Feedback {
+    /// Set the lock leds of a keyboard
+    ///
+    /// Leds with an unknown state are turned off.
+    KeyboardLeds(LedState),
+}
+
+impl Feedback {
+    /// Returns the kind of the feedback
+    pub fn kind(&self) -> FeedbackKind {
+        match self {
+            Feedback::KeyboardLeds(_) => FeedbackKind::KeyboardLeds,
+        }
+    }
+}
+
+/// Kinds of [`Feedback`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum FeedbackKind {
+    /// See [`Feedback::KeyboardLeds`]
+    KeyboardLeds,
+}
+
+/// Errors of [`FeedbackDevice::feedback`]
+#[derive(Debug, thiserror::Error)]
+pub enum FeedbackError {
+    /// The device does not support the feedback
+    #[error("The device does not support {0:?} feedback")]
+    Unsupported(FeedbackKind),
+    /// The feedback is invalid for the device
+    #[error("Invalid feedback for the device: {0:?}")]
+    Invalid(Feedback),
+    /// The device failed to show the feedback
+    #[error("The device failed to show the feedback")]
+    Io(#[from] std::io::Error),
+}
+
+/// Input device providing feedback to the user
+pub trait FeedbackDevice {
+    /// Returns whether the device supports a kind of feedback
+    fn supports_feedback(&self, kind: FeedbackKind) -> bool;
+
+    /// Show feedback on the device
+    fn feedback(&mut self, feedback: Feedback) -> Result<(), FeedbackError>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Keyboard {
+        leds: Option<LedState>,
+    }
+
+    impl FeedbackDevice for Keyboard {
+        fn supports_feedback(&self, kind: FeedbackKind) -> bool {
+            kind == FeedbackKind::KeyboardLeds
+        }
+
+        fn feedback(&mut self, feedback: Feedback) -> Result<(), FeedbackError> {
+            match feedback {
+                Feedback::KeyboardLeds(leds) => self.leds = Some(leds),
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn keyboard_leds() {
+        let leds = LedState {
+            caps: Some(true),
+            ..LedState::default()
+        };
+        let feedback = Feedback::KeyboardLeds(leds);
+        assert_eq!(feedback.kind(), FeedbackKind::KeyboardLeds);
+
+        let mut keyboard = Keyboard::default();
+        assert!(keyboard.supports_feedback(feedback.kind()));
+        keyboard.feedback(feedback).unwrap();
+        assert_eq!(keyboard.leds, Some(leds));
+
+        let err = FeedbackError::Unsupported(feedback.kind());
+        assert_eq!(
+            err.to_string(),
+            "The device does not support KeyboardLeds feedback"
+        );
+    }
+}
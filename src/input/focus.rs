@@ -0,0 +1,176 @@
+//! Keyboard focus policies
+//!
+//! A [`FocusPolicy`] decides how the keyboard focus follows pointer and touch input.
+//! Common policies are provided by this module:
+//!
+//! - [`ClickToFocus`] focuses targets when they are clicked or touched
+//! - [`FocusFollowsMouse`] focuses whatever is under the pointer and clears the focus over empty space
+//! - [`SloppyFocus`] focuses targets the pointer enters, but keeps the focus over empty space
+//!
+//! Policies are evaluated using [`Seat::apply_focus_policy`](super::Seat::apply_focus_policy),
+//! which notifies the compositor about suggested focus changes through
+//! [`SeatHandler::focus_change_suggested`](super::SeatHandler::focus_change_suggested).
+//!
+//! ```no_run
+//! # use smithay::input::{Seat, SeatHandler, focus::{FocusEvent, SloppyFocus}};
+//! # use smithay::utils::SERIAL_COUNTER;
+//! # fn example<D: SeatHandler + 'static>(state: &mut D, seat: &Seat<D>, under: Option<D::KeyboardFocus>) {
+//! let mut policy = SloppyFocus;
+//! // after processing a pointer motion event
+//! seat.apply_focus_policy(
+//!     state,
+//!     &mut policy,
+//!     FocusEvent::PointerMotion { under: under.as_ref() },
+//!     SERIAL_COUNTER.next_serial(),
+//! );
+//! # }
+//! ```
+
+/// Input events relevant for focus decisions
+///
+/// `under` is the keyboard focus target at the position of the event, if any.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FocusEvent<'a, F> {
+    /// The pointer moved
+    PointerMotion {
+        /// Target under the pointer
+        under: Option<&'a F>,
+    },
+    /// A pointer button was pressed
+    PointerButton {
+        /// Target under the pointer
+        under: Option<&'a F>,
+    },
+    /// A new touch point went down
+    TouchDown {
+        /// Target under the touch point
+        under: Option<&'a F>,
+    },
+}
+
+impl<'a, F> FocusEvent<'a, F> {
+    /// Returns the target at the position of the event
+    pub fn under(&self) -> Option<&'a F> {
+        match *self {
+            FocusEvent::PointerMotion { under }
+            | FocusEvent::PointerButton { under }
+            | FocusEvent::TouchDown { under } => under,
+        }
+    }
+
+    /// Returns `true` for button presses and touch downs
+    pub fn is_press(&self) -> bool {
+        !matches!(self, FocusEvent::PointerMotion { .. })
+    }
+}
+
+/// Focus change suggested by a [`FocusPolicy`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FocusSuggestion<F> {
+    /// Keep the current focus
+    Keep,
+    /// Focus the given target
+    Focus(F),
+    /// Clear the focus
+    Clear,
+}
+
+/// Policy deciding how the keyboard focus follows input
+pub trait FocusPolicy<F> {
+    /// Evaluate an input event
+    ///
+    /// `current` is the current keyboard focus.
+    fn evaluate(&mut self, current: Option<&F>, event: &FocusEvent<'_, F>) -> FocusSuggestion<F>;
+}
+
+impl<F, P: FocusPolicy<F> + ?Sized> FocusPolicy<F> for Box<P> {
+    fn evaluate(&mut self, current: Option<&F>, event: &FocusEvent<'_, F>) -> FocusSuggestion<F> {
+        (**self).evaluate(current, event)
+    }
+}
+
+/// Focus targets when they are clicked or touched
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ClickToFocus {
+    /// Clear the focus when clicking on empty space
+    pub clear_on_empty: bool,
+}
+
+impl<F: Clone> FocusPolicy<F> for ClickToFocus {
+    fn evaluate(&mut self, _current: Option<&F>, event: &FocusEvent<'_, F>) -> FocusSuggestion<F> {
+        if !event.is_press() {
+            return FocusSuggestion::Keep;
+        }
+        match event.under() {
+            Some(target) => FocusSuggestion::Focus(target.clone()),
+            None if self.clear_on_empty => FocusSuggestion::Clear,
+            None => FocusSuggestion::Keep,
+        }
+    }
+}
+
+/// Focus whatever is under the pointer, clearing the focus over empty space
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FocusFollowsMouse;
+
+impl<F: Clone> FocusPolicy<F> for FocusFollowsMouse {
+    fn evaluate(&mut self, _current: Option<&F>, event: &FocusEvent<'_, F>) -> FocusSuggestion<F> {
+        match event.under() {
+            Some(target) => FocusSuggestion::Focus(target.clone()),
+            None => FocusSuggestion::Clear,
+        }
+    }
+}
+
+/// Focus targets the pointer enters, keeping the focus over empty space
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SloppyFocus;
+
+impl<F: Clone> FocusPolicy<F> for SloppyFocus {
+    fn evaluate(&mut self, _current: Option<&F>, event: &FocusEvent<'_, F>) -> FocusSuggestion<F> {
+        match event.under() {
+            Some(target) => FocusSuggestion::Focus(target.clone()),
+            None => FocusSuggestion::Keep,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run<P: FocusPolicy<u32>>(policy: &mut P, events: &[FocusEvent<'_, u32>]) -> Option<u32> {
+        let mut focus = None;
+        for event in events {
+            match policy.evaluate(focus.as_ref(), event) {
+                FocusSuggestion::Keep => {}
+                FocusSuggestion::Focus(target) => focus = Some(target),
+                FocusSuggestion::Clear => focus = None,
+            }
+        }
+        focus
+    }
+
+    #[test]
+    fn policies() {
+        let enter = FocusEvent::PointerMotion { under: Some(&1) };
+        let empty = FocusEvent::PointerMotion { under: None };
+        let click = FocusEvent::PointerButton { under: Some(&2) };
+        let click_empty = FocusEvent::PointerButton { under: None };
+        let touch = FocusEvent::TouchDown { under: Some(&3) };
+
+        let mut click_to_focus = ClickToFocus::default();
+        assert_eq!(run(&mut click_to_focus, &[enter]), None);
+        assert_eq!(run(&mut click_to_focus, &[click, enter, empty]), Some(2));
+        assert_eq!(run(&mut click_to_focus, &[click, click_empty]), Some(2));
+        assert_eq!(run(&mut click_to_focus, &[click, touch]), Some(3));
+        let mut clearing = ClickToFocus { clear_on_empty: true };
+        assert_eq!(run(&mut clearing, &[click, click_empty]), None);
+
+        assert_eq!(run(&mut FocusFollowsMouse, &[enter]), Some(1));
+        assert_eq!(run(&mut FocusFollowsMouse, &[click, empty]), None);
+
+        assert_eq!(run(&mut SloppyFocus, &[enter, empty]), Some(1));
+        assert_eq!(run(&mut SloppyFocus, &[enter, click, empty]), Some(2));
+    }
+}
@@ -1,6 +1,11 @@
 use std::ffi::CString;
 use std::os::unix::io::{AsFd, BorrowedFd};
 use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "wayland_frontend")]
+use std::{collections::HashMap, fs::File, sync::Mutex};
+
+#[cfg(feature = "wayland_frontend")]
+use wayland_server::backend::ClientId;
 
 use tracing::error;
 use xkbcommon::xkb::{self, Keymap, KEYMAP_FORMAT_TEXT_V1};
@@ -11,11 +16,17 @@ use crate::utils::SealedFile;
 static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
 
 /// Wraps an XKB keymap into a sealed file or stores as just a string for sending to WlKeyboard over an fd
+///
+/// The sealed file is shared read-only by all keyboards supporting it. Keyboards of older clients,
+/// which might map the keymap writable, receive an unsealed copy, shared by the keyboards of the
+/// same client until the keymap is changed.
 #[derive(Debug)]
 pub struct KeymapFile {
     sealed: Option<SealedFile>,
     keymap: String,
     id: usize,
+    #[cfg(feature = "wayland_frontend")]
+    client_files: Mutex<HashMap<ClientId, File>>,
 }
 
 impl KeymapFile {
@@ -35,6 +46,8 @@ impl KeymapFile {
             sealed: sealed.ok(),
             keymap,
             id,
+            #[cfg(feature = "wayland_frontend")]
+            client_files: Mutex::new(HashMap::new()),
         }
     }
 
@@ -52,6 +65,7 @@ impl KeymapFile {
         self.id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
         self.sealed = sealed.ok();
         self.keymap = keymap;
+        self.client_files.get_mut().unwrap().clear();
     }
 
     #[cfg(feature = "wayland_frontend")]
@@ -60,24 +74,29 @@ impl KeymapFile {
     where
         F: FnOnce(BorrowedFd<'_>, usize),
     {
-        use std::{io::Write, path::PathBuf};
-
         if let Some(file) = supports_sealed.then_some(self.sealed.as_ref()).flatten() {
             cb(file.as_fd(), file.size());
         } else {
-            let dir = std::env::var_os("XDG_RUNTIME_DIR")
-                .map(PathBuf::from)
-                .unwrap_or_else(std::env::temp_dir);
-
-            let mut file = tempfile::tempfile_in(dir)?;
-            file.write_all(self.keymap.as_bytes())?;
-            file.flush()?;
-
+            let file = self.unsealed_file()?;
             cb(file.as_fd(), self.keymap.len());
         }
         Ok(())
     }
 
+    #[cfg(feature = "wayland_frontend")]
+    fn unsealed_file(&self) -> Result<File, std::io::Error> {
+        use std::{io::Write, path::PathBuf};
+
+        let dir = std::env::var_os("XDG_RUNTIME_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(std::env::temp_dir);
+
+        let mut file = tempfile::tempfile_in(dir)?;
+        file.write_all(self.keymap.as_bytes())?;
+        file.flush()?;
+        Ok(file)
+    }
+
     /// Send the keymap contained within to a WlKeyboard
     pub fn send(
         &self,
@@ -85,9 +104,29 @@ impl KeymapFile {
     ) -> Result<(), std::io::Error> {
         use wayland_server::{protocol::wl_keyboard::KeymapFormat, Resource};
 
-        self.with_fd(keyboard.version() >= 7, |fd, size| {
-            keyboard.keymap(KeymapFormat::XkbV1, fd, size as u32);
-        })
+        if let Some(file) = (keyboard.version() >= 7)
+            .then_some(self.sealed.as_ref())
+            .flatten()
+        {
+            keyboard.keymap(KeymapFormat::XkbV1, file.as_fd(), file.size() as u32);
+            return Ok(());
+        }
+
+        let Some(client) = keyboard.client() else {
+            // the client is already gone
+            return Ok(());
+        };
+        let mut client_files = self.client_files.lock().unwrap();
+        if !client_files.contains_key(&client.id()) {
+            // drop the copies of disconnected clients
+            if let Some(handle) = keyboard.handle().upgrade() {
+                client_files.retain(|id, _| handle.get_client_data(id.clone()).is_ok());
+            }
+            client_files.insert(client.id(), self.unsealed_file()?);
+        }
+        let file = &client_files[&client.id()];
+        keyboard.keymap(KeymapFormat::XkbV1, file.as_fd(), self.keymap.len() as u32);
+        Ok(())
     }
 
     /// Get this keymap's unique ID.
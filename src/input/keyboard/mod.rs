@@ -3,7 +3,7 @@
 use crate::backend::input::KeyState;
 use crate::utils::{IsAlive, Serial, SERIAL_COUNTER};
 use downcast_rs::{impl_downcast, Downcast};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 #[cfg(feature = "wayland_frontend")]
 use std::sync::RwLock;
 use std::{
@@ -17,19 +17,24 @@ use tracing::{debug, error, info, info_span, instrument, trace};
 use xkbcommon::xkb::ffi::XKB_STATE_LAYOUT_EFFECTIVE;
 pub use xkbcommon::xkb::{self, keysyms, Keycode, Keysym};
 
-use super::{GrabStatus, Seat, SeatHandler};
+use super::{ActiveGrab, GrabStatus, Seat, SeatHandler};
 
 #[cfg(feature = "wayland_frontend")]
-use wayland_server::{Resource, Weak};
+use crate::wayland::version::VersionedResource;
+#[cfg(feature = "wayland_frontend")]
+use wayland_server::{protocol::wl_keyboard, Weak};
 #[cfg(feature = "wayland_frontend")]
 mod keymap_file;
 #[cfg(feature = "wayland_frontend")]
 pub use keymap_file::KeymapFile;
 
 mod modifiers_state;
+mod repeat;
 pub use modifiers_state::{ModifiersState, SerializedMods};
+pub use repeat::KeyRepeat;
 
 mod xkb_config;
+use xkb_config::apply_modifiers;
 pub use xkb_config::XkbConfig;
 
 /// Trait representing object that can receive keyboard interactions
@@ -131,6 +136,37 @@ impl LedState {
     }
 }
 
+/// State of a keyboard relevant for indicators in panels or on-screen displays
+///
+/// See [`SeatHandler::keyboard_indicators_changed`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct KeyboardIndicators {
+    /// The active layout
+    pub layout: Layout,
+    /// Human readable name of the active layout
+    pub layout_name: String,
+    /// Whether caps lock is active
+    pub caps_lock: bool,
+    /// Whether num lock is active
+    pub num_lock: bool,
+}
+
+impl KeyboardIndicators {
+    fn from_xkb(xkb: &Xkb) -> Self {
+        let layout = xkb.active_layout();
+        KeyboardIndicators {
+            layout,
+            layout_name: xkb.layout_name(layout).to_owned(),
+            caps_lock: xkb
+                .state
+                .mod_name_is_active(xkb::MOD_NAME_CAPS, xkb::STATE_MODS_EFFECTIVE),
+            num_lock: xkb
+                .state
+                .mod_name_is_active(xkb::MOD_NAME_NUM, xkb::STATE_MODS_EFFECTIVE),
+        }
+    }
+}
+
 /// An xkbcommon context, keymap, and state, that can be sent to another
 /// thread, but should not have additional ref-counts kept on one thread.
 pub struct Xkb {
@@ -203,6 +239,38 @@ impl fmt::Debug for Xkb {
 // same thread
 unsafe impl Send for Xkb {}
 
+// The state of a physical keyboard currently not driving the seat keyboard
+#[derive(Debug)]
+struct KeyboardDevice {
+    xkb: Arc<Mutex<Xkb>>,
+    mods_state: ModifiersState,
+    led_mapping: LedMapping,
+    led_state: LedState,
+}
+
+impl KeyboardDevice {
+    fn new(xkb_config: XkbConfig<'_>) -> Result<Self, ()> {
+        let context = xkb::Context::new(xkb::CONTEXT_NO_FLAGS);
+        let keymap = xkb_config.compile_keymap(&context)?;
+        let mut state = xkb::State::new(&keymap);
+        xkb_config.apply_modifiers(&keymap, &mut state);
+        let led_mapping = LedMapping::from_keymap(&keymap);
+        let led_state = LedState::from_state(&state, &led_mapping);
+        let mut mods_state = ModifiersState::default();
+        mods_state.update_with(&state);
+        Ok(KeyboardDevice {
+            xkb: Arc::new(Mutex::new(Xkb {
+                context,
+                keymap,
+                state,
+            })),
+            mods_state,
+            led_mapping,
+            led_state,
+        })
+    }
+}
+
 pub(crate) struct KbdInternal<D: SeatHandler> {
     pub(crate) focus: Option<(<D as SeatHandler>::KeyboardFocus, Serial)>,
     pending_focus: Option<<D as SeatHandler>::KeyboardFocus>,
@@ -214,6 +282,15 @@ pub(crate) struct KbdInternal<D: SeatHandler> {
     pub(crate) repeat_delay: i32,
     led_mapping: LedMapping,
     pub(crate) led_state: LedState,
+    // the initial led state differs from the default and was not yet reported to the compositor
+    led_state_unreported: bool,
+    // the modifiers locked by the xkb config, locked again whenever the keymap changes
+    locked_modifiers: Vec<String>,
+    indicators: KeyboardIndicators,
+    // the device owning the active xkb state, `None` for the default state of the seat keyboard
+    active_device: Option<String>,
+    // inactive device states, including the default state if a device is active
+    devices: HashMap<Option<String>, KeyboardDevice>,
     grab: GrabStatus<dyn KeyboardGrab<D>>,
 }
 
@@ -229,6 +306,8 @@ impl<D: SeatHandler> fmt::Debug for KbdInternal<D> {
             .field("xkb", &self.xkb)
             .field("repeat_rate", &self.repeat_rate)
             .field("repeat_delay", &self.repeat_delay)
+            .field("active_device", &self.active_device)
+            .field("devices", &self.devices)
             .finish()
     }
 }
@@ -247,28 +326,71 @@ impl<D: SeatHandler + 'static> KbdInternal<D> {
         // non-threadsafety properly.
         let context = xkb::Context::new(xkb::CONTEXT_NO_FLAGS);
         let keymap = xkb_config.compile_keymap(&context)?;
-        let state = xkb::State::new(&keymap);
+        let mut state = xkb::State::new(&keymap);
+        xkb_config.apply_modifiers(&keymap, &mut state);
         let led_mapping = LedMapping::from_keymap(&keymap);
         let led_state = LedState::from_state(&state, &led_mapping);
+        let mut mods_state = ModifiersState::default();
+        mods_state.update_with(&state);
+        let xkb = Xkb {
+            context,
+            keymap,
+            state,
+        };
+        let indicators = KeyboardIndicators::from_xkb(&xkb);
         Ok(KbdInternal {
             focus: None,
             pending_focus: None,
             pressed_keys: HashSet::new(),
             forwarded_pressed_keys: HashSet::new(),
-            mods_state: ModifiersState::default(),
-            xkb: Arc::new(Mutex::new(Xkb {
-                context,
-                keymap,
-                state,
-            })),
+            mods_state,
+            xkb: Arc::new(Mutex::new(xkb)),
             repeat_rate,
             repeat_delay,
             led_mapping,
             led_state,
+            led_state_unreported: led_state != LedState::default(),
+            locked_modifiers: xkb_config
+                .locked_modifiers
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+            indicators,
+            active_device: None,
+            devices: HashMap::new(),
             grab: GrabStatus::None,
         })
     }
 
+    // swaps the active xkb state with the state of the given device,
+    // returns the previous led state or `None` if the device is unknown or already active
+    fn activate_device(&mut self, device: Option<&str>) -> Option<LedState> {
+        if self.active_device.as_deref() == device {
+            return None;
+        }
+        let next = self.devices.remove(&device.map(String::from))?;
+        let previous = KeyboardDevice {
+            xkb: std::mem::replace(&mut self.xkb, next.xkb),
+            mods_state: std::mem::replace(&mut self.mods_state, next.mods_state),
+            led_mapping: std::mem::replace(&mut self.led_mapping, next.led_mapping),
+            led_state: std::mem::replace(&mut self.led_state, next.led_state),
+        };
+        let led_state = previous.led_state;
+        let previous_id = std::mem::replace(&mut self.active_device, device.map(String::from));
+        self.devices.insert(previous_id, previous);
+        Some(led_state)
+    }
+
+    // recomputes the indicators, returns them if they changed
+    fn update_indicators(&mut self) -> Option<KeyboardIndicators> {
+        let indicators = KeyboardIndicators::from_xkb(&self.xkb.lock().unwrap());
+        if indicators == self.indicators {
+            return None;
+        }
+        self.indicators = indicators.clone();
+        Some(indicators)
+    }
+
     // returns whether the modifiers or led state has changed
     fn key_input(&mut self, keycode: Keycode, state: KeyState) -> (bool, bool) {
         // track pressed keys as xkbcommon does not seem to expose it :(
@@ -355,7 +477,7 @@ pub(crate) struct KbdRc<D: SeatHandler> {
     #[cfg(feature = "wayland_frontend")]
     pub(crate) keymap: Mutex<KeymapFile>,
     #[cfg(feature = "wayland_frontend")]
-    pub(crate) known_kbds: Mutex<Vec<Weak<wayland_server::protocol::wl_keyboard::WlKeyboard>>>,
+    pub(crate) known_kbds: Mutex<Vec<Weak<wl_keyboard::WlKeyboard>>>,
     #[cfg(feature = "wayland_frontend")]
     pub(crate) last_enter: Mutex<Option<Serial>>,
     pub(crate) span: tracing::Span,
@@ -622,6 +744,11 @@ pub trait KeyboardGrab<D: SeatHandler>: Downcast {
 
     /// The grab has been unset or replaced with another grab.
     fn unset(&mut self, data: &mut D);
+
+    /// Name of the type of the grab, see [`ActiveGrab`](crate::input::ActiveGrab).
+    fn type_name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
 }
 
 impl_downcast!(KeyboardGrab<D> where D: SeatHandler);
@@ -729,9 +856,7 @@ impl<D: SeatHandler + 'static> KeyboardHandle<D> {
         keymap_file: &KeymapFile,
         mods: ModifiersState,
     ) -> bool {
-        use std::os::unix::io::AsFd;
         use tracing::warn;
-        use wayland_server::{protocol::wl_keyboard::KeymapFormat, Resource};
 
         // Ignore request which do not change the keymap.
         let new_id = keymap_file.id();
@@ -747,10 +872,7 @@ impl<D: SeatHandler + 'static> KeyboardHandle<D> {
                 continue;
             };
 
-            let res = keymap_file.with_fd(kbd.version() >= 7, |fd, size| {
-                kbd.keymap(KeymapFormat::XkbV1, fd.as_fd(), size as u32)
-            });
-            if let Err(e) = res {
+            if let Err(e) = keymap_file.send(&kbd) {
                 warn!(
                     err = ?e,
                     "Failed to send keymap to client"
@@ -767,13 +889,14 @@ impl<D: SeatHandler + 'static> KeyboardHandle<D> {
         true
     }
 
-    fn update_xkb_state(&self, data: &mut D, keymap: xkb::Keymap) {
+    fn update_xkb_state(&self, data: &mut D, keymap: xkb::Keymap, latched_modifiers: &[&str]) {
         let mut internal = self.arc.internal.lock().unwrap();
 
         let mut state = xkb::State::new(&keymap);
         for key in &internal.pressed_keys {
             state.update_key(*key, xkb::KeyDirection::Down);
         }
+        apply_modifiers(&keymap, &mut state, &internal.locked_modifiers, latched_modifiers);
 
         let led_mapping = LedMapping::from_keymap(&keymap);
         internal.led_mapping = led_mapping;
@@ -784,6 +907,17 @@ impl<D: SeatHandler + 'static> KeyboardHandle<D> {
         xkb.state = state;
         drop(xkb);
 
+        self.notify_xkb_state_changed(data, internal, &keymap, leds_changed);
+    }
+
+    // informs the focus and the compositor about a changed keymap or xkb state
+    fn notify_xkb_state_changed(
+        &self,
+        data: &mut D,
+        mut internal: std::sync::MutexGuard<'_, KbdInternal<D>>,
+        keymap: &xkb::Keymap,
+        leds_changed: bool,
+    ) {
         let mods = internal.mods_state;
         let focus = internal.focus.as_mut().map(|(focus, _)| focus);
 
@@ -793,20 +927,57 @@ impl<D: SeatHandler + 'static> KeyboardHandle<D> {
             focus.modifiers(&seat, data, mods, SERIAL_COUNTER.next_serial());
         };
 
+        #[cfg(not(feature = "wayland_frontend"))]
+        let _ = keymap;
         #[cfg(feature = "wayland_frontend")]
-        self.change_keymap(data, &focus, &keymap, mods);
+        self.change_keymap(data, &focus, keymap, mods);
 
-        if leds_changed {
-            let led_state = internal.led_state;
-            std::mem::drop(internal);
-            let seat = self.get_seat(data);
+        let indicators = internal.update_indicators();
+        let led_state = leds_changed.then_some(internal.led_state);
+        std::mem::drop(internal);
+        self.notify_indicators(data, led_state, indicators);
+    }
+
+    // informs the compositor about changed leds or indicators
+    fn notify_indicators(
+        &self,
+        data: &mut D,
+        led_state: Option<LedState>,
+        indicators: Option<KeyboardIndicators>,
+    ) {
+        // the initial led state is reported on first use, as the keyboard is created without access to `D`
+        let mut internal = self.arc.internal.lock().unwrap();
+        let unreported = std::mem::take(&mut internal.led_state_unreported);
+        let led_state = led_state.or(unreported.then_some(internal.led_state));
+        std::mem::drop(internal);
+
+        if led_state.is_none() && indicators.is_none() {
+            return;
+        }
+        let seat = self.get_seat(data);
+        if let Some(led_state) = led_state {
             data.led_state_changed(&seat, led_state);
         }
+        if let Some(indicators) = indicators {
+            data.keyboard_indicators_changed(&seat, &indicators);
+        }
+    }
+
+    // forwards changed modifiers to an active drag'n'drop
+    #[cfg(feature = "wayland_frontend")]
+    fn notify_modifiers(&self, data: &mut D, modifiers: ModifiersState) {
+        let seat = self.get_seat(data);
+        crate::wayland::selection::data_device::dnd_modifiers_changed(data, &seat, modifiers);
     }
 
+    #[cfg(not(feature = "wayland_frontend"))]
+    fn notify_modifiers(&self, _data: &mut D, _modifiers: ModifiersState) {}
+
     /// Change the [`Keymap`](xkb::Keymap) used by the keyboard.
     ///
     /// The input is a keymap in XKB_KEYMAP_FORMAT_TEXT_V1 format.
+    ///
+    /// The [locked modifiers](XkbConfig::locked_modifiers) of the current [`XkbConfig`] are locked in the new state.
     pub fn set_keymap_from_string(&self, data: &mut D, keymap: String) -> Result<(), Error> {
         // Construct the Keymap internally instead of accepting one as input
         // because libxkbcommon is not thread-safe.
@@ -820,11 +991,15 @@ impl<D: SeatHandler + 'static> KeyboardHandle<D> {
             debug!("Loading keymap from string failed");
             Error::BadKeymap
         })?;
-        self.update_xkb_state(data, keymap);
+        self.update_xkb_state(data, keymap, &[]);
         Ok(())
     }
 
     /// Change the [`XkbConfig`] used by the keyboard.
+    ///
+    /// The modifiers of the config are locked or latched in the new state,
+    /// see [`XkbConfig::locked_modifiers`]. The locked modifiers are locked again
+    /// when the keymap is later changed using [`KeyboardHandle::set_keymap_from_string`].
     pub fn set_xkb_config(&self, data: &mut D, xkb_config: XkbConfig<'_>) -> Result<(), Error> {
         let keymap = xkb_config
             .compile_keymap(&self.arc.internal.lock().unwrap().xkb.lock().unwrap().context)
@@ -832,10 +1007,138 @@ impl<D: SeatHandler + 'static> KeyboardHandle<D> {
                 debug!("Loading keymap from XkbConfig failed");
                 Error::BadKeymap
             })?;
-        self.update_xkb_state(data, keymap);
+        self.arc.internal.lock().unwrap().locked_modifiers = xkb_config
+            .locked_modifiers
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
+        self.update_xkb_state(data, keymap, xkb_config.latched_modifiers);
         Ok(())
     }
 
+    /// Adds a physical keyboard with its own keymap and xkb state
+    ///
+    /// All physical keyboards of a seat share a single keyboard focus, but every keyboard added
+    /// using this function keeps track of its own layout, modifiers and leds. Use
+    /// [`KeyboardHandle::input_from_device`] to feed key events of the device, which switches
+    /// the keymap sent to clients whenever a different device is used.
+    ///
+    /// Keyboards without their own state use the default state of the seat keyboard.
+    /// Adding an already known device replaces its state.
+    pub fn add_device(
+        &self,
+        data: &mut D,
+        device_id: impl Into<String>,
+        xkb_config: XkbConfig<'_>,
+    ) -> Result<(), Error> {
+        let device_id = device_id.into();
+        let device = KeyboardDevice::new(xkb_config).map_err(|_| {
+            debug!("Loading keymap for keyboard device failed");
+            Error::BadKeymap
+        })?;
+        let mut internal = self.arc.internal.lock().unwrap();
+        if internal.active_device.as_deref() == Some(&device_id) {
+            // replace the active state by switching away from it first
+            internal.activate_device(None);
+            internal.devices.insert(Some(device_id.clone()), device);
+            self.activate_device_internal(data, internal, Some(&device_id));
+        } else {
+            internal.devices.insert(Some(device_id), device);
+        }
+        Ok(())
+    }
+
+    /// Removes the state of a physical keyboard added with [`KeyboardHandle::add_device`]
+    ///
+    /// If the device is currently active, the default state of the seat keyboard gets activated.
+    pub fn remove_device(&self, data: &mut D, device_id: &str) {
+        let internal = self.arc.internal.lock().unwrap();
+        if internal.active_device.as_deref() == Some(device_id) {
+            self.activate_device_internal(data, internal, None);
+        } else {
+            drop(internal);
+        }
+        self.arc
+            .internal
+            .lock()
+            .unwrap()
+            .devices
+            .remove(&Some(device_id.to_owned()));
+    }
+
+    /// Returns the id of the physical keyboard whose state is currently active
+    ///
+    /// Returns `None` if the default state of the seat keyboard is active.
+    pub fn active_device(&self) -> Option<String> {
+        self.arc.internal.lock().unwrap().active_device.clone()
+    }
+
+    /// Returns the led state of a physical keyboard
+    ///
+    /// `None` refers to the default state of the seat keyboard.
+    /// Returns `None` if the device is unknown.
+    pub fn device_led_state(&self, device_id: Option<&str>) -> Option<LedState> {
+        let internal = self.arc.internal.lock().unwrap();
+        if internal.active_device.as_deref() == device_id {
+            Some(internal.led_state)
+        } else {
+            internal
+                .devices
+                .get(&device_id.map(String::from))
+                .map(|device| device.led_state)
+        }
+    }
+
+    /// Activates the state of a physical keyboard
+    ///
+    /// `None` activates the default state of the seat keyboard. Unknown devices are ignored.
+    /// The keymap and modifiers of the device are sent to clients if they differ.
+    pub fn activate_device(&self, data: &mut D, device_id: Option<&str>) {
+        let internal = self.arc.internal.lock().unwrap();
+        self.activate_device_internal(data, internal, device_id);
+    }
+
+    fn activate_device_internal(
+        &self,
+        data: &mut D,
+        mut internal: std::sync::MutexGuard<'_, KbdInternal<D>>,
+        device_id: Option<&str>,
+    ) {
+        let Some(previous_led_state) = internal.activate_device(device_id) else {
+            return;
+        };
+        let keymap = internal.xkb.lock().unwrap().keymap.clone();
+        let leds_changed = previous_led_state != internal.led_state;
+        self.notify_xkb_state_changed(data, internal, &keymap, leds_changed);
+    }
+
+    /// Handle a keystroke of a physical keyboard
+    ///
+    /// Activates the state of the device before handling the keystroke like [`KeyboardHandle::input`].
+    /// Devices not added using [`KeyboardHandle::add_device`] use the default state of the seat keyboard.
+    #[allow(clippy::too_many_arguments)]
+    pub fn input_from_device<T, F>(
+        &self,
+        data: &mut D,
+        device_id: &str,
+        keycode: Keycode,
+        state: KeyState,
+        serial: Serial,
+        time: u32,
+        filter: F,
+    ) -> Option<T>
+    where
+        F: FnOnce(&mut D, &ModifiersState, KeysymHandle<'_>) -> FilterResult<T>,
+    {
+        let known = {
+            let internal = self.arc.internal.lock().unwrap();
+            internal.active_device.as_deref() == Some(device_id)
+                || internal.devices.contains_key(&Some(device_id.to_owned()))
+        };
+        self.activate_device(data, known.then_some(device_id));
+        self.input(data, keycode, state, serial, time, filter)
+    }
+
     /// Access the underlying Xkb state and perform mutable operations on it, like
     /// changing layouts.
     ///
@@ -844,7 +1147,7 @@ impl<D: SeatHandler + 'static> KeyboardHandle<D> {
     where
         F: FnMut(XkbContext<'_>) -> T,
     {
-        let (result, new_led_state) = {
+        let (result, new_led_state, indicators, new_mods_state) = {
             let internal = &mut *self.arc.internal.lock().unwrap();
             let mut mods_changed = false;
             let mut leds_changed = false;
@@ -866,14 +1169,19 @@ impl<D: SeatHandler + 'static> KeyboardHandle<D> {
                 };
             }
 
-            (result, leds_changed.then_some(internal.led_state))
+            let indicators = internal.update_indicators();
+            (
+                result,
+                leds_changed.then_some(internal.led_state),
+                indicators,
+                mods_changed.then_some(internal.mods_state),
+            )
         };
 
-        if let Some(led_state) = new_led_state {
-            let seat = self.get_seat(data);
-            data.led_state_changed(&seat, led_state)
+        self.notify_indicators(data, new_led_state, indicators);
+        if let Some(mods_state) = new_mods_state {
+            self.notify_modifiers(data, mods_state);
         }
-
         result
     }
 
@@ -931,6 +1239,14 @@ impl<D: SeatHandler + 'static> KeyboardHandle<D> {
         }
     }
 
+    /// Returns the type and serial of the active grab, if any.
+    pub fn active_grab(&self) -> Option<ActiveGrab> {
+        self.with_grab(|serial, grab| ActiveGrab {
+            type_name: grab.type_name(),
+            serial,
+        })
+    }
+
     /// Handle a keystroke
     ///
     /// All keystrokes from the input backend should be fed _in order_ to this method of the
@@ -989,7 +1305,8 @@ impl<D: SeatHandler + 'static> KeyboardHandle<D> {
 
         let mut guard = self.arc.internal.lock().unwrap();
         let (mods_changed, leds_changed) = guard.key_input(keycode, state);
-        let led_state = guard.led_state;
+        let led_state = leds_changed.then_some(guard.led_state);
+        let indicators = guard.update_indicators();
         let mods_state = guard.mods_state;
         let xkb = guard.xkb.clone();
         std::mem::drop(guard);
@@ -999,9 +1316,9 @@ impl<D: SeatHandler + 'static> KeyboardHandle<D> {
         trace!(mods_state = ?mods_state, sym = xkb::keysym_get_name(key_handle.modified_sym()), "Calling input filter");
         let filter_result = filter(data, &mods_state, key_handle);
 
-        if leds_changed {
-            let seat = self.get_seat(data);
-            data.led_state_changed(&seat, led_state);
+        self.notify_indicators(data, led_state, indicators);
+        if mods_changed {
+            self.notify_modifiers(data, mods_state);
         }
 
         (filter_result, mods_changed)
@@ -1056,6 +1373,8 @@ impl<D: SeatHandler + 'static> KeyboardHandle<D> {
         guard.with_grab(data, &seat, |data, handle, grab| {
             grab.set_focus(data, handle, focus, serial);
         });
+        std::mem::drop(guard);
+        self.notify_indicators(data, None, None);
     }
 
     /// Return the key codes of the currently pressed keys.
@@ -1090,10 +1409,13 @@ impl<D: SeatHandler + 'static> KeyboardHandle<D> {
     }
 
     /// Set the modifiers state.
-    pub fn set_modifier_state(&self, mods_state: ModifiersState) -> u32 {
-        let internal = &mut self.arc.internal.lock().unwrap();
+    ///
+    /// Changed modifiers are forwarded to an active drag'n'drop and changed leds or
+    /// indicators are reported to the [`SeatHandler`].
+    pub fn set_modifier_state(&self, data: &mut D, mods_state: ModifiersState) -> u32 {
+        let mut internal = self.arc.internal.lock().unwrap();
 
-        let (leds_changed, led_state, modifiers_changed) = {
+        let (leds_changed, led_state, modifiers_changed, mods_state) = {
             let state = &mut internal.xkb.lock().unwrap().state;
 
             let serialized = mods_state.serialize_back(state);
@@ -1115,14 +1437,21 @@ impl<D: SeatHandler + 'static> KeyboardHandle<D> {
             let led_mapping = &internal.led_mapping;
             let mut led_state = internal.led_state;
             let leds_changed = led_state.update_with(state, led_mapping);
+            let mut mods_state = internal.mods_state;
+            mods_state.update_with(state);
 
-            (leds_changed, led_state, modifiers_changed)
+            (leds_changed, led_state, modifiers_changed, mods_state)
         };
 
         if leds_changed {
             internal.led_state = led_state;
         }
+        internal.mods_state = mods_state;
+        let indicators = internal.update_indicators();
+        std::mem::drop(internal);
 
+        self.notify_indicators(data, leds_changed.then_some(led_state), indicators);
+        self.notify_modifiers(data, mods_state);
         modifiers_changed
     }
 
@@ -1131,11 +1460,49 @@ impl<D: SeatHandler + 'static> KeyboardHandle<D> {
         self.arc.internal.lock().unwrap().led_state
     }
 
+    /// Get the current layout and lock state of the keyboard
+    ///
+    /// Changes are reported via [`SeatHandler::keyboard_indicators_changed`].
+    pub fn indicators(&self) -> KeyboardIndicators {
+        self.arc.internal.lock().unwrap().indicators.clone()
+    }
+
     /// Check if keyboard has focus
     pub fn is_focused(&self) -> bool {
         self.arc.internal.lock().unwrap().focus.is_some()
     }
 
+    /// Returns the repeat rate, in keys per second, and the repeat delay, in milliseconds,
+    /// configured for this keyboard
+    pub fn repeat_info(&self) -> (i32, i32) {
+        let guard = self.arc.internal.lock().unwrap();
+        (guard.repeat_rate, guard.repeat_delay)
+    }
+
+    /// Returns whether the keymap marks the key as repeating
+    pub fn key_repeats(&self, keycode: Keycode) -> bool {
+        let guard = self.arc.internal.lock().unwrap();
+        let xkb = guard.xkb.lock().unwrap();
+        xkb.keymap.key_repeats(keycode)
+    }
+
+    /// Forward a repeated press of a key to the current grab and focus
+    ///
+    /// The state of the keyboard is not updated. Wayland clients handle key repeat themselves,
+    /// so this is meant for compositor-internal targets, see [`KeyRepeat`].
+    /// Does nothing, if the key is not pressed anymore.
+    pub fn repeat_input(&self, data: &mut D, keycode: Keycode, serial: Serial, time: u32) {
+        let mut guard = self.arc.internal.lock().unwrap();
+        if !guard.forwarded_pressed_keys.contains(&keycode) {
+            return;
+        }
+
+        let seat = self.get_seat(data);
+        guard.with_grab(data, &seat, |data, handle, grab| {
+            grab.input(data, handle, keycode, KeyState::Pressed, None, serial, time);
+        });
+    }
+
     /// Change the repeat info configured for this keyboard
     #[instrument(parent = &self.arc.span, skip(self))]
     pub fn change_repeat_info(&self, rate: i32, delay: i32) {
@@ -1147,9 +1514,9 @@ impl<D: SeatHandler + 'static> KeyboardHandle<D> {
             let Ok(kbd) = kbd.upgrade() else {
                 continue;
             };
-            if kbd.version() >= 4 {
-                kbd.repeat_info(rate, delay);
-            }
+            kbd.send_since(wl_keyboard::EVT_REPEAT_INFO_SINCE, |kbd| {
+                kbd.repeat_info(rate, delay)
+            });
         }
     }
 
@@ -1339,8 +1706,15 @@ impl<D: SeatHandler + 'static> KeyboardInnerHandle<'_, D> {
                         })
                         .collect();
 
-                    focus.replace(old_focus, self.seat, data, keys, self.inner.mods_state, serial);
-                    data.focus_changed(self.seat, Some(&focus));
+                    focus.replace(
+                        old_focus.clone(),
+                        self.seat,
+                        data,
+                        keys,
+                        self.inner.mods_state,
+                        serial,
+                    );
+                    data.keyboard_focus_changed(self.seat, Some(&old_focus), Some(&focus), serial);
                 }
                 (focus, None) => {
                     let keys = self
@@ -1355,12 +1729,13 @@ impl<D: SeatHandler + 'static> KeyboardInnerHandle<'_, D> {
 
                     focus.enter(self.seat, data, keys, serial);
                     focus.modifiers(self.seat, data, self.inner.mods_state, serial);
-                    data.focus_changed(self.seat, Some(&focus));
+                    data.keyboard_focus_changed(self.seat, None, Some(&focus), serial);
                 }
             }
         } else if let Some((old_focus, _)) = self.inner.focus.take() {
             trace!("Focus unset");
             old_focus.leave(self.seat, data, serial);
+            data.keyboard_focus_changed(self.seat, Some(&old_focus), None, serial);
         }
     }
 }
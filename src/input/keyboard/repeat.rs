@@ -0,0 +1,103 @@
+use std::{fmt, time::Duration};
+
+use calloop::{
+    timer::{TimeoutAction, Timer},
+    LoopHandle, RegistrationToken,
+};
+use tracing::warn;
+
+use crate::{backend::input::KeyState, input::SeatHandler, utils::SERIAL_COUNTER};
+
+use super::{KeyboardHandle, Keycode};
+
+/// Synthesizes key repeats for compositor-internal keyboard targets
+///
+/// Wayland clients repeat keys themselves based on the repeat info of the keyboard.
+/// Targets implemented by the compositor, e.g. the text entry of a compositor-drawn lock screen
+/// or a keyboard grab, don't get that for free. Feed the key events delivered to such targets
+/// to [`KeyRepeat::key`], and repeated presses are forwarded using
+/// [`KeyboardHandle::repeat_input`] with the repeat info of the keyboard.
+///
+/// Call [`KeyRepeat::stop`] once the focus moves to a client again.
+pub struct KeyRepeat<D: SeatHandler> {
+    loop_handle: LoopHandle<'static, D>,
+    repeating: Option<(Keycode, RegistrationToken)>,
+}
+
+impl<D: SeatHandler> fmt::Debug for KeyRepeat<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyRepeat")
+            .field("repeating", &self.repeating.as_ref().map(|(keycode, _)| keycode))
+            .finish_non_exhaustive()
+    }
+}
+
+impl<D: SeatHandler + 'static> KeyRepeat<D> {
+    /// Create a new key repeat, scheduling repeats on the given event loop
+    pub fn new(loop_handle: LoopHandle<'static, D>) -> Self {
+        KeyRepeat {
+            loop_handle,
+            repeating: None,
+        }
+    }
+
+    /// Returns the key currently repeating, if any
+    pub fn repeating(&self) -> Option<Keycode> {
+        self.repeating.as_ref().map(|(keycode, _)| *keycode)
+    }
+
+    /// Track a key event delivered to a compositor-internal target
+    ///
+    /// Pressing a key, which the keymap marks as repeating, starts repeating it after the
+    /// repeat delay, replacing the previously repeating key. Releasing the key stops the repeat.
+    /// `time` is the time of the key event, in milliseconds.
+    pub fn key(&mut self, keyboard: &KeyboardHandle<D>, keycode: Keycode, state: KeyState, time: u32) {
+        match state {
+            KeyState::Pressed => {
+                self.stop();
+
+                let (rate, delay) = keyboard.repeat_info();
+                if rate <= 0 || !keyboard.key_repeats(keycode) {
+                    return;
+                }
+                let delay = delay.max(0) as u32;
+                let interval = (1000 / rate as u32).max(1);
+
+                let keyboard = keyboard.clone();
+                let mut repeat_time = time.wrapping_add(delay);
+                let timer = Timer::from_duration(Duration::from_millis(delay as u64));
+                match self.loop_handle.insert_source(timer, move |_, _, data| {
+                    if !keyboard.pressed_keys().contains(&keycode) {
+                        return TimeoutAction::Drop;
+                    }
+                    keyboard.repeat_input(data, keycode, SERIAL_COUNTER.next_serial(), repeat_time);
+                    repeat_time = repeat_time.wrapping_add(interval);
+                    TimeoutAction::ToDuration(Duration::from_millis(interval as u64))
+                }) {
+                    Ok(token) => self.repeating = Some((keycode, token)),
+                    Err(err) => warn!(error = ?err.error, "Failed to schedule key repeat"),
+                }
+            }
+            KeyState::Released => {
+                if self.repeating() == Some(keycode) {
+                    self.stop();
+                }
+            }
+        }
+    }
+
+    /// Stop repeating the current key
+    pub fn stop(&mut self) {
+        if let Some((_, token)) = self.repeating.take() {
+            self.loop_handle.remove(token);
+        }
+    }
+}
+
+impl<D: SeatHandler> Drop for KeyRepeat<D> {
+    fn drop(&mut self) {
+        if let Some((_, token)) = self.repeating.take() {
+            self.loop_handle.remove(token);
+        }
+    }
+}
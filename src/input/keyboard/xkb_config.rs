@@ -8,6 +8,9 @@ pub use xkbcommon::xkb;
 ///
 /// For details, see the [documentation at xkbcommon.org][docs].
 ///
+/// The locked and latched modifiers are applied whenever the configuration is used to create
+/// a keyboard or to switch its keymap.
+///
 /// [docs]: https://xkbcommon.org/doc/current/structxkb__rule__names.html
 #[derive(Clone, Debug, Default)]
 pub struct XkbConfig<'a> {
@@ -27,6 +30,15 @@ pub struct XkbConfig<'a> {
     /// preferences, like which key combinations are used for switching layouts, or which key is the
     /// Compose key.
     pub options: Option<String>,
+    /// Names of modifiers to lock when the keymap is applied, e.g. [`xkb::MOD_NAME_NUM`] to
+    /// enable num lock.
+    ///
+    /// Names unknown to the keymap are ignored.
+    pub locked_modifiers: &'a [&'a str],
+    /// Names of modifiers to latch when the keymap is applied.
+    ///
+    /// Names unknown to the keymap are ignored.
+    pub latched_modifiers: &'a [&'a str],
 }
 
 impl XkbConfig<'_> {
@@ -42,4 +54,60 @@ impl XkbConfig<'_> {
         )
         .ok_or(())
     }
+
+    // locks and latches the configured modifiers on top of the current state
+    pub(crate) fn apply_modifiers(&self, keymap: &xkb::Keymap, state: &mut xkb::State) {
+        apply_modifiers(keymap, state, self.locked_modifiers, self.latched_modifiers);
+    }
+}
+
+// locks and latches the given modifiers on top of the current state
+pub(crate) fn apply_modifiers(
+    keymap: &xkb::Keymap,
+    state: &mut xkb::State,
+    locked: &[impl AsRef<str>],
+    latched: &[impl AsRef<str>],
+) {
+    fn mask(keymap: &xkb::Keymap, names: &[impl AsRef<str>]) -> u32 {
+        names
+            .iter()
+            .map(|name| keymap.mod_get_index(name.as_ref()))
+            .filter(|&idx| idx != xkb::MOD_INVALID)
+            .fold(0, |mask, idx| mask | (1 << idx))
+    }
+    let locked = mask(keymap, locked);
+    let latched = mask(keymap, latched);
+    if locked == 0 && latched == 0 {
+        return;
+    }
+
+    state.update_mask(
+        state.serialize_mods(xkb::STATE_MODS_DEPRESSED),
+        state.serialize_mods(xkb::STATE_MODS_LATCHED) | latched,
+        state.serialize_mods(xkb::STATE_MODS_LOCKED) | locked,
+        state.serialize_layout(xkb::STATE_LAYOUT_DEPRESSED),
+        state.serialize_layout(xkb::STATE_LAYOUT_LATCHED),
+        state.serialize_layout(xkb::STATE_LAYOUT_LOCKED),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn apply_locked_modifiers() {
+        let context = xkb::Context::new(xkb::CONTEXT_NO_FLAGS);
+        let config = XkbConfig {
+            layout: "us",
+            locked_modifiers: &[xkb::MOD_NAME_NUM, "NotAModifier"],
+            ..XkbConfig::default()
+        };
+        let keymap = config.compile_keymap(&context).unwrap();
+        let mut state = xkb::State::new(&keymap);
+        config.apply_modifiers(&keymap, &mut state);
+
+        assert!(state.mod_name_is_active(xkb::MOD_NAME_NUM, xkb::STATE_MODS_LOCKED));
+        assert!(!state.mod_name_is_active(xkb::MOD_NAME_CAPS, xkb::STATE_MODS_EFFECTIVE));
+    }
 }
@@ -0,0 +1,198 @@
+//! Mapping of absolute input devices to outputs
+//!
+//! Touchscreens and drawing tablets report absolute positions normalized to the surface of the device.
+//! To translate them into the global compositor space, the device has to be associated with an output,
+//! which is what [`InputMapping`] keeps track of. Positions are adjusted by an optional
+//! [`CalibrationMatrix`] and transformed according to the transform and geometry of the output.
+//!
+//! ```no_run
+//! # use smithay::backend::input::{InputBackend, TouchDownEvent};
+//! # use smithay::desktop::{Space, Window};
+//! # use smithay::input::mapping::InputMapping;
+//! # fn handle<B: InputBackend, E: TouchDownEvent<B>>(mapping: &InputMapping, space: &Space<Window>, event: E) {
+//! if let Some((output, position)) = mapping.map_position(&event, |output| space.output_geometry(output)) {
+//!     // `position` is in global logical coordinates
+//! }
+//! # }
+//! ```
+
+use std::collections::HashMap;
+
+use crate::{
+    backend::input::{AbsolutePositionEvent, Device, InputBackend},
+    output::{Output, WeakOutput},
+    utils::{Logical, Point, Raw, Rectangle, Size, Transform},
+};
+
+/// Calibration matrix of an absolute input device
+///
+/// Uses the same format as libinput: the first two rows of a 3x3 matrix in row-major order,
+/// applied to positions normalized to `0.0..=1.0`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CalibrationMatrix(pub [f32; 6]);
+
+impl Default for CalibrationMatrix {
+    #[inline]
+    fn default() -> Self {
+        CalibrationMatrix::IDENTITY
+    }
+}
+
+impl CalibrationMatrix {
+    /// The identity matrix, not altering any positions
+    pub const IDENTITY: CalibrationMatrix = CalibrationMatrix([1.0, 0.0, 0.0, 0.0, 1.0, 0.0]);
+
+    /// Applies the matrix to a normalized position
+    pub fn apply(&self, point: Point<f64, Raw>) -> Point<f64, Raw> {
+        let [a, b, c, d, e, f] = self.0.map(f64::from);
+        (a * point.x + b * point.y + c, d * point.x + e * point.y + f).into()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct DeviceMapping {
+    output: Option<WeakOutput>,
+    calibration: CalibrationMatrix,
+}
+
+/// Associates absolute input devices with outputs
+///
+/// Devices are identified by their [`Device::id`].
+#[derive(Debug, Default)]
+pub struct InputMapping {
+    devices: HashMap<String, DeviceMapping>,
+}
+
+impl InputMapping {
+    /// Create a new empty mapping
+    pub fn new() -> Self {
+        InputMapping::default()
+    }
+
+    /// Maps a device to an output, or removes the output mapping if `None` is passed
+    pub fn map_to_output<D: Device>(&mut self, device: &D, output: Option<&Output>) {
+        self.devices.entry(device.id()).or_default().output = output.map(Output::downgrade);
+    }
+
+    /// Returns the output a device is mapped to
+    ///
+    /// Returns `None` if the device is not mapped or the output was destroyed.
+    pub fn output<D: Device>(&self, device: &D) -> Option<Output> {
+        self.devices
+            .get(&device.id())
+            .and_then(|mapping| mapping.output.as_ref())
+            .and_then(WeakOutput::upgrade)
+    }
+
+    /// Sets the calibration matrix of a device
+    pub fn set_calibration<D: Device>(&mut self, device: &D, calibration: CalibrationMatrix) {
+        self.devices.entry(device.id()).or_default().calibration = calibration;
+    }
+
+    /// Returns the calibration matrix of a device
+    pub fn calibration<D: Device>(&self, device: &D) -> CalibrationMatrix {
+        self.devices
+            .get(&device.id())
+            .map(|mapping| mapping.calibration)
+            .unwrap_or_default()
+    }
+
+    /// Forgets about a device, e.g. once it was removed
+    pub fn remove_device<D: Device>(&mut self, device: &D) {
+        self.devices.remove(&device.id());
+    }
+
+    /// Maps the position of an absolute event to the output of its device
+    ///
+    /// `output_geometry` has to return the geometry of an output in the global compositor space,
+    /// e.g. using [`Space::output_geometry`](crate::desktop::Space::output_geometry).
+    ///
+    /// Returns the output and the position in global logical coordinates, or `None`
+    /// if the device is not mapped to an output or the output has no geometry.
+    pub fn map_position<B, E, F>(
+        &self,
+        event: &E,
+        output_geometry: F,
+    ) -> Option<(Output, Point<f64, Logical>)>
+    where
+        B: InputBackend,
+        E: AbsolutePositionEvent<B>,
+        F: FnOnce(&Output) -> Option<Rectangle<i32, Logical>>,
+    {
+        let device = event.device();
+        let output = self.output(&device)?;
+        let geometry = output_geometry(&output)?;
+        let normalized = Point::from((event.x_transformed(1), event.y_transformed(1)));
+        let position = transform_position(
+            normalized,
+            self.calibration(&device),
+            output.current_transform(),
+            geometry,
+        );
+        Some((output, position))
+    }
+}
+
+/// Transforms a normalized device position into the global compositor space
+///
+/// The position is calibrated first, then transformed from the orientation of the panel
+/// to the orientation of the output contents given by `transform` and finally scaled to
+/// the output `geometry`.
+pub fn transform_position(
+    position: Point<f64, Raw>,
+    calibration: CalibrationMatrix,
+    transform: Transform,
+    geometry: Rectangle<i32, Logical>,
+) -> Point<f64, Logical> {
+    let position = calibration.apply(position);
+    let unit = Size::<f64, Raw>::from((1.0, 1.0));
+    let position = transform.invert().transform_point_in(position, &unit);
+    let geometry = geometry.to_f64();
+    Point::from((
+        geometry.loc.x + position.x * geometry.size.w,
+        geometry.loc.y + position.y * geometry.size.h,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transformed_positions() {
+        let geometry = Rectangle::<i32, Logical>::new((100, 0).into(), (200, 100).into());
+        let position = Point::<f64, Raw>::from((0.25, 0.5));
+
+        assert_eq!(
+            transform_position(position, CalibrationMatrix::IDENTITY, Transform::Normal, geometry),
+            (150.0, 50.0).into()
+        );
+        // the panel is upside down, so its top left corner is the bottom right of the output
+        assert_eq!(
+            transform_position(
+                (0.0, 0.0).into(),
+                CalibrationMatrix::IDENTITY,
+                Transform::_180,
+                geometry
+            ),
+            (300.0, 100.0).into()
+        );
+        assert_eq!(
+            transform_position(
+                (0.0, 0.0).into(),
+                CalibrationMatrix::IDENTITY,
+                Transform::Flipped,
+                geometry
+            ),
+            (300.0, 0.0).into()
+        );
+
+        // swap the axes and invert x
+        let calibration = CalibrationMatrix([0.0, -1.0, 1.0, 1.0, 0.0, 0.0]);
+        assert_eq!(calibration.apply(position), (0.5, 0.25).into());
+        assert_eq!(
+            transform_position(position, calibration, Transform::Normal, geometry),
+            (200.0, 25.0).into()
+        );
+    }
+}
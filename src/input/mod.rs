@@ -116,6 +116,9 @@
 //! [`Seat::add_keyboard`], [`Seat::add_pointer`] and [`Seat::add_touch`].
 //! These methods return handles that can be cloned and sent across thread, so you can keep one around
 //! in your event-handling code to forward inputs to your clients.
+//! Alternatively [`SeatDevices`](devices::SeatDevices) manages the capabilities based on the connected devices.
+//! For multi-seat setups [`SeatAssignments`](devices::SeatAssignments) routes the devices to the seats
+//! they are assigned to by udev.
 //!
 
 use std::{
@@ -126,18 +129,26 @@ use std::{
 
 use tracing::{info_span, instrument};
 
+use self::focus::{FocusEvent, FocusPolicy, FocusSuggestion};
 use self::touch::TouchTarget;
 use self::{
-    keyboard::{Error as KeyboardError, KeyboardHandle, KeyboardTarget, LedState},
+    keyboard::{Error as KeyboardError, KeyboardHandle, KeyboardIndicators, KeyboardTarget, LedState},
     touch::TouchHandle,
 };
 use self::{
-    pointer::{CursorImageStatus, PointerHandle, PointerTarget},
+    pointer::{CursorImageStatus, CursorSize, PointerHandle, PointerTarget},
     touch::TouchGrab,
 };
-use crate::utils::{user_data::UserDataMap, Serial};
+use crate::{
+    backend::input::TouchSlot,
+    utils::{user_data::UserDataMap, Serial},
+};
 
+pub mod devices;
+pub mod feedback;
+pub mod focus;
 pub mod keyboard;
+pub mod mapping;
 pub mod pointer;
 pub mod touch;
 
@@ -154,14 +165,132 @@ pub trait SeatHandler: Sized {
     fn seat_state(&mut self) -> &mut SeatState<Self>;
 
     /// Callback that will be notified whenever the focus of the seat changes.
+    ///
+    /// This is called by the default implementation of [`SeatHandler::keyboard_focus_changed`],
+    /// including with `None` when the keyboard focus is unset.
     fn focus_changed(&mut self, _seat: &Seat<Self>, _focused: Option<&Self::KeyboardFocus>) {}
 
+    /// Callback that will be notified whenever the keyboard focus of the seat changes.
+    ///
+    /// `serial` is the serial passed to [`KeyboardHandle::set_focus`](keyboard::KeyboardHandle::set_focus).
+    /// The default implementation calls [`SeatHandler::focus_changed`] with the new focus,
+    /// which is `None` if the focus was unset.
+    fn keyboard_focus_changed(
+        &mut self,
+        seat: &Seat<Self>,
+        old: Option<&Self::KeyboardFocus>,
+        new: Option<&Self::KeyboardFocus>,
+        serial: Serial,
+    ) {
+        let _ = (old, serial);
+        self.focus_changed(seat, new);
+    }
+
+    /// Callback that will be notified whenever the pointer focus of the seat changes.
+    ///
+    /// `serial` is the serial of the motion event, that moved the pointer onto or off the target.
+    fn pointer_focus_changed(
+        &mut self,
+        _seat: &Seat<Self>,
+        _old: Option<&Self::PointerFocus>,
+        _new: Option<&Self::PointerFocus>,
+        _serial: Serial,
+    ) {
+    }
+
+    /// Callback that will be notified whenever the focus of a touch point changes.
+    ///
+    /// The focus is set when the touch point goes down and unset when it goes up or is cancelled.
+    /// `serial` is the serial of the down or up event, or the sequence serial of the cancellation.
+    fn touch_focus_changed(
+        &mut self,
+        _seat: &Seat<Self>,
+        _slot: TouchSlot,
+        _old: Option<&Self::TouchFocus>,
+        _new: Option<&Self::TouchFocus>,
+        _serial: Serial,
+    ) {
+    }
+
     /// Callback that will be notified whenever a client requests to set a custom cursor image.
     fn cursor_image(&mut self, _seat: &Seat<Self>, _image: CursorImageStatus) {}
 
     /// Callback that will be notified whenever the keyboard led state changes.
     fn led_state_changed(&mut self, _seat: &Seat<Self>, _led_state: LedState) {}
+
+    /// Callback that will be notified whenever the active layout, caps lock or num lock
+    /// of the keyboard changes.
+    ///
+    /// Useful to update indicators in panels or on-screen displays.
+    fn keyboard_indicators_changed(&mut self, _seat: &Seat<Self>, _indicators: &KeyboardIndicators) {}
+
+    /// Callback that will be notified whenever the capabilities of a seat changed
+    /// because input devices were connected or disconnected.
+    ///
+    /// See [`SeatDevices`](devices::SeatDevices).
+    fn capabilities_changed(&mut self, _seat: &Seat<Self>, _capabilities: SeatCapabilities) {}
+
+    /// Callback that will be notified whenever a [`FocusPolicy`] suggests a change of the keyboard focus.
+    ///
+    /// See [`Seat::apply_focus_policy`]. The default implementation sets the focus of the keyboard
+    /// of the seat, override this to veto suggestions or to additionally raise the new focus.
+    fn focus_change_suggested(
+        &mut self,
+        seat: &Seat<Self>,
+        target: Option<Self::KeyboardFocus>,
+        serial: Serial,
+    ) where
+        Self: 'static,
+    {
+        if let Some(keyboard) = seat.get_keyboard() {
+            keyboard.set_focus(self, target, serial);
+        }
+    }
+}
+
+bitflags::bitflags! {
+    /// Capabilities of a [`Seat`]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    pub struct SeatCapabilities: u32 {
+        /// The seat has a pointer
+        const POINTER = 1;
+        /// The seat has a keyboard
+        const KEYBOARD = 2;
+        /// The seat has a touch device
+        const TOUCH = 4;
+    }
 }
+
+/// An active grab of a pointer, keyboard or touch device
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ActiveGrab {
+    /// Name of the type of the grab, e.g. `smithay::wayland::selection::data_device::DnDGrab<State>`
+    pub type_name: &'static str,
+    /// The serial the grab was started with
+    pub serial: Serial,
+}
+
+/// Active grabs of the devices of a [`Seat`]
+///
+/// Compositors can use this to suppress their own input handling, e.g. keybindings or
+/// edge triggers, while a grab is active.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SeatGrabs {
+    /// Grab of the pointer, if any
+    pub pointer: Option<ActiveGrab>,
+    /// Grab of the keyboard, if any
+    pub keyboard: Option<ActiveGrab>,
+    /// Grab of the touch device, if any
+    pub touch: Option<ActiveGrab>,
+}
+
+impl SeatGrabs {
+    /// Returns whether any device of the seat is grabbed
+    pub fn is_grabbed(&self) -> bool {
+        self.pointer.is_some() || self.keyboard.is_some() || self.touch.is_some()
+    }
+}
+
 /// Delegate type for all [Seat] globals.
 ///
 /// Events will be forwarded to an instance of the Seat global.
@@ -238,11 +367,14 @@ pub(crate) struct Inner<D: SeatHandler> {
     pub(crate) pointer: Option<PointerHandle<D>>,
     pub(crate) keyboard: Option<KeyboardHandle<D>>,
     pub(crate) touch: Option<TouchHandle<D>>,
+    pub(crate) cursor_size: CursorSize,
 
     #[cfg(feature = "wayland_frontend")]
     pub(crate) global: Option<wayland_server::backend::GlobalId>,
     #[cfg(feature = "wayland_frontend")]
     pub(crate) known_seats: Vec<wayland_server::Weak<wayland_server::protocol::wl_seat::WlSeat>>,
+    #[cfg(feature = "wayland_frontend")]
+    pub(crate) objects: crate::wayland::seat::SeatObjects,
 }
 
 #[cfg(not(feature = "wayland_frontend"))]
@@ -251,6 +383,7 @@ impl<D: SeatHandler> fmt::Debug for Inner<D> {
         f.debug_struct("Inner")
             .field("pointer", &self.pointer)
             .field("keyboard", &self.keyboard)
+            .field("cursor_size", &self.cursor_size)
             .finish()
     }
 }
@@ -262,8 +395,10 @@ impl<D: SeatHandler> fmt::Debug for Inner<D> {
             .field("pointer", &self.pointer)
             .field("keyboard", &self.keyboard)
             .field("touch", &self.touch)
+            .field("cursor_size", &self.cursor_size)
             .field("global", &self.global)
             .field("known_seats", &self.known_seats)
+            .field("objects", &self.objects)
             .finish()
     }
 }
@@ -322,11 +457,14 @@ impl<D: SeatHandler> SeatState<D> {
                 pointer: None,
                 keyboard: None,
                 touch: None,
+                cursor_size: CursorSize::default(),
 
                 #[cfg(feature = "wayland_frontend")]
                 global: None,
                 #[cfg(feature = "wayland_frontend")]
                 known_seats: Vec::new(),
+                #[cfg(feature = "wayland_frontend")]
+                objects: Default::default(),
             }),
             span,
             user_data_map: UserDataMap::new(),
@@ -335,6 +473,23 @@ impl<D: SeatHandler> SeatState<D> {
 
         Seat { arc }
     }
+
+    /// Removes a seat
+    ///
+    /// All capabilities are removed from the seat. Seats created with
+    /// [`SeatState::new_wl_seat`] should be removed using [`SeatState::remove_wl_seat`] instead,
+    /// to also destroy their global.
+    pub fn remove_seat(&mut self, seat: &Seat<D>)
+    where
+        D: 'static,
+    {
+        self.seats.retain(|s| s != seat);
+
+        let mut seat = seat.clone();
+        seat.remove_pointer();
+        seat.remove_keyboard();
+        seat.remove_touch();
+    }
 }
 
 impl<D: SeatHandler + 'static> Seat<D> {
@@ -438,7 +593,10 @@ impl<D: SeatHandler + 'static> Seat<D> {
         }
         inner.pointer = Some(pointer.clone());
         #[cfg(feature = "wayland_frontend")]
-        inner.send_all_caps();
+        {
+            inner.bind_objects();
+            inner.send_all_caps();
+        }
         pointer
     }
 
@@ -473,6 +631,10 @@ impl<D: SeatHandler + 'static> Seat<D> {
     /// will overwrite it, and will be seen by the clients as if the
     /// keyboard was unplugged and a new one was plugged.
     ///
+    /// If the initial led state differs from the default, e.g. because of the
+    /// [locked modifiers](keyboard::XkbConfig::locked_modifiers) of the config,
+    /// [`SeatHandler::led_state_changed`] is called the first time the keyboard is focused or receives input.
+    ///
     /// # Examples
     ///
     /// ```no_run
@@ -575,7 +737,10 @@ impl<D: SeatHandler + 'static> Seat<D> {
         }
         inner.keyboard = Some(keyboard.clone());
         #[cfg(feature = "wayland_frontend")]
-        inner.send_all_caps();
+        {
+            inner.bind_objects();
+            inner.send_all_caps();
+        }
         Ok(keyboard)
     }
 
@@ -597,6 +762,17 @@ impl<D: SeatHandler + 'static> Seat<D> {
         }
     }
 
+    // Re-adds a keyboard previously removed from this seat, keeping its keymap and state
+    pub(crate) fn restore_keyboard(&mut self, keyboard: KeyboardHandle<D>) {
+        let mut inner = self.arc.inner.lock().unwrap();
+        inner.keyboard = Some(keyboard);
+        #[cfg(feature = "wayland_frontend")]
+        {
+            inner.bind_objects();
+            inner.send_all_caps();
+        }
+    }
+
     /// Adds the touch capability to this seat
     ///
     /// You are provided a [`TouchHandle`], which allows you to send input events
@@ -650,7 +826,10 @@ impl<D: SeatHandler + 'static> Seat<D> {
         }
         inner.touch = Some(touch.clone());
         #[cfg(feature = "wayland_frontend")]
-        inner.send_all_caps();
+        {
+            inner.bind_objects();
+            inner.send_all_caps();
+        }
         touch
     }
 
@@ -659,6 +838,31 @@ impl<D: SeatHandler + 'static> Seat<D> {
         self.arc.inner.lock().unwrap().touch.clone()
     }
 
+    /// Returns the current capabilities of this seat
+    pub fn capabilities(&self) -> SeatCapabilities {
+        let inner = self.arc.inner.lock().unwrap();
+        let mut capabilities = SeatCapabilities::empty();
+        if inner.pointer.is_some() {
+            capabilities |= SeatCapabilities::POINTER;
+        }
+        if inner.keyboard.is_some() {
+            capabilities |= SeatCapabilities::KEYBOARD;
+        }
+        if inner.touch.is_some() {
+            capabilities |= SeatCapabilities::TOUCH;
+        }
+        capabilities
+    }
+
+    /// Returns the active grabs of the devices of this seat
+    pub fn grabs(&self) -> SeatGrabs {
+        SeatGrabs {
+            pointer: self.get_pointer().and_then(|pointer| pointer.active_grab()),
+            keyboard: self.get_keyboard().and_then(|keyboard| keyboard.active_grab()),
+            touch: self.get_touch().and_then(|touch| touch.active_grab()),
+        }
+    }
+
     /// Remove the touch capability from this seat
     ///
     /// Clients will be appropriately notified.
@@ -675,6 +879,47 @@ impl<D: SeatHandler + 'static> Seat<D> {
     pub fn name(&self) -> &str {
         &self.arc.name
     }
+
+    /// Returns the nominal size of named cursors of this seat
+    pub fn cursor_size(&self) -> CursorSize {
+        self.arc.inner.lock().unwrap().cursor_size
+    }
+
+    /// Sets the nominal size of named cursors of this seat
+    ///
+    /// Defaults to [`CursorSize::default`]. The size is not applied by smithay,
+    /// it is up to the compositor to pick cursor images per output based on it.
+    pub fn set_cursor_size(&self, size: CursorSize) {
+        self.arc.inner.lock().unwrap().cursor_size = size;
+    }
+
+    /// Evaluates a [`FocusPolicy`] for an input event
+    ///
+    /// The policy is given the current keyboard focus of this seat. If it suggests a different focus,
+    /// [`SeatHandler::focus_change_suggested`] is called.
+    ///
+    /// Returns `true` if a focus change was suggested.
+    pub fn apply_focus_policy<P>(
+        &self,
+        data: &mut D,
+        policy: &mut P,
+        event: FocusEvent<'_, D::KeyboardFocus>,
+        serial: Serial,
+    ) -> bool
+    where
+        P: FocusPolicy<D::KeyboardFocus> + ?Sized,
+    {
+        let current = self.get_keyboard().and_then(|keyboard| keyboard.current_focus());
+        let target = match policy.evaluate(current.as_ref(), &event) {
+            FocusSuggestion::Keep => return false,
+            FocusSuggestion::Focus(target) if current.as_ref() == Some(&target) => return false,
+            FocusSuggestion::Focus(target) => Some(target),
+            FocusSuggestion::Clear if current.is_none() => return false,
+            FocusSuggestion::Clear => None,
+        };
+        data.focus_change_suggested(self, target, serial);
+        true
+    }
 }
 
 pub(super) enum GrabStatus<G: ?Sized> {
@@ -693,3 +938,232 @@ impl<G: ?Sized> fmt::Debug for GrabStatus<G> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        keyboard::{KeysymHandle, ModifiersState},
+        pointer::{
+            AxisFrame, ButtonEvent, GestureHoldBeginEvent, GestureHoldEndEvent, GesturePinchBeginEvent,
+            GesturePinchEndEvent, GesturePinchUpdateEvent, GestureSwipeBeginEvent, GestureSwipeEndEvent,
+            GestureSwipeUpdateEvent, MotionEvent, RelativeMotionEvent,
+        },
+        touch::{DownEvent, MotionEvent as TouchMotionEvent, OrientationEvent, ShapeEvent, UpEvent},
+        *,
+    };
+    use crate::{
+        backend::input::{KeyState, TouchSlot},
+        utils::IsAlive,
+    };
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Target(u32);
+
+    impl IsAlive for Target {
+        fn alive(&self) -> bool {
+            true
+        }
+    }
+
+    impl KeyboardTarget<State> for Target {
+        fn enter(&self, _: &Seat<State>, _: &mut State, _: Vec<KeysymHandle<'_>>, _: Serial) {}
+        fn leave(&self, _: &Seat<State>, _: &mut State, _: Serial) {}
+        fn key(&self, _: &Seat<State>, _: &mut State, _: KeysymHandle<'_>, _: KeyState, _: Serial, _: u32) {}
+        fn modifiers(&self, _: &Seat<State>, _: &mut State, _: ModifiersState, _: Serial) {}
+    }
+
+    impl PointerTarget<State> for Target {
+        fn enter(&self, _: &Seat<State>, _: &mut State, _: &MotionEvent) {}
+        fn motion(&self, _: &Seat<State>, _: &mut State, _: &MotionEvent) {}
+        fn relative_motion(&self, _: &Seat<State>, _: &mut State, _: &RelativeMotionEvent) {}
+        fn button(&self, _: &Seat<State>, _: &mut State, _: &ButtonEvent) {}
+        fn axis(&self, _: &Seat<State>, _: &mut State, _: AxisFrame) {}
+        fn frame(&self, _: &Seat<State>, _: &mut State) {}
+        fn gesture_swipe_begin(&self, _: &Seat<State>, _: &mut State, _: &GestureSwipeBeginEvent) {}
+        fn gesture_swipe_update(&self, _: &Seat<State>, _: &mut State, _: &GestureSwipeUpdateEvent) {}
+        fn gesture_swipe_end(&self, _: &Seat<State>, _: &mut State, _: &GestureSwipeEndEvent) {}
+        fn gesture_pinch_begin(&self, _: &Seat<State>, _: &mut State, _: &GesturePinchBeginEvent) {}
+        fn gesture_pinch_update(&self, _: &Seat<State>, _: &mut State, _: &GesturePinchUpdateEvent) {}
+        fn gesture_pinch_end(&self, _: &Seat<State>, _: &mut State, _: &GesturePinchEndEvent) {}
+        fn gesture_hold_begin(&self, _: &Seat<State>, _: &mut State, _: &GestureHoldBeginEvent) {}
+        fn gesture_hold_end(&self, _: &Seat<State>, _: &mut State, _: &GestureHoldEndEvent) {}
+        fn leave(&self, _: &Seat<State>, _: &mut State, _: Serial, _: u32) {}
+    }
+
+    impl TouchTarget<State> for Target {
+        fn down(&self, _: &Seat<State>, _: &mut State, _: &DownEvent, _: Serial) {}
+        fn up(&self, _: &Seat<State>, _: &mut State, _: &UpEvent, _: Serial) {}
+        fn motion(&self, _: &Seat<State>, _: &mut State, _: &TouchMotionEvent, _: Serial) {}
+        fn frame(&self, _: &Seat<State>, _: &mut State, _: Serial) {}
+        fn cancel(&self, _: &Seat<State>, _: &mut State, _: Serial) {}
+        fn shape(&self, _: &Seat<State>, _: &mut State, _: &ShapeEvent, _: Serial) {}
+        fn orientation(&self, _: &Seat<State>, _: &mut State, _: &OrientationEvent, _: Serial) {}
+    }
+
+    type Change = (Option<u32>, Option<u32>);
+
+    #[derive(Default)]
+    struct State {
+        seat_state: SeatState<Self>,
+        focus: Vec<Option<u32>>,
+        pointer: Vec<Change>,
+        touch: Vec<(TouchSlot, Option<u32>, Option<u32>)>,
+        leds: Vec<keyboard::LedState>,
+    }
+
+    impl SeatHandler for State {
+        type KeyboardFocus = Target;
+        type PointerFocus = Target;
+        type TouchFocus = Target;
+
+        fn seat_state(&mut self) -> &mut SeatState<Self> {
+            &mut self.seat_state
+        }
+        fn focus_changed(&mut self, _seat: &Seat<Self>, focused: Option<&Target>) {
+            self.focus.push(focused.map(|t| t.0));
+        }
+        fn pointer_focus_changed(
+            &mut self,
+            _seat: &Seat<Self>,
+            old: Option<&Target>,
+            new: Option<&Target>,
+            _serial: Serial,
+        ) {
+            self.pointer.push((old.map(|t| t.0), new.map(|t| t.0)));
+        }
+        fn touch_focus_changed(
+            &mut self,
+            _seat: &Seat<Self>,
+            slot: TouchSlot,
+            old: Option<&Target>,
+            new: Option<&Target>,
+            _serial: Serial,
+        ) {
+            self.touch.push((slot, old.map(|t| t.0), new.map(|t| t.0)));
+        }
+        fn led_state_changed(&mut self, _seat: &Seat<Self>, led_state: keyboard::LedState) {
+            self.leds.push(led_state);
+        }
+    }
+
+    #[test]
+    fn keyboard_focus_changes() {
+        let mut state = State::default();
+        let mut seat = state.seat_state.new_seat("seat0");
+        let keyboard = seat.add_keyboard(Default::default(), 200, 25).unwrap();
+
+        keyboard.set_focus(&mut state, Some(Target(1)), 1.into());
+        keyboard.set_focus(&mut state, Some(Target(2)), 2.into());
+        keyboard.set_focus(&mut state, None, 3.into());
+        // the default `keyboard_focus_changed` also reports unsetting the focus
+        assert_eq!(state.focus, [Some(1), Some(2), None]);
+    }
+
+    #[test]
+    fn initial_led_state_is_reported() {
+        let mut state = State::default();
+        let mut seat = state.seat_state.new_seat("seat0");
+        let keyboard = seat
+            .add_keyboard(
+                keyboard::XkbConfig {
+                    locked_modifiers: &[keyboard::xkb::MOD_NAME_NUM],
+                    ..Default::default()
+                },
+                200,
+                25,
+            )
+            .unwrap();
+
+        keyboard.set_focus(&mut state, Some(Target(1)), 1.into());
+        keyboard.set_focus(&mut state, Some(Target(2)), 2.into());
+        assert_eq!(state.leds.len(), 1);
+        assert_eq!(state.leds[0].num, Some(true));
+    }
+
+    #[test]
+    fn locked_modifiers_survive_keymap_changes() {
+        let mut state = State::default();
+        let mut seat = state.seat_state.new_seat("seat0");
+        let keyboard = seat.add_keyboard(Default::default(), 200, 25).unwrap();
+        keyboard
+            .set_xkb_config(
+                &mut state,
+                keyboard::XkbConfig {
+                    locked_modifiers: &[keyboard::xkb::MOD_NAME_NUM],
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert!(keyboard.modifier_state().num_lock);
+
+        let keymap = keyboard.with_xkb_state(&mut state, |context| {
+            let xkb = context.xkb().lock().unwrap();
+            // SAFETY: the keymap is only used to serialize it
+            unsafe { xkb.keymap() }.get_as_string(keyboard::xkb::KEYMAP_FORMAT_TEXT_V1)
+        });
+        keyboard.set_keymap_from_string(&mut state, keymap).unwrap();
+        assert!(keyboard.modifier_state().num_lock);
+        assert_eq!(keyboard.led_state().num, Some(true));
+    }
+
+    #[test]
+    fn pointer_focus_changes() {
+        let mut state = State::default();
+        let mut seat = state.seat_state.new_seat("seat0");
+        let pointer = seat.add_pointer();
+
+        let motion = |state: &mut State, focus: Option<u32>, serial: u32| {
+            let event = MotionEvent {
+                location: (0.0, 0.0).into(),
+                serial: serial.into(),
+                time: 0,
+            };
+            pointer.motion(state, focus.map(|f| (Target(f), (0.0, 0.0).into())), &event);
+        };
+        motion(&mut state, Some(1), 1);
+        motion(&mut state, Some(1), 2);
+        motion(&mut state, Some(2), 3);
+        motion(&mut state, None, 4);
+        assert_eq!(
+            state.pointer,
+            [(None, Some(1)), (Some(1), Some(2)), (Some(2), None)]
+        );
+        assert!(state.focus.is_empty());
+    }
+
+    #[test]
+    fn touch_focus_changes() {
+        let mut state = State::default();
+        let mut seat = state.seat_state.new_seat("seat0");
+        let touch = seat.add_touch();
+
+        let first = TouchSlot::from(Some(0));
+        let second = TouchSlot::from(Some(1));
+        let down = |slot| DownEvent {
+            slot,
+            location: (0.0, 0.0).into(),
+            serial: 1.into(),
+            time: 0,
+        };
+        touch.down(&mut state, Some((Target(1), (0.0, 0.0).into())), &down(first));
+        touch.down(&mut state, Some((Target(1), (0.0, 0.0).into())), &down(second));
+        touch.up(
+            &mut state,
+            &UpEvent {
+                slot: first,
+                serial: 2.into(),
+                time: 0,
+            },
+        );
+        touch.cancel(&mut state);
+        assert_eq!(
+            state.touch,
+            [
+                (first, None, Some(1)),
+                (second, None, Some(1)),
+                (first, Some(1), None),
+                (second, Some(1), None),
+            ]
+        );
+    }
+}
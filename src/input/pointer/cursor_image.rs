@@ -3,7 +3,7 @@ use wayland_server::protocol::wl_surface::WlSurface;
 
 pub use cursor_icon::CursorIcon;
 
-use crate::utils::{Logical, Point};
+use crate::utils::{Buffer, Logical, Physical, Point, Rectangle, Size};
 use std::sync::Mutex;
 
 /// The role representing a surface set as the pointer cursor
@@ -48,3 +48,162 @@ impl CursorImageStatus {
         Self::Named(CursorIcon::Default)
     }
 }
+
+/// Nominal size of named cursors in logical pixels
+///
+/// Cursor themes provide images in a set of sizes. To display cursors with the same
+/// logical size on outputs with different scales, the image size has to be picked
+/// per output using [`CursorSize::nearest`] and rendered using the [`CursorPlacement`]
+/// returned by [`CursorSize::placement`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CursorSize(u32);
+
+impl Default for CursorSize {
+    #[inline]
+    fn default() -> Self {
+        CursorSize(24)
+    }
+}
+
+impl CursorSize {
+    /// Create a new cursor size, the size has to be at least 1
+    pub fn new(size: u32) -> Self {
+        CursorSize(size.max(1))
+    }
+
+    /// Read the cursor size from the `XCURSOR_SIZE` environment variable
+    ///
+    /// Falls back to the default size of 24 if the variable is not set or invalid.
+    pub fn from_env() -> Self {
+        std::env::var("XCURSOR_SIZE")
+            .ok()
+            .and_then(|size| size.parse::<u32>().ok())
+            .filter(|size| *size > 0)
+            .map(CursorSize)
+            .unwrap_or_default()
+    }
+
+    /// Returns the nominal size
+    pub fn get(&self) -> u32 {
+        self.0
+    }
+
+    /// Returns the ideal image size for an output scale
+    pub fn for_scale(&self, scale: f64) -> u32 {
+        ((self.0 as f64 * scale).round() as u32).max(1)
+    }
+
+    /// Picks the image size closest to the ideal size for an output scale from the available sizes
+    ///
+    /// If two sizes are equally close the bigger one is preferred, as downscaling looks better.
+    pub fn nearest(&self, scale: f64, available: impl IntoIterator<Item = u32>) -> Option<u32> {
+        let ideal = self.for_scale(scale) as i64;
+        available
+            .into_iter()
+            .min_by_key(|size| ((ideal - *size as i64).abs(), std::cmp::Reverse(*size)))
+    }
+
+    /// Places a cursor image at a pointer location
+    ///
+    /// `image_size` is the nominal size of the image, which might differ from its `dimensions`,
+    /// and `hotspot` the hotspot inside of the image.
+    pub fn placement(
+        &self,
+        location: Point<f64, Logical>,
+        image_size: u32,
+        dimensions: Size<i32, Buffer>,
+        hotspot: Point<i32, Buffer>,
+    ) -> CursorPlacement {
+        let buffer_scale = image_size.max(1) as f64 / self.0 as f64;
+        let hotspot =
+            Point::<f64, Logical>::from((hotspot.x as f64 / buffer_scale, hotspot.y as f64 / buffer_scale));
+        let size = Size::<f64, Logical>::from((
+            dimensions.w as f64 / buffer_scale,
+            dimensions.h as f64 / buffer_scale,
+        ));
+        CursorPlacement {
+            buffer_scale,
+            hotspot,
+            geometry: Rectangle::new(location - hotspot, size),
+        }
+    }
+}
+
+/// Placement of a cursor image, see [`CursorSize::placement`]
+///
+/// A cursor spanning multiple outputs should be placed once per output, using the image
+/// picked for the scale of the respective output. The logical geometries of the placements
+/// only differ by the rounding of the image hotspots, so the cursor lines up across outputs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CursorPlacement {
+    /// Scale of the image relative to the nominal cursor size
+    ///
+    /// The image has to be rendered with this buffer scale.
+    pub buffer_scale: f64,
+    /// Hotspot of the cursor in logical coordinates relative to the image
+    pub hotspot: Point<f64, Logical>,
+    /// Logical geometry covered by the cursor image
+    pub geometry: Rectangle<f64, Logical>,
+}
+
+impl CursorPlacement {
+    /// Returns whether the cursor is visible on an output with the given geometry
+    pub fn overlaps(&self, output_geometry: Rectangle<i32, Logical>) -> bool {
+        self.geometry.overlaps(output_geometry.to_f64())
+    }
+
+    /// Returns the physical location to render the image at on an output
+    pub fn render_location(
+        &self,
+        output_geometry: Rectangle<i32, Logical>,
+        scale: f64,
+    ) -> Point<f64, Physical> {
+        (self.geometry.loc - output_geometry.loc.to_f64()).to_physical(scale)
+    }
+
+    /// Returns the physical size of the rendered image on an output
+    pub fn render_size(&self, scale: f64) -> Size<f64, Physical> {
+        self.geometry.size.to_physical(scale)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nearest_size() {
+        let size = CursorSize::new(24);
+        let available = [24, 32, 48, 64];
+        assert_eq!(size.nearest(1.0, available), Some(24));
+        assert_eq!(size.nearest(2.0, available), Some(48));
+        // 1.5 * 24 = 36 is in between 32 and 40, prefer the bigger one on ties
+        assert_eq!(size.nearest(1.5, [32, 40]), Some(40));
+        assert_eq!(size.nearest(1.25, available), Some(32));
+        assert_eq!(size.nearest(1.0, []), None);
+    }
+
+    #[test]
+    fn placement_spanning_outputs() {
+        let size = CursorSize::new(24);
+        let location = Point::<f64, Logical>::from((1918.0, 500.0));
+        let left = Rectangle::<i32, Logical>::new((0, 0).into(), (1920, 1080).into());
+        let right = Rectangle::<i32, Logical>::new((1920, 0).into(), (1920, 1080).into());
+
+        let low = size.placement(location, 24, (24, 24).into(), (4, 4).into());
+        let high = size.placement(location, 48, (48, 48).into(), (8, 8).into());
+        assert_eq!(low.buffer_scale, 1.0);
+        assert_eq!(high.buffer_scale, 2.0);
+        // both images cover the same logical area
+        assert_eq!(low.geometry, high.geometry);
+        assert_eq!(high.hotspot, (4.0, 4.0).into());
+
+        for placement in [low, high] {
+            assert!(placement.overlaps(left));
+            assert!(placement.overlaps(right));
+        }
+        assert_eq!(low.render_location(left, 1.0), (1914.0, 496.0).into());
+        assert_eq!(high.render_location(right, 2.0), (-12.0, 992.0).into());
+        assert_eq!(high.render_size(2.0), (48.0, 48.0).into());
+    }
+}
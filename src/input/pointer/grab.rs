@@ -1,4 +1,4 @@
-use std::fmt;
+use std::{fmt, time::Duration};
 
 use downcast_rs::{impl_downcast, Downcast};
 
@@ -170,6 +170,10 @@ pub trait PointerGrab<D: SeatHandler>: Send + Downcast {
     fn start_data(&self) -> &GrabStartData<D>;
     /// The grab has been unset or replaced with another grab.
     fn unset(&mut self, data: &mut D);
+    /// Name of the type of the grab, see [`ActiveGrab`](crate::input::ActiveGrab).
+    fn type_name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
 }
 
 impl_downcast!(PointerGrab<D> where D: SeatHandler);
@@ -468,3 +472,241 @@ impl<D: SeatHandler + 'static> PointerGrab<D> for ClickGrab<D> {
 
     fn unset(&mut self, _data: &mut D) {}
 }
+
+/// Threshold an input has to exceed before a drag is started
+///
+/// Used to tell a click or tap apart from the start of an interactive move or a drag'n'drop
+/// operation, see [`DragThresholdGrab`] and [`TouchDragThresholdGrab`](crate::input::touch::TouchDragThresholdGrab).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DragThreshold {
+    /// Distance in logical pixels the pointer or touch point has to move
+    pub distance: f64,
+    /// Duration a touch point has to be held down, `None` to only consider the distance
+    ///
+    /// Only applies to touch input.
+    pub long_press: Option<Duration>,
+}
+
+impl Default for DragThreshold {
+    fn default() -> Self {
+        DragThreshold {
+            distance: 8.0,
+            long_press: Some(Duration::from_millis(500)),
+        }
+    }
+}
+
+impl DragThreshold {
+    /// Returns whether the movement from `start` to `location` exceeds the distance
+    pub fn distance_exceeded(&self, start: Point<f64, Logical>, location: Point<f64, Logical>) -> bool {
+        let delta = location - start;
+        delta.x * delta.x + delta.y * delta.y >= self.distance * self.distance
+    }
+
+    /// Returns whether an input held down since `start_time` is a long press at `time`
+    ///
+    /// Both times are in milliseconds, as provided by input events.
+    pub fn long_press_reached(&self, start_time: u32, time: u32) -> bool {
+        self.long_press
+            .is_some_and(|long_press| u128::from(time.wrapping_sub(start_time)) >= long_press.as_millis())
+    }
+}
+
+/// A grab starting a drag once the pointer moved far enough
+///
+/// Until the [`DragThreshold`] is exceeded, it behaves like a [`ClickGrab`]. Once exceeded,
+/// the grab returned by `start` replaces it, e.g. a move grab or a drag'n'drop grab.
+/// Releasing all buttons before that ends the grab without starting the drag.
+pub struct DragThresholdGrab<D: SeatHandler, G, F> {
+    start_data: GrabStartData<D>,
+    threshold: DragThreshold,
+    focus: Focus,
+    start: Option<F>,
+    _grab: std::marker::PhantomData<fn() -> G>,
+}
+
+impl<D: SeatHandler + 'static, G, F> fmt::Debug for DragThresholdGrab<D, G, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DragThresholdGrab")
+            .field("start_data", &self.start_data)
+            .field("threshold", &self.threshold)
+            .field("focus", &self.focus)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<D, G, F> DragThresholdGrab<D, G, F>
+where
+    D: SeatHandler + 'static,
+    G: PointerGrab<D> + 'static,
+    F: FnOnce(&mut D, GrabStartData<D>) -> G + Send + 'static,
+{
+    /// Create a new grab, calling `start` once the threshold is exceeded
+    ///
+    /// The grab returned by `start` is set using the given `focus` behavior.
+    pub fn new(start_data: GrabStartData<D>, threshold: DragThreshold, focus: Focus, start: F) -> Self {
+        DragThresholdGrab {
+            start_data,
+            threshold,
+            focus,
+            start: Some(start),
+            _grab: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<D, G, F> PointerGrab<D> for DragThresholdGrab<D, G, F>
+where
+    D: SeatHandler + 'static,
+    G: PointerGrab<D> + 'static,
+    F: FnOnce(&mut D, GrabStartData<D>) -> G + Send + 'static,
+{
+    fn motion(
+        &mut self,
+        data: &mut D,
+        handle: &mut PointerInnerHandle<'_, D>,
+        _focus: Option<(<D as SeatHandler>::PointerFocus, Point<f64, Logical>)>,
+        event: &MotionEvent,
+    ) {
+        handle.motion(data, self.start_data.focus.clone(), event);
+        if self
+            .threshold
+            .distance_exceeded(self.start_data.location, event.location)
+        {
+            if let Some(start) = self.start.take() {
+                let grab = start(data, self.start_data.clone());
+                let focus = self.focus;
+                handle.set_grab(self, data, event.serial, focus, grab);
+            }
+        }
+    }
+
+    fn relative_motion(
+        &mut self,
+        data: &mut D,
+        handle: &mut PointerInnerHandle<'_, D>,
+        focus: Option<(<D as SeatHandler>::PointerFocus, Point<f64, Logical>)>,
+        event: &RelativeMotionEvent,
+    ) {
+        handle.relative_motion(data, focus, event);
+    }
+
+    fn button(&mut self, data: &mut D, handle: &mut PointerInnerHandle<'_, D>, event: &ButtonEvent) {
+        handle.button(data, event);
+        if handle.current_pressed().is_empty() {
+            // released before the threshold was exceeded, don't start the drag
+            handle.unset_grab(self, data, event.serial, event.time, false);
+        }
+    }
+
+    fn axis(&mut self, data: &mut D, handle: &mut PointerInnerHandle<'_, D>, details: AxisFrame) {
+        handle.axis(data, details);
+    }
+
+    fn frame(&mut self, data: &mut D, handle: &mut PointerInnerHandle<'_, D>) {
+        handle.frame(data);
+    }
+
+    fn gesture_swipe_begin(
+        &mut self,
+        data: &mut D,
+        handle: &mut PointerInnerHandle<'_, D>,
+        event: &GestureSwipeBeginEvent,
+    ) {
+        handle.gesture_swipe_begin(data, event);
+    }
+
+    fn gesture_swipe_update(
+        &mut self,
+        data: &mut D,
+        handle: &mut PointerInnerHandle<'_, D>,
+        event: &GestureSwipeUpdateEvent,
+    ) {
+        handle.gesture_swipe_update(data, event);
+    }
+
+    fn gesture_swipe_end(
+        &mut self,
+        data: &mut D,
+        handle: &mut PointerInnerHandle<'_, D>,
+        event: &GestureSwipeEndEvent,
+    ) {
+        handle.gesture_swipe_end(data, event);
+    }
+
+    fn gesture_pinch_begin(
+        &mut self,
+        data: &mut D,
+        handle: &mut PointerInnerHandle<'_, D>,
+        event: &GesturePinchBeginEvent,
+    ) {
+        handle.gesture_pinch_begin(data, event);
+    }
+
+    fn gesture_pinch_update(
+        &mut self,
+        data: &mut D,
+        handle: &mut PointerInnerHandle<'_, D>,
+        event: &GesturePinchUpdateEvent,
+    ) {
+        handle.gesture_pinch_update(data, event);
+    }
+
+    fn gesture_pinch_end(
+        &mut self,
+        data: &mut D,
+        handle: &mut PointerInnerHandle<'_, D>,
+        event: &GesturePinchEndEvent,
+    ) {
+        handle.gesture_pinch_end(data, event);
+    }
+
+    fn gesture_hold_begin(
+        &mut self,
+        data: &mut D,
+        handle: &mut PointerInnerHandle<'_, D>,
+        event: &GestureHoldBeginEvent,
+    ) {
+        handle.gesture_hold_begin(data, event);
+    }
+
+    fn gesture_hold_end(
+        &mut self,
+        data: &mut D,
+        handle: &mut PointerInnerHandle<'_, D>,
+        event: &GestureHoldEndEvent,
+    ) {
+        handle.gesture_hold_end(data, event);
+    }
+
+    fn start_data(&self) -> &GrabStartData<D> {
+        &self.start_data
+    }
+
+    fn unset(&mut self, _data: &mut D) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::DragThreshold;
+
+    #[test]
+    fn drag_threshold() {
+        let threshold = DragThreshold {
+            distance: 5.0,
+            long_press: Some(Duration::from_millis(400)),
+        };
+        assert!(!threshold.distance_exceeded((10.0, 10.0).into(), (13.0, 13.0).into()));
+        assert!(threshold.distance_exceeded((10.0, 10.0).into(), (13.0, 14.0).into()));
+        assert!(!threshold.long_press_reached(1000, 1399));
+        assert!(threshold.long_press_reached(1000, 1400));
+        assert!(threshold.long_press_reached(u32::MAX - 100, 300));
+        assert!(!DragThreshold {
+            long_press: None,
+            ..threshold
+        }
+        .long_press_reached(0, 10_000));
+    }
+}
@@ -7,18 +7,20 @@ use std::{
 
 use crate::{
     backend::input::{Axis, AxisRelativeDirection, AxisSource, ButtonState},
-    input::{GrabStatus, Seat, SeatHandler},
+    input::{ActiveGrab, GrabStatus, Seat, SeatHandler},
     utils::Serial,
     utils::{Clock, IsAlive, Logical, Monotonic, Point},
 };
 
 mod cursor_image;
 pub use cursor_icon::CursorIcon;
-pub use cursor_image::{CursorImageAttributes, CursorImageStatus, CursorImageSurfaceData};
+pub use cursor_image::{
+    CursorImageAttributes, CursorImageStatus, CursorImageSurfaceData, CursorPlacement, CursorSize,
+};
 
 mod grab;
 use grab::DefaultGrab;
-pub use grab::{ClickGrab, GrabStartData, PointerGrab};
+pub use grab::{ClickGrab, DragThreshold, DragThresholdGrab, GrabStartData, PointerGrab};
 use tracing::{info_span, instrument};
 
 /// An handle to a pointer handler
@@ -217,6 +219,14 @@ impl<D: SeatHandler + 'static> PointerHandle<D> {
         }
     }
 
+    /// Returns the type and serial of the active grab, if any.
+    pub fn active_grab(&self) -> Option<ActiveGrab> {
+        self.with_grab(|serial, grab| ActiveGrab {
+            type_name: grab.type_name(),
+            serial,
+        })
+    }
+
     /// Notify that the pointer moved
     ///
     /// You provide the new location of the pointer, in the form of:
@@ -804,16 +814,19 @@ impl<D: SeatHandler + 'static> PointerInternal<D> {
                 }
                 (focus, Some((old_focus, _))) => {
                     // the target has been replaced
-                    focus.replace(old_focus, seat, data, &event);
+                    focus.replace(old_focus.clone(), seat, data, &event);
+                    data.pointer_focus_changed(seat, Some(&old_focus), Some(&focus), event.serial);
                 }
                 (focus, None) => {
                     // we entered a new target
                     focus.enter(seat, data, &event);
+                    data.pointer_focus_changed(seat, None, Some(&focus), event.serial);
                 }
             };
         } else if let Some((old_focus, _)) = self.focus.take() {
             old_focus.leave(seat, data, event.serial, event.time);
             data.cursor_image(seat, CursorImageStatus::default_named());
+            data.pointer_focus_changed(seat, Some(&old_focus), None, event.serial);
         }
     }
 
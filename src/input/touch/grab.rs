@@ -4,7 +4,7 @@ use downcast_rs::{impl_downcast, Downcast};
 
 use crate::{
     backend::input::TouchSlot,
-    input::SeatHandler,
+    input::{pointer::DragThreshold, SeatHandler},
     utils::{Logical, Point, Serial},
 };
 
@@ -110,6 +110,11 @@ pub trait TouchGrab<D: SeatHandler>: Send + Downcast {
 
     /// The grab has been unset or replaced with another grab.
     fn unset(&mut self, data: &mut D);
+
+    /// Name of the type of the grab, see [`ActiveGrab`](crate::input::ActiveGrab).
+    fn type_name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
 }
 
 impl_downcast!(TouchGrab<D> where D: SeatHandler);
@@ -301,3 +306,133 @@ impl<D: SeatHandler + 'static> TouchGrab<D> for TouchDownGrab<D> {
 
     fn unset(&mut self, _data: &mut D) {}
 }
+
+/// A grab starting a drag once the touch point moved far enough or was held down long enough
+///
+/// Until the [`DragThreshold`] is exceeded, it behaves like a [`TouchDownGrab`]. Once exceeded,
+/// the grab returned by `start` replaces it, e.g. a move grab or a drag'n'drop grab.
+/// Lifting all touch points before that ends the grab without starting the drag.
+///
+/// The long press is only detected when the touch point reports a motion, as this grab has
+/// no timer of its own.
+pub struct TouchDragThresholdGrab<D: SeatHandler, G, F> {
+    start_data: GrabStartData<D>,
+    start_time: u32,
+    threshold: DragThreshold,
+    touch_points: usize,
+    start: Option<F>,
+    _grab: std::marker::PhantomData<fn() -> G>,
+}
+
+impl<D: SeatHandler + 'static, G, F> fmt::Debug for TouchDragThresholdGrab<D, G, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TouchDragThresholdGrab")
+            .field("start_data", &self.start_data)
+            .field("start_time", &self.start_time)
+            .field("threshold", &self.threshold)
+            .field("touch_points", &self.touch_points)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<D, G, F> TouchDragThresholdGrab<D, G, F>
+where
+    D: SeatHandler + 'static,
+    G: TouchGrab<D> + 'static,
+    F: FnOnce(&mut D, GrabStartData<D>) -> G + Send + 'static,
+{
+    /// Create a new grab, calling `start` once the threshold is exceeded
+    ///
+    /// `start_time` is the time of the down event starting the grab, in milliseconds.
+    pub fn new(start_data: GrabStartData<D>, start_time: u32, threshold: DragThreshold, start: F) -> Self {
+        TouchDragThresholdGrab {
+            start_data,
+            start_time,
+            threshold,
+            touch_points: 1,
+            start: Some(start),
+            _grab: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<D, G, F> TouchGrab<D> for TouchDragThresholdGrab<D, G, F>
+where
+    D: SeatHandler + 'static,
+    G: TouchGrab<D> + 'static,
+    F: FnOnce(&mut D, GrabStartData<D>) -> G + Send + 'static,
+{
+    fn down(
+        &mut self,
+        data: &mut D,
+        handle: &mut TouchInnerHandle<'_, D>,
+        _focus: Option<(<D as SeatHandler>::TouchFocus, Point<f64, Logical>)>,
+        event: &DownEvent,
+        seq: Serial,
+    ) {
+        handle.down(data, self.start_data.focus.clone(), event, seq);
+        self.touch_points += 1;
+    }
+
+    fn up(&mut self, data: &mut D, handle: &mut TouchInnerHandle<'_, D>, event: &UpEvent, seq: Serial) {
+        handle.up(data, event, seq);
+        self.touch_points = self.touch_points.saturating_sub(1);
+        if self.touch_points == 0 {
+            // lifted before the threshold was exceeded, don't start the drag
+            handle.unset_grab(self, data);
+        }
+    }
+
+    fn motion(
+        &mut self,
+        data: &mut D,
+        handle: &mut TouchInnerHandle<'_, D>,
+        _focus: Option<(<D as SeatHandler>::TouchFocus, Point<f64, Logical>)>,
+        event: &MotionEvent,
+        seq: Serial,
+    ) {
+        handle.motion(data, self.start_data.focus.clone(), event, seq);
+        if event.slot != self.start_data.slot {
+            return;
+        }
+        if self
+            .threshold
+            .distance_exceeded(self.start_data.location, event.location)
+            || self.threshold.long_press_reached(self.start_time, event.time)
+        {
+            if let Some(start) = self.start.take() {
+                let grab = start(data, self.start_data.clone());
+                handle.set_grab(self, data, seq, grab);
+            }
+        }
+    }
+
+    fn frame(&mut self, data: &mut D, handle: &mut TouchInnerHandle<'_, D>, seq: Serial) {
+        handle.frame(data, seq)
+    }
+
+    fn cancel(&mut self, data: &mut D, handle: &mut TouchInnerHandle<'_, D>, seq: Serial) {
+        handle.cancel(data, seq);
+        handle.unset_grab(self, data);
+    }
+
+    fn shape(&mut self, data: &mut D, handle: &mut TouchInnerHandle<'_, D>, event: &ShapeEvent, seq: Serial) {
+        handle.shape(data, event, seq)
+    }
+
+    fn orientation(
+        &mut self,
+        data: &mut D,
+        handle: &mut TouchInnerHandle<'_, D>,
+        event: &OrientationEvent,
+        seq: Serial,
+    ) {
+        handle.orientation(data, event, seq)
+    }
+
+    fn start_data(&self) -> &GrabStartData<D> {
+        &self.start_data
+    }
+
+    fn unset(&mut self, _data: &mut D) {}
+}
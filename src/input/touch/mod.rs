@@ -11,9 +11,9 @@ use wayland_server::Weak;
 use crate::backend::input::TouchSlot;
 use crate::utils::{IsAlive, Logical, Point, Serial, SerialCounter};
 
-pub use grab::{DefaultGrab, GrabStartData, TouchDownGrab, TouchGrab};
+pub use grab::{DefaultGrab, GrabStartData, TouchDownGrab, TouchDragThresholdGrab, TouchGrab};
 
-use super::{GrabStatus, Seat, SeatHandler};
+use super::{ActiveGrab, GrabStatus, Seat, SeatHandler};
 
 mod grab;
 
@@ -279,6 +279,14 @@ impl<D: SeatHandler + 'static> TouchHandle<D> {
         }
     }
 
+    /// Returns the type and serial of the active grab, if any.
+    pub fn active_grab(&self) -> Option<ActiveGrab> {
+        self.with_grab(|serial, grab| ActiveGrab {
+            type_name: grab.type_name(),
+            serial,
+        })
+    }
+
     /// Notify that a new touch point appeared
     ///
     /// You provide the location of the touch, in the form of:
@@ -295,7 +303,7 @@ impl<D: SeatHandler + 'static> TouchHandle<D> {
     ) {
         let mut inner = self.inner.lock().unwrap();
         let seat = self.get_seat(data);
-        let seq = inner.seq_counter.next_serial();
+        let seq = inner.next_serial(data, &seat);
         inner.with_grab(data, &seat, |data, handle, grab| {
             grab.down(data, handle, focus, event, seq);
         });
@@ -305,7 +313,7 @@ impl<D: SeatHandler + 'static> TouchHandle<D> {
     pub fn up(&self, data: &mut D, event: &UpEvent) {
         let mut inner = self.inner.lock().unwrap();
         let seat = self.get_seat(data);
-        let seq = inner.seq_counter.next_serial();
+        let seq = inner.next_serial(data, &seat);
         inner.with_grab(data, &seat, |data, handle, grab| {
             grab.up(data, handle, event, seq);
         });
@@ -331,7 +339,7 @@ impl<D: SeatHandler + 'static> TouchHandle<D> {
     ) {
         let mut inner = self.inner.lock().unwrap();
         let seat = self.get_seat(data);
-        let seq = inner.seq_counter.next_serial();
+        let seq = inner.next_serial(data, &seat);
         inner.with_grab(data, &seat, |data, handle, grab| {
             grab.motion(data, handle, focus, event, seq);
         });
@@ -343,7 +351,7 @@ impl<D: SeatHandler + 'static> TouchHandle<D> {
     pub fn frame(&self, data: &mut D) {
         let mut inner = self.inner.lock().unwrap();
         let seat = self.get_seat(data);
-        let seq = inner.seq_counter.next_serial();
+        let seq = inner.next_serial(data, &seat);
         inner.with_grab(data, &seat, |data, handle, grab| {
             grab.frame(data, handle, seq);
         });
@@ -357,17 +365,30 @@ impl<D: SeatHandler + 'static> TouchHandle<D> {
     pub fn cancel(&self, data: &mut D) {
         let mut inner = self.inner.lock().unwrap();
         let seat = self.get_seat(data);
-        let seq = inner.seq_counter.next_serial();
+        let seq = inner.next_serial(data, &seat);
         inner.with_grab(data, &seat, |data, handle, grab| {
             grab.cancel(data, handle, seq);
         });
     }
 
+    /// Cancel all touch points focused on the given target
+    ///
+    /// Use this if the target is unmapped while it is touched. The target is sent a cancel event,
+    /// the touch points are dropped and [`SeatHandler::touch_focus_changed`] is notified. An active
+    /// grab started on the target is unset. Touch points of destroyed targets are cancelled
+    /// automatically with the next event.
+    pub fn cancel_target(&self, data: &mut D, target: &<D as SeatHandler>::TouchFocus) {
+        let mut inner = self.inner.lock().unwrap();
+        let seat = self.get_seat(data);
+        let seq = inner.next_serial(data, &seat);
+        inner.cancel_slots(data, &seat, seq, |focus| focus == target);
+    }
+
     /// Notify that a touch point has changed its shape.
     pub fn shape(&self, data: &mut D, event: &ShapeEvent) {
         let mut inner = self.inner.lock().unwrap();
         let seat = self.get_seat(data);
-        let seq = inner.seq_counter.next_serial();
+        let seq = inner.next_serial(data, &seat);
         inner.with_grab(data, &seat, |data, handle, grab| {
             grab.shape(data, handle, event, seq);
         });
@@ -377,7 +398,7 @@ impl<D: SeatHandler + 'static> TouchHandle<D> {
     pub fn orientation(&self, data: &mut D, event: &OrientationEvent) {
         let mut inner = self.inner.lock().unwrap();
         let seat = self.get_seat(data);
-        let seq = inner.seq_counter.next_serial();
+        let seq = inner.next_serial(data, &seat);
         inner.with_grab(data, &seat, |data, handle, grab| {
             grab.orientation(data, handle, event, seq);
         });
@@ -547,12 +568,13 @@ impl<D: SeatHandler + 'static> TouchInternal<D> {
         event: &DownEvent,
         seq: Serial,
     ) {
+        let mut old_focus = None;
         self.focus
             .entry(event.slot)
             .and_modify(|state| {
                 state.pending = seq;
                 state.frame_pending = None;
-                state.focus.clone_from(&focus);
+                old_focus = std::mem::replace(&mut state.focus, focus.clone());
             })
             .or_insert_with(|| TouchSlotState {
                 focus,
@@ -561,11 +583,22 @@ impl<D: SeatHandler + 'static> TouchInternal<D> {
                 current: None,
             });
         let state = self.focus.get(&event.slot).unwrap();
+        let new_focus = state.focus.as_ref().map(|(focus, _)| focus.clone());
         if let Some((focus, loc)) = state.focus.as_ref() {
             let mut new_event = event.clone();
             new_event.location -= *loc;
             focus.down(seat, data, &new_event, seq);
         }
+        let old_focus = old_focus.map(|(focus, _)| focus);
+        if old_focus != new_focus {
+            data.touch_focus_changed(
+                seat,
+                event.slot,
+                old_focus.as_ref(),
+                new_focus.as_ref(),
+                event.serial,
+            );
+        }
     }
 
     fn up(&mut self, data: &mut D, seat: &Seat<D>, event: &UpEvent, seq: Serial) {
@@ -578,7 +611,8 @@ impl<D: SeatHandler + 'static> TouchInternal<D> {
 
             // Keep the focus around to be able to send a frame event after up, but move
             // it out of the current focus to prevent sending other events.
-            state.frame_pending = Some(focus);
+            state.frame_pending = Some(focus.clone());
+            data.touch_focus_changed(seat, event.slot, Some(&focus), None, event.serial);
         }
     }
 
@@ -617,16 +651,75 @@ impl<D: SeatHandler + 'static> TouchInternal<D> {
                 focus.frame(seat, data, seq);
             }
         }
+        self.drop_finished_slots();
     }
 
     fn cancel(&mut self, data: &mut D, seat: &Seat<D>, seq: Serial) {
-        for state in self.focus.values_mut() {
+        for (slot, state) in self.focus.iter_mut() {
             if state.current.map(|c| c >= state.pending).unwrap_or(false) {
                 continue;
             }
             state.current = Some(seq);
             if let Some((focus, _)) = state.focus.take() {
                 focus.cancel(seat, data, seq);
+                data.touch_focus_changed(seat, *slot, Some(&focus), None, seq);
+            }
+        }
+        self.drop_finished_slots();
+    }
+
+    // Touch points, that went up and received their frame, don't need to be tracked anymore
+    fn drop_finished_slots(&mut self) {
+        self.focus
+            .retain(|_, state| state.focus.is_some() || state.frame_pending.is_some());
+    }
+
+    // Cancels touch points of destroyed targets, before generating the serial of the next event
+    fn next_serial(&mut self, data: &mut D, seat: &Seat<D>) -> Serial {
+        let has_dead = self.focus.values().any(|state| {
+            state.focus.as_ref().is_some_and(|(focus, _)| !focus.alive())
+                || state.frame_pending.as_ref().is_some_and(|focus| !focus.alive())
+        });
+        if has_dead {
+            let seq = self.seq_counter.next_serial();
+            self.cancel_slots(data, seat, seq, |focus| !focus.alive());
+        }
+        self.seq_counter.next_serial()
+    }
+
+    fn cancel_slots(
+        &mut self,
+        data: &mut D,
+        seat: &Seat<D>,
+        seq: Serial,
+        filter: impl Fn(&<D as SeatHandler>::TouchFocus) -> bool,
+    ) {
+        if let GrabStatus::Active(_, handler) = &mut self.grab {
+            if handler
+                .start_data()
+                .focus
+                .as_ref()
+                .is_some_and(|(focus, _)| filter(focus))
+            {
+                handler.unset(data);
+                self.grab = GrabStatus::None;
+            }
+        }
+
+        let slots = self
+            .focus
+            .iter()
+            .filter(|(_, state)| {
+                state.focus.as_ref().is_some_and(|(focus, _)| filter(focus))
+                    || state.frame_pending.as_ref().is_some_and(&filter)
+            })
+            .map(|(slot, _)| *slot)
+            .collect::<Vec<_>>();
+        for slot in slots {
+            let state = self.focus.remove(&slot).unwrap();
+            if let Some((focus, _)) = state.focus {
+                focus.cancel(seat, data, seq);
+                data.touch_focus_changed(seat, slot, Some(&focus), None, seq);
             }
         }
     }
@@ -69,6 +69,11 @@ use wayland_server::{
 
 use crate::utils::{self, user_data::UserDataMap, Logical, Physical, Point, Raw, Size, Transform};
 
+mod auto_scale;
+mod watchdog;
+pub use self::auto_scale::{auto_scale, AutoScaleConfig};
+pub use self::watchdog::{FrameWatchdog, Stall};
+
 /// An output mode
 ///
 /// A possible combination of dimensions and refresh rate for an output.
@@ -218,6 +223,8 @@ pub(crate) struct Inner {
     pub(crate) physical: PhysicalProperties,
     pub(crate) location: Point<i32, Logical>,
     pub(crate) transform: Transform,
+    pub(crate) suggested_transform: Transform,
+    pub(crate) powered: bool,
     pub(crate) scale: Scale,
     pub(crate) modes: Vec<Mode>,
     pub(crate) current_mode: Option<Mode>,
@@ -269,6 +276,8 @@ impl Output {
                 physical,
                 location: (0, 0).into(),
                 transform: Transform::Normal,
+                suggested_transform: Transform::Normal,
+                powered: true,
                 scale: Scale::Integer(1),
                 modes: Vec::new(),
                 current_mode: None,
@@ -344,6 +353,49 @@ impl Output {
         self.inner.0.lock().unwrap().physical.clone()
     }
 
+    /// Suggests a scale for the output, see [`auto_scale`]
+    ///
+    /// Uses the physical size of the output and the resolution of its preferred mode,
+    /// or of the current mode if no preferred mode is set.
+    /// Returns `None` if the output has neither.
+    pub fn suggested_scale(&self, config: &AutoScaleConfig) -> Option<Scale> {
+        let inner = self.inner.0.lock().unwrap();
+        let mode = inner.preferred_mode.or(inner.current_mode)?;
+        Some(auto_scale(inner.physical.size, mode.size, config))
+    }
+
+    /// Sets the transform suggested for the output
+    ///
+    /// This is the transform the output should use by default, e.g. to compensate
+    /// a rotated panel. It is not applied automatically, use [`Output::change_current_state`].
+    /// The [`DrmCompositor`](crate::backend::drm::compositor::DrmCompositor) sets it from the panel orientation
+    /// of its connectors.
+    pub fn set_suggested_transform(&self, transform: Transform) {
+        self.inner.0.lock().unwrap().suggested_transform = transform;
+    }
+
+    /// Returns the transform suggested for the output, see [`Output::set_suggested_transform`]
+    pub fn suggested_transform(&self) -> Transform {
+        self.inner.0.lock().unwrap().suggested_transform
+    }
+
+    /// Sets if the output is powered on
+    ///
+    /// Outputs are assumed to be powered on, until they are turned off, e.g. using DPMS.
+    /// Surfaces only shown on outputs that are powered off do not receive frame callbacks
+    /// from [`send_frames_surface_tree`](crate::desktop::utils::send_frames_surface_tree).
+    /// Surfaces also shown on a powered on output switch their primary scan-out output
+    /// to it, once they are presented there, see
+    /// [`PrimaryScanoutOutput`](crate::backend::renderer::element::PrimaryScanoutOutput).
+    pub fn set_powered(&self, powered: bool) {
+        self.inner.0.lock().unwrap().powered = powered;
+    }
+
+    /// Returns if the output is powered on, see [`Output::set_powered`]
+    pub fn is_powered(&self) -> bool {
+        self.inner.0.lock().unwrap().powered
+    }
+
     /// Returns the currently advertised modes of the output
     pub fn modes(&self) -> Vec<Mode> {
         self.inner.0.lock().unwrap().modes.clone()
@@ -550,3 +602,24 @@ impl TryFrom<OutputModeSource> for (Size<i32, Physical>, utils::Scale<f64>, Tran
 #[derive(Debug, thiserror::Error)]
 #[error("Output has no active mode")]
 pub struct OutputNoMode;
+
+// Creates an output with a 60Hz mode of the given size as its current and preferred mode
+#[cfg(test)]
+pub(crate) fn test_output(name: &str, size: impl Into<Size<i32, Physical>>) -> Output {
+    let output = Output::new(
+        name.into(),
+        PhysicalProperties {
+            size: (0, 0).into(),
+            subpixel: Subpixel::Unknown,
+            make: "test".into(),
+            model: "test".into(),
+        },
+    );
+    let mode = Mode {
+        size: size.into(),
+        refresh: 60_000,
+    };
+    output.change_current_state(Some(mode), None, None, None);
+    output.set_preferred(mode);
+    output
+}
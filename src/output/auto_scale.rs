@@ -0,0 +1,153 @@
+//! Heuristic suggesting an initial scale for outputs
+
+use crate::utils::{Logical, Physical, Raw, Size};
+
+use super::Scale;
+
+// Sizes reported by monitors encoding their aspect ratio instead of their physical size
+const BOGUS_SIZES: [(i32, i32); 6] = [
+    (1600, 900),
+    (1600, 1000),
+    (160, 90),
+    (160, 100),
+    (16, 9),
+    (16, 10),
+];
+
+/// Thresholds used by [`auto_scale`]
+///
+/// Smaller screens are usually viewed from a shorter distance, e.g. laptop panels,
+/// so they use a higher target dpi than larger screens.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AutoScaleConfig {
+    /// Target dpi of screens smaller than `large_screen_diagonal`
+    pub small_screen_dpi: f64,
+    /// Target dpi of screens at least as large as `large_screen_diagonal`
+    pub large_screen_dpi: f64,
+    /// Diagonal in inches from which on a screen is considered large
+    pub large_screen_diagonal: f64,
+    /// Granularity of the suggested scale, `1.0` only suggests integer scales
+    pub step: f64,
+    /// Largest scale to suggest
+    pub max_scale: f64,
+    /// Smallest logical size the output has to keep
+    pub min_logical_size: Size<i32, Logical>,
+}
+
+impl Default for AutoScaleConfig {
+    fn default() -> Self {
+        AutoScaleConfig {
+            small_screen_dpi: 135.0,
+            large_screen_dpi: 110.0,
+            large_screen_diagonal: 20.0,
+            step: 0.25,
+            max_scale: 4.0,
+            min_logical_size: (800, 480).into(),
+        }
+    }
+}
+
+/// Suggests a scale for an output based on its physical size and resolution
+///
+/// `physical_size` is the size of the output in millimeters, as reported by its EDID,
+/// see [`PhysicalProperties::size`](super::PhysicalProperties::size).
+/// `resolution` is the size of the mode in use.
+///
+/// The dpi of the output is divided by the target dpi of the `config` and rounded to the
+/// configured step. The scale never goes below `1.0`, so low dpi outputs are not scaled down.
+/// Outputs with missing or implausible physical sizes, as reported e.g. by projectors,
+/// always get a scale of `1`.
+pub fn auto_scale(
+    physical_size: Size<i32, Raw>,
+    resolution: Size<i32, Physical>,
+    config: &AutoScaleConfig,
+) -> Scale {
+    if !plausible_size(physical_size, resolution) || config.step <= 0.0 {
+        return Scale::Integer(1);
+    }
+
+    let width_inches = physical_size.w as f64 / 25.4;
+    let height_inches = physical_size.h as f64 / 25.4;
+    let diagonal = width_inches.hypot(height_inches);
+    let dpi = resolution.w as f64 / width_inches;
+    let target_dpi = if diagonal < config.large_screen_diagonal {
+        config.small_screen_dpi
+    } else {
+        config.large_screen_dpi
+    };
+
+    // keep at least the minimal logical size, regardless of the orientation
+    let (long, short) = (resolution.w.max(resolution.h), resolution.w.min(resolution.h));
+    let min_size = config.min_logical_size;
+    let (min_long, min_short) = (min_size.w.max(min_size.h), min_size.w.min(min_size.h));
+    let max_scale = f64::min(
+        long as f64 / min_long.max(1) as f64,
+        short as f64 / min_short.max(1) as f64,
+    )
+    .min(config.max_scale);
+    let max_scale = (max_scale / config.step).floor() * config.step;
+
+    let scale = ((dpi / target_dpi) / config.step).round() * config.step;
+    let scale = scale.min(max_scale).max(1.0);
+    if scale.fract() == 0.0 {
+        Scale::Integer(scale as i32)
+    } else {
+        Scale::Fractional(scale)
+    }
+}
+
+fn plausible_size(physical_size: Size<i32, Raw>, resolution: Size<i32, Physical>) -> bool {
+    // smaller than any real screen
+    if physical_size.w < 10 || physical_size.h < 10 || resolution.w <= 0 || resolution.h <= 0 {
+        return false;
+    }
+    if BOGUS_SIZES.contains(&(physical_size.w, physical_size.h)) {
+        return false;
+    }
+    // pixels should be roughly square
+    let physical_ratio = physical_size.w as f64 / physical_size.h as f64;
+    let pixel_ratio = resolution.w as f64 / resolution.h as f64;
+    (0.5..=2.0).contains(&(physical_ratio / pixel_ratio))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn suggest(physical_size: (i32, i32), resolution: (i32, i32)) -> f64 {
+        auto_scale(
+            physical_size.into(),
+            resolution.into(),
+            &AutoScaleConfig::default(),
+        )
+        .fractional_scale()
+    }
+
+    #[test]
+    fn suggested_scales() {
+        // 14" laptop
+        assert_eq!(suggest((302, 189), (2880, 1800)), 1.75);
+        // 13" laptop with a low resolution
+        assert_eq!(suggest((294, 165), (1366, 768)), 1.0);
+        // 24" 1080p monitor
+        assert_eq!(suggest((531, 299), (1920, 1080)), 1.0);
+        // 27" 4k monitor
+        assert_eq!(suggest((597, 336), (3840, 2160)), 1.5);
+        // small phone sized panel limited by the minimal logical size
+        assert_eq!(suggest((68, 136), (720, 1440)), 1.5);
+
+        // bogus sizes
+        assert_eq!(suggest((160, 90), (3840, 2160)), 1.0);
+        assert_eq!(suggest((0, 0), (3840, 2160)), 1.0);
+        assert_eq!(suggest((600, 50), (3840, 2160)), 1.0);
+
+        let integer = AutoScaleConfig {
+            step: 1.0,
+            ..Default::default()
+        };
+        assert!(matches!(
+            auto_scale((302, 189).into(), (2880, 1800).into(), &integer),
+            Scale::Integer(2)
+        ));
+    }
+}
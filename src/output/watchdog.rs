@@ -0,0 +1,171 @@
+//! Detection of outputs that stopped presenting frames
+
+use std::time::Duration;
+
+/// Detects outputs that stopped presenting frames
+///
+/// Bugs like a stuck fence or a lost page flip event can stop an output from ever presenting
+/// another frame, freezing its contents. The watchdog detects those stalls, so the compositor
+/// can try to recover instead of waiting forever.
+///
+/// The watchdog needs to be informed about the state of the render loop using
+/// [`damaged`](FrameWatchdog::damaged), [`frame_queued`](FrameWatchdog::frame_queued) and
+/// [`frame_presented`](FrameWatchdog::frame_presented). [`check`](FrameWatchdog::check)
+/// reports stalls and should be called periodically, e.g. from a timer running every few frames.
+///
+/// All timestamps are expected to come from the same monotonic clock.
+///
+/// ```
+/// use std::time::Duration;
+/// use smithay::output::{FrameWatchdog, Stall};
+///
+/// let mut watchdog = FrameWatchdog::new(Duration::from_micros(16_667), 10);
+///
+/// watchdog.frame_queued(Duration::from_millis(1000));
+/// // no page flip event arrived for a while
+/// match watchdog.check(Duration::from_millis(1500)) {
+///     Some(Stall::NotPresented { .. }) => {
+///         // reset the surface, e.g. using `DrmCompositor::discard_pending_frame`,
+///         // and queue a new frame
+///     }
+///     Some(Stall::NotQueued { .. }) => {
+///         // re-schedule rendering the output
+///     }
+///     None => {}
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct FrameWatchdog {
+    frame_interval: Duration,
+    max_missed_frames: u32,
+    damaged_since: Option<Duration>,
+    queued_since: Option<Duration>,
+}
+
+/// A stall detected by [`FrameWatchdog::check`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stall {
+    /// A queued frame was never presented
+    ///
+    /// Usually caused by a stuck fence or a lost page flip event.
+    /// The surface needs to be reset, before a new frame can be queued.
+    NotPresented {
+        /// Time the frame was queued at
+        since: Duration,
+    },
+    /// The output is damaged, but no frame was queued
+    ///
+    /// Usually caused by a render loop that was not re-scheduled.
+    NotQueued {
+        /// Time the output was damaged at
+        since: Duration,
+    },
+}
+
+impl FrameWatchdog {
+    /// Creates a new watchdog
+    ///
+    /// Stalls are reported once nothing happened for `max_missed_frames` frames of `frame_interval`.
+    pub fn new(frame_interval: Duration, max_missed_frames: u32) -> Self {
+        FrameWatchdog {
+            frame_interval,
+            max_missed_frames: max_missed_frames.max(1),
+            damaged_since: None,
+            queued_since: None,
+        }
+    }
+
+    /// Updates the frame interval, e.g. after a mode change
+    pub fn set_frame_interval(&mut self, frame_interval: Duration) {
+        self.frame_interval = frame_interval;
+    }
+
+    /// Returns the time without progress after which a stall is reported
+    pub fn timeout(&self) -> Duration {
+        self.frame_interval * self.max_missed_frames
+    }
+
+    /// The output was damaged at `now` and needs a new frame
+    pub fn damaged(&mut self, now: Duration) {
+        self.damaged_since.get_or_insert(now);
+    }
+
+    /// A frame was queued for presentation at `now`
+    ///
+    /// Only the oldest frame not yet presented is tracked.
+    pub fn frame_queued(&mut self, now: Duration) {
+        self.damaged_since = None;
+        self.queued_since.get_or_insert(now);
+    }
+
+    /// A frame was presented
+    pub fn frame_presented(&mut self) {
+        self.queued_since = None;
+    }
+
+    /// Forgets all tracked state
+    ///
+    /// Should be called when the output is intentionally not presenting,
+    /// e.g. while it is disabled or the session is inactive.
+    pub fn reset(&mut self) {
+        self.damaged_since = None;
+        self.queued_since = None;
+    }
+
+    /// Checks for stalls at `now`
+    ///
+    /// Every stall is reported once, tracking starts over with the next damage or queued frame.
+    pub fn check(&mut self, now: Duration) -> Option<Stall> {
+        let timeout = self.timeout();
+        let stalled = |since: &mut Duration| now.saturating_sub(*since) >= timeout;
+
+        if let Some(since) = self.queued_since.take_if(stalled) {
+            return Some(Stall::NotPresented { since });
+        }
+        if self.queued_since.is_none() {
+            if let Some(since) = self.damaged_since.take_if(stalled) {
+                return Some(Stall::NotQueued { since });
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME: Duration = Duration::from_millis(10);
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn detects_stalls() {
+        let mut watchdog = FrameWatchdog::new(FRAME, 3);
+
+        watchdog.damaged(ms(0));
+        watchdog.frame_queued(ms(5));
+        assert_eq!(watchdog.check(ms(20)), None);
+        watchdog.frame_presented();
+        assert_eq!(watchdog.check(ms(100)), None);
+
+        watchdog.frame_queued(ms(100));
+        assert_eq!(watchdog.check(ms(129)), None);
+        assert_eq!(
+            watchdog.check(ms(130)),
+            Some(Stall::NotPresented { since: ms(100) })
+        );
+        // reported only once
+        assert_eq!(watchdog.check(ms(200)), None);
+
+        watchdog.damaged(ms(200));
+        watchdog.damaged(ms(210));
+        assert_eq!(watchdog.check(ms(230)), Some(Stall::NotQueued { since: ms(200) }));
+
+        watchdog.damaged(ms(300));
+        watchdog.reset();
+        assert_eq!(watchdog.check(ms(400)), None);
+    }
+}
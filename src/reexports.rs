@@ -21,6 +21,8 @@ pub use wayland_protocols;
 #[cfg(feature = "wayland_frontend")]
 pub use wayland_protocols_misc;
 #[cfg(feature = "wayland_frontend")]
+pub use wayland_protocols_plasma;
+#[cfg(feature = "wayland_frontend")]
 pub use wayland_protocols_wlr;
 #[cfg(feature = "wayland_frontend")]
 pub use wayland_server;
@@ -332,12 +332,18 @@ where
 /// A point as defined by its x and y coordinates
 ///
 /// Operations on points are saturating.
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(serialize = "N: serde::Serialize", deserialize = "N: serde::Deserialize<'de>"))
+)]
 #[repr(C)]
 pub struct Point<N, Kind> {
     /// horizontal coordinate
     pub x: N,
     /// vertical coordinate
     pub y: N,
+    #[cfg_attr(feature = "serde", serde(skip))]
     _kind: std::marker::PhantomData<Kind>,
 }
 
@@ -692,12 +698,18 @@ impl<N: Default, Kind> Default for Point<N, Kind> {
 /// can break this invariant.
 ///
 /// Operations on sizes are saturating.
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(serialize = "N: serde::Serialize", deserialize = "N: serde::Deserialize<'de>"))
+)]
 #[repr(C)]
 pub struct Size<N, Kind> {
     /// horizontal coordinate
     pub w: N,
     /// vertical coordinate
     pub h: N,
+    #[cfg_attr(feature = "serde", serde(skip))]
     _kind: std::marker::PhantomData<Kind>,
 }
 
@@ -1065,6 +1077,11 @@ impl<N: Coordinate, Kind> Sub<Size<N, Kind>> for Point<N, Kind> {
 /// A rectangle defined by its top-left corner and dimensions
 ///
 /// Operations on rectangles are saturating.
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(serialize = "N: serde::Serialize", deserialize = "N: serde::Deserialize<'de>"))
+)]
 #[repr(C)]
 pub struct Rectangle<N, Kind> {
     /// Location of the top-left corner of the rectangle
@@ -1584,6 +1601,7 @@ impl<N: Default, Kind> Default for Rectangle<N, Kind> {
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 /// Possible transformations to two-dimensional planes
 #[derive(Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Transform {
     /// Identity transformation (plane is unaltered when applied)
     #[default]
@@ -44,6 +44,16 @@ impl SealedFile {
             SealFlags::SEAL | SealFlags::SHRINK | SealFlags::GROW | SealFlags::WRITE,
         )?;
 
+        // Share a read-only fd, if procfs is available to reopen it.
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        if let Ok(fd) = rustix::fs::open(
+            format!("/proc/self/fd/{}", file.as_raw_fd()),
+            rustix::fs::OFlags::RDONLY | rustix::fs::OFlags::CLOEXEC,
+            rustix::fs::Mode::empty(),
+        ) {
+            file = fd.into();
+        }
+
         Ok(Self {
             file,
             size: data.len(),
@@ -112,3 +122,25 @@ impl AsFd for SealedFile {
         self.file.as_fd()
     }
 }
+
+#[cfg(all(test, any(target_os = "linux", target_os = "android")))]
+mod tests {
+    use std::{fs::File, io::Read, os::unix::io::AsFd};
+
+    use rustix::fs::{fcntl_getfl, OFlags};
+
+    use super::SealedFile;
+
+    #[test]
+    fn read_only() {
+        let sealed = SealedFile::with_content(c"smithay-test", c"keymap").unwrap();
+        assert_eq!(sealed.size(), 7);
+        assert_eq!(fcntl_getfl(&sealed).unwrap() & OFlags::RWMODE, OFlags::RDONLY);
+
+        let mut content = Vec::new();
+        File::from(sealed.as_fd().try_clone_to_owned().unwrap())
+            .read_to_end(&mut content)
+            .unwrap();
+        assert_eq!(content, b"keymap\0");
+    }
+}
@@ -23,6 +23,7 @@
 //!
 //! delegate_alpha_modifier!(State);
 //! delegate_compositor!(State);
+//! # impl smithay::wayland::protocol_error::ProtocolErrorHandler for State {}
 //!
 //! impl CompositorHandler for State {
 //!    fn compositor_state(&mut self) -> &mut CompositorState {
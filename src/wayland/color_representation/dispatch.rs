@@ -0,0 +1,221 @@
+use wayland_protocols::wp::color_representation::v1::server::{
+    wp_color_representation_manager_v1::{self, WpColorRepresentationManagerV1},
+    wp_color_representation_surface_v1::{self, WpColorRepresentationSurfaceV1},
+};
+use wayland_server::{
+    backend::ClientId, Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource, WEnum,
+};
+
+use super::{
+    ColorRepresentationGlobalData, ColorRepresentationState, ColorRepresentationSurfaceCachedState,
+    ColorRepresentationSurfaceData, ColorRepresentationUserData,
+};
+use crate::wayland::compositor;
+
+impl<D> GlobalDispatch<WpColorRepresentationManagerV1, ColorRepresentationGlobalData, D>
+    for ColorRepresentationState
+where
+    D: GlobalDispatch<WpColorRepresentationManagerV1, ColorRepresentationGlobalData>,
+    D: Dispatch<WpColorRepresentationManagerV1, ColorRepresentationGlobalData>,
+    D: Dispatch<WpColorRepresentationSurfaceV1, ColorRepresentationUserData>,
+    D: 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _: &DisplayHandle,
+        _: &Client,
+        resource: New<WpColorRepresentationManagerV1>,
+        global_data: &ColorRepresentationGlobalData,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        let manager = data_init.init(
+            resource,
+            ColorRepresentationGlobalData {
+                supported: global_data.supported.clone(),
+            },
+        );
+
+        for alpha_mode in &global_data.supported.alpha_modes {
+            manager.supported_alpha_mode(*alpha_mode);
+        }
+        for (coefficients, range) in &global_data.supported.coefficients_and_ranges {
+            manager.supported_coefficients_and_ranges(*coefficients, *range);
+        }
+        manager.done();
+    }
+}
+
+impl<D> Dispatch<WpColorRepresentationManagerV1, ColorRepresentationGlobalData, D>
+    for ColorRepresentationState
+where
+    D: Dispatch<WpColorRepresentationManagerV1, ColorRepresentationGlobalData>,
+    D: Dispatch<WpColorRepresentationSurfaceV1, ColorRepresentationUserData>,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _: &Client,
+        manager: &WpColorRepresentationManagerV1,
+        request: wp_color_representation_manager_v1::Request,
+        data: &ColorRepresentationGlobalData,
+        _dh: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            wp_color_representation_manager_v1::Request::GetSurface { id, surface } => {
+                let already_taken = compositor::with_states(&surface, |states| {
+                    states
+                        .data_map
+                        .insert_if_missing_threadsafe(ColorRepresentationSurfaceData::new);
+                    let data = states.data_map.get::<ColorRepresentationSurfaceData>().unwrap();
+
+                    let already_taken = data.is_resource_attached();
+
+                    if !already_taken {
+                        data.set_is_resource_attached(true);
+                    }
+
+                    already_taken
+                });
+
+                if already_taken {
+                    manager.post_error(
+                        wp_color_representation_manager_v1::Error::SurfaceExists,
+                        "WlSurface already has WpColorRepresentationSurfaceV1 attached",
+                    )
+                } else {
+                    data_init.init(
+                        id,
+                        ColorRepresentationUserData::new(surface, data.supported.clone()),
+                    );
+                }
+            }
+
+            wp_color_representation_manager_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<WpColorRepresentationSurfaceV1, ColorRepresentationUserData, D> for ColorRepresentationState
+where
+    D: Dispatch<WpColorRepresentationSurfaceV1, ColorRepresentationUserData>,
+{
+    fn request(
+        _state: &mut D,
+        _: &Client,
+        resource: &WpColorRepresentationSurfaceV1,
+        request: wp_color_representation_surface_v1::Request,
+        data: &ColorRepresentationUserData,
+        _dh: &DisplayHandle,
+        _: &mut DataInit<'_, D>,
+    ) {
+        use wp_color_representation_surface_v1::Error;
+
+        // Switch back to not specifying the color representation of this surface,
+        // including double buffering semantics.
+        if let wp_color_representation_surface_v1::Request::Destroy = request {
+            let Some(surface) = data.wl_surface() else {
+                return;
+            };
+
+            compositor::with_states(&surface, |states| {
+                states
+                    .data_map
+                    .get::<ColorRepresentationSurfaceData>()
+                    .unwrap()
+                    .set_is_resource_attached(false);
+
+                *states
+                    .cached_state
+                    .get::<ColorRepresentationSurfaceCachedState>()
+                    .pending() = Default::default();
+            });
+            return;
+        }
+
+        let Some(surface) = data.wl_surface() else {
+            resource.post_error(Error::Inert, "The associated wl_surface was destroyed");
+            return;
+        };
+
+        match request {
+            wp_color_representation_surface_v1::Request::SetAlphaMode { alpha_mode } => {
+                let alpha_mode = match alpha_mode {
+                    WEnum::Value(alpha_mode) if data.supported.alpha_modes.contains(&alpha_mode) => {
+                        alpha_mode
+                    }
+                    _ => {
+                        resource
+                            .post_error(Error::AlphaMode, format!("Unsupported alpha mode {alpha_mode:?}"));
+                        return;
+                    }
+                };
+
+                compositor::with_states(&surface, |states| {
+                    states
+                        .cached_state
+                        .get::<ColorRepresentationSurfaceCachedState>()
+                        .pending()
+                        .alpha_mode = Some(alpha_mode);
+                })
+            }
+            wp_color_representation_surface_v1::Request::SetCoefficientsAndRange { coefficients, range } => {
+                let coefficients_and_range = match (coefficients, range) {
+                    (WEnum::Value(coefficients), WEnum::Value(range))
+                        if data
+                            .supported
+                            .coefficients_and_ranges
+                            .contains(&(coefficients, range)) =>
+                    {
+                        (coefficients, range)
+                    }
+                    _ => {
+                        resource.post_error(
+                            Error::Coefficients,
+                            format!("Unsupported coefficients {coefficients:?} with range {range:?}"),
+                        );
+                        return;
+                    }
+                };
+
+                compositor::with_states(&surface, |states| {
+                    states
+                        .cached_state
+                        .get::<ColorRepresentationSurfaceCachedState>()
+                        .pending()
+                        .coefficients_and_range = Some(coefficients_and_range);
+                })
+            }
+            wp_color_representation_surface_v1::Request::SetChromaLocation { chroma_location } => {
+                let WEnum::Value(chroma_location) = chroma_location else {
+                    resource.post_error(
+                        Error::ChromaLocation,
+                        format!("Invalid chroma location {chroma_location:?}"),
+                    );
+                    return;
+                };
+
+                compositor::with_states(&surface, |states| {
+                    states
+                        .cached_state
+                        .get::<ColorRepresentationSurfaceCachedState>()
+                        .pending()
+                        .chroma_location = Some(chroma_location);
+                })
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(
+        _state: &mut D,
+        _client: ClientId,
+        _object: &WpColorRepresentationSurfaceV1,
+        _data: &ColorRepresentationUserData,
+    ) {
+        // Nothing to do here, graceful Destroy is already handled with double buffering
+        // and in case of client close WlSurface destroyed handler will clean up the data anyway,
+        // so there is no point in queuing new update
+    }
+}
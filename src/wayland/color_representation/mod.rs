@@ -0,0 +1,382 @@
+//! Implementation of wp_color_representation protocol
+//!
+//! This protocol allows clients to declare the alpha mode, the matrix coefficients,
+//! the quantization range and the chroma siting of the buffers attached to a surface.
+//! The main use case is converting YCbCr buffers to RGB.
+//!
+//! The compositor advertises the alpha modes and the combinations of coefficients and ranges
+//! it supports when creating the global. Requests using values that were not advertised
+//! are rejected with a protocol error.
+//!
+//! The committed values are available from the [`ColorRepresentationSurfaceCachedState`]
+//! of a surface. [`ImportDmaWl::import_dma_buffer`](crate::backend::renderer::ImportDmaWl::import_dma_buffer)
+//! automatically passes the [`YuvConversion`] of a surface to the renderer.
+//!
+//! ### Example
+//!
+//! ```no_run
+//! # extern crate wayland_server;
+//! #
+//! use wayland_server::{protocol::wl_surface::WlSurface, DisplayHandle};
+//! use smithay::{
+//!     delegate_color_representation, delegate_compositor,
+//!     reexports::wayland_protocols::wp::color_representation::v1::server::wp_color_representation_surface_v1::{
+//!         AlphaMode, Coefficients, Range,
+//!     },
+//!     wayland::compositor::{self, CompositorState, CompositorClientState, CompositorHandler},
+//!     wayland::color_representation::{ColorRepresentationSurfaceCachedState, ColorRepresentationState},
+//! };
+//!
+//! pub struct State {
+//!     compositor_state: CompositorState,
+//! };
+//! struct ClientState { compositor_state: CompositorClientState }
+//! impl wayland_server::backend::ClientData for ClientState {}
+//!
+//! delegate_color_representation!(State);
+//! delegate_compositor!(State);
+//! # impl smithay::wayland::protocol_error::ProtocolErrorHandler for State {}
+//!
+//! impl CompositorHandler for State {
+//!    fn compositor_state(&mut self) -> &mut CompositorState {
+//!        &mut self.compositor_state
+//!    }
+//!
+//!    fn client_compositor_state<'a>(&self, client: &'a wayland_server::Client) -> &'a CompositorClientState {
+//!        &client.get_data::<ClientState>().unwrap().compositor_state
+//!    }
+//!
+//!    fn commit(&mut self, surface: &WlSurface) {
+//!        compositor::with_states(&surface, |states| {
+//!            let mut guard = states.cached_state.get::<ColorRepresentationSurfaceCachedState>();
+//!            let current = guard.current();
+//!            dbg!(current.coefficients_and_range());
+//!        });
+//!    }
+//! }
+//!
+//! let mut display = wayland_server::Display::<State>::new().unwrap();
+//!
+//! let compositor_state = CompositorState::new::<State>(&display.handle());
+//! ColorRepresentationState::new::<State>(
+//!     &display.handle(),
+//!     [AlphaMode::PremultipliedElectrical],
+//!     [
+//!         (Coefficients::Bt601, Range::Limited),
+//!         (Coefficients::Bt709, Range::Limited),
+//!         (Coefficients::Bt709, Range::Full),
+//!     ],
+//! );
+//!
+//! let state = State {
+//!     compositor_state,
+//! };
+//! ```
+
+use std::sync::{
+    atomic::{self, AtomicBool},
+    Arc, Mutex,
+};
+
+use wayland_protocols::wp::color_representation::v1::server::{
+    wp_color_representation_manager_v1::WpColorRepresentationManagerV1,
+    wp_color_representation_surface_v1::{
+        AlphaMode, ChromaLocation, Coefficients, Range, WpColorRepresentationSurfaceV1,
+    },
+};
+use wayland_server::{
+    backend::GlobalId, protocol::wl_surface::WlSurface, Dispatch, DisplayHandle, GlobalDispatch, Resource,
+    Weak,
+};
+
+use super::compositor::{Cacheable, SurfaceData};
+use crate::backend::renderer::{self, ColorRange, MatrixCoefficients, YuvConversion};
+
+mod dispatch;
+
+/// Data associated with WlSurface
+/// Represents the client pending state
+///
+/// ```no_run
+/// use smithay::wayland::compositor;
+/// use smithay::wayland::color_representation::ColorRepresentationSurfaceCachedState;
+///
+/// # let wl_surface = todo!();
+/// compositor::with_states(&wl_surface, |states| {
+///     let mut guard = states.cached_state.get::<ColorRepresentationSurfaceCachedState>();
+///     let current = guard.current();
+///     dbg!(current.yuv_conversion());
+/// });
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ColorRepresentationSurfaceCachedState {
+    alpha_mode: Option<AlphaMode>,
+    coefficients_and_range: Option<(Coefficients, Range)>,
+    chroma_location: Option<ChromaLocation>,
+}
+
+impl ColorRepresentationSurfaceCachedState {
+    /// Alpha mode of the buffer contents
+    ///
+    /// `None` if unset, in which case premultiplied electrical alpha is assumed.
+    pub fn alpha_mode(&self) -> Option<AlphaMode> {
+        self.alpha_mode
+    }
+
+    /// Matrix coefficients and quantization range of the buffer contents
+    pub fn coefficients_and_range(&self) -> Option<(Coefficients, Range)> {
+        self.coefficients_and_range
+    }
+
+    /// Location of the chroma samples of subsampled buffer contents
+    pub fn chroma_location(&self) -> Option<ChromaLocation> {
+        self.chroma_location
+    }
+
+    /// Parameters for converting YCbCr buffer contents to RGB
+    pub fn yuv_conversion(&self) -> YuvConversion {
+        YuvConversion {
+            coefficients: self.coefficients_and_range.and_then(|(coefficients, _)| {
+                Some(match coefficients {
+                    Coefficients::Identity => MatrixCoefficients::Identity,
+                    Coefficients::Bt709 => MatrixCoefficients::Bt709,
+                    Coefficients::Fcc => MatrixCoefficients::Fcc,
+                    Coefficients::Bt601 => MatrixCoefficients::Bt601,
+                    Coefficients::Smpte240 => MatrixCoefficients::Smpte240,
+                    Coefficients::Bt2020 => MatrixCoefficients::Bt2020,
+                    Coefficients::Bt2020Cl => MatrixCoefficients::Bt2020ConstantLuminance,
+                    Coefficients::Ictcp => MatrixCoefficients::ICtCp,
+                    _ => return None,
+                })
+            }),
+            range: self.coefficients_and_range.and_then(|(_, range)| {
+                Some(match range {
+                    Range::Full => ColorRange::Full,
+                    Range::Limited => ColorRange::Limited,
+                    _ => return None,
+                })
+            }),
+            chroma_location: self.chroma_location.and_then(|location| {
+                Some(match location {
+                    ChromaLocation::Type0 => renderer::ChromaLocation::Type0,
+                    ChromaLocation::Type1 => renderer::ChromaLocation::Type1,
+                    ChromaLocation::Type2 => renderer::ChromaLocation::Type2,
+                    ChromaLocation::Type3 => renderer::ChromaLocation::Type3,
+                    ChromaLocation::Type4 => renderer::ChromaLocation::Type4,
+                    ChromaLocation::Type5 => renderer::ChromaLocation::Type5,
+                    _ => return None,
+                })
+            }),
+        }
+    }
+}
+
+impl Cacheable for ColorRepresentationSurfaceCachedState {
+    fn commit(&mut self, _dh: &DisplayHandle) -> Self {
+        *self
+    }
+
+    fn merge_into(self, into: &mut Self, _dh: &DisplayHandle) {
+        *into = self;
+    }
+}
+
+/// Returns the [`YuvConversion`] currently committed for a surface
+pub fn surface_yuv_conversion(states: &SurfaceData) -> YuvConversion {
+    states
+        .cached_state
+        .get::<ColorRepresentationSurfaceCachedState>()
+        .current()
+        .yuv_conversion()
+}
+
+#[derive(Debug)]
+struct ColorRepresentationSurfaceData {
+    is_resource_attached: AtomicBool,
+}
+
+impl ColorRepresentationSurfaceData {
+    fn new() -> Self {
+        Self {
+            is_resource_attached: AtomicBool::new(false),
+        }
+    }
+
+    fn set_is_resource_attached(&self, is_attached: bool) {
+        self.is_resource_attached
+            .store(is_attached, atomic::Ordering::Release)
+    }
+
+    fn is_resource_attached(&self) -> bool {
+        self.is_resource_attached.load(atomic::Ordering::Acquire)
+    }
+}
+
+/// User data of `WpColorRepresentationSurfaceV1` object
+#[derive(Debug)]
+pub struct ColorRepresentationUserData {
+    surface: Mutex<Weak<WlSurface>>,
+    supported: Arc<SupportedRepresentations>,
+}
+
+impl ColorRepresentationUserData {
+    fn new(surface: WlSurface, supported: Arc<SupportedRepresentations>) -> Self {
+        Self {
+            surface: Mutex::new(surface.downgrade()),
+            supported,
+        }
+    }
+
+    #[inline]
+    fn wl_surface(&self) -> Option<WlSurface> {
+        self.surface.lock().unwrap().upgrade().ok()
+    }
+}
+
+#[derive(Debug)]
+struct SupportedRepresentations {
+    alpha_modes: Vec<AlphaMode>,
+    coefficients_and_ranges: Vec<(Coefficients, Range)>,
+}
+
+/// Global data of the [WpColorRepresentationManagerV1] global
+#[derive(Debug)]
+pub struct ColorRepresentationGlobalData {
+    supported: Arc<SupportedRepresentations>,
+}
+
+/// Delegate type for [WpColorRepresentationManagerV1] global.
+#[derive(Debug)]
+pub struct ColorRepresentationState {
+    global: GlobalId,
+}
+
+impl ColorRepresentationState {
+    /// Register new [WpColorRepresentationManagerV1] global
+    ///
+    /// Clients may only use the given alpha modes and combinations of coefficients and ranges.
+    pub fn new<D>(
+        display: &DisplayHandle,
+        alpha_modes: impl IntoIterator<Item = AlphaMode>,
+        coefficients_and_ranges: impl IntoIterator<Item = (Coefficients, Range)>,
+    ) -> ColorRepresentationState
+    where
+        D: GlobalDispatch<WpColorRepresentationManagerV1, ColorRepresentationGlobalData>
+            + Dispatch<WpColorRepresentationManagerV1, ColorRepresentationGlobalData>
+            + Dispatch<WpColorRepresentationSurfaceV1, ColorRepresentationUserData>
+            + 'static,
+    {
+        let supported = Arc::new(SupportedRepresentations {
+            alpha_modes: alpha_modes.into_iter().collect(),
+            coefficients_and_ranges: coefficients_and_ranges.into_iter().collect(),
+        });
+        let global = display.create_global::<D, WpColorRepresentationManagerV1, _>(
+            1,
+            ColorRepresentationGlobalData { supported },
+        );
+
+        ColorRepresentationState { global }
+    }
+
+    /// Returns the WpColorRepresentationManagerV1 global id
+    pub fn global(&self) -> GlobalId {
+        self.global.clone()
+    }
+}
+
+/// Macro to delegate implementation of the wp color representation protocol
+#[macro_export]
+macro_rules! delegate_color_representation {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        type __WpColorRepresentationManagerV1 =
+            $crate::reexports::wayland_protocols::wp::color_representation::v1::server::wp_color_representation_manager_v1::WpColorRepresentationManagerV1;
+        type __WpColorRepresentationSurfaceV1 =
+            $crate::reexports::wayland_protocols::wp::color_representation::v1::server::wp_color_representation_surface_v1::WpColorRepresentationSurfaceV1;
+
+        $crate::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty:
+            [
+                __WpColorRepresentationManagerV1: $crate::wayland::color_representation::ColorRepresentationGlobalData
+            ] => $crate::wayland::color_representation::ColorRepresentationState
+        );
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty:
+            [
+                __WpColorRepresentationManagerV1: $crate::wayland::color_representation::ColorRepresentationGlobalData
+            ] => $crate::wayland::color_representation::ColorRepresentationState
+        );
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty:
+            [
+                __WpColorRepresentationSurfaceV1: $crate::wayland::color_representation::ColorRepresentationUserData
+            ] => $crate::wayland::color_representation::ColorRepresentationState
+        );
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use wayland_protocols::wp::color_representation::v1::client::{
+        wp_color_representation_manager_v1::WpColorRepresentationManagerV1 as ClientManager,
+        wp_color_representation_surface_v1::{
+            ChromaLocation as ClientChromaLocation, Coefficients as ClientCoefficients, Range as ClientRange,
+        },
+    };
+
+    use super::*;
+    use crate::wayland::{compositor::with_states, test_utils::Fixture};
+
+    #[test]
+    fn committed_conversion() {
+        let mut fixture = Fixture::new();
+        let (surface, server_surface) = fixture.create_surface();
+        let manager: ClientManager = fixture.bind(1);
+        let representation = manager.get_surface(&surface, &fixture.handle(), ());
+        representation.set_coefficients_and_range(ClientCoefficients::Bt709, ClientRange::Full);
+        representation.set_chroma_location(ClientChromaLocation::Type2);
+        fixture.roundtrip();
+
+        let conversion = || with_states(&server_surface, surface_yuv_conversion);
+        // double buffered until the surface is committed
+        assert_eq!(conversion(), YuvConversion::default());
+
+        surface.commit();
+        fixture.roundtrip();
+        assert_eq!(
+            conversion(),
+            YuvConversion {
+                coefficients: Some(MatrixCoefficients::Bt709),
+                range: Some(ColorRange::Full),
+                chroma_location: Some(renderer::ChromaLocation::Type2),
+            }
+        );
+
+        // destroying the object unsets the representation with the next commit
+        representation.destroy();
+        fixture.roundtrip();
+        assert_eq!(conversion().range, Some(ColorRange::Full));
+        surface.commit();
+        fixture.roundtrip();
+        assert_eq!(conversion(), YuvConversion::default());
+    }
+
+    #[test]
+    fn yuv_conversion_of_cached_state() {
+        let state = ColorRepresentationSurfaceCachedState {
+            alpha_mode: Some(AlphaMode::Straight),
+            coefficients_and_range: Some((Coefficients::Bt2020Cl, Range::Limited)),
+            chroma_location: None,
+        };
+        assert_eq!(
+            state.yuv_conversion(),
+            YuvConversion {
+                coefficients: Some(MatrixCoefficients::Bt2020ConstantLuminance),
+                range: Some(ColorRange::Limited),
+                chroma_location: None,
+            }
+        );
+        assert_eq!(
+            ColorRepresentationSurfaceCachedState::default().yuv_conversion(),
+            YuvConversion::default()
+        );
+    }
+}
@@ -58,6 +58,33 @@
 //! });
 //! ```
 //!
+//! [`signal_commit_timers`] does the same for a single surface and is meant to be called for all
+//! surfaces shown on an output right before rendering it, with the time the frame is expected to
+//! be presented at:
+//!
+//! ```no_run
+//! # use wayland_server::{backend::ObjectId, protocol::wl_surface, Resource};
+//! use smithay::wayland::compositor;
+//! use smithay::wayland::commit_timing::signal_commit_timers;
+//! # use smithay::wayland::commit_timing::Timestamp;
+//! # struct State;
+//! # let mut display = wayland_server::Display::<State>::new().unwrap();
+//! # let dh = display.handle();
+//! # let surface = wl_surface::WlSurface::from_id(&dh, ObjectId::null()).unwrap();
+//! # let frame_target: Timestamp = todo!();
+//! compositor::with_surface_tree_downward(
+//!     &surface,
+//!     (),
+//!     |_, _, _| compositor::TraversalAction::DoChildren(()),
+//!     |_, states, _| {
+//!         if signal_commit_timers(states, frame_target) {
+//!             // ..signal blocker cleared
+//!         }
+//!     },
+//!     |_, _, _| true,
+//! );
+//! ```
+//!
 //! ### Unmanaged mode
 //!
 //! If for some reason the integrated solution for commit timers does not suit your needs
@@ -96,7 +123,7 @@ use crate::{
     wayland::compositor::{add_blocker, add_pre_commit_hook},
 };
 
-use super::compositor::{with_states, Barrier};
+use super::compositor::{with_states, Barrier, SurfaceData};
 
 /// State for the [`WpCommitTimingManagerV1`] global
 #[derive(Debug)]
@@ -417,6 +444,18 @@ impl CommitTimerBarrierState {
     }
 }
 
+/// Signals the commit timers of a surface, that are due for a frame presented at `frame_target`
+///
+/// Returns `true` if a commit timer was signaled. In that case
+/// [`CompositorClientState::blocker_cleared`](super::compositor::CompositorClientState::blocker_cleared)
+/// needs to be called for the client of the surface to apply the unblocked commits.
+pub fn signal_commit_timers(states: &SurfaceData, frame_target: impl Into<Timestamp>) -> bool {
+    states
+        .data_map
+        .get::<CommitTimerBarrierStateUserData>()
+        .is_some_and(|commit_timer| commit_timer.lock().unwrap().signal_until(frame_target))
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 struct CommitTimerBarrier {
     timestamp: Timestamp,
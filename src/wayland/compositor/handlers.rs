@@ -15,9 +15,15 @@ use wayland_server::{
     DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource, WEnum,
 };
 
-use crate::utils::{
-    alive_tracker::{AliveTracker, IsAlive},
-    Client, Logical, Point,
+use crate::{
+    utils::{
+        alive_tracker::{AliveTracker, IsAlive},
+        Client, Logical, Point,
+    },
+    wayland::{
+        protocol_error::{post_error, ProtocolErrorHandler, ProtocolErrorKind},
+        version::VersionedResource,
+    },
 };
 
 use super::{
@@ -179,7 +185,7 @@ where
 
                 // If version predates 5 just use the offset
                 // Otherwise error out and use None
-                let offset = if surface.version() < 5 {
+                let offset = if !surface.supports(wl_surface::REQ_OFFSET_SINCE) {
                     offset
                 } else {
                     if offset.is_some() {
@@ -447,7 +453,7 @@ impl<D> Dispatch<WlSubcompositor, (), D> for CompositorState
 where
     D: Dispatch<WlSubcompositor, ()>,
     D: Dispatch<WlSubsurface, SubsurfaceUserData>,
-    D: CompositorHandler,
+    D: CompositorHandler + ProtocolErrorHandler,
     D: 'static,
 {
     fn request(
@@ -462,8 +468,13 @@ where
         match request {
             wl_subcompositor::Request::GetSubsurface { id, surface, parent } => {
                 if let Err(AlreadyHasRole) = PrivateSurfaceData::set_parent(&surface, &parent) {
-                    subcompositor
-                        .post_error(wl_subcompositor::Error::BadSurface, "Surface already has a role.");
+                    post_error(
+                        state,
+                        subcompositor,
+                        ProtocolErrorKind::Role,
+                        wl_subcompositor::Error::BadSurface,
+                        "Surface already has a role.",
+                    );
                     return;
                 }
 
@@ -61,6 +61,8 @@
 //!    }
 //! }
 //! delegate_compositor!(State);
+//! // required to report protocol errors, see `smithay::wayland::protocol_error`
+//! impl smithay::wayland::protocol_error::ProtocolErrorHandler for State {}
 //!
 //! // You're now ready to go!
 //! ```
@@ -124,11 +126,15 @@ use self::tree::{PrivateSurfaceData, SuggestedSurfaceState};
 pub use crate::utils::hook::HookId;
 use crate::utils::Transform;
 use crate::utils::{user_data::UserDataMap, Buffer, Logical, Point, Rectangle};
+use crate::wayland::version::VersionedResource;
 use atomic_float::AtomicF64;
 use wayland_server::backend::GlobalId;
 use wayland_server::protocol::wl_compositor::WlCompositor;
 use wayland_server::protocol::wl_subcompositor::WlSubcompositor;
-use wayland_server::protocol::{wl_buffer, wl_callback, wl_output, wl_region, wl_surface::WlSurface};
+use wayland_server::protocol::{
+    wl_buffer, wl_callback, wl_output, wl_region,
+    wl_surface::{self, WlSurface},
+};
 use wayland_server::{Client, DisplayHandle, GlobalDispatch, Resource};
 
 /// The role of a subsurface surface.
@@ -407,7 +413,7 @@ where
 ///
 /// The new state is only send when it differs from the already cached one on the calling thread.
 pub fn send_surface_state(surface: &WlSurface, data: &SurfaceData, scale: i32, transform: Transform) {
-    if surface.version() < 6 {
+    if !surface.supports(wl_surface::EVT_PREFERRED_BUFFER_SCALE_SINCE) {
         return;
     }
 
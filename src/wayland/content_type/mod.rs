@@ -20,6 +20,7 @@
 //!
 //! delegate_content_type!(State);
 //! delegate_compositor!(State);
+//! # impl smithay::wayland::protocol_error::ProtocolErrorHandler for State {}
 //!
 //! impl CompositorHandler for State {
 //!    fn compositor_state(&mut self) -> &mut CompositorState {
@@ -185,7 +185,7 @@ where
                 pointer,
             } => {
                 let pointer_data = pointer.data::<PointerUserData<D>>();
-                let handle = match pointer_data.and_then(|data| data.handle.as_ref()) {
+                let handle = match pointer_data.and_then(|data| data.handle()) {
                     Some(handle) => handle,
                     None => return,
                 };
@@ -194,7 +194,7 @@ where
                     .seat_state()
                     .seats
                     .iter()
-                    .find(|seat| seat.get_pointer().map(|h| &h == handle).unwrap_or(false))
+                    .find(|seat| seat.get_pointer().map(|h| h == handle).unwrap_or(false))
                     .cloned()
                 else {
                     return;
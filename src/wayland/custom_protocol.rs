@@ -0,0 +1,235 @@
+//! Utilities for implementing custom protocols
+//!
+//! Compositors often implement private protocols next to the ones provided by smithay.
+//! This module contains the building blocks smithay uses for its own protocol modules,
+//! so custom protocols can follow the same structure (see the [module level docs](super)):
+//!
+//! - [`FilteredGlobalData`] is global data restricting which clients can see a global
+//! - [`SimpleCachedState`] is a double-buffered surface state applied on commit
+//! - [`delegate_protocol!`](crate::delegate_protocol) delegates the dispatching of globals
+//!   and resources to a module-`State` type
+//!
+//! ```no_run
+//! # extern crate wayland_server;
+//! # use smithay::reexports::wayland_protocols::wp::alpha_modifier::v1::server::{
+//! #     wp_alpha_modifier_v1::{self as my_manager, WpAlphaModifierV1 as MyManager},
+//! #     wp_alpha_modifier_surface_v1::{self as my_object, WpAlphaModifierSurfaceV1 as MyObject},
+//! # };
+//! use smithay::delegate_protocol;
+//! use smithay::wayland::custom_protocol::FilteredGlobalData;
+//! use wayland_server::{
+//!     backend::GlobalId, Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New,
+//! };
+//!
+//! // `MyManager` and `MyObject` are generated using `wayland-scanner`
+//! pub struct MyProtocolState {
+//!     global: GlobalId,
+//! }
+//!
+//! impl MyProtocolState {
+//!     pub fn new<D, F>(display: &DisplayHandle, filter: F) -> Self
+//!     where
+//!         D: GlobalDispatch<MyManager, FilteredGlobalData> + 'static,
+//!         F: for<'c> Fn(&'c Client) -> bool + Send + Sync + 'static,
+//!     {
+//!         let global = display.create_global::<D, MyManager, _>(1, FilteredGlobalData::new((), filter));
+//!         MyProtocolState { global }
+//!     }
+//! }
+//!
+//! impl<D> GlobalDispatch<MyManager, FilteredGlobalData, D> for MyProtocolState
+//! where
+//!     D: GlobalDispatch<MyManager, FilteredGlobalData> + Dispatch<MyManager, ()>,
+//! {
+//!     fn bind(
+//!         _state: &mut D,
+//!         _dh: &DisplayHandle,
+//!         _client: &Client,
+//!         resource: New<MyManager>,
+//!         _global_data: &FilteredGlobalData,
+//!         data_init: &mut DataInit<'_, D>,
+//!     ) {
+//!         data_init.init(resource, ());
+//!     }
+//!
+//!     fn can_view(client: Client, global_data: &FilteredGlobalData) -> bool {
+//!         global_data.can_view(&client)
+//!     }
+//! }
+//!
+//! impl<D> Dispatch<MyManager, (), D> for MyProtocolState
+//! where
+//!     D: Dispatch<MyManager, ()> + Dispatch<MyObject, ()>,
+//! {
+//!     fn request(
+//!         _state: &mut D,
+//!         _client: &Client,
+//!         _resource: &MyManager,
+//!         request: my_manager::Request,
+//!         _data: &(),
+//!         _dh: &DisplayHandle,
+//!         data_init: &mut DataInit<'_, D>,
+//!     ) {
+//!         if let my_manager::Request::GetSurface { id, .. } = request {
+//!             data_init.init(id, ());
+//!         }
+//!     }
+//! }
+//!
+//! impl<D> Dispatch<MyObject, (), D> for MyProtocolState
+//! where
+//!     D: Dispatch<MyObject, ()>,
+//! {
+//!     fn request(
+//!         _state: &mut D,
+//!         _client: &Client,
+//!         _resource: &MyObject,
+//!         _request: my_object::Request,
+//!         _data: &(),
+//!         _dh: &DisplayHandle,
+//!         _data_init: &mut DataInit<'_, D>,
+//!     ) {
+//!     }
+//! }
+//!
+//! struct State;
+//! delegate_protocol!(State => MyProtocolState {
+//!     globals: [MyManager: FilteredGlobalData],
+//!     resources: [MyManager: (), MyObject: ()],
+//! });
+//! ```
+
+use std::fmt;
+
+use wayland_server::{Client, DisplayHandle};
+
+use super::compositor::Cacheable;
+
+/// Global data restricting which clients can see a global
+///
+/// Forward [`GlobalDispatch::can_view`](wayland_server::GlobalDispatch::can_view) to
+/// [`FilteredGlobalData::can_view`] to apply the filter.
+pub struct FilteredGlobalData<T = ()> {
+    data: T,
+    filter: Box<dyn for<'c> Fn(&'c Client) -> bool + Send + Sync>,
+}
+
+impl<T: fmt::Debug> fmt::Debug for FilteredGlobalData<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FilteredGlobalData")
+            .field("data", &self.data)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> FilteredGlobalData<T> {
+    /// Create global data only visible to clients passing the `filter`
+    pub fn new<F>(data: T, filter: F) -> Self
+    where
+        F: for<'c> Fn(&'c Client) -> bool + Send + Sync + 'static,
+    {
+        FilteredGlobalData {
+            data,
+            filter: Box::new(filter),
+        }
+    }
+
+    /// Create global data visible to all clients
+    pub fn unfiltered(data: T) -> Self {
+        Self::new(data, |_| true)
+    }
+
+    /// Check if the global is visible to a client
+    pub fn can_view(&self, client: &Client) -> bool {
+        (self.filter)(client)
+    }
+
+    /// Returns the wrapped global data
+    pub fn data(&self) -> &T {
+        &self.data
+    }
+}
+
+/// Double-buffered surface state replaced on every commit
+///
+/// Implements [`Cacheable`] for plain state types, which don't need any merging logic.
+/// The pending state is kept after a commit, so it only has to be updated on changes.
+///
+/// ```no_run
+/// # use smithay::wayland::compositor::with_states;
+/// # use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
+/// use smithay::wayland::custom_protocol::SimpleCachedState;
+///
+/// #[derive(Debug, Default, Clone)]
+/// struct MyCachedState {
+///     opacity: Option<f32>,
+/// }
+///
+/// # let surface: WlSurface = todo!();
+/// // in a request handler
+/// with_states(&surface, |states| {
+///     let mut cached = states.cached_state.get::<SimpleCachedState<MyCachedState>>();
+///     cached.pending().opacity = Some(0.5);
+/// });
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SimpleCachedState<T>(pub T);
+
+impl<T: Default + Clone> Cacheable for SimpleCachedState<T> {
+    fn commit(&mut self, _dh: &DisplayHandle) -> Self {
+        self.clone()
+    }
+
+    fn merge_into(self, into: &mut Self, _dh: &DisplayHandle) {
+        *into = self;
+    }
+}
+
+impl<T> std::ops::Deref for SimpleCachedState<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> std::ops::DerefMut for SimpleCachedState<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+/// Macro to delegate the implementation of a custom protocol to a module-`State` type
+///
+/// `globals` lists the global interfaces with their global data, `resources` all interfaces
+/// with their user data. The module-`State` has to implement the matching
+/// [`GlobalDispatch`](wayland_server::GlobalDispatch) and [`Dispatch`](wayland_server::Dispatch) traits.
+///
+/// See the [`custom_protocol`](crate::wayland::custom_protocol) module for an example.
+#[macro_export]
+macro_rules! delegate_protocol {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty => $state: ty {
+        globals: [$($global: ty: $global_data: ty),* $(,)?],
+        resources: [$($interface: ty: $udata: ty),* $(,)?] $(,)?
+    }) => {
+        $crate::delegate_protocol!(@expand { $(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? } $ty => $state;
+            [$($global: $global_data),*];
+            [$($interface: $udata),*]
+        );
+    };
+    // the generics are passed as a single token tree, so they can be repeated for every interface
+    (@expand $generics: tt $ty: ty => $state: ty; [$($global: ty: $global_data: ty),*]; [$($interface: ty: $udata: ty),*]) => {
+        $($crate::delegate_protocol!(@global $generics $ty => $state; $global: $global_data);)*
+        $($crate::delegate_protocol!(@resource $generics $ty => $state; $interface: $udata);)*
+    };
+    (@global { $($generics: tt)* } $ty: ty => $state: ty; $global: ty: $global_data: ty) => {
+        $crate::reexports::wayland_server::delegate_global_dispatch!($($generics)* $ty: [
+            $global: $global_data
+        ] => $state);
+    };
+    (@resource { $($generics: tt)* } $ty: ty => $state: ty; $interface: ty: $udata: ty) => {
+        $crate::reexports::wayland_server::delegate_dispatch!($($generics)* $ty: [
+            $interface: $udata
+        ] => $state);
+    };
+}
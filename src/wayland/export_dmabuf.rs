@@ -0,0 +1,313 @@
+//! Utilities for handling the `wlr-export-dmabuf` protocol
+//!
+//! This protocol allows clients, like screen recorders, to capture the contents of outputs by
+//! receiving the dmabufs the compositor presents, without any copies.
+//!
+//! For every capture [`ExportDmabufHandler::frame`] is called with an [`ExportDmabufFrame`], which
+//! the compositor fulfills by passing the buffer of the next frame presented on the output to
+//! [`ExportDmabufFrame::submit`]. When using a
+//! [`DrmCompositor`](crate::backend::drm::compositor::DrmCompositor), the buffer of the primary plane
+//! can be retrieved using
+//! [`PrimarySwapchainElement::export`](crate::backend::drm::compositor::PrimarySwapchainElement::export),
+//! as long as the output is composited without using overlay or cursor planes.
+//!
+//! Dropping the [`ExportDmabufFrame`] notifies the client, that the capture failed temporarily.
+//! Use [`ExportDmabufFrame::cancel`] to report a different [`CancelReason`].
+//!
+//! ## How to use it
+//!
+//! ```
+//! use smithay::delegate_export_dmabuf;
+//! use smithay::wayland::export_dmabuf::{ExportDmabufFrame, ExportDmabufHandler, ExportDmabufManagerState};
+//!
+//! # struct State { export_dmabuf_state: ExportDmabufManagerState }
+//! # let mut display = wayland_server::Display::<State>::new().unwrap();
+//! // Create the export-dmabuf state
+//! let export_dmabuf_state = ExportDmabufManagerState::new::<State, _>(&display.handle(), |_client| true);
+//!
+//! // Insert the ExportDmabufManagerState into your state.
+//!
+//! // Implement the necessary trait.
+//! impl ExportDmabufHandler for State {
+//!     fn export_dmabuf_state(&mut self) -> &mut ExportDmabufManagerState {
+//!         &mut self.export_dmabuf_state
+//!     }
+//!
+//!     fn frame(&mut self, frame: ExportDmabufFrame) {
+//!         // Store the frame and, once the next frame of `frame.output()` was presented,
+//!         // call `frame.submit(..)` with its buffer.
+//!     }
+//! }
+//! delegate_export_dmabuf!(State);
+//! ```
+
+use std::time::Duration;
+
+use rustix::fs::SeekFrom;
+use tracing::warn;
+use wayland_protocols_wlr::export_dmabuf::v1::server::{
+    zwlr_export_dmabuf_frame_v1::{self, CancelReason, ZwlrExportDmabufFrameV1},
+    zwlr_export_dmabuf_manager_v1::{self, ZwlrExportDmabufManagerV1},
+};
+use wayland_server::{backend::GlobalId, Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New};
+
+use crate::{
+    backend::allocator::{
+        dmabuf::{Dmabuf, DmabufFlags},
+        Buffer as _,
+    },
+    output::Output,
+};
+
+/// State of the [`ZwlrExportDmabufManagerV1`] global
+#[derive(Debug)]
+pub struct ExportDmabufManagerState {
+    global: GlobalId,
+}
+
+impl ExportDmabufManagerState {
+    /// Create a new [`ZwlrExportDmabufManagerV1`] global
+    ///
+    /// The filter decides which clients are allowed to capture outputs.
+    pub fn new<D, F>(display: &DisplayHandle, filter: F) -> Self
+    where
+        D: GlobalDispatch<ZwlrExportDmabufManagerV1, ExportDmabufManagerGlobalData>,
+        D: Dispatch<ZwlrExportDmabufManagerV1, ()>,
+        D: Dispatch<ZwlrExportDmabufFrameV1, ()>,
+        D: ExportDmabufHandler,
+        D: 'static,
+        F: for<'c> Fn(&'c Client) -> bool + Send + Sync + 'static,
+    {
+        let data = ExportDmabufManagerGlobalData {
+            filter: Box::new(filter),
+        };
+        let global = display.create_global::<D, ZwlrExportDmabufManagerV1, _>(1, data);
+
+        ExportDmabufManagerState { global }
+    }
+
+    /// Returns the id of the [`ZwlrExportDmabufManagerV1`] global
+    pub fn global(&self) -> GlobalId {
+        self.global.clone()
+    }
+}
+
+/// Handler trait for wlr-export-dmabuf
+pub trait ExportDmabufHandler {
+    /// [`ExportDmabufManagerState`] getter
+    fn export_dmabuf_state(&mut self) -> &mut ExportDmabufManagerState;
+
+    /// A client requested to capture the next frame of an output
+    fn frame(&mut self, frame: ExportDmabufFrame);
+}
+
+#[allow(missing_debug_implementations)]
+#[doc(hidden)]
+pub struct ExportDmabufManagerGlobalData {
+    filter: Box<dyn for<'c> Fn(&'c Client) -> bool + Send + Sync>,
+}
+
+/// A pending capture of an output
+///
+/// See [`ExportDmabufHandler::frame`]. Dropping it without calling [`ExportDmabufFrame::submit`]
+/// notifies the client, that the capture failed temporarily.
+#[derive(Debug)]
+pub struct ExportDmabufFrame {
+    frame: ZwlrExportDmabufFrameV1,
+    output: Output,
+    overlay_cursor: bool,
+    done: bool,
+}
+
+impl ExportDmabufFrame {
+    /// Returns the underlying [`ZwlrExportDmabufFrameV1`]
+    pub fn frame(&self) -> &ZwlrExportDmabufFrameV1 {
+        &self.frame
+    }
+
+    /// Returns the captured output
+    pub fn output(&self) -> &Output {
+        &self.output
+    }
+
+    /// Returns whether the client requested the cursor to be included
+    ///
+    /// Buffers of the primary plane usually don't include the cursor,
+    /// the client has no way to tell if it is missing.
+    pub fn overlay_cursor(&self) -> bool {
+        self.overlay_cursor
+    }
+
+    /// Send the buffer presented on the output to the client
+    ///
+    /// The buffer is marked as transient, as the buffers of swapchains are reused for later frames.
+    /// `timestamp` is the presentation time of the buffer, in the clock domain of the presentation
+    /// time protocol.
+    pub fn submit(mut self, dmabuf: &Dmabuf, timestamp: Duration) {
+        let object_sizes = match dmabuf
+            .handles()
+            .map(|fd| rustix::fs::seek(fd, SeekFrom::End(0)))
+            .collect::<Result<Vec<_>, _>>()
+        {
+            Ok(sizes) => sizes,
+            Err(err) => {
+                warn!(?err, "Failed to determine the size of a dmabuf plane");
+                self.cancel(CancelReason::Temporary);
+                return;
+            }
+        };
+
+        let size = dmabuf.size();
+        let format = dmabuf.format();
+        let modifier = u64::from(format.modifier);
+        let buffer_flags = if dmabuf.y_inverted() {
+            DmabufFlags::Y_INVERT
+        } else {
+            DmabufFlags::empty()
+        };
+
+        self.frame.frame(
+            size.w as u32,
+            size.h as u32,
+            0,
+            0,
+            buffer_flags.bits(),
+            zwlr_export_dmabuf_frame_v1::Flags::Transient,
+            format.code as u32,
+            (modifier >> 32) as u32,
+            modifier as u32,
+            dmabuf.num_planes() as u32,
+        );
+        let planes = dmabuf.handles().zip(dmabuf.offsets()).zip(dmabuf.strides());
+        for (index, (((fd, offset), stride), object_size)) in planes.zip(object_sizes).enumerate() {
+            self.frame
+                .object(index as u32, fd, object_size as u32, offset, stride, index as u32);
+        }
+
+        let secs = timestamp.as_secs();
+        self.frame
+            .ready((secs >> 32) as u32, secs as u32, timestamp.subsec_nanos());
+        self.done = true;
+    }
+
+    /// Notify the client, that the capture failed for the given reason
+    pub fn cancel(mut self, reason: CancelReason) {
+        self.frame.cancel(reason);
+        self.done = true;
+    }
+}
+
+impl Drop for ExportDmabufFrame {
+    fn drop(&mut self) {
+        if !self.done {
+            self.frame.cancel(CancelReason::Temporary);
+        }
+    }
+}
+
+impl<D> GlobalDispatch<ZwlrExportDmabufManagerV1, ExportDmabufManagerGlobalData, D>
+    for ExportDmabufManagerState
+where
+    D: GlobalDispatch<ZwlrExportDmabufManagerV1, ExportDmabufManagerGlobalData>,
+    D: Dispatch<ZwlrExportDmabufManagerV1, ()>,
+    D: Dispatch<ZwlrExportDmabufFrameV1, ()>,
+    D: ExportDmabufHandler,
+    D: 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _display: &DisplayHandle,
+        _client: &Client,
+        manager: New<ZwlrExportDmabufManagerV1>,
+        _global_data: &ExportDmabufManagerGlobalData,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        data_init.init(manager, ());
+    }
+
+    fn can_view(client: Client, global_data: &ExportDmabufManagerGlobalData) -> bool {
+        (global_data.filter)(&client)
+    }
+}
+
+impl<D> Dispatch<ZwlrExportDmabufManagerV1, (), D> for ExportDmabufManagerState
+where
+    D: Dispatch<ZwlrExportDmabufManagerV1, ()>,
+    D: Dispatch<ZwlrExportDmabufFrameV1, ()>,
+    D: ExportDmabufHandler,
+    D: 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        _manager: &ZwlrExportDmabufManagerV1,
+        request: zwlr_export_dmabuf_manager_v1::Request,
+        _data: &(),
+        _display: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zwlr_export_dmabuf_manager_v1::Request::CaptureOutput {
+                frame,
+                overlay_cursor,
+                output,
+            } => {
+                let frame = data_init.init(frame, ());
+                let Some(output) = Output::from_resource(&output) else {
+                    frame.cancel(CancelReason::Permanent);
+                    return;
+                };
+                state.frame(ExportDmabufFrame {
+                    frame,
+                    output,
+                    overlay_cursor: overlay_cursor != 0,
+                    done: false,
+                });
+            }
+            zwlr_export_dmabuf_manager_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<ZwlrExportDmabufFrameV1, (), D> for ExportDmabufManagerState
+where
+    D: Dispatch<ZwlrExportDmabufFrameV1, ()>,
+    D: ExportDmabufHandler,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        _frame: &ZwlrExportDmabufFrameV1,
+        request: zwlr_export_dmabuf_frame_v1::Request,
+        _data: &(),
+        _display: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zwlr_export_dmabuf_frame_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// Macro to delegate implementation of wlr-export-dmabuf to [`ExportDmabufManagerState`].
+///
+/// You must also implement [`ExportDmabufHandler`] to use this.
+#[macro_export]
+macro_rules! delegate_export_dmabuf {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        $crate::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols_wlr::export_dmabuf::v1::server::zwlr_export_dmabuf_manager_v1::ZwlrExportDmabufManagerV1: $crate::wayland::export_dmabuf::ExportDmabufManagerGlobalData
+        ] => $crate::wayland::export_dmabuf::ExportDmabufManagerState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols_wlr::export_dmabuf::v1::server::zwlr_export_dmabuf_manager_v1::ZwlrExportDmabufManagerV1: ()
+        ] => $crate::wayland::export_dmabuf::ExportDmabufManagerState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols_wlr::export_dmabuf::v1::server::zwlr_export_dmabuf_frame_v1::ZwlrExportDmabufFrameV1: ()
+        ] => $crate::wayland::export_dmabuf::ExportDmabufManagerState);
+    };
+}
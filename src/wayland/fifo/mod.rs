@@ -61,6 +61,31 @@
 //! });
 //! ```
 //!
+//! [`signal_fifo_barrier`] does the same for a single surface. To release at most one content
+//! update per refresh cycle, it is meant to be called once per frame of an output, e.g. when the
+//! frame was presented, for all surfaces having that output as their primary scan-out output:
+//!
+//! ```no_run
+//! # use wayland_server::{backend::ObjectId, protocol::wl_surface, Resource};
+//! use smithay::wayland::compositor;
+//! use smithay::wayland::fifo::signal_fifo_barrier;
+//! # struct State;
+//! # let mut display = wayland_server::Display::<State>::new().unwrap();
+//! # let dh = display.handle();
+//! # let surface = wl_surface::WlSurface::from_id(&dh, ObjectId::null()).unwrap();
+//! compositor::with_surface_tree_downward(
+//!     &surface,
+//!     (),
+//!     |_, _, _| compositor::TraversalAction::DoChildren(()),
+//!     |_, states, _| {
+//!         if signal_fifo_barrier(states) {
+//!             // ..signal blocker cleared
+//!         }
+//!     },
+//!     |_, _, _| true,
+//! );
+//! ```
+//!
 //! ### Unmanaged mode
 //!
 //! If for some reason the integrated solution for fifo does not suit your needs
@@ -92,7 +117,7 @@ use wayland_server::{
 
 use crate::wayland::compositor::{add_blocker, add_pre_commit_hook};
 
-use super::compositor::{is_sync_subsurface, with_states, Barrier, Cacheable};
+use super::compositor::{is_sync_subsurface, with_states, Barrier, Cacheable, SurfaceData};
 
 /// State for the [`WpFifoManagerV1`] global
 #[derive(Debug)]
@@ -389,6 +414,27 @@ impl Cacheable for FifoBarrierCachedState {
     }
 }
 
+/// Signals the fifo barrier of the current content update of a surface
+///
+/// Returns `true` if a barrier was signaled. In that case
+/// [`CompositorClientState::blocker_cleared`](super::compositor::CompositorClientState::blocker_cleared)
+/// needs to be called for the client of the surface to apply the unblocked commits.
+pub fn signal_fifo_barrier(states: &SurfaceData) -> bool {
+    let fifo_barrier = states
+        .cached_state
+        .get::<FifoBarrierCachedState>()
+        .current()
+        .barrier
+        .take();
+
+    if let Some(fifo_barrier) = fifo_barrier {
+        fifo_barrier.signal();
+        true
+    } else {
+        false
+    }
+}
+
 /// Macro used to delegate [`WpFifoManagerV1`] events
 #[macro_export]
 macro_rules! delegate_fifo {
@@ -129,6 +129,12 @@ pub struct ForeignToplevelHandle {
     inner: Arc<(Mutex<ForeignToplevelHandleInner>, UserDataMap)>,
 }
 
+impl PartialEq for ForeignToplevelHandle {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
 impl ForeignToplevelHandle {
     fn new(
         identifier: String,
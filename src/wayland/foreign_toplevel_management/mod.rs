@@ -0,0 +1,846 @@
+//! Foreign toplevel management
+//!
+//! This module implements the `wlr-foreign-toplevel-management` protocol, which allows privileged
+//! clients like taskbars and docks to list toplevels and to request to activate, close, (un)maximize,
+//! (un)minimize or (un)fullscreen them. Unlike the read-only
+//! [foreign toplevel list](crate::wayland::foreign_toplevel_list), requests of clients are forwarded
+//! to the [`ForeignToplevelManagementHandler`], which decides whether and how to apply them.
+//!
+//! The state of a toplevel is advertised through its [`ForeignToplevelManagementHandle`]. For xdg toplevels
+//! [`ForeignToplevelManagementHandle::update_from_toplevel`] synchronizes the title, app id, parent and
+//! the maximized, fullscreen and activated states, and should be called after every commit and configure.
+//! Entered outputs and the minimized state have to be updated by the compositor.
+//!
+//! ```no_run
+//! use smithay::output::Output;
+//! use smithay::reexports::wayland_server::protocol::wl_seat::WlSeat;
+//! use smithay::wayland::foreign_toplevel_management::{
+//!     ForeignToplevelManagementHandle, ForeignToplevelManagementHandler, ForeignToplevelManagementState,
+//! };
+//! # use smithay::wayland::shell::xdg::ToplevelSurface;
+//!
+//! pub struct State {
+//!     foreign_toplevel_management: ForeignToplevelManagementState,
+//! }
+//!
+//! smithay::delegate_foreign_toplevel_management!(State);
+//!
+//! impl ForeignToplevelManagementHandler for State {
+//!     fn foreign_toplevel_management_state(&mut self) -> &mut ForeignToplevelManagementState {
+//!         &mut self.foreign_toplevel_management
+//!     }
+//!
+//!     fn activate(&mut self, handle: &ForeignToplevelManagementHandle, seat: WlSeat) {
+//!         // raise and focus the window of the handle
+//!     }
+//!
+//!     fn close(&mut self, handle: &ForeignToplevelManagementHandle) {
+//!         // ask the window of the handle to close, e.g. with `ToplevelSurface::send_close`
+//!     }
+//! }
+//!
+//! # let mut display = wayland_server::Display::<State>::new().unwrap();
+//! # let display_handle = display.handle();
+//! # let toplevel: ToplevelSurface = unimplemented!();
+//! # let output: Output = unimplemented!();
+//! let mut state = State {
+//!     foreign_toplevel_management: ForeignToplevelManagementState::new::<State>(&display_handle),
+//! };
+//!
+//! // once the toplevel was mapped
+//! let handle = state.foreign_toplevel_management.new_toplevel::<State>("", "");
+//! handle.send_output_enter(&output);
+//! handle.update_from_toplevel(&toplevel);
+//!
+//! // once the toplevel was unmapped
+//! state.foreign_toplevel_management.remove_toplevel(&handle);
+//! ```
+
+use std::sync::{Arc, Mutex};
+
+use wayland_protocols::xdg::shell::server::xdg_toplevel;
+use wayland_protocols_wlr::foreign_toplevel::v1::server::{
+    zwlr_foreign_toplevel_handle_v1::{self, ZwlrForeignToplevelHandleV1},
+    zwlr_foreign_toplevel_manager_v1::{self, ZwlrForeignToplevelManagerV1},
+};
+use wayland_server::{
+    backend::{ClientId, GlobalId},
+    protocol::{wl_seat::WlSeat, wl_surface::WlSurface},
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource, Weak,
+};
+
+use crate::{
+    output::Output,
+    utils::{user_data::UserDataMap, Logical, Rectangle},
+    wayland::{
+        compositor,
+        shell::xdg::{ToplevelSurface, XdgToplevelSurfaceData},
+    },
+};
+
+const MANAGER_VERSION: u32 = 3;
+
+/// Handler for the foreign toplevel management protocol
+pub trait ForeignToplevelManagementHandler:
+    GlobalDispatch<ZwlrForeignToplevelManagerV1, ForeignToplevelManagementGlobalData>
+    + Dispatch<ZwlrForeignToplevelManagerV1, ()>
+    + Dispatch<ZwlrForeignToplevelHandleV1, ForeignToplevelManagementHandle>
+    + 'static
+{
+    /// [ForeignToplevelManagementState] getter
+    fn foreign_toplevel_management_state(&mut self) -> &mut ForeignToplevelManagementState;
+
+    /// A client requested to activate the toplevel on the given seat
+    fn activate(&mut self, handle: &ForeignToplevelManagementHandle, seat: WlSeat);
+
+    /// A client requested to close the toplevel
+    fn close(&mut self, handle: &ForeignToplevelManagementHandle);
+
+    /// A client requested to maximize the toplevel
+    fn set_maximized(&mut self, handle: &ForeignToplevelManagementHandle) {
+        let _ = handle;
+    }
+
+    /// A client requested to unmaximize the toplevel
+    fn unset_maximized(&mut self, handle: &ForeignToplevelManagementHandle) {
+        let _ = handle;
+    }
+
+    /// A client requested to minimize the toplevel
+    fn set_minimized(&mut self, handle: &ForeignToplevelManagementHandle) {
+        let _ = handle;
+    }
+
+    /// A client requested to unminimize the toplevel
+    fn unset_minimized(&mut self, handle: &ForeignToplevelManagementHandle) {
+        let _ = handle;
+    }
+
+    /// A client requested to make the toplevel fullscreen, optionally on the given output
+    fn set_fullscreen(&mut self, handle: &ForeignToplevelManagementHandle, output: Option<Output>) {
+        let _ = (handle, output);
+    }
+
+    /// A client requested to leave fullscreen
+    fn unset_fullscreen(&mut self, handle: &ForeignToplevelManagementHandle) {
+        let _ = handle;
+    }
+
+    /// A client set the rectangle representing the toplevel on one of its surfaces
+    ///
+    /// The rectangle is relative to the surface, e.g. the location of a taskbar button,
+    /// and can be used as the target of a minimize animation. An empty rectangle unsets it.
+    fn set_rectangle(
+        &mut self,
+        handle: &ForeignToplevelManagementHandle,
+        surface: WlSurface,
+        rectangle: Rectangle<i32, Logical>,
+    ) {
+        let _ = (handle, surface, rectangle);
+    }
+}
+
+/// States of a toplevel advertised by a [`ForeignToplevelManagementHandle`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ForeignToplevelState {
+    /// The toplevel is maximized
+    pub maximized: bool,
+    /// The toplevel is minimized
+    pub minimized: bool,
+    /// The toplevel is activated
+    pub activated: bool,
+    /// The toplevel is fullscreen
+    pub fullscreen: bool,
+}
+
+impl ForeignToplevelState {
+    fn to_array(self, version: u32) -> Vec<u8> {
+        let states = [
+            (self.maximized, zwlr_foreign_toplevel_handle_v1::State::Maximized),
+            (self.minimized, zwlr_foreign_toplevel_handle_v1::State::Minimized),
+            (self.activated, zwlr_foreign_toplevel_handle_v1::State::Activated),
+            (
+                self.fullscreen && version >= 2,
+                zwlr_foreign_toplevel_handle_v1::State::Fullscreen,
+            ),
+        ];
+        states
+            .into_iter()
+            .filter(|(set, _)| *set)
+            .flat_map(|(_, state)| (state as u32).to_ne_bytes())
+            .collect()
+    }
+}
+
+#[derive(Debug)]
+struct ForeignToplevelManagementHandleInner {
+    title: String,
+    app_id: String,
+    state: ForeignToplevelState,
+    outputs: Vec<Output>,
+    parent: Option<ForeignToplevelManagementWeakHandle>,
+    // Each ZwlrForeignToplevelHandleV1 contains the handle in it's user data,
+    // so this ref has to be weak
+    instances: Vec<Weak<ZwlrForeignToplevelHandleV1>>,
+    closed: bool,
+}
+
+impl ForeignToplevelManagementHandleInner {
+    fn instances(&self) -> impl Iterator<Item = ZwlrForeignToplevelHandleV1> + '_ {
+        self.instances.iter().filter_map(|weak| weak.upgrade().ok())
+    }
+
+    fn send_closed(&mut self) {
+        if self.closed {
+            return;
+        }
+
+        self.closed = true;
+        // drain to prevent any events from being sent to closed handles
+        for toplevel in self.instances.drain(..) {
+            if let Ok(toplevel) = toplevel.upgrade() {
+                toplevel.closed();
+            }
+        }
+    }
+}
+
+impl Drop for ForeignToplevelManagementHandleInner {
+    fn drop(&mut self) {
+        self.send_closed()
+    }
+}
+
+/// Weak version of [ForeignToplevelManagementHandle]
+#[derive(Debug, Clone)]
+pub struct ForeignToplevelManagementWeakHandle {
+    inner: std::sync::Weak<(Mutex<ForeignToplevelManagementHandleInner>, UserDataMap)>,
+}
+
+impl ForeignToplevelManagementWeakHandle {
+    /// Upgrade weak [ForeignToplevelManagementWeakHandle] to strong [ForeignToplevelManagementHandle]
+    pub fn upgrade(&self) -> Option<ForeignToplevelManagementHandle> {
+        Some(ForeignToplevelManagementHandle {
+            inner: self.inner.upgrade()?,
+        })
+    }
+}
+
+// Associates the handle with the wl_surface of a toplevel
+type SurfaceHandle = Mutex<Option<ForeignToplevelManagementWeakHandle>>;
+
+/// Handle of a toplevel, used to advertise its state to foreign toplevel management clients
+///
+/// Changes are only applied by clients after [ForeignToplevelManagementHandle::send_done].
+#[derive(Debug, Clone)]
+pub struct ForeignToplevelManagementHandle {
+    inner: Arc<(Mutex<ForeignToplevelManagementHandleInner>, UserDataMap)>,
+}
+
+impl PartialEq for ForeignToplevelManagementHandle {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+impl ForeignToplevelManagementHandle {
+    fn new(title: String, app_id: String) -> Self {
+        Self {
+            inner: Arc::new((
+                Mutex::new(ForeignToplevelManagementHandleInner {
+                    title,
+                    app_id,
+                    state: ForeignToplevelState::default(),
+                    outputs: Vec::new(),
+                    parent: None,
+                    instances: Vec::new(),
+                    closed: false,
+                }),
+                UserDataMap::new(),
+            )),
+        }
+    }
+
+    /// Downgrade strong [ForeignToplevelManagementHandle] to weak [ForeignToplevelManagementWeakHandle]
+    pub fn downgrade(&self) -> ForeignToplevelManagementWeakHandle {
+        ForeignToplevelManagementWeakHandle {
+            inner: Arc::downgrade(&self.inner),
+        }
+    }
+
+    /// Attempt to retrieve [ForeignToplevelManagementHandle] from an existing resource
+    pub fn from_resource(resource: &ZwlrForeignToplevelHandleV1) -> Option<Self> {
+        resource.data::<Self>().cloned()
+    }
+
+    /// Retrieve the handle associated with a toplevel surface by
+    /// [ForeignToplevelManagementHandle::update_from_toplevel]
+    pub fn from_surface(surface: &WlSurface) -> Option<Self> {
+        compositor::with_states(surface, |states| {
+            states
+                .data_map
+                .get::<SurfaceHandle>()
+                .and_then(|handle| handle.lock().unwrap().as_ref().and_then(|weak| weak.upgrade()))
+        })
+    }
+
+    /// Retrieve [`ZwlrForeignToplevelHandleV1`] instances for this handle.
+    pub fn resources(&self) -> Vec<ZwlrForeignToplevelHandleV1> {
+        self.inner.0.lock().unwrap().instances().collect()
+    }
+
+    /// Retrieve [`ZwlrForeignToplevelHandleV1`] instances for this handle of a given [`Client`].
+    pub fn resources_for_client(&self, client: &Client) -> Vec<ZwlrForeignToplevelHandleV1> {
+        self.resources()
+            .into_iter()
+            .filter(|handle| handle.client().as_ref().is_some_and(|c| c == client))
+            .collect()
+    }
+
+    /// Access the [UserDataMap] associated with this [ForeignToplevelManagementHandle]
+    pub fn user_data(&self) -> &UserDataMap {
+        &self.inner.1
+    }
+
+    /// The title of the toplevel has changed.
+    ///
+    /// [Self::send_done] has to be called to finalize the update
+    pub fn send_title(&self, title: &str) {
+        let mut inner = self.inner.0.lock().unwrap();
+        if inner.title == title {
+            return;
+        }
+
+        inner.title = title.to_string();
+        for toplevel in inner.instances() {
+            toplevel.title(title.to_string());
+        }
+    }
+
+    /// The app_id of the toplevel has changed.
+    ///
+    /// [Self::send_done] has to be called to finalize the update
+    pub fn send_app_id(&self, app_id: &str) {
+        let mut inner = self.inner.0.lock().unwrap();
+        if inner.app_id == app_id {
+            return;
+        }
+
+        inner.app_id = app_id.to_string();
+        for toplevel in inner.instances() {
+            toplevel.app_id(app_id.to_string());
+        }
+    }
+
+    /// The states of the toplevel have changed.
+    ///
+    /// [Self::send_done] has to be called to finalize the update
+    pub fn send_state(&self, state: ForeignToplevelState) {
+        let mut inner = self.inner.0.lock().unwrap();
+        if inner.state == state {
+            return;
+        }
+
+        inner.state = state;
+        for toplevel in inner.instances() {
+            toplevel.state(state.to_array(toplevel.version()));
+        }
+    }
+
+    /// The toplevel entered an output.
+    ///
+    /// [Self::send_done] has to be called to finalize the update
+    pub fn send_output_enter(&self, output: &Output) {
+        let mut inner = self.inner.0.lock().unwrap();
+        if inner.outputs.contains(output) {
+            return;
+        }
+
+        inner.outputs.push(output.clone());
+        for toplevel in inner.instances() {
+            send_output_enter(&toplevel, output);
+        }
+    }
+
+    /// The toplevel left an output.
+    ///
+    /// [Self::send_done] has to be called to finalize the update
+    pub fn send_output_leave(&self, output: &Output) {
+        let mut inner = self.inner.0.lock().unwrap();
+        let Some(pos) = inner.outputs.iter().position(|o| o == output) else {
+            return;
+        };
+
+        inner.outputs.remove(pos);
+        for toplevel in inner.instances() {
+            let Some(client) = toplevel.client() else {
+                continue;
+            };
+            for wl_output in output.client_outputs(&client) {
+                toplevel.output_leave(&wl_output);
+            }
+        }
+    }
+
+    /// The parent of the toplevel has changed.
+    ///
+    /// [Self::send_done] has to be called to finalize the update
+    pub fn send_parent(&self, parent: Option<&ForeignToplevelManagementHandle>) {
+        let mut inner = self.inner.0.lock().unwrap();
+        if inner.parent.as_ref().and_then(|weak| weak.upgrade()).as_ref() == parent {
+            return;
+        }
+
+        inner.parent = parent.map(|parent| parent.downgrade());
+        for toplevel in inner.instances() {
+            send_parent(&toplevel, parent);
+        }
+    }
+
+    /// This event is should be sent after all changes in the toplevel state have been sent.
+    pub fn send_done(&self) {
+        let inner = self.inner.0.lock().unwrap();
+        for toplevel in inner.instances() {
+            toplevel.done();
+        }
+    }
+
+    /// The toplevel has been closed
+    pub fn send_closed(&self) {
+        self.inner.0.lock().unwrap().send_closed();
+    }
+
+    /// Synchronize the handle with the current state of an xdg toplevel and send done, if anything changed
+    ///
+    /// Updates the title, app id, parent and the maximized, fullscreen and activated states.
+    /// The handle is associated with the surface, see [ForeignToplevelManagementHandle::from_surface].
+    pub fn update_from_toplevel(&self, toplevel: &ToplevelSurface) {
+        let (title, app_id) = compositor::with_states(toplevel.wl_surface(), |states| {
+            states
+                .data_map
+                .insert_if_missing_threadsafe(|| SurfaceHandle::new(None));
+            *states.data_map.get::<SurfaceHandle>().unwrap().lock().unwrap() = Some(self.downgrade());
+
+            let attributes = states
+                .data_map
+                .get::<XdgToplevelSurfaceData>()
+                .unwrap()
+                .lock()
+                .unwrap();
+            (
+                attributes.title.clone().unwrap_or_default(),
+                attributes.app_id.clone().unwrap_or_default(),
+            )
+        });
+        let current = toplevel.current_state();
+        let parent = toplevel
+            .parent()
+            .and_then(|parent| ForeignToplevelManagementHandle::from_surface(&parent));
+
+        let (old_title, old_app_id, old_state, old_parent) = {
+            let inner = self.inner.0.lock().unwrap();
+            (
+                inner.title.clone(),
+                inner.app_id.clone(),
+                inner.state,
+                inner.parent.as_ref().and_then(|weak| weak.upgrade()),
+            )
+        };
+        let state = ForeignToplevelState {
+            maximized: current.states.contains(xdg_toplevel::State::Maximized),
+            fullscreen: current.states.contains(xdg_toplevel::State::Fullscreen),
+            activated: current.states.contains(xdg_toplevel::State::Activated),
+            ..old_state
+        };
+        if title == old_title && app_id == old_app_id && state == old_state && parent == old_parent {
+            return;
+        }
+
+        self.send_title(&title);
+        self.send_app_id(&app_id);
+        self.send_state(state);
+        self.send_parent(parent.as_ref());
+        self.send_done();
+    }
+
+    /// The title of the toplevel
+    pub fn title(&self) -> String {
+        self.inner.0.lock().unwrap().title.clone()
+    }
+
+    /// The app id of the toplevel
+    pub fn app_id(&self) -> String {
+        self.inner.0.lock().unwrap().app_id.clone()
+    }
+
+    /// The states of the toplevel
+    pub fn state(&self) -> ForeignToplevelState {
+        self.inner.0.lock().unwrap().state
+    }
+
+    /// The outputs the toplevel entered
+    pub fn outputs(&self) -> Vec<Output> {
+        self.inner.0.lock().unwrap().outputs.clone()
+    }
+
+    /// The parent of the toplevel
+    pub fn parent(&self) -> Option<ForeignToplevelManagementHandle> {
+        self.inner
+            .0
+            .lock()
+            .unwrap()
+            .parent
+            .as_ref()
+            .and_then(|weak| weak.upgrade())
+    }
+
+    /// The toplevel has been closed
+    pub fn is_closed(&self) -> bool {
+        self.inner.0.lock().unwrap().closed
+    }
+
+    fn add_instance(&self, toplevel: &ZwlrForeignToplevelHandleV1) {
+        debug_assert!(
+            !self.is_closed(),
+            "No handles should ever be created for closed toplevel"
+        );
+        self.inner.0.lock().unwrap().instances.push(toplevel.downgrade());
+    }
+
+    fn init_instance(&self, toplevel: &ZwlrForeignToplevelHandleV1) {
+        let (title, app_id, state, outputs) = {
+            let inner = self.inner.0.lock().unwrap();
+            (
+                inner.title.clone(),
+                inner.app_id.clone(),
+                inner.state,
+                inner.outputs.clone(),
+            )
+        };
+
+        toplevel.title(title);
+        toplevel.app_id(app_id);
+        for output in &outputs {
+            send_output_enter(toplevel, output);
+        }
+        toplevel.state(state.to_array(toplevel.version()));
+        send_parent(toplevel, self.parent().as_ref());
+        toplevel.done();
+    }
+
+    fn remove_instance(&self, instance: &ZwlrForeignToplevelHandleV1) {
+        let mut inner = self.inner.0.lock().unwrap();
+        if let Some(pos) = inner.instances.iter().position(|i| i == instance) {
+            inner.instances.remove(pos);
+        }
+    }
+}
+
+fn send_output_enter(toplevel: &ZwlrForeignToplevelHandleV1, output: &Output) {
+    let Some(client) = toplevel.client() else {
+        return;
+    };
+    for wl_output in output.client_outputs(&client) {
+        toplevel.output_enter(&wl_output);
+    }
+}
+
+fn send_parent(toplevel: &ZwlrForeignToplevelHandleV1, parent: Option<&ForeignToplevelManagementHandle>) {
+    if toplevel.version() < 3 {
+        return;
+    }
+    let parent = parent.and_then(|parent| {
+        let client = toplevel.client()?;
+        parent.resources_for_client(&client).into_iter().next()
+    });
+    toplevel.parent(parent.as_ref());
+}
+
+/// State of the [ZwlrForeignToplevelManagerV1] global
+#[derive(Debug)]
+pub struct ForeignToplevelManagementState {
+    global: GlobalId,
+    toplevels: Vec<ForeignToplevelManagementWeakHandle>,
+    instances: Vec<ZwlrForeignToplevelManagerV1>,
+    dh: DisplayHandle,
+}
+
+impl ForeignToplevelManagementState {
+    /// Register new [ZwlrForeignToplevelManagerV1] global
+    pub fn new<D: ForeignToplevelManagementHandler>(dh: &DisplayHandle) -> Self {
+        Self::new_with_filter::<D>(dh, |_| true)
+    }
+
+    /// Register new [ZwlrForeignToplevelManagerV1] global with filter
+    ///
+    /// As the protocol allows to control other clients, access should usually be restricted.
+    pub fn new_with_filter<D: ForeignToplevelManagementHandler>(
+        dh: &DisplayHandle,
+        can_view: impl Fn(&Client) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        let global = dh.create_global::<D, ZwlrForeignToplevelManagerV1, _>(
+            MANAGER_VERSION,
+            ForeignToplevelManagementGlobalData {
+                filter: Box::new(can_view),
+            },
+        );
+
+        Self {
+            global,
+            toplevels: Vec::new(),
+            instances: Vec::new(),
+            dh: dh.clone(),
+        }
+    }
+
+    /// [ZwlrForeignToplevelManagerV1] GlobalId getter
+    pub fn global(&self) -> GlobalId {
+        self.global.clone()
+    }
+
+    /// Announce a new toplevel to all clients
+    ///
+    /// Further state like outputs or the states of the toplevel can be sent using the returned handle.
+    pub fn new_toplevel<D: ForeignToplevelManagementHandler>(
+        &mut self,
+        title: impl Into<String>,
+        app_id: impl Into<String>,
+    ) -> ForeignToplevelManagementHandle {
+        let handle = ForeignToplevelManagementHandle::new(title.into(), app_id.into());
+
+        for instance in &self.instances {
+            if let Some(toplevel) = create_instance::<D>(&self.dh, instance, &handle) {
+                handle.init_instance(&toplevel);
+            }
+        }
+
+        self.toplevels.push(handle.downgrade());
+
+        handle
+    }
+
+    /// Remove the toplevel, and send closed event if needed
+    ///
+    /// Alternatively, you can just call [ForeignToplevelManagementHandle::send_closed] and the handle
+    /// will be lazely cleaned up, either by [Self::cleanup_closed_handles], or during next global bind
+    pub fn remove_toplevel(&mut self, handle: &ForeignToplevelManagementHandle) {
+        handle.send_closed();
+        self.toplevels
+            .retain(|h| h.upgrade().is_some_and(|h| &h != handle));
+    }
+
+    /// Auto cleanup closed handles
+    ///
+    /// This is not needed if you already manually remove each handle with [Self::remove_toplevel]
+    pub fn cleanup_closed_handles(&mut self) {
+        self.toplevels
+            .retain(|handle| handle.upgrade().is_some_and(|handle| !handle.is_closed()));
+    }
+
+    /// Returns all toplevels, which were not closed
+    pub fn toplevels(&self) -> impl Iterator<Item = ForeignToplevelManagementHandle> + '_ {
+        self.toplevels
+            .iter()
+            .filter_map(|handle| handle.upgrade())
+            .filter(|handle| !handle.is_closed())
+    }
+}
+
+fn create_instance<D: ForeignToplevelManagementHandler>(
+    dh: &DisplayHandle,
+    manager: &ZwlrForeignToplevelManagerV1,
+    handle: &ForeignToplevelManagementHandle,
+) -> Option<ZwlrForeignToplevelHandleV1> {
+    let client = dh.get_client(manager.id()).ok()?;
+    let toplevel = client
+        .create_resource::<ZwlrForeignToplevelHandleV1, _, D>(dh, manager.version(), handle.clone())
+        .ok()?;
+    manager.toplevel(&toplevel);
+    handle.add_instance(&toplevel);
+    Some(toplevel)
+}
+
+/// Glabal data of [ZwlrForeignToplevelManagerV1]
+pub struct ForeignToplevelManagementGlobalData {
+    filter: Box<dyn Fn(&Client) -> bool + Send + Sync>,
+}
+
+impl std::fmt::Debug for ForeignToplevelManagementGlobalData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ForeignToplevelManagementGlobalData")
+            .finish_non_exhaustive()
+    }
+}
+
+impl<D: ForeignToplevelManagementHandler>
+    GlobalDispatch<ZwlrForeignToplevelManagerV1, ForeignToplevelManagementGlobalData, D>
+    for ForeignToplevelManagementState
+{
+    fn bind(
+        state: &mut D,
+        dh: &DisplayHandle,
+        _client: &Client,
+        resource: New<ZwlrForeignToplevelManagerV1>,
+        _global_data: &ForeignToplevelManagementGlobalData,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        let instance = data_init.init(resource, ());
+
+        let state = state.foreign_toplevel_management_state();
+        state.cleanup_closed_handles();
+
+        // create all instances first, so parents can be referenced
+        let toplevels = state
+            .toplevels()
+            .filter_map(|handle| {
+                create_instance::<D>(dh, &instance, &handle).map(|toplevel| (handle, toplevel))
+            })
+            .collect::<Vec<_>>();
+        for (handle, toplevel) in toplevels {
+            handle.init_instance(&toplevel);
+        }
+
+        state.instances.push(instance);
+    }
+
+    fn can_view(client: Client, global_data: &ForeignToplevelManagementGlobalData) -> bool {
+        (global_data.filter)(&client)
+    }
+}
+
+impl<D: ForeignToplevelManagementHandler> Dispatch<ZwlrForeignToplevelManagerV1, (), D>
+    for ForeignToplevelManagementState
+{
+    fn request(
+        state: &mut D,
+        client: &Client,
+        manager: &ZwlrForeignToplevelManagerV1,
+        request: zwlr_foreign_toplevel_manager_v1::Request,
+        data: &(),
+        _dh: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zwlr_foreign_toplevel_manager_v1::Request::Stop => {
+                Self::destroyed(state, client.id(), manager, data);
+                manager.finished();
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(state: &mut D, _client: ClientId, resource: &ZwlrForeignToplevelManagerV1, _data: &()) {
+        state
+            .foreign_toplevel_management_state()
+            .instances
+            .retain(|i| i != resource);
+    }
+}
+
+impl<D: ForeignToplevelManagementHandler>
+    Dispatch<ZwlrForeignToplevelHandleV1, ForeignToplevelManagementHandle, D>
+    for ForeignToplevelManagementState
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        resource: &ZwlrForeignToplevelHandleV1,
+        request: zwlr_foreign_toplevel_handle_v1::Request,
+        handle: &ForeignToplevelManagementHandle,
+        _dh: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        if let zwlr_foreign_toplevel_handle_v1::Request::SetRectangle { width, height, .. } = request {
+            if width < 0 || height < 0 {
+                resource.post_error(
+                    zwlr_foreign_toplevel_handle_v1::Error::InvalidRectangle,
+                    "width and height must be non-negative",
+                );
+                return;
+            }
+        }
+        if handle.is_closed() {
+            // requests of closed toplevels are ignored
+            return;
+        }
+
+        match request {
+            zwlr_foreign_toplevel_handle_v1::Request::SetMaximized => state.set_maximized(handle),
+            zwlr_foreign_toplevel_handle_v1::Request::UnsetMaximized => state.unset_maximized(handle),
+            zwlr_foreign_toplevel_handle_v1::Request::SetMinimized => state.set_minimized(handle),
+            zwlr_foreign_toplevel_handle_v1::Request::UnsetMinimized => state.unset_minimized(handle),
+            zwlr_foreign_toplevel_handle_v1::Request::Activate { seat } => state.activate(handle, seat),
+            zwlr_foreign_toplevel_handle_v1::Request::Close => state.close(handle),
+            zwlr_foreign_toplevel_handle_v1::Request::SetRectangle {
+                surface,
+                x,
+                y,
+                width,
+                height,
+            } => {
+                state.set_rectangle(
+                    handle,
+                    surface,
+                    Rectangle::new((x, y).into(), (width, height).into()),
+                );
+            }
+            zwlr_foreign_toplevel_handle_v1::Request::SetFullscreen { output } => {
+                let output = output.as_ref().and_then(Output::from_resource);
+                state.set_fullscreen(handle, output);
+            }
+            zwlr_foreign_toplevel_handle_v1::Request::UnsetFullscreen => state.unset_fullscreen(handle),
+            zwlr_foreign_toplevel_handle_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(
+        _state: &mut D,
+        _client: ClientId,
+        resource: &ZwlrForeignToplevelHandleV1,
+        handle: &ForeignToplevelManagementHandle,
+    ) {
+        handle.remove_instance(resource);
+    }
+}
+
+/// Macro to delegate implementation of the wlr foreign toplevel management to [ForeignToplevelManagementState].
+///
+/// You must also implement [ForeignToplevelManagementHandler] to use this.
+#[macro_export]
+macro_rules! delegate_foreign_toplevel_management {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        $crate::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols_wlr::foreign_toplevel::v1::server::zwlr_foreign_toplevel_manager_v1::ZwlrForeignToplevelManagerV1: $crate::wayland::foreign_toplevel_management::ForeignToplevelManagementGlobalData
+        ] => $crate::wayland::foreign_toplevel_management::ForeignToplevelManagementState);
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols_wlr::foreign_toplevel::v1::server::zwlr_foreign_toplevel_manager_v1::ZwlrForeignToplevelManagerV1: ()
+        ] => $crate::wayland::foreign_toplevel_management::ForeignToplevelManagementState);
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols_wlr::foreign_toplevel::v1::server::zwlr_foreign_toplevel_handle_v1::ZwlrForeignToplevelHandleV1: $crate::wayland::foreign_toplevel_management::ForeignToplevelManagementHandle
+        ] => $crate::wayland::foreign_toplevel_management::ForeignToplevelManagementState);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn state_array() {
+        let state = ForeignToplevelState {
+            maximized: true,
+            fullscreen: true,
+            ..Default::default()
+        };
+        let states = |version| {
+            state
+                .to_array(version)
+                .chunks(4)
+                .map(|chunk| u32::from_ne_bytes(chunk.try_into().unwrap()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(states(1), vec![0]);
+        assert_eq!(states(3), vec![0, 3]);
+    }
+}
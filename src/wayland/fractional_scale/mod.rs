@@ -59,6 +59,19 @@
 //!     });
 //! })
 //! ```
+//!
+//! ### Clients without fractional scale support
+//!
+//! Clients not supporting this protocol can only render at integer scales.
+//! [`send_scale_state`] takes care of both cases: Clients supporting this protocol receive the exact
+//! fractional scale, while all others are asked to render at an integer scale chosen by an
+//! [`IntegerScalePolicy`] via `wl_surface.preferred_buffer_scale`.
+//!
+//! [`IntegerScaleEmulation::for_surface`] returns the emulation for surfaces of such clients.
+//! [`IntegerScaleEmulation::viewport`] computes the source crop and destination size to scale their
+//! buffers to the fractional scale, cropped to whole physical pixels to avoid blurry edges.
+//! Input coordinates relative to the destination are mapped back to the surface coordinates of the
+//! client by [`IntegerScaleEmulation::to_surface`].
 
 use std::cell::RefCell;
 
@@ -69,7 +82,8 @@ use wayland_server::{
     backend::GlobalId, protocol::wl_surface, Dispatch, DisplayHandle, GlobalDispatch, Resource, Weak,
 };
 
-use super::compositor::{with_states, SurfaceData};
+use super::compositor::{send_surface_state, with_states, SurfaceData};
+use crate::utils::{Buffer, Logical, Point, Rectangle, Size, Transform};
 
 /// State of the wp_fractional_scale_manager_v1 Global
 #[derive(Debug)]
@@ -248,6 +262,152 @@ impl FractionalScaleState {
     pub fn preferred_scale(&self) -> Option<f64> {
         self.preferred_scale
     }
+
+    /// Returns `true` if the client created a fractional scale object for this surface
+    pub fn is_fractional_scale_aware(&self) -> bool {
+        self.fractional_scale.is_some()
+    }
+}
+
+/// Policy choosing the integer scale clients without fractional scale support render at
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum IntegerScalePolicy {
+    /// Render at the next larger integer scale, the buffers are downscaled
+    #[default]
+    Ceil,
+    /// Render at the nearest integer scale, the buffers are down- or upscaled
+    ///
+    /// Saves memory and bandwidth for scales slightly above an integer at the cost of sharpness.
+    Round,
+}
+
+impl IntegerScalePolicy {
+    /// Returns the integer scale for a given fractional scale
+    pub fn integer_scale(&self, scale: f64) -> i32 {
+        let scale = match self {
+            IntegerScalePolicy::Ceil => scale.ceil(),
+            IntegerScalePolicy::Round => scale.round(),
+        };
+        (scale as i32).max(1)
+    }
+}
+
+/// Emulation of a fractional scale for clients rendering at an integer scale
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IntegerScaleEmulation {
+    scale: f64,
+    buffer_scale: i32,
+}
+
+/// Viewport to display a buffer rendered at an integer scale at a fractional scale
+///
+/// Returned by [`IntegerScaleEmulation::viewport`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EmulatedViewport {
+    /// Source rectangle of the buffer
+    pub src: Rectangle<f64, Buffer>,
+    /// Destination size of the surface
+    pub dst: Size<i32, Logical>,
+}
+
+impl IntegerScaleEmulation {
+    /// Creates the emulation of a fractional scale using the given policy
+    pub fn new(scale: f64, policy: IntegerScalePolicy) -> Self {
+        IntegerScaleEmulation {
+            scale,
+            buffer_scale: policy.integer_scale(scale),
+        }
+    }
+
+    /// Returns the emulation for a surface, if necessary
+    ///
+    /// Returns `None` for surfaces of clients supporting fractional scale or if
+    /// the preferred scale of the surface is an integer.
+    pub fn for_surface(states: &SurfaceData, policy: IntegerScalePolicy) -> Option<Self> {
+        with_fractional_scale(states, |fractional_scale| {
+            if fractional_scale.is_fractional_scale_aware() {
+                return None;
+            }
+            let scale = fractional_scale.preferred_scale()?;
+            (scale.fract() != 0.0).then(|| IntegerScaleEmulation::new(scale, policy))
+        })
+    }
+
+    /// The emulated fractional scale
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+
+    /// The integer scale the client is asked to render at
+    pub fn buffer_scale(&self) -> i32 {
+        self.buffer_scale
+    }
+
+    /// Factor buffers are scaled by to match the fractional scale
+    pub fn factor(&self) -> f64 {
+        self.scale / self.buffer_scale as f64
+    }
+
+    /// Computes the viewport for a buffer of the given size
+    ///
+    /// The buffer is cropped, so the scaled buffer covers whole physical pixels.
+    pub fn viewport(&self, buffer_size: Size<i32, Buffer>) -> EmulatedViewport {
+        let factor = self.factor();
+        let physical = Size::<f64, Buffer>::from((
+            (buffer_size.w as f64 * factor).floor().max(1.0),
+            (buffer_size.h as f64 * factor).floor().max(1.0),
+        ));
+        let src = Rectangle::from_size(physical.downscale(factor));
+        let dst = physical.downscale(self.scale);
+        EmulatedViewport {
+            src,
+            dst: Size::from((dst.w.round().max(1.0) as i32, dst.h.round().max(1.0) as i32)),
+        }
+    }
+
+    /// Transforms a point relative to the destination of a viewport into surface coordinates of the client
+    pub fn to_surface(&self, viewport: &EmulatedViewport, point: Point<f64, Logical>) -> Point<f64, Logical> {
+        let src = viewport.src;
+        let dst = viewport.dst.to_f64();
+        let buffer_scale = self.buffer_scale as f64;
+        Point::from((
+            (src.loc.x + point.x * src.size.w / dst.w) / buffer_scale,
+            (src.loc.y + point.y * src.size.h / dst.h) / buffer_scale,
+        ))
+    }
+
+    /// Transforms a point in surface coordinates of the client into coordinates relative to the destination of a viewport
+    pub fn from_surface(
+        &self,
+        viewport: &EmulatedViewport,
+        point: Point<f64, Logical>,
+    ) -> Point<f64, Logical> {
+        let src = viewport.src;
+        let dst = viewport.dst.to_f64();
+        let buffer_scale = self.buffer_scale as f64;
+        Point::from((
+            (point.x * buffer_scale - src.loc.x) * dst.w / src.size.w,
+            (point.y * buffer_scale - src.loc.y) * dst.h / src.size.h,
+        ))
+    }
+}
+
+/// Send the preferred scale and transform for the given surface
+///
+/// The fractional `scale` is set as the preferred scale of the [`FractionalScaleState`], while
+/// the preferred buffer scale is set to the integer scale chosen by `policy` for clients without
+/// fractional scale support, see [`send_surface_state`].
+pub fn send_scale_state(
+    surface: &wl_surface::WlSurface,
+    states: &SurfaceData,
+    scale: f64,
+    transform: Transform,
+    policy: IntegerScalePolicy,
+) {
+    with_fractional_scale(states, |fractional_scale| {
+        fractional_scale.set_preferred_scale(scale);
+    });
+    send_surface_state(surface, states, policy.integer_scale(scale), transform);
 }
 
 /// Run a closure on the [`FractionalScaleState`] of a [`WlSurface`](wl_surface::WlSurface)
@@ -279,3 +439,59 @@ macro_rules! delegate_fractional_scale {
         ] => $crate::wayland::fractional_scale::FractionalScaleManagerState);
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integer_scale_policy() {
+        assert_eq!(IntegerScalePolicy::Ceil.integer_scale(1.25), 2);
+        assert_eq!(IntegerScalePolicy::Round.integer_scale(1.25), 1);
+        assert_eq!(IntegerScalePolicy::Round.integer_scale(1.75), 2);
+        assert_eq!(IntegerScalePolicy::Ceil.integer_scale(0.5), 1);
+        assert_eq!(IntegerScalePolicy::Ceil.integer_scale(2.0), 2);
+    }
+
+    #[test]
+    fn viewport_crops_to_physical_pixels() {
+        let emulation = IntegerScaleEmulation::new(1.5, IntegerScalePolicy::Ceil);
+        assert_eq!(emulation.buffer_scale(), 2);
+        assert_eq!(emulation.factor(), 0.75);
+
+        // 301 * 0.75 = 225.75, the last buffer column does not cover a whole physical pixel
+        let viewport = emulation.viewport((301, 200).into());
+        assert_eq!(viewport.src, Rectangle::from_size((300.0, 200.0).into()));
+        assert_eq!(viewport.dst, (150, 100).into());
+    }
+
+    #[test]
+    fn viewport_upscales_with_round_policy() {
+        let emulation = IntegerScaleEmulation::new(1.25, IntegerScalePolicy::Round);
+        assert_eq!(emulation.buffer_scale(), 1);
+
+        let viewport = emulation.viewport((100, 48).into());
+        assert_eq!(viewport.src, Rectangle::from_size((100.0, 48.0).into()));
+        assert_eq!(viewport.dst, (100, 48).into());
+
+        // 50 * 1.25 = 62.5 -> 62 physical pixels
+        let viewport = emulation.viewport((100, 50).into());
+        assert_eq!(viewport.src, Rectangle::from_size((100.0, 49.6).into()));
+    }
+
+    #[test]
+    fn input_transform_roundtrip() {
+        let emulation = IntegerScaleEmulation::new(1.5, IntegerScalePolicy::Ceil);
+        let viewport = emulation.viewport((301, 201).into());
+        // 201 * 0.75 = 150.75 -> 150 physical pixels -> 100 logical, 200 buffer pixels
+        assert_eq!(viewport.dst, (150, 100).into());
+
+        let point = Point::from((75.0, 50.0));
+        let surface = emulation.to_surface(&viewport, point);
+        assert_eq!(surface, Point::from((75.0, 50.0)));
+        assert_eq!(emulation.from_surface(&viewport, surface), point);
+
+        let corner = emulation.to_surface(&viewport, Point::from((150.0, 100.0)));
+        assert_eq!(corner, Point::from((150.0, 100.0)));
+    }
+}
@@ -0,0 +1,167 @@
+use wayland_server::{
+    backend::ClientId, Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, WEnum,
+};
+
+use super::{
+    protocol::{
+        frog_color_managed_surface::{self, FrogColorManagedSurface},
+        frog_color_management_factory_v1::{self, FrogColorManagementFactoryV1},
+    },
+    FrogColorManagementHandler, FrogColorManagementState, FrogColorManagementSurfaceCachedState,
+    FrogColorManagementSurfaceData, FrogColorManagementUserData, HdrMetadata,
+};
+use crate::wayland::compositor;
+
+impl<D> GlobalDispatch<FrogColorManagementFactoryV1, (), D> for FrogColorManagementState
+where
+    D: GlobalDispatch<FrogColorManagementFactoryV1, ()>,
+    D: Dispatch<FrogColorManagementFactoryV1, ()>,
+    D: Dispatch<FrogColorManagedSurface, FrogColorManagementUserData>,
+    D: FrogColorManagementHandler,
+    D: 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _: &DisplayHandle,
+        _: &Client,
+        resource: New<FrogColorManagementFactoryV1>,
+        _: &(),
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        data_init.init(resource, ());
+    }
+}
+
+impl<D> Dispatch<FrogColorManagementFactoryV1, (), D> for FrogColorManagementState
+where
+    D: Dispatch<FrogColorManagementFactoryV1, ()>,
+    D: Dispatch<FrogColorManagedSurface, FrogColorManagementUserData>,
+    D: FrogColorManagementHandler,
+    D: 'static,
+{
+    fn request(
+        state: &mut D,
+        _: &Client,
+        _: &FrogColorManagementFactoryV1,
+        request: frog_color_management_factory_v1::Request,
+        _data: &(),
+        _dh: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            frog_color_management_factory_v1::Request::GetColorManagedSurface { surface, callback } => {
+                // The protocol defines no error for duplicate objects, the latest one takes precedence.
+                let resource = data_init.init(callback, FrogColorManagementUserData::new(surface.clone()));
+                compositor::with_states(&surface, |states| {
+                    states
+                        .data_map
+                        .insert_if_missing_threadsafe(FrogColorManagementSurfaceData::default);
+                    *states
+                        .data_map
+                        .get::<FrogColorManagementSurfaceData>()
+                        .unwrap()
+                        .resource
+                        .lock()
+                        .unwrap() = Some(resource);
+                });
+
+                state.new_surface(&surface);
+            }
+            frog_color_management_factory_v1::Request::Destroy => {}
+        }
+    }
+}
+
+impl<D> Dispatch<FrogColorManagedSurface, FrogColorManagementUserData, D> for FrogColorManagementState
+where
+    D: Dispatch<FrogColorManagedSurface, FrogColorManagementUserData>,
+{
+    fn request(
+        _state: &mut D,
+        _: &Client,
+        resource: &FrogColorManagedSurface,
+        request: frog_color_managed_surface::Request,
+        data: &FrogColorManagementUserData,
+        _dh: &DisplayHandle,
+        _: &mut DataInit<'_, D>,
+    ) {
+        let Some(surface) = data.wl_surface() else {
+            return;
+        };
+
+        compositor::with_states(&surface, |states| {
+            let mut guard = states.cached_state.get::<FrogColorManagementSurfaceCachedState>();
+            let pending = guard.pending();
+
+            match request {
+                frog_color_managed_surface::Request::SetKnownTransferFunction { transfer_function } => {
+                    if let WEnum::Value(transfer_function) = transfer_function {
+                        pending.transfer_function = transfer_function;
+                    }
+                }
+                frog_color_managed_surface::Request::SetKnownContainerColorVolume { primaries } => {
+                    if let WEnum::Value(primaries) = primaries {
+                        pending.primaries = primaries;
+                    }
+                }
+                frog_color_managed_surface::Request::SetRenderIntent { render_intent } => {
+                    if let WEnum::Value(render_intent) = render_intent {
+                        pending.render_intent = render_intent;
+                    }
+                }
+                frog_color_managed_surface::Request::SetHdrMetadata {
+                    mastering_display_primary_red_x,
+                    mastering_display_primary_red_y,
+                    mastering_display_primary_green_x,
+                    mastering_display_primary_green_y,
+                    mastering_display_primary_blue_x,
+                    mastering_display_primary_blue_y,
+                    mastering_white_point_x,
+                    mastering_white_point_y,
+                    max_display_mastering_luminance,
+                    min_display_mastering_luminance,
+                    max_cll,
+                    max_fall,
+                } => {
+                    pending.hdr_metadata = Some(HdrMetadata {
+                        mastering_display_primaries: [
+                            (mastering_display_primary_red_x, mastering_display_primary_red_y),
+                            (
+                                mastering_display_primary_green_x,
+                                mastering_display_primary_green_y,
+                            ),
+                            (mastering_display_primary_blue_x, mastering_display_primary_blue_y),
+                        ],
+                        mastering_white_point: (mastering_white_point_x, mastering_white_point_y),
+                        max_display_mastering_luminance,
+                        min_display_mastering_luminance,
+                        max_cll,
+                        max_fall,
+                    });
+                }
+                // Destroying the object resets the color state of the surface,
+                // including double buffering semantics.
+                frog_color_managed_surface::Request::Destroy => {
+                    *pending = Default::default();
+
+                    let data = states.data_map.get::<FrogColorManagementSurfaceData>().unwrap();
+                    let mut current = data.resource.lock().unwrap();
+                    if current.as_ref() == Some(resource) {
+                        *current = None;
+                    }
+                }
+            }
+        });
+    }
+
+    fn destroyed(
+        _state: &mut D,
+        _client: ClientId,
+        _object: &FrogColorManagedSurface,
+        _data: &FrogColorManagementUserData,
+    ) {
+        // Nothing to do here, graceful Destroy is already handled with double buffering
+        // and in case of client close WlSurface destroyed handler will clean up the data anyway,
+        // so there is no point in queuing new update
+    }
+}
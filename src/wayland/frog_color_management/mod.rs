@@ -0,0 +1,331 @@
+//! Implementation of the frog_color_management_v1 protocol
+//!
+//! This is an intentionally limited color management protocol used by gamescope and
+//! Proton titles to output HDR content, until the upstream color management protocol
+//! is widely supported. Clients declare the transfer function, container color volume
+//! (primaries), render intent and HDR metadata of their surfaces, the compositor can
+//! inform them about the metadata it prefers using [`send_preferred_metadata`].
+//!
+//! The declared values are double-buffered and available from the
+//! [`FrogColorManagementSurfaceCachedState`] of a surface. Interpreting them is up to the
+//! compositor.
+//!
+//! This module is only available with the `frog_color_management` feature.
+//!
+//! ### Example
+//!
+//! ```no_run
+//! # extern crate wayland_server;
+//! #
+//! use wayland_server::{protocol::wl_surface::WlSurface, DisplayHandle};
+//! use smithay::{
+//!     delegate_frog_color_management, delegate_compositor,
+//!     wayland::compositor::{self, CompositorState, CompositorClientState, CompositorHandler},
+//!     wayland::frog_color_management::{
+//!         FrogColorManagementHandler, FrogColorManagementState, FrogColorManagementSurfaceCachedState,
+//!     },
+//! };
+//!
+//! pub struct State {
+//!     compositor_state: CompositorState,
+//! };
+//! struct ClientState { compositor_state: CompositorClientState }
+//! impl wayland_server::backend::ClientData for ClientState {}
+//!
+//! delegate_frog_color_management!(State);
+//! delegate_compositor!(State);
+//!
+//! impl FrogColorManagementHandler for State {
+//!     fn new_surface(&mut self, surface: &WlSurface) {
+//!         // send the preferred metadata of the output the surface is on
+//!     }
+//! }
+//!
+//! impl CompositorHandler for State {
+//!    fn compositor_state(&mut self) -> &mut CompositorState {
+//!        &mut self.compositor_state
+//!    }
+//!
+//!    fn client_compositor_state<'a>(&self, client: &'a wayland_server::Client) -> &'a CompositorClientState {
+//!        &client.get_data::<ClientState>().unwrap().compositor_state
+//!    }
+//!
+//!    fn commit(&mut self, surface: &WlSurface) {
+//!        compositor::with_states(&surface, |states| {
+//!            let mut guard = states.cached_state.get::<FrogColorManagementSurfaceCachedState>();
+//!            let current = guard.current();
+//!            dbg!(current.transfer_function(), current.primaries());
+//!        });
+//!    }
+//! }
+//!
+//! let mut display = wayland_server::Display::<State>::new().unwrap();
+//!
+//! let compositor_state = CompositorState::new::<State>(&display.handle());
+//! FrogColorManagementState::new::<State>(&display.handle());
+//!
+//! let state = State {
+//!     compositor_state,
+//! };
+//! ```
+
+use std::sync::Mutex;
+
+use wayland_server::{
+    backend::GlobalId, protocol::wl_surface::WlSurface, Dispatch, DisplayHandle, GlobalDispatch, Resource,
+    Weak,
+};
+
+use self::protocol::{
+    frog_color_managed_surface::{FrogColorManagedSurface, Primaries, RenderIntent, TransferFunction},
+    frog_color_management_factory_v1::FrogColorManagementFactoryV1,
+};
+use super::compositor::{self, Cacheable};
+
+mod dispatch;
+
+/// Generated bindings of the frog_color_management_v1 protocol
+pub mod protocol {
+    pub use self::generated::server::*;
+
+    mod generated {
+        #![allow(dead_code, non_camel_case_types, unused_unsafe, unused_variables)]
+        #![allow(non_upper_case_globals, non_snake_case, unused_imports)]
+        #![allow(missing_docs, clippy::all)]
+
+        pub mod server {
+            use wayland_server;
+            use wayland_server::protocol::*;
+
+            pub mod __interfaces {
+                use wayland_server::protocol::__interfaces::*;
+                wayland_scanner::generate_interfaces!(
+                    "./src/wayland/frog_color_management/frog-color-management-v1.xml"
+                );
+            }
+            use self::__interfaces::*;
+
+            wayland_scanner::generate_server_code!(
+                "./src/wayland/frog_color_management/frog-color-management-v1.xml"
+            );
+        }
+    }
+}
+
+/// HDR metadata of a surface, coded as per CTA-861-G
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HdrMetadata {
+    /// Chromaticity coordinates of the red, green and blue primaries of the mastering display
+    pub mastering_display_primaries: [(u32, u32); 3],
+    /// Chromaticity coordinates of the white point of the mastering display
+    pub mastering_white_point: (u32, u32),
+    /// Maximum luminance of the mastering display in cd/m²
+    pub max_display_mastering_luminance: u32,
+    /// Minimum luminance of the mastering display in 0.0001 cd/m²
+    pub min_display_mastering_luminance: u32,
+    /// Maximum content light level in cd/m²
+    pub max_cll: u32,
+    /// Maximum frame-average light level in cd/m²
+    pub max_fall: u32,
+}
+
+/// Metadata preferred by the compositor for a surface, see [`send_preferred_metadata`]
+///
+/// Chromaticity coordinates are coded as per CTA-861-G.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PreferredMetadata {
+    /// Transfer function of the output the surface is targeting
+    pub transfer_function: TransferFunction,
+    /// Chromaticity coordinates of the red, green and blue primaries of the output
+    pub primaries: [(u32, u32); 3],
+    /// Chromaticity coordinates of the white point of the output
+    pub white_point: (u32, u32),
+    /// Maximum luminance the output can display in small areas in cd/m²
+    pub max_luminance: u32,
+    /// Minimum luminance of the output in 0.0001 cd/m²
+    pub min_luminance: u32,
+    /// Maximum luminance the output can sustain for the full frame in cd/m²
+    pub max_full_frame_luminance: u32,
+}
+
+/// Data associated with WlSurface
+/// Represents the client pending state
+///
+/// ```no_run
+/// use smithay::wayland::compositor;
+/// use smithay::wayland::frog_color_management::FrogColorManagementSurfaceCachedState;
+///
+/// # let wl_surface = todo!();
+/// compositor::with_states(&wl_surface, |states| {
+///     let mut guard = states.cached_state.get::<FrogColorManagementSurfaceCachedState>();
+///     let current = guard.current();
+///     dbg!(current.hdr_metadata());
+/// });
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct FrogColorManagementSurfaceCachedState {
+    transfer_function: TransferFunction,
+    primaries: Primaries,
+    render_intent: RenderIntent,
+    hdr_metadata: Option<HdrMetadata>,
+}
+
+impl FrogColorManagementSurfaceCachedState {
+    /// Transfer function of the buffer contents
+    pub fn transfer_function(&self) -> TransferFunction {
+        self.transfer_function
+    }
+
+    /// Primaries of the container color volume of the buffer contents
+    pub fn primaries(&self) -> Primaries {
+        self.primaries
+    }
+
+    /// Render intent requested for the buffer contents
+    pub fn render_intent(&self) -> RenderIntent {
+        self.render_intent
+    }
+
+    /// HDR metadata of the buffer contents, if set
+    pub fn hdr_metadata(&self) -> Option<&HdrMetadata> {
+        self.hdr_metadata.as_ref()
+    }
+}
+
+impl Default for FrogColorManagementSurfaceCachedState {
+    fn default() -> Self {
+        Self {
+            transfer_function: TransferFunction::Undefined,
+            primaries: Primaries::Undefined,
+            render_intent: RenderIntent::Perceptual,
+            hdr_metadata: None,
+        }
+    }
+}
+
+impl Cacheable for FrogColorManagementSurfaceCachedState {
+    fn commit(&mut self, _dh: &DisplayHandle) -> Self {
+        *self
+    }
+
+    fn merge_into(self, into: &mut Self, _dh: &DisplayHandle) {
+        *into = self;
+    }
+}
+
+/// Sends the metadata preferred by the compositor to the client of a surface
+///
+/// Does nothing if the client did not create a color managed surface for it.
+pub fn send_preferred_metadata(surface: &WlSurface, metadata: &PreferredMetadata) {
+    let Some(resource) = compositor::with_states(surface, |states| {
+        states
+            .data_map
+            .get::<FrogColorManagementSurfaceData>()
+            .and_then(|data| data.resource.lock().unwrap().clone())
+    }) else {
+        return;
+    };
+
+    let [(red_x, red_y), (green_x, green_y), (blue_x, blue_y)] = metadata.primaries;
+    let (white_x, white_y) = metadata.white_point;
+    resource.preferred_metadata(
+        metadata.transfer_function,
+        red_x,
+        red_y,
+        green_x,
+        green_y,
+        blue_x,
+        blue_y,
+        white_x,
+        white_y,
+        metadata.max_luminance,
+        metadata.min_luminance,
+        metadata.max_full_frame_luminance,
+    );
+}
+
+#[derive(Debug, Default)]
+struct FrogColorManagementSurfaceData {
+    resource: Mutex<Option<FrogColorManagedSurface>>,
+}
+
+/// User data of `FrogColorManagedSurface` object
+#[derive(Debug)]
+pub struct FrogColorManagementUserData(Mutex<Weak<WlSurface>>);
+
+impl FrogColorManagementUserData {
+    fn new(surface: WlSurface) -> Self {
+        Self(Mutex::new(surface.downgrade()))
+    }
+
+    #[inline]
+    fn wl_surface(&self) -> Option<WlSurface> {
+        self.0.lock().unwrap().upgrade().ok()
+    }
+}
+
+/// Handler for the frog color management protocol
+pub trait FrogColorManagementHandler {
+    /// A client created a color managed surface for `surface`
+    ///
+    /// This is a good place to send the initial [`PreferredMetadata`].
+    fn new_surface(&mut self, surface: &WlSurface) {
+        let _ = surface;
+    }
+}
+
+/// Delegate type for [FrogColorManagementFactoryV1] global.
+#[derive(Debug)]
+pub struct FrogColorManagementState {
+    global: GlobalId,
+}
+
+impl FrogColorManagementState {
+    /// Register new [FrogColorManagementFactoryV1] global
+    pub fn new<D>(display: &DisplayHandle) -> FrogColorManagementState
+    where
+        D: GlobalDispatch<FrogColorManagementFactoryV1, ()>
+            + Dispatch<FrogColorManagementFactoryV1, ()>
+            + Dispatch<FrogColorManagedSurface, FrogColorManagementUserData>
+            + FrogColorManagementHandler
+            + 'static,
+    {
+        let global = display.create_global::<D, FrogColorManagementFactoryV1, _>(1, ());
+
+        FrogColorManagementState { global }
+    }
+
+    /// Returns the FrogColorManagementFactoryV1 global id
+    pub fn global(&self) -> GlobalId {
+        self.global.clone()
+    }
+}
+
+/// Macro to delegate implementation of the frog color management protocol
+#[macro_export]
+macro_rules! delegate_frog_color_management {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        type __FrogColorManagementFactoryV1 =
+            $crate::wayland::frog_color_management::protocol::frog_color_management_factory_v1::FrogColorManagementFactoryV1;
+        type __FrogColorManagedSurface =
+            $crate::wayland::frog_color_management::protocol::frog_color_managed_surface::FrogColorManagedSurface;
+
+        $crate::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty:
+            [
+                __FrogColorManagementFactoryV1: ()
+            ] => $crate::wayland::frog_color_management::FrogColorManagementState
+        );
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty:
+            [
+                __FrogColorManagementFactoryV1: ()
+            ] => $crate::wayland::frog_color_management::FrogColorManagementState
+        );
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty:
+            [
+                __FrogColorManagedSurface: $crate::wayland::frog_color_management::FrogColorManagementUserData
+            ] => $crate::wayland::frog_color_management::FrogColorManagementState
+        );
+    };
+}
@@ -0,0 +1,405 @@
+//! Utilities for handling the `wlr-gamma-control` protocol
+//!
+//! This protocol allows privileged clients, like redshift or gammastep, to set the gamma ramps
+//! of outputs. Only one client can control the gamma of an output at a time.
+//!
+//! When a client asks for control over an output, [`GammaControlHandler::gamma_size`] is used
+//! to tell the client how many entries its ramps need to have. Clients sending smaller ramps are
+//! killed with a protocol error, so a [`GammaRamp`] passed to [`GammaControlHandler::set_gamma`]
+//! always has the size returned for the output.
+//!
+//! With the DRM backend, use [`DrmSurface::gamma_size`](crate::backend::drm::DrmSurface::gamma_size)
+//! as the size of the gamma LUT of the crtc driving an output and apply ramps using
+//! [`DrmSurface::set_gamma`](crate::backend::drm::DrmSurface::set_gamma).
+//!
+//! ## How to use it
+//!
+//! ```
+//! use smithay::delegate_gamma_control;
+//! use smithay::output::Output;
+//! use smithay::wayland::gamma_control::{GammaControlHandler, GammaControlState, GammaRamp};
+//!
+//! # struct State { gamma_control_state: GammaControlState }
+//! # let mut display = wayland_server::Display::<State>::new().unwrap();
+//! // Create the gamma control state
+//! let gamma_control_state = GammaControlState::new::<State, _>(&display.handle(), |_client| true);
+//!
+//! // Insert the GammaControlState into your state.
+//!
+//! // Implement the necessary trait.
+//! impl GammaControlHandler for State {
+//!     fn gamma_control_state(&mut self) -> &mut GammaControlState {
+//!         &mut self.gamma_control_state
+//!     }
+//!
+//!     fn gamma_size(&mut self, output: &Output) -> Option<u32> {
+//!         // return the size of the gamma LUT of the output, if it has one
+//! #       None
+//!     }
+//!
+//!     fn set_gamma(&mut self, output: &Output, ramp: Option<GammaRamp>) -> bool {
+//!         // apply the gamma ramp or restore the default one
+//!         true
+//!     }
+//! }
+//! delegate_gamma_control!(State);
+//! ```
+
+use std::{fs::File, io, os::unix::fs::FileExt, sync::Mutex};
+
+use wayland_protocols_wlr::gamma_control::v1::server::{
+    zwlr_gamma_control_manager_v1::{self, ZwlrGammaControlManagerV1},
+    zwlr_gamma_control_v1::{self, ZwlrGammaControlV1},
+};
+use wayland_server::{
+    backend::{ClientId, GlobalId},
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource,
+};
+
+use crate::output::{Output, WeakOutput};
+
+const MANAGER_VERSION: u32 = 1;
+
+/// Gamma ramps of the red, green and blue channel of an output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GammaRamp {
+    // red, green and blue ramps after each other
+    table: Vec<u16>,
+}
+
+impl GammaRamp {
+    /// Creates a linear gamma ramp of the given size, which does not alter colors
+    pub fn linear(size: u32) -> GammaRamp {
+        let size = size as usize;
+        let ramp = (0..size).map(|i| {
+            if size > 1 {
+                (i * u16::MAX as usize / (size - 1)) as u16
+            } else {
+                u16::MAX
+            }
+        });
+        GammaRamp {
+            table: ramp.clone().chain(ramp.clone()).chain(ramp).collect(),
+        }
+    }
+
+    fn from_bytes(bytes: &[u8]) -> GammaRamp {
+        let table = bytes
+            .chunks_exact(2)
+            .map(|value| u16::from_ne_bytes([value[0], value[1]]))
+            .collect();
+        GammaRamp { table }
+    }
+
+    /// Returns the number of entries of every ramp
+    pub fn size(&self) -> u32 {
+        (self.table.len() / 3) as u32
+    }
+
+    /// Returns the ramp of the red channel
+    pub fn red(&self) -> &[u16] {
+        &self.table[..self.size() as usize]
+    }
+
+    /// Returns the ramp of the green channel
+    pub fn green(&self) -> &[u16] {
+        let size = self.size() as usize;
+        &self.table[size..2 * size]
+    }
+
+    /// Returns the ramp of the blue channel
+    pub fn blue(&self) -> &[u16] {
+        let size = self.size() as usize;
+        &self.table[2 * size..]
+    }
+}
+
+/// Handler trait for wlr-gamma-control
+pub trait GammaControlHandler {
+    /// [`GammaControlState`] getter
+    fn gamma_control_state(&mut self) -> &mut GammaControlState;
+
+    /// Returns the number of entries of the gamma ramps of an output
+    ///
+    /// Return `None`, if the gamma of the output cannot be changed.
+    fn gamma_size(&mut self, output: &Output) -> Option<u32>;
+
+    /// A client set the gamma ramps of an output
+    ///
+    /// `None` requests to restore the default gamma ramps, because the client stopped controlling
+    /// the output. Return `false`, if the ramps cannot be applied, which ends the control of the client.
+    fn set_gamma(&mut self, output: &Output, ramp: Option<GammaRamp>) -> bool;
+}
+
+/// State of the [`ZwlrGammaControlManagerV1`] global
+#[derive(Debug)]
+pub struct GammaControlState {
+    global: GlobalId,
+    controls: Vec<ZwlrGammaControlV1>,
+}
+
+impl GammaControlState {
+    /// Create a new [`ZwlrGammaControlManagerV1`] global
+    ///
+    /// The filter decides which clients are allowed to control the gamma of outputs.
+    pub fn new<D, F>(display: &DisplayHandle, filter: F) -> Self
+    where
+        D: GlobalDispatch<ZwlrGammaControlManagerV1, GammaControlManagerGlobalData>,
+        D: Dispatch<ZwlrGammaControlManagerV1, ()>,
+        D: Dispatch<ZwlrGammaControlV1, GammaControlData>,
+        D: GammaControlHandler,
+        D: 'static,
+        F: for<'c> Fn(&'c Client) -> bool + Send + Sync + 'static,
+    {
+        let data = GammaControlManagerGlobalData {
+            filter: Box::new(filter),
+        };
+        let global = display.create_global::<D, ZwlrGammaControlManagerV1, _>(MANAGER_VERSION, data);
+
+        GammaControlState {
+            global,
+            controls: Vec::new(),
+        }
+    }
+
+    /// Returns the id of the [`ZwlrGammaControlManagerV1`] global
+    pub fn global(&self) -> GlobalId {
+        self.global.clone()
+    }
+
+    /// Stop controlling the gamma of an output, e.g. because it was disconnected or its size changed
+    ///
+    /// The controlling client is notified, that its control is no longer valid.
+    pub fn remove_output(&mut self, output: &Output) {
+        let controls = self.controls_for(output).collect::<Vec<_>>();
+        for control in controls {
+            fail(control);
+        }
+        self.controls.retain(|control| control_output(control).is_some());
+    }
+
+    fn controls_for<'a>(&'a self, output: &'a Output) -> impl Iterator<Item = &'a ZwlrGammaControlV1> + 'a {
+        self.controls
+            .iter()
+            .filter(move |control| control_output(control).is_some_and(|o| o == *output))
+    }
+}
+
+fn control_output(control: &ZwlrGammaControlV1) -> Option<WeakOutput> {
+    control
+        .data::<GammaControlData>()
+        .and_then(|data| data.output.lock().unwrap().clone())
+}
+
+// Sends failed and makes the control inert
+fn fail(control: &ZwlrGammaControlV1) {
+    if let Some(data) = control.data::<GammaControlData>() {
+        data.output.lock().unwrap().take();
+    }
+    control.failed();
+}
+
+fn read_ramp(fd: File, size: u32) -> io::Result<GammaRamp> {
+    let mut bytes = vec![0u8; size as usize * 3 * 2];
+    fd.read_exact_at(&mut bytes, 0)?;
+    Ok(GammaRamp::from_bytes(&bytes))
+}
+
+#[allow(missing_debug_implementations)]
+#[doc(hidden)]
+pub struct GammaControlManagerGlobalData {
+    filter: Box<dyn for<'c> Fn(&'c Client) -> bool + Send + Sync>,
+}
+
+/// User data of the [`ZwlrGammaControlV1`] resource
+#[derive(Debug)]
+pub struct GammaControlData {
+    // `None` once the control failed
+    output: Mutex<Option<WeakOutput>>,
+    size: u32,
+}
+
+impl<D> GlobalDispatch<ZwlrGammaControlManagerV1, GammaControlManagerGlobalData, D> for GammaControlState
+where
+    D: GlobalDispatch<ZwlrGammaControlManagerV1, GammaControlManagerGlobalData>,
+    D: Dispatch<ZwlrGammaControlManagerV1, ()>,
+    D: Dispatch<ZwlrGammaControlV1, GammaControlData>,
+    D: GammaControlHandler,
+    D: 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _dh: &DisplayHandle,
+        _client: &Client,
+        resource: New<ZwlrGammaControlManagerV1>,
+        _global_data: &GammaControlManagerGlobalData,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        data_init.init(resource, ());
+    }
+
+    fn can_view(client: Client, global_data: &GammaControlManagerGlobalData) -> bool {
+        (global_data.filter)(&client)
+    }
+}
+
+impl<D> Dispatch<ZwlrGammaControlManagerV1, (), D> for GammaControlState
+where
+    D: Dispatch<ZwlrGammaControlManagerV1, ()>,
+    D: Dispatch<ZwlrGammaControlV1, GammaControlData>,
+    D: GammaControlHandler,
+    D: 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        _resource: &ZwlrGammaControlManagerV1,
+        request: zwlr_gamma_control_manager_v1::Request,
+        _data: &(),
+        _dh: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zwlr_gamma_control_manager_v1::Request::GetGammaControl { id, output } => {
+                let output = Output::from_resource(&output);
+                let size = output
+                    .as_ref()
+                    .and_then(|output| state.gamma_size(output))
+                    .unwrap_or(0);
+                let control = data_init.init(
+                    id,
+                    GammaControlData {
+                        output: Mutex::new(output.as_ref().map(Output::downgrade)),
+                        size,
+                    },
+                );
+
+                let state = state.gamma_control_state();
+                let Some(output) = output.filter(|_| size > 0) else {
+                    fail(&control);
+                    return;
+                };
+                // another client already controls the output
+                if state.controls_for(&output).next().is_some() {
+                    fail(&control);
+                    return;
+                }
+
+                control.gamma_size(size);
+                state.controls.push(control);
+            }
+            zwlr_gamma_control_manager_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<ZwlrGammaControlV1, GammaControlData, D> for GammaControlState
+where
+    D: Dispatch<ZwlrGammaControlV1, GammaControlData>,
+    D: GammaControlHandler,
+    D: 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        resource: &ZwlrGammaControlV1,
+        request: zwlr_gamma_control_v1::Request,
+        data: &GammaControlData,
+        _dh: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zwlr_gamma_control_v1::Request::SetGamma { fd } => {
+                // requests of failed controls are ignored
+                let output = data.output.lock().unwrap().as_ref().and_then(WeakOutput::upgrade);
+                let Some(output) = output else {
+                    return;
+                };
+
+                let ramp = match read_ramp(File::from(fd), data.size) {
+                    Ok(ramp) => ramp,
+                    Err(err) => {
+                        resource.post_error(
+                            zwlr_gamma_control_v1::Error::InvalidGamma,
+                            format!("failed to read gamma ramps of size {}: {}", data.size, err),
+                        );
+                        return;
+                    }
+                };
+
+                if !state.set_gamma(&output, Some(ramp)) {
+                    fail(resource);
+                    state
+                        .gamma_control_state()
+                        .controls
+                        .retain(|control| control != resource);
+                    state.set_gamma(&output, None);
+                }
+            }
+            zwlr_gamma_control_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(state: &mut D, _client: ClientId, resource: &ZwlrGammaControlV1, data: &GammaControlData) {
+        state
+            .gamma_control_state()
+            .controls
+            .retain(|control| control != resource);
+
+        // restore the gamma of outputs, which are still controlled by the client
+        let output = data
+            .output
+            .lock()
+            .unwrap()
+            .take()
+            .and_then(|output| output.upgrade());
+        if let Some(output) = output {
+            state.set_gamma(&output, None);
+        }
+    }
+}
+
+/// Macro to delegate implementation of wlr-gamma-control to [`GammaControlState`].
+///
+/// You must also implement [`GammaControlHandler`] to use this.
+#[macro_export]
+macro_rules! delegate_gamma_control {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        $crate::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols_wlr::gamma_control::v1::server::zwlr_gamma_control_manager_v1::ZwlrGammaControlManagerV1: $crate::wayland::gamma_control::GammaControlManagerGlobalData
+        ] => $crate::wayland::gamma_control::GammaControlState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols_wlr::gamma_control::v1::server::zwlr_gamma_control_manager_v1::ZwlrGammaControlManagerV1: ()
+        ] => $crate::wayland::gamma_control::GammaControlState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols_wlr::gamma_control::v1::server::zwlr_gamma_control_v1::ZwlrGammaControlV1: $crate::wayland::gamma_control::GammaControlData
+        ] => $crate::wayland::gamma_control::GammaControlState);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ramp_channels() {
+        let values: [u16; 6] = [1, 2, 3, 4, 5, 6];
+        let bytes = values
+            .iter()
+            .flat_map(|value| value.to_ne_bytes())
+            .collect::<Vec<_>>();
+        let ramp = GammaRamp::from_bytes(&bytes);
+        assert_eq!(ramp.size(), 2);
+        assert_eq!(ramp.red(), &[1, 2]);
+        assert_eq!(ramp.green(), &[3, 4]);
+        assert_eq!(ramp.blue(), &[5, 6]);
+
+        let linear = GammaRamp::linear(3);
+        assert_eq!(linear.red(), &[0, 32767, u16::MAX]);
+        assert_eq!(linear.red(), linear.blue());
+    }
+}
@@ -0,0 +1,286 @@
+//! Utilities for handling the `ext-image-capture-source` protocol
+//!
+//! This protocol lets clients create [`ImageCaptureSource`]s, which are consumed by capture protocols
+//! like [`ext-image-copy-capture`](crate::wayland::image_copy_capture). [`OutputCaptureSourceState`]
+//! creates sources for [`Output`]s, [`ToplevelCaptureSourceState`] for toplevels advertised through the
+//! [foreign toplevel list](crate::wayland::foreign_toplevel_list). With the `desktop` feature, toplevel
+//! sources can be resolved to their window using `Window::is_capture_source`.
+//!
+//! Neither global requires a handler, they only need to be delegated:
+//!
+//! ```
+//! use smithay::delegate_image_capture_source;
+//! use smithay::wayland::image_capture_source::{OutputCaptureSourceState, ToplevelCaptureSourceState};
+//!
+//! # struct State;
+//! # let mut display = wayland_server::Display::<State>::new().unwrap();
+//! let output_source_state = OutputCaptureSourceState::new::<State, _>(&display.handle(), |_client| true);
+//! let toplevel_source_state = ToplevelCaptureSourceState::new::<State, _>(&display.handle(), |_client| true);
+//!
+//! delegate_image_capture_source!(State);
+//! ```
+
+use wayland_protocols::ext::image_capture_source::v1::server::{
+    ext_foreign_toplevel_image_capture_source_manager_v1::{
+        self, ExtForeignToplevelImageCaptureSourceManagerV1,
+    },
+    ext_image_capture_source_v1::ExtImageCaptureSourceV1,
+    ext_output_image_capture_source_manager_v1::{self, ExtOutputImageCaptureSourceManagerV1},
+};
+use wayland_server::{
+    backend::GlobalId, Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource,
+};
+
+use crate::{output::Output, wayland::foreign_toplevel_list::ForeignToplevelHandle};
+
+/// A source of images, that can be captured
+#[derive(Debug, Clone)]
+pub enum ImageCaptureSource {
+    /// The contents of an output
+    Output(Output),
+    /// The contents of a toplevel, advertised through the foreign toplevel list
+    Toplevel(ForeignToplevelHandle),
+}
+
+impl ImageCaptureSource {
+    /// Retrieve the source from an [`ExtImageCaptureSourceV1`]
+    ///
+    /// Returns `None` if the source is inert, e.g. because it was created for a destroyed output.
+    pub fn from_resource(resource: &ExtImageCaptureSourceV1) -> Option<Self> {
+        resource
+            .data::<ImageCaptureSourceData>()
+            .and_then(|data| data.source.clone())
+    }
+}
+
+/// User data of the [`ExtImageCaptureSourceV1`] resource
+#[derive(Debug)]
+pub struct ImageCaptureSourceData {
+    source: Option<ImageCaptureSource>,
+}
+
+#[allow(missing_debug_implementations)]
+#[doc(hidden)]
+pub struct CaptureSourceGlobalData {
+    filter: Box<dyn for<'c> Fn(&'c Client) -> bool + Send + Sync>,
+}
+
+/// State of the [`ExtOutputImageCaptureSourceManagerV1`] global
+#[derive(Debug)]
+pub struct OutputCaptureSourceState {
+    global: GlobalId,
+}
+
+impl OutputCaptureSourceState {
+    /// Create a new [`ExtOutputImageCaptureSourceManagerV1`] global
+    ///
+    /// The filter decides which clients are allowed to create capture sources for outputs.
+    pub fn new<D, F>(display: &DisplayHandle, filter: F) -> Self
+    where
+        D: GlobalDispatch<ExtOutputImageCaptureSourceManagerV1, CaptureSourceGlobalData>,
+        D: Dispatch<ExtOutputImageCaptureSourceManagerV1, ()>,
+        D: Dispatch<ExtImageCaptureSourceV1, ImageCaptureSourceData>,
+        D: 'static,
+        F: for<'c> Fn(&'c Client) -> bool + Send + Sync + 'static,
+    {
+        let data = CaptureSourceGlobalData {
+            filter: Box::new(filter),
+        };
+        let global = display.create_global::<D, ExtOutputImageCaptureSourceManagerV1, _>(1, data);
+
+        OutputCaptureSourceState { global }
+    }
+
+    /// Returns the id of the [`ExtOutputImageCaptureSourceManagerV1`] global
+    pub fn global(&self) -> GlobalId {
+        self.global.clone()
+    }
+}
+
+/// State of the [`ExtForeignToplevelImageCaptureSourceManagerV1`] global
+#[derive(Debug)]
+pub struct ToplevelCaptureSourceState {
+    global: GlobalId,
+}
+
+impl ToplevelCaptureSourceState {
+    /// Create a new [`ExtForeignToplevelImageCaptureSourceManagerV1`] global
+    ///
+    /// Toplevels are referenced by their handles of the
+    /// [foreign toplevel list](crate::wayland::foreign_toplevel_list).
+    /// The filter decides which clients are allowed to create capture sources for toplevels.
+    pub fn new<D, F>(display: &DisplayHandle, filter: F) -> Self
+    where
+        D: GlobalDispatch<ExtForeignToplevelImageCaptureSourceManagerV1, CaptureSourceGlobalData>,
+        D: Dispatch<ExtForeignToplevelImageCaptureSourceManagerV1, ()>,
+        D: Dispatch<ExtImageCaptureSourceV1, ImageCaptureSourceData>,
+        D: 'static,
+        F: for<'c> Fn(&'c Client) -> bool + Send + Sync + 'static,
+    {
+        let data = CaptureSourceGlobalData {
+            filter: Box::new(filter),
+        };
+        let global = display.create_global::<D, ExtForeignToplevelImageCaptureSourceManagerV1, _>(1, data);
+
+        ToplevelCaptureSourceState { global }
+    }
+
+    /// Returns the id of the [`ExtForeignToplevelImageCaptureSourceManagerV1`] global
+    pub fn global(&self) -> GlobalId {
+        self.global.clone()
+    }
+}
+
+impl<D> GlobalDispatch<ExtOutputImageCaptureSourceManagerV1, CaptureSourceGlobalData, D>
+    for OutputCaptureSourceState
+where
+    D: GlobalDispatch<ExtOutputImageCaptureSourceManagerV1, CaptureSourceGlobalData>,
+    D: Dispatch<ExtOutputImageCaptureSourceManagerV1, ()>,
+    D: Dispatch<ExtImageCaptureSourceV1, ImageCaptureSourceData>,
+    D: 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _display: &DisplayHandle,
+        _client: &Client,
+        manager: New<ExtOutputImageCaptureSourceManagerV1>,
+        _global_data: &CaptureSourceGlobalData,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        data_init.init(manager, ());
+    }
+
+    fn can_view(client: Client, global_data: &CaptureSourceGlobalData) -> bool {
+        (global_data.filter)(&client)
+    }
+}
+
+impl<D> Dispatch<ExtOutputImageCaptureSourceManagerV1, (), D> for OutputCaptureSourceState
+where
+    D: Dispatch<ExtOutputImageCaptureSourceManagerV1, ()>,
+    D: Dispatch<ExtImageCaptureSourceV1, ImageCaptureSourceData>,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        _manager: &ExtOutputImageCaptureSourceManagerV1,
+        request: ext_output_image_capture_source_manager_v1::Request,
+        _data: &(),
+        _display: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            ext_output_image_capture_source_manager_v1::Request::CreateSource { source, output } => {
+                let source_data = ImageCaptureSourceData {
+                    source: Output::from_resource(&output).map(ImageCaptureSource::Output),
+                };
+                data_init.init(source, source_data);
+            }
+            ext_output_image_capture_source_manager_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> GlobalDispatch<ExtForeignToplevelImageCaptureSourceManagerV1, CaptureSourceGlobalData, D>
+    for ToplevelCaptureSourceState
+where
+    D: GlobalDispatch<ExtForeignToplevelImageCaptureSourceManagerV1, CaptureSourceGlobalData>,
+    D: Dispatch<ExtForeignToplevelImageCaptureSourceManagerV1, ()>,
+    D: Dispatch<ExtImageCaptureSourceV1, ImageCaptureSourceData>,
+    D: 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _display: &DisplayHandle,
+        _client: &Client,
+        manager: New<ExtForeignToplevelImageCaptureSourceManagerV1>,
+        _global_data: &CaptureSourceGlobalData,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        data_init.init(manager, ());
+    }
+
+    fn can_view(client: Client, global_data: &CaptureSourceGlobalData) -> bool {
+        (global_data.filter)(&client)
+    }
+}
+
+impl<D> Dispatch<ExtForeignToplevelImageCaptureSourceManagerV1, (), D> for ToplevelCaptureSourceState
+where
+    D: Dispatch<ExtForeignToplevelImageCaptureSourceManagerV1, ()>,
+    D: Dispatch<ExtImageCaptureSourceV1, ImageCaptureSourceData>,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        _manager: &ExtForeignToplevelImageCaptureSourceManagerV1,
+        request: ext_foreign_toplevel_image_capture_source_manager_v1::Request,
+        _data: &(),
+        _display: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            ext_foreign_toplevel_image_capture_source_manager_v1::Request::CreateSource {
+                source,
+                toplevel_handle,
+            } => {
+                let source_data = ImageCaptureSourceData {
+                    source: ForeignToplevelHandle::from_resource(&toplevel_handle)
+                        .filter(|handle| !handle.is_closed())
+                        .map(ImageCaptureSource::Toplevel),
+                };
+                data_init.init(source, source_data);
+            }
+            ext_foreign_toplevel_image_capture_source_manager_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<ExtImageCaptureSourceV1, ImageCaptureSourceData, D> for OutputCaptureSourceState
+where
+    D: Dispatch<ExtImageCaptureSourceV1, ImageCaptureSourceData>,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        _source: &ExtImageCaptureSourceV1,
+        _request: <ExtImageCaptureSourceV1 as Resource>::Request,
+        _data: &ImageCaptureSourceData,
+        _display: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        // the only request is the destructor
+    }
+}
+
+/// Macro to delegate implementation of ext-image-capture-source to [`OutputCaptureSourceState`]
+/// and [`ToplevelCaptureSourceState`].
+#[macro_export]
+macro_rules! delegate_image_capture_source {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        $crate::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::ext::image_capture_source::v1::server::ext_output_image_capture_source_manager_v1::ExtOutputImageCaptureSourceManagerV1: $crate::wayland::image_capture_source::CaptureSourceGlobalData
+        ] => $crate::wayland::image_capture_source::OutputCaptureSourceState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::ext::image_capture_source::v1::server::ext_output_image_capture_source_manager_v1::ExtOutputImageCaptureSourceManagerV1: ()
+        ] => $crate::wayland::image_capture_source::OutputCaptureSourceState);
+
+        $crate::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::ext::image_capture_source::v1::server::ext_foreign_toplevel_image_capture_source_manager_v1::ExtForeignToplevelImageCaptureSourceManagerV1: $crate::wayland::image_capture_source::CaptureSourceGlobalData
+        ] => $crate::wayland::image_capture_source::ToplevelCaptureSourceState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::ext::image_capture_source::v1::server::ext_foreign_toplevel_image_capture_source_manager_v1::ExtForeignToplevelImageCaptureSourceManagerV1: ()
+        ] => $crate::wayland::image_capture_source::ToplevelCaptureSourceState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::ext::image_capture_source::v1::server::ext_image_capture_source_v1::ExtImageCaptureSourceV1: $crate::wayland::image_capture_source::ImageCaptureSourceData
+        ] => $crate::wayland::image_capture_source::OutputCaptureSourceState);
+    };
+}
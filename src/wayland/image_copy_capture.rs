@@ -0,0 +1,852 @@
+//! Utilities for handling the `ext-image-copy-capture` protocol
+//!
+//! This protocol allows clients, like screen recorders or the screencast portal, to copy the contents
+//! of image capture sources into their own shm or dmabuf buffers. Capture sources are created by the
+//! [`ext-image-capture-source`](crate::wayland::image_capture_source) protocol.
+//!
+//! A client creates a [session](ImageCopyCaptureSession) for a source. The compositor advertises the
+//! [`BufferConstraints`] for the session, as returned by [`ImageCopyCaptureHandler::capture_constraints`],
+//! and may update them at any time with [`ImageCopyCaptureSession::update_constraints`], e.g. after
+//! the mode of a captured output changed. For every captured image the client creates a frame, attaches
+//! a buffer and requests the capture. Buffers are validated against the constraints of the session,
+//! frames with mismatching buffers fail with [`FailureReason::BufferConstraints`], so the client can
+//! retry with a new buffer. Valid frames are passed to [`ImageCopyCaptureHandler::frame`] as a [`Frame`],
+//! which the compositor fulfills by rendering the source into the buffer and calling [`Frame::success`].
+//! Dropping the [`Frame`] notifies the client, that the capture failed.
+//! [`capture_elements_into_buffer`](crate::backend::renderer::utils::capture_elements_into_buffer)
+//! renders directly into the buffer of a frame, without an intermediate copy for dmabufs the
+//! renderer can bind.
+//!
+//! Frames are usually only completed, once the source was damaged. To render them efficiently, every
+//! session can use a dedicated [`OutputDamageTracker`](crate::backend::renderer::damage::OutputDamageTracker):
+//! render with the [age](Frame::buffer_age) of the attached buffer and pass the resulting damage
+//! to [`Frame::success_with_damage`].
+//!
+//! Cursor capture sessions are not supported, their capture sessions are stopped immediately.
+//!
+//! ## How to use it
+//!
+//! ```
+//! use smithay::{delegate_image_capture_source, delegate_image_copy_capture};
+//! use smithay::wayland::image_capture_source::{ImageCaptureSource, OutputCaptureSourceState};
+//! use smithay::wayland::image_copy_capture::{
+//!     BufferConstraints, Frame, ImageCopyCaptureHandler, ImageCopyCaptureSession, ImageCopyCaptureState,
+//! };
+//!
+//! # struct State { image_copy_capture_state: ImageCopyCaptureState }
+//! # let mut display = wayland_server::Display::<State>::new().unwrap();
+//! // Create the globals
+//! let image_copy_capture_state = ImageCopyCaptureState::new::<State, _>(&display.handle(), |_client| true);
+//! let output_source_state = OutputCaptureSourceState::new::<State, _>(&display.handle(), |_client| true);
+//!
+//! // Insert the ImageCopyCaptureState into your state.
+//!
+//! // Implement the necessary trait.
+//! impl ImageCopyCaptureHandler for State {
+//!     fn image_copy_capture_state(&mut self) -> &mut ImageCopyCaptureState {
+//!         &mut self.image_copy_capture_state
+//!     }
+//!
+//!     fn capture_constraints(&mut self, source: &ImageCaptureSource) -> Option<BufferConstraints> {
+//!         // Return the size of the source and the supported buffer formats,
+//!         // or `None` if the source can't be captured.
+//! #       None
+//!     }
+//!
+//!     fn frame(&mut self, session: &ImageCopyCaptureSession, frame: Frame) {
+//!         // Render `session.source()` into `frame.buffer()`, once it was damaged,
+//!         // then call `frame.success(..)`.
+//!     }
+//! }
+//! delegate_image_copy_capture!(State);
+//! delegate_image_capture_source!(State);
+//! ```
+
+use std::{sync::Mutex, time::Duration};
+
+use wayland_protocols::ext::image_copy_capture::v1::server::{
+    ext_image_copy_capture_cursor_session_v1::{self, ExtImageCopyCaptureCursorSessionV1},
+    ext_image_copy_capture_frame_v1::{self, ExtImageCopyCaptureFrameV1},
+    ext_image_copy_capture_manager_v1::{self, ExtImageCopyCaptureManagerV1},
+    ext_image_copy_capture_session_v1::{self, ExtImageCopyCaptureSessionV1},
+};
+use wayland_server::{
+    backend::{ClientId, GlobalId},
+    protocol::{wl_buffer::WlBuffer, wl_shm},
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource, WEnum,
+};
+
+use crate::{
+    backend::allocator::{Buffer as _, Format},
+    utils::{Buffer, Physical, Rectangle, Size, Transform},
+    wayland::{dmabuf::get_dmabuf, image_capture_source::ImageCaptureSource, shm},
+};
+
+pub use ext_image_copy_capture_frame_v1::FailureReason;
+
+/// Constraints of the dmabufs accepted by a session
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DmabufConstraints {
+    /// Device the dmabufs have to be allocated on
+    pub device: libc::dev_t,
+    /// Supported formats and modifiers
+    pub formats: Vec<Format>,
+}
+
+/// Constraints of the buffers accepted by a session
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BufferConstraints {
+    /// Size of the buffers in buffer coordinates of the source
+    pub size: Size<i32, Buffer>,
+    /// Supported shm formats, empty if shm buffers are not supported
+    pub shm: Vec<wl_shm::Format>,
+    /// Constraints of dmabufs, `None` if dmabufs are not supported
+    pub dmabuf: Option<DmabufConstraints>,
+}
+
+/// State of the [`ExtImageCopyCaptureManagerV1`] global
+#[derive(Debug)]
+pub struct ImageCopyCaptureState {
+    global: GlobalId,
+    sessions: Vec<ImageCopyCaptureSession>,
+}
+
+impl ImageCopyCaptureState {
+    /// Create a new [`ExtImageCopyCaptureManagerV1`] global
+    ///
+    /// The filter decides which clients are allowed to capture sources.
+    pub fn new<D, F>(display: &DisplayHandle, filter: F) -> Self
+    where
+        D: GlobalDispatch<ExtImageCopyCaptureManagerV1, ImageCopyCaptureGlobalData>,
+        D: Dispatch<ExtImageCopyCaptureManagerV1, ()>,
+        D: Dispatch<ExtImageCopyCaptureSessionV1, ImageCopyCaptureSessionData>,
+        D: Dispatch<ExtImageCopyCaptureCursorSessionV1, ImageCopyCaptureCursorSessionData>,
+        D: Dispatch<ExtImageCopyCaptureFrameV1, ImageCopyCaptureFrameData>,
+        D: ImageCopyCaptureHandler,
+        D: 'static,
+        F: for<'c> Fn(&'c Client) -> bool + Send + Sync + 'static,
+    {
+        let data = ImageCopyCaptureGlobalData {
+            filter: Box::new(filter),
+        };
+        let global = display.create_global::<D, ExtImageCopyCaptureManagerV1, _>(1, data);
+
+        ImageCopyCaptureState {
+            global,
+            sessions: Vec::new(),
+        }
+    }
+
+    /// Returns the id of the [`ExtImageCopyCaptureManagerV1`] global
+    pub fn global(&self) -> GlobalId {
+        self.global.clone()
+    }
+
+    /// Returns all active capture sessions
+    pub fn sessions(&self) -> impl Iterator<Item = &ImageCopyCaptureSession> {
+        self.sessions.iter().filter(|session| !session.is_stopped())
+    }
+}
+
+/// Handler trait for ext-image-copy-capture
+pub trait ImageCopyCaptureHandler {
+    /// [`ImageCopyCaptureState`] getter
+    fn image_copy_capture_state(&mut self) -> &mut ImageCopyCaptureState;
+
+    /// Buffer constraints for capturing the given source
+    ///
+    /// Returning `None` stops the session immediately, e.g. if the source is not available anymore.
+    fn capture_constraints(&mut self, source: &ImageCaptureSource) -> Option<BufferConstraints>;
+
+    /// A new capture session was created
+    fn new_session(&mut self, session: &ImageCopyCaptureSession) {
+        let _ = session;
+    }
+
+    /// A client requested to capture the source of a session into a buffer
+    ///
+    /// The buffer was already validated against the constraints of the session.
+    fn frame(&mut self, session: &ImageCopyCaptureSession, frame: Frame);
+
+    /// A capture session was destroyed by the client
+    fn session_destroyed(&mut self, session: &ImageCopyCaptureSession) {
+        let _ = session;
+    }
+}
+
+#[allow(missing_debug_implementations)]
+#[doc(hidden)]
+pub struct ImageCopyCaptureGlobalData {
+    filter: Box<dyn for<'c> Fn(&'c Client) -> bool + Send + Sync>,
+}
+
+// Tracks the number of captures since buffers were last captured
+#[derive(Debug)]
+struct BufferAges<B> {
+    captures: usize,
+    buffers: Vec<(B, usize)>,
+}
+
+impl<B: PartialEq> BufferAges<B> {
+    fn age(&self, buffer: &B) -> usize {
+        self.buffers
+            .iter()
+            .find(|(known, _)| known == buffer)
+            .map(|(_, capture)| self.captures - capture)
+            .unwrap_or(0)
+    }
+
+    fn captured(&mut self, buffer: B, alive: impl Fn(&B) -> bool) {
+        self.buffers.retain(|(known, _)| known != &buffer && alive(known));
+        self.buffers.push((buffer, self.captures));
+        self.captures += 1;
+    }
+}
+
+impl<B> Default for BufferAges<B> {
+    fn default() -> Self {
+        BufferAges {
+            captures: 0,
+            buffers: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct SessionState {
+    constraints: Option<BufferConstraints>,
+    stopped: bool,
+    has_frame: bool,
+    ages: BufferAges<WlBuffer>,
+}
+
+/// User data of the [`ExtImageCopyCaptureSessionV1`] resource
+#[derive(Debug, Default)]
+pub struct ImageCopyCaptureSessionData {
+    state: Mutex<SessionState>,
+}
+
+/// User data of the [`ExtImageCopyCaptureCursorSessionV1`] resource
+#[derive(Debug, Default)]
+pub struct ImageCopyCaptureCursorSessionData {
+    has_session: Mutex<bool>,
+}
+
+#[derive(Debug, Default)]
+struct FrameState {
+    buffer: Option<WlBuffer>,
+    damage: Vec<Rectangle<i32, Buffer>>,
+    captured: bool,
+}
+
+/// User data of the [`ExtImageCopyCaptureFrameV1`] resource
+#[derive(Debug)]
+pub struct ImageCopyCaptureFrameData {
+    session: ExtImageCopyCaptureSessionV1,
+    state: Mutex<FrameState>,
+}
+
+/// A capture session of an [`ImageCaptureSource`]
+#[derive(Debug, Clone)]
+pub struct ImageCopyCaptureSession {
+    session: ExtImageCopyCaptureSessionV1,
+    source: ImageCaptureSource,
+    paint_cursors: bool,
+}
+
+impl PartialEq for ImageCopyCaptureSession {
+    fn eq(&self, other: &Self) -> bool {
+        self.session == other.session
+    }
+}
+
+impl ImageCopyCaptureSession {
+    /// Returns the underlying [`ExtImageCopyCaptureSessionV1`]
+    pub fn session(&self) -> &ExtImageCopyCaptureSessionV1 {
+        &self.session
+    }
+
+    /// Returns the captured source
+    pub fn source(&self) -> &ImageCaptureSource {
+        &self.source
+    }
+
+    /// Returns whether cursors should be painted onto the captured images
+    pub fn paint_cursors(&self) -> bool {
+        self.paint_cursors
+    }
+
+    /// Returns the current buffer constraints of the session
+    pub fn constraints(&self) -> Option<BufferConstraints> {
+        self.data().state.lock().unwrap().constraints.clone()
+    }
+
+    /// Advertise new buffer constraints
+    ///
+    /// Pending frames with buffers not matching the new constraints
+    /// should be failed with [`FailureReason::BufferConstraints`].
+    pub fn update_constraints(&self, constraints: BufferConstraints) {
+        let mut state = self.data().state.lock().unwrap();
+        if state.stopped || state.constraints.as_ref() == Some(&constraints) {
+            return;
+        }
+        send_constraints(&self.session, &constraints);
+        state.constraints = Some(constraints);
+    }
+
+    /// Stop the session, e.g. because the source was destroyed
+    ///
+    /// Pending frames should be failed with [`FailureReason::Stopped`],
+    /// new frames fail automatically.
+    pub fn stop(&self) {
+        let mut state = self.data().state.lock().unwrap();
+        if !state.stopped {
+            state.stopped = true;
+            self.session.stopped();
+        }
+    }
+
+    /// Returns whether the session was stopped
+    pub fn is_stopped(&self) -> bool {
+        self.data().state.lock().unwrap().stopped
+    }
+
+    fn data(&self) -> &ImageCopyCaptureSessionData {
+        self.session.data::<ImageCopyCaptureSessionData>().unwrap()
+    }
+}
+
+fn send_constraints(session: &ExtImageCopyCaptureSessionV1, constraints: &BufferConstraints) {
+    session.buffer_size(constraints.size.w as u32, constraints.size.h as u32);
+    for format in &constraints.shm {
+        session.shm_format(*format);
+    }
+    if let Some(dmabuf) = constraints.dmabuf.as_ref() {
+        session.dmabuf_device(dmabuf.device.to_ne_bytes().to_vec());
+        let mut codes = Vec::new();
+        for format in &dmabuf.formats {
+            if !codes.contains(&format.code) {
+                codes.push(format.code);
+            }
+        }
+        for code in codes {
+            let modifiers = dmabuf
+                .formats
+                .iter()
+                .filter(|format| format.code == code)
+                .flat_map(|format| u64::from(format.modifier).to_ne_bytes())
+                .collect::<Vec<_>>();
+            session.dmabuf_format(code as u32, modifiers);
+        }
+    }
+    session.done();
+}
+
+/// A pending capture into a client buffer
+///
+/// See [`ImageCopyCaptureHandler::frame`]. Dropping it without calling [`Frame::success`]
+/// notifies the client, that the capture failed.
+#[derive(Debug)]
+pub struct Frame {
+    frame: ExtImageCopyCaptureFrameV1,
+    session: ExtImageCopyCaptureSessionV1,
+    buffer: WlBuffer,
+    client_damage: Vec<Rectangle<i32, Buffer>>,
+    size: Size<i32, Buffer>,
+    age: usize,
+    first: bool,
+    done: bool,
+}
+
+impl Frame {
+    /// Returns the underlying [`ExtImageCopyCaptureFrameV1`]
+    pub fn frame(&self) -> &ExtImageCopyCaptureFrameV1 {
+        &self.frame
+    }
+
+    /// Returns the buffer to capture the source into
+    pub fn buffer(&self) -> &WlBuffer {
+        &self.buffer
+    }
+
+    /// Returns the size of the buffer
+    pub fn size(&self) -> Size<i32, Buffer> {
+        self.size
+    }
+
+    /// Returns the regions of the buffer the client modified since the buffer was last captured
+    pub fn client_damage(&self) -> &[Rectangle<i32, Buffer>] {
+        &self.client_damage
+    }
+
+    /// Returns the age of the buffer, counted in successful frames of the session
+    ///
+    /// Returns `0`, if the buffer was not captured in this session before or its contents were
+    /// damaged by the client. The age can be passed to an
+    /// [`OutputDamageTracker`](crate::backend::renderer::damage::OutputDamageTracker),
+    /// as long as it is only used to render the successful frames of this session.
+    pub fn buffer_age(&self) -> usize {
+        self.age
+    }
+
+    /// Notify the client, that the buffer contains the captured contents
+    ///
+    /// `transform` is the transform the client has to apply to the buffer contents, e.g. the
+    /// transform of a captured output. `damage` contains the regions of the buffer modified by this
+    /// capture and is extended to the whole buffer for the first frame of a session. `presentation_time`
+    /// is the presentation time of the captured contents, in the clock domain of the presentation
+    /// time protocol.
+    pub fn success(
+        mut self,
+        transform: Transform,
+        damage: impl IntoIterator<Item = Rectangle<i32, Buffer>>,
+        presentation_time: Duration,
+    ) {
+        let bounds = Rectangle::from_size(self.size);
+        self.frame.transform(transform.into());
+        if self.first {
+            self.frame.damage(0, 0, self.size.w, self.size.h);
+        } else {
+            for rect in damage.into_iter().filter_map(|rect| rect.intersection(bounds)) {
+                self.frame
+                    .damage(rect.loc.x, rect.loc.y, rect.size.w, rect.size.h);
+            }
+        }
+        let secs = presentation_time.as_secs();
+        self.frame
+            .presentation_time((secs >> 32) as u32, secs as u32, presentation_time.subsec_nanos());
+        self.frame.ready();
+
+        if let Some(data) = self.session.data::<ImageCopyCaptureSessionData>() {
+            data.state
+                .lock()
+                .unwrap()
+                .ages
+                .captured(self.buffer.clone(), |buffer| buffer.is_alive());
+        }
+        self.done = true;
+    }
+
+    /// Notify the client, that the buffer contains the captured contents, using the damage of a render pass
+    ///
+    /// `damage` is the damage returned by
+    /// [`OutputDamageTracker::render_output`](crate::backend::renderer::damage::OutputDamageTracker::render_output),
+    /// which is converted into buffer coordinates using `transform`. See [`Frame::success`] for the other arguments.
+    pub fn success_with_damage(
+        self,
+        transform: Transform,
+        damage: Option<&[Rectangle<i32, Physical>]>,
+        presentation_time: Duration,
+    ) {
+        let damage = damage_to_buffer(damage.unwrap_or_default(), self.size, transform);
+        self.success(transform, damage, presentation_time);
+    }
+
+    /// Notify the client, that the capture failed
+    ///
+    /// Clients retry captures failed with [`FailureReason::BufferConstraints`] using a new buffer,
+    /// while [`FailureReason::Stopped`] indicates the end of the session.
+    pub fn fail(mut self, reason: FailureReason) {
+        self.frame.failed(reason);
+        self.done = true;
+    }
+}
+
+impl Drop for Frame {
+    fn drop(&mut self) {
+        if !self.done && self.frame.is_alive() {
+            self.frame.failed(FailureReason::Unknown);
+        }
+    }
+}
+
+// Converts damage in the transformed space of the rendered output into buffer coordinates
+fn damage_to_buffer(
+    damage: &[Rectangle<i32, Physical>],
+    size: Size<i32, Buffer>,
+    transform: Transform,
+) -> Vec<Rectangle<i32, Buffer>> {
+    let area = transform.transform_size(size.to_logical(1, Transform::Normal));
+    damage
+        .iter()
+        .map(|rect| rect.to_logical(1).to_buffer(1, transform, &area))
+        .collect()
+}
+
+// Checks the buffer against the advertised constraints
+fn is_valid_buffer(buffer: &WlBuffer, constraints: &BufferConstraints) -> bool {
+    if let Ok(dmabuf) = get_dmabuf(buffer) {
+        return constraints.dmabuf.as_ref().is_some_and(|dmabuf_constraints| {
+            dmabuf_constraints.formats.contains(&dmabuf.format()) && dmabuf.size() == constraints.size
+        });
+    }
+    shm::with_buffer_contents(buffer, |_, _, data| {
+        constraints.shm.contains(&data.format)
+            && data.width == constraints.size.w
+            && data.height == constraints.size.h
+    })
+    .unwrap_or(false)
+}
+
+impl<D> GlobalDispatch<ExtImageCopyCaptureManagerV1, ImageCopyCaptureGlobalData, D> for ImageCopyCaptureState
+where
+    D: GlobalDispatch<ExtImageCopyCaptureManagerV1, ImageCopyCaptureGlobalData>,
+    D: Dispatch<ExtImageCopyCaptureManagerV1, ()>,
+    D: Dispatch<ExtImageCopyCaptureSessionV1, ImageCopyCaptureSessionData>,
+    D: Dispatch<ExtImageCopyCaptureCursorSessionV1, ImageCopyCaptureCursorSessionData>,
+    D: Dispatch<ExtImageCopyCaptureFrameV1, ImageCopyCaptureFrameData>,
+    D: ImageCopyCaptureHandler,
+    D: 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _display: &DisplayHandle,
+        _client: &Client,
+        manager: New<ExtImageCopyCaptureManagerV1>,
+        _global_data: &ImageCopyCaptureGlobalData,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        data_init.init(manager, ());
+    }
+
+    fn can_view(client: Client, global_data: &ImageCopyCaptureGlobalData) -> bool {
+        (global_data.filter)(&client)
+    }
+}
+
+impl<D> Dispatch<ExtImageCopyCaptureManagerV1, (), D> for ImageCopyCaptureState
+where
+    D: Dispatch<ExtImageCopyCaptureManagerV1, ()>,
+    D: Dispatch<ExtImageCopyCaptureSessionV1, ImageCopyCaptureSessionData>,
+    D: Dispatch<ExtImageCopyCaptureCursorSessionV1, ImageCopyCaptureCursorSessionData>,
+    D: Dispatch<ExtImageCopyCaptureFrameV1, ImageCopyCaptureFrameData>,
+    D: ImageCopyCaptureHandler,
+    D: 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        manager: &ExtImageCopyCaptureManagerV1,
+        request: ext_image_copy_capture_manager_v1::Request,
+        _data: &(),
+        _display: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            ext_image_copy_capture_manager_v1::Request::CreateSession {
+                session,
+                source,
+                options,
+            } => {
+                let WEnum::Value(options) = options else {
+                    manager.post_error(
+                        ext_image_copy_capture_manager_v1::Error::InvalidOption,
+                        "invalid options",
+                    );
+                    return;
+                };
+                let session = data_init.init(session, ImageCopyCaptureSessionData::default());
+                let Some(source) = ImageCaptureSource::from_resource(&source) else {
+                    stop_session(&session);
+                    return;
+                };
+                let Some(constraints) = state.capture_constraints(&source) else {
+                    stop_session(&session);
+                    return;
+                };
+
+                send_constraints(&session, &constraints);
+                session
+                    .data::<ImageCopyCaptureSessionData>()
+                    .unwrap()
+                    .state
+                    .lock()
+                    .unwrap()
+                    .constraints = Some(constraints);
+
+                let session = ImageCopyCaptureSession {
+                    session,
+                    source,
+                    paint_cursors: options.contains(ext_image_copy_capture_manager_v1::Options::PaintCursors),
+                };
+                state.image_copy_capture_state().sessions.push(session.clone());
+                state.new_session(&session);
+            }
+            ext_image_copy_capture_manager_v1::Request::CreatePointerCursorSession { session, .. } => {
+                data_init.init(session, ImageCopyCaptureCursorSessionData::default());
+            }
+            ext_image_copy_capture_manager_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+fn stop_session(session: &ExtImageCopyCaptureSessionV1) {
+    if let Some(data) = session.data::<ImageCopyCaptureSessionData>() {
+        data.state.lock().unwrap().stopped = true;
+    }
+    session.stopped();
+}
+
+impl<D> Dispatch<ExtImageCopyCaptureCursorSessionV1, ImageCopyCaptureCursorSessionData, D>
+    for ImageCopyCaptureState
+where
+    D: Dispatch<ExtImageCopyCaptureCursorSessionV1, ImageCopyCaptureCursorSessionData>,
+    D: Dispatch<ExtImageCopyCaptureSessionV1, ImageCopyCaptureSessionData>,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        cursor_session: &ExtImageCopyCaptureCursorSessionV1,
+        request: ext_image_copy_capture_cursor_session_v1::Request,
+        data: &ImageCopyCaptureCursorSessionData,
+        _display: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            ext_image_copy_capture_cursor_session_v1::Request::GetCaptureSession { session } => {
+                let mut has_session = data.has_session.lock().unwrap();
+                if *has_session {
+                    cursor_session.post_error(
+                        ext_image_copy_capture_cursor_session_v1::Error::DuplicateSession,
+                        "capture session was already created",
+                    );
+                    return;
+                }
+                *has_session = true;
+
+                // cursor capturing is not supported
+                let session = data_init.init(session, ImageCopyCaptureSessionData::default());
+                stop_session(&session);
+            }
+            ext_image_copy_capture_cursor_session_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<ExtImageCopyCaptureSessionV1, ImageCopyCaptureSessionData, D> for ImageCopyCaptureState
+where
+    D: Dispatch<ExtImageCopyCaptureSessionV1, ImageCopyCaptureSessionData>,
+    D: Dispatch<ExtImageCopyCaptureFrameV1, ImageCopyCaptureFrameData>,
+    D: ImageCopyCaptureHandler,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        session: &ExtImageCopyCaptureSessionV1,
+        request: ext_image_copy_capture_session_v1::Request,
+        data: &ImageCopyCaptureSessionData,
+        _display: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            ext_image_copy_capture_session_v1::Request::CreateFrame { frame } => {
+                let mut state = data.state.lock().unwrap();
+                if state.has_frame {
+                    session.post_error(
+                        ext_image_copy_capture_session_v1::Error::DuplicateFrame,
+                        "previous frame was not destroyed",
+                    );
+                    return;
+                }
+                state.has_frame = true;
+                data_init.init(
+                    frame,
+                    ImageCopyCaptureFrameData {
+                        session: session.clone(),
+                        state: Mutex::new(FrameState::default()),
+                    },
+                );
+            }
+            ext_image_copy_capture_session_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(
+        state: &mut D,
+        _client: ClientId,
+        session: &ExtImageCopyCaptureSessionV1,
+        _data: &ImageCopyCaptureSessionData,
+    ) {
+        let sessions = &mut state.image_copy_capture_state().sessions;
+        let Some(index) = sessions.iter().position(|known| &known.session == session) else {
+            return;
+        };
+        let session = sessions.remove(index);
+        state.session_destroyed(&session);
+    }
+}
+
+impl<D> Dispatch<ExtImageCopyCaptureFrameV1, ImageCopyCaptureFrameData, D> for ImageCopyCaptureState
+where
+    D: Dispatch<ExtImageCopyCaptureFrameV1, ImageCopyCaptureFrameData>,
+    D: ImageCopyCaptureHandler,
+    D: 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        frame: &ExtImageCopyCaptureFrameV1,
+        request: ext_image_copy_capture_frame_v1::Request,
+        data: &ImageCopyCaptureFrameData,
+        _display: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        let mut frame_state = data.state.lock().unwrap();
+        if frame_state.captured && !matches!(request, ext_image_copy_capture_frame_v1::Request::Destroy) {
+            frame.post_error(
+                ext_image_copy_capture_frame_v1::Error::AlreadyCaptured,
+                "frame was already captured",
+            );
+            return;
+        }
+
+        match request {
+            ext_image_copy_capture_frame_v1::Request::AttachBuffer { buffer } => {
+                frame_state.buffer = Some(buffer);
+            }
+            ext_image_copy_capture_frame_v1::Request::DamageBuffer { x, y, width, height } => {
+                if x < 0 || y < 0 || width <= 0 || height <= 0 {
+                    frame.post_error(
+                        ext_image_copy_capture_frame_v1::Error::InvalidBufferDamage,
+                        "invalid buffer damage",
+                    );
+                    return;
+                }
+                frame_state
+                    .damage
+                    .push(Rectangle::new((x, y).into(), (width, height).into()));
+            }
+            ext_image_copy_capture_frame_v1::Request::Capture => {
+                let Some(buffer) = frame_state.buffer.clone() else {
+                    frame.post_error(
+                        ext_image_copy_capture_frame_v1::Error::NoBuffer,
+                        "no buffer was attached",
+                    );
+                    return;
+                };
+                frame_state.captured = true;
+                let client_damage = std::mem::take(&mut frame_state.damage);
+                drop(frame_state);
+
+                let session = state
+                    .image_copy_capture_state()
+                    .sessions
+                    .iter()
+                    .find(|session| session.session == data.session && !session.is_stopped())
+                    .cloned();
+                let Some(session) = session else {
+                    frame.failed(FailureReason::Stopped);
+                    return;
+                };
+
+                let (size, age, first) = {
+                    let session_state = session.data().state.lock().unwrap();
+                    let constraints = session_state.constraints.as_ref();
+                    if !constraints.is_some_and(|constraints| is_valid_buffer(&buffer, constraints)) {
+                        frame.failed(FailureReason::BufferConstraints);
+                        return;
+                    }
+                    let age = if client_damage.is_empty() {
+                        session_state.ages.age(&buffer)
+                    } else {
+                        0
+                    };
+                    (constraints.unwrap().size, age, session_state.ages.captures == 0)
+                };
+
+                state.frame(
+                    &session,
+                    Frame {
+                        frame: frame.clone(),
+                        session: data.session.clone(),
+                        buffer,
+                        client_damage,
+                        size,
+                        age,
+                        first,
+                        done: false,
+                    },
+                );
+            }
+            ext_image_copy_capture_frame_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(
+        _state: &mut D,
+        _client: ClientId,
+        _frame: &ExtImageCopyCaptureFrameV1,
+        data: &ImageCopyCaptureFrameData,
+    ) {
+        if let Some(session_data) = data.session.data::<ImageCopyCaptureSessionData>() {
+            session_data.state.lock().unwrap().has_frame = false;
+        }
+    }
+}
+
+/// Macro to delegate implementation of ext-image-copy-capture to [`ImageCopyCaptureState`].
+///
+/// You must also implement [`ImageCopyCaptureHandler`] to use this.
+#[macro_export]
+macro_rules! delegate_image_copy_capture {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        $crate::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::ext::image_copy_capture::v1::server::ext_image_copy_capture_manager_v1::ExtImageCopyCaptureManagerV1: $crate::wayland::image_copy_capture::ImageCopyCaptureGlobalData
+        ] => $crate::wayland::image_copy_capture::ImageCopyCaptureState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::ext::image_copy_capture::v1::server::ext_image_copy_capture_manager_v1::ExtImageCopyCaptureManagerV1: ()
+        ] => $crate::wayland::image_copy_capture::ImageCopyCaptureState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::ext::image_copy_capture::v1::server::ext_image_copy_capture_session_v1::ExtImageCopyCaptureSessionV1: $crate::wayland::image_copy_capture::ImageCopyCaptureSessionData
+        ] => $crate::wayland::image_copy_capture::ImageCopyCaptureState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::ext::image_copy_capture::v1::server::ext_image_copy_capture_cursor_session_v1::ExtImageCopyCaptureCursorSessionV1: $crate::wayland::image_copy_capture::ImageCopyCaptureCursorSessionData
+        ] => $crate::wayland::image_copy_capture::ImageCopyCaptureState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::ext::image_copy_capture::v1::server::ext_image_copy_capture_frame_v1::ExtImageCopyCaptureFrameV1: $crate::wayland::image_copy_capture::ImageCopyCaptureFrameData
+        ] => $crate::wayland::image_copy_capture::ImageCopyCaptureState);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buffer_age() {
+        let mut ages = BufferAges::default();
+        assert_eq!(ages.age(&0), 0);
+        ages.captured(0, |_| true);
+        assert_eq!(ages.age(&1), 0);
+        ages.captured(1, |_| true);
+        assert_eq!(ages.age(&0), 2);
+        assert_eq!(ages.age(&1), 1);
+        ages.captured(0, |buffer| *buffer != 1);
+        assert_eq!(ages.age(&0), 1);
+        assert_eq!(ages.age(&1), 0);
+    }
+
+    #[test]
+    fn render_damage() {
+        let size = Size::from((100, 50));
+        let damage = [Rectangle::new((0, 0).into(), (10, 20).into())];
+        assert_eq!(
+            damage_to_buffer(&damage, size, Transform::Normal),
+            vec![Rectangle::new((0, 0).into(), (10, 20).into())]
+        );
+        assert_eq!(
+            damage_to_buffer(&damage, size, Transform::_90),
+            vec![Rectangle::new((80, 0).into(), (20, 10).into())]
+        );
+    }
+}
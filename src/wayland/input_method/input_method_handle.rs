@@ -17,7 +17,12 @@ use wayland_server::{
 use crate::{
     input::{keyboard::KeyboardHandle, SeatHandler},
     utils::{alive_tracker::AliveTracker, Logical, Rectangle, SERIAL_COUNTER},
-    wayland::{compositor, seat::WaylandFocus, text_input::TextInputHandle},
+    wayland::{
+        compositor,
+        protocol_error::{post_error, ProtocolErrorHandler, ProtocolErrorKind},
+        seat::WaylandFocus,
+        text_input::TextInputHandle,
+    },
 };
 
 use super::{
@@ -190,7 +195,7 @@ where
     D: Dispatch<ZwpInputMethodV2, InputMethodUserData<D>>,
     D: Dispatch<ZwpInputPopupSurfaceV2, InputMethodPopupSurfaceUserData>,
     D: Dispatch<ZwpInputMethodKeyboardGrabV2, InputMethodKeyboardUserData<D>>,
-    D: SeatHandler,
+    D: SeatHandler + ProtocolErrorHandler,
     D: InputMethodHandler,
     <D as SeatHandler>::KeyboardFocus: WaylandFocus,
     D: 'static,
@@ -245,7 +250,13 @@ where
                     && compositor::get_role(&surface) != Some(INPUT_POPUP_SURFACE_ROLE)
                 {
                     // Protocol requires this raise an error, but doesn't define an error enum
-                    seat.post_error(0u32, "Surface already has a role.");
+                    post_error(
+                        state,
+                        seat,
+                        ProtocolErrorKind::Role,
+                        0u32,
+                        "Surface already has a role.",
+                    );
                     return;
                 }
 
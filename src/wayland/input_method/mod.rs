@@ -19,6 +19,7 @@
 //! # struct State { seat_state: SeatState<Self> };
 //!
 //! delegate_seat!(State);
+//! # impl smithay::wayland::protocol_error::ProtocolErrorHandler for State {}
 //! # delegate_compositor!(State);
 //!
 //! impl InputMethodHandler for State {
@@ -0,0 +1,318 @@
+//! Introspection of the protocol state of clients
+//!
+//! [`IntrospectionState`] collects information about the connected clients, like the globals they
+//! bound, the number of protocol objects they own and the rate of requests they send. This can be used
+//! to build a "wayland-info"-like page in the compositor settings or to spot misbehaving clients.
+//!
+//! Globals are not enumerable, so they have to be registered using [`IntrospectionState::add_global`].
+//! Request rates are only measured, if clients are dispatched through [`IntrospectionState::dispatch_clients`]
+//! instead of [`Display::dispatch_clients`].
+//!
+//! ```no_run
+//! use smithay::wayland::introspection::IntrospectionState;
+//! # use smithay::wayland::{buffer::BufferHandler, shm::{ShmHandler, ShmState}};
+//! # use smithay::reexports::wayland_server::protocol::wl_buffer::WlBuffer;
+//!
+//! # struct State { shm_state: ShmState }
+//! # impl BufferHandler for State {
+//! #     fn buffer_destroyed(&mut self, _buffer: &WlBuffer) {}
+//! # }
+//! # impl ShmHandler for State {
+//! #     fn shm_state(&self) -> &ShmState { &self.shm_state }
+//! # }
+//! # smithay::delegate_shm!(State);
+//! # let mut display = wayland_server::Display::<State>::new().unwrap();
+//! let shm_state = ShmState::new::<State>(&display.handle(), vec![]);
+//! # let mut state = State { shm_state };
+//!
+//! let mut introspection = IntrospectionState::new();
+//! introspection.add_global(state.shm_state.global());
+//!
+//! // when the display fd is readable
+//! introspection.dispatch_clients(&mut display, &mut state).unwrap();
+//!
+//! for client in introspection.clients(&display.handle()) {
+//!     println!(
+//!         "{:?}: {} objects, {:.1} requests/s",
+//!         client.credentials, client.object_count, client.request_rate
+//!     );
+//! }
+//! ```
+
+use std::{
+    collections::HashMap,
+    io,
+    time::{Duration, Instant},
+};
+
+use wayland_server::{
+    backend::{ClientId, Credentials, GlobalId, Handle, ObjectId},
+    Display, DisplayHandle,
+};
+
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+/// Information about a global
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GlobalInfo {
+    /// Id of the global
+    pub id: GlobalId,
+    /// Name of the interface of the global
+    pub interface: &'static str,
+    /// Version advertised to clients
+    pub version: u32,
+    /// Whether the global is disabled
+    pub disabled: bool,
+}
+
+/// A global bound by a client
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BoundGlobal {
+    /// Name of the interface of the global
+    pub interface: &'static str,
+    /// Version the client bound
+    pub version: u32,
+    /// Number of times the client bound the global with this version
+    pub count: usize,
+}
+
+/// Information about a connected client
+#[derive(Debug, Clone)]
+pub struct ClientInfo {
+    /// Id of the client
+    pub id: ClientId,
+    /// Credentials of the client process, if available
+    pub credentials: Option<Credentials>,
+    /// Registered globals bound by the client, sorted by interface
+    ///
+    /// All objects of the interface of a registered global are assumed to be bound through the registry.
+    pub bound_globals: Vec<BoundGlobal>,
+    /// Number of objects owned by the client per interface
+    pub objects: HashMap<&'static str, usize>,
+    /// Total number of objects owned by the client
+    pub object_count: usize,
+    /// Requests per second sent by the client, measured over the last second
+    pub request_rate: f64,
+}
+
+#[derive(Debug)]
+struct RateCounter {
+    window_start: Instant,
+    count: usize,
+    rate: f64,
+}
+
+impl RateCounter {
+    fn new(now: Instant) -> Self {
+        RateCounter {
+            window_start: now,
+            count: 0,
+            rate: 0.0,
+        }
+    }
+
+    fn record(&mut self, now: Instant, count: usize) {
+        self.update(now);
+        self.count += count;
+    }
+
+    fn update(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.window_start);
+        if elapsed >= RATE_WINDOW {
+            self.rate = self.count as f64 / elapsed.as_secs_f64();
+            self.count = 0;
+            self.window_start = now;
+        }
+    }
+}
+
+/// State collecting protocol statistics of clients
+///
+/// See the [module docs](self) for details.
+#[derive(Debug, Default)]
+pub struct IntrospectionState {
+    globals: Vec<GlobalId>,
+    request_rates: HashMap<ClientId, RateCounter>,
+    total_rate: Option<RateCounter>,
+}
+
+impl IntrospectionState {
+    /// Create a new introspection state without any registered globals
+    pub fn new() -> Self {
+        IntrospectionState::default()
+    }
+
+    /// Register a global to be reported by [`IntrospectionState::globals`] and
+    /// [`ClientInfo::bound_globals`]
+    pub fn add_global(&mut self, global: GlobalId) {
+        if !self.globals.contains(&global) {
+            self.globals.push(global);
+        }
+    }
+
+    /// Returns the registered globals, which were not removed yet
+    pub fn globals(&mut self, display: &DisplayHandle) -> Vec<GlobalInfo> {
+        let handle = display.backend_handle();
+        let mut infos = Vec::new();
+        self.globals.retain(|id| match handle.global_info(id.clone()) {
+            Ok(info) => {
+                infos.push(GlobalInfo {
+                    id: id.clone(),
+                    interface: info.interface.name,
+                    version: info.version,
+                    disabled: info.disabled,
+                });
+                true
+            }
+            Err(_) => false,
+        });
+        infos
+    }
+
+    /// Dispatch the requests of all clients, measuring their request rates
+    ///
+    /// Use this instead of [`Display::dispatch_clients`]. Returns the number of dispatched requests.
+    ///
+    /// With the `use_system_lib` feature libwayland can only dispatch all clients at once,
+    /// so only the [total request rate](Self::total_request_rate) is measured.
+    pub fn dispatch_clients<D: 'static>(
+        &mut self,
+        display: &mut Display<D>,
+        state: &mut D,
+    ) -> io::Result<usize> {
+        let now = Instant::now();
+
+        #[cfg(feature = "use_system_lib")]
+        let dispatched = display.dispatch_clients(state)?;
+
+        #[cfg(not(feature = "use_system_lib"))]
+        let dispatched = {
+            let mut clients = Vec::new();
+            display
+                .handle()
+                .backend_handle()
+                .with_all_clients(|id| clients.push(id));
+
+            let mut dispatched = 0;
+            for client in clients {
+                // clients failing to dispatch are disconnected by the backend
+                let Ok(count) = display.backend().dispatch_single_client(state, client.clone()) else {
+                    continue;
+                };
+                self.request_rates
+                    .entry(client)
+                    .or_insert_with(|| RateCounter::new(now))
+                    .record(now, count);
+                dispatched += count;
+            }
+            dispatched
+        };
+
+        self.total_rate
+            .get_or_insert_with(|| RateCounter::new(now))
+            .record(now, dispatched);
+        Ok(dispatched)
+    }
+
+    /// Returns the requests per second sent by all clients, measured over the last second
+    pub fn total_request_rate(&mut self) -> f64 {
+        self.total_rate
+            .as_mut()
+            .map(|rate| {
+                rate.update(Instant::now());
+                rate.rate
+            })
+            .unwrap_or(0.0)
+    }
+
+    /// Returns information about all connected clients
+    pub fn clients(&mut self, display: &DisplayHandle) -> Vec<ClientInfo> {
+        let handle = display.backend_handle();
+        let mut clients = Vec::new();
+        handle.with_all_clients(|id| clients.push(id));
+        self.request_rates.retain(|id, _| clients.contains(id));
+
+        let global_interfaces = self
+            .globals
+            .iter()
+            .filter_map(|id| handle.global_info(id.clone()).ok())
+            .map(|info| info.interface.name)
+            .collect::<Vec<_>>();
+
+        let now = Instant::now();
+        clients
+            .into_iter()
+            .filter_map(|id| {
+                let request_rate = self
+                    .request_rates
+                    .get_mut(&id)
+                    .map(|rate| {
+                        rate.update(now);
+                        rate.rate
+                    })
+                    .unwrap_or(0.0);
+                client_info(&handle, id, &global_interfaces, request_rate)
+            })
+            .collect()
+    }
+}
+
+fn client_info(
+    handle: &Handle,
+    id: ClientId,
+    global_interfaces: &[&'static str],
+    request_rate: f64,
+) -> Option<ClientInfo> {
+    // the handle must not be used while iterating the objects
+    let mut object_ids: Vec<ObjectId> = Vec::new();
+    handle
+        .with_all_objects_for(id.clone(), |object| object_ids.push(object))
+        .ok()?;
+
+    let mut objects = HashMap::new();
+    let mut bound = HashMap::new();
+    for object in &object_ids {
+        let interface = object.interface().name;
+        *objects.entry(interface).or_insert(0) += 1;
+        if global_interfaces.contains(&interface) {
+            if let Ok(info) = handle.object_info(object.clone()) {
+                *bound.entry((interface, info.version)).or_insert(0) += 1;
+            }
+        }
+    }
+    let mut bound_globals = bound
+        .into_iter()
+        .map(|((interface, version), count)| BoundGlobal {
+            interface,
+            version,
+            count,
+        })
+        .collect::<Vec<_>>();
+    bound_globals.sort_by(|a, b| (a.interface, a.version).cmp(&(b.interface, b.version)));
+
+    Some(ClientInfo {
+        credentials: handle.get_client_credentials(id.clone()).ok(),
+        id,
+        bound_globals,
+        objects,
+        object_count: object_ids.len(),
+        request_rate,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request_rate() {
+        let start = Instant::now();
+        let mut rate = RateCounter::new(start);
+        rate.record(start, 10);
+        rate.record(start + Duration::from_millis(500), 10);
+        assert_eq!(rate.rate, 0.0);
+        rate.update(start + Duration::from_secs(2));
+        assert_eq!(rate.rate, 10.0);
+        assert_eq!(rate.count, 0);
+    }
+}
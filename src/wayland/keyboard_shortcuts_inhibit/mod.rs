@@ -18,7 +18,10 @@ use wayland_server::{
 mod dispatch;
 pub use dispatch::KeyboardShortcutsInhibitorUserData;
 
-use crate::input::{Seat, SeatHandler};
+use crate::input::{
+    keyboard::{Keysym, KeysymHandle, ModifiersState},
+    Seat, SeatHandler,
+};
 
 type SeatId = ObjectId;
 
@@ -221,6 +224,177 @@ where
     }
 }
 
+/// Key chord always reaching the compositor, even while shortcuts are inhibited
+///
+/// Gives users a way to regain control over the compositor if a client keeps
+/// its inhibitor active, e.g. by inactivating the inhibitor when the chord is pressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EscapeChord {
+    /// The "control" key has to be held
+    pub ctrl: bool,
+    /// The "alt" key has to be held
+    pub alt: bool,
+    /// The "shift" key has to be held
+    pub shift: bool,
+    /// The "logo" key has to be held
+    pub logo: bool,
+    /// The key completing the chord
+    pub keysym: Keysym,
+}
+
+impl EscapeChord {
+    /// Create a chord of a single key without any modifiers
+    pub fn new(keysym: Keysym) -> Self {
+        EscapeChord {
+            ctrl: false,
+            alt: false,
+            shift: false,
+            logo: false,
+            keysym,
+        }
+    }
+
+    /// Check if a key press matches this chord
+    ///
+    /// The modifiers have to match exactly, locked modifiers are ignored.
+    /// The key is compared layout agnostic using [`KeysymHandle::raw_latin_sym_or_raw_current_sym`]
+    /// and falls back to the modified keysym.
+    pub fn matches(&self, modifiers: &ModifiersState, keysym: &KeysymHandle<'_>) -> bool {
+        self.ctrl == modifiers.ctrl
+            && self.alt == modifiers.alt
+            && self.shift == modifiers.shift
+            && self.logo == modifiers.logo
+            && (keysym.raw_latin_sym_or_raw_current_sym() == Some(self.keysym)
+                || keysym.modified_sym() == self.keysym)
+    }
+}
+
+/// Result of [`ShortcutsInhibitFilter::filter`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShortcutFilterResult<T> {
+    /// The compositor should handle its shortcut and intercept the key
+    Shortcut(T),
+    /// The escape chord was pressed while shortcuts were inhibited
+    ///
+    /// The key should be intercepted, what the compositor does in response,
+    /// e.g. inactivating the inhibitor, is up to it.
+    Escape(KeyboardShortcutsInhibitor),
+    /// The key should be forwarded to the client
+    Forward,
+}
+
+/// Helper to filter compositor shortcuts according to the inhibitor of the focused surface
+///
+/// The filter has to be created before calling [`KeyboardHandle::input`](crate::input::keyboard::KeyboardHandle::input)
+/// and can then be used inside of the filter closure, when processing key presses:
+///
+/// ```no_run
+/// # use smithay::input::{Seat, SeatHandler, keyboard::{FilterResult, Keycode, KeyboardHandle, Keysym}};
+/// # use smithay::backend::input::KeyState;
+/// # use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
+/// # use smithay::utils::SERIAL_COUNTER;
+/// use smithay::wayland::keyboard_shortcuts_inhibit::{
+///     EscapeChord, KeyboardShortcutsInhibitHandler, ShortcutFilterResult, ShortcutsInhibitFilter,
+/// };
+/// # fn process_shortcut(keysym: Keysym) -> Option<()> { None }
+/// # fn example<D: SeatHandler + KeyboardShortcutsInhibitHandler + 'static>(
+/// #     state: &mut D,
+/// #     seat: &Seat<D>,
+/// #     keyboard: &KeyboardHandle<D>,
+/// #     focus: Option<&WlSurface>,
+/// #     keycode: Keycode,
+/// # ) {
+/// let escape = EscapeChord {
+///     ctrl: true,
+///     alt: true,
+///     ..EscapeChord::new(Keysym::Escape)
+/// };
+/// let shortcuts = ShortcutsInhibitFilter::new(seat, focus).with_escape_chord(escape);
+/// let serial = SERIAL_COUNTER.next_serial();
+/// keyboard.input(state, keycode, KeyState::Pressed, serial, 0, |state, modifiers, handle| {
+///     let shortcut = process_shortcut(handle.modified_sym());
+///     match shortcuts.filter(state, modifiers, &handle, shortcut) {
+///         ShortcutFilterResult::Shortcut(action) => FilterResult::Intercept(Some(action)),
+///         ShortcutFilterResult::Escape(inhibitor) => {
+///             inhibitor.inactivate();
+///             FilterResult::Intercept(None)
+///         }
+///         ShortcutFilterResult::Forward => FilterResult::Forward,
+///     }
+/// });
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ShortcutsInhibitFilter {
+    inhibitor: Option<KeyboardShortcutsInhibitor>,
+    escape: Option<EscapeChord>,
+}
+
+impl ShortcutsInhibitFilter {
+    /// Create a new filter for the given focused surface
+    ///
+    /// Shortcuts are inhibited if the surface has an active inhibitor on the seat.
+    pub fn new<D>(seat: &Seat<D>, focus: Option<&WlSurface>) -> Self
+    where
+        D: SeatHandler + 'static,
+    {
+        let inhibitor = focus
+            .and_then(|surface| seat.keyboard_shortcuts_inhibitor_for_surface(surface))
+            .filter(|inhibitor| inhibitor.is_active());
+        ShortcutsInhibitFilter {
+            inhibitor,
+            escape: None,
+        }
+    }
+
+    /// Set the escape chord reaching the compositor regardless of the inhibitor
+    pub fn with_escape_chord(mut self, escape: EscapeChord) -> Self {
+        self.escape = Some(escape);
+        self
+    }
+
+    /// Returns the active inhibitor, if shortcuts are inhibited
+    pub fn inhibitor(&self) -> Option<&KeyboardShortcutsInhibitor> {
+        self.inhibitor.as_ref()
+    }
+
+    /// Filter a key press
+    ///
+    /// `shortcut` is the compositor shortcut bound to the key, if any.
+    /// If it is bypassed because of the inhibitor, [`KeyboardShortcutsInhibitHandler::shortcut_bypassed`]
+    /// is called.
+    pub fn filter<D, T>(
+        &self,
+        data: &mut D,
+        modifiers: &ModifiersState,
+        keysym: &KeysymHandle<'_>,
+        shortcut: Option<T>,
+    ) -> ShortcutFilterResult<T>
+    where
+        D: KeyboardShortcutsInhibitHandler,
+    {
+        let Some(inhibitor) = self.inhibitor.as_ref() else {
+            return shortcut
+                .map(ShortcutFilterResult::Shortcut)
+                .unwrap_or(ShortcutFilterResult::Forward);
+        };
+
+        if self
+            .escape
+            .as_ref()
+            .map(|escape| escape.matches(modifiers, keysym))
+            .unwrap_or(false)
+        {
+            return ShortcutFilterResult::Escape(inhibitor.clone());
+        }
+
+        if shortcut.is_some() {
+            data.shortcut_bypassed(inhibitor.clone());
+        }
+        ShortcutFilterResult::Forward
+    }
+}
+
 /// WP Keyboard shortcuts inhibit handler
 #[allow(unused_variables)]
 pub trait KeyboardShortcutsInhibitHandler {
@@ -235,6 +409,12 @@ pub trait KeyboardShortcutsInhibitHandler {
 
     /// Inhibitor got destoryed
     fn inhibitor_destroyed(&mut self, inhibitor: KeyboardShortcutsInhibitor) {}
+
+    /// A compositor shortcut was bypassed because of an active inhibitor
+    ///
+    /// Called by [`ShortcutsInhibitFilter::filter`], can be used to indicate to the user
+    /// that the shortcuts are passed through to the client.
+    fn shortcut_bypassed(&mut self, inhibitor: KeyboardShortcutsInhibitor) {}
 }
 
 /// Macro to delegate implementation of the keyboard shortcuts inhibit protocol
@@ -47,27 +47,43 @@
 
 pub mod alpha_modifier;
 pub mod buffer;
+pub mod color_representation;
 pub mod commit_timing;
 pub mod compositor;
 pub mod content_type;
 pub mod cursor_shape;
+pub mod custom_protocol;
 pub mod dmabuf;
 #[cfg(feature = "backend_drm")]
 pub mod drm_lease;
 #[cfg(feature = "backend_drm")]
 pub mod drm_syncobj;
+pub mod export_dmabuf;
 pub mod fifo;
 pub mod foreign_toplevel_list;
+pub mod foreign_toplevel_management;
 pub mod fractional_scale;
+#[cfg(feature = "frog_color_management")]
+pub mod frog_color_management;
+pub mod gamma_control;
 pub mod idle_inhibit;
 pub mod idle_notify;
+pub mod image_capture_source;
+pub mod image_copy_capture;
 pub mod input_method;
+#[cfg(feature = "wayland_introspection")]
+pub mod introspection;
 pub mod keyboard_shortcuts_inhibit;
 pub mod output;
+pub mod output_management;
+pub mod output_power_management;
 pub mod pointer_constraints;
 pub mod pointer_gestures;
+pub mod pointer_warp;
 pub mod presentation;
+pub mod protocol_error;
 pub mod relative_pointer;
+pub mod screencopy;
 pub mod seat;
 pub mod security_context;
 pub mod selection;
@@ -77,13 +93,21 @@ pub mod shm;
 pub mod single_pixel_buffer;
 pub mod socket;
 pub mod tablet_manager;
+pub mod tearing_control;
+#[cfg(test)]
+pub(crate) mod test_utils;
 pub mod text_input;
+#[cfg(feature = "libwayland_server_1_22")]
+pub mod transient_seat;
+pub mod version;
 pub mod viewporter;
 pub mod virtual_keyboard;
 pub mod xdg_activation;
 pub mod xdg_foreign;
 pub mod xdg_system_bell;
+pub mod xdg_toplevel_drag;
 pub mod xdg_toplevel_icon;
+pub mod xdg_toplevel_tag;
 #[cfg(feature = "xwayland")]
 pub mod xwayland_keyboard_grab;
 #[cfg(feature = "xwayland")]
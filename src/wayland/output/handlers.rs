@@ -11,7 +11,7 @@ use wayland_server::{
     Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource,
 };
 
-use crate::wayland::compositor::CompositorHandler;
+use crate::wayland::{compositor::CompositorHandler, version::VersionedResource};
 
 use super::{xdg::XdgOutput, Output, OutputHandler, OutputManagerState, OutputUserData, WlOutputData};
 
@@ -75,15 +75,15 @@ where
             output.mode(flags, mode.size.w, mode.size.h, mode.refresh);
         }
 
-        if output.version() >= 4 {
+        if output.supports(wl_output::EVT_NAME_SINCE) {
             output.name(inner.name.clone());
             output.description(inner.description.clone())
         }
 
-        if output.version() >= 2 {
+        if output.supports(wl_output::EVT_SCALE_SINCE) {
             output.scale(inner.scale.integer_scale());
-            output.done();
         }
+        output.send_since(wl_output::EVT_DONE_SINCE, WlOutput::done);
 
         // Send enter for surfaces already on this output.
         for surface in &inner.surfaces {
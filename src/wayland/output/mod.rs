@@ -68,6 +68,7 @@
 //!
 //! delegate_output!(State);
 //! # delegate_compositor!(State);
+//! # impl smithay::wayland::protocol_error::ProtocolErrorHandler for State {}
 //! ```
 
 mod handlers;
@@ -86,13 +87,14 @@ use wayland_protocols::xdg::xdg_output::zv1::server::zxdg_output_manager_v1::Zxd
 use wayland_server::{
     backend::{ClientId, GlobalId},
     protocol::{
-        wl_output::{Mode as WMode, Subpixel as WlSubpixel, Transform, WlOutput},
+        wl_output::{self, Mode as WMode, Subpixel as WlSubpixel, Transform, WlOutput},
         wl_surface,
     },
     Client, DisplayHandle, GlobalDispatch, Resource,
 };
 
 use crate::utils::{Logical, Point};
+use crate::wayland::version::VersionedResource;
 
 pub use self::handlers::XdgOutputUserData;
 
@@ -247,13 +249,11 @@ impl Output {
             if new_transform.is_some() || new_location.is_some() {
                 inner.send_geometry_to(&output);
             }
-            if (new_scale.is_some() || scale_changed) && output.version() >= 2 {
+            if (new_scale.is_some() || scale_changed) && output.supports(wl_output::EVT_SCALE_SINCE) {
                 let scale = (inner.scale.integer_scale() as f64 / client_scale).max(1.).ceil() as i32;
                 output.scale(scale);
             }
-            if output.version() >= 2 {
-                output.done();
-            }
+            output.send_since(wl_output::EVT_DONE_SINCE, WlOutput::done);
         }
     }
 
@@ -321,6 +321,11 @@ impl Output {
         }
     }
 
+    pub(crate) fn entered_surfaces(&self) -> Vec<wl_surface::WlSurface> {
+        let inner = self.inner.0.lock().unwrap();
+        inner.surfaces.iter().filter_map(|s| s.upgrade().ok()).collect()
+    }
+
     pub(crate) fn cleanup_surfaces(&self) {
         let mut inner = self.inner.0.lock().unwrap();
         inner.surfaces.retain(|s| s.is_alive());
@@ -6,13 +6,22 @@
 use std::sync::{atomic::Ordering, Arc, Mutex};
 
 use tracing::trace;
-use wayland_protocols::xdg::xdg_output::zv1::server::zxdg_output_v1::ZxdgOutputV1;
-use wayland_server::{protocol::wl_output::WlOutput, Resource, Weak};
+use wayland_protocols::xdg::xdg_output::zv1::server::zxdg_output_v1::{self, ZxdgOutputV1};
+use wayland_server::{
+    protocol::wl_output::{self, WlOutput},
+    Resource, Weak,
+};
 
-use crate::utils::{Logical, Physical, Point, Size, Transform};
+use crate::{
+    utils::{Logical, Physical, Point, Size, Transform},
+    wayland::version::VersionedResource,
+};
 
 use super::{Mode, OutputUserData, Scale, XdgOutputUserData};
 
+/// `xdg_output.done` is deprecated since version 3 in favor of `wl_output.done`
+const XDG_OUTPUT_DONE_DEPRECATED_SINCE: u32 = 3;
+
 #[derive(Debug)]
 pub(crate) struct Inner {
     name: String,
@@ -73,17 +82,13 @@ impl XdgOutput {
             xdg_output.logical_size(transformed_size.w, transformed_size.h);
         }
 
-        if xdg_output.version() >= 2 {
+        if xdg_output.supports(zxdg_output_v1::EVT_NAME_SINCE) {
             xdg_output.name(inner.name.clone());
             xdg_output.description(inner.description.clone());
         }
 
-        // xdg_output.done() is deprecated since version 3
-        if xdg_output.version() < 3 {
-            xdg_output.done();
-        }
-
-        wl_output.done();
+        xdg_output.send_before(XDG_OUTPUT_DONE_DEPRECATED_SINCE, ZxdgOutputV1::done);
+        wl_output.send_since(wl_output::EVT_DONE_SINCE, WlOutput::done);
 
         inner.instances.push(xdg_output.downgrade());
     }
@@ -138,10 +143,7 @@ impl XdgOutput {
                 instance.logical_position(logical_position.x, logical_position.y);
             }
 
-            // xdg_output.done() is deprecated since version 3
-            if instance.version() < 3 {
-                instance.done();
-            }
+            instance.send_before(XDG_OUTPUT_DONE_DEPRECATED_SINCE, ZxdgOutputV1::done);
 
             // No need for wl_output.done() here, it will be called by caller (super::Output::change_current_state)
         }
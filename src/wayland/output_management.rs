@@ -0,0 +1,1094 @@
+//! Utilities for handling the `wlr-output-management` protocol
+//!
+//! This protocol allows privileged clients, like `wlr-randr` or `kanshi`, to list the outputs
+//! of the compositor and to change their mode, position, transform, scale and whether they are
+//! enabled.
+//!
+//! Outputs are advertised as heads. Add every [`Output`] of your compositor using
+//! [`OutputManagementState::add_head`], including disabled ones, and call
+//! [`OutputManagementState::update`] after changing the state of any output, to advertise the changes
+//! to clients.
+//!
+//! Configurations requested by clients are passed to [`OutputManagementHandler::apply_configuration`]
+//! and [`OutputManagementHandler::test_configuration`]. Configurations are only forwarded, if they are
+//! based on the current state of all heads. [`OutputConfiguration::changes`] lists the requested changes
+//! compared to this state.
+//!
+//! ## How to use it
+//!
+//! ```
+//! use smithay::delegate_output_management;
+//! use smithay::output::Scale;
+//! use smithay::wayland::output_management::{
+//!     ModeConfiguration, OutputConfiguration, OutputManagementHandler, OutputManagementState,
+//! };
+//!
+//! # struct State { output_management_state: OutputManagementState }
+//! # let mut display = wayland_server::Display::<State>::new().unwrap();
+//! // Create the output management state
+//! let output_management_state =
+//!     OutputManagementState::new::<State, _>(&display.handle(), |_client| true);
+//!
+//! // Insert the OutputManagementState into your state.
+//!
+//! // Implement the necessary trait.
+//! impl OutputManagementHandler for State {
+//!     fn output_management_state(&mut self) -> &mut OutputManagementState {
+//!         &mut self.output_management_state
+//!     }
+//!
+//!     fn apply_configuration(&mut self, config: OutputConfiguration) -> bool {
+//!         for (output, changes) in config.changes() {
+//!             let mode = match changes.mode {
+//!                 Some(ModeConfiguration::Mode(mode)) => Some(mode),
+//!                 // custom modes are not supported
+//!                 Some(ModeConfiguration::Custom { .. }) => return false,
+//!                 None => None,
+//!             };
+//!             output.change_current_state(
+//!                 mode,
+//!                 changes.transform,
+//!                 changes.scale.map(Scale::Fractional),
+//!                 changes.position,
+//!             );
+//!             if let Some(enabled) = changes.enabled {
+//!                 // enable or disable the output and update the state of its head
+//!                 self.output_management_state.set_head_enabled(output, enabled);
+//!             }
+//!         }
+//!         true
+//!     }
+//!
+//!     fn test_configuration(&mut self, config: OutputConfiguration) -> bool {
+//!         // check, whether the configuration could be applied
+//!         true
+//!     }
+//! }
+//! delegate_output_management!(State);
+//! ```
+
+use std::sync::Mutex;
+
+use wayland_protocols_wlr::output_management::v1::server::{
+    zwlr_output_configuration_head_v1::{self, ZwlrOutputConfigurationHeadV1},
+    zwlr_output_configuration_v1::{self, ZwlrOutputConfigurationV1},
+    zwlr_output_head_v1::{self, ZwlrOutputHeadV1},
+    zwlr_output_manager_v1::{self, ZwlrOutputManagerV1},
+    zwlr_output_mode_v1::{self, ZwlrOutputModeV1},
+};
+use wayland_server::{
+    backend::{ClientId, GlobalId},
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource, WEnum,
+};
+
+use crate::{
+    output::{Mode, Output, WeakOutput},
+    utils::{Logical, Physical, Point, Size, Transform},
+};
+
+const MANAGER_VERSION: u32 = 4;
+
+/// Handler trait for wlr-output-management
+pub trait OutputManagementHandler:
+    GlobalDispatch<ZwlrOutputManagerV1, OutputManagementGlobalData>
+    + Dispatch<ZwlrOutputManagerV1, ()>
+    + Dispatch<ZwlrOutputHeadV1, OutputHeadData>
+    + Dispatch<ZwlrOutputModeV1, OutputModeData>
+    + Dispatch<ZwlrOutputConfigurationV1, OutputConfigurationData>
+    + Dispatch<ZwlrOutputConfigurationHeadV1, OutputConfigurationHeadData>
+    + 'static
+{
+    /// [`OutputManagementState`] getter
+    fn output_management_state(&mut self) -> &mut OutputManagementState;
+
+    /// A client requested to apply a configuration
+    ///
+    /// Returns whether the configuration was applied. A failed configuration should not leave
+    /// any changes behind. Changes to the outputs are advertised after this returns.
+    fn apply_configuration(&mut self, config: OutputConfiguration) -> bool;
+
+    /// A client requested to test a configuration
+    ///
+    /// Returns whether the configuration could be applied.
+    fn test_configuration(&mut self, config: OutputConfiguration) -> bool;
+}
+
+/// Mode requested for a head
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModeConfiguration {
+    /// One of the advertised modes of the output
+    Mode(Mode),
+    /// A custom mode
+    Custom {
+        /// The size of the mode, in pixels
+        size: Size<i32, Physical>,
+        /// The refresh rate in millihertz, `None` if the compositor should pick one
+        refresh: Option<i32>,
+    },
+}
+
+impl ModeConfiguration {
+    fn matches(&self, mode: Option<Mode>) -> bool {
+        match (*self, mode) {
+            (ModeConfiguration::Mode(requested), Some(mode)) => requested == mode,
+            (ModeConfiguration::Custom { size, refresh }, Some(mode)) => {
+                size == mode.size && refresh.unwrap_or(mode.refresh) == mode.refresh
+            }
+            (_, None) => false,
+        }
+    }
+}
+
+/// Configuration requested for a single head
+#[derive(Debug, Clone)]
+pub struct HeadConfiguration {
+    /// Output of the head
+    pub output: Output,
+    /// Whether the head should be enabled
+    ///
+    /// The other properties are never set for disabled heads.
+    pub enabled: bool,
+    /// The requested mode
+    pub mode: Option<ModeConfiguration>,
+    /// The requested position in the global compositor space
+    pub position: Option<Point<i32, Logical>>,
+    /// The requested transform
+    pub transform: Option<Transform>,
+    /// The requested scale
+    pub scale: Option<f64>,
+    /// Whether adaptive sync should be enabled
+    pub adaptive_sync: Option<bool>,
+    current: HeadSnapshot,
+}
+
+impl HeadConfiguration {
+    /// Returns the properties differing from the current state of the head
+    pub fn changes(&self) -> HeadChanges {
+        let current = &self.current;
+        if !self.enabled {
+            return HeadChanges {
+                enabled: current.enabled.then_some(false),
+                ..Default::default()
+            };
+        }
+
+        HeadChanges {
+            enabled: (!current.enabled).then_some(true),
+            mode: self.mode.filter(|mode| !mode.matches(current.current_mode)),
+            position: self.position.filter(|position| *position != current.position),
+            transform: self.transform.filter(|transform| *transform != current.transform),
+            scale: self.scale.filter(|scale| *scale != current.scale),
+            adaptive_sync: self
+                .adaptive_sync
+                .filter(|adaptive_sync| *adaptive_sync != current.adaptive_sync),
+        }
+    }
+}
+
+/// Changes requested for a head, compared to its current state
+///
+/// See [`HeadConfiguration::changes`].
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct HeadChanges {
+    /// The head should be enabled or disabled
+    pub enabled: Option<bool>,
+    /// The mode should be changed
+    pub mode: Option<ModeConfiguration>,
+    /// The position should be changed
+    pub position: Option<Point<i32, Logical>>,
+    /// The transform should be changed
+    pub transform: Option<Transform>,
+    /// The scale should be changed
+    pub scale: Option<f64>,
+    /// Adaptive sync should be enabled or disabled
+    pub adaptive_sync: Option<bool>,
+}
+
+impl HeadChanges {
+    /// Returns whether nothing changes
+    pub fn is_empty(&self) -> bool {
+        *self == HeadChanges::default()
+    }
+}
+
+/// Configuration of all heads requested by a client
+#[derive(Debug, Clone)]
+pub struct OutputConfiguration {
+    /// Configurations of all heads
+    pub heads: Vec<HeadConfiguration>,
+}
+
+impl OutputConfiguration {
+    /// Returns the outputs, which should be changed, with their changes
+    pub fn changes(&self) -> impl Iterator<Item = (&Output, HeadChanges)> + '_ {
+        self.heads
+            .iter()
+            .map(|head| (&head.output, head.changes()))
+            .filter(|(_, changes)| !changes.is_empty())
+    }
+}
+
+// State of a head as advertised to clients
+#[derive(Debug, Clone, PartialEq)]
+struct HeadSnapshot {
+    enabled: bool,
+    modes: Vec<Mode>,
+    preferred_mode: Option<Mode>,
+    current_mode: Option<Mode>,
+    position: Point<i32, Logical>,
+    transform: Transform,
+    scale: f64,
+    adaptive_sync: bool,
+}
+
+#[derive(Debug)]
+struct HeadInstance {
+    head: ZwlrOutputHeadV1,
+    modes: Vec<(Mode, ZwlrOutputModeV1)>,
+}
+
+#[derive(Debug)]
+struct Head {
+    output: Output,
+    enabled: bool,
+    adaptive_sync: bool,
+    // `None` until the head was advertised by `OutputManagementState::update`
+    advertised: Option<HeadSnapshot>,
+    instances: Vec<HeadInstance>,
+}
+
+impl Head {
+    fn snapshot(&self) -> HeadSnapshot {
+        HeadSnapshot {
+            enabled: self.enabled,
+            modes: self.output.modes(),
+            preferred_mode: self.output.preferred_mode(),
+            current_mode: self.output.current_mode(),
+            position: self.output.current_location(),
+            transform: self.output.current_transform(),
+            scale: self.output.current_scale().fractional_scale(),
+            adaptive_sync: self.adaptive_sync,
+        }
+    }
+
+    fn finish(&mut self) {
+        for instance in self.instances.drain(..) {
+            for (_, mode) in instance.modes {
+                mode.finished();
+            }
+            instance.head.finished();
+        }
+    }
+}
+
+/// State of the [`ZwlrOutputManagerV1`] global
+#[derive(Debug)]
+pub struct OutputManagementState {
+    global: GlobalId,
+    dh: DisplayHandle,
+    serial: u32,
+    heads: Vec<Head>,
+    removed: Vec<Head>,
+    managers: Vec<ZwlrOutputManagerV1>,
+}
+
+impl OutputManagementState {
+    /// Create a new [`ZwlrOutputManagerV1`] global
+    ///
+    /// The filter decides which clients are allowed to configure outputs.
+    pub fn new<D, F>(display: &DisplayHandle, filter: F) -> Self
+    where
+        D: OutputManagementHandler,
+        F: for<'c> Fn(&'c Client) -> bool + Send + Sync + 'static,
+    {
+        let data = OutputManagementGlobalData {
+            filter: Box::new(filter),
+        };
+        let global = display.create_global::<D, ZwlrOutputManagerV1, _>(MANAGER_VERSION, data);
+
+        OutputManagementState {
+            global,
+            dh: display.clone(),
+            serial: 0,
+            heads: Vec::new(),
+            removed: Vec::new(),
+            managers: Vec::new(),
+        }
+    }
+
+    /// Returns the id of the [`ZwlrOutputManagerV1`] global
+    pub fn global(&self) -> GlobalId {
+        self.global.clone()
+    }
+
+    /// Returns the serial of the advertised configuration
+    pub fn serial(&self) -> u32 {
+        self.serial
+    }
+
+    /// Add a head for an output
+    ///
+    /// The head is advertised on the next [`OutputManagementState::update`].
+    pub fn add_head(&mut self, output: &Output, enabled: bool) {
+        if self.heads.iter().any(|head| head.output == *output) {
+            return;
+        }
+
+        self.heads.push(Head {
+            output: output.clone(),
+            enabled,
+            adaptive_sync: false,
+            advertised: None,
+            instances: Vec::new(),
+        });
+    }
+
+    /// Remove the head of an output
+    ///
+    /// The removal is advertised on the next [`OutputManagementState::update`].
+    pub fn remove_head(&mut self, output: &Output) {
+        if let Some(pos) = self.heads.iter().position(|head| head.output == *output) {
+            let head = self.heads.remove(pos);
+            self.removed.push(head);
+        }
+    }
+
+    /// Returns the outputs of all heads
+    pub fn heads(&self) -> impl Iterator<Item = &Output> {
+        self.heads.iter().map(|head| &head.output)
+    }
+
+    /// Set whether the head of an output is enabled
+    pub fn set_head_enabled(&mut self, output: &Output, enabled: bool) {
+        if let Some(head) = self.heads.iter_mut().find(|head| head.output == *output) {
+            head.enabled = enabled;
+        }
+    }
+
+    /// Returns whether the head of an output is enabled
+    pub fn is_head_enabled(&self, output: &Output) -> bool {
+        self.heads
+            .iter()
+            .find(|head| head.output == *output)
+            .is_some_and(|head| head.enabled)
+    }
+
+    /// Set whether adaptive sync is enabled for the head of an output
+    pub fn set_head_adaptive_sync(&mut self, output: &Output, adaptive_sync: bool) {
+        if let Some(head) = self.heads.iter_mut().find(|head| head.output == *output) {
+            head.adaptive_sync = adaptive_sync;
+        }
+    }
+
+    /// Advertise the changes of all heads since the last update
+    ///
+    /// This compares the current state of the outputs with the advertised state, so it is fine to call
+    /// this after every change. Pending configurations of clients, which are based on an older state,
+    /// are cancelled.
+    pub fn update<D: OutputManagementHandler>(&mut self) {
+        let mut changed = !self.removed.is_empty();
+        for mut head in self.removed.drain(..) {
+            head.finish();
+        }
+
+        for head in &mut self.heads {
+            let new = head.snapshot();
+            match head.advertised.take() {
+                Some(old) => {
+                    if old != new {
+                        for instance in &mut head.instances {
+                            send_changes::<D>(&self.dh, instance, &old, &new);
+                        }
+                        changed = true;
+                    }
+                }
+                None => {
+                    for manager in &self.managers {
+                        if let Some(instance) = create_head::<D>(&self.dh, manager, &head.output, &new) {
+                            head.instances.push(instance);
+                        }
+                    }
+                    changed = true;
+                }
+            }
+            head.advertised = Some(new);
+        }
+
+        if changed {
+            self.serial = self.serial.wrapping_add(1);
+            for manager in &self.managers {
+                manager.done(self.serial);
+            }
+        }
+    }
+
+    fn configuration(
+        &self,
+        heads: &[(WeakOutput, Option<ZwlrOutputConfigurationHeadV1>)],
+    ) -> Option<OutputConfiguration> {
+        let heads = heads
+            .iter()
+            .map(|(output, config_head)| {
+                let output = output.upgrade()?;
+                let current = self
+                    .heads
+                    .iter()
+                    .find(|head| head.output == output)?
+                    .advertised
+                    .clone()?;
+                let pending = config_head
+                    .as_ref()
+                    .and_then(|config_head| config_head.data::<OutputConfigurationHeadData>())
+                    .map(|data| *data.pending.lock().unwrap());
+                Some(match pending {
+                    Some(pending) => HeadConfiguration {
+                        output,
+                        enabled: true,
+                        mode: pending.mode,
+                        position: pending.position,
+                        transform: pending.transform,
+                        scale: pending.scale,
+                        adaptive_sync: pending.adaptive_sync,
+                        current,
+                    },
+                    None => HeadConfiguration {
+                        output,
+                        enabled: false,
+                        mode: None,
+                        position: None,
+                        transform: None,
+                        scale: None,
+                        adaptive_sync: None,
+                        current,
+                    },
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(OutputConfiguration { heads })
+    }
+}
+
+fn create_head<D: OutputManagementHandler>(
+    dh: &DisplayHandle,
+    manager: &ZwlrOutputManagerV1,
+    output: &Output,
+    snapshot: &HeadSnapshot,
+) -> Option<HeadInstance> {
+    let client = dh.get_client(manager.id()).ok()?;
+    let head = client
+        .create_resource::<ZwlrOutputHeadV1, _, D>(
+            dh,
+            manager.version(),
+            OutputHeadData {
+                output: output.downgrade(),
+            },
+        )
+        .ok()?;
+    manager.head(&head);
+
+    head.name(output.name());
+    head.description(output.description());
+    let physical = output.physical_properties();
+    if physical.size.w > 0 && physical.size.h > 0 {
+        head.physical_size(physical.size.w, physical.size.h);
+    }
+    if head.version() >= 2 {
+        head.make(physical.make);
+        head.model(physical.model);
+    }
+
+    let mut instance = HeadInstance {
+        head,
+        modes: Vec::new(),
+    };
+    for mode in &snapshot.modes {
+        create_mode::<D>(dh, &client, &mut instance, output, *mode, snapshot.preferred_mode);
+    }
+    send_state(&instance, snapshot);
+
+    Some(instance)
+}
+
+fn create_mode<D: OutputManagementHandler>(
+    dh: &DisplayHandle,
+    client: &Client,
+    instance: &mut HeadInstance,
+    output: &Output,
+    mode: Mode,
+    preferred_mode: Option<Mode>,
+) {
+    let Ok(resource) = client.create_resource::<ZwlrOutputModeV1, _, D>(
+        dh,
+        instance.head.version(),
+        OutputModeData {
+            output: output.downgrade(),
+            mode,
+        },
+    ) else {
+        return;
+    };
+    instance.head.mode(&resource);
+    resource.size(mode.size.w, mode.size.h);
+    if mode.refresh > 0 {
+        resource.refresh(mode.refresh);
+    }
+    if preferred_mode == Some(mode) {
+        resource.preferred();
+    }
+    instance.modes.push((mode, resource));
+}
+
+// Sends the state of an enabled head, or just the enabled event for disabled heads
+fn send_state(instance: &HeadInstance, snapshot: &HeadSnapshot) {
+    let head = &instance.head;
+    head.enabled(snapshot.enabled as i32);
+    if !snapshot.enabled {
+        return;
+    }
+
+    if let Some(mode) = snapshot
+        .current_mode
+        .and_then(|current| instance.modes.iter().find(|(mode, _)| *mode == current))
+    {
+        head.current_mode(&mode.1);
+    }
+    head.position(snapshot.position.x, snapshot.position.y);
+    head.transform(snapshot.transform.into());
+    head.scale(snapshot.scale);
+    if head.version() >= 4 {
+        head.adaptive_sync(adaptive_sync_state(snapshot.adaptive_sync));
+    }
+}
+
+fn send_changes<D: OutputManagementHandler>(
+    dh: &DisplayHandle,
+    instance: &mut HeadInstance,
+    old: &HeadSnapshot,
+    new: &HeadSnapshot,
+) {
+    instance.modes.retain(|(mode, resource)| {
+        let keep = new.modes.contains(mode);
+        if !keep {
+            resource.finished();
+        }
+        keep
+    });
+    if let Ok(client) = dh.get_client(instance.head.id()) {
+        if let Some(output) = instance
+            .head
+            .data::<OutputHeadData>()
+            .and_then(|data| data.output.upgrade())
+        {
+            for mode in new.modes.iter().filter(|mode| !old.modes.contains(mode)) {
+                create_mode::<D>(dh, &client, instance, &output, *mode, new.preferred_mode);
+            }
+        }
+    }
+
+    if old.enabled != new.enabled {
+        send_state(instance, new);
+        return;
+    }
+    if !new.enabled {
+        return;
+    }
+
+    let head = &instance.head;
+    if old.current_mode != new.current_mode {
+        if let Some(mode) = new
+            .current_mode
+            .and_then(|current| instance.modes.iter().find(|(mode, _)| *mode == current))
+        {
+            head.current_mode(&mode.1);
+        }
+    }
+    if old.position != new.position {
+        head.position(new.position.x, new.position.y);
+    }
+    if old.transform != new.transform {
+        head.transform(new.transform.into());
+    }
+    if old.scale != new.scale {
+        head.scale(new.scale);
+    }
+    if old.adaptive_sync != new.adaptive_sync && head.version() >= 4 {
+        head.adaptive_sync(adaptive_sync_state(new.adaptive_sync));
+    }
+}
+
+fn adaptive_sync_state(enabled: bool) -> zwlr_output_head_v1::AdaptiveSyncState {
+    if enabled {
+        zwlr_output_head_v1::AdaptiveSyncState::Enabled
+    } else {
+        zwlr_output_head_v1::AdaptiveSyncState::Disabled
+    }
+}
+
+#[allow(missing_debug_implementations)]
+#[doc(hidden)]
+pub struct OutputManagementGlobalData {
+    filter: Box<dyn for<'c> Fn(&'c Client) -> bool + Send + Sync>,
+}
+
+/// User data of the [`ZwlrOutputHeadV1`] resource
+#[derive(Debug)]
+pub struct OutputHeadData {
+    output: WeakOutput,
+}
+
+/// User data of the [`ZwlrOutputModeV1`] resource
+#[derive(Debug)]
+pub struct OutputModeData {
+    output: WeakOutput,
+    mode: Mode,
+}
+
+#[derive(Debug, Default)]
+struct PendingConfiguration {
+    heads: Vec<(WeakOutput, Option<ZwlrOutputConfigurationHeadV1>)>,
+    used: bool,
+}
+
+/// User data of the [`ZwlrOutputConfigurationV1`] resource
+#[derive(Debug)]
+pub struct OutputConfigurationData {
+    serial: u32,
+    pending: Mutex<PendingConfiguration>,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct PendingHead {
+    mode: Option<ModeConfiguration>,
+    position: Option<Point<i32, Logical>>,
+    transform: Option<Transform>,
+    scale: Option<f64>,
+    adaptive_sync: Option<bool>,
+}
+
+/// User data of the [`ZwlrOutputConfigurationHeadV1`] resource
+#[derive(Debug)]
+pub struct OutputConfigurationHeadData {
+    output: WeakOutput,
+    pending: Mutex<PendingHead>,
+}
+
+impl<D: OutputManagementHandler> GlobalDispatch<ZwlrOutputManagerV1, OutputManagementGlobalData, D>
+    for OutputManagementState
+{
+    fn bind(
+        state: &mut D,
+        dh: &DisplayHandle,
+        _client: &Client,
+        resource: New<ZwlrOutputManagerV1>,
+        _global_data: &OutputManagementGlobalData,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        let manager = data_init.init(resource, ());
+
+        let state = state.output_management_state();
+        for head in &mut state.heads {
+            let Some(snapshot) = head.advertised.as_ref() else {
+                continue;
+            };
+            if let Some(instance) = create_head::<D>(dh, &manager, &head.output, snapshot) {
+                head.instances.push(instance);
+            }
+        }
+        manager.done(state.serial);
+
+        state.managers.push(manager);
+    }
+
+    fn can_view(client: Client, global_data: &OutputManagementGlobalData) -> bool {
+        (global_data.filter)(&client)
+    }
+}
+
+impl<D: OutputManagementHandler> Dispatch<ZwlrOutputManagerV1, (), D> for OutputManagementState {
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        manager: &ZwlrOutputManagerV1,
+        request: zwlr_output_manager_v1::Request,
+        _data: &(),
+        _dh: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zwlr_output_manager_v1::Request::CreateConfiguration { id, serial } => {
+                data_init.init(
+                    id,
+                    OutputConfigurationData {
+                        serial,
+                        pending: Mutex::new(PendingConfiguration::default()),
+                    },
+                );
+            }
+            zwlr_output_manager_v1::Request::Stop => {
+                state.output_management_state().managers.retain(|m| m != manager);
+                manager.finished();
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(state: &mut D, _client: ClientId, resource: &ZwlrOutputManagerV1, _data: &()) {
+        state.output_management_state().managers.retain(|m| m != resource);
+    }
+}
+
+impl<D: OutputManagementHandler> Dispatch<ZwlrOutputHeadV1, OutputHeadData, D> for OutputManagementState {
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        _resource: &ZwlrOutputHeadV1,
+        request: zwlr_output_head_v1::Request,
+        _data: &OutputHeadData,
+        _dh: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zwlr_output_head_v1::Request::Release => {}
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(state: &mut D, _client: ClientId, resource: &ZwlrOutputHeadV1, _data: &OutputHeadData) {
+        for head in &mut state.output_management_state().heads {
+            head.instances.retain(|instance| instance.head != *resource);
+        }
+    }
+}
+
+impl<D: OutputManagementHandler> Dispatch<ZwlrOutputModeV1, OutputModeData, D> for OutputManagementState {
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        _resource: &ZwlrOutputModeV1,
+        request: zwlr_output_mode_v1::Request,
+        _data: &OutputModeData,
+        _dh: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zwlr_output_mode_v1::Request::Release => {}
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(state: &mut D, _client: ClientId, resource: &ZwlrOutputModeV1, _data: &OutputModeData) {
+        for head in &mut state.output_management_state().heads {
+            for instance in &mut head.instances {
+                instance.modes.retain(|(_, mode)| mode != resource);
+            }
+        }
+    }
+}
+
+impl<D: OutputManagementHandler> Dispatch<ZwlrOutputConfigurationV1, OutputConfigurationData, D>
+    for OutputManagementState
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        resource: &ZwlrOutputConfigurationV1,
+        request: zwlr_output_configuration_v1::Request,
+        data: &OutputConfigurationData,
+        _dh: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        let mut pending = data.pending.lock().unwrap();
+        if pending.used && !matches!(request, zwlr_output_configuration_v1::Request::Destroy) {
+            resource.post_error(
+                zwlr_output_configuration_v1::Error::AlreadyUsed,
+                "configuration was already applied or tested",
+            );
+            return;
+        }
+
+        match request {
+            zwlr_output_configuration_v1::Request::EnableHead { id, head } => {
+                let output = head.data::<OutputHeadData>().unwrap().output.clone();
+                if pending.heads.iter().any(|(o, _)| *o == output) {
+                    resource.post_error(
+                        zwlr_output_configuration_v1::Error::AlreadyConfiguredHead,
+                        "head was already configured",
+                    );
+                    return;
+                }
+
+                let config_head = data_init.init(
+                    id,
+                    OutputConfigurationHeadData {
+                        output: output.clone(),
+                        pending: Mutex::new(PendingHead::default()),
+                    },
+                );
+                pending.heads.push((output, Some(config_head)));
+            }
+            zwlr_output_configuration_v1::Request::DisableHead { head } => {
+                let output = head.data::<OutputHeadData>().unwrap().output.clone();
+                if pending.heads.iter().any(|(o, _)| *o == output) {
+                    resource.post_error(
+                        zwlr_output_configuration_v1::Error::AlreadyConfiguredHead,
+                        "head was already configured",
+                    );
+                    return;
+                }
+
+                pending.heads.push((output, None));
+            }
+            request @ (zwlr_output_configuration_v1::Request::Apply
+            | zwlr_output_configuration_v1::Request::Test) => {
+                pending.used = true;
+                let heads = std::mem::take(&mut pending.heads);
+                drop(pending);
+
+                let output_management_state = state.output_management_state();
+                if data.serial != output_management_state.serial {
+                    resource.cancelled();
+                    return;
+                }
+
+                let unconfigured = output_management_state
+                    .heads
+                    .iter()
+                    .filter(|head| head.advertised.is_some())
+                    .any(|head| !heads.iter().any(|(output, _)| head.output == *output));
+                if unconfigured {
+                    resource.post_error(
+                        zwlr_output_configuration_v1::Error::UnconfiguredHead,
+                        "not all heads were configured",
+                    );
+                    return;
+                }
+
+                // heads of removed outputs, which were not advertised as finished yet
+                let Some(config) = output_management_state.configuration(&heads) else {
+                    resource.cancelled();
+                    return;
+                };
+
+                let apply = matches!(request, zwlr_output_configuration_v1::Request::Apply);
+                let succeeded = if apply {
+                    state.apply_configuration(config)
+                } else {
+                    state.test_configuration(config)
+                };
+
+                if succeeded {
+                    resource.succeeded();
+                    if apply {
+                        state.output_management_state().update::<D>();
+                    }
+                } else {
+                    resource.failed();
+                }
+            }
+            zwlr_output_configuration_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D: OutputManagementHandler> Dispatch<ZwlrOutputConfigurationHeadV1, OutputConfigurationHeadData, D>
+    for OutputManagementState
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        resource: &ZwlrOutputConfigurationHeadV1,
+        request: zwlr_output_configuration_head_v1::Request,
+        data: &OutputConfigurationHeadData,
+        _dh: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        let mut pending = data.pending.lock().unwrap();
+
+        macro_rules! set_once {
+            ($field:ident, $value:expr) => {{
+                if pending.$field.is_some() {
+                    resource.post_error(
+                        zwlr_output_configuration_head_v1::Error::AlreadySet,
+                        concat!(stringify!($field), " was already set"),
+                    );
+                    return;
+                }
+                pending.$field = Some($value);
+            }};
+        }
+
+        match request {
+            zwlr_output_configuration_head_v1::Request::SetMode { mode } => {
+                let mode_data = mode.data::<OutputModeData>().unwrap();
+                if mode_data.output != data.output {
+                    resource.post_error(
+                        zwlr_output_configuration_head_v1::Error::InvalidMode,
+                        "mode doesn't belong to head",
+                    );
+                    return;
+                }
+                set_once!(mode, ModeConfiguration::Mode(mode_data.mode));
+            }
+            zwlr_output_configuration_head_v1::Request::SetCustomMode {
+                width,
+                height,
+                refresh,
+            } => {
+                if width <= 0 || height <= 0 || refresh < 0 {
+                    resource.post_error(
+                        zwlr_output_configuration_head_v1::Error::InvalidCustomMode,
+                        "invalid custom mode",
+                    );
+                    return;
+                }
+                set_once!(
+                    mode,
+                    ModeConfiguration::Custom {
+                        size: (width, height).into(),
+                        refresh: (refresh > 0).then_some(refresh),
+                    }
+                );
+            }
+            zwlr_output_configuration_head_v1::Request::SetPosition { x, y } => {
+                set_once!(position, (x, y).into());
+            }
+            zwlr_output_configuration_head_v1::Request::SetTransform { transform } => {
+                let WEnum::Value(transform) = transform else {
+                    resource.post_error(
+                        zwlr_output_configuration_head_v1::Error::InvalidTransform,
+                        "invalid transform",
+                    );
+                    return;
+                };
+                set_once!(transform, transform.into());
+            }
+            zwlr_output_configuration_head_v1::Request::SetScale { scale } => {
+                if scale <= 0.0 {
+                    resource.post_error(
+                        zwlr_output_configuration_head_v1::Error::InvalidScale,
+                        "scale must be positive",
+                    );
+                    return;
+                }
+                set_once!(scale, scale);
+            }
+            zwlr_output_configuration_head_v1::Request::SetAdaptiveSync { state } => {
+                let enabled = match state {
+                    WEnum::Value(zwlr_output_head_v1::AdaptiveSyncState::Enabled) => true,
+                    WEnum::Value(zwlr_output_head_v1::AdaptiveSyncState::Disabled) => false,
+                    _ => {
+                        resource.post_error(
+                            zwlr_output_configuration_head_v1::Error::InvalidAdaptiveSyncState,
+                            "invalid adaptive sync state",
+                        );
+                        return;
+                    }
+                };
+                set_once!(adaptive_sync, enabled);
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// Macro to delegate implementation of wlr-output-management to [`OutputManagementState`].
+///
+/// You must also implement [`OutputManagementHandler`] to use this.
+#[macro_export]
+macro_rules! delegate_output_management {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        $crate::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols_wlr::output_management::v1::server::zwlr_output_manager_v1::ZwlrOutputManagerV1: $crate::wayland::output_management::OutputManagementGlobalData
+        ] => $crate::wayland::output_management::OutputManagementState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols_wlr::output_management::v1::server::zwlr_output_manager_v1::ZwlrOutputManagerV1: ()
+        ] => $crate::wayland::output_management::OutputManagementState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols_wlr::output_management::v1::server::zwlr_output_head_v1::ZwlrOutputHeadV1: $crate::wayland::output_management::OutputHeadData
+        ] => $crate::wayland::output_management::OutputManagementState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols_wlr::output_management::v1::server::zwlr_output_mode_v1::ZwlrOutputModeV1: $crate::wayland::output_management::OutputModeData
+        ] => $crate::wayland::output_management::OutputManagementState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols_wlr::output_management::v1::server::zwlr_output_configuration_v1::ZwlrOutputConfigurationV1: $crate::wayland::output_management::OutputConfigurationData
+        ] => $crate::wayland::output_management::OutputManagementState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols_wlr::output_management::v1::server::zwlr_output_configuration_head_v1::ZwlrOutputConfigurationHeadV1: $crate::wayland::output_management::OutputConfigurationHeadData
+        ] => $crate::wayland::output_management::OutputManagementState);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::test_output;
+
+    fn head(enabled: bool) -> HeadConfiguration {
+        let output = test_output("test", (1920, 1080));
+        let mode = output.current_mode().unwrap();
+        HeadConfiguration {
+            output,
+            enabled,
+            mode: None,
+            position: None,
+            transform: None,
+            scale: None,
+            adaptive_sync: None,
+            current: HeadSnapshot {
+                enabled: true,
+                modes: vec![mode],
+                preferred_mode: Some(mode),
+                current_mode: Some(mode),
+                position: (0, 0).into(),
+                transform: Transform::Normal,
+                scale: 1.0,
+                adaptive_sync: false,
+            },
+        }
+    }
+
+    #[test]
+    fn head_changes() {
+        let disabled = head(false);
+        assert_eq!(
+            disabled.changes(),
+            HeadChanges {
+                enabled: Some(false),
+                ..Default::default()
+            }
+        );
+
+        // requesting the current state changes nothing
+        let mut unchanged = head(true);
+        unchanged.mode = Some(ModeConfiguration::Custom {
+            size: (1920, 1080).into(),
+            refresh: None,
+        });
+        unchanged.position = Some((0, 0).into());
+        unchanged.scale = Some(1.0);
+        assert!(unchanged.changes().is_empty());
+
+        let mut changed = head(true);
+        changed.mode = Some(ModeConfiguration::Custom {
+            size: (1920, 1080).into(),
+            refresh: Some(144_000),
+        });
+        changed.transform = Some(Transform::_90);
+        changed.adaptive_sync = Some(true);
+        assert_eq!(
+            changed.changes(),
+            HeadChanges {
+                mode: changed.mode,
+                transform: Some(Transform::_90),
+                adaptive_sync: Some(true),
+                ..Default::default()
+            }
+        );
+    }
+}
@@ -0,0 +1,333 @@
+//! Utilities for handling the `wlr-output-power-management` protocol
+//!
+//! This protocol allows privileged clients, like idle daemons, to turn outputs on and off.
+//! Only one client can control the power mode of an output at a time.
+//!
+//! Requests of clients are passed to [`OutputPowerManagementHandler::set_mode`]. Once the mode
+//! was applied, or whenever the compositor changes the power mode of an output by itself, call
+//! [`OutputPowerManagementState::set_output_mode`] to notify clients about it. This also updates
+//! [`Output::is_powered`], which stops frame callbacks to surfaces only shown on outputs
+//! that are turned off.
+//!
+//! With the DRM backend, outputs can be turned off and on using
+//! [`DrmCompositor::set_powered`](crate::backend::drm::compositor::DrmCompositor::set_powered).
+//!
+//! ## How to use it
+//!
+//! ```
+//! use smithay::delegate_output_power_management;
+//! use smithay::output::Output;
+//! use smithay::wayland::output_power_management::{
+//!     OutputPowerManagementHandler, OutputPowerManagementState, OutputPowerMode,
+//! };
+//!
+//! # struct State { output_power_management_state: OutputPowerManagementState }
+//! # let mut display = wayland_server::Display::<State>::new().unwrap();
+//! // Create the output power management state
+//! let output_power_management_state =
+//!     OutputPowerManagementState::new::<State, _>(&display.handle(), |_client| true);
+//!
+//! // Insert the OutputPowerManagementState into your state.
+//!
+//! // Implement the necessary trait.
+//! impl OutputPowerManagementHandler for State {
+//!     fn output_power_management_state(&mut self) -> &mut OutputPowerManagementState {
+//!         &mut self.output_power_management_state
+//!     }
+//!
+//!     fn set_mode(&mut self, output: &Output, mode: OutputPowerMode) {
+//!         // turn the output on or off, then notify clients
+//!         self.output_power_management_state.set_output_mode(output, mode);
+//!     }
+//! }
+//! delegate_output_power_management!(State);
+//! ```
+
+use std::{collections::HashMap, sync::Mutex};
+
+use wayland_protocols_wlr::output_power_management::v1::server::{
+    zwlr_output_power_manager_v1::{self, ZwlrOutputPowerManagerV1},
+    zwlr_output_power_v1::{self, ZwlrOutputPowerV1},
+};
+use wayland_server::{
+    backend::{ClientId, GlobalId},
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource, WEnum,
+};
+
+use crate::output::{Output, WeakOutput};
+
+const MANAGER_VERSION: u32 = 1;
+
+/// Power mode of an output
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OutputPowerMode {
+    /// The output is turned off
+    Off,
+    /// The output is turned on
+    #[default]
+    On,
+}
+
+impl From<OutputPowerMode> for zwlr_output_power_v1::Mode {
+    #[inline]
+    fn from(mode: OutputPowerMode) -> Self {
+        match mode {
+            OutputPowerMode::Off => zwlr_output_power_v1::Mode::Off,
+            OutputPowerMode::On => zwlr_output_power_v1::Mode::On,
+        }
+    }
+}
+
+/// Handler trait for wlr-output-power-management
+pub trait OutputPowerManagementHandler {
+    /// [`OutputPowerManagementState`] getter
+    fn output_power_management_state(&mut self) -> &mut OutputPowerManagementState;
+
+    /// A client requested to change the power mode of an output
+    ///
+    /// Call [`OutputPowerManagementState::set_output_mode`] once the mode was applied.
+    fn set_mode(&mut self, output: &Output, mode: OutputPowerMode);
+}
+
+/// State of the [`ZwlrOutputPowerManagerV1`] global
+#[derive(Debug)]
+pub struct OutputPowerManagementState {
+    global: GlobalId,
+    modes: HashMap<WeakOutput, OutputPowerMode>,
+    controls: Vec<ZwlrOutputPowerV1>,
+}
+
+impl OutputPowerManagementState {
+    /// Create a new [`ZwlrOutputPowerManagerV1`] global
+    ///
+    /// The filter decides which clients are allowed to control the power mode of outputs.
+    pub fn new<D, F>(display: &DisplayHandle, filter: F) -> Self
+    where
+        D: GlobalDispatch<ZwlrOutputPowerManagerV1, OutputPowerManagementGlobalData>,
+        D: Dispatch<ZwlrOutputPowerManagerV1, ()>,
+        D: Dispatch<ZwlrOutputPowerV1, OutputPowerData>,
+        D: OutputPowerManagementHandler,
+        D: 'static,
+        F: for<'c> Fn(&'c Client) -> bool + Send + Sync + 'static,
+    {
+        let data = OutputPowerManagementGlobalData {
+            filter: Box::new(filter),
+        };
+        let global = display.create_global::<D, ZwlrOutputPowerManagerV1, _>(MANAGER_VERSION, data);
+
+        OutputPowerManagementState {
+            global,
+            modes: HashMap::new(),
+            controls: Vec::new(),
+        }
+    }
+
+    /// Returns the id of the [`ZwlrOutputPowerManagerV1`] global
+    pub fn global(&self) -> GlobalId {
+        self.global.clone()
+    }
+
+    /// Returns the power mode of an output
+    ///
+    /// Outputs are assumed to be turned on, until changed with [`OutputPowerManagementState::set_output_mode`].
+    pub fn output_mode(&self, output: &Output) -> OutputPowerMode {
+        self.modes.get(&output.downgrade()).copied().unwrap_or_default()
+    }
+
+    /// Set the power mode of an output, notifying the controlling client, if it changed
+    ///
+    /// Also updates [`Output::is_powered`] accordingly.
+    pub fn set_output_mode(&mut self, output: &Output, mode: OutputPowerMode) {
+        output.set_powered(mode == OutputPowerMode::On);
+        self.modes.retain(|output, _| output.is_alive());
+        let previous = self.modes.insert(output.downgrade(), mode).unwrap_or_default();
+        if previous == mode {
+            return;
+        }
+
+        for control in self.controls_for(output) {
+            control.mode(mode.into());
+        }
+    }
+
+    /// Stop controlling the power mode of an output, e.g. because it was disconnected
+    ///
+    /// The controlling client is notified, that its control is no longer valid.
+    pub fn remove_output(&mut self, output: &Output) {
+        self.modes.remove(&output.downgrade());
+        let controls = self.controls_for(output).collect::<Vec<_>>();
+        for control in controls {
+            fail(control);
+        }
+        self.controls.retain(|control| control_output(control).is_some());
+    }
+
+    fn controls_for<'a>(&'a self, output: &'a Output) -> impl Iterator<Item = &'a ZwlrOutputPowerV1> + 'a {
+        self.controls
+            .iter()
+            .filter(move |control| control_output(control).is_some_and(|o| o == *output))
+    }
+}
+
+fn control_output(control: &ZwlrOutputPowerV1) -> Option<WeakOutput> {
+    control
+        .data::<OutputPowerData>()
+        .and_then(|data| data.output.lock().unwrap().clone())
+}
+
+// Sends failed and makes the control inert
+fn fail(control: &ZwlrOutputPowerV1) {
+    if let Some(data) = control.data::<OutputPowerData>() {
+        data.output.lock().unwrap().take();
+    }
+    control.failed();
+}
+
+#[allow(missing_debug_implementations)]
+#[doc(hidden)]
+pub struct OutputPowerManagementGlobalData {
+    filter: Box<dyn for<'c> Fn(&'c Client) -> bool + Send + Sync>,
+}
+
+/// User data of the [`ZwlrOutputPowerV1`] resource
+#[derive(Debug)]
+pub struct OutputPowerData {
+    // `None` once the control failed
+    output: Mutex<Option<WeakOutput>>,
+}
+
+impl<D> GlobalDispatch<ZwlrOutputPowerManagerV1, OutputPowerManagementGlobalData, D>
+    for OutputPowerManagementState
+where
+    D: GlobalDispatch<ZwlrOutputPowerManagerV1, OutputPowerManagementGlobalData>,
+    D: Dispatch<ZwlrOutputPowerManagerV1, ()>,
+    D: Dispatch<ZwlrOutputPowerV1, OutputPowerData>,
+    D: OutputPowerManagementHandler,
+    D: 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _dh: &DisplayHandle,
+        _client: &Client,
+        resource: New<ZwlrOutputPowerManagerV1>,
+        _global_data: &OutputPowerManagementGlobalData,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        data_init.init(resource, ());
+    }
+
+    fn can_view(client: Client, global_data: &OutputPowerManagementGlobalData) -> bool {
+        (global_data.filter)(&client)
+    }
+}
+
+impl<D> Dispatch<ZwlrOutputPowerManagerV1, (), D> for OutputPowerManagementState
+where
+    D: Dispatch<ZwlrOutputPowerManagerV1, ()>,
+    D: Dispatch<ZwlrOutputPowerV1, OutputPowerData>,
+    D: OutputPowerManagementHandler,
+    D: 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        _resource: &ZwlrOutputPowerManagerV1,
+        request: zwlr_output_power_manager_v1::Request,
+        _data: &(),
+        _dh: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zwlr_output_power_manager_v1::Request::GetOutputPower { id, output } => {
+                let output = Output::from_resource(&output);
+                let control = data_init.init(
+                    id,
+                    OutputPowerData {
+                        output: Mutex::new(output.as_ref().map(Output::downgrade)),
+                    },
+                );
+
+                let state = state.output_power_management_state();
+                let Some(output) = output else {
+                    fail(&control);
+                    return;
+                };
+                // another client already controls the output
+                if state.controls_for(&output).next().is_some() {
+                    fail(&control);
+                    return;
+                }
+
+                control.mode(state.output_mode(&output).into());
+                state.controls.push(control);
+            }
+            zwlr_output_power_manager_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<ZwlrOutputPowerV1, OutputPowerData, D> for OutputPowerManagementState
+where
+    D: Dispatch<ZwlrOutputPowerV1, OutputPowerData>,
+    D: OutputPowerManagementHandler,
+    D: 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        resource: &ZwlrOutputPowerV1,
+        request: zwlr_output_power_v1::Request,
+        data: &OutputPowerData,
+        _dh: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zwlr_output_power_v1::Request::SetMode { mode } => {
+                let mode = match mode {
+                    WEnum::Value(zwlr_output_power_v1::Mode::Off) => OutputPowerMode::Off,
+                    WEnum::Value(zwlr_output_power_v1::Mode::On) => OutputPowerMode::On,
+                    _ => {
+                        resource.post_error(zwlr_output_power_v1::Error::InvalidMode, "invalid power mode");
+                        return;
+                    }
+                };
+
+                // requests of failed controls are ignored
+                let output = data.output.lock().unwrap().as_ref().and_then(WeakOutput::upgrade);
+                if let Some(output) = output {
+                    state.set_mode(&output, mode);
+                }
+            }
+            zwlr_output_power_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(state: &mut D, _client: ClientId, resource: &ZwlrOutputPowerV1, _data: &OutputPowerData) {
+        state
+            .output_power_management_state()
+            .controls
+            .retain(|control| control != resource);
+    }
+}
+
+/// Macro to delegate implementation of wlr-output-power-management to [`OutputPowerManagementState`].
+///
+/// You must also implement [`OutputPowerManagementHandler`] to use this.
+#[macro_export]
+macro_rules! delegate_output_power_management {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        $crate::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols_wlr::output_power_management::v1::server::zwlr_output_power_manager_v1::ZwlrOutputPowerManagerV1: $crate::wayland::output_power_management::OutputPowerManagementGlobalData
+        ] => $crate::wayland::output_power_management::OutputPowerManagementState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols_wlr::output_power_management::v1::server::zwlr_output_power_manager_v1::ZwlrOutputPowerManagerV1: ()
+        ] => $crate::wayland::output_power_management::OutputPowerManagementState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols_wlr::output_power_management::v1::server::zwlr_output_power_v1::ZwlrOutputPowerV1: $crate::wayland::output_power_management::OutputPowerData
+        ] => $crate::wayland::output_power_management::OutputPowerManagementState);
+    };
+}
@@ -369,7 +369,7 @@ where
                 lifetime,
             } => {
                 let region = region.as_ref().map(compositor::get_region_attributes);
-                let pointer = pointer.data::<PointerUserData<D>>().unwrap().handle.clone();
+                let pointer = pointer.data::<PointerUserData<D>>().unwrap().handle();
                 let handle = data_init.init(
                     id,
                     PointerConstraintUserData {
@@ -403,7 +403,7 @@ where
                 lifetime,
             } => {
                 let region = region.as_ref().map(compositor::get_region_attributes);
-                let pointer = pointer.data::<PointerUserData<D>>().unwrap().handle.clone();
+                let pointer = pointer.data::<PointerUserData<D>>().unwrap().handle();
                 let handle = data_init.init(
                     id,
                     PointerConstraintUserData {
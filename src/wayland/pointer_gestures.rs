@@ -420,36 +420,36 @@ where
             zwp_pointer_gestures_v1::Request::GetSwipeGesture { id, pointer } => {
                 let data = pointer.data::<PointerUserData<D>>().unwrap();
                 let user_data = PointerGestureUserData {
-                    handle: data.handle.clone(),
+                    handle: data.handle(),
                     in_progress_on: Mutex::new(None),
                     client_scale: data.client_scale.clone(),
                 };
                 let gesture = data_init.init(id, user_data);
-                if let Some(handle) = &data.handle {
+                if let Some(handle) = data.handle() {
                     handle.wp_pointer_gestures.new_swipe_gesture(gesture);
                 }
             }
             zwp_pointer_gestures_v1::Request::GetPinchGesture { id, pointer } => {
                 let data = pointer.data::<PointerUserData<D>>().unwrap();
                 let user_data = PointerGestureUserData {
-                    handle: data.handle.clone(),
+                    handle: data.handle(),
                     in_progress_on: Mutex::new(None),
                     client_scale: data.client_scale.clone(),
                 };
                 let gesture = data_init.init(id, user_data);
-                if let Some(handle) = &data.handle {
+                if let Some(handle) = data.handle() {
                     handle.wp_pointer_gestures.new_pinch_gesture(gesture);
                 }
             }
             zwp_pointer_gestures_v1::Request::GetHoldGesture { id, pointer } => {
                 let data = pointer.data::<PointerUserData<D>>().unwrap();
                 let user_data = PointerGestureUserData {
-                    handle: data.handle.clone(),
+                    handle: data.handle(),
                     in_progress_on: Mutex::new(None),
                     client_scale: data.client_scale.clone(),
                 };
                 let gesture = data_init.init(id, user_data);
-                if let Some(handle) = &data.handle {
+                if let Some(handle) = data.handle() {
                     handle.wp_pointer_gestures.new_hold_gesture(gesture);
                 }
             }
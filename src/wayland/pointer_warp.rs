@@ -0,0 +1,192 @@
+//! Utilities for handling the `wp-pointer-warp` protocol
+//!
+//! This protocol allows clients to request the pointer to be moved to a position on one of their
+//! surfaces, e.g. games or remote desktop viewers.
+//!
+//! Requests are validated with [`PointerHandle::can_warp`], only requests of clients with an
+//! active pointer focus and a matching enter serial reach the [`PointerWarpHandler`].
+//!
+//! ## Initialization
+//!
+//! To initialize this implementation, create the [`PointerWarpManager`], store it in your `State`
+//! struct and implement the [`PointerWarpHandler`], as shown in this example:
+//!
+//! ```
+//! # extern crate wayland_server;
+//! use smithay::delegate_pointer_warp;
+//! use smithay::input::pointer::{MotionEvent, PointerHandle};
+//! use smithay::utils::{Logical, Point, SERIAL_COUNTER};
+//! use smithay::wayland::pointer_warp::{PointerWarpHandler, PointerWarpManager};
+//! # use smithay::input::{Seat, SeatHandler, SeatState};
+//! # use wayland_server::protocol::wl_surface::WlSurface;
+//!
+//! # struct State { seat_state: SeatState<State> }
+//! # let mut display = wayland_server::Display::<State>::new().unwrap();
+//! // Create the pointer warp manager
+//! let pointer_warp_manager = PointerWarpManager::new::<State>(&display.handle());
+//!
+//! // Insert the PointerWarpManager into your state
+//! // ..
+//!
+//! // Implement the necessary trait
+//! # impl SeatHandler for State {
+//! #     type KeyboardFocus = WlSurface;
+//! #     type PointerFocus = WlSurface;
+//! #     type TouchFocus = WlSurface;
+//! #     fn seat_state(&mut self) -> &mut SeatState<Self> { &mut self.seat_state }
+//! # }
+//! impl PointerWarpHandler for State {
+//!     fn warp_pointer(
+//!         &mut self,
+//!         surface: WlSurface,
+//!         pointer: PointerHandle<Self>,
+//!         location: Point<f64, Logical>,
+//!     ) {
+//!         // Find the position of the surface and check the location is inside of it
+//!         # let surface_origin: Point<f64, Logical> = (0.0, 0.0).into();
+//!         let event = MotionEvent {
+//!             location: surface_origin + location,
+//!             serial: SERIAL_COUNTER.next_serial(),
+//!             time: 0,
+//!         };
+//!         pointer.motion(self, Some((surface, surface_origin)), &event);
+//!         pointer.frame(self);
+//!     }
+//! }
+//!
+//! delegate_pointer_warp!(State);
+//! ```
+
+use std::sync::atomic::Ordering;
+
+use wayland_protocols::wp::pointer_warp::v1::server::wp_pointer_warp_v1::{self, WpPointerWarpV1};
+use wayland_server::{
+    backend::GlobalId, protocol::wl_surface::WlSurface, Client, DataInit, Dispatch, DisplayHandle,
+    GlobalDispatch, New, Resource,
+};
+
+use crate::{
+    input::{pointer::PointerHandle, SeatHandler},
+    utils::{Client as ClientCoords, Logical, Point, Serial},
+    wayland::seat::{PointerUserData, WaylandFocus},
+};
+
+/// Handler for the pointer warp protocol
+pub trait PointerWarpHandler: SeatHandler {
+    /// A client requested to warp the `pointer` to `location`, relative to the origin of `surface`
+    ///
+    /// The serial and pointer focus of the request have already been validated using
+    /// [`PointerHandle::can_warp`]. It is still up to the compositor to decide if the request is
+    /// honored, it should be rejected if `location` is outside of `surface`.
+    fn warp_pointer(
+        &mut self,
+        surface: WlSurface,
+        pointer: PointerHandle<Self>,
+        location: Point<f64, Logical>,
+    );
+}
+
+/// State of the pointer warp protocol
+#[derive(Debug)]
+pub struct PointerWarpManager {
+    global: GlobalId,
+}
+
+impl PointerWarpManager {
+    /// Register new [`WpPointerWarpV1`] global
+    pub fn new<D>(display: &DisplayHandle) -> Self
+    where
+        D: GlobalDispatch<WpPointerWarpV1, ()>,
+        D: Dispatch<WpPointerWarpV1, ()>,
+        D: PointerWarpHandler,
+        D: 'static,
+    {
+        let global = display.create_global::<D, WpPointerWarpV1, _>(1, ());
+        Self { global }
+    }
+
+    /// Returns the id of the [`WpPointerWarpV1`] global
+    pub fn global(&self) -> GlobalId {
+        self.global.clone()
+    }
+}
+
+impl<D> GlobalDispatch<WpPointerWarpV1, (), D> for PointerWarpManager
+where
+    D: GlobalDispatch<WpPointerWarpV1, ()>,
+    D: Dispatch<WpPointerWarpV1, ()>,
+    D: PointerWarpHandler,
+    D: 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _handle: &DisplayHandle,
+        _client: &Client,
+        resource: New<WpPointerWarpV1>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        data_init.init(resource, ());
+    }
+}
+
+impl<D> Dispatch<WpPointerWarpV1, (), D> for PointerWarpManager
+where
+    D: Dispatch<WpPointerWarpV1, ()>,
+    D: PointerWarpHandler,
+    <D as SeatHandler>::PointerFocus: WaylandFocus,
+    D: 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        _resource: &WpPointerWarpV1,
+        request: wp_pointer_warp_v1::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            wp_pointer_warp_v1::Request::WarpPointer {
+                surface,
+                pointer,
+                x,
+                y,
+                serial,
+            } => {
+                let Some(data) = pointer.data::<PointerUserData<D>>() else {
+                    return;
+                };
+                let Some(handle) = data.handle() else {
+                    return;
+                };
+
+                if x < 0.0 || y < 0.0 || !handle.can_warp(&surface, Serial::from(serial)) {
+                    return;
+                }
+
+                let client_scale = data.client_scale.load(Ordering::Acquire);
+                let location = Point::<f64, ClientCoords>::from((x, y)).to_logical(client_scale);
+                state.warp_pointer(surface, handle, location);
+            }
+            wp_pointer_warp_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// Macro to delegate implementation of the pointer warp protocol to [`PointerWarpManager`].
+///
+/// You must also implement [`PointerWarpHandler`] to use this.
+#[macro_export]
+macro_rules! delegate_pointer_warp {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        $crate::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::wp::pointer_warp::v1::server::wp_pointer_warp_v1::WpPointerWarpV1: ()
+        ] => $crate::wayland::pointer_warp::PointerWarpManager);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::wp::pointer_warp::v1::server::wp_pointer_warp_v1::WpPointerWarpV1: ()
+        ] => $crate::wayland::pointer_warp::PointerWarpManager);
+    };
+}
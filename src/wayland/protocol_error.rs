@@ -0,0 +1,118 @@
+//! Reporting of protocol errors
+//!
+//! When a client violates a protocol, smithay posts a protocol error, which disconnects the client.
+//! The client usually just crashes, leaving the user without any explanation.
+//! This module lets compositors learn about the errors smithay posted, e.g. to log them
+//! together with the offending client or to notify the user.
+//!
+//! To use it, implement [`ProtocolErrorHandler`] for your compositor state. It is required by the
+//! protocol implementations reporting their errors, the default implementation ignores all reports.
+//!
+//! ```no_run
+//! use smithay::wayland::protocol_error::{ProtocolErrorHandler, ProtocolErrorReport};
+//!
+//! struct State;
+//!
+//! impl ProtocolErrorHandler for State {
+//!     fn protocol_error(&mut self, report: ProtocolErrorReport) {
+//!         eprintln!(
+//!             "client {:?} ({:?}) caused a {:?} error on {}: {}",
+//!             report.client, report.credentials, report.kind, report.object, report.message
+//!         );
+//!     }
+//! }
+//! ```
+//!
+//! Custom protocol implementations can use [`post_error`] to report their own errors.
+//!
+//! ### Unreported errors
+//!
+//! Currently only the role, serial and configure errors of the compositor, xdg-shell,
+//! wlr-layer-shell, session-lock, xwayland-shell and input-method protocols, as well as
+//! `wl_pointer.set_cursor`, `zwp_tablet_tool_v2.set_cursor` and `wl_data_device.start_drag` are reported. Errors posted outside of request handlers, like by
+//! [`ToplevelSurface::ensure_configured`](crate::wayland::shell::xdg::ToplevelSurface::ensure_configured),
+//! and all other protocol errors are not reported yet. The client still learns about them and they
+//! are passed as [`DisconnectReason::ProtocolError`](wayland_server::backend::DisconnectReason::ProtocolError)
+//! to [`ClientData::disconnected`](wayland_server::backend::ClientData::disconnected).
+
+use wayland_server::{
+    backend::{ClientId, Credentials, ObjectId},
+    DisplayHandle, Resource,
+};
+
+/// Category of a protocol error
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ProtocolErrorKind {
+    /// A surface was given a role it can not have, e.g. because it already has another role
+    Role,
+    /// The client sent an invalid serial, e.g. acknowledging a configure it never received
+    Serial,
+    /// The client used a surface before it was configured
+    Configure,
+    /// Any other protocol error
+    Other,
+}
+
+/// Report of a protocol error posted to a client
+#[derive(Debug, Clone)]
+pub struct ProtocolErrorReport {
+    /// The client the error was posted to
+    pub client: Option<ClientId>,
+    /// Credentials of the client, if they could be retrieved
+    pub credentials: Option<Credentials>,
+    /// The object the error was posted on
+    pub object: ObjectId,
+    /// Interface of the object
+    pub interface: &'static str,
+    /// Error code, as defined by the interface
+    pub code: u32,
+    /// Error message sent to the client
+    pub message: String,
+    /// Category of the error
+    pub kind: ProtocolErrorKind,
+}
+
+/// Handler for protocol error reports
+pub trait ProtocolErrorHandler {
+    /// A protocol error was posted to a client
+    ///
+    /// The client is already disconnected when this is called.
+    fn protocol_error(&mut self, report: ProtocolErrorReport) {
+        let _ = report;
+    }
+}
+
+/// Post a protocol error on a resource and report it
+///
+/// Behaves like [`Resource::post_error`], additionally passing a [`ProtocolErrorReport`]
+/// to [`ProtocolErrorHandler::protocol_error`].
+pub fn post_error<D: ProtocolErrorHandler, R: Resource>(
+    state: &mut D,
+    resource: &R,
+    kind: ProtocolErrorKind,
+    code: impl Into<u32>,
+    message: impl Into<String>,
+) {
+    let code = code.into();
+    let message = message.into();
+
+    // the credentials are not available anymore after posting the error
+    let client = resource.client();
+    let credentials = client.as_ref().and_then(|client| {
+        let dh = DisplayHandle::from(resource.handle().upgrade()?);
+        client.get_credentials(&dh).ok()
+    });
+    let report = ProtocolErrorReport {
+        client: client.map(|client| client.id()),
+        credentials,
+        object: resource.id(),
+        interface: R::interface().name,
+        code,
+        message: message.clone(),
+        kind,
+    };
+
+    resource.post_error(code, message);
+    state.protocol_error(report);
+}
@@ -208,11 +208,11 @@ where
             zwp_relative_pointer_manager_v1::Request::GetRelativePointer { id, pointer } => {
                 let data = pointer.data::<PointerUserData<D>>().unwrap();
                 let user_data = RelativePointerUserData {
-                    handle: data.handle.clone(),
+                    handle: data.handle(),
                     client_scale: data.client_scale.clone(),
                 };
                 let pointer = data_init.init(id, user_data);
-                if let Some(handle) = &data.handle {
+                if let Some(handle) = data.handle() {
                     handle.wp_relative.new_relative_pointer(pointer);
                 }
             }
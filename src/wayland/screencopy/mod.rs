@@ -0,0 +1,636 @@
+//! Utilities for handling the `wlr-screencopy` protocol
+//!
+//! This protocol allows clients, like screenshot tools or screen recorders, to copy the contents of
+//! outputs into their own shm or dmabuf buffers.
+//!
+//! For every capture the client receives the buffer parameters of the frame, which are advertised
+//! according to [`ScreencopyHandler::buffer_formats`]. Once it provided a matching buffer,
+//! [`ScreencopyHandler::frame`] is called with a [`Screencopy`], which the compositor fulfills by
+//! rendering the requested region of the output into the buffer and calling [`Screencopy::submit`].
+//! Dropping the [`Screencopy`] notifies the client, that the capture failed.
+//!
+//! [`capture_elements_into_buffer`](crate::backend::renderer::utils::capture_elements_into_buffer)
+//! renders the elements of an output directly into the buffer of the frame. Dmabufs are rendered
+//! into without an intermediate copy, as long as the renderer can bind them.
+//!
+//! ## How to use it
+//!
+//! ```
+//! use smithay::delegate_screencopy;
+//! use smithay::wayland::screencopy::{Screencopy, ScreencopyHandler, ScreencopyManagerState};
+//!
+//! # struct State { screencopy_state: ScreencopyManagerState }
+//! # let mut display = wayland_server::Display::<State>::new().unwrap();
+//! // Create the screencopy state
+//! let screencopy_state = ScreencopyManagerState::new::<State, _>(&display.handle(), |_client| true);
+//!
+//! // Insert the ScreencopyManagerState into your state.
+//!
+//! // Implement the necessary trait.
+//! impl ScreencopyHandler for State {
+//!     fn screencopy_state(&mut self) -> &mut ScreencopyManagerState {
+//!         &mut self.screencopy_state
+//!     }
+//!
+//!     fn frame(&mut self, frame: Screencopy) {
+//!         // Render `frame.region()` of `frame.output()` into `frame.buffer()`,
+//!         // including the cursor if `frame.overlay_cursor()` is set.
+//!         //
+//!         // If `frame.with_damage()` is set, wait until the output was damaged
+//!         // and report it using `frame.damage(..)`.
+//!         //
+//!         // Then call `frame.submit(..)`.
+//!     }
+//! }
+//! delegate_screencopy!(State);
+//! ```
+
+use std::{sync::Mutex, time::Duration};
+
+use wayland_protocols_wlr::screencopy::v1::server::{
+    zwlr_screencopy_frame_v1::{self, ZwlrScreencopyFrameV1},
+    zwlr_screencopy_manager_v1::{self, ZwlrScreencopyManagerV1},
+};
+use wayland_server::{
+    backend::GlobalId,
+    protocol::{wl_buffer::WlBuffer, wl_output::WlOutput, wl_shm},
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource, WEnum,
+};
+
+use crate::{
+    backend::allocator::{Buffer as _, Fourcc},
+    output::Output,
+    utils::{Buffer, Logical, Rectangle, Size},
+    wayland::{dmabuf::get_dmabuf, shm},
+};
+
+const MANAGER_VERSION: u32 = 3;
+
+/// Buffer formats advertised for a captured frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScreencopyFormats {
+    /// Format of shm buffers, `None` if shm buffers are not supported
+    ///
+    /// Clients binding a version before 3 only support shm buffers, their frames fail without it.
+    pub shm: Option<wl_shm::Format>,
+    /// Fourcc format of dmabufs, `None` if dmabufs are not supported
+    pub dmabuf: Option<Fourcc>,
+}
+
+impl Default for ScreencopyFormats {
+    fn default() -> Self {
+        ScreencopyFormats {
+            shm: Some(wl_shm::Format::Xrgb8888),
+            dmabuf: None,
+        }
+    }
+}
+
+/// State of the [`ZwlrScreencopyManagerV1`] global
+#[derive(Debug)]
+pub struct ScreencopyManagerState {
+    global: GlobalId,
+}
+
+impl ScreencopyManagerState {
+    /// Create a new [`ZwlrScreencopyManagerV1`] global
+    ///
+    /// The filter decides which clients are allowed to capture outputs.
+    pub fn new<D, F>(display: &DisplayHandle, filter: F) -> Self
+    where
+        D: GlobalDispatch<ZwlrScreencopyManagerV1, ScreencopyManagerGlobalData>,
+        D: Dispatch<ZwlrScreencopyManagerV1, ()>,
+        D: Dispatch<ZwlrScreencopyFrameV1, ScreencopyFrameData>,
+        D: ScreencopyHandler,
+        D: 'static,
+        F: for<'c> Fn(&'c Client) -> bool + Send + Sync + 'static,
+    {
+        let data = ScreencopyManagerGlobalData {
+            filter: Box::new(filter),
+        };
+        let global = display.create_global::<D, ZwlrScreencopyManagerV1, _>(MANAGER_VERSION, data);
+
+        ScreencopyManagerState { global }
+    }
+
+    /// Returns the id of the [`ZwlrScreencopyManagerV1`] global
+    pub fn global(&self) -> GlobalId {
+        self.global.clone()
+    }
+}
+
+/// Handler trait for wlr-screencopy
+pub trait ScreencopyHandler {
+    /// [`ScreencopyManagerState`] getter
+    fn screencopy_state(&mut self) -> &mut ScreencopyManagerState;
+
+    /// Buffer formats supported for capturing the given output
+    ///
+    /// Defaults to [`wl_shm::Format::Xrgb8888`] shm buffers.
+    fn buffer_formats(&mut self, _output: &Output) -> ScreencopyFormats {
+        ScreencopyFormats::default()
+    }
+
+    /// A client provided a buffer to copy the contents of an output into
+    ///
+    /// The buffer was already validated against the advertised [`ScreencopyFormats`].
+    fn frame(&mut self, frame: Screencopy);
+}
+
+#[allow(missing_debug_implementations)]
+#[doc(hidden)]
+pub struct ScreencopyManagerGlobalData {
+    filter: Box<dyn for<'c> Fn(&'c Client) -> bool + Send + Sync>,
+}
+
+#[derive(Debug)]
+struct FrameInfo {
+    output: Output,
+    region: Rectangle<i32, Buffer>,
+    overlay_cursor: bool,
+    formats: ScreencopyFormats,
+}
+
+/// User data of the [`ZwlrScreencopyFrameV1`] resource
+#[derive(Debug)]
+pub struct ScreencopyFrameData {
+    // `None` if the frame failed immediately
+    info: Option<FrameInfo>,
+    used: Mutex<bool>,
+}
+
+/// A pending capture of an output
+///
+/// See [`ScreencopyHandler::frame`]. Dropping it without calling [`Screencopy::submit`]
+/// notifies the client, that the capture failed.
+#[derive(Debug)]
+pub struct Screencopy {
+    frame: ZwlrScreencopyFrameV1,
+    output: Output,
+    region: Rectangle<i32, Buffer>,
+    overlay_cursor: bool,
+    with_damage: bool,
+    buffer: WlBuffer,
+    done: bool,
+}
+
+impl Screencopy {
+    /// Returns the underlying [`ZwlrScreencopyFrameV1`]
+    pub fn frame(&self) -> &ZwlrScreencopyFrameV1 {
+        &self.frame
+    }
+
+    /// Returns the captured output
+    pub fn output(&self) -> &Output {
+        &self.output
+    }
+
+    /// Returns the captured region in buffer coordinates of the current mode of the output
+    ///
+    /// Like the contents of the output this region is not transformed by the output transform,
+    /// its size matches the size of the [buffer](Self::buffer).
+    pub fn region(&self) -> Rectangle<i32, Buffer> {
+        self.region
+    }
+
+    /// Returns whether the cursor should be included
+    pub fn overlay_cursor(&self) -> bool {
+        self.overlay_cursor
+    }
+
+    /// Returns whether the client requested to receive the damage since its last capture
+    ///
+    /// The frame should only be submitted, once the output was damaged.
+    pub fn with_damage(&self) -> bool {
+        self.with_damage
+    }
+
+    /// Returns the buffer to copy the region into
+    pub fn buffer(&self) -> &WlBuffer {
+        &self.buffer
+    }
+
+    /// Report damaged regions of the buffer since the last capture
+    ///
+    /// The damage is relative to the [region](Self::region) and is only sent,
+    /// if the client requested it.
+    pub fn damage(&self, damage: impl IntoIterator<Item = Rectangle<i32, Buffer>>) {
+        if !self.with_damage {
+            return;
+        }
+        let bounds = Rectangle::from_size(self.region.size);
+        for rect in damage.into_iter().filter_map(|rect| rect.intersection(bounds)) {
+            self.frame.damage(
+                rect.loc.x as u32,
+                rect.loc.y as u32,
+                rect.size.w as u32,
+                rect.size.h as u32,
+            );
+        }
+    }
+
+    /// Notify the client, that the buffer contains the captured contents
+    ///
+    /// `timestamp` is the presentation time of the captured contents, in the clock domain
+    /// of the presentation time protocol. Set `y_invert` if the contents are flipped vertically.
+    pub fn submit(mut self, y_invert: bool, timestamp: Duration) {
+        let flags = if y_invert {
+            zwlr_screencopy_frame_v1::Flags::YInvert
+        } else {
+            zwlr_screencopy_frame_v1::Flags::empty()
+        };
+        self.frame.flags(flags);
+        let secs = timestamp.as_secs();
+        self.frame
+            .ready((secs >> 32) as u32, secs as u32, timestamp.subsec_nanos());
+        self.done = true;
+    }
+
+    /// Notify the client, that the capture failed
+    pub fn failed(self) {}
+}
+
+impl Drop for Screencopy {
+    fn drop(&mut self) {
+        if !self.done {
+            self.frame.failed();
+        }
+    }
+}
+
+impl<D> GlobalDispatch<ZwlrScreencopyManagerV1, ScreencopyManagerGlobalData, D> for ScreencopyManagerState
+where
+    D: GlobalDispatch<ZwlrScreencopyManagerV1, ScreencopyManagerGlobalData>,
+    D: Dispatch<ZwlrScreencopyManagerV1, ()>,
+    D: Dispatch<ZwlrScreencopyFrameV1, ScreencopyFrameData>,
+    D: ScreencopyHandler,
+    D: 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _display: &DisplayHandle,
+        _client: &Client,
+        manager: New<ZwlrScreencopyManagerV1>,
+        _global_data: &ScreencopyManagerGlobalData,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        data_init.init(manager, ());
+    }
+
+    fn can_view(client: Client, global_data: &ScreencopyManagerGlobalData) -> bool {
+        (global_data.filter)(&client)
+    }
+}
+
+impl<D> Dispatch<ZwlrScreencopyManagerV1, (), D> for ScreencopyManagerState
+where
+    D: GlobalDispatch<ZwlrScreencopyManagerV1, ScreencopyManagerGlobalData>,
+    D: Dispatch<ZwlrScreencopyManagerV1, ()>,
+    D: Dispatch<ZwlrScreencopyFrameV1, ScreencopyFrameData>,
+    D: ScreencopyHandler,
+    D: 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        _manager: &ZwlrScreencopyManagerV1,
+        request: zwlr_screencopy_manager_v1::Request,
+        _data: &(),
+        _display: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        let (frame, overlay_cursor, output, region) = match request {
+            zwlr_screencopy_manager_v1::Request::CaptureOutput {
+                frame,
+                overlay_cursor,
+                output,
+            } => (frame, overlay_cursor, output, None),
+            zwlr_screencopy_manager_v1::Request::CaptureOutputRegion {
+                frame,
+                overlay_cursor,
+                output,
+                x,
+                y,
+                width,
+                height,
+            } => (
+                frame,
+                overlay_cursor,
+                output,
+                Some(Rectangle::new((x, y).into(), (width, height).into())),
+            ),
+            zwlr_screencopy_manager_v1::Request::Destroy => return,
+            _ => unreachable!(),
+        };
+
+        let info = capture_region(&output, region).map(|(output, region)| FrameInfo {
+            formats: state.buffer_formats(&output),
+            output,
+            region,
+            overlay_cursor: overlay_cursor != 0,
+        });
+        let frame = data_init.init(
+            frame,
+            ScreencopyFrameData {
+                info,
+                used: Mutex::new(false),
+            },
+        );
+
+        let Some(info) = frame.data::<ScreencopyFrameData>().unwrap().info.as_ref() else {
+            frame.failed();
+            return;
+        };
+        // clients before version 3 only know about shm buffers
+        let dmabuf = info.formats.dmabuf.filter(|_| frame.version() >= 3);
+        if info.formats.shm.is_none() && dmabuf.is_none() {
+            frame.failed();
+            return;
+        }
+
+        let size = info.region.size;
+        if let Some(format) = info.formats.shm {
+            let stride = size.w * shm::wl_bytes_per_pixel(WEnum::Value(format));
+            frame.buffer(format, size.w as u32, size.h as u32, stride as u32);
+        }
+        if frame.version() >= 3 {
+            if let Some(format) = dmabuf {
+                frame.linux_dmabuf(format as u32, size.w as u32, size.h as u32);
+            }
+            frame.buffer_done();
+        }
+    }
+}
+
+// Returns the region of the output to capture in buffer coordinates of its mode
+fn capture_region(
+    output: &WlOutput,
+    region: Option<Rectangle<i32, Logical>>,
+) -> Option<(Output, Rectangle<i32, Buffer>)> {
+    let output = Output::from_resource(output)?;
+    let mode = output.current_mode()?;
+    let bounds = Rectangle::from_size(mode.size.to_logical(1).to_buffer(1, Default::default()));
+
+    let region = match region {
+        Some(region) => {
+            let transform = output.current_transform();
+            let scale = output.current_scale().fractional_scale();
+            let logical_size = transform.transform_size(mode.size).to_f64().to_logical(scale);
+            region
+                .to_f64()
+                .to_buffer(scale, transform, &logical_size)
+                .to_i32_round()
+                .intersection(bounds)?
+        }
+        None => bounds,
+    };
+    Some((output, region))
+}
+
+// Checks the buffer against the advertised buffer parameters
+fn is_valid_buffer(buffer: &WlBuffer, info: &FrameInfo) -> bool {
+    let size: Size<i32, Buffer> = info.region.size;
+    if let Ok(dmabuf) = get_dmabuf(buffer) {
+        return info.formats.dmabuf == Some(dmabuf.format().code) && dmabuf.size() == size;
+    }
+    shm::with_buffer_contents(buffer, |_, _, data| {
+        let stride = size.w * shm::wl_bytes_per_pixel(WEnum::Value(data.format));
+        info.formats.shm == Some(data.format)
+            && data.width == size.w
+            && data.height == size.h
+            && data.stride == stride
+    })
+    .unwrap_or(false)
+}
+
+impl<D> Dispatch<ZwlrScreencopyFrameV1, ScreencopyFrameData, D> for ScreencopyManagerState
+where
+    D: Dispatch<ZwlrScreencopyFrameV1, ScreencopyFrameData>,
+    D: ScreencopyHandler,
+    D: 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        frame: &ZwlrScreencopyFrameV1,
+        request: zwlr_screencopy_frame_v1::Request,
+        data: &ScreencopyFrameData,
+        _display: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        let (buffer, with_damage) = match request {
+            zwlr_screencopy_frame_v1::Request::Copy { buffer } => (buffer, false),
+            zwlr_screencopy_frame_v1::Request::CopyWithDamage { buffer } => (buffer, true),
+            zwlr_screencopy_frame_v1::Request::Destroy => return,
+            _ => unreachable!(),
+        };
+
+        {
+            let mut used = data.used.lock().unwrap();
+            if *used {
+                frame.post_error(
+                    zwlr_screencopy_frame_v1::Error::AlreadyUsed,
+                    "frame was already used to copy",
+                );
+                return;
+            }
+            *used = true;
+        }
+
+        let Some(info) = data.info.as_ref() else {
+            // the failure was already sent
+            return;
+        };
+        if !is_valid_buffer(&buffer, info) {
+            frame.post_error(
+                zwlr_screencopy_frame_v1::Error::InvalidBuffer,
+                "buffer does not match the advertised parameters",
+            );
+            return;
+        }
+        state.frame(Screencopy {
+            frame: frame.clone(),
+            output: info.output.clone(),
+            region: info.region,
+            overlay_cursor: info.overlay_cursor,
+            with_damage,
+            buffer,
+            done: false,
+        });
+    }
+}
+
+/// Macro to delegate implementation of wlr-screencopy to [`ScreencopyManagerState`].
+///
+/// You must also implement [`ScreencopyHandler`] to use this.
+#[macro_export]
+macro_rules! delegate_screencopy {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        $crate::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols_wlr::screencopy::v1::server::zwlr_screencopy_manager_v1::ZwlrScreencopyManagerV1: $crate::wayland::screencopy::ScreencopyManagerGlobalData
+        ] => $crate::wayland::screencopy::ScreencopyManagerState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols_wlr::screencopy::v1::server::zwlr_screencopy_manager_v1::ZwlrScreencopyManagerV1: ()
+        ] => $crate::wayland::screencopy::ScreencopyManagerState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols_wlr::screencopy::v1::server::zwlr_screencopy_frame_v1::ZwlrScreencopyFrameV1: $crate::wayland::screencopy::ScreencopyFrameData
+        ] => $crate::wayland::screencopy::ScreencopyManagerState);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use wayland_client::protocol::{wl_output::WlOutput as ClientOutput, wl_shm::Format as ClientFormat};
+    use wayland_protocols_wlr::screencopy::v1::client::zwlr_screencopy_manager_v1::ZwlrScreencopyManagerV1 as ClientManager;
+
+    use super::*;
+    use crate::{
+        output::{test_output, Scale},
+        utils::Transform,
+        wayland::test_utils::{ClientEvent, Fixture, TestState},
+    };
+
+    fn bind_output(fixture: &mut Fixture, output: &Output) -> ClientOutput {
+        output.create_global::<TestState>(&fixture.display.handle());
+        fixture.roundtrip();
+        fixture.bind(4)
+    }
+
+    #[test]
+    fn capture_output_advertises_buffer_parameters() {
+        let mut fixture = Fixture::new();
+        let output = bind_output(&mut fixture, &test_output("test", (1920, 1080)));
+        fixture.state.screencopy_formats = ScreencopyFormats {
+            shm: Some(wl_shm::Format::Argb8888),
+            dmabuf: Some(Fourcc::Xrgb8888),
+        };
+
+        let manager: ClientManager = fixture.bind(3);
+        manager.capture_output(0, &output, &fixture.handle(), ());
+        fixture.roundtrip();
+        assert_eq!(
+            fixture.client.events,
+            [
+                ClientEvent::ScreencopyBuffer(ClientFormat::Argb8888, 1920, 1080, 7680),
+                ClientEvent::ScreencopyLinuxDmabuf(Fourcc::Xrgb8888 as u32, 1920, 1080),
+                ClientEvent::ScreencopyBufferDone,
+            ]
+        );
+
+        // older clients only learn about shm buffers
+        fixture.client.events.clear();
+        let manager: ClientManager = fixture.bind(2);
+        manager.capture_output(0, &output, &fixture.handle(), ());
+        fixture.roundtrip();
+        assert_eq!(
+            fixture.client.events,
+            [ClientEvent::ScreencopyBuffer(
+                ClientFormat::Argb8888,
+                1920,
+                1080,
+                7680
+            )]
+        );
+
+        // and can't capture at all without them
+        fixture.client.events.clear();
+        fixture.state.screencopy_formats.shm = None;
+        manager.capture_output(0, &output, &fixture.handle(), ());
+        fixture.roundtrip();
+        assert_eq!(fixture.client.events, [ClientEvent::ScreencopyFailed]);
+    }
+
+    #[test]
+    fn capture_output_region_in_buffer_coordinates() {
+        let mut fixture = Fixture::new();
+        let output = test_output("test", (1920, 1080));
+        output.change_current_state(None, Some(Transform::_90), Some(Scale::Fractional(2.0)), None);
+        let client_output = bind_output(&mut fixture, &output);
+        let manager: ClientManager = fixture.bind(3);
+
+        // the logical output is 540x960 and rotated relative to its mode
+        let frame = manager.capture_output_region(0, &client_output, 10, 20, 100, 50, &fixture.handle(), ());
+        fixture.roundtrip();
+        assert_eq!(
+            fixture.client.events,
+            [
+                ClientEvent::ScreencopyBuffer(ClientFormat::Xrgb8888, 100, 200, 400),
+                ClientEvent::ScreencopyBufferDone,
+            ]
+        );
+        let buffer = fixture.create_buffer(100, 200, ClientFormat::Xrgb8888);
+        frame.copy(&buffer);
+        fixture.roundtrip();
+        let screencopy = fixture.state.screencopies.pop().unwrap();
+        assert_eq!(
+            screencopy.region(),
+            Rectangle::new((1780, 20).into(), (100, 200).into())
+        );
+        assert_eq!(screencopy.output(), &output);
+
+        // regions are clipped to the output
+        fixture.client.events.clear();
+        let frame =
+            manager.capture_output_region(0, &client_output, 500, 900, 100, 100, &fixture.handle(), ());
+        fixture.roundtrip();
+        assert_eq!(
+            fixture.client.events,
+            [
+                ClientEvent::ScreencopyBuffer(ClientFormat::Xrgb8888, 120, 80, 480),
+                ClientEvent::ScreencopyBufferDone,
+            ]
+        );
+        let buffer = fixture.create_buffer(120, 80, ClientFormat::Xrgb8888);
+        frame.copy(&buffer);
+        fixture.roundtrip();
+        let screencopy = fixture.state.screencopies.pop().unwrap();
+        assert_eq!(
+            screencopy.region(),
+            Rectangle::new((0, 1000).into(), (120, 80).into())
+        );
+
+        // and fail entirely outside of it
+        fixture.client.events.clear();
+        manager.capture_output_region(0, &client_output, 600, 0, 100, 100, &fixture.handle(), ());
+        fixture.roundtrip();
+        assert_eq!(fixture.client.events, [ClientEvent::ScreencopyFailed]);
+    }
+
+    #[test]
+    fn frame_can_only_be_copied_once() {
+        let mut fixture = Fixture::new();
+        let output = bind_output(&mut fixture, &test_output("test", (64, 64)));
+        let manager: ClientManager = fixture.bind(3);
+
+        let frame = manager.capture_output(0, &output, &fixture.handle(), ());
+        let buffer = fixture.create_buffer(64, 64, ClientFormat::Xrgb8888);
+        frame.copy(&buffer);
+        fixture.roundtrip();
+        assert_eq!(fixture.state.screencopies.len(), 1);
+        assert!(fixture.protocol_error().is_none());
+
+        frame.copy_with_damage(&buffer);
+        fixture.roundtrip();
+        assert_eq!(fixture.state.screencopies.len(), 1);
+        let error = fixture.protocol_error().unwrap();
+        assert_eq!(error.code, zwlr_screencopy_frame_v1::Error::AlreadyUsed as u32);
+        assert_eq!(error.object_interface, "zwlr_screencopy_frame_v1");
+    }
+
+    #[test]
+    fn copy_into_mismatching_buffer() {
+        let mut fixture = Fixture::new();
+        let output = bind_output(&mut fixture, &test_output("test", (64, 64)));
+        let manager: ClientManager = fixture.bind(3);
+
+        let frame = manager.capture_output(0, &output, &fixture.handle(), ());
+        let buffer = fixture.create_buffer(32, 32, ClientFormat::Xrgb8888);
+        frame.copy(&buffer);
+        fixture.roundtrip();
+        assert!(fixture.state.screencopies.is_empty());
+        let error = fixture.protocol_error().unwrap();
+        assert_eq!(error.code, zwlr_screencopy_frame_v1::Error::InvalidBuffer as u32);
+    }
+}
@@ -1,4 +1,4 @@
-use std::fmt;
+use std::{fmt, sync::Mutex};
 
 use tracing::{error, instrument, trace, warn};
 use wayland_server::{
@@ -18,7 +18,7 @@ use crate::{
         Seat, SeatHandler, SeatState,
     },
     utils::{iter::new_locked_obj_iter_from_vec, Serial},
-    wayland::{input_method::InputMethodSeat, text_input::TextInputSeat},
+    wayland::{input_method::InputMethodSeat, text_input::TextInputSeat, version::VersionedResource},
 };
 
 impl<D> KeyboardHandle<D>
@@ -45,6 +45,12 @@ where
         new_locked_obj_iter_from_vec(guard, client.id())
     }
 
+    /// Returns the client, that was focused when the active grab started, if any
+    pub fn grab_client(&self) -> Option<Client> {
+        let start_data = self.grab_start_data()?;
+        start_data.focus?.wl_surface()?.client()
+    }
+
     /// Register a new keyboard to this handler
     ///
     /// The keymap will automatically be sent to it
@@ -52,24 +58,11 @@ where
     /// This should be done first, before anything else is done with this keyboard.
     #[instrument(parent = &self.arc.span, skip(self))]
     pub(crate) fn new_kbd(&self, kbd: WlKeyboard) {
-        trace!("Sending keymap to client");
-
-        // prepare a tempfile with the keymap, to send it to the client
-        let keymap_file = self.arc.keymap.lock().unwrap();
-        let ret = keymap_file.send(&kbd);
-
-        if let Err(e) = ret {
-            warn!(
-                err = ?e,
-                "Failed write keymap to client in a tempfile"
-            );
+        if !self.send_keymap_to(&kbd) {
             return;
-        };
+        }
 
         let guard = self.arc.internal.lock().unwrap();
-        if kbd.version() >= 4 {
-            kbd.repeat_info(guard.repeat_rate, guard.repeat_delay);
-        }
         if let Some((focused, serial)) = guard.focus.as_ref() {
             if focused.same_client_as(&kbd.id()) {
                 let serialized = guard.mods_state.serialized;
@@ -89,19 +82,58 @@ where
     }
 }
 
+impl<D: SeatHandler + 'static> KeyboardHandle<D> {
+    // Sends the keymap and repeat info, returns `false` if the keymap could not be sent
+    fn send_keymap_to(&self, kbd: &WlKeyboard) -> bool {
+        trace!("Sending keymap to client");
+
+        // prepare a tempfile with the keymap, to send it to the client
+        let keymap_file = self.arc.keymap.lock().unwrap();
+        let ret = keymap_file.send(kbd);
+
+        if let Err(e) = ret {
+            warn!(
+                err = ?e,
+                "Failed write keymap to client in a tempfile"
+            );
+            return false;
+        };
+
+        let guard = self.arc.internal.lock().unwrap();
+        kbd.send_since(wl_keyboard::EVT_REPEAT_INFO_SINCE, |kbd| {
+            kbd.repeat_info(guard.repeat_rate, guard.repeat_delay)
+        });
+        true
+    }
+
+    // Registers a keyboard created while the seat had no keyboard capability.
+    // It receives the keymap, but only enters on the next focus change.
+    pub(crate) fn activate_kbd(&self, kbd: WlKeyboard) {
+        if self.send_keymap_to(&kbd) {
+            self.arc.known_kbds.lock().unwrap().push(kbd.downgrade());
+        }
+    }
+}
+
 impl<D: SeatHandler + 'static> KeyboardHandle<D> {
     /// Attempt to retrieve a [`KeyboardHandle`] from an existing resource
     ///
     /// May return `None` for a valid `WlKeyboard` that was created without
     /// the keyboard capability.
     pub fn from_resource(seat: &WlKeyboard) -> Option<Self> {
-        seat.data::<KeyboardUserData<D>>()?.handle.clone()
+        seat.data::<KeyboardUserData<D>>()?.handle()
     }
 }
 
 /// User data for keyboard
 pub struct KeyboardUserData<D: SeatHandler> {
-    pub(crate) handle: Option<KeyboardHandle<D>>,
+    pub(crate) handle: Mutex<Option<KeyboardHandle<D>>>,
+}
+
+impl<D: SeatHandler> KeyboardUserData<D> {
+    pub(crate) fn handle(&self) -> Option<KeyboardHandle<D>> {
+        self.handle.lock().unwrap().clone()
+    }
 }
 
 impl<D: SeatHandler> fmt::Debug for KeyboardUserData<D> {
@@ -129,7 +161,7 @@ where
     }
 
     fn destroyed(_state: &mut D, _client_id: ClientId, keyboard: &WlKeyboard, data: &KeyboardUserData<D>) {
-        if let Some(ref handle) = data.handle {
+        if let Some(handle) = data.handle() {
             handle
                 .arc
                 .known_kbds
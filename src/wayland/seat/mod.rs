@@ -45,6 +45,7 @@
 //!     }
 //! }
 //! delegate_seat!(State);
+//! # impl smithay::wayland::protocol_error::ProtocolErrorHandler for State {}
 //!
 //! # impl CompositorHandler for State {
 //! #     fn compositor_state(&mut self) -> &mut CompositorState { unimplemented!() }
@@ -70,7 +71,11 @@ pub(crate) mod keyboard;
 pub(crate) mod pointer;
 mod touch;
 
-use std::{borrow::Cow, fmt, sync::Arc};
+use std::{
+    borrow::Cow,
+    fmt,
+    sync::{Arc, Mutex},
+};
 
 use crate::input::{Inner, Seat, SeatHandler, SeatRc, SeatState};
 
@@ -89,10 +94,10 @@ use wayland_server::{
         wl_surface,
         wl_touch::WlTouch,
     },
-    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource,
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource, Weak,
 };
 
-use super::compositor::CompositorHandler;
+use super::{compositor::CompositorHandler, version::VersionedResource};
 
 /// Focused objects that *might* have an underlying wl_surface.
 pub trait WaylandFocus {
@@ -144,6 +149,60 @@ impl<D: SeatHandler> Inner<D> {
     }
 }
 
+// Pointer, keyboard and touch objects created on this seat.
+// Objects created while the seat lacked the matching capability stay inert, as the protocol
+// does not allow us to post an error. Whenever a capability is added, all objects of that kind
+// are bound to the new handle, so they keep working if a capability is removed and added again.
+#[derive(Debug, Default)]
+pub(crate) struct SeatObjects {
+    pointers: Vec<Weak<WlPointer>>,
+    keyboards: Vec<Weak<WlKeyboard>>,
+    touches: Vec<Weak<WlTouch>>,
+}
+
+impl<D: SeatHandler + 'static> Inner<D> {
+    pub(crate) fn bind_objects(&mut self) {
+        let objects = &mut self.objects;
+        objects.pointers.retain(|p| p.upgrade().is_ok());
+        objects.keyboards.retain(|k| k.upgrade().is_ok());
+        objects.touches.retain(|t| t.upgrade().is_ok());
+
+        if let Some(handle) = self.pointer.as_ref() {
+            for pointer in objects.pointers.iter().filter_map(|p| p.upgrade().ok()) {
+                if let Some(data) = pointer.data::<PointerUserData<D>>() {
+                    let mut bound = data.handle.lock().unwrap();
+                    if bound.as_ref() != Some(handle) {
+                        *bound = Some(handle.clone());
+                        handle.wl_pointer.new_pointer(pointer.clone());
+                    }
+                }
+            }
+        }
+        if let Some(handle) = self.keyboard.as_ref() {
+            for keyboard in objects.keyboards.iter().filter_map(|k| k.upgrade().ok()) {
+                if let Some(data) = keyboard.data::<KeyboardUserData<D>>() {
+                    let mut bound = data.handle.lock().unwrap();
+                    if bound.as_ref() != Some(handle) {
+                        *bound = Some(handle.clone());
+                        handle.activate_kbd(keyboard.clone());
+                    }
+                }
+            }
+        }
+        if let Some(handle) = self.touch.as_ref() {
+            for touch in objects.touches.iter().filter_map(|t| t.upgrade().ok()) {
+                if let Some(data) = touch.data::<TouchUserData<D>>() {
+                    let mut bound = data.handle.lock().unwrap();
+                    if bound.as_ref() != Some(handle) {
+                        *bound = Some(handle.clone());
+                        handle.new_touch(touch.clone());
+                    }
+                }
+            }
+        }
+    }
+}
+
 /// Global data of WlSeat
 pub struct SeatGlobalData<D: SeatHandler> {
     arc: Arc<SeatRc<D>>,
@@ -178,6 +237,16 @@ impl<D: SeatHandler + 'static> SeatState<D> {
 
         Seat { arc }
     }
+
+    /// Removes a seat created with [`SeatState::new_wl_seat`]
+    ///
+    /// All capabilities are removed from the seat and its global is destroyed.
+    pub fn remove_wl_seat(&mut self, display: &DisplayHandle, seat: &Seat<D>) {
+        self.remove_seat(seat);
+        if let Some(global) = seat.arc.inner.lock().unwrap().global.take() {
+            display.remove_global::<D>(global);
+        }
+    }
 }
 
 impl<D: SeatHandler + 'static> Seat<D> {
@@ -269,56 +338,62 @@ where
     ) {
         match request {
             wl_seat::Request::GetPointer { id } => {
-                let inner = data.arc.inner.lock().unwrap();
+                let mut inner = data.arc.inner.lock().unwrap();
 
                 let client_scale = state.client_compositor_state(client).clone_client_scale();
                 let pointer = data_init.init(
                     id,
                     PointerUserData {
-                        handle: inner.pointer.clone(),
+                        handle: Mutex::new(inner.pointer.clone()),
                         client_scale,
                     },
                 );
 
+                // without the capability the protocol does not allow us to send an error,
+                // the pointer stays inert until the capability is added
+                let pointers = &mut inner.objects.pointers;
+                pointers.retain(|p| p.upgrade().is_ok());
+                pointers.push(pointer.downgrade());
                 if let Some(ref ptr_handle) = inner.pointer {
                     ptr_handle.wl_pointer.new_pointer(pointer);
-                } else {
-                    // we should send a protocol error... but the protocol does not allow
-                    // us, so this pointer will just remain inactive ¯\_(ツ)_/¯
                 }
             }
             wl_seat::Request::GetKeyboard { id } => {
-                let inner = data.arc.inner.lock().unwrap();
+                let mut inner = data.arc.inner.lock().unwrap();
 
                 let keyboard = data_init.init(
                     id,
                     KeyboardUserData {
-                        handle: inner.keyboard.clone(),
+                        handle: Mutex::new(inner.keyboard.clone()),
                     },
                 );
 
+                // same as pointer, keep it inert without the capability
+                let keyboards = &mut inner.objects.keyboards;
+                keyboards.retain(|k| k.upgrade().is_ok());
+                keyboards.push(keyboard.downgrade());
                 if let Some(ref h) = inner.keyboard {
                     h.new_kbd(keyboard);
-                } else {
-                    // same as pointer, should error but cannot
                 }
             }
             wl_seat::Request::GetTouch { id } => {
-                let inner = data.arc.inner.lock().unwrap();
+                let mut inner = data.arc.inner.lock().unwrap();
 
                 let client_scale = state.client_compositor_state(client).clone_client_scale();
                 let touch = data_init.init(
                     id,
                     TouchUserData {
-                        handle: inner.touch.clone(),
+                        handle: Mutex::new(inner.touch.clone()),
                         client_scale,
                     },
                 );
 
+                // same as pointer, keep it inert without the capability
+                let touches = &mut inner.objects.touches;
+                touches.retain(|t| t.upgrade().is_ok());
+                touches.push(touch.downgrade());
                 if let Some(ref h) = inner.touch {
                     h.new_touch(touch);
-                } else {
-                    // same as pointer, should error but cannot
                 }
             }
             wl_seat::Request::Release => {
@@ -362,12 +437,228 @@ where
 
         let resource = data_init.init(resource, data);
 
-        if resource.version() >= 2 {
-            resource.name(global_data.arc.name.clone());
-        }
+        resource.send_since(wl_seat::EVT_NAME_SINCE, |seat| {
+            seat.name(global_data.arc.name.clone())
+        });
 
         let mut inner = global_data.arc.inner.lock().unwrap();
         resource.capabilities(inner.compute_caps());
         inner.known_seats.push(resource.downgrade());
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{io::ErrorKind, os::unix::net::UnixStream, sync::Arc};
+
+    use wayland_client::{
+        protocol::{wl_keyboard, wl_pointer, wl_registry, wl_seat as client_seat},
+        Connection, Dispatch as ClientDispatch, EventQueue, QueueHandle,
+    };
+    use wayland_server::{backend::ClientData, protocol::wl_surface::WlSurface, Display};
+
+    use super::*;
+    use crate::{
+        input::{
+            keyboard::XkbConfig,
+            pointer::{CursorImageStatus, PointerHandle},
+        },
+        wayland::{
+            compositor::{CompositorClientState, CompositorState},
+            protocol_error::ProtocolErrorHandler,
+        },
+    };
+
+    struct State {
+        compositor_state: CompositorState,
+        seat_state: SeatState<Self>,
+    }
+
+    #[derive(Default)]
+    struct ClientState {
+        compositor_state: CompositorClientState,
+    }
+
+    impl ClientData for ClientState {}
+
+    impl SeatHandler for State {
+        type KeyboardFocus = WlSurface;
+        type PointerFocus = WlSurface;
+        type TouchFocus = WlSurface;
+
+        fn seat_state(&mut self) -> &mut SeatState<Self> {
+            &mut self.seat_state
+        }
+        fn cursor_image(&mut self, _seat: &Seat<Self>, _image: CursorImageStatus) {}
+    }
+
+    impl CompositorHandler for State {
+        fn compositor_state(&mut self) -> &mut CompositorState {
+            &mut self.compositor_state
+        }
+        fn client_compositor_state<'a>(&self, client: &'a Client) -> &'a CompositorClientState {
+            &client.get_data::<ClientState>().unwrap().compositor_state
+        }
+        fn commit(&mut self, _surface: &WlSurface) {}
+    }
+
+    impl ProtocolErrorHandler for State {}
+
+    crate::delegate_compositor!(State);
+    crate::delegate_seat!(State);
+
+    #[derive(Default)]
+    struct TestClient {
+        seat: Option<client_seat::WlSeat>,
+        keymaps: usize,
+    }
+
+    impl ClientDispatch<wl_registry::WlRegistry, ()> for TestClient {
+        fn event(
+            state: &mut Self,
+            registry: &wl_registry::WlRegistry,
+            event: wl_registry::Event,
+            _: &(),
+            _: &Connection,
+            qh: &QueueHandle<Self>,
+        ) {
+            if let wl_registry::Event::Global { name, interface, .. } = event {
+                if interface == "wl_seat" {
+                    state.seat = Some(registry.bind(name, 7, qh, ()));
+                }
+            }
+        }
+    }
+
+    impl ClientDispatch<client_seat::WlSeat, ()> for TestClient {
+        fn event(
+            _: &mut Self,
+            _: &client_seat::WlSeat,
+            _: client_seat::Event,
+            _: &(),
+            _: &Connection,
+            _: &QueueHandle<Self>,
+        ) {
+        }
+    }
+
+    impl ClientDispatch<wl_pointer::WlPointer, ()> for TestClient {
+        fn event(
+            _: &mut Self,
+            _: &wl_pointer::WlPointer,
+            _: wl_pointer::Event,
+            _: &(),
+            _: &Connection,
+            _: &QueueHandle<Self>,
+        ) {
+        }
+    }
+
+    impl ClientDispatch<wl_keyboard::WlKeyboard, ()> for TestClient {
+        fn event(
+            state: &mut Self,
+            _: &wl_keyboard::WlKeyboard,
+            event: wl_keyboard::Event,
+            _: &(),
+            _: &Connection,
+            _: &QueueHandle<Self>,
+        ) {
+            if let wl_keyboard::Event::Keymap { .. } = event {
+                state.keymaps += 1;
+            }
+        }
+    }
+
+    struct Fixture {
+        display: Display<State>,
+        state: State,
+        connection: Connection,
+        queue: EventQueue<TestClient>,
+        client: TestClient,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            let display = Display::<State>::new().unwrap();
+            let dh = display.handle();
+            let state = State {
+                compositor_state: CompositorState::new::<State>(&dh),
+                seat_state: SeatState::new(),
+            };
+
+            let (server, client) = UnixStream::pair().unwrap();
+            dh.clone()
+                .insert_client(server, Arc::new(ClientState::default()))
+                .unwrap();
+            let connection = Connection::from_socket(client).unwrap();
+            let queue = connection.new_event_queue();
+            connection.display().get_registry(&queue.handle(), ());
+
+            Fixture {
+                display,
+                state,
+                connection,
+                queue,
+                client: TestClient::default(),
+            }
+        }
+
+        fn roundtrip(&mut self) {
+            for _ in 0..2 {
+                self.queue.flush().unwrap();
+                self.display.dispatch_clients(&mut self.state).unwrap();
+                self.display.flush_clients().unwrap();
+                if let Some(guard) = self.connection.prepare_read() {
+                    if let Err(err) = guard.read() {
+                        assert!(
+                            matches!(err, wayland_client::backend::WaylandError::Io(ref err) if err.kind() == ErrorKind::WouldBlock)
+                        );
+                    }
+                }
+                self.queue.dispatch_pending(&mut self.client).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn objects_survive_capability_cycles() {
+        let mut fixture = Fixture::new();
+        let mut seat = fixture
+            .state
+            .seat_state
+            .new_wl_seat(&fixture.display.handle(), "seat0");
+        fixture.roundtrip();
+
+        // created without the capabilities, so both are inert
+        let wl_seat = fixture.client.seat.clone().unwrap();
+        wl_seat.get_pointer(&fixture.queue.handle(), ());
+        wl_seat.get_keyboard(&fixture.queue.handle(), ());
+        fixture.roundtrip();
+        let pointer = seat.arc.inner.lock().unwrap().objects.pointers[0]
+            .upgrade()
+            .unwrap();
+        assert_eq!(PointerHandle::<State>::from_resource(&pointer), None);
+        assert_eq!(fixture.client.keymaps, 0);
+
+        for cycle in 1..=2 {
+            let handle = seat.add_pointer();
+            seat.add_keyboard(XkbConfig::default(), 200, 25).unwrap();
+            fixture.roundtrip();
+            assert_eq!(PointerHandle::from_resource(&pointer), Some(handle));
+            assert_eq!(fixture.client.keymaps, cycle);
+
+            seat.remove_pointer();
+            seat.remove_keyboard();
+            fixture.roundtrip();
+        }
+
+        // restoring the same keyboard does not send the keymap again
+        let keyboard = seat.add_keyboard(XkbConfig::default(), 200, 25).unwrap();
+        fixture.roundtrip();
+        assert_eq!(fixture.client.keymaps, 3);
+        seat.remove_keyboard();
+        seat.restore_keyboard(keyboard);
+        fixture.roundtrip();
+        assert_eq!(fixture.client.keymaps, 3);
+    }
+}
@@ -25,11 +25,19 @@ use crate::{
         Seat,
     },
     utils::{iter::new_locked_obj_iter_from_vec, Client as ClientCoords, Point, Serial},
-    wayland::{compositor, pointer_constraints::with_pointer_constraint},
+    wayland::{
+        compositor,
+        pointer_constraints::with_pointer_constraint,
+        protocol_error::{post_error, ProtocolErrorHandler, ProtocolErrorKind},
+        version::VersionedResource,
+    },
 };
 
 use super::{SeatHandler, SeatState, WaylandFocus};
 
+// `wl_pointer.axis_source.wheel_tilt`
+const AXIS_SOURCE_WHEEL_TILT_SINCE: u32 = 6;
+
 // Use to accumulate discrete values for `wl_pointer` < 8
 #[derive(Default)]
 struct V120UserData {
@@ -46,7 +54,7 @@ impl<D: SeatHandler + 'static> PointerHandle<D> {
     /// May return `None` for a valid `WlPointer` that was created without
     /// the keyboard capability.
     pub fn from_resource(seat: &WlPointer) -> Option<Self> {
-        seat.data::<PointerUserData<D>>()?.handle.clone()
+        seat.data::<PointerUserData<D>>()?.handle()
     }
 
     /// Return all raw [`WlPointer`] instances for a particular [`Client`]
@@ -56,6 +64,36 @@ impl<D: SeatHandler + 'static> PointerHandle<D> {
     }
 }
 
+impl<D> PointerHandle<D>
+where
+    D: SeatHandler + 'static,
+    <D as SeatHandler>::PointerFocus: WaylandFocus,
+{
+    /// Returns the client, that was focused when the active grab started, if any
+    pub fn grab_client(&self) -> Option<Client> {
+        let (focus, _) = self.grab_start_data()?.focus?;
+        focus.wl_surface()?.client()
+    }
+
+    /// Checks if the client of `surface` may warp this pointer, e.g. using the pointer warp protocol
+    ///
+    /// This is the case if `serial` matches the last enter event and the pointer is currently
+    /// focused on `surface`. Unlike setting the cursor, an active grab does not allow warping.
+    pub fn can_warp(&self, surface: &WlSurface, serial: Serial) -> bool {
+        if *self.wl_pointer.last_enter.lock().unwrap() != Some(serial) {
+            return false;
+        }
+
+        self.inner
+            .lock()
+            .unwrap()
+            .focus
+            .as_ref()
+            .and_then(|(focus, _)| focus.wl_surface())
+            .is_some_and(|focus| *focus == *surface)
+    }
+}
+
 #[derive(Debug, Default)]
 pub(crate) struct WlPointerHandle {
     pub(crate) last_enter: Mutex<Option<Serial>>,
@@ -84,9 +122,7 @@ impl WlPointerHandle {
     fn leave(&self, surface: &WlSurface, serial: Serial, _time: u32) {
         self.for_each_focused_pointer(surface, |ptr| {
             ptr.leave(serial.into(), surface);
-            if ptr.version() >= 5 {
-                ptr.frame();
-            }
+            ptr.send_since(wl_pointer::EVT_FRAME_SINCE, WlPointer::frame);
         });
 
         *self.last_enter.lock().unwrap() = None;
@@ -112,12 +148,12 @@ impl WlPointerHandle {
 
     fn axis<D: SeatHandler + 'static>(&self, surface: &WlSurface, details: AxisFrame) {
         self.for_each_focused_pointer(surface, |ptr| {
-            if ptr.version() >= 5 {
+            if ptr.supports(wl_pointer::EVT_AXIS_SOURCE_SINCE) {
                 // axis source
                 if let Some(source) = details.source {
                     // WheelTilt was not supported before version 6
                     // The best we can do is replace it with Wheel
-                    let source = if ptr.version() < 6 {
+                    let source = if !ptr.supports(AXIS_SOURCE_WHEEL_TILT_SINCE) {
                         match source {
                             AxisSource::WheelTilt => AxisSource::Wheel,
                             other => other,
@@ -130,7 +166,7 @@ impl WlPointerHandle {
                 }
                 // axis discrete
                 if let Some((x, y)) = details.v120 {
-                    if ptr.version() >= 8 {
+                    if ptr.supports(wl_pointer::EVT_AXIS_VALUE120_SINCE) {
                         if x != 0 {
                             ptr.axis_value120(WlAxis::HorizontalScroll, x);
                         }
@@ -186,12 +222,9 @@ impl WlPointerHandle {
                 .client_scale
                 .load(Ordering::Acquire);
             if details.axis.0 != 0.0 {
-                if ptr.version() >= 9 {
-                    ptr.axis_relative_direction(
-                        WlAxis::HorizontalScroll,
-                        details.relative_direction.0.into(),
-                    );
-                }
+                ptr.send_since(wl_pointer::EVT_AXIS_RELATIVE_DIRECTION_SINCE, |ptr| {
+                    ptr.axis_relative_direction(WlAxis::HorizontalScroll, details.relative_direction.0.into())
+                });
                 ptr.axis(
                     details.time,
                     WlAxis::HorizontalScroll,
@@ -199,9 +232,9 @@ impl WlPointerHandle {
                 );
             }
             if details.axis.1 != 0.0 {
-                if ptr.version() >= 9 {
-                    ptr.axis_relative_direction(WlAxis::VerticalScroll, details.relative_direction.1.into());
-                }
+                ptr.send_since(wl_pointer::EVT_AXIS_RELATIVE_DIRECTION_SINCE, |ptr| {
+                    ptr.axis_relative_direction(WlAxis::VerticalScroll, details.relative_direction.1.into())
+                });
                 ptr.axis(
                     details.time,
                     WlAxis::VerticalScroll,
@@ -213,9 +246,7 @@ impl WlPointerHandle {
 
     fn frame(&self, surface: &WlSurface) {
         self.for_each_focused_pointer(surface, |ptr| {
-            if ptr.version() >= 5 {
-                ptr.frame();
-            }
+            ptr.send_since(wl_pointer::EVT_FRAME_SINCE, WlPointer::frame);
         });
     }
 
@@ -344,14 +375,20 @@ where
 /// User data for pointer
 #[derive(Debug)]
 pub struct PointerUserData<D: SeatHandler> {
-    pub(crate) handle: Option<PointerHandle<D>>,
+    pub(crate) handle: Mutex<Option<PointerHandle<D>>>,
     pub(crate) client_scale: Arc<AtomicF64>,
 }
 
+impl<D: SeatHandler> PointerUserData<D> {
+    pub(crate) fn handle(&self) -> Option<PointerHandle<D>> {
+        self.handle.lock().unwrap().clone()
+    }
+}
+
 impl<D> Dispatch<WlPointer, PointerUserData<D>, D> for SeatState<D>
 where
     D: Dispatch<WlPointer, PointerUserData<D>>,
-    D: SeatHandler,
+    D: SeatHandler + ProtocolErrorHandler,
     <D as SeatHandler>::PointerFocus: WaylandFocus,
     D: 'static,
 {
@@ -371,12 +408,11 @@ where
                 hotspot_x,
                 hotspot_y,
             } => {
-                let handle = match &data.handle {
-                    Some(handle) => handle,
-                    None => return,
+                let Some(handle) = data.handle() else {
+                    return;
                 };
 
-                if !allow_setting_cursor(handle, Serial(serial), &pointer.id()) {
+                if !allow_setting_cursor(&handle, Serial(serial), &pointer.id()) {
                     return;
                 }
 
@@ -386,7 +422,13 @@ where
                         if compositor::give_role(&surface, CURSOR_IMAGE_ROLE).is_err()
                             && compositor::get_role(&surface) != Some(CURSOR_IMAGE_ROLE)
                         {
-                            pointer.post_error(wl_pointer::Error::Role, "Given wl_surface has another role.");
+                            post_error(
+                                state,
+                                pointer,
+                                ProtocolErrorKind::Role,
+                                wl_pointer::Error::Role,
+                                "Given wl_surface has another role.",
+                            );
                             return;
                         }
 
@@ -423,7 +465,7 @@ where
                     .seat_state()
                     .seats
                     .iter()
-                    .find(|seat| seat.get_pointer().map(|h| h == *handle).unwrap_or(false))
+                    .find(|seat| seat.get_pointer().map(|h| h == handle).unwrap_or(false))
                     .cloned();
 
                 if let Some(seat) = seat {
@@ -438,7 +480,7 @@ where
     }
 
     fn destroyed(_state: &mut D, _: ClientId, pointer: &WlPointer, data: &PointerUserData<D>) {
-        if let Some(ref handle) = data.handle {
+        if let Some(handle) = data.handle() {
             handle
                 .wl_pointer
                 .known_pointers
@@ -548,3 +590,39 @@ where
         .as_ref()
         .is_some_and(|(focus, _)| focus.same_client_as(object_id))
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        input::pointer::MotionEvent,
+        utils::{Serial, SERIAL_COUNTER},
+        wayland::test_utils::Fixture,
+    };
+
+    #[test]
+    fn warping_requires_focus_and_serial() {
+        let mut fixture = Fixture::new();
+        let (_, surface) = fixture.create_surface();
+        let (_, other) = fixture.create_surface();
+        let mut seat = fixture.state.seat_state.new_seat("seat0");
+        let pointer = seat.add_pointer();
+
+        let motion = |serial: Serial| MotionEvent {
+            location: (10.0, 10.0).into(),
+            serial,
+            time: 0,
+        };
+        let enter = SERIAL_COUNTER.next_serial();
+        let focus = Some((surface.clone(), (0.0, 0.0).into()));
+        pointer.motion(&mut fixture.state, focus, &motion(enter));
+
+        assert!(pointer.can_warp(&surface, enter));
+        assert!(!pointer.can_warp(&surface, SERIAL_COUNTER.next_serial()));
+        // another surface of the same client
+        assert!(!pointer.can_warp(&other, enter));
+
+        // the pointer left the surface
+        pointer.motion(&mut fixture.state, None, &motion(SERIAL_COUNTER.next_serial()));
+        assert!(!pointer.can_warp(&surface, enter));
+    }
+}
@@ -1,20 +1,20 @@
-use std::sync::{atomic::Ordering, Arc};
+use std::sync::{atomic::Ordering, Arc, Mutex};
 
 use atomic_float::AtomicF64;
 use wayland_server::{
     backend::ClientId,
     protocol::wl_touch::{self, WlTouch},
-    Dispatch, DisplayHandle, Resource,
+    Client, Dispatch, DisplayHandle, Resource,
 };
 
-use super::{SeatHandler, SeatState};
+use super::{SeatHandler, SeatState, WaylandFocus};
 use crate::input::touch::TouchTarget;
 use crate::input::{
     touch::{MotionEvent, OrientationEvent, ShapeEvent, UpEvent},
     Seat,
 };
 use crate::{input::touch::DownEvent, wayland::seat::wl_surface::WlSurface};
-use crate::{input::touch::TouchHandle, utils::Serial};
+use crate::{input::touch::TouchHandle, utils::Serial, wayland::version::VersionedResource};
 
 impl<D: SeatHandler> TouchHandle<D> {
     pub(crate) fn new_touch(&self, touch: WlTouch) {
@@ -29,7 +29,19 @@ impl<D: SeatHandler + 'static> TouchHandle<D> {
     /// May return `None` for a valid `WlTouch` that was created without
     /// the keyboard capability.
     pub fn from_resource(seat: &WlTouch) -> Option<Self> {
-        seat.data::<TouchUserData<D>>()?.handle.clone()
+        seat.data::<TouchUserData<D>>()?.handle()
+    }
+}
+
+impl<D> TouchHandle<D>
+where
+    D: SeatHandler + 'static,
+    <D as SeatHandler>::TouchFocus: WaylandFocus,
+{
+    /// Returns the client, that was focused when the active grab started, if any
+    pub fn grab_client(&self) -> Option<Client> {
+        let (focus, _) = self.grab_start_data()?.focus?;
+        focus.wl_surface()?.client()
     }
 }
 
@@ -116,14 +128,18 @@ where
     fn shape(&self, seat: &Seat<D>, _data: &mut D, event: &ShapeEvent, seq: Serial) {
         let slot = event.slot;
         for_each_focused_touch(seat, self, seq, |touch| {
-            touch.shape(slot.into(), event.major, event.minor);
+            touch.send_since(wl_touch::EVT_SHAPE_SINCE, |touch| {
+                touch.shape(slot.into(), event.major, event.minor)
+            });
         })
     }
 
     fn orientation(&self, seat: &Seat<D>, _data: &mut D, event: &OrientationEvent, seq: Serial) {
         let slot = event.slot;
         for_each_focused_touch(seat, self, seq, |touch| {
-            touch.orientation(slot.into(), event.orientation);
+            touch.send_since(wl_touch::EVT_ORIENTATION_SINCE, |touch| {
+                touch.orientation(slot.into(), event.orientation)
+            });
         })
     }
 }
@@ -131,10 +147,16 @@ where
 /// User data for touch
 #[derive(Debug)]
 pub struct TouchUserData<D: SeatHandler> {
-    pub(crate) handle: Option<TouchHandle<D>>,
+    pub(crate) handle: Mutex<Option<TouchHandle<D>>>,
     pub(crate) client_scale: Arc<AtomicF64>,
 }
 
+impl<D: SeatHandler> TouchUserData<D> {
+    pub(crate) fn handle(&self) -> Option<TouchHandle<D>> {
+        self.handle.lock().unwrap().clone()
+    }
+}
+
 impl<D> Dispatch<WlTouch, TouchUserData<D>, D> for SeatState<D>
 where
     D: Dispatch<WlTouch, TouchUserData<D>>,
@@ -153,7 +175,7 @@ where
     }
 
     fn destroyed(_state: &mut D, _client_id: ClientId, touch: &WlTouch, data: &TouchUserData<D>) {
-        if let Some(ref handle) = data.handle {
+        if let Some(handle) = data.handle() {
             handle
                 .known_instances
                 .lock()
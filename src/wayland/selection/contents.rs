@@ -0,0 +1,379 @@
+use std::{
+    cell::{Cell, RefCell},
+    io,
+    os::unix::io::{AsFd, OwnedFd},
+    rc::Rc,
+    sync::Arc,
+    time::Duration,
+};
+
+use calloop::{
+    generic::Generic,
+    timer::{TimeoutAction, Timer},
+    Interest, LoopHandle, Mode, PostAction, RegistrationToken,
+};
+use rustix::{
+    fs::{fcntl_getfl, fcntl_setfl, OFlags},
+    io::Errno,
+    pipe::{pipe_with, PipeFlags},
+};
+use tracing::debug;
+
+/// Data of a compositor-owned selection in a number of mime types
+///
+/// The contents can be offered to clients using e.g.
+/// [`set_data_device_selection`](super::data_device::set_data_device_selection) and requests
+/// of clients answered from [`SelectionHandler::send_selection`](super::SelectionHandler::send_selection)
+/// using [`SelectionContents::send`], which writes the data from the event loop without blocking.
+/// Cloning is cheap, the data is shared between clones.
+///
+/// ```no_run
+/// # use std::os::unix::io::OwnedFd;
+/// use smithay::reexports::calloop::LoopHandle;
+/// use smithay::wayland::selection::{SelectionContents, SelectionHandler, SelectionTarget};
+/// use smithay::wayland::selection::data_device::set_data_device_selection;
+/// # use smithay::input::{Seat, SeatHandler, SeatState};
+/// # use smithay::reexports::wayland_server::{protocol::wl_surface::WlSurface, DisplayHandle};
+///
+/// struct State {
+///     loop_handle: LoopHandle<'static, State>,
+///     // ...
+/// }
+///
+/// impl SelectionHandler for State {
+///     type SelectionUserData = SelectionContents;
+///
+///     fn send_selection(
+///         &mut self,
+///         ty: SelectionTarget,
+///         mime_type: String,
+///         fd: OwnedFd,
+///         seat: Seat<Self>,
+///         contents: &SelectionContents,
+///     ) {
+///         if let Err(err) = contents.send(&self.loop_handle, &mime_type, fd) {
+///             eprintln!("Failed to send selection: {}", err);
+///         }
+///     }
+/// }
+/// # impl SeatHandler for State {
+/// #     type KeyboardFocus = WlSurface;
+/// #     type PointerFocus = WlSurface;
+/// #     type TouchFocus = WlSurface;
+/// #     fn seat_state(&mut self) -> &mut SeatState<Self> { unimplemented!() }
+/// # }
+/// # impl smithay::wayland::selection::data_device::DataDeviceHandler for State {
+/// #     fn data_device_state(&self) -> &smithay::wayland::selection::data_device::DataDeviceState { unimplemented!() }
+/// # }
+/// # impl smithay::wayland::selection::data_device::ClientDndGrabHandler for State {}
+/// # impl smithay::wayland::selection::data_device::ServerDndGrabHandler for State {}
+///
+/// # let dh: DisplayHandle = unimplemented!();
+/// # let seat: Seat<State> = unimplemented!();
+/// // copy a screenshot to the clipboard
+/// # let png: Vec<u8> = Vec::new();
+/// let contents = SelectionContents::new().with_data("image/png", png);
+/// set_data_device_selection(&dh, &seat, contents.mime_types(), contents);
+/// ```
+///
+/// Selections set by clients can be read asynchronously using
+/// [`read_data_device_selection`](super::data_device::read_data_device_selection) and
+/// [`read_primary_selection`](super::primary_selection::read_primary_selection).
+#[derive(Debug, Clone, Default)]
+pub struct SelectionContents {
+    entries: Arc<Vec<(String, Arc<[u8]>)>>,
+}
+
+impl SelectionContents {
+    /// Creates empty selection contents
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the data for a mime type, replacing any previous data for it
+    pub fn with_data(mut self, mime_type: impl Into<String>, data: impl Into<Arc<[u8]>>) -> Self {
+        let mime_type = mime_type.into();
+        let entries = Arc::make_mut(&mut self.entries);
+        entries.retain(|(existing, _)| *existing != mime_type);
+        entries.push((mime_type, data.into()));
+        self
+    }
+
+    /// Returns the mime types the contents are available in
+    pub fn mime_types(&self) -> Vec<String> {
+        self.entries
+            .iter()
+            .map(|(mime_type, _)| mime_type.clone())
+            .collect()
+    }
+
+    /// Returns the data for the given mime type
+    pub fn get(&self, mime_type: &str) -> Option<&[u8]> {
+        self.entries
+            .iter()
+            .find(|(existing, _)| existing == mime_type)
+            .map(|(_, data)| &**data)
+    }
+
+    /// Writes the data for the given mime type into `fd`
+    ///
+    /// The data is written without blocking whenever `fd` becomes writable,
+    /// the `fd` is closed once all data was written.
+    pub fn send<D>(
+        &self,
+        handle: &LoopHandle<'_, D>,
+        mime_type: &str,
+        fd: OwnedFd,
+    ) -> Result<(), SelectionTransferError> {
+        let data = self
+            .entries
+            .iter()
+            .find(|(existing, _)| existing == mime_type)
+            .map(|(_, data)| data.clone())
+            .ok_or(SelectionTransferError::InvalidMimetype)?;
+
+        let flags = fcntl_getfl(&fd).map_err(io::Error::from)?;
+        fcntl_setfl(&fd, flags | OFlags::NONBLOCK).map_err(io::Error::from)?;
+
+        let mut written = 0;
+        handle
+            .insert_source(Generic::new(fd, Interest::WRITE, Mode::Level), move |_, fd, _| {
+                while written < data.len() {
+                    match rustix::io::write(fd.as_fd(), &data[written..]) {
+                        Ok(len) => written += len,
+                        Err(Errno::INTR) => continue,
+                        Err(Errno::AGAIN) => return Ok(PostAction::Continue),
+                        Err(err) => {
+                            debug!(?err, "Failed to write selection");
+                            break;
+                        }
+                    }
+                }
+                Ok(PostAction::Remove)
+            })
+            .map_err(|err| io::Error::from(err.error))?;
+
+        Ok(())
+    }
+}
+
+/// Errors happening when transferring selection contents
+#[derive(Debug, thiserror::Error)]
+pub enum SelectionTransferError {
+    /// Requested mime type is not available
+    #[error("Requested mime type is not available")]
+    InvalidMimetype,
+    /// Reading server side selection contents is not supported
+    #[error("Current selection is server-side")]
+    ServerSideSelection,
+    /// There is no active selection
+    #[error("No active selection to query")]
+    NoSelection,
+    /// Setting up the transfer failed
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+impl From<super::data_device::SelectionRequestError> for SelectionTransferError {
+    fn from(err: super::data_device::SelectionRequestError) -> Self {
+        use super::data_device::SelectionRequestError;
+        match err {
+            SelectionRequestError::InvalidMimetype => Self::InvalidMimetype,
+            SelectionRequestError::ServerSideSelection => Self::ServerSideSelection,
+            SelectionRequestError::NoSelection => Self::NoSelection,
+        }
+    }
+}
+
+impl From<super::primary_selection::SelectionRequestError> for SelectionTransferError {
+    fn from(err: super::primary_selection::SelectionRequestError) -> Self {
+        use super::primary_selection::SelectionRequestError;
+        match err {
+            SelectionRequestError::InvalidMimetype => Self::InvalidMimetype,
+            SelectionRequestError::ServerSideSelection => Self::ServerSideSelection,
+            SelectionRequestError::NoSelection => Self::NoSelection,
+        }
+    }
+}
+
+/// Reads a selection through a pipe, whose write end is handed to `request`
+///
+/// `callback` is called with the data once the writer closed the pipe, or with an error if
+/// the data exceeds `max_size` bytes or the writer did not finish within `timeout`.
+pub(super) fn read_selection<'l, D, E, F>(
+    handle: &LoopHandle<'l, D>,
+    request: impl FnOnce(OwnedFd) -> Result<(), E>,
+    max_size: usize,
+    timeout: Duration,
+    callback: F,
+) -> Result<(), SelectionTransferError>
+where
+    D: 'l,
+    E: Into<SelectionTransferError>,
+    F: FnOnce(&mut D, io::Result<Vec<u8>>) + 'l,
+{
+    let (read, write) = pipe_with(PipeFlags::CLOEXEC).map_err(io::Error::from)?;
+    request(write).map_err(Into::into)?;
+    receive(handle, read, max_size, timeout, callback)
+}
+
+fn receive<'l, D, F>(
+    handle: &LoopHandle<'l, D>,
+    fd: OwnedFd,
+    max_size: usize,
+    timeout: Duration,
+    callback: F,
+) -> Result<(), SelectionTransferError>
+where
+    D: 'l,
+    F: FnOnce(&mut D, io::Result<Vec<u8>>) + 'l,
+{
+    let flags = fcntl_getfl(&fd).map_err(io::Error::from)?;
+    fcntl_setfl(&fd, flags | OFlags::NONBLOCK).map_err(io::Error::from)?;
+
+    // shared by the read and the timeout source, whichever finishes first calls it and removes the other
+    let callback = Rc::new(RefCell::new(Some(callback)));
+    let timer_token = Rc::new(Cell::new(None::<RegistrationToken>));
+
+    let read_callback = callback.clone();
+    let read_timer_token = timer_token.clone();
+    let read_handle = handle.clone();
+    let mut data = Vec::new();
+    let read_token = handle
+        .insert_source(
+            Generic::new(fd, Interest::READ, Mode::Level),
+            move |_, fd, state| {
+                let mut buf = [0; 4096];
+                let result = loop {
+                    match rustix::io::read(fd.as_fd(), &mut buf) {
+                        Ok(0) => break Ok(std::mem::take(&mut data)),
+                        Ok(len) if data.len() + len > max_size => {
+                            break Err(io::Error::new(
+                                io::ErrorKind::InvalidData,
+                                "selection exceeds the size limit",
+                            ))
+                        }
+                        Ok(len) => data.extend_from_slice(&buf[..len]),
+                        Err(Errno::INTR) => continue,
+                        Err(Errno::AGAIN) => return Ok(PostAction::Continue),
+                        Err(err) => break Err(io::Error::from(err)),
+                    }
+                };
+                if let Some(token) = read_timer_token.take() {
+                    read_handle.remove(token);
+                }
+                if let Some(callback) = read_callback.borrow_mut().take() {
+                    callback(state, result);
+                }
+                Ok(PostAction::Remove)
+            },
+        )
+        .map_err(|err| io::Error::from(err.error))?;
+
+    let timer_handle = handle.clone();
+    let token = handle
+        .insert_source(Timer::from_duration(timeout), move |_, _, state| {
+            timer_handle.remove(read_token);
+            if let Some(callback) = callback.borrow_mut().take() {
+                debug!("Reading selection timed out");
+                callback(state, Err(io::ErrorKind::TimedOut.into()));
+            }
+            TimeoutAction::Drop
+        })
+        .map_err(|err| {
+            handle.remove(read_token);
+            io::Error::from(err.error)
+        })?;
+    timer_token.set(Some(token));
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use calloop::EventLoop;
+
+    use super::*;
+
+    #[test]
+    fn send_and_receive() {
+        let mut event_loop = EventLoop::<Option<Vec<u8>>>::try_new().unwrap();
+        let handle = event_loop.handle();
+
+        let payload = vec![42; 100_000];
+        let contents = SelectionContents::new()
+            .with_data("text/plain", b"hello".to_vec())
+            .with_data("application/octet-stream", payload.clone());
+        assert_eq!(contents.mime_types(), ["text/plain", "application/octet-stream"]);
+        assert!(matches!(
+            contents.send(&handle, "image/png", pipe_with(PipeFlags::CLOEXEC).unwrap().1),
+            Err(SelectionTransferError::InvalidMimetype)
+        ));
+
+        read_selection(
+            &handle,
+            |fd| contents.send(&handle, "application/octet-stream", fd),
+            usize::MAX,
+            Duration::from_secs(5),
+            |state: &mut Option<Vec<u8>>, data| *state = Some(data.unwrap()),
+        )
+        .unwrap();
+
+        let mut received = None;
+        while received.is_none() {
+            event_loop
+                .dispatch(Some(Duration::from_secs(1)), &mut received)
+                .unwrap();
+        }
+        assert_eq!(received.unwrap(), payload);
+    }
+
+    fn read_error(
+        event_loop: &mut EventLoop<'static, Option<io::Error>>,
+        request: impl FnOnce(OwnedFd) -> Result<(), SelectionTransferError>,
+        max_size: usize,
+    ) -> io::Error {
+        read_selection(
+            &event_loop.handle(),
+            request,
+            max_size,
+            Duration::from_millis(50),
+            |state: &mut Option<io::Error>, data| *state = data.err(),
+        )
+        .unwrap();
+
+        let mut error = None;
+        while error.is_none() {
+            event_loop
+                .dispatch(Some(Duration::from_secs(1)), &mut error)
+                .unwrap();
+        }
+        error.unwrap()
+    }
+
+    #[test]
+    fn receive_is_limited() {
+        let mut event_loop = EventLoop::<Option<io::Error>>::try_new().unwrap();
+        let handle = event_loop.handle();
+        let contents = SelectionContents::new().with_data("text/plain", vec![0; 10_000]);
+
+        let error = read_error(
+            &mut event_loop,
+            |fd| contents.send(&handle, "text/plain", fd),
+            4096,
+        );
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        // the writer never closes the pipe
+        let mut writer = None;
+        let error = read_error(
+            &mut event_loop,
+            |fd| {
+                writer = Some(fd);
+                Ok(())
+            },
+            usize::MAX,
+        );
+        assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+    }
+}
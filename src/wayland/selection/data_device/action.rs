@@ -0,0 +1,245 @@
+use std::{cell::RefCell, rc::Rc};
+
+use wayland_server::protocol::wl_data_device_manager::DndAction;
+
+use crate::input::{keyboard::ModifiersState, Seat, SeatHandler};
+
+use super::{default_action_chooser, DataDeviceHandler};
+
+type Renegotiate<D> = Rc<dyn Fn(&mut D, &Seat<D>)>;
+type ModifiersHook<D> = Rc<dyn Fn(&mut D, &Seat<D>, ModifiersState)>;
+
+// Last action negotiation of the active drag'n'drop offer of a seat
+struct DndActionNegotiation<D: SeatHandler>(RefCell<Option<Renegotiate<D>>>);
+
+// Handler notified about modifier changes during the active drag'n'drop of a seat
+struct DndModifiersHook<D: SeatHandler>(RefCell<Option<ModifiersHook<D>>>);
+
+/// An action chooser for DnD negociation following the keyboard modifiers
+///
+/// Holding ctrl forces the copy action, shift the move action and both together
+/// let the target ask the user, if the action is available.
+/// Otherwise this falls back to [`default_action_chooser`].
+///
+/// Use it in [`DataDeviceHandler::dnd_action_choice`], modifier changes during the drag
+/// renegotiate the action automatically.
+pub fn modifier_action_chooser(
+    available: DndAction,
+    preferred: DndAction,
+    modifiers: &ModifiersState,
+) -> DndAction {
+    let forced = match (modifiers.ctrl, modifiers.shift) {
+        (true, true) => DndAction::Ask,
+        (true, false) => DndAction::Copy,
+        (false, true) => DndAction::Move,
+        (false, false) => DndAction::empty(),
+    };
+    if !forced.is_empty() && available.contains(forced) {
+        forced
+    } else {
+        default_action_chooser(available, preferred)
+    }
+}
+
+/// Renegotiates the action of the active drag'n'drop offer of a seat
+///
+/// Call this when the inputs of your [`DataDeviceHandler::dnd_action_choice`] change during a drag.
+/// Changes of the keyboard modifiers are already handled automatically. The action is chosen again using the actions
+/// last set by the target and both sides are notified if it changed.
+///
+/// Does nothing if there is no active offer.
+pub fn update_dnd_action<D>(data: &mut D, seat: &Seat<D>)
+where
+    D: DataDeviceHandler + 'static,
+{
+    let renegotiate = seat
+        .user_data()
+        .get::<DndActionNegotiation<D>>()
+        .and_then(|negotiation| negotiation.0.borrow().clone());
+    if let Some(renegotiate) = renegotiate {
+        renegotiate(data, seat);
+    }
+}
+
+/// Forwards changed keyboard modifiers to the active drag'n'drop of a seat
+///
+/// Called by the keyboard, renegotiates the action of the active offer and notifies
+/// the handler of the drag.
+pub(crate) fn dnd_modifiers_changed<D>(data: &mut D, seat: &Seat<D>, modifiers: ModifiersState)
+where
+    D: SeatHandler + 'static,
+{
+    let hook = seat
+        .user_data()
+        .get::<DndModifiersHook<D>>()
+        .and_then(|hook| hook.0.borrow().clone());
+    if let Some(hook) = hook {
+        hook(data, seat, modifiers);
+    }
+
+    let renegotiate = seat
+        .user_data()
+        .get::<DndActionNegotiation<D>>()
+        .and_then(|negotiation| negotiation.0.borrow().clone());
+    if let Some(renegotiate) = renegotiate {
+        renegotiate(data, seat);
+    }
+}
+
+pub(super) fn choose_dnd_action<D>(
+    data: &mut D,
+    seat: &Seat<D>,
+    available: DndAction,
+    preferred: DndAction,
+) -> DndAction
+where
+    D: DataDeviceHandler,
+{
+    let chosen_action = data.dnd_action_choice(seat, available, preferred);
+    // check that the user provided callback respects that one precise action should be chosen
+    debug_assert!(
+        [DndAction::None, DndAction::Move, DndAction::Copy, DndAction::Ask].contains(&chosen_action),
+        "Only one precise action should be chosen"
+    );
+    chosen_action
+}
+
+pub(super) fn set_dnd_action_negotiation<D, F>(seat: &Seat<D>, renegotiate: F)
+where
+    D: SeatHandler + 'static,
+    F: Fn(&mut D, &Seat<D>) + 'static,
+{
+    let user_data = seat.user_data();
+    user_data.insert_if_missing(|| DndActionNegotiation::<D>(RefCell::new(None)));
+    *user_data.get::<DndActionNegotiation<D>>().unwrap().0.borrow_mut() = Some(Rc::new(renegotiate));
+}
+
+pub(super) fn clear_dnd_action_negotiation<D>(seat: &Seat<D>)
+where
+    D: SeatHandler + 'static,
+{
+    if let Some(negotiation) = seat.user_data().get::<DndActionNegotiation<D>>() {
+        negotiation.0.borrow_mut().take();
+    }
+}
+
+pub(super) fn set_dnd_modifiers_hook<D, F>(seat: &Seat<D>, hook: F)
+where
+    D: SeatHandler + 'static,
+    F: Fn(&mut D, &Seat<D>, ModifiersState) + 'static,
+{
+    let user_data = seat.user_data();
+    user_data.insert_if_missing(|| DndModifiersHook::<D>(RefCell::new(None)));
+    *user_data.get::<DndModifiersHook<D>>().unwrap().0.borrow_mut() = Some(Rc::new(hook));
+}
+
+pub(super) fn clear_dnd_modifiers_hook<D>(seat: &Seat<D>)
+where
+    D: SeatHandler + 'static,
+{
+    if let Some(hook) = seat.user_data().get::<DndModifiersHook<D>>() {
+        hook.0.borrow_mut().take();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        input::{keyboard::XkbConfig, pointer::GrabStartData},
+        wayland::{
+            selection::data_device::{dnd_grab::DnDGrab, start_dnd, SourceMetadata},
+            test_utils::Fixture,
+        },
+    };
+
+    #[test]
+    fn modifiers_force_actions() {
+        let all = DndAction::Copy | DndAction::Move | DndAction::Ask;
+        let mut modifiers = ModifiersState::default();
+        assert_eq!(
+            modifier_action_chooser(all, DndAction::Move, &modifiers),
+            DndAction::Move
+        );
+
+        modifiers.ctrl = true;
+        assert_eq!(
+            modifier_action_chooser(all, DndAction::Move, &modifiers),
+            DndAction::Copy
+        );
+        modifiers.shift = true;
+        assert_eq!(
+            modifier_action_chooser(all, DndAction::Move, &modifiers),
+            DndAction::Ask
+        );
+
+        // unavailable actions are not forced
+        modifiers.ctrl = false;
+        assert_eq!(
+            modifier_action_chooser(DndAction::Copy, DndAction::None, &modifiers),
+            DndAction::Copy
+        );
+    }
+
+    fn ctrl() -> ModifiersState {
+        ModifiersState {
+            ctrl: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn client_drags_forward_modifiers() {
+        let mut fixture = Fixture::new();
+        let (_, origin) = fixture.create_surface();
+        let mut seat = fixture.state.seat_state.new_seat("seat0");
+        let keyboard = seat.add_keyboard(XkbConfig::default(), 200, 25).unwrap();
+
+        let start_data = GrabStartData {
+            focus: None,
+            button: 0x110,
+            location: (0.0, 0.0).into(),
+        };
+        // creating the grab already forwards the modifiers, before the grab is set
+        let dh = fixture.display.handle();
+        let _grab = DnDGrab::new_pointer(&dh, start_data, None, origin, seat.clone(), None);
+        keyboard.set_modifier_state(&mut fixture.state, ctrl());
+
+        assert_eq!(fixture.state.client_dnd_modifiers.len(), 1);
+        assert!(fixture.state.client_dnd_modifiers[0].ctrl);
+        assert!(fixture.state.server_dnd_modifiers.is_empty());
+    }
+
+    #[test]
+    fn server_drags_forward_modifiers() {
+        let mut fixture = Fixture::new();
+        let mut seat = fixture.state.seat_state.new_seat("seat0");
+        let keyboard = seat.add_keyboard(XkbConfig::default(), 200, 25).unwrap();
+        let pointer = seat.add_pointer();
+
+        let start_data = GrabStartData {
+            focus: None,
+            button: 0x110,
+            location: (0.0, 0.0).into(),
+        };
+        let dh = fixture.display.handle();
+        start_dnd(
+            &dh,
+            &seat,
+            &mut fixture.state,
+            1.into(),
+            Some(start_data),
+            None,
+            SourceMetadata::default(),
+        );
+        keyboard.set_modifier_state(&mut fixture.state, ctrl());
+        assert_eq!(fixture.state.server_dnd_modifiers.len(), 1);
+        assert!(fixture.state.server_dnd_modifiers[0].ctrl);
+
+        // no longer forwarded once the drag ended
+        pointer.unset_grab(&mut fixture.state, 2.into(), 0);
+        keyboard.set_modifier_state(&mut fixture.state, ModifiersState::default());
+        assert_eq!(fixture.state.server_dnd_modifiers.len(), 1);
+        assert!(fixture.state.client_dnd_modifiers.is_empty());
+    }
+}
@@ -14,6 +14,7 @@ use crate::{
     utils::Serial,
     wayland::{
         compositor,
+        protocol_error::{post_error, ProtocolErrorHandler, ProtocolErrorKind},
         seat::WaylandFocus,
         selection::{
             device::SelectionDevice,
@@ -22,6 +23,7 @@ use crate::{
             source::{SelectionSource, SelectionSourceProvider},
             SelectionTarget,
         },
+        xdg_toplevel_drag,
     },
 };
 
@@ -40,7 +42,7 @@ impl<D> Dispatch<WlDataDevice, DataDeviceUserData, D> for DataDeviceState
 where
     D: Dispatch<WlDataDevice, DataDeviceUserData>,
     D: DataDeviceHandler,
-    D: SeatHandler,
+    D: SeatHandler + ProtocolErrorHandler,
     <D as SeatHandler>::PointerFocus: WaylandFocus,
     <D as SeatHandler>::TouchFocus: WaylandFocus,
     <D as SeatHandler>::KeyboardFocus: WaylandFocus,
@@ -72,7 +74,10 @@ where
                     if pointer.has_grab(serial) {
                         if let Some(ref icon) = icon {
                             if compositor::give_role(icon, DND_ICON_ROLE).is_err() {
-                                resource.post_error(
+                                post_error(
+                                    handler,
+                                    resource,
+                                    ProtocolErrorKind::Role,
                                     wl_data_device::Error::Role,
                                     "Given surface already has an other role",
                                 );
@@ -95,7 +100,10 @@ where
                     if touch.has_grab(serial) {
                         if let Some(ref icon) = icon {
                             if compositor::give_role(icon, DND_ICON_ROLE).is_err() {
-                                resource.post_error(
+                                post_error(
+                                    handler,
+                                    resource,
+                                    ProtocolErrorKind::Role,
                                     wl_data_device::Error::Role,
                                     "Given surface already has an other role",
                                 );
@@ -129,6 +137,12 @@ where
                         return;
                     }
                 };
+                if source
+                    .as_ref()
+                    .is_some_and(xdg_toplevel_drag::reject_non_dnd_source)
+                {
+                    return;
+                }
                 let source = source.map(SelectionSourceProvider::DataDevice);
 
                 handler.new_selection(
@@ -1,4 +1,5 @@
 use std::{
+    borrow::Cow,
     cell::RefCell,
     fmt,
     os::unix::io::{AsFd, OwnedFd},
@@ -11,6 +12,7 @@ use wayland_server::{
         wl_data_device_manager::DndAction,
         wl_data_offer::{self, WlDataOffer},
         wl_data_source::{self, WlDataSource},
+        wl_seat::WlSeat,
         wl_surface::WlSurface,
     },
     DisplayHandle, Resource,
@@ -28,10 +30,20 @@ use crate::{
         Seat, SeatHandler,
     },
     utils::{IsAlive, Logical, Point, Serial, SERIAL_COUNTER},
-    wayland::{seat::WaylandFocus, selection::seat_data::SeatData},
+    wayland::{
+        compositor, seat::WaylandFocus, selection::seat_data::SeatData, version::VersionedResource,
+        xdg_toplevel_drag,
+    },
 };
 
-use super::{with_source_metadata, ClientDndGrabHandler, DataDeviceHandler};
+use super::{
+    action::{
+        choose_dnd_action, clear_dnd_action_negotiation, clear_dnd_modifiers_hook,
+        set_dnd_action_negotiation, set_dnd_modifiers_hook,
+    },
+    transfer::DndTransfers,
+    with_source_metadata, ClientDndGrabHandler, DataDeviceHandler, DataDeviceUserData,
+};
 
 /// Grab during a client-initiated DnD operation.
 pub struct DnDGrab<D: SeatHandler> {
@@ -64,7 +76,10 @@ impl<D: SeatHandler + 'static> fmt::Debug for DnDGrab<D> {
     }
 }
 
-impl<D: SeatHandler> DnDGrab<D> {
+impl<D> DnDGrab<D>
+where
+    D: DataDeviceHandler + 'static,
+{
     pub(crate) fn new_pointer(
         dh: &DisplayHandle,
         start_data: PointerGrabStartData<D>,
@@ -73,6 +88,10 @@ impl<D: SeatHandler> DnDGrab<D> {
         seat: Seat<D>,
         icon: Option<WlSurface>,
     ) -> Self {
+        if let Some(source) = source.as_ref() {
+            xdg_toplevel_drag::set_dragging(source, true);
+        }
+        forward_modifiers(&seat);
         Self {
             dh: dh.clone(),
             pointer_start_data: Some(start_data),
@@ -95,6 +114,10 @@ impl<D: SeatHandler> DnDGrab<D> {
         seat: Seat<D>,
         icon: Option<WlSurface>,
     ) -> Self {
+        if let Some(source) = source.as_ref() {
+            xdg_toplevel_drag::set_dragging(source, true);
+        }
+        forward_modifiers(&seat);
         Self {
             dh: dh.clone(),
             pointer_start_data: None,
@@ -123,6 +146,8 @@ where
         serial: Serial,
         time: u32,
     ) {
+        // an attached toplevel does not participate in the selection of the drop target
+        let focus = focus.filter(|(focus, _)| !self.is_attached_toplevel(focus));
         let seat_data = self
             .seat
             .user_data()
@@ -143,6 +168,7 @@ where
                     self.pending_offers.clear();
                     if let Some(offer_data) = self.offer_data.take() {
                         offer_data.lock().unwrap().active = false;
+                        clear_dnd_action_negotiation(&self.seat);
                     }
                 }
             }
@@ -167,11 +193,16 @@ where
                         finished: false,
                         chosen_action: DndAction::empty(),
                     }));
+                    let transfers = DndTransfers::new(source.clone());
                     for device in seat_data
                         .known_data_devices()
                         .filter(|d| d.id().same_client_as(&surface.id()))
                     {
                         let handle = self.dh.backend_handle();
+                        let wl_seat = match device.data::<DataDeviceUserData>() {
+                            Some(data) => data.wl_seat.clone(),
+                            None => continue,
+                        };
 
                         // create a data offer
                         let offer = handle
@@ -182,6 +213,8 @@ where
                                 Arc::new(DndDataOffer {
                                     offer_data: offer_data.clone(),
                                     source: source.clone(),
+                                    transfers: transfers.clone(),
+                                    wl_seat,
                                 }),
                             )
                             .unwrap();
@@ -193,7 +226,9 @@ where
                             for mime_type in meta.mime_types.iter().cloned() {
                                 offer.offer(mime_type);
                             }
-                            offer.source_actions(meta.dnd_action);
+                            if offer.supports(wl_data_offer::EVT_SOURCE_ACTIONS_SINCE) {
+                                offer.source_actions(meta.dnd_action);
+                            }
                         })
                         .unwrap();
                         device.enter(serial.into(), &surface, x, y, Some(&offer));
@@ -224,6 +259,34 @@ where
         }
     }
 
+    fn is_attached_toplevel<F: WaylandFocus>(&self, focus: &F) -> bool {
+        let Some((toplevel, _)) = self
+            .data_source
+            .as_ref()
+            .and_then(xdg_toplevel_drag::attached_toplevel)
+        else {
+            return false;
+        };
+        let Some(mut surface) = focus.wl_surface().map(Cow::into_owned) else {
+            return false;
+        };
+        while let Some(parent) = compositor::get_parent(&surface) {
+            surface = parent;
+        }
+        &surface == toplevel.wl_surface()
+    }
+
+    fn move_attached_toplevel(&mut self, data: &mut D, location: Point<f64, Logical>) {
+        if let Some((toplevel, offset)) = self
+            .data_source
+            .as_ref()
+            .and_then(xdg_toplevel_drag::attached_toplevel)
+        {
+            let location = location.to_i32_round() - offset;
+            data.toplevel_drag_motion(toplevel, location, self.seat.clone());
+        }
+    }
+
     fn drop(&mut self, data: &mut D) {
         // the user dropped, proceed to the drop
         let seat_data = self
@@ -255,12 +318,18 @@ where
                 data.active = false;
             }
         }
+        clear_dnd_action_negotiation(&self.seat);
+        clear_dnd_modifiers_hook(&self.seat);
         if let Some(ref source) = self.data_source {
             if !validated {
                 source.cancelled();
-            } else if source.version() >= wl_data_source::EVT_DND_DROP_PERFORMED_SINCE {
-                source.dnd_drop_performed();
+            } else {
+                source.send_since(
+                    wl_data_source::EVT_DND_DROP_PERFORMED_SINCE,
+                    WlDataSource::dnd_drop_performed,
+                );
             }
+            xdg_toplevel_drag::set_dragging(source, false);
         }
 
         ClientDndGrabHandler::dropped(data, self.current_focus.clone(), validated, self.seat.clone());
@@ -295,6 +364,7 @@ where
         handle.motion(data, None, event);
 
         self.update_focus(focus, event.location, event.serial, event.time);
+        self.move_attached_toplevel(data, event.location);
     }
 
     fn relative_motion(
@@ -438,7 +508,7 @@ where
 
     fn motion(
         &mut self,
-        _data: &mut D,
+        data: &mut D,
         _handle: &mut crate::input::touch::TouchInnerHandle<'_, D>,
         focus: Option<(<D as SeatHandler>::TouchFocus, Point<f64, Logical>)>,
         event: &crate::input::touch::MotionEvent,
@@ -449,6 +519,7 @@ where
         }
 
         self.update_focus(focus, event.location, SERIAL_COUNTER.next_serial(), event.time);
+        self.move_attached_toplevel(data, event.location);
     }
 
     fn frame(
@@ -496,6 +567,16 @@ where
     }
 }
 
+// Forwards modifier changes during a client initiated drag'n'drop to the handler
+fn forward_modifiers<D>(seat: &Seat<D>)
+where
+    D: DataDeviceHandler + 'static,
+{
+    set_dnd_modifiers_hook(seat, |data: &mut D, seat: &Seat<D>, modifiers| {
+        ClientDndGrabHandler::modifiers_changed(data, modifiers, seat.clone());
+    });
+}
+
 #[derive(Debug)]
 struct OfferData {
     active: bool,
@@ -509,6 +590,8 @@ struct OfferData {
 struct DndDataOffer {
     offer_data: Arc<Mutex<OfferData>>,
     source: WlDataSource,
+    transfers: Arc<DndTransfers>,
+    wl_seat: WlSeat,
 }
 
 impl<D> ObjectData<D> for DndDataOffer
@@ -548,7 +631,10 @@ where
 {
     use self::wl_data_offer::Request;
     let source = &data.source;
-    let mut data = data.offer_data.lock().unwrap();
+    let transfers = &data.transfers;
+    let wl_seat = &data.wl_seat;
+    let offer_data = &data.offer_data;
+    let mut data = offer_data.lock().unwrap();
     match request {
         Request::Accept { mime_type, .. } => {
             if let Some(mtype) = mime_type {
@@ -568,12 +654,15 @@ where
                 && source.alive()
                 && data.active;
             if valid {
-                source.send(mime_type, fd.as_fd());
+                match handler.data_device_state().dnd_transfers.as_ref() {
+                    Some(tracking) => tracking.receive(transfers, mime_type, fd),
+                    None => source.send(mime_type, fd.as_fd()),
+                }
             }
         }
         Request::Destroy => {
-            if source.version() >= 3 && data.dropped && !data.finished {
-                source.cancelled();
+            if source.supports(wl_data_source::EVT_DND_FINISHED_SINCE) && data.dropped && !data.finished {
+                transfers.cancel(handler.data_device_state().dnd_transfers.as_ref());
             }
         }
         Request::Finish => {
@@ -627,18 +716,64 @@ where
             let source_actions =
                 with_source_metadata(source, |meta| meta.dnd_action).unwrap_or_else(|_| DndAction::empty());
             let possible_actions = source_actions & dnd_actions;
-            let chosen_action = handler.action_choice(possible_actions, preferred_action);
-            // check that the user provided callback respects that one precise action should be chosen
-            debug_assert!(
-                [DndAction::None, DndAction::Move, DndAction::Copy, DndAction::Ask].contains(&chosen_action),
-                "Only one precise action should be chosen"
+            std::mem::drop(data);
+
+            let Some(seat) = Seat::<D>::from_resource(wl_seat) else {
+                return;
+            };
+            negotiate_action(
+                handler,
+                &seat,
+                offer,
+                source,
+                offer_data,
+                possible_actions,
+                preferred_action,
             );
-            if chosen_action != data.chosen_action {
-                data.chosen_action = chosen_action;
-                offer.action(chosen_action);
-                source.action(chosen_action);
-            }
+
+            // remember the negotiation to follow changes of the seat state
+            let (offer, source, offer_data) = (offer.clone(), source.clone(), offer_data.clone());
+            set_dnd_action_negotiation(&seat, move |handler: &mut D, seat: &Seat<D>| {
+                let data = offer_data.lock().unwrap();
+                if !data.active || data.dropped {
+                    return;
+                }
+                std::mem::drop(data);
+                negotiate_action(
+                    handler,
+                    seat,
+                    &offer,
+                    &source,
+                    &offer_data,
+                    possible_actions,
+                    preferred_action,
+                );
+            });
         }
         _ => unreachable!(),
     }
 }
+
+fn negotiate_action<D>(
+    handler: &mut D,
+    seat: &Seat<D>,
+    offer: &WlDataOffer,
+    source: &WlDataSource,
+    offer_data: &Mutex<OfferData>,
+    available: DndAction,
+    preferred: DndAction,
+) where
+    D: DataDeviceHandler,
+{
+    let mut data = offer_data.lock().unwrap();
+    let chosen_action = choose_dnd_action(handler, seat, available, preferred);
+    if chosen_action != data.chosen_action {
+        data.chosen_action = chosen_action;
+        offer.send_since(wl_data_offer::EVT_ACTION_SINCE, |offer| {
+            offer.action(chosen_action)
+        });
+        source.send_since(wl_data_source::EVT_ACTION_SINCE, |source| {
+            source.action(chosen_action)
+        });
+    }
+}
@@ -18,6 +18,7 @@
 //!   allows you to set the contents of the selection for your clients
 //! - the freestanding function [`start_dnd`] allows you to initiate a drag'n'drop event from the compositor
 //!   itself and receive interactions of clients with it via an other dedicated callback.
+//!   [`start_dnd_after_threshold`] only does so once the pointer or touch point moved far enough.
 //!
 //! The module defines the role `"dnd_icon"` that is assigned to surfaces used as drag'n'drop icons.
 //!
@@ -64,15 +65,19 @@
 //!     // ... override default implementations here to customize handling ...
 //! }
 //! delegate_data_device!(State);
+//! # impl smithay::wayland::protocol_error::ProtocolErrorHandler for State {}
 //!
 //! // You're now ready to go!
 //! ```
 
 use std::{
     cell::{Ref, RefCell},
+    io,
     os::unix::io::OwnedFd,
+    time::Duration,
 };
 
+use calloop::LoopHandle;
 use tracing::instrument;
 use wayland_server::{
     backend::GlobalId,
@@ -86,27 +91,32 @@ use wayland_server::{
 
 use crate::{
     input::{
-        pointer::{Focus, GrabStartData as PointerGrabStartData},
-        touch::GrabStartData as TouchGrabStartData,
+        keyboard::ModifiersState,
+        pointer::{DragThreshold, DragThresholdGrab, Focus, GrabStartData as PointerGrabStartData},
+        touch::{GrabStartData as TouchGrabStartData, TouchDragThresholdGrab},
         Seat, SeatHandler,
     },
-    utils::Serial,
-    wayland::seat::WaylandFocus,
+    utils::{Logical, Point, Serial},
+    wayland::{seat::WaylandFocus, shell::xdg::ToplevelSurface},
 };
 
+mod action;
 mod device;
 mod dnd_grab;
 mod server_dnd_grab;
 mod source;
+mod transfer;
 
+pub(crate) use action::dnd_modifiers_changed;
+pub use action::{modifier_action_chooser, update_dnd_action};
 pub use device::{DataDeviceUserData, DND_ICON_ROLE};
 pub use dnd_grab::DnDGrab;
 pub use server_dnd_grab::ServerDnDGrab;
 pub use source::{with_source_metadata, DataSourceUserData, SourceMetadata};
 
 use super::{
-    offer::OfferReplySource, seat_data::SeatData, source::CompositorSelectionProvider, SelectionHandler,
-    SelectionTarget,
+    contents, offer::OfferReplySource, seat_data::SeatData, source::CompositorSelectionProvider,
+    SelectionHandler, SelectionTarget, SelectionTransferError,
 };
 
 /// Events that are generated by interactions of the clients with the data device
@@ -119,6 +129,23 @@ pub trait DataDeviceHandler: Sized + SelectionHandler + ClientDndGrabHandler + S
     fn action_choice(&mut self, available: DndAction, preferred: DndAction) -> DndAction {
         default_action_chooser(available, preferred)
     }
+
+    /// Action chooser for DnD negociation on a given seat
+    ///
+    /// Used for both client and compositor initiated drags. Allows taking the state of the seat
+    /// into account, e.g. the keyboard modifiers using [`modifier_action_chooser`].
+    /// The action is negociated again whenever the keyboard modifiers change during a drag.
+    /// If other state changes, call [`update_dnd_action`] to negociate the action again.
+    ///
+    /// Defaults to [`DataDeviceHandler::action_choice`].
+    fn dnd_action_choice(
+        &mut self,
+        seat: &Seat<Self>,
+        available: DndAction,
+        preferred: DndAction,
+    ) -> DndAction {
+        self.action_choice(available, preferred)
+    }
 }
 
 /// Events that are generated during client initiated drag'n'drop
@@ -145,6 +172,31 @@ pub trait ClientDndGrabHandler: SeatHandler + Sized {
     ///   was cancelled or otherwise not successful.
     /// * `seat` - The seat on which the DnD action was finished.
     fn dropped(&mut self, target: Option<WlSurface>, validated: bool, seat: Seat<Self>) {}
+
+    /// A toplevel attached to the drag'n'drop via [`xdg_toplevel_drag`](crate::wayland::xdg_toplevel_drag)
+    /// has to follow the cursor
+    ///
+    /// * `toplevel` - The attached toplevel.
+    /// * `location` - The new location of the geometry of the toplevel, the cursor location minus
+    ///   the offset requested by the client.
+    /// * `seat` - The seat on which the DnD operation is happening.
+    fn toplevel_drag_motion(
+        &mut self,
+        toplevel: ToplevelSurface,
+        location: Point<i32, Logical>,
+        seat: Seat<Self>,
+    ) {
+    }
+
+    /// The keyboard modifiers changed during the drag'n'drop
+    ///
+    /// The action of the current offer is negotiated again automatically, so
+    /// [`DataDeviceHandler::dnd_action_choice`] can follow the modifiers.
+    /// Use this to update e.g. a copy or move indicator next to the drag icon.
+    ///
+    /// * `modifiers` - The new state of the keyboard modifiers.
+    /// * `seat` - The seat on which the DnD operation is happening.
+    fn modifiers_changed(&mut self, modifiers: ModifiersState, seat: Seat<Self>) {}
 }
 
 /// Event generated by the interactions of clients with a server initiated drag'n'drop
@@ -189,12 +241,22 @@ pub trait ServerDndGrabHandler: SeatHandler {
     /// This can only happen after the resource was dropped.
     /// * `seat` - The seat on which the DnD action was finished.
     fn finished(&mut self, seat: Seat<Self>) {}
+
+    /// The keyboard modifiers changed during the drag'n'drop
+    ///
+    /// The action of the current offer is negotiated again automatically, so
+    /// [`DataDeviceHandler::dnd_action_choice`] can follow the modifiers.
+    ///
+    /// * `modifiers` - The new state of the keyboard modifiers.
+    /// * `seat` - The seat on which the DnD operation is happening.
+    fn modifiers_changed(&mut self, modifiers: ModifiersState, seat: Seat<Self>) {}
 }
 
 /// State of data device
 #[derive(Debug)]
 pub struct DataDeviceState {
     manager_global: GlobalId,
+    dnd_transfers: Option<transfer::DndTransferTracking>,
 }
 
 impl DataDeviceState {
@@ -206,13 +268,33 @@ impl DataDeviceState {
     {
         let manager_global = display.create_global::<D, WlDataDeviceManager, _>(3, ());
 
-        Self { manager_global }
+        Self {
+            manager_global,
+            dnd_transfers: None,
+        }
     }
 
     /// [WlDataDeviceManager] GlobalId getter
     pub fn global(&self) -> GlobalId {
         self.manager_global.clone()
     }
+
+    /// Keep drag'n'drop sources alive while their data is still being transferred
+    ///
+    /// If a target destroys its offer after a drop without finishing it, the source is cancelled.
+    /// Slow targets might still be reading at that point, resulting in truncated drops.
+    ///
+    /// With tracking enabled, the data of client drag'n'drop sources is passed through the compositor,
+    /// so the cancellation can be delayed until the source has written all of its data,
+    /// or `timeout` elapsed. The data is copied using non-blocking io on the event loop, transfers still
+    /// running after `timeout` are aborted by closing both ends.
+    pub fn enable_dnd_transfer_tracking<D: 'static>(
+        &mut self,
+        loop_handle: &LoopHandle<'static, D>,
+        timeout: Duration,
+    ) {
+        self.dnd_transfers = Some(transfer::DndTransferTracking::new(loop_handle, timeout));
+    }
 }
 
 /// A simple action chooser for DnD negociation
@@ -338,6 +420,33 @@ where
     }
 }
 
+/// Read the current data_device selection of the given seat in the given mime type
+///
+/// The selection is transferred from the client without blocking using the event loop
+/// and `callback` is called with the data once the client finished writing it.
+/// Transfers exceeding `max_size` bytes fail with [`io::ErrorKind::InvalidData`],
+/// transfers not finished within `timeout` with [`io::ErrorKind::TimedOut`].
+pub fn read_data_device_selection<'l, D, F>(
+    handle: &LoopHandle<'l, D>,
+    seat: &Seat<D>,
+    mime_type: String,
+    max_size: usize,
+    timeout: Duration,
+    callback: F,
+) -> Result<(), SelectionTransferError>
+where
+    D: SeatHandler + DataDeviceHandler + 'static,
+    F: FnOnce(&mut D, io::Result<Vec<u8>>) + 'l,
+{
+    contents::read_selection(
+        handle,
+        |fd| request_data_device_client_selection(seat, mime_type, fd),
+        max_size,
+        timeout,
+        callback,
+    )
+}
+
 /// Gets the user_data for the currently active selection, if set by the compositor
 #[instrument(name = "wayland_data_device", level = "debug", skip_all, fields(seat = seat.name()))]
 pub fn current_data_device_selection_userdata<D>(seat: &Seat<D>) -> Option<Ref<'_, D::SelectionUserData>>
@@ -411,6 +520,52 @@ pub fn start_dnd<D>(
     }
 }
 
+/// Start a drag'n'drop from a resource controlled by the compositor, once a drag threshold is exceeded
+///
+/// Behaves like [`start_dnd`], but the pointer or touch point first has to move further than the
+/// [`DragThreshold`] (or for touch input, be held down long enough). Until then the input is
+/// delivered to the surface it started on, releasing it before does not start a drag'n'drop.
+///
+/// `time` is the time of the button press or touch down starting the drag'n'drop, in milliseconds.
+#[allow(clippy::too_many_arguments)]
+#[instrument(name = "wayland_data_device", level = "debug", skip(dh, seat, data), fields(seat = seat.name()))]
+pub fn start_dnd_after_threshold<D>(
+    dh: &DisplayHandle,
+    seat: &Seat<D>,
+    data: &mut D,
+    serial: Serial,
+    time: u32,
+    pointer_start_data: Option<PointerGrabStartData<D>>,
+    touch_start_data: Option<TouchGrabStartData<D>>,
+    metadata: SourceMetadata,
+    threshold: DragThreshold,
+) where
+    D: SeatHandler + DataDeviceHandler + 'static,
+    <D as SeatHandler>::PointerFocus: WaylandFocus,
+    <D as SeatHandler>::TouchFocus: WaylandFocus,
+{
+    seat.user_data()
+        .insert_if_missing(|| RefCell::new(SeatData::<D::SelectionUserData>::new()));
+    let dh = dh.clone();
+    let dnd_seat = seat.clone();
+    if let (Some(pointer_start_data), Some(pointer)) = (pointer_start_data, seat.get_pointer()) {
+        let grab = DragThresholdGrab::new(
+            pointer_start_data,
+            threshold,
+            Focus::Keep,
+            move |_, start_data| {
+                server_dnd_grab::ServerDnDGrab::new_pointer(&dh, start_data, metadata, dnd_seat)
+            },
+        );
+        pointer.set_grab(data, grab, serial, Focus::Keep);
+    } else if let (Some(touch_start_data), Some(touch)) = (touch_start_data, seat.get_touch()) {
+        let grab = TouchDragThresholdGrab::new(touch_start_data, time, threshold, move |_, start_data| {
+            server_dnd_grab::ServerDnDGrab::new_touch(&dh, start_data, metadata, dnd_seat)
+        });
+        touch.set_grab(data, grab, serial);
+    }
+}
+
 mod handlers {
     use std::cell::RefCell;
 
@@ -29,10 +29,16 @@ use crate::{
         touch::{GrabStartData as TouchGrabStartData, TouchGrab},
         Seat, SeatHandler,
     },
-    wayland::selection::seat_data::SeatData,
+    wayland::{selection::seat_data::SeatData, version::VersionedResource},
 };
 
-use super::{DataDeviceHandler, DataDeviceUserData, ServerDndGrabHandler, SourceMetadata};
+use super::{
+    action::{
+        choose_dnd_action, clear_dnd_action_negotiation, clear_dnd_modifiers_hook,
+        set_dnd_action_negotiation, set_dnd_modifiers_hook,
+    },
+    DataDeviceHandler, DataDeviceUserData, ServerDndGrabHandler, SourceMetadata,
+};
 
 /// Grab during a compositor-initiated DnD operation.
 pub struct ServerDnDGrab<D: SeatHandler> {
@@ -61,13 +67,17 @@ impl<D: SeatHandler + 'static> fmt::Debug for ServerDnDGrab<D> {
     }
 }
 
-impl<D: SeatHandler> ServerDnDGrab<D> {
+impl<D> ServerDnDGrab<D>
+where
+    D: DataDeviceHandler + 'static,
+{
     pub(crate) fn new_pointer(
         dh: &DisplayHandle,
         start_data: PointerGrabStartData<D>,
         metadata: super::SourceMetadata,
         seat: Seat<D>,
     ) -> Self {
+        forward_modifiers(&seat);
         Self {
             dh: dh.clone(),
             pointer_start_data: Some(start_data),
@@ -86,6 +96,7 @@ impl<D: SeatHandler> ServerDnDGrab<D> {
         metadata: super::SourceMetadata,
         seat: Seat<D>,
     ) -> Self {
+        forward_modifiers(&seat);
         Self {
             dh: dh.clone(),
             pointer_start_data: None,
@@ -130,6 +141,7 @@ where
                 self.pending_offers.clear();
                 if let Some(offer_data) = self.offer_data.take() {
                     offer_data.lock().unwrap().active = false;
+                    clear_dnd_action_negotiation(&self.seat);
                 }
             }
         }
@@ -180,7 +192,9 @@ where
                     for mime_type in self.metadata.mime_types.iter().cloned() {
                         offer.offer(mime_type);
                     }
-                    offer.source_actions(self.metadata.dnd_action);
+                    if offer.supports(wl_data_offer::EVT_SOURCE_ACTIONS_SINCE) {
+                        offer.source_actions(self.metadata.dnd_action);
+                    }
                     device.enter(serial.into(), &surface, x, y, Some(&offer));
                     self.pending_offers.push(offer);
                 }
@@ -226,6 +240,8 @@ where
                 data.active = false;
             }
         }
+        clear_dnd_action_negotiation(&self.seat);
+        clear_dnd_modifiers_hook(&self.seat);
 
         ServerDndGrabHandler::dropped(data, self.seat.clone());
         if !validated {
@@ -473,6 +489,16 @@ where
     }
 }
 
+// Forwards modifier changes during a compositor initiated drag'n'drop to the handler
+fn forward_modifiers<D>(seat: &Seat<D>)
+where
+    D: DataDeviceHandler + 'static,
+{
+    set_dnd_modifiers_hook(seat, |data: &mut D, seat: &Seat<D>, modifiers| {
+        ServerDndGrabHandler::modifiers_changed(data, modifiers, seat.clone());
+    });
+}
+
 #[derive(Debug)]
 struct ServerDndOfferData {
     active: bool,
@@ -603,18 +629,55 @@ fn handle_server_dnd<D>(
                 return;
             }
             let possible_actions = metadata.dnd_action & dnd_actions;
-            let chosen_action = handler.action_choice(possible_actions, preferred_action);
-            // check that the user provided callback respects that one precise action should be chosen
-            debug_assert!(
-                [DndAction::None, DndAction::Move, DndAction::Copy, DndAction::Ask].contains(&chosen_action),
-                "Only one precise action should be chosen"
+            std::mem::drop(data);
+            negotiate_action(
+                handler,
+                &seat,
+                offer,
+                offer_data,
+                possible_actions,
+                preferred_action,
             );
-            if chosen_action != data.chosen_action {
-                data.chosen_action = chosen_action;
-                offer.action(chosen_action);
-                handler.action(chosen_action, seat);
-            }
+
+            // remember the negotiation to follow changes of the seat state
+            let (offer, offer_data) = (offer.clone(), offer_data.clone());
+            set_dnd_action_negotiation(&seat, move |handler: &mut D, seat: &Seat<D>| {
+                let data = offer_data.lock().unwrap();
+                if !data.active || data.dropped {
+                    return;
+                }
+                std::mem::drop(data);
+                negotiate_action(
+                    handler,
+                    seat,
+                    &offer,
+                    &offer_data,
+                    possible_actions,
+                    preferred_action,
+                );
+            });
         }
         _ => unreachable!(),
     }
 }
+
+fn negotiate_action<D>(
+    handler: &mut D,
+    seat: &Seat<D>,
+    offer: &WlDataOffer,
+    offer_data: &Mutex<ServerDndOfferData>,
+    available: DndAction,
+    preferred: DndAction,
+) where
+    D: DataDeviceHandler + SeatHandler + 'static,
+{
+    let mut data = offer_data.lock().unwrap();
+    let chosen_action = choose_dnd_action(handler, seat, available, preferred);
+    if chosen_action != data.chosen_action {
+        data.chosen_action = chosen_action;
+        offer.send_since(wl_data_offer::EVT_ACTION_SINCE, |offer| {
+            offer.action(chosen_action)
+        });
+        handler.action(chosen_action, seat.clone());
+    }
+}
@@ -1,6 +1,7 @@
 use std::sync::Mutex;
 use tracing::error;
 
+use wayland_protocols::xdg::toplevel_drag::v1::server::xdg_toplevel_drag_v1::XdgToplevelDragV1;
 use wayland_server::{
     backend::ClientId,
     protocol::wl_data_source::{self},
@@ -34,6 +35,7 @@ impl Default for SourceMetadata {
 #[derive(Debug)]
 pub struct DataSourceUserData {
     pub(crate) inner: Mutex<SourceMetadata>,
+    pub(crate) toplevel_drag: Mutex<Option<XdgToplevelDragV1>>,
     alive_tracker: AliveTracker,
 }
 
@@ -41,6 +43,7 @@ impl DataSourceUserData {
     pub(super) fn new() -> Self {
         Self {
             inner: Default::default(),
+            toplevel_drag: Default::default(),
             alive_tracker: Default::default(),
         }
     }
@@ -0,0 +1,352 @@
+use std::{
+    cell::RefCell,
+    fmt, io,
+    os::unix::io::{AsFd, OwnedFd},
+    rc::Rc,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use calloop::{
+    generic::Generic,
+    timer::{TimeoutAction, Timer},
+    Interest, LoopHandle, Mode, PostAction, RegistrationToken,
+};
+use rustix::{
+    fs::{fcntl_getfl, fcntl_setfl, OFlags},
+    io::Errno,
+    pipe::{pipe_with, PipeFlags},
+};
+use tracing::{debug, warn};
+use wayland_server::protocol::wl_data_source::WlDataSource;
+
+type StartCopy = Box<dyn Fn(OwnedFd, OwnedFd, Arc<DndTransfers>) -> io::Result<()>>;
+type ScheduleTimeout = Box<dyn Fn(Duration, Arc<DndTransfers>)>;
+
+// Passes drag'n'drop transfers through the compositor to keep track of them
+pub(super) struct DndTransferTracking {
+    start_copy: StartCopy,
+    schedule_timeout: ScheduleTimeout,
+    timeout: Duration,
+}
+
+impl fmt::Debug for DndTransferTracking {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DndTransferTracking")
+            .field("timeout", &self.timeout)
+            .finish_non_exhaustive()
+    }
+}
+
+impl DndTransferTracking {
+    pub(super) fn new<D: 'static>(loop_handle: &LoopHandle<'static, D>, timeout: Duration) -> Self {
+        let handle = loop_handle.clone();
+        let start_copy = Box::new(move |read, write, transfers: Arc<DndTransfers>| {
+            let tokens = insert_pipe_copy(&handle, read, write, {
+                let transfers = transfers.clone();
+                move |tokens| transfers.finish_one(Some(tokens))
+            })?;
+            transfers.state.lock().unwrap().copies.push(tokens);
+            Ok(())
+        });
+
+        let loop_handle = loop_handle.clone();
+        let schedule_timeout = Box::new(move |timeout, transfers: Arc<DndTransfers>| {
+            let handle = loop_handle.clone();
+            if let Err(err) = loop_handle.insert_source(Timer::from_duration(timeout), move |_, _, _| {
+                // removing the sources closes both ends of the remaining copies
+                for (read, write) in transfers.expire() {
+                    handle.remove(read);
+                    handle.remove(write);
+                }
+                TimeoutAction::Drop
+            }) {
+                warn!(error = ?err.error, "Failed to schedule drag'n'drop cancellation");
+            }
+        });
+
+        DndTransferTracking {
+            start_copy,
+            schedule_timeout,
+            timeout,
+        }
+    }
+
+    // Request the data from the source, copying it into `fd` on the event loop
+    pub(super) fn receive(&self, transfers: &Arc<DndTransfers>, mime_type: String, fd: OwnedFd) {
+        let (read, write) = match pipe_with(PipeFlags::CLOEXEC) {
+            Ok(pipe) => pipe,
+            Err(err) => {
+                warn!(?err, "Failed to create pipe for drag'n'drop transfer");
+                transfers.source.send(mime_type, fd.as_fd());
+                return;
+            }
+        };
+        transfers.source.send(mime_type, write.as_fd());
+        std::mem::drop(write);
+
+        transfers.state.lock().unwrap().in_flight += 1;
+        if let Err(err) = (self.start_copy)(read, fd, transfers.clone()) {
+            warn!(?err, "Failed to start drag'n'drop transfer");
+            transfers.finish_one(None);
+        }
+    }
+}
+
+const COPY_CHUNK_SIZE: usize = 16 * 1024;
+
+#[derive(Debug, Default)]
+struct PipeCopy {
+    pending: Vec<u8>,
+    offset: usize,
+    tokens: Option<(RegistrationToken, RegistrationToken)>,
+}
+
+// Copies everything from `read` into `write` using non-blocking io driven by the event loop
+//
+// `finished` is called with the tokens of the read and write source once `read` is closed
+// or an error occurred, both sources are removed at that point. Removing the sources
+// before closes both file descriptors.
+fn insert_pipe_copy<D: 'static>(
+    handle: &LoopHandle<'static, D>,
+    read: OwnedFd,
+    write: OwnedFd,
+    finished: impl FnOnce((RegistrationToken, RegistrationToken)) + 'static,
+) -> io::Result<(RegistrationToken, RegistrationToken)> {
+    for fd in [&read, &write] {
+        let flags = fcntl_getfl(fd)?;
+        fcntl_setfl(fd, flags | OFlags::NONBLOCK)?;
+    }
+
+    let copy = Rc::new(RefCell::new(PipeCopy::default()));
+    let finished = Rc::new(RefCell::new(Some(finished)));
+    // removes the other source and notifies about the completed copy
+    let finish = {
+        let copy = copy.clone();
+        let handle = handle.clone();
+        move |reading: bool| {
+            let Some(tokens) = copy.borrow_mut().tokens.take() else {
+                return;
+            };
+            handle.remove(if reading { tokens.1 } else { tokens.0 });
+            if let Some(finished) = finished.borrow_mut().take() {
+                finished(tokens);
+            }
+        }
+    };
+
+    let write_token = handle
+        .insert_source(Generic::new(write, Interest::WRITE, Mode::Level), {
+            let copy = copy.clone();
+            let handle = handle.clone();
+            let finish = finish.clone();
+            move |_, fd, _| {
+                let mut copy = copy.borrow_mut();
+                let PipeCopy {
+                    pending,
+                    offset,
+                    tokens,
+                } = &mut *copy;
+                match rustix::io::write(&*fd, &pending[*offset..]) {
+                    Ok(written) => {
+                        *offset += written;
+                        if *offset < pending.len() {
+                            return Ok(PostAction::Continue);
+                        }
+                        pending.clear();
+                        *offset = 0;
+                        if let Some((read, _)) = tokens {
+                            handle.enable(read).map_err(io::Error::other)?;
+                        }
+                        Ok(PostAction::Disable)
+                    }
+                    Err(Errno::AGAIN) | Err(Errno::INTR) => Ok(PostAction::Continue),
+                    Err(err) => {
+                        debug!(?err, "Drag'n'drop transfer failed");
+                        std::mem::drop(copy);
+                        finish(false);
+                        Ok(PostAction::Remove)
+                    }
+                }
+            }
+        })
+        .map_err(|err| io::Error::other(err.error))?;
+    if let Err(err) = handle.disable(&write_token) {
+        handle.remove(write_token);
+        return Err(io::Error::other(err));
+    }
+
+    let read_token = handle
+        .insert_source(Generic::new(read, Interest::READ, Mode::Level), {
+            let copy = copy.clone();
+            let handle = handle.clone();
+            move |_, fd, _| {
+                let mut copy = copy.borrow_mut();
+                copy.pending.resize(COPY_CHUNK_SIZE, 0);
+                match rustix::io::read(&*fd, &mut copy.pending) {
+                    Ok(0) => {
+                        std::mem::drop(copy);
+                        finish(true);
+                        Ok(PostAction::Remove)
+                    }
+                    Ok(len) => {
+                        copy.pending.truncate(len);
+                        // the data is written, before reading more
+                        if let Some((_, write)) = copy.tokens {
+                            handle.enable(&write).map_err(io::Error::other)?;
+                        }
+                        Ok(PostAction::Disable)
+                    }
+                    Err(Errno::AGAIN) | Err(Errno::INTR) => {
+                        copy.pending.clear();
+                        Ok(PostAction::Continue)
+                    }
+                    Err(err) => {
+                        debug!(?err, "Drag'n'drop transfer failed");
+                        std::mem::drop(copy);
+                        finish(true);
+                        Ok(PostAction::Remove)
+                    }
+                }
+            }
+        })
+        .map_err(|err| {
+            handle.remove(write_token);
+            io::Error::other(err.error)
+        })?;
+
+    let tokens = (read_token, write_token);
+    copy.borrow_mut().tokens = Some(tokens);
+    Ok(tokens)
+}
+
+#[derive(Debug, Default)]
+struct TransferState {
+    in_flight: usize,
+    cancel_pending: bool,
+    // read and write sources of the running copies
+    copies: Vec<(RegistrationToken, RegistrationToken)>,
+}
+
+// Transfers of the offers of a drag'n'drop source to one target
+#[derive(Debug)]
+pub(super) struct DndTransfers {
+    source: WlDataSource,
+    state: Mutex<TransferState>,
+}
+
+impl DndTransfers {
+    pub(super) fn new(source: WlDataSource) -> Arc<Self> {
+        Arc::new(DndTransfers {
+            source,
+            state: Mutex::new(TransferState::default()),
+        })
+    }
+
+    // Cancel the source, once all transfers completed if they are tracked
+    pub(super) fn cancel(self: &Arc<Self>, tracking: Option<&DndTransferTracking>) {
+        let mut state = self.state.lock().unwrap();
+        match tracking {
+            Some(tracking) if state.in_flight > 0 => {
+                state.cancel_pending = true;
+                (tracking.schedule_timeout)(tracking.timeout, self.clone());
+            }
+            _ => self.source.cancelled(),
+        }
+    }
+
+    fn finish_one(&self, copy: Option<(RegistrationToken, RegistrationToken)>) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(copy) = copy {
+            state.copies.retain(|tokens| *tokens != copy);
+        }
+        state.in_flight = state.in_flight.saturating_sub(1);
+        if state.in_flight == 0 && state.cancel_pending {
+            state.cancel_pending = false;
+            self.source.cancelled();
+        }
+    }
+
+    // Cancel the source if still pending, returns the sources of the copies to abort
+    fn expire(&self) -> Vec<(RegistrationToken, RegistrationToken)> {
+        let mut state = self.state.lock().unwrap();
+        if !state.cancel_pending {
+            return Vec::new();
+        }
+        debug!("Drag'n'drop transfer timed out, cancelling source");
+        state.cancel_pending = false;
+        state.in_flight = 0;
+        self.source.cancelled();
+        std::mem::take(&mut state.copies)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, io::Read, time::Duration};
+
+    use super::*;
+
+    fn pipe() -> (OwnedFd, OwnedFd) {
+        pipe_with(PipeFlags::CLOEXEC).unwrap()
+    }
+
+    #[test]
+    fn copies_until_closed() {
+        let mut event_loop = calloop::EventLoop::<()>::try_new().unwrap();
+        let (source_read, source_write) = pipe();
+        let (target_read, target_write) = pipe();
+
+        let finished = Rc::new(Cell::new(false));
+        insert_pipe_copy(&event_loop.handle(), source_read, target_write, {
+            let finished = finished.clone();
+            move |_| finished.set(true)
+        })
+        .unwrap();
+
+        let data = vec![42u8; 3 * COPY_CHUNK_SIZE / 2];
+        rustix::io::write(&source_write, &data).unwrap();
+        std::mem::drop(source_write);
+
+        for _ in 0..10 {
+            event_loop.dispatch(Duration::ZERO, &mut ()).unwrap();
+        }
+        assert!(finished.get());
+
+        // the target is closed once the copy finished
+        let mut received = Vec::new();
+        std::fs::File::from(target_read)
+            .read_to_end(&mut received)
+            .unwrap();
+        assert_eq!(received, data);
+    }
+
+    #[test]
+    fn removing_closes_both_ends() {
+        let mut event_loop = calloop::EventLoop::<()>::try_new().unwrap();
+        let handle = event_loop.handle();
+        let (source_read, source_write) = pipe();
+        let (target_read, target_write) = pipe();
+
+        let (read, write) = insert_pipe_copy(&handle, source_read, target_write, |_| {
+            panic!("copy should not finish")
+        })
+        .unwrap();
+        rustix::io::write(&source_write, b"partial").unwrap();
+        // read and write
+        event_loop.dispatch(Duration::ZERO, &mut ()).unwrap();
+        event_loop.dispatch(Duration::ZERO, &mut ()).unwrap();
+
+        handle.remove(read);
+        handle.remove(write);
+        event_loop.dispatch(Duration::ZERO, &mut ()).unwrap();
+
+        // a client still writing gets an error instead of blocking forever
+        assert_eq!(rustix::io::write(&source_write, b"more"), Err(Errno::PIPE));
+        let mut received = Vec::new();
+        std::fs::File::from(target_read)
+            .read_to_end(&mut received)
+            .unwrap();
+        assert_eq!(received, b"partial");
+    }
+}
@@ -20,11 +20,13 @@ pub mod ext_data_control;
 pub mod primary_selection;
 pub mod wlr_data_control;
 
+mod contents;
 mod device;
 mod offer;
 mod seat_data;
 mod source;
 
+pub use contents::{SelectionContents, SelectionTransferError};
 pub use source::SelectionSource;
 
 /// Events that are generated by interactions of the clients with the data device.
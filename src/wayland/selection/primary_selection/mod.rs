@@ -65,10 +65,13 @@
 
 use std::{
     cell::{Ref, RefCell},
+    io,
     os::unix::io::OwnedFd,
     sync::Arc,
+    time::Duration,
 };
 
+use calloop::LoopHandle;
 use tracing::instrument;
 use wayland_protocols::wp::primary_selection::zv1::server::zwp_primary_selection_device_manager_v1::ZwpPrimarySelectionDeviceManagerV1 as PrimaryDeviceManager;
 use wayland_server::{backend::GlobalId, Client, DisplayHandle, GlobalDispatch};
@@ -85,7 +88,7 @@ pub use device::PrimaryDeviceUserData;
 pub use source::{PrimarySourceUserData, SourceMetadata};
 
 use super::source::CompositorSelectionProvider;
-use super::SelectionHandler;
+use super::{contents, SelectionHandler, SelectionTransferError};
 use super::{offer::OfferReplySource, seat_data::SeatData};
 
 /// Access the primary selection state.
@@ -251,6 +254,33 @@ where
     }
 }
 
+/// Read the current primary selection of the given seat in the given mime type
+///
+/// The selection is transferred from the client without blocking using the event loop
+/// and `callback` is called with the data once the client finished writing it.
+/// Transfers exceeding `max_size` bytes fail with [`io::ErrorKind::InvalidData`],
+/// transfers not finished within `timeout` with [`io::ErrorKind::TimedOut`].
+pub fn read_primary_selection<'l, D, F>(
+    handle: &LoopHandle<'l, D>,
+    seat: &Seat<D>,
+    mime_type: String,
+    max_size: usize,
+    timeout: Duration,
+    callback: F,
+) -> Result<(), SelectionTransferError>
+where
+    D: SeatHandler + PrimarySelectionHandler + 'static,
+    F: FnOnce(&mut D, io::Result<Vec<u8>>) + 'l,
+{
+    contents::read_selection(
+        handle,
+        |fd| request_primary_client_selection(seat, mime_type, fd),
+        max_size,
+        timeout,
+        callback,
+    )
+}
+
 /// Gets the user_data for the currently active selection, if set by the compositor
 #[instrument(name = "wayland_primary_selection", level = "debug", skip_all, fields(seat = seat.name()))]
 pub fn current_primary_selection_userdata<D>(seat: &Seat<D>) -> Option<Ref<'_, D::SelectionUserData>>
@@ -7,6 +7,7 @@ use crate::backend::renderer::buffer_dimensions;
 use crate::utils::Size;
 use crate::wayland::compositor::SurfaceAttributes;
 use crate::wayland::compositor::{self, BufferAssignment};
+use crate::wayland::protocol_error::{post_error, ProtocolErrorHandler, ProtocolErrorKind};
 use crate::wayland::viewporter::{ViewportCachedState, ViewporterSurfaceState};
 use _session_lock::ext_session_lock_surface_v1::ExtSessionLockSurfaceV1;
 use _session_lock::ext_session_lock_v1::{Error, ExtSessionLockV1, Request};
@@ -37,7 +38,7 @@ impl<D> Dispatch<ExtSessionLockV1, SessionLockState, D> for SessionLockManagerSt
 where
     D: Dispatch<ExtSessionLockV1, SessionLockState>,
     D: Dispatch<ExtSessionLockSurfaceV1, ExtLockSurfaceUserData>,
-    D: SessionLockHandler,
+    D: SessionLockHandler + ProtocolErrorHandler,
     D: 'static,
 {
     fn request(
@@ -53,7 +54,13 @@ where
             Request::GetLockSurface { id, surface, output } => {
                 // Assign surface a role and ensure it never had one before.
                 if compositor::give_role(&surface, LOCK_SURFACE_ROLE).is_err() {
-                    lock.post_error(Error::Role, "Surface already has a role.");
+                    post_error(
+                        state,
+                        lock,
+                        ProtocolErrorKind::Role,
+                        Error::Role,
+                        "Surface already has a role.",
+                    );
                     return;
                 }
 
@@ -44,6 +44,7 @@
 //!     }
 //! }
 //! delegate_session_lock!(State);
+//! # impl smithay::wayland::protocol_error::ProtocolErrorHandler for State {}
 //!
 //! // You're now ready to go!
 //! ```
@@ -4,10 +4,11 @@ use std::sync::Mutex;
 
 use crate::utils::{IsAlive, Logical, Serial, Size, SERIAL_COUNTER};
 use crate::wayland::compositor;
+use crate::wayland::protocol_error::{post_error, ProtocolErrorHandler, ProtocolErrorKind};
 use _session_lock::ext_session_lock_surface_v1::{Error, ExtSessionLockSurfaceV1, Request};
 use wayland_protocols::ext::session_lock::v1::server::{self as _session_lock, ext_session_lock_surface_v1};
 use wayland_server::protocol::wl_surface::WlSurface;
-use wayland_server::{Client, DataInit, Dispatch, DisplayHandle, Resource, Weak};
+use wayland_server::{Client, DataInit, Dispatch, DisplayHandle, Weak};
 
 use crate::wayland::session_lock::{SessionLockHandler, SessionLockManagerState};
 
@@ -23,7 +24,7 @@ pub struct ExtLockSurfaceUserData {
 impl<D> Dispatch<ExtSessionLockSurfaceV1, ExtLockSurfaceUserData, D> for SessionLockManagerState
 where
     D: Dispatch<ExtSessionLockSurfaceV1, ExtLockSurfaceUserData>,
-    D: SessionLockHandler,
+    D: SessionLockHandler + ProtocolErrorHandler,
     D: 'static,
 {
     fn request(
@@ -50,7 +51,10 @@ where
 
                 match configure {
                     Some(configure) => state.ack_configure(surface.clone(), configure),
-                    None => lock_surface.post_error(
+                    None => post_error(
+                        state,
+                        lock_surface,
+                        ProtocolErrorKind::Serial,
                         Error::InvalidSerial,
                         format!("wrong configure serial: {}", <u32>::from(serial)),
                     ),
@@ -0,0 +1,153 @@
+//! Protocol independent negotiation of window decorations
+//!
+//! Clients can ask for server-side decorations through two protocols: the standard
+//! [`xdg-decoration`](super::xdg::decoration) protocol and KDE's legacy
+//! [`server-decoration`](super::kde::decoration) protocol, which is still the only one queried
+//! by several Qt applications.
+//!
+//! A [`DecorationPolicy`] decides on the [`DecorationMode`] of a window, regardless of the protocol
+//! used to request it, so both protocols can be answered consistently.
+//!
+//! ```
+//! use smithay::reexports::wayland_protocols::xdg::decoration::zv1::server::zxdg_toplevel_decoration_v1::Mode as XdgMode;
+//! use smithay::reexports::wayland_protocols_misc::server_decoration::server::org_kde_kwin_server_decoration::{
+//!     Mode as KdeMode, OrgKdeKwinServerDecoration,
+//! };
+//! use smithay::reexports::wayland_server::{protocol::wl_surface::WlSurface, WEnum};
+//! use smithay::wayland::shell::decoration::{DecorationMode, DecorationPolicy};
+//! use smithay::wayland::shell::kde::decoration::{KdeDecorationHandler, KdeDecorationState};
+//! use smithay::wayland::shell::xdg::ToplevelSurface;
+//!
+//! const POLICY: DecorationPolicy = DecorationPolicy::PreferServerSide;
+//!
+//! # struct State { kde_decoration_state: KdeDecorationState }
+//! # let mut display = wayland_server::Display::<State>::new().unwrap();
+//! // Announce the same default mode on the KDE global
+//! let kde_decoration_state =
+//!     KdeDecorationState::new::<State>(&display.handle(), POLICY.default_mode().into());
+//!
+//! impl KdeDecorationHandler for State {
+//!     fn kde_decoration_state(&self) -> &KdeDecorationState {
+//!         &self.kde_decoration_state
+//!     }
+//!
+//!     fn request_mode(&mut self, _surface: &WlSurface, decoration: &OrgKdeKwinServerDecoration, mode: WEnum<KdeMode>) {
+//!         let requested = mode.into_result().ok().and_then(DecorationMode::from_kde);
+//!         decoration.mode(POLICY.negotiate(requested).into());
+//!     }
+//! }
+//! smithay::delegate_kde_decoration!(State);
+//!
+//! // Called from `XdgDecorationHandler::request_mode`
+//! fn xdg_request_mode(toplevel: &ToplevelSurface, mode: XdgMode) {
+//!     let mode = POLICY.negotiate(DecorationMode::from_xdg(mode));
+//!     toplevel.with_pending_state(|state| state.decoration_mode = Some(mode.into()));
+//!     if toplevel.is_initial_configure_sent() {
+//!         toplevel.send_pending_configure();
+//!     }
+//! }
+//! ```
+
+use wayland_protocols::xdg::decoration::zv1::server::zxdg_toplevel_decoration_v1::Mode as XdgMode;
+use wayland_protocols_misc::server_decoration::server::{
+    org_kde_kwin_server_decoration::Mode as KdeMode,
+    org_kde_kwin_server_decoration_manager::Mode as KdeDefaultMode,
+};
+
+/// Who is responsible for drawing the decorations of a window
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DecorationMode {
+    /// The client draws its own decorations, if any
+    ClientSide,
+    /// The compositor draws the decorations
+    ServerSide,
+}
+
+impl DecorationMode {
+    /// Converts a mode of the xdg-decoration protocol, `None` for unknown modes
+    pub fn from_xdg(mode: XdgMode) -> Option<Self> {
+        match mode {
+            XdgMode::ClientSide => Some(DecorationMode::ClientSide),
+            XdgMode::ServerSide => Some(DecorationMode::ServerSide),
+            _ => None,
+        }
+    }
+
+    /// Converts a mode of the KDE server decoration protocol, `None` for unknown modes
+    ///
+    /// Clients asking for no decorations at all are treated as client-side,
+    /// as the compositor must not decorate them.
+    pub fn from_kde(mode: KdeMode) -> Option<Self> {
+        match mode {
+            KdeMode::None | KdeMode::Client => Some(DecorationMode::ClientSide),
+            KdeMode::Server => Some(DecorationMode::ServerSide),
+            _ => None,
+        }
+    }
+}
+
+impl From<DecorationMode> for XdgMode {
+    #[inline]
+    fn from(mode: DecorationMode) -> Self {
+        match mode {
+            DecorationMode::ClientSide => XdgMode::ClientSide,
+            DecorationMode::ServerSide => XdgMode::ServerSide,
+        }
+    }
+}
+
+impl From<DecorationMode> for KdeMode {
+    #[inline]
+    fn from(mode: DecorationMode) -> Self {
+        match mode {
+            DecorationMode::ClientSide => KdeMode::Client,
+            DecorationMode::ServerSide => KdeMode::Server,
+        }
+    }
+}
+
+impl From<DecorationMode> for KdeDefaultMode {
+    #[inline]
+    fn from(mode: DecorationMode) -> Self {
+        match mode {
+            DecorationMode::ClientSide => KdeDefaultMode::Client,
+            DecorationMode::ServerSide => KdeDefaultMode::Server,
+        }
+    }
+}
+
+/// Policy of the compositor for answering decoration mode requests
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DecorationPolicy {
+    /// Clients always draw their own decorations
+    ClientSide,
+    /// The compositor always draws the decorations
+    ServerSide,
+    /// Follow the requests of clients, defaulting to client-side decorations
+    PreferClientSide,
+    /// Follow the requests of clients, defaulting to server-side decorations
+    PreferServerSide,
+}
+
+impl DecorationPolicy {
+    /// Mode of windows, which did not request any mode
+    pub fn default_mode(&self) -> DecorationMode {
+        match self {
+            DecorationPolicy::ClientSide | DecorationPolicy::PreferClientSide => DecorationMode::ClientSide,
+            DecorationPolicy::ServerSide | DecorationPolicy::PreferServerSide => DecorationMode::ServerSide,
+        }
+    }
+
+    /// Decides on the mode of a window, after the client requested `requested`
+    ///
+    /// `None` is used, if the client unset its preference or requested an unknown mode.
+    pub fn negotiate(&self, requested: Option<DecorationMode>) -> DecorationMode {
+        match self {
+            DecorationPolicy::ClientSide => DecorationMode::ClientSide,
+            DecorationPolicy::ServerSide => DecorationMode::ServerSide,
+            DecorationPolicy::PreferClientSide | DecorationPolicy::PreferServerSide => {
+                requested.unwrap_or_else(|| self.default_mode())
+            }
+        }
+    }
+}
@@ -3,6 +3,8 @@
 //! This interface allows a compositor to announce support for KDE's legacy server-side decorations.
 //!
 //! A client can use this protocol to request being decorated by a supporting compositor.
+//! Use a [`DecorationPolicy`](crate::wayland::shell::decoration::DecorationPolicy) to answer it
+//! consistently with the xdg-decoration protocol.
 //!
 //! ```
 //! extern crate wayland_server;
@@ -2,3 +2,4 @@
 
 pub mod decoration;
 mod handlers;
+pub mod window_management;
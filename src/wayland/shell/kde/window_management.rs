@@ -0,0 +1,1058 @@
+//! KDE Plasma window management
+//!
+//! This module implements the `org_kde_plasma_window_management` protocol, which is used by Plasma
+//! panels, task managers and docks like latte-dock to list the windows of the compositor and to
+//! request activating, minimizing or closing them. Requests of clients are forwarded to the
+//! [`KdeWindowManagementHandler`], which decides whether and how to apply them.
+//!
+//! The state of a window is advertised through its [`PlasmaWindowHandle`]. Unlike most protocols,
+//! clients apply every change immediately. For [`Window`](crate::desktop::Window)s
+//! [`PlasmaWindowHandle::update_from_window`] synchronizes the title, app id, geometry, parent and the
+//! maximized, fullscreen and activated states, and should be called after every commit, configure
+//! and move of the window. Wayland windows have no minimized state, it has to be updated by the
+//! compositor using [`PlasmaWindowHandle::send_state`], just like the capabilities of the window.
+//!
+//! Virtual desktops, activities and window icons are not supported. Compositors without virtual
+//! desktops should set [`PlasmaWindowState::on_all_desktops`], as task managers may hide windows
+//! that are not on the current desktop otherwise.
+//!
+//! ```no_run
+//! use smithay::wayland::shell::kde::window_management::{
+//!     KdeWindowManagementHandler, KdeWindowManagementState, PlasmaWindowHandle, PlasmaWindowState,
+//! };
+//! # use smithay::desktop::Window;
+//!
+//! pub struct State {
+//!     kde_window_management: KdeWindowManagementState,
+//! }
+//!
+//! smithay::delegate_kde_window_management!(State);
+//!
+//! impl KdeWindowManagementHandler for State {
+//!     fn kde_window_management_state(&mut self) -> &mut KdeWindowManagementState {
+//!         &mut self.kde_window_management
+//!     }
+//!
+//!     fn activate(&mut self, handle: &PlasmaWindowHandle) {
+//!         // raise and focus the window of the handle
+//!     }
+//!
+//!     fn close(&mut self, handle: &PlasmaWindowHandle) {
+//!         // ask the window of the handle to close, e.g. with `ToplevelSurface::send_close`
+//!     }
+//!
+//!     fn set_minimized(&mut self, handle: &PlasmaWindowHandle, minimized: bool) {
+//!         // (un)map the window and report the new state
+//!         handle.send_state(PlasmaWindowState {
+//!             minimized,
+//!             ..handle.state()
+//!         });
+//!     }
+//! }
+//!
+//! # let mut display = wayland_server::Display::<State>::new().unwrap();
+//! # let display_handle = display.handle();
+//! # let window: Window = unimplemented!();
+//! let mut state = State {
+//!     kde_window_management: KdeWindowManagementState::new::<State>(&display_handle),
+//! };
+//!
+//! // once the window was mapped
+//! let handle = state.kde_window_management.new_window("", "");
+//! handle.send_state(PlasmaWindowState {
+//!     on_all_desktops: true,
+//!     ..handle.state()
+//! });
+//! handle.update_from_window(&window, (0, 0).into());
+//!
+//! // once the window was unmapped
+//! state.kde_window_management.remove_window(&handle);
+//! ```
+
+use std::sync::{Arc, Mutex};
+
+#[cfg(feature = "desktop")]
+use wayland_protocols::xdg::shell::server::xdg_toplevel;
+use wayland_protocols_plasma::plasma_window_management::server::{
+    org_kde_plasma_window::{self, OrgKdePlasmaWindow},
+    org_kde_plasma_window_management::{self, OrgKdePlasmaWindowManagement, ShowDesktop, State},
+};
+use wayland_server::{
+    backend::{ClientId, GlobalId},
+    protocol::wl_surface::WlSurface,
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource, Weak,
+};
+
+#[cfg(feature = "desktop")]
+use crate::desktop::Window;
+#[cfg(all(feature = "desktop", feature = "xwayland"))]
+use crate::xwayland::X11Surface;
+use crate::{
+    output::Output,
+    utils::{user_data::UserDataMap, Logical, Rectangle},
+    wayland::compositor,
+};
+#[cfg(feature = "desktop")]
+use crate::{
+    utils::Point,
+    wayland::shell::xdg::{ToplevelSurface, XdgToplevelSurfaceData},
+};
+
+crate::utils::ids::id_gen!(plasma_window_id);
+
+const MANAGER_VERSION: u32 = 16;
+
+/// Handler for the plasma window management protocol
+pub trait KdeWindowManagementHandler:
+    GlobalDispatch<OrgKdePlasmaWindowManagement, KdeWindowManagementGlobalData>
+    + Dispatch<OrgKdePlasmaWindowManagement, ()>
+    + Dispatch<OrgKdePlasmaWindow, PlasmaWindowHandle>
+    + 'static
+{
+    /// [KdeWindowManagementState] getter
+    fn kde_window_management_state(&mut self) -> &mut KdeWindowManagementState;
+
+    /// A client requested to activate the window
+    fn activate(&mut self, handle: &PlasmaWindowHandle);
+
+    /// A client requested to close the window
+    fn close(&mut self, handle: &PlasmaWindowHandle);
+
+    /// A client requested to minimize or unminimize the window
+    fn set_minimized(&mut self, handle: &PlasmaWindowHandle, minimized: bool) {
+        let _ = (handle, minimized);
+    }
+
+    /// A client requested to maximize or unmaximize the window
+    fn set_maximized(&mut self, handle: &PlasmaWindowHandle, maximized: bool) {
+        let _ = (handle, maximized);
+    }
+
+    /// A client requested to make the window fullscreen or to leave fullscreen
+    fn set_fullscreen(&mut self, handle: &PlasmaWindowHandle, fullscreen: bool) {
+        let _ = (handle, fullscreen);
+    }
+
+    /// A client set or unset the geometry representing the window on one of its panels
+    ///
+    /// The geometry is relative to the panel surface, e.g. the location of a taskbar entry,
+    /// and can be used as the target of a minimize animation.
+    fn set_minimized_geometry(
+        &mut self,
+        handle: &PlasmaWindowHandle,
+        panel: WlSurface,
+        geometry: Option<Rectangle<i32, Logical>>,
+    ) {
+        let _ = (handle, panel, geometry);
+    }
+
+    /// A client requested an interactive move of the window
+    fn request_move(&mut self, handle: &PlasmaWindowHandle) {
+        let _ = handle;
+    }
+
+    /// A client requested an interactive resize of the window
+    fn request_resize(&mut self, handle: &PlasmaWindowHandle) {
+        let _ = handle;
+    }
+
+    /// A client requested to move the window to the given output
+    fn send_to_output(&mut self, handle: &PlasmaWindowHandle, output: Output) {
+        let _ = (handle, output);
+    }
+
+    /// A client requested to show or hide the desktop
+    ///
+    /// The new state has to be reported using [`KdeWindowManagementState::set_show_desktop`].
+    fn show_desktop(&mut self, show: bool) {
+        let _ = show;
+    }
+}
+
+/// States of a window advertised by a [`PlasmaWindowHandle`]
+///
+/// By default windows are closeable, minimizable, maximizable, fullscreenable, movable and resizable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PlasmaWindowState {
+    /// The window is activated
+    pub active: bool,
+    /// The window is minimized
+    pub minimized: bool,
+    /// The window is maximized
+    pub maximized: bool,
+    /// The window is fullscreen
+    pub fullscreen: bool,
+    /// The window is kept above other windows
+    pub keep_above: bool,
+    /// The window is kept below other windows
+    pub keep_below: bool,
+    /// The window is shown on all virtual desktops
+    pub on_all_desktops: bool,
+    /// The window demands the attention of the user
+    pub demands_attention: bool,
+    /// The window can be closed
+    pub closeable: bool,
+    /// The window can be minimized
+    pub minimizable: bool,
+    /// The window can be maximized
+    pub maximizable: bool,
+    /// The window can be made fullscreen
+    pub fullscreenable: bool,
+    /// The window can be moved
+    pub movable: bool,
+    /// The window can be resized
+    pub resizable: bool,
+    /// The window should not be shown in task managers
+    pub skip_taskbar: bool,
+    /// The window should not be shown in window switchers
+    pub skip_switcher: bool,
+}
+
+impl Default for PlasmaWindowState {
+    fn default() -> Self {
+        PlasmaWindowState {
+            active: false,
+            minimized: false,
+            maximized: false,
+            fullscreen: false,
+            keep_above: false,
+            keep_below: false,
+            on_all_desktops: false,
+            demands_attention: false,
+            closeable: true,
+            minimizable: true,
+            maximizable: true,
+            fullscreenable: true,
+            movable: true,
+            resizable: true,
+            skip_taskbar: false,
+            skip_switcher: false,
+        }
+    }
+}
+
+impl PlasmaWindowState {
+    fn to_flags(self, version: u32) -> u32 {
+        let states = [
+            (self.active, State::Active, 1),
+            (self.minimized, State::Minimized, 1),
+            (self.maximized, State::Maximized, 1),
+            (self.fullscreen, State::Fullscreen, 1),
+            (self.keep_above, State::KeepAbove, 1),
+            (self.keep_below, State::KeepBelow, 1),
+            (self.on_all_desktops, State::OnAllDesktops, 1),
+            (self.demands_attention, State::DemandsAttention, 1),
+            (self.closeable, State::Closeable, 1),
+            (self.minimizable, State::Minimizable, 1),
+            (self.maximizable, State::Maximizable, 1),
+            (self.fullscreenable, State::Fullscreenable, 1),
+            (self.skip_taskbar, State::Skiptaskbar, 2),
+            (self.movable, State::Movable, 3),
+            (self.resizable, State::Resizable, 3),
+            (self.skip_switcher, State::Skipswitcher, 9),
+        ];
+        states
+            .into_iter()
+            .filter(|(set, _, since)| *set && version >= *since)
+            .fold(0, |flags, (_, state, _)| flags | state as u32)
+    }
+}
+
+#[derive(Debug)]
+struct PlasmaWindowHandleInner {
+    id: usize,
+    uuid: String,
+    title: String,
+    app_id: String,
+    pid: Option<u32>,
+    state: PlasmaWindowState,
+    geometry: Option<Rectangle<i32, Logical>>,
+    parent: Option<PlasmaWindowWeakHandle>,
+    // Each OrgKdePlasmaWindow contains the handle in it's user data,
+    // so this ref has to be weak
+    instances: Vec<Weak<OrgKdePlasmaWindow>>,
+    unmapped: bool,
+}
+
+impl PlasmaWindowHandleInner {
+    fn instances(&self) -> impl Iterator<Item = OrgKdePlasmaWindow> + '_ {
+        self.instances.iter().filter_map(|weak| weak.upgrade().ok())
+    }
+
+    fn send_unmapped(&mut self) {
+        if self.unmapped {
+            return;
+        }
+
+        self.unmapped = true;
+        // drain to prevent any events from being sent to unmapped windows
+        for window in self.instances.drain(..) {
+            if let Ok(window) = window.upgrade() {
+                window.unmapped();
+            }
+        }
+    }
+}
+
+impl Drop for PlasmaWindowHandleInner {
+    fn drop(&mut self) {
+        self.send_unmapped();
+        plasma_window_id::remove(self.id);
+    }
+}
+
+/// Weak version of [PlasmaWindowHandle]
+#[derive(Debug, Clone)]
+pub struct PlasmaWindowWeakHandle {
+    inner: std::sync::Weak<(Mutex<PlasmaWindowHandleInner>, UserDataMap)>,
+}
+
+impl PlasmaWindowWeakHandle {
+    /// Upgrade weak [PlasmaWindowWeakHandle] to strong [PlasmaWindowHandle]
+    pub fn upgrade(&self) -> Option<PlasmaWindowHandle> {
+        Some(PlasmaWindowHandle {
+            inner: self.inner.upgrade()?,
+        })
+    }
+}
+
+// Associates the handle with the wl_surface of a toplevel
+type SurfaceHandle = Mutex<Option<PlasmaWindowWeakHandle>>;
+
+/// Handle of a window, used to advertise its state to plasma window management clients
+#[derive(Debug, Clone)]
+pub struct PlasmaWindowHandle {
+    inner: Arc<(Mutex<PlasmaWindowHandleInner>, UserDataMap)>,
+}
+
+impl PartialEq for PlasmaWindowHandle {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+impl PlasmaWindowHandle {
+    fn new(title: String, app_id: String) -> Self {
+        let id = plasma_window_id::next();
+        Self {
+            inner: Arc::new((
+                Mutex::new(PlasmaWindowHandleInner {
+                    id,
+                    uuid: format!("smithay-window-{id}"),
+                    title,
+                    app_id,
+                    pid: None,
+                    state: PlasmaWindowState::default(),
+                    geometry: None,
+                    parent: None,
+                    instances: Vec::new(),
+                    unmapped: false,
+                }),
+                UserDataMap::new(),
+            )),
+        }
+    }
+
+    // Used for windows requested by clients, which do not exist (anymore)
+    fn new_unmapped() -> Self {
+        let handle = Self::new(String::new(), String::new());
+        handle.inner.0.lock().unwrap().unmapped = true;
+        handle
+    }
+
+    /// Downgrade strong [PlasmaWindowHandle] to weak [PlasmaWindowWeakHandle]
+    pub fn downgrade(&self) -> PlasmaWindowWeakHandle {
+        PlasmaWindowWeakHandle {
+            inner: Arc::downgrade(&self.inner),
+        }
+    }
+
+    /// Attempt to retrieve [PlasmaWindowHandle] from an existing resource
+    pub fn from_resource(resource: &OrgKdePlasmaWindow) -> Option<Self> {
+        resource.data::<Self>().cloned()
+    }
+
+    /// Retrieve the handle associated with a toplevel surface by
+    /// [PlasmaWindowHandle::update_from_window]
+    pub fn from_surface(surface: &WlSurface) -> Option<Self> {
+        compositor::with_states(surface, |states| {
+            states
+                .data_map
+                .get::<SurfaceHandle>()
+                .and_then(|handle| handle.lock().unwrap().as_ref().and_then(|weak| weak.upgrade()))
+        })
+    }
+
+    /// Retrieve [`OrgKdePlasmaWindow`] instances for this handle.
+    pub fn resources(&self) -> Vec<OrgKdePlasmaWindow> {
+        self.inner.0.lock().unwrap().instances().collect()
+    }
+
+    /// Retrieve [`OrgKdePlasmaWindow`] instances for this handle of a given [`Client`].
+    pub fn resources_for_client(&self, client: &Client) -> Vec<OrgKdePlasmaWindow> {
+        self.resources()
+            .into_iter()
+            .filter(|window| window.client().as_ref().is_some_and(|c| c == client))
+            .collect()
+    }
+
+    /// Access the [UserDataMap] associated with this [PlasmaWindowHandle]
+    pub fn user_data(&self) -> &UserDataMap {
+        &self.inner.1
+    }
+
+    /// The id announced to clients
+    pub fn id(&self) -> u32 {
+        self.inner.0.lock().unwrap().id as u32
+    }
+
+    /// The uuid announced to clients
+    pub fn uuid(&self) -> String {
+        self.inner.0.lock().unwrap().uuid.clone()
+    }
+
+    /// The title of the window has changed.
+    pub fn send_title(&self, title: &str) {
+        let mut inner = self.inner.0.lock().unwrap();
+        if inner.title == title {
+            return;
+        }
+
+        inner.title = title.to_string();
+        for window in inner.instances() {
+            window.title_changed(title.to_string());
+        }
+    }
+
+    /// The app id of the window has changed.
+    pub fn send_app_id(&self, app_id: &str) {
+        let mut inner = self.inner.0.lock().unwrap();
+        if inner.app_id == app_id {
+            return;
+        }
+
+        inner.app_id = app_id.to_string();
+        for window in inner.instances() {
+            window.app_id_changed(app_id.to_string());
+        }
+    }
+
+    /// The process id of the window has changed.
+    ///
+    /// Clients are not notified about an unknown process id.
+    pub fn send_pid(&self, pid: Option<u32>) {
+        let mut inner = self.inner.0.lock().unwrap();
+        if inner.pid == pid {
+            return;
+        }
+
+        inner.pid = pid;
+        if let Some(pid) = pid {
+            for window in inner.instances() {
+                window.pid_changed(pid);
+            }
+        }
+    }
+
+    /// The states of the window have changed.
+    pub fn send_state(&self, state: PlasmaWindowState) {
+        let mut inner = self.inner.0.lock().unwrap();
+        if inner.state == state {
+            return;
+        }
+
+        inner.state = state;
+        for window in inner.instances() {
+            window.state_changed(state.to_flags(window.version()));
+        }
+    }
+
+    /// The geometry of the window in the global compositor space has changed.
+    ///
+    /// Clients are not notified about an unknown geometry.
+    pub fn send_geometry(&self, geometry: Option<Rectangle<i32, Logical>>) {
+        let mut inner = self.inner.0.lock().unwrap();
+        if inner.geometry == geometry {
+            return;
+        }
+
+        inner.geometry = geometry;
+        if let Some(geometry) = geometry {
+            for window in inner.instances() {
+                send_geometry(&window, geometry);
+            }
+        }
+    }
+
+    /// The parent of the window has changed.
+    pub fn send_parent(&self, parent: Option<&PlasmaWindowHandle>) {
+        let mut inner = self.inner.0.lock().unwrap();
+        if inner.parent.as_ref().and_then(|weak| weak.upgrade()).as_ref() == parent {
+            return;
+        }
+
+        inner.parent = parent.map(|parent| parent.downgrade());
+        for window in inner.instances() {
+            send_parent(&window, parent);
+        }
+    }
+
+    /// The window has been unmapped
+    pub fn send_unmapped(&self) {
+        self.inner.0.lock().unwrap().send_unmapped();
+    }
+
+    /// Synchronize the handle with the current state of a window
+    ///
+    /// Updates the title, app id, process id and the maximized, fullscreen and activated states.
+    /// For X11 windows the minimized state is updated as well, for Wayland windows the parent.
+    /// The handle is associated with the surface of Wayland windows, see
+    /// [PlasmaWindowHandle::from_surface].
+    ///
+    /// `location` is the location of the window in the global compositor space,
+    /// e.g. as returned by [`Space::element_location`](crate::desktop::Space::element_location).
+    #[cfg(feature = "desktop")]
+    pub fn update_from_window(&self, window: &Window, location: Point<i32, Logical>) {
+        if let Some(toplevel) = window.toplevel() {
+            self.update_from_toplevel(toplevel);
+        }
+        #[cfg(feature = "xwayland")]
+        if let Some(surface) = window.x11_surface() {
+            self.update_from_x11_surface(surface);
+        }
+        self.send_geometry(Some(Rectangle::new(location, window.geometry().size)));
+    }
+
+    #[cfg(feature = "desktop")]
+    fn update_from_toplevel(&self, toplevel: &ToplevelSurface) {
+        let (title, app_id) = compositor::with_states(toplevel.wl_surface(), |states| {
+            states
+                .data_map
+                .insert_if_missing_threadsafe(|| SurfaceHandle::new(None));
+            *states.data_map.get::<SurfaceHandle>().unwrap().lock().unwrap() = Some(self.downgrade());
+
+            let attributes = states
+                .data_map
+                .get::<XdgToplevelSurfaceData>()
+                .unwrap()
+                .lock()
+                .unwrap();
+            (
+                attributes.title.clone().unwrap_or_default(),
+                attributes.app_id.clone().unwrap_or_default(),
+            )
+        });
+        let surface = toplevel.wl_surface();
+        let pid = surface
+            .client()
+            .zip(surface.handle().upgrade().map(DisplayHandle::from))
+            .and_then(|(client, dh)| client.get_credentials(&dh).ok())
+            .map(|credentials| credentials.pid as u32);
+        let current = toplevel.current_state();
+        let parent = toplevel
+            .parent()
+            .and_then(|parent| PlasmaWindowHandle::from_surface(&parent));
+
+        self.send_title(&title);
+        self.send_app_id(&app_id);
+        self.send_pid(pid);
+        self.send_state(PlasmaWindowState {
+            active: current.states.contains(xdg_toplevel::State::Activated),
+            maximized: current.states.contains(xdg_toplevel::State::Maximized),
+            fullscreen: current.states.contains(xdg_toplevel::State::Fullscreen),
+            ..self.state()
+        });
+        self.send_parent(parent.as_ref());
+    }
+
+    #[cfg(all(feature = "desktop", feature = "xwayland"))]
+    fn update_from_x11_surface(&self, surface: &X11Surface) {
+        self.send_title(&surface.title());
+        self.send_app_id(&surface.class());
+        self.send_pid(surface.pid());
+        self.send_state(PlasmaWindowState {
+            active: surface.is_activated(),
+            minimized: surface.is_minimized(),
+            maximized: surface.is_maximized(),
+            fullscreen: surface.is_fullscreen(),
+            ..self.state()
+        });
+    }
+
+    /// The title of the window
+    pub fn title(&self) -> String {
+        self.inner.0.lock().unwrap().title.clone()
+    }
+
+    /// The app id of the window
+    pub fn app_id(&self) -> String {
+        self.inner.0.lock().unwrap().app_id.clone()
+    }
+
+    /// The process id of the window
+    pub fn pid(&self) -> Option<u32> {
+        self.inner.0.lock().unwrap().pid
+    }
+
+    /// The states of the window
+    pub fn state(&self) -> PlasmaWindowState {
+        self.inner.0.lock().unwrap().state
+    }
+
+    /// The geometry of the window in the global compositor space
+    pub fn geometry(&self) -> Option<Rectangle<i32, Logical>> {
+        self.inner.0.lock().unwrap().geometry
+    }
+
+    /// The parent of the window
+    pub fn parent(&self) -> Option<PlasmaWindowHandle> {
+        self.inner
+            .0
+            .lock()
+            .unwrap()
+            .parent
+            .as_ref()
+            .and_then(|weak| weak.upgrade())
+    }
+
+    /// The window has been unmapped
+    pub fn is_unmapped(&self) -> bool {
+        self.inner.0.lock().unwrap().unmapped
+    }
+
+    fn add_instance(&self, window: &OrgKdePlasmaWindow) {
+        let mut inner = self.inner.0.lock().unwrap();
+        // unmapped windows never send any further events
+        if !inner.unmapped {
+            inner.instances.push(window.downgrade());
+        }
+    }
+
+    fn init_instance(&self, window: &OrgKdePlasmaWindow) {
+        let (title, app_id, pid, state, geometry, unmapped) = {
+            let inner = self.inner.0.lock().unwrap();
+            (
+                inner.title.clone(),
+                inner.app_id.clone(),
+                inner.pid,
+                inner.state,
+                inner.geometry,
+                inner.unmapped,
+            )
+        };
+
+        window.title_changed(title);
+        window.app_id_changed(app_id);
+        if let Some(pid) = pid {
+            window.pid_changed(pid);
+        }
+        window.state_changed(state.to_flags(window.version()));
+        if let Some(geometry) = geometry {
+            send_geometry(window, geometry);
+        }
+        send_parent(window, self.parent().as_ref());
+        if unmapped {
+            window.unmapped();
+        }
+        if window.version() >= 4 {
+            window.initial_state();
+        }
+    }
+
+    fn remove_instance(&self, instance: &OrgKdePlasmaWindow) {
+        let mut inner = self.inner.0.lock().unwrap();
+        if let Some(pos) = inner.instances.iter().position(|i| i == instance) {
+            inner.instances.remove(pos);
+        }
+    }
+}
+
+fn send_geometry(window: &OrgKdePlasmaWindow, geometry: Rectangle<i32, Logical>) {
+    if window.version() < 6 {
+        return;
+    }
+    window.geometry(
+        geometry.loc.x,
+        geometry.loc.y,
+        geometry.size.w.max(0) as u32,
+        geometry.size.h.max(0) as u32,
+    );
+}
+
+fn send_parent(window: &OrgKdePlasmaWindow, parent: Option<&PlasmaWindowHandle>) {
+    if window.version() < 5 {
+        return;
+    }
+    let parent = parent.and_then(|parent| {
+        let client = window.client()?;
+        parent.resources_for_client(&client).into_iter().next()
+    });
+    window.parent_window(parent.as_ref());
+}
+
+/// State of the [OrgKdePlasmaWindowManagement] global
+#[derive(Debug)]
+pub struct KdeWindowManagementState {
+    global: GlobalId,
+    windows: Vec<PlasmaWindowWeakHandle>,
+    stacking_order: Vec<PlasmaWindowWeakHandle>,
+    show_desktop: bool,
+    instances: Vec<OrgKdePlasmaWindowManagement>,
+}
+
+impl KdeWindowManagementState {
+    /// Register new [OrgKdePlasmaWindowManagement] global
+    pub fn new<D: KdeWindowManagementHandler>(dh: &DisplayHandle) -> Self {
+        Self::new_with_filter::<D>(dh, |_| true)
+    }
+
+    /// Register new [OrgKdePlasmaWindowManagement] global with filter
+    ///
+    /// As the protocol allows to control other clients, access should usually be restricted.
+    pub fn new_with_filter<D: KdeWindowManagementHandler>(
+        dh: &DisplayHandle,
+        can_view: impl Fn(&Client) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        let global = dh.create_global::<D, OrgKdePlasmaWindowManagement, _>(
+            MANAGER_VERSION,
+            KdeWindowManagementGlobalData {
+                filter: Box::new(can_view),
+            },
+        );
+
+        Self {
+            global,
+            windows: Vec::new(),
+            stacking_order: Vec::new(),
+            show_desktop: false,
+            instances: Vec::new(),
+        }
+    }
+
+    /// [OrgKdePlasmaWindowManagement] GlobalId getter
+    pub fn global(&self) -> GlobalId {
+        self.global.clone()
+    }
+
+    /// Announce a new window to all clients
+    ///
+    /// Further state like the geometry or the states of the window can be sent using the returned handle.
+    pub fn new_window(&mut self, title: impl Into<String>, app_id: impl Into<String>) -> PlasmaWindowHandle {
+        let handle = PlasmaWindowHandle::new(title.into(), app_id.into());
+
+        for instance in &self.instances {
+            announce_window(instance, &handle);
+        }
+
+        self.windows.push(handle.downgrade());
+
+        handle
+    }
+
+    /// Remove the window, and send the unmapped event if needed
+    ///
+    /// Alternatively, you can just call [PlasmaWindowHandle::send_unmapped] and the handle
+    /// will be lazely cleaned up, either by [Self::cleanup_unmapped_windows], or during next global bind
+    pub fn remove_window(&mut self, handle: &PlasmaWindowHandle) {
+        handle.send_unmapped();
+        self.windows.retain(|h| h.upgrade().is_some_and(|h| &h != handle));
+    }
+
+    /// Auto cleanup unmapped windows
+    ///
+    /// This is not needed if you already manually remove each handle with [Self::remove_window]
+    pub fn cleanup_unmapped_windows(&mut self) {
+        self.windows
+            .retain(|handle| handle.upgrade().is_some_and(|handle| !handle.is_unmapped()));
+    }
+
+    /// Returns all windows, which were not unmapped
+    pub fn windows(&self) -> impl Iterator<Item = PlasmaWindowHandle> + '_ {
+        self.windows
+            .iter()
+            .filter_map(|handle| handle.upgrade())
+            .filter(|handle| !handle.is_unmapped())
+    }
+
+    /// Announce the stacking order of the windows, from bottom to top
+    pub fn set_stacking_order<'a>(&mut self, windows: impl IntoIterator<Item = &'a PlasmaWindowHandle>) {
+        self.stacking_order = windows.into_iter().map(PlasmaWindowHandle::downgrade).collect();
+        for instance in &self.instances {
+            send_stacking_order(instance, &self.stacking_order);
+        }
+    }
+
+    /// Enter or leave the show desktop mode
+    pub fn set_show_desktop(&mut self, show: bool) {
+        if self.show_desktop == show {
+            return;
+        }
+
+        self.show_desktop = show;
+        for instance in &self.instances {
+            send_show_desktop(instance, show);
+        }
+    }
+
+    /// Returns whether the show desktop mode is active
+    pub fn show_desktop(&self) -> bool {
+        self.show_desktop
+    }
+}
+
+fn announce_window(manager: &OrgKdePlasmaWindowManagement, handle: &PlasmaWindowHandle) {
+    if manager.version() >= 13 {
+        manager.window_with_uuid(handle.id(), handle.uuid());
+    } else {
+        manager.window(handle.id());
+    }
+}
+
+fn send_stacking_order(manager: &OrgKdePlasmaWindowManagement, windows: &[PlasmaWindowWeakHandle]) {
+    let windows = windows
+        .iter()
+        .filter_map(|handle| handle.upgrade())
+        .filter(|handle| !handle.is_unmapped())
+        .collect::<Vec<_>>();
+    if manager.version() >= 11 {
+        let ids = windows
+            .iter()
+            .flat_map(|handle| handle.id().to_ne_bytes())
+            .collect();
+        manager.stacking_order_changed(ids);
+    }
+    if manager.version() >= 12 {
+        let uuids = windows.iter().map(|handle| handle.uuid()).collect::<Vec<_>>();
+        manager.stacking_order_uuid_changed(uuids.join(";"));
+    }
+}
+
+fn send_show_desktop(manager: &OrgKdePlasmaWindowManagement, show: bool) {
+    let state = if show {
+        ShowDesktop::Enabled
+    } else {
+        ShowDesktop::Disabled
+    };
+    manager.show_desktop_changed(state as u32);
+}
+
+/// Glabal data of [OrgKdePlasmaWindowManagement]
+pub struct KdeWindowManagementGlobalData {
+    filter: Box<dyn Fn(&Client) -> bool + Send + Sync>,
+}
+
+impl std::fmt::Debug for KdeWindowManagementGlobalData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KdeWindowManagementGlobalData")
+            .finish_non_exhaustive()
+    }
+}
+
+impl<D: KdeWindowManagementHandler>
+    GlobalDispatch<OrgKdePlasmaWindowManagement, KdeWindowManagementGlobalData, D>
+    for KdeWindowManagementState
+{
+    fn bind(
+        state: &mut D,
+        _dh: &DisplayHandle,
+        _client: &Client,
+        resource: New<OrgKdePlasmaWindowManagement>,
+        _global_data: &KdeWindowManagementGlobalData,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        let instance = data_init.init(resource, ());
+
+        let state = state.kde_window_management_state();
+        state.cleanup_unmapped_windows();
+
+        send_show_desktop(&instance, state.show_desktop);
+        for handle in state.windows() {
+            announce_window(&instance, &handle);
+        }
+        send_stacking_order(&instance, &state.stacking_order);
+
+        state.instances.push(instance);
+    }
+
+    fn can_view(client: Client, global_data: &KdeWindowManagementGlobalData) -> bool {
+        (global_data.filter)(&client)
+    }
+}
+
+impl<D: KdeWindowManagementHandler> Dispatch<OrgKdePlasmaWindowManagement, (), D>
+    for KdeWindowManagementState
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        _manager: &OrgKdePlasmaWindowManagement,
+        request: org_kde_plasma_window_management::Request,
+        _data: &(),
+        _dh: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            org_kde_plasma_window_management::Request::ShowDesktop { state: show } => {
+                state.show_desktop(show == ShowDesktop::Enabled as u32);
+            }
+            org_kde_plasma_window_management::Request::GetWindow {
+                id,
+                internal_window_id,
+            } => {
+                let handle = state
+                    .kde_window_management_state()
+                    .windows()
+                    .find(|handle| handle.id() == internal_window_id);
+                init_window(data_init, id, handle);
+            }
+            org_kde_plasma_window_management::Request::GetWindowByUuid {
+                id,
+                internal_window_uuid,
+            } => {
+                let handle = state
+                    .kde_window_management_state()
+                    .windows()
+                    .find(|handle| handle.uuid() == internal_window_uuid);
+                init_window(data_init, id, handle);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(state: &mut D, _client: ClientId, resource: &OrgKdePlasmaWindowManagement, _data: &()) {
+        state
+            .kde_window_management_state()
+            .instances
+            .retain(|i| i != resource);
+    }
+}
+
+fn init_window<D: KdeWindowManagementHandler>(
+    data_init: &mut DataInit<'_, D>,
+    id: New<OrgKdePlasmaWindow>,
+    handle: Option<PlasmaWindowHandle>,
+) {
+    // windows which are already gone are reported as unmapped
+    let handle = handle.unwrap_or_else(PlasmaWindowHandle::new_unmapped);
+    let window = data_init.init(id, handle.clone());
+    handle.add_instance(&window);
+    handle.init_instance(&window);
+}
+
+impl<D: KdeWindowManagementHandler> Dispatch<OrgKdePlasmaWindow, PlasmaWindowHandle, D>
+    for KdeWindowManagementState
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        _resource: &OrgKdePlasmaWindow,
+        request: org_kde_plasma_window::Request,
+        handle: &PlasmaWindowHandle,
+        _dh: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        if handle.is_unmapped() {
+            // requests of unmapped windows are ignored
+            return;
+        }
+
+        match request {
+            org_kde_plasma_window::Request::SetState { flags, state: value } => {
+                let changed = |state: State| flags & state as u32 != 0;
+                let set = |state: State| value & state as u32 != 0;
+                if changed(State::Active) && set(State::Active) {
+                    state.activate(handle);
+                }
+                if changed(State::Minimized) {
+                    state.set_minimized(handle, set(State::Minimized));
+                }
+                if changed(State::Maximized) {
+                    state.set_maximized(handle, set(State::Maximized));
+                }
+                if changed(State::Fullscreen) {
+                    state.set_fullscreen(handle, set(State::Fullscreen));
+                }
+            }
+            org_kde_plasma_window::Request::SetMinimizedGeometry {
+                panel,
+                x,
+                y,
+                width,
+                height,
+            } => {
+                let geometry =
+                    Rectangle::new((x as i32, y as i32).into(), (width as i32, height as i32).into());
+                state.set_minimized_geometry(handle, panel, Some(geometry));
+            }
+            org_kde_plasma_window::Request::UnsetMinimizedGeometry { panel } => {
+                state.set_minimized_geometry(handle, panel, None);
+            }
+            org_kde_plasma_window::Request::Close => state.close(handle),
+            org_kde_plasma_window::Request::RequestMove => state.request_move(handle),
+            org_kde_plasma_window::Request::RequestResize => state.request_resize(handle),
+            org_kde_plasma_window::Request::SendToOutput { output } => {
+                if let Some(output) = Output::from_resource(&output) {
+                    state.send_to_output(handle, output);
+                }
+            }
+            // virtual desktops, activities and icons are not supported,
+            // dropping the fd of `GetIcon` leaves the icon empty
+            org_kde_plasma_window::Request::SetVirtualDesktop { .. }
+            | org_kde_plasma_window::Request::RequestEnterVirtualDesktop { .. }
+            | org_kde_plasma_window::Request::RequestEnterNewVirtualDesktop
+            | org_kde_plasma_window::Request::RequestLeaveVirtualDesktop { .. }
+            | org_kde_plasma_window::Request::RequestEnterActivity { .. }
+            | org_kde_plasma_window::Request::RequestLeaveActivity { .. }
+            | org_kde_plasma_window::Request::GetIcon { .. } => {}
+            org_kde_plasma_window::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(
+        _state: &mut D,
+        _client: ClientId,
+        resource: &OrgKdePlasmaWindow,
+        handle: &PlasmaWindowHandle,
+    ) {
+        handle.remove_instance(resource);
+    }
+}
+
+/// Macro to delegate implementation of the plasma window management to [KdeWindowManagementState].
+///
+/// You must also implement [KdeWindowManagementHandler] to use this.
+#[macro_export]
+macro_rules! delegate_kde_window_management {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        $crate::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols_plasma::plasma_window_management::server::org_kde_plasma_window_management::OrgKdePlasmaWindowManagement: $crate::wayland::shell::kde::window_management::KdeWindowManagementGlobalData
+        ] => $crate::wayland::shell::kde::window_management::KdeWindowManagementState);
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols_plasma::plasma_window_management::server::org_kde_plasma_window_management::OrgKdePlasmaWindowManagement: ()
+        ] => $crate::wayland::shell::kde::window_management::KdeWindowManagementState);
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols_plasma::plasma_window_management::server::org_kde_plasma_window::OrgKdePlasmaWindow: $crate::wayland::shell::kde::window_management::PlasmaWindowHandle
+        ] => $crate::wayland::shell::kde::window_management::KdeWindowManagementState);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn state_flags() {
+        let state = PlasmaWindowState {
+            active: true,
+            skip_taskbar: true,
+            skip_switcher: true,
+            ..Default::default()
+        };
+        // closeable, minimizable, maximizable and fullscreenable
+        let capabilities = 0xf00;
+        assert_eq!(state.to_flags(1), 0x1 | capabilities);
+        assert_eq!(state.to_flags(2), 0x1001 | capabilities);
+        assert_eq!(state.to_flags(16), 0x59001 | capabilities);
+    }
+}
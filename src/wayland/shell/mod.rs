@@ -14,12 +14,16 @@
 //! - The [`wlr_layer`](wlr_layer/index.html) module provides handlers for the `wlr_layer_shell`
 //!   protocol, which is for windows rendering above/below normal XDG windows
 //! - The [`kde`](kde/index.html) module provides handlers for KDE-specific protocols
+//!
+//! The [`decoration`](decoration/index.html) module helps answering the decoration protocols of
+//! `xdg_shell` and KDE consistently.
 
 use crate::{utils::Serial, wayland::compositor};
 use thiserror::Error;
 use wayland_server::protocol::wl_surface::WlSurface;
 use xdg::XdgToplevelSurfaceData;
 
+pub mod decoration;
 pub mod kde;
 pub mod wlr_layer;
 pub mod xdg;
@@ -10,6 +10,7 @@ use crate::utils::{
     alive_tracker::{AliveTracker, IsAlive},
     Serial,
 };
+use crate::wayland::protocol_error::{post_error, ProtocolErrorHandler, ProtocolErrorKind};
 use crate::wayland::shell::xdg::XdgPopupSurfaceData;
 use crate::wayland::{compositor, shell::wlr_layer::Layer};
 
@@ -52,7 +53,7 @@ impl<D> Dispatch<ZwlrLayerShellV1, (), D> for WlrLayerShellState
 where
     D: Dispatch<ZwlrLayerShellV1, ()>,
     D: Dispatch<ZwlrLayerSurfaceV1, WlrLayerSurfaceUserData>,
-    D: WlrLayerShellHandler,
+    D: WlrLayerShellHandler + ProtocolErrorHandler,
     D: 'static,
 {
     fn request(
@@ -84,7 +85,13 @@ where
                 };
 
                 if compositor::give_role(&wl_surface, LAYER_SURFACE_ROLE).is_err() {
-                    shell.post_error(zwlr_layer_shell_v1::Error::Role, "Surface already has a role.");
+                    post_error(
+                        state,
+                        shell,
+                        ProtocolErrorKind::Role,
+                        zwlr_layer_shell_v1::Error::Role,
+                        "Surface already has a role.",
+                    );
                     return;
                 }
 
@@ -215,7 +222,7 @@ impl IsAlive for ZwlrLayerSurfaceV1 {
 impl<D> Dispatch<ZwlrLayerSurfaceV1, WlrLayerSurfaceUserData, D> for WlrLayerShellState
 where
     D: Dispatch<ZwlrLayerSurfaceV1, WlrLayerSurfaceUserData>,
-    D: WlrLayerShellHandler,
+    D: WlrLayerShellHandler + ProtocolErrorHandler,
 {
     fn request(
         state: &mut D,
@@ -335,7 +342,10 @@ where
                 let configure = match found_configure {
                     Some(configure) => configure,
                     None => {
-                        layer_surface.post_error(
+                        post_error(
+                            state,
+                            layer_surface,
+                            ProtocolErrorKind::Serial,
                             zwlr_layer_surface_v1::Error::InvalidSurfaceState,
                             format!("wrong configure serial: {}", <u32>::from(serial)),
                         );
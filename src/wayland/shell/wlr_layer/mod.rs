@@ -41,6 +41,7 @@
 //! }
 //! // let smithay implement wayland_server::DelegateDispatch
 //! delegate_layer_shell!(State);
+//! # impl smithay::wayland::protocol_error::ProtocolErrorHandler for State {}
 //!
 //! // You're now ready to go!
 //! ```
@@ -82,6 +82,7 @@
 //!     }
 //! }
 //! delegate_xdg_shell!(State);
+//! # impl smithay::wayland::protocol_error::ProtocolErrorHandler for State {}
 //! delegate_xdg_decoration!(State);
 //!
 //! // You are ready to go!  
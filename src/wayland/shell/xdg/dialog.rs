@@ -70,6 +70,7 @@
 //!     }
 //! }
 //! delegate_xdg_shell!(State);
+//! # impl smithay::wayland::protocol_error::ProtocolErrorHandler for State {}
 //! delegate_xdg_dialog!(State);
 //!
 //! // You are ready to go!  
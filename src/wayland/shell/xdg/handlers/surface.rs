@@ -5,6 +5,7 @@ use std::sync::Mutex;
 use indexmap::IndexSet;
 
 use crate::utils::alive_tracker::{AliveTracker, IsAlive};
+use crate::wayland::protocol_error::{post_error, ProtocolErrorHandler, ProtocolErrorKind};
 use crate::wayland::shell::xdg::{XdgPopupSurfaceData, XdgToplevelSurfaceData};
 use crate::{
     utils::{Rectangle, Serial},
@@ -50,7 +51,7 @@ where
     D: Dispatch<XdgSurface, XdgSurfaceUserData>,
     D: Dispatch<XdgToplevel, XdgShellSurfaceUserData>,
     D: Dispatch<XdgPopup, XdgShellSurfaceUserData>,
-    D: XdgShellHandler,
+    D: XdgShellHandler + ProtocolErrorHandler,
     D: 'static,
 {
     fn request(
@@ -82,7 +83,10 @@ where
                 }
 
                 if data.has_active_role.load(Ordering::Acquire) {
-                    data.wm_base.post_error(
+                    post_error(
+                        state,
+                        &data.wm_base,
+                        ProtocolErrorKind::Role,
                         xdg_wm_base::Error::Role,
                         "xdg_surface was destroyed before its role object",
                     );
@@ -94,7 +98,13 @@ where
                 let shell = &data.wm_base;
 
                 if compositor::give_role(surface, XDG_TOPLEVEL_ROLE).is_err() {
-                    shell.post_error(xdg_wm_base::Error::Role, "Surface already has a role.");
+                    post_error(
+                        state,
+                        shell,
+                        ProtocolErrorKind::Role,
+                        xdg_wm_base::Error::Role,
+                        "Surface already has a role.",
+                    );
                     return;
                 }
 
@@ -179,7 +189,13 @@ where
                     ..Default::default()
                 };
                 if compositor::give_role(surface, XDG_POPUP_ROLE).is_err() {
-                    shell.post_error(xdg_wm_base::Error::Role, "Surface already has a role.");
+                    post_error(
+                        state,
+                        shell,
+                        ProtocolErrorKind::Role,
+                        xdg_wm_base::Error::Role,
+                        "Surface already has a role.",
+                    );
                     return;
                 }
 
@@ -239,7 +255,10 @@ where
                 let role = compositor::get_role(surface);
 
                 if role.is_none() {
-                    xdg_surface.post_error(
+                    post_error(
+                        state,
+                        xdg_surface,
+                        ProtocolErrorKind::Role,
                         xdg_surface::Error::NotConstructed,
                         "xdg_surface must have a role.",
                     );
@@ -247,7 +266,10 @@ where
                 }
 
                 if role != Some(XDG_TOPLEVEL_ROLE) && role != Some(XDG_POPUP_ROLE) {
-                    data.wm_base.post_error(
+                    post_error(
+                        state,
+                        &data.wm_base,
+                        ProtocolErrorKind::Role,
                         xdg_wm_base::Error::Role,
                         "xdg_surface must have a role of xdg_toplevel or xdg_popup.",
                     );
@@ -266,7 +288,10 @@ where
                 // or xdg_popup. If none of the role matches the xdg_surface has no role set
                 // which is a protocol error.
                 if compositor::get_role(surface).is_none() {
-                    xdg_surface.post_error(
+                    post_error(
+                        state,
+                        xdg_surface,
+                        ProtocolErrorKind::Role,
                         xdg_surface::Error::NotConstructed,
                         "xdg_surface must have a role.",
                     );
@@ -310,14 +335,20 @@ where
                 let configure = match found_configure {
                     Ok(Some(configure)) => configure,
                     Ok(None) => {
-                        data.wm_base.post_error(
+                        post_error(
+                            state,
+                            &data.wm_base,
+                            ProtocolErrorKind::Serial,
                             xdg_wm_base::Error::InvalidSurfaceState,
                             format!("wrong configure serial: {}", <u32>::from(serial)),
                         );
                         return;
                     }
                     Err(()) => {
-                        data.wm_base.post_error(
+                        post_error(
+                            state,
+                            &data.wm_base,
+                            ProtocolErrorKind::Role,
                             xdg_wm_base::Error::Role as u32,
                             "xdg_surface must have a role of xdg_toplevel or xdg_popup.",
                         );
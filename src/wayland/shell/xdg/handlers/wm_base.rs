@@ -36,6 +36,7 @@ where
     ) {
         let shell = data_init.init(resource, XdgWmBaseUserData::default());
 
+        state.xdg_shell_state().known_clients.push(shell.clone());
         XdgShellHandler::new_client(state, ShellClient::new(&shell));
     }
 }
@@ -81,18 +82,22 @@ where
             }
             xdg_wm_base::Request::Pong { serial } => {
                 let serial = Serial::from(serial);
-                let valid = {
+                let (valid, was_unresponsive) = {
                     let mut guard = data.client_data.lock().unwrap();
                     if guard.pending_ping == Some(serial) {
                         guard.pending_ping = None;
-                        true
+                        guard.auto_ping = None;
+                        (true, std::mem::take(&mut guard.unresponsive))
                     } else {
-                        false
+                        (false, false)
                     }
                 };
                 if valid {
                     XdgShellHandler::client_pong(state, ShellClient::new(wm_base));
                 }
+                if was_unresponsive {
+                    XdgShellHandler::client_responsiveness_changed(state, ShellClient::new(wm_base), true);
+                }
             }
             xdg_wm_base::Request::Destroy => {
                 if !data.known_surfaces.lock().unwrap().is_empty() {
@@ -107,6 +112,7 @@ where
     }
 
     fn destroyed(state: &mut D, _client_id: ClientId, wm_base: &XdgWmBase, data: &XdgWmBaseUserData) {
+        state.xdg_shell_state().known_clients.retain(|c| c != wm_base);
         XdgShellHandler::client_destroyed(state, ShellClient::new(wm_base));
         data.alive_tracker.destroy_notify();
     }
@@ -95,6 +95,7 @@
 //!     }
 //! }
 //! delegate_xdg_shell!(State);
+//! # impl smithay::wayland::protocol_error::ProtocolErrorHandler for State {}
 //!
 //! // You're now ready to go!
 //! ```
@@ -123,8 +124,18 @@ use crate::utils::{user_data::UserDataMap, Logical, Point, Rectangle, Size};
 use crate::utils::{Serial, SERIAL_COUNTER};
 use crate::wayland::compositor;
 use crate::wayland::compositor::Cacheable;
+use crate::wayland::protocol_error::{post_error, ProtocolErrorHandler, ProtocolErrorKind};
+use calloop::{
+    timer::{TimeoutAction, Timer},
+    InsertError, LoopHandle, RegistrationToken,
+};
 use std::cmp::min;
-use std::{collections::HashSet, fmt::Debug, sync::Mutex};
+use std::{
+    collections::HashSet,
+    fmt::Debug,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
 
 use wayland_protocols::xdg::decoration::zv1::server::zxdg_toplevel_decoration_v1;
 use wayland_protocols::xdg::shell::server::xdg_positioner::{Anchor, ConstraintAdjustment, Gravity};
@@ -141,6 +152,7 @@ use super::PingError;
 
 pub mod decoration;
 pub mod dialog;
+pub mod transaction;
 
 // handlers for the xdg_shell protocol
 pub(super) mod handlers;
@@ -155,6 +167,7 @@ pub const XDG_POPUP_ROLE: &str = "xdg_popup";
 /// Constant for toplevel state version checking
 const XDG_TOPLEVEL_STATE_TILED_SINCE: u32 = 2;
 const XDG_TOPLEVEL_STATE_SUSPENDED_SINCE: u32 = 6;
+const XDG_TOPLEVEL_STATE_CONSTRAINED_SINCE: u32 = 7;
 
 macro_rules! xdg_role {
     ($state:ty,
@@ -372,9 +385,22 @@ xdg_role!(
         /// For D-Bus activatable applications, the app ID is used as the D-Bus
         /// service name.
         pub app_id: Option<String>,
+        /// Holds the optional untranslated tag the client has set
+        /// for this toplevel using [`xdg_toplevel_tag`](crate::wayland::xdg_toplevel_tag).
+        ///
+        /// Unlike the title the tag stays the same across restarts of the application,
+        /// which makes it suitable to match window rules against.
+        pub tag: Option<String>,
+        /// Holds the optional translated description of the tag
+        /// the client has set for this toplevel.
+        pub tag_description: Option<String>,
         /// An `zxdg_toplevel_decoration_v1::configure` event has been sent
         /// to the client.
-        pub initial_decoration_configure_sent: bool
+        pub initial_decoration_configure_sent: bool,
+        /// Time the last configure was sent to the client
+        last_configure_time: Option<Instant>,
+        /// Earliest time a configure held back by a throttled send may be sent
+        throttled_configure_deadline: Option<Instant>
     }
 );
 
@@ -939,56 +965,107 @@ impl ToplevelStateSet {
         }
     }
 
-    /// Filter the states according to the provided version
-    /// of the [`XdgToplevel`]
-    pub(crate) fn into_filtered_states(self, version: u32) -> Vec<xdg_toplevel::State> {
-        let tiled_supported = version >= XDG_TOPLEVEL_STATE_TILED_SINCE;
-        let suspended_supported = version >= XDG_TOPLEVEL_STATE_SUSPENDED_SINCE;
-
-        // If the client version supports the suspended states
-        // we can directly return the states which will save
-        // us from allocating another vector
-        if suspended_supported {
-            return self.states;
+    /// Sets the tiled states for the given edges and unsets them for all other edges.
+    ///
+    /// Tiled edges indicate that the window is placed next to other windows or the edge of
+    /// the output, so clients are expected to drop shadows or rounded corners on those edges.
+    pub fn set_tiled(&mut self, edges: ToplevelEdges) {
+        for (edge, state) in TILED_STATES {
+            if edges.contains(edge) {
+                self.set(state);
+            } else {
+                self.unset(state);
+            }
         }
+    }
 
-        let is_suspended = |state: &xdg_toplevel::State| *state == xdg_toplevel::State::Suspended;
-        let contains_suspended = self.states.contains(&xdg_toplevel::State::Suspended);
+    /// Returns the edges with a set tiled state
+    pub fn tiled(&self) -> ToplevelEdges {
+        TILED_STATES
+            .into_iter()
+            .filter(|(_, state)| self.contains(*state))
+            .fold(ToplevelEdges::empty(), |edges, (edge, _)| edges | edge)
+    }
 
-        // If tiled is supported and there is no suspend state there is nothing to filter out
-        if tiled_supported && !contains_suspended {
-            return self.states;
+    /// Sets the constrained states for the given edges and unsets them for all other edges.
+    ///
+    /// Constrained edges indicate that the window can't be resized from these edges,
+    /// e.g. because they are adjacent to other tiled windows.
+    ///
+    /// Requires xdg-shell version 7, the states are filtered out for older clients.
+    pub fn set_constrained(&mut self, edges: ToplevelEdges) {
+        for (edge, state) in CONSTRAINED_STATES {
+            if edges.contains(edge) {
+                self.set(state);
+            } else {
+                self.unset(state);
+            }
         }
+    }
 
-        let is_tiled = |state: &xdg_toplevel::State| {
-            matches!(
-                state,
-                xdg_toplevel::State::TiledTop
-                    | xdg_toplevel::State::TiledBottom
-                    | xdg_toplevel::State::TiledLeft
-                    | xdg_toplevel::State::TiledRight
-            )
+    /// Returns the edges with a set constrained state
+    pub fn constrained(&self) -> ToplevelEdges {
+        CONSTRAINED_STATES
+            .into_iter()
+            .filter(|(_, state)| self.contains(*state))
+            .fold(ToplevelEdges::empty(), |edges, (edge, _)| edges | edge)
+    }
+
+    /// Filter the states according to the provided version
+    /// of the [`XdgToplevel`]
+    pub(crate) fn into_filtered_states(self, version: u32) -> Vec<xdg_toplevel::State> {
+        let is_supported = |state: &xdg_toplevel::State| match state {
+            xdg_toplevel::State::TiledTop
+            | xdg_toplevel::State::TiledBottom
+            | xdg_toplevel::State::TiledLeft
+            | xdg_toplevel::State::TiledRight => version >= XDG_TOPLEVEL_STATE_TILED_SINCE,
+            xdg_toplevel::State::Suspended => version >= XDG_TOPLEVEL_STATE_SUSPENDED_SINCE,
+            xdg_toplevel::State::ConstrainedTop
+            | xdg_toplevel::State::ConstrainedBottom
+            | xdg_toplevel::State::ConstrainedLeft
+            | xdg_toplevel::State::ConstrainedRight => version >= XDG_TOPLEVEL_STATE_CONSTRAINED_SINCE,
+            _ => true,
         };
-        let contains_tiled = self.states.iter().any(is_tiled);
 
-        // If it does not contain unsupported values
-        if !contains_suspended && !contains_tiled {
+        // If there is nothing to filter out we can directly return the
+        // states which will save us from allocating another vector
+        if self.states.iter().all(is_supported) {
             return self.states;
         }
 
-        self.states
-            .into_iter()
-            .filter(|state| {
-                if tiled_supported {
-                    !is_suspended(state)
-                } else {
-                    !is_suspended(state) && !is_tiled(state)
-                }
-            })
-            .collect()
+        self.states.into_iter().filter(is_supported).collect()
+    }
+}
+
+bitflags::bitflags! {
+    /// Edges of a toplevel surface, used for the tiled and constrained states
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct ToplevelEdges: u32 {
+        /// The top edge
+        const TOP = 1;
+        /// The bottom edge
+        const BOTTOM = 2;
+        /// The left edge
+        const LEFT = 4;
+        /// The right edge
+        const RIGHT = 8;
     }
 }
 
+const TILED_STATES: [(ToplevelEdges, xdg_toplevel::State); 4] = [
+    (ToplevelEdges::TOP, xdg_toplevel::State::TiledTop),
+    (ToplevelEdges::BOTTOM, xdg_toplevel::State::TiledBottom),
+    (ToplevelEdges::LEFT, xdg_toplevel::State::TiledLeft),
+    (ToplevelEdges::RIGHT, xdg_toplevel::State::TiledRight),
+];
+
+const CONSTRAINED_STATES: [(ToplevelEdges, xdg_toplevel::State); 4] = [
+    (ToplevelEdges::TOP, xdg_toplevel::State::ConstrainedTop),
+    (ToplevelEdges::BOTTOM, xdg_toplevel::State::ConstrainedBottom),
+    (ToplevelEdges::LEFT, xdg_toplevel::State::ConstrainedLeft),
+    (ToplevelEdges::RIGHT, xdg_toplevel::State::ConstrainedRight),
+];
+
 impl IntoIterator for ToplevelStateSet {
     type Item = xdg_toplevel::State;
     type IntoIter = std::vec::IntoIter<Self::Item>;
@@ -1101,6 +1178,12 @@ pub trait XdgShellHandler {
     /// from the pending ping.
     fn client_pong(&mut self, client: ShellClient) {}
 
+    /// The responsiveness of a shell client changed
+    ///
+    /// Only called for automatic pings, see [`XdgShellState::start_pinging`].
+    /// A client is considered unresponsive, if it did not answer a ping until the next one is due.
+    fn client_responsiveness_changed(&mut self, client: ShellClient, responsive: bool) {}
+
     /// A new toplevel surface was created
     ///
     /// You likely need to send a [`ToplevelConfigure`] to the surface, to hint the
@@ -1218,6 +1301,7 @@ pub trait XdgShellHandler {
 /// currently known to the shell global.
 #[derive(Debug)]
 pub struct XdgShellState {
+    known_clients: Vec<xdg_wm_base::XdgWmBase>,
     known_toplevels: Vec<ToplevelSurface>,
     known_popups: Vec<PopupSurface>,
     default_capabilities: WmCapabilitySet,
@@ -1249,9 +1333,10 @@ impl XdgShellState {
     where
         D: GlobalDispatch<XdgWmBase, ()> + 'static,
     {
-        let global = display.create_global::<D, XdgWmBase, _>(6, ());
+        let global = display.create_global::<D, XdgWmBase, _>(7, ());
 
         XdgShellState {
+            known_clients: Vec::new(),
             known_toplevels: Vec::new(),
             known_popups: Vec::new(),
             default_capabilities: capabilities.into(),
@@ -1298,11 +1383,46 @@ impl XdgShellState {
     pub fn global(&self) -> GlobalId {
         self.global.clone()
     }
+
+    /// Periodically ping all shell clients
+    ///
+    /// Every `interval` a ping is sent to all clients without a pending ping.
+    /// Clients that did not answer the previous automatic ping until the next one is due are considered
+    /// unresponsive and reported via [`XdgShellHandler::client_responsiveness_changed`]
+    /// once, until they answer again.
+    ///
+    /// Pings sent manually via [`ShellClient::send_ping`] are not affected.
+    ///
+    /// Pinging stops, when the returned [`RegistrationToken`] is removed from the event loop.
+    pub fn start_pinging<D>(
+        &self,
+        loop_handle: &LoopHandle<'static, D>,
+        interval: Duration,
+    ) -> Result<RegistrationToken, InsertError<Timer>>
+    where
+        D: XdgShellHandler + 'static,
+    {
+        loop_handle.insert_source(Timer::from_duration(interval), move |_, _, state: &mut D| {
+            let unresponsive = state
+                .xdg_shell_state()
+                .known_clients
+                .iter()
+                .filter(|wm_base| ShellClient::new(wm_base).auto_ping())
+                .cloned()
+                .collect::<Vec<_>>();
+            for wm_base in unresponsive {
+                state.client_responsiveness_changed(ShellClient::new(&wm_base), false);
+            }
+            TimeoutAction::ToDuration(interval)
+        })
+    }
 }
 
 #[derive(Default, Debug)]
 pub(crate) struct ShellClientData {
     pending_ping: Option<Serial>,
+    auto_ping: Option<Serial>,
+    unresponsive: bool,
     data: UserDataMap,
 }
 
@@ -1365,6 +1485,39 @@ impl ShellClient {
         Ok(())
     }
 
+    /// Returns `false`, if the shell client did not answer an automatic ping in time
+    ///
+    /// See [`XdgShellState::start_pinging`].
+    pub fn is_responsive(&self) -> bool {
+        let user_data = self.kind.data::<self::handlers::XdgWmBaseUserData>().unwrap();
+        !user_data.client_data.lock().unwrap().unresponsive
+    }
+
+    // Sends an automatic ping, returns `true` if the client just became unresponsive
+    fn auto_ping(&self) -> bool {
+        if !self.alive() {
+            return false;
+        }
+        let user_data = self.kind.data::<self::handlers::XdgWmBaseUserData>().unwrap();
+        let mut guard = user_data.client_data.lock().unwrap();
+        match guard.pending_ping {
+            Some(serial) if guard.auto_ping == Some(serial) => {
+                let changed = !guard.unresponsive;
+                guard.unresponsive = true;
+                changed
+            }
+            // a manual ping is pending
+            Some(_) => false,
+            None => {
+                let serial = SERIAL_COUNTER.next_serial();
+                guard.pending_ping = Some(serial);
+                guard.auto_ping = Some(serial);
+                self.kind.ping(serial.into());
+                false
+            }
+        }
+    }
+
     /// Kill the shell client for being unresponsive.
     ///
     /// Generally this will be used if the client does not respond to a ping in a reasonable amount of time.
@@ -1479,6 +1632,62 @@ impl ToplevelSurface {
         }
     }
 
+    /// Send a pending configure event to this toplevel surface, limiting the rate of configure events
+    ///
+    /// Behaves like [`send_pending_configure`](ToplevelSurface::send_pending_configure), but does not send
+    /// a configure event if the last one was sent less than `min_interval` ago. The pending state is kept
+    /// in that case and further changes are coalesced into it, so the next call sends the latest state.
+    ///
+    /// This is useful to avoid flooding clients with configure events during interactive resizes.
+    /// A typical interval is the refresh interval of the output the surface is displayed on.
+    ///
+    /// A held back configure is not sent on its own. Call this again once the deadline returned by
+    /// [`next_configure_deadline`](ToplevelSurface::next_configure_deadline) has passed,
+    /// e.g. from a [`Timer`], to make sure the final state of a resize reaches the client.
+    pub fn send_pending_configure_throttled(&self, min_interval: Duration) -> Option<Serial> {
+        let throttled = compositor::with_states(&self.wl_surface, |states| {
+            let mut attributes = states
+                .data_map
+                .get::<XdgToplevelSurfaceData>()
+                .unwrap()
+                .lock()
+                .unwrap();
+            let deadline = attributes
+                .last_configure_time
+                .map(|time| time + min_interval)
+                .filter(|deadline| *deadline > Instant::now());
+            if deadline.is_some() && attributes.has_pending_changes() {
+                attributes.throttled_configure_deadline = deadline;
+            }
+            deadline.is_some()
+        });
+
+        if throttled {
+            None
+        } else {
+            self.send_pending_configure()
+        }
+    }
+
+    /// Returns the time a configure held back by
+    /// [`send_pending_configure_throttled`](ToplevelSurface::send_pending_configure_throttled) is due
+    ///
+    /// Returns `None` if no configure is held back, either because none was throttled
+    /// or because it was sent in the meantime.
+    pub fn next_configure_deadline(&self) -> Option<Instant> {
+        compositor::with_states(&self.wl_surface, |states| {
+            let attributes = states
+                .data_map
+                .get::<XdgToplevelSurfaceData>()
+                .unwrap()
+                .lock()
+                .unwrap();
+            attributes
+                .throttled_configure_deadline
+                .filter(|_| attributes.has_pending_changes())
+        })
+    }
+
     /// Send a configure event to this toplevel surface to suggest it a new configuration
     ///
     /// The serial of this configure will be tracked waiting for the client to ACK it.
@@ -1529,6 +1738,8 @@ impl ToplevelSurface {
 
                 attributes.pending_configures.push(configure.clone());
                 attributes.initial_configure_sent = true;
+                attributes.last_configure_time = Some(Instant::now());
+                attributes.throttled_configure_deadline = None;
                 if decoration.is_some() {
                     attributes.initial_decoration_configure_sent = true;
                 }
@@ -1750,6 +1961,21 @@ impl ToplevelSurface {
     pub fn parent(&self) -> Option<wl_surface::WlSurface> {
         handlers::get_parent(&self.shell_surface)
     }
+
+    /// Returns whether the client marked this toplevel as a modal dialog
+    ///
+    /// See [`dialog`] for the protocol providing this hint.
+    pub fn is_modal(&self) -> bool {
+        compositor::with_states(&self.wl_surface, |states| {
+            states
+                .data_map
+                .get::<XdgToplevelSurfaceData>()
+                .unwrap()
+                .lock()
+                .unwrap()
+                .modal
+        })
+    }
 }
 
 /// Represents the possible errors that
@@ -1965,8 +2191,8 @@ impl PopupSurface {
     ///
     /// This should be called when the underlying WlSurface
     /// handles a wl_surface.commit request.
-    pub(crate) fn pre_commit_hook<D: 'static>(
-        _state: &mut D,
+    pub(crate) fn pre_commit_hook<D: ProtocolErrorHandler + 'static>(
+        state: &mut D,
         _dh: &DisplayHandle,
         surface: &wl_surface::WlSurface,
     ) {
@@ -1985,7 +2211,10 @@ impl PopupSurface {
         });
         if let Some(handle) = send_error_to {
             let data = handle.data::<self::handlers::XdgShellSurfaceUserData>().unwrap();
-            data.xdg_surface.post_error(
+            post_error(
+                state,
+                &data.xdg_surface,
+                ProtocolErrorKind::Configure,
                 xdg_surface::Error::NotConstructed,
                 "Surface has not been configured yet.",
             );
@@ -2181,3 +2410,49 @@ macro_rules! delegate_xdg_shell {
         ] => $crate::wayland::shell::xdg::XdgShellState);
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wayland::test_utils::Fixture;
+
+    #[test]
+    fn throttled_configure_is_sent_after_deadline() {
+        let mut fixture = Fixture::new();
+        let (_, toplevel) = fixture.create_toplevel();
+        let interval = Duration::from_millis(50);
+
+        // the initial configure was just sent, so a resize is held back
+        toplevel.with_pending_state(|state| state.size = Some((100, 100).into()));
+        assert_eq!(toplevel.send_pending_configure_throttled(interval), None);
+        let deadline = toplevel.next_configure_deadline().unwrap();
+
+        // and further changes are coalesced into it
+        toplevel.with_pending_state(|state| state.size = Some((200, 150).into()));
+        assert_eq!(toplevel.send_pending_configure_throttled(interval), None);
+        assert_eq!(toplevel.next_configure_deadline(), Some(deadline));
+
+        std::thread::sleep(deadline.saturating_duration_since(Instant::now()));
+        let serial = toplevel.send_pending_configure_throttled(interval).unwrap();
+        assert_eq!(toplevel.next_configure_deadline(), None);
+        fixture.roundtrip();
+
+        let last_acked = compositor::with_states(toplevel.wl_surface(), |states| {
+            let attributes = states
+                .data_map
+                .get::<XdgToplevelSurfaceData>()
+                .unwrap()
+                .lock()
+                .unwrap();
+            (
+                attributes.configure_serial,
+                attributes.last_acked.as_ref().unwrap().size,
+            )
+        });
+        assert_eq!(last_acked, (Some(serial), Some((200, 150).into())));
+
+        // nothing is held back without pending changes
+        assert_eq!(toplevel.send_pending_configure_throttled(interval), None);
+        assert_eq!(toplevel.next_configure_deadline(), None);
+    }
+}
@@ -0,0 +1,397 @@
+//! Synchronized configures across multiple toplevels
+//!
+//! When resizing multiple windows at once, e.g. both sides of a tiling split, clients
+//! will commit their new buffers at different times, causing visible seams or overlaps for a few frames.
+//!
+//! A [`ConfigureTransaction`] groups the configures sent to multiple toplevels.
+//! Commits acknowledging these configures are held back using a [`Blocker`](crate::wayland::compositor::Blocker)
+//! until every toplevel of the transaction committed its new state, or a timeout expires,
+//! so all new buffers are latched together. The held back commits are applied from the event loop,
+//! once the last participating commit was processed.
+//!
+//! ```no_run
+//! # use std::time::Duration;
+//! # use smithay::wayland::shell::xdg::{ToplevelSurface, transaction::ConfigureTransaction};
+//! # use smithay::wayland::compositor::{CompositorHandler, CompositorState, CompositorClientState};
+//! # use smithay::reexports::wayland_server::{Client, protocol::wl_surface::WlSurface};
+//! # struct State;
+//! # impl CompositorHandler for State {
+//! #     fn compositor_state(&mut self) -> &mut CompositorState { unimplemented!() }
+//! #     fn client_compositor_state<'a>(&self, client: &'a Client) -> &'a CompositorClientState { unimplemented!() }
+//! #     fn commit(&mut self, surface: &WlSurface) {}
+//! # }
+//! # let event_loop = smithay::reexports::calloop::EventLoop::<State>::try_new().unwrap();
+//! # let display = smithay::reexports::wayland_server::Display::<State>::new().unwrap();
+//! # let (left, right): (ToplevelSurface, ToplevelSurface) = unimplemented!();
+//! let transaction = ConfigureTransaction::new(&display.handle(), &event_loop.handle()).unwrap();
+//! for (toplevel, width) in [(&left, 600), (&right, 400)] {
+//!     toplevel.with_pending_state(|state| state.size = Some((width, 800).into()));
+//!     let serial = toplevel.send_configure();
+//!     transaction.add_toplevel::<State>(toplevel, serial);
+//! }
+//! transaction
+//!     .set_timeout(&display.handle(), &event_loop.handle(), Duration::from_millis(150))
+//!     .unwrap();
+//! ```
+
+use std::{
+    cell::Cell,
+    rc::Rc,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use calloop::{
+    ping::{make_ping, Ping},
+    timer::{TimeoutAction, Timer},
+    InsertError, LoopHandle, RegistrationToken,
+};
+use wayland_server::{backend::ClientId, protocol::wl_surface::WlSurface, DisplayHandle, Resource, Weak};
+
+use crate::{
+    utils::Serial,
+    wayland::compositor::{
+        add_blocker, add_destruction_hook, add_pre_commit_hook, remove_destruction_hook,
+        remove_pre_commit_hook, with_states, Barrier, CompositorHandler, HookId,
+    },
+};
+
+use super::{ToplevelSurface, XdgToplevelSurfaceData};
+
+#[derive(Debug)]
+struct Participant {
+    surface: Weak<WlSurface>,
+    serial: Serial,
+    commit_hook: HookId,
+    destruction_hook: HookId,
+    committed: bool,
+}
+
+#[derive(Debug, Default)]
+struct TransactionState {
+    participants: Vec<Participant>,
+    finished: bool,
+}
+
+/// A group of toplevel configures, whose resulting commits are applied together
+///
+/// See the [module-level documentation](self) for more information.
+#[derive(Debug, Clone)]
+pub struct ConfigureTransaction {
+    state: Arc<Mutex<TransactionState>>,
+    barrier: Barrier,
+    ping: Ping,
+}
+
+impl ConfigureTransaction {
+    /// Create a new empty transaction
+    ///
+    /// Once every toplevel committed its new state, the transaction is released
+    /// from an idle callback of the provided event loop instead of the commit handler of the last toplevel.
+    pub fn new<D>(dh: &DisplayHandle, loop_handle: &LoopHandle<'static, D>) -> Result<Self, calloop::Error>
+    where
+        D: CompositorHandler + 'static,
+    {
+        let (ping, source) = make_ping()?;
+        let transaction = ConfigureTransaction {
+            state: Arc::new(Mutex::new(TransactionState::default())),
+            barrier: Barrier::new(false),
+            ping,
+        };
+
+        let token = Rc::new(Cell::new(None));
+        let handle = loop_handle.clone();
+        let dh = dh.clone();
+        let released = transaction.clone();
+        let source_token = token.clone();
+        let registration = loop_handle
+            .insert_source(source, move |_, _, _| {
+                if let Some(token) = source_token.take() {
+                    handle.remove(token);
+                }
+                let released = released.clone();
+                let dh = dh.clone();
+                handle.insert_idle(move |state| released.finish(state, &dh));
+            })
+            .map_err(|err| err.error)?;
+        token.set(Some(registration));
+
+        Ok(transaction)
+    }
+
+    /// Add a toplevel to this transaction
+    ///
+    /// `serial` is the serial of the configure sent to the toplevel, which the client has to
+    /// acknowledge with the commit that is part of this transaction.
+    ///
+    /// All toplevels should be added before the client connections are flushed,
+    /// otherwise the transaction might finish before all toplevels were added.
+    pub fn add_toplevel<D>(&self, toplevel: &ToplevelSurface, serial: Serial)
+    where
+        D: CompositorHandler + 'static,
+    {
+        let surface = toplevel.wl_surface();
+        let mut state = self.state.lock().unwrap();
+        if state.finished {
+            return;
+        }
+
+        let transaction = self.clone();
+        let commit_hook =
+            add_pre_commit_hook::<D, _>(surface, move |_, _, surface| transaction.pre_commit(surface));
+        let transaction = self.clone();
+        let destruction_hook =
+            add_destruction_hook::<D, _>(surface, move |_, surface| transaction.destroyed(surface));
+
+        state.participants.push(Participant {
+            surface: surface.downgrade(),
+            serial,
+            commit_hook,
+            destruction_hook,
+            committed: false,
+        });
+    }
+
+    /// Returns `true` once every toplevel committed its new state or the transaction timed out
+    pub fn is_finished(&self) -> bool {
+        self.state.lock().unwrap().finished
+    }
+
+    /// Release the transaction after a timeout, even if not every toplevel has committed yet
+    ///
+    /// This avoids unresponsive clients blocking the updates of the remaining toplevels forever.
+    pub fn set_timeout<D>(
+        &self,
+        dh: &DisplayHandle,
+        loop_handle: &LoopHandle<'static, D>,
+        timeout: Duration,
+    ) -> Result<RegistrationToken, InsertError<Timer>>
+    where
+        D: CompositorHandler + 'static,
+    {
+        let transaction = self.clone();
+        let dh = dh.clone();
+        loop_handle.insert_source(Timer::from_duration(timeout), move |_, _, state| {
+            transaction.finish(state, &dh);
+            TimeoutAction::Drop
+        })
+    }
+
+    /// Release the transaction immediately
+    ///
+    /// Commits that were held back by this transaction are applied.
+    pub fn release<D>(&self, state: &mut D, dh: &DisplayHandle)
+    where
+        D: CompositorHandler + 'static,
+    {
+        self.finish(state, dh);
+    }
+
+    fn pre_commit(&self, surface: &WlSurface) {
+        let acked = with_states(surface, |states| {
+            states
+                .data_map
+                .get::<XdgToplevelSurfaceData>()
+                .and_then(|data| data.lock().unwrap().configure_serial)
+        });
+
+        let mut guard = self.state.lock().unwrap();
+        if guard.finished {
+            return;
+        }
+        let Some(participant) = guard
+            .participants
+            .iter_mut()
+            .find(|participant| participant.surface == *surface)
+        else {
+            return;
+        };
+        if participant.committed || !acked.is_some_and(|acked| acked >= participant.serial) {
+            return;
+        }
+
+        participant.committed = true;
+        add_blocker(surface, self.barrier.clone());
+        self.schedule_release(&guard);
+    }
+
+    fn destroyed(&self, surface: &WlSurface) {
+        let mut guard = self.state.lock().unwrap();
+        if guard.finished {
+            return;
+        }
+        // a destroyed toplevel will never commit, so it must not hold back the others
+        if let Some(participant) = guard
+            .participants
+            .iter_mut()
+            .find(|participant| participant.surface == *surface)
+        {
+            participant.committed = true;
+        }
+        self.schedule_release(&guard);
+    }
+
+    // The blockers are cleared from the event loop, as clearing them from inside
+    // a pre-commit hook would re-enter the commit handling of the committing client.
+    fn schedule_release(&self, state: &TransactionState) {
+        let done = state
+            .participants
+            .iter()
+            .all(|participant| participant.committed || participant.surface.upgrade().is_err());
+        if done {
+            self.ping.ping();
+        }
+    }
+
+    fn finish<D>(&self, state: &mut D, dh: &DisplayHandle)
+    where
+        D: CompositorHandler + 'static,
+    {
+        let participants = {
+            let mut guard = self.state.lock().unwrap();
+            if guard.finished {
+                return;
+            }
+            guard.finished = true;
+            std::mem::take(&mut guard.participants)
+        };
+        self.barrier.signal();
+        // also unregisters the ping source, if the transaction was released otherwise
+        self.ping.ping();
+
+        let mut clients: Vec<ClientId> = Vec::new();
+        for participant in participants {
+            let Ok(surface) = participant.surface.upgrade() else {
+                continue;
+            };
+            remove_pre_commit_hook(&surface, participant.commit_hook);
+            remove_destruction_hook(&surface, participant.destruction_hook);
+            if let Some(client) = surface.client() {
+                if !clients.contains(&client.id()) {
+                    clients.push(client.id());
+                    state.client_compositor_state(&client).blocker_cleared(state, dh);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use calloop::EventLoop;
+    use wayland_protocols::xdg::shell::client::xdg_wm_base::XdgWmBase;
+
+    use super::*;
+    use crate::wayland::test_utils::{Fixture, TestState};
+
+    fn resize(transaction: &ConfigureTransaction, toplevel: &ToplevelSurface, width: i32) {
+        toplevel.with_pending_state(|state| state.size = Some((width, 100).into()));
+        let serial = toplevel.send_configure();
+        transaction.add_toplevel::<TestState>(toplevel, serial);
+    }
+
+    #[test]
+    fn toplevels_latch_together() {
+        let mut fixture = Fixture::new();
+        let mut event_loop = EventLoop::<TestState>::try_new().unwrap();
+        let (left_surface, left) = fixture.create_toplevel();
+        let (right_surface, right) = fixture.create_toplevel();
+
+        let transaction = ConfigureTransaction::new(&fixture.display.handle(), &event_loop.handle()).unwrap();
+        resize(&transaction, &left, 600);
+        resize(&transaction, &right, 400);
+        fixture.roundtrip();
+        fixture.state.commits.clear();
+
+        left_surface.commit();
+        fixture.roundtrip();
+        assert!(fixture.state.commits.is_empty());
+
+        // the last commit only schedules the release
+        right_surface.commit();
+        fixture.roundtrip();
+        assert!(fixture.state.commits.is_empty());
+        assert!(!transaction.is_finished());
+
+        event_loop
+            .dispatch(Some(Duration::ZERO), &mut fixture.state)
+            .unwrap();
+        assert!(transaction.is_finished());
+        assert_eq!(fixture.state.commits.len(), 2);
+        assert!(fixture.state.commits.contains(left.wl_surface()));
+        assert!(fixture.state.commits.contains(right.wl_surface()));
+    }
+
+    #[test]
+    fn destroyed_toplevel_does_not_block() {
+        let mut fixture = Fixture::new();
+        let mut event_loop = EventLoop::<TestState>::try_new().unwrap();
+        let (left_surface, left) = fixture.create_toplevel();
+
+        let (surface, _) = fixture.create_surface();
+        let wm_base: XdgWmBase = fixture.bind(6);
+        let xdg_surface = wm_base.get_xdg_surface(&surface, &fixture.handle(), ());
+        let xdg_toplevel = xdg_surface.get_toplevel(&fixture.handle(), ());
+        surface.commit();
+        fixture.roundtrip();
+        let right = fixture.state.toplevels.last().unwrap().clone();
+
+        let transaction = ConfigureTransaction::new(&fixture.display.handle(), &event_loop.handle()).unwrap();
+        resize(&transaction, &left, 600);
+        resize(&transaction, &right, 400);
+        fixture.roundtrip();
+        fixture.state.commits.clear();
+
+        left_surface.commit();
+        fixture.roundtrip();
+        assert!(fixture.state.commits.is_empty());
+
+        xdg_toplevel.destroy();
+        xdg_surface.destroy();
+        surface.destroy();
+        fixture.roundtrip();
+        event_loop
+            .dispatch(Some(Duration::ZERO), &mut fixture.state)
+            .unwrap();
+        assert!(transaction.is_finished());
+        assert_eq!(fixture.state.commits, vec![left.wl_surface().clone()]);
+    }
+
+    #[test]
+    fn timeout_releases_committed_toplevels() {
+        let mut fixture = Fixture::new();
+        let mut event_loop = EventLoop::<TestState>::try_new().unwrap();
+        let (left_surface, left) = fixture.create_toplevel();
+        let (_, right) = fixture.create_toplevel();
+
+        let transaction = ConfigureTransaction::new(&fixture.display.handle(), &event_loop.handle()).unwrap();
+        resize(&transaction, &left, 600);
+        resize(&transaction, &right, 400);
+        transaction
+            .set_timeout(
+                &fixture.display.handle(),
+                &event_loop.handle(),
+                Duration::from_millis(10),
+            )
+            .unwrap();
+        fixture.roundtrip();
+        fixture.state.commits.clear();
+
+        // the right toplevel never commits
+        left_surface.commit();
+        fixture.roundtrip();
+        assert!(fixture.state.commits.is_empty());
+
+        while !transaction.is_finished() {
+            event_loop
+                .dispatch(Some(Duration::from_millis(100)), &mut fixture.state)
+                .unwrap();
+        }
+        assert_eq!(fixture.state.commits, vec![left.wl_surface().clone()]);
+
+        // later commits are no longer held back
+        left_surface.commit();
+        fixture.roundtrip();
+        assert_eq!(fixture.state.commits.len(), 2);
+    }
+}
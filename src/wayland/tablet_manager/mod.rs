@@ -56,6 +56,7 @@
 //!     }
 //! }
 //! delegate_seat!(State);
+//! # impl smithay::wayland::protocol_error::ProtocolErrorHandler for State {}
 //!
 //! impl TabletSeatHandler for State {
 //!     fn tablet_tool_image(&mut self, tool: &TabletToolDescriptor, image: CursorImageStatus) {
@@ -6,6 +6,7 @@ use crate::backend::input::{ButtonState, TabletToolCapabilities, TabletToolDescr
 use crate::input::pointer::{CursorImageAttributes, CursorImageStatus};
 use crate::utils::{Client as ClientCoords, Logical, Point};
 use crate::wayland::compositor::CompositorHandler;
+use crate::wayland::protocol_error::{post_error, ProtocolErrorHandler, ProtocolErrorKind};
 use crate::wayland::seat::CURSOR_IMAGE_ROLE;
 use atomic_float::AtomicF64;
 use wayland_protocols::wp::tablet::zv2::server::{
@@ -469,7 +470,7 @@ impl fmt::Debug for TabletToolUserData {
 impl<D> Dispatch<ZwpTabletToolV2, TabletToolUserData, D> for TabletManagerState
 where
     D: Dispatch<ZwpTabletToolV2, TabletToolUserData>,
-    D: TabletSeatHandler + 'static,
+    D: TabletSeatHandler + ProtocolErrorHandler + 'static,
 {
     fn request(
         state: &mut D,
@@ -496,7 +497,10 @@ where
                             if compositor::give_role(&surface, CURSOR_IMAGE_ROLE).is_err()
                                 && compositor::get_role(&surface) != Some(CURSOR_IMAGE_ROLE)
                             {
-                                tool.post_error(
+                                post_error(
+                                    state,
+                                    tool,
+                                    ProtocolErrorKind::Role,
                                     zwp_tablet_tool_v2::Error::Role,
                                     "Given wl_surface has another role.",
                                 );
@@ -0,0 +1,497 @@
+// A compositor and a client connected through a socket pair, for tests of the protocol implementations.
+
+use std::{
+    io::ErrorKind,
+    os::unix::{io::AsFd, net::UnixStream},
+    sync::Arc,
+};
+
+use rustix::fs::{ftruncate, memfd_create, MemfdFlags};
+use wayland_client::{
+    backend::{protocol::ProtocolError, WaylandError},
+    delegate_noop,
+    protocol::{
+        wl_buffer, wl_callback, wl_compositor, wl_output, wl_registry, wl_shm, wl_shm_pool, wl_subcompositor,
+        wl_subsurface, wl_surface,
+    },
+    Connection, Dispatch as ClientDispatch, EventQueue, Proxy, QueueHandle,
+};
+#[cfg(feature = "libwayland_server_1_22")]
+use wayland_protocols::ext::transient_seat::v1::client::{
+    ext_transient_seat_manager_v1, ext_transient_seat_v1,
+};
+use wayland_protocols::{
+    wp::{
+        color_representation::v1::{
+            client::{wp_color_representation_manager_v1, wp_color_representation_surface_v1},
+            server::wp_color_representation_surface_v1::{AlphaMode, Coefficients, Range},
+        },
+        viewporter::client::{wp_viewport, wp_viewporter},
+    },
+    xdg::shell::client::{xdg_surface, xdg_toplevel, xdg_wm_base},
+};
+use wayland_protocols_wlr::screencopy::v1::client::{zwlr_screencopy_frame_v1, zwlr_screencopy_manager_v1};
+use wayland_server::{
+    backend::ClientData,
+    protocol::{wl_buffer::WlBuffer, wl_seat::WlSeat, wl_surface::WlSurface},
+    Client, Display, Resource,
+};
+
+use crate::{
+    backend::renderer::utils::on_commit_buffer_handler,
+    input::{keyboard::ModifiersState, pointer::CursorImageStatus, Seat, SeatHandler, SeatState},
+    utils::Serial,
+    wayland::{
+        buffer::BufferHandler,
+        color_representation::ColorRepresentationState,
+        compositor::{CompositorClientState, CompositorHandler, CompositorState},
+        output::OutputHandler,
+        protocol_error::ProtocolErrorHandler,
+        screencopy::{Screencopy, ScreencopyFormats, ScreencopyHandler, ScreencopyManagerState},
+        selection::{
+            data_device::{ClientDndGrabHandler, DataDeviceHandler, DataDeviceState, ServerDndGrabHandler},
+            SelectionHandler,
+        },
+        shell::xdg::{PopupSurface, PositionerState, ToplevelSurface, XdgShellHandler, XdgShellState},
+        shm::{ShmHandler, ShmState},
+        viewporter::ViewporterState,
+    },
+};
+
+pub(crate) struct TestState {
+    pub compositor_state: CompositorState,
+    pub seat_state: SeatState<Self>,
+    pub xdg_shell_state: XdgShellState,
+    pub shm_state: ShmState,
+    pub data_device_state: DataDeviceState,
+    pub screencopy_state: ScreencopyManagerState,
+    pub screencopy_formats: ScreencopyFormats,
+    pub screencopies: Vec<Screencopy>,
+    pub commits: Vec<WlSurface>,
+    pub toplevels: Vec<ToplevelSurface>,
+    pub client_dnd_modifiers: Vec<ModifiersState>,
+    pub server_dnd_modifiers: Vec<ModifiersState>,
+}
+
+#[derive(Default)]
+struct ClientState {
+    compositor_state: CompositorClientState,
+}
+
+impl ClientData for ClientState {}
+
+impl CompositorHandler for TestState {
+    fn compositor_state(&mut self) -> &mut CompositorState {
+        &mut self.compositor_state
+    }
+    fn client_compositor_state<'a>(&self, client: &'a Client) -> &'a CompositorClientState {
+        &client.get_data::<ClientState>().unwrap().compositor_state
+    }
+    fn commit(&mut self, surface: &WlSurface) {
+        on_commit_buffer_handler::<Self>(surface);
+        self.commits.push(surface.clone());
+    }
+}
+
+impl XdgShellHandler for TestState {
+    fn xdg_shell_state(&mut self) -> &mut XdgShellState {
+        &mut self.xdg_shell_state
+    }
+    fn new_toplevel(&mut self, surface: ToplevelSurface) {
+        surface.send_configure();
+        self.toplevels.push(surface);
+    }
+    fn new_popup(&mut self, _surface: PopupSurface, _positioner: PositionerState) {}
+    fn grab(&mut self, _surface: PopupSurface, _seat: WlSeat, _serial: Serial) {}
+    fn reposition_request(&mut self, _surface: PopupSurface, _positioner: PositionerState, _token: u32) {}
+}
+
+impl BufferHandler for TestState {
+    fn buffer_destroyed(&mut self, _buffer: &WlBuffer) {}
+}
+
+impl ShmHandler for TestState {
+    fn shm_state(&self) -> &ShmState {
+        &self.shm_state
+    }
+}
+
+impl SeatHandler for TestState {
+    type KeyboardFocus = WlSurface;
+    type PointerFocus = WlSurface;
+    type TouchFocus = WlSurface;
+
+    fn seat_state(&mut self) -> &mut SeatState<Self> {
+        &mut self.seat_state
+    }
+    fn cursor_image(&mut self, _seat: &Seat<Self>, _image: CursorImageStatus) {}
+}
+
+impl SelectionHandler for TestState {
+    type SelectionUserData = ();
+}
+
+impl DataDeviceHandler for TestState {
+    fn data_device_state(&self) -> &DataDeviceState {
+        &self.data_device_state
+    }
+}
+
+impl ClientDndGrabHandler for TestState {
+    fn modifiers_changed(&mut self, modifiers: ModifiersState, _seat: Seat<Self>) {
+        self.client_dnd_modifiers.push(modifiers);
+    }
+}
+
+impl ServerDndGrabHandler for TestState {
+    fn modifiers_changed(&mut self, modifiers: ModifiersState, _seat: Seat<Self>) {
+        self.server_dnd_modifiers.push(modifiers);
+    }
+}
+
+impl ScreencopyHandler for TestState {
+    fn screencopy_state(&mut self) -> &mut ScreencopyManagerState {
+        &mut self.screencopy_state
+    }
+    fn buffer_formats(&mut self, _output: &crate::output::Output) -> ScreencopyFormats {
+        self.screencopy_formats
+    }
+    fn frame(&mut self, frame: Screencopy) {
+        self.screencopies.push(frame);
+    }
+}
+
+impl OutputHandler for TestState {}
+impl ProtocolErrorHandler for TestState {}
+
+crate::delegate_compositor!(TestState);
+crate::delegate_seat!(TestState);
+crate::delegate_output!(TestState);
+crate::delegate_xdg_shell!(TestState);
+crate::delegate_shm!(TestState);
+crate::delegate_viewporter!(TestState);
+crate::delegate_data_device!(TestState);
+crate::delegate_color_representation!(TestState);
+crate::delegate_screencopy!(TestState);
+
+#[cfg(feature = "libwayland_server_1_22")]
+impl crate::wayland::transient_seat::TransientSeatHandler for TestState {}
+#[cfg(feature = "libwayland_server_1_22")]
+crate::delegate_transient_seat!(TestState);
+
+// Events received by the client
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum ClientEvent {
+    PreferredBufferScale(u32, i32),
+    FrameDone(u32),
+    ScreencopyBuffer(wl_shm::Format, u32, u32, u32),
+    ScreencopyLinuxDmabuf(u32, u32, u32),
+    ScreencopyBufferDone,
+    ScreencopyReady,
+    ScreencopyFailed,
+    #[cfg(feature = "libwayland_server_1_22")]
+    TransientSeatReady(u32),
+}
+
+#[derive(Default)]
+pub(crate) struct TestClient {
+    pub globals: Vec<(u32, String, u32)>,
+    pub events: Vec<ClientEvent>,
+}
+
+impl ClientDispatch<wl_registry::WlRegistry, ()> for TestClient {
+    fn event(
+        state: &mut Self,
+        _: &wl_registry::WlRegistry,
+        event: wl_registry::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let wl_registry::Event::Global {
+            name,
+            interface,
+            version,
+        } = event
+        {
+            state.globals.push((name, interface, version));
+        }
+    }
+}
+
+delegate_noop!(TestClient: wl_compositor::WlCompositor);
+delegate_noop!(TestClient: wl_subcompositor::WlSubcompositor);
+delegate_noop!(TestClient: wl_subsurface::WlSubsurface);
+delegate_noop!(TestClient: wl_shm_pool::WlShmPool);
+delegate_noop!(TestClient: ignore wl_shm::WlShm);
+delegate_noop!(TestClient: ignore wl_buffer::WlBuffer);
+delegate_noop!(TestClient: ignore xdg_toplevel::XdgToplevel);
+delegate_noop!(TestClient: wp_viewporter::WpViewporter);
+delegate_noop!(TestClient: wp_viewport::WpViewport);
+delegate_noop!(TestClient: ignore wp_color_representation_manager_v1::WpColorRepresentationManagerV1);
+delegate_noop!(TestClient: wp_color_representation_surface_v1::WpColorRepresentationSurfaceV1);
+delegate_noop!(TestClient: ignore wl_output::WlOutput);
+delegate_noop!(TestClient: zwlr_screencopy_manager_v1::ZwlrScreencopyManagerV1);
+
+#[cfg(feature = "libwayland_server_1_22")]
+delegate_noop!(TestClient: ext_transient_seat_manager_v1::ExtTransientSeatManagerV1);
+
+#[cfg(feature = "libwayland_server_1_22")]
+impl ClientDispatch<ext_transient_seat_v1::ExtTransientSeatV1, ()> for TestClient {
+    fn event(
+        state: &mut Self,
+        _: &ext_transient_seat_v1::ExtTransientSeatV1,
+        event: ext_transient_seat_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let ext_transient_seat_v1::Event::Ready { global_name } = event {
+            state.events.push(ClientEvent::TransientSeatReady(global_name));
+        }
+    }
+}
+
+impl ClientDispatch<xdg_wm_base::XdgWmBase, ()> for TestClient {
+    fn event(
+        _: &mut Self,
+        wm_base: &xdg_wm_base::XdgWmBase,
+        event: xdg_wm_base::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let xdg_wm_base::Event::Ping { serial } = event {
+            wm_base.pong(serial);
+        }
+    }
+}
+
+impl ClientDispatch<xdg_surface::XdgSurface, ()> for TestClient {
+    fn event(
+        _: &mut Self,
+        xdg_surface: &xdg_surface::XdgSurface,
+        event: xdg_surface::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let xdg_surface::Event::Configure { serial } = event {
+            xdg_surface.ack_configure(serial);
+        }
+    }
+}
+
+impl ClientDispatch<wl_surface::WlSurface, ()> for TestClient {
+    fn event(
+        state: &mut Self,
+        surface: &wl_surface::WlSurface,
+        event: wl_surface::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let wl_surface::Event::PreferredBufferScale { factor } = event {
+            let id = surface.id().protocol_id();
+            state.events.push(ClientEvent::PreferredBufferScale(id, factor));
+        }
+    }
+}
+
+impl ClientDispatch<wl_callback::WlCallback, u32> for TestClient {
+    fn event(
+        state: &mut Self,
+        _: &wl_callback::WlCallback,
+        event: wl_callback::Event,
+        surface: &u32,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let wl_callback::Event::Done { .. } = event {
+            state.events.push(ClientEvent::FrameDone(*surface));
+        }
+    }
+}
+
+impl ClientDispatch<zwlr_screencopy_frame_v1::ZwlrScreencopyFrameV1, ()> for TestClient {
+    fn event(
+        state: &mut Self,
+        _: &zwlr_screencopy_frame_v1::ZwlrScreencopyFrameV1,
+        event: zwlr_screencopy_frame_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let event = match event {
+            zwlr_screencopy_frame_v1::Event::Buffer {
+                format,
+                width,
+                height,
+                stride,
+            } => ClientEvent::ScreencopyBuffer(format.into_result().unwrap(), width, height, stride),
+            zwlr_screencopy_frame_v1::Event::LinuxDmabuf {
+                format,
+                width,
+                height,
+            } => ClientEvent::ScreencopyLinuxDmabuf(format, width, height),
+            zwlr_screencopy_frame_v1::Event::BufferDone => ClientEvent::ScreencopyBufferDone,
+            zwlr_screencopy_frame_v1::Event::Ready { .. } => ClientEvent::ScreencopyReady,
+            zwlr_screencopy_frame_v1::Event::Failed => ClientEvent::ScreencopyFailed,
+            _ => return,
+        };
+        state.events.push(event);
+    }
+}
+
+pub(crate) struct Fixture {
+    pub display: Display<TestState>,
+    pub state: TestState,
+    pub client: TestClient,
+    server_client: Client,
+    connection: Connection,
+    queue: EventQueue<TestClient>,
+    registry: wl_registry::WlRegistry,
+}
+
+impl Fixture {
+    pub fn new() -> Self {
+        let display = Display::<TestState>::new().unwrap();
+        let mut dh = display.handle();
+        ViewporterState::new::<TestState>(&dh);
+        ColorRepresentationState::new::<TestState>(
+            &dh,
+            [AlphaMode::PremultipliedElectrical],
+            [
+                (Coefficients::Bt709, Range::Limited),
+                (Coefficients::Bt709, Range::Full),
+            ],
+        );
+        let state = TestState {
+            compositor_state: CompositorState::new_v6::<TestState>(&dh),
+            seat_state: SeatState::new(),
+            xdg_shell_state: XdgShellState::new::<TestState>(&dh),
+            shm_state: ShmState::new::<TestState>(&dh, Vec::new()),
+            data_device_state: DataDeviceState::new::<TestState>(&dh),
+            screencopy_state: ScreencopyManagerState::new::<TestState, _>(&dh, |_| true),
+            screencopy_formats: ScreencopyFormats::default(),
+            screencopies: Vec::new(),
+            commits: Vec::new(),
+            toplevels: Vec::new(),
+            client_dnd_modifiers: Vec::new(),
+            server_dnd_modifiers: Vec::new(),
+        };
+
+        let (server, client) = UnixStream::pair().unwrap();
+        let server_client = dh
+            .insert_client(server, Arc::new(ClientState::default()))
+            .unwrap();
+        let connection = Connection::from_socket(client).unwrap();
+        let queue = connection.new_event_queue();
+        let registry = connection.display().get_registry(&queue.handle(), ());
+
+        let mut fixture = Fixture {
+            display,
+            state,
+            client: TestClient::default(),
+            server_client,
+            connection,
+            queue,
+            registry,
+        };
+        fixture.roundtrip();
+        fixture
+    }
+
+    pub fn handle(&self) -> QueueHandle<TestClient> {
+        self.queue.handle()
+    }
+
+    // Exchanges requests and events until both sides are idle
+    pub fn roundtrip(&mut self) {
+        for _ in 0..3 {
+            // the client was killed by a protocol error
+            if self.connection.protocol_error().is_some() {
+                return;
+            }
+            self.queue.flush().unwrap();
+            self.display.dispatch_clients(&mut self.state).unwrap();
+            self.display.flush_clients().unwrap();
+            if let Some(guard) = self.connection.prepare_read() {
+                match guard.read() {
+                    Ok(_) => {}
+                    Err(WaylandError::Io(err)) if err.kind() == ErrorKind::WouldBlock => {}
+                    Err(WaylandError::Protocol(_)) => return,
+                    Err(err) => panic!("failed to read events: {err}"),
+                }
+            }
+            self.queue.dispatch_pending(&mut self.client).unwrap();
+        }
+    }
+
+    // Binds a global announced since the last roundtrip
+    pub fn bind<I>(&mut self, version: u32) -> I
+    where
+        I: Proxy + 'static,
+        TestClient: ClientDispatch<I, ()>,
+    {
+        let (name, _, _) = self
+            .client
+            .globals
+            .iter()
+            .rev()
+            .find(|(_, interface, _)| interface == I::interface().name)
+            .expect("global not announced");
+        self.registry.bind(*name, version, &self.queue.handle(), ())
+    }
+
+    // Returns the server side object of a proxy
+    pub fn server_object<R: Resource + 'static>(&self, proxy: &impl Proxy) -> R {
+        self.server_client
+            .object_from_protocol_id(&self.display.handle(), proxy.id().protocol_id())
+            .unwrap()
+    }
+
+    // Creates a surface on the client side and returns it with its server side object
+    pub fn create_surface(&mut self) -> (wl_surface::WlSurface, WlSurface) {
+        let compositor: wl_compositor::WlCompositor = self.bind(6);
+        let surface = compositor.create_surface(&self.handle(), ());
+        self.roundtrip();
+        let server = self.server_object(&surface);
+        (surface, server)
+    }
+
+    // Creates an xdg toplevel, which already acked its initial configure
+    pub fn create_toplevel(&mut self) -> (wl_surface::WlSurface, ToplevelSurface) {
+        let (surface, _) = self.create_surface();
+        let wm_base: xdg_wm_base::XdgWmBase = self.bind(6);
+        let xdg_surface = wm_base.get_xdg_surface(&surface, &self.handle(), ());
+        xdg_surface.get_toplevel(&self.handle(), ());
+        surface.commit();
+        self.roundtrip();
+        let toplevel = self.state.toplevels.last().unwrap().clone();
+        (surface, toplevel)
+    }
+
+    // Returns the error, the server killed the client with
+    pub fn protocol_error(&self) -> Option<ProtocolError> {
+        self.connection.protocol_error()
+    }
+
+    // Attaches a new argb8888 shm buffer of the given size to the surface
+    pub fn attach_buffer(&mut self, surface: &wl_surface::WlSurface, width: i32, height: i32) {
+        let buffer = self.create_buffer(width, height, wl_shm::Format::Argb8888);
+        surface.attach(Some(&buffer), 0, 0);
+        surface.damage_buffer(0, 0, width, height);
+    }
+
+    // Creates a new shm buffer of the given size with a 32bpp format
+    pub fn create_buffer(&mut self, width: i32, height: i32, format: wl_shm::Format) -> wl_buffer::WlBuffer {
+        let stride = width * 4;
+        let fd = memfd_create("test-buffer", MemfdFlags::CLOEXEC).unwrap();
+        ftruncate(&fd, (stride * height) as u64).unwrap();
+
+        let shm: wl_shm::WlShm = self.bind(1);
+        let pool = shm.create_pool(fd.as_fd(), stride * height, &self.handle(), ());
+        pool.create_buffer(0, width, height, stride, format, &self.handle(), ())
+    }
+}
@@ -19,6 +19,7 @@
 //! # struct State { seat_state: SeatState<Self> };
 //!
 //! delegate_seat!(State);
+//! # impl smithay::wayland::protocol_error::ProtocolErrorHandler for State {}
 //! // Delegate text input handling for State to TextInputManagerState.
 //! delegate_text_input_manager!(State);
 //!
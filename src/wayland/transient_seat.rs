@@ -0,0 +1,337 @@
+//! Utilities for handling the `ext-transient-seat` protocol
+//!
+//! This protocol allows privileged clients, like remote desktop servers, to create short-lived seats.
+//! The seats are typically used together with virtual input protocols, like
+//! [`virtual_keyboard`](crate::wayland::virtual_keyboard), which allow clients to select a seat.
+//!
+//! Seats are created with [`SeatState::new_wl_seat`] and announced to the compositor through
+//! [`TransientSeatHandler::new_transient_seat`]. They do not have any capabilities on their own.
+//! Once the client destroys its transient seat or disconnects, the seat is passed to
+//! [`TransientSeatHandler::transient_seat_destroyed`] and removed with [`SeatState::remove_wl_seat`],
+//! removing all its capabilities.
+//!
+//! This module requires the `libwayland_server_1_22` feature, to announce the global name
+//! of the created seats.
+//!
+//! ## How to use it
+//!
+//! ```
+//! # extern crate wayland_server;
+//! use smithay::delegate_transient_seat;
+//! use smithay::input::Seat;
+//! use smithay::wayland::transient_seat::{TransientSeatHandler, TransientSeatManagerState};
+//! # use smithay::input::{SeatHandler, SeatState};
+//! # use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
+//!
+//! # use smithay::wayland::compositor::{CompositorClientState, CompositorHandler, CompositorState};
+//! # struct State { seat_state: SeatState<State>, compositor_state: CompositorState }
+//! # impl CompositorHandler for State {
+//! #     fn compositor_state(&mut self) -> &mut CompositorState { &mut self.compositor_state }
+//! #     fn client_compositor_state<'a>(&self, client: &'a wayland_server::Client) -> &'a CompositorClientState { todo!() }
+//! #     fn commit(&mut self, surface: &WlSurface) {}
+//! # }
+//! # impl SeatHandler for State {
+//! #     type KeyboardFocus = WlSurface;
+//! #     type PointerFocus = WlSurface;
+//! #     type TouchFocus = WlSurface;
+//! #     fn seat_state(&mut self) -> &mut SeatState<Self> { &mut self.seat_state }
+//! # }
+//! # smithay::delegate_seat!(State);
+//! # impl smithay::wayland::protocol_error::ProtocolErrorHandler for State {}
+//! # let mut display = wayland_server::Display::<State>::new().unwrap();
+//! // Create the transient seat manager, only exposed to privileged clients
+//! let transient_seat_manager_state =
+//!     TransientSeatManagerState::new::<State, _>(&display.handle(), |_client| true);
+//!
+//! // Implement the necessary trait
+//! impl TransientSeatHandler for State {
+//!     fn new_transient_seat(&mut self, seat: Seat<Self>) {
+//!         // e.g. keep track of the seat to route virtual input devices to it
+//!     }
+//! }
+//!
+//! delegate_transient_seat!(State);
+//! ```
+
+use std::sync::Mutex;
+
+use tracing::warn;
+use wayland_protocols::ext::transient_seat::v1::server::{
+    ext_transient_seat_manager_v1::{self, ExtTransientSeatManagerV1},
+    ext_transient_seat_v1::{self, ExtTransientSeatV1},
+};
+use wayland_server::{
+    backend::{ClientId, GlobalId},
+    protocol::wl_seat::WlSeat,
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource,
+};
+
+use crate::{
+    input::{Seat, SeatHandler},
+    wayland::seat::{SeatGlobalData, WaylandFocus},
+};
+
+/// Handler for the transient seat protocol
+pub trait TransientSeatHandler: SeatHandler {
+    /// A client created a new transient seat
+    ///
+    /// The seat has no capabilities, until they are added by the compositor or other protocols.
+    fn new_transient_seat(&mut self, seat: Seat<Self>) {
+        let _ = seat;
+    }
+
+    /// A transient seat is about to be removed, because its client destroyed it or disconnected
+    fn transient_seat_destroyed(&mut self, seat: &Seat<Self>) {
+        let _ = seat;
+    }
+}
+
+/// State of the [`ExtTransientSeatManagerV1`] global
+#[derive(Debug)]
+pub struct TransientSeatManagerState {
+    global: GlobalId,
+}
+
+impl TransientSeatManagerState {
+    /// Create a new [`ExtTransientSeatManagerV1`] global
+    ///
+    /// The filter decides which clients are allowed to create transient seats.
+    pub fn new<D, F>(display: &DisplayHandle, filter: F) -> Self
+    where
+        D: GlobalDispatch<ExtTransientSeatManagerV1, TransientSeatManagerGlobalData>,
+        D: Dispatch<ExtTransientSeatManagerV1, ()>,
+        D: Dispatch<ExtTransientSeatV1, TransientSeatUserData<D>>,
+        D: TransientSeatHandler,
+        D: 'static,
+        F: for<'c> Fn(&'c Client) -> bool + Send + Sync + 'static,
+    {
+        let data = TransientSeatManagerGlobalData {
+            filter: Box::new(filter),
+        };
+        let global = display.create_global::<D, ExtTransientSeatManagerV1, _>(1, data);
+
+        TransientSeatManagerState { global }
+    }
+
+    /// Returns the id of the [`ExtTransientSeatManagerV1`] global
+    pub fn global(&self) -> GlobalId {
+        self.global.clone()
+    }
+}
+
+#[allow(missing_debug_implementations)]
+#[doc(hidden)]
+pub struct TransientSeatManagerGlobalData {
+    filter: Box<dyn for<'c> Fn(&'c Client) -> bool + Send + Sync>,
+}
+
+/// User data of the [`ExtTransientSeatV1`] resource
+#[derive(Debug)]
+pub struct TransientSeatUserData<D: SeatHandler> {
+    // `None` if the creation was denied or the seat was already removed
+    seat: Mutex<Option<Seat<D>>>,
+}
+
+impl<D> GlobalDispatch<ExtTransientSeatManagerV1, TransientSeatManagerGlobalData, D>
+    for TransientSeatManagerState
+where
+    D: GlobalDispatch<ExtTransientSeatManagerV1, TransientSeatManagerGlobalData>,
+    D: Dispatch<ExtTransientSeatManagerV1, ()>,
+    D: Dispatch<ExtTransientSeatV1, TransientSeatUserData<D>>,
+    D: TransientSeatHandler,
+    D: 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _dh: &DisplayHandle,
+        _client: &Client,
+        resource: New<ExtTransientSeatManagerV1>,
+        _global_data: &TransientSeatManagerGlobalData,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        data_init.init(resource, ());
+    }
+
+    fn can_view(client: Client, global_data: &TransientSeatManagerGlobalData) -> bool {
+        (global_data.filter)(&client)
+    }
+}
+
+impl<D> Dispatch<ExtTransientSeatManagerV1, (), D> for TransientSeatManagerState
+where
+    D: Dispatch<ExtTransientSeatManagerV1, ()>,
+    D: Dispatch<ExtTransientSeatV1, TransientSeatUserData<D>>,
+    D: GlobalDispatch<WlSeat, SeatGlobalData<D>>,
+    D: TransientSeatHandler,
+    <D as SeatHandler>::PointerFocus: WaylandFocus,
+    <D as SeatHandler>::KeyboardFocus: WaylandFocus,
+    D: 'static,
+{
+    fn request(
+        state: &mut D,
+        client: &Client,
+        _resource: &ExtTransientSeatManagerV1,
+        request: ext_transient_seat_manager_v1::Request,
+        _data: &(),
+        dh: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            ext_transient_seat_manager_v1::Request::Create { seat } => {
+                // seat names have to be unique among all seats
+                let seats = &state.seat_state().seats;
+                let name = (0..)
+                    .map(|id| format!("transient-{id}"))
+                    .find(|name| seats.iter().all(|seat| seat.name() != name))
+                    .unwrap();
+                let new_seat = state.seat_state().new_wl_seat(dh, name);
+
+                let global_name = new_seat
+                    .global()
+                    .and_then(|global| dh.backend_handle().global_name(global, client.id()));
+                let Some(global_name) = global_name else {
+                    warn!("Unable to determine the global name of a transient seat");
+                    state.seat_state().remove_wl_seat(dh, &new_seat);
+                    let resource = data_init.init(
+                        seat,
+                        TransientSeatUserData {
+                            seat: Mutex::new(None),
+                        },
+                    );
+                    resource.denied();
+                    return;
+                };
+
+                let resource = data_init.init(
+                    seat,
+                    TransientSeatUserData {
+                        seat: Mutex::new(Some(new_seat.clone())),
+                    },
+                );
+                resource.ready(global_name);
+                state.new_transient_seat(new_seat);
+            }
+            ext_transient_seat_manager_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<ExtTransientSeatV1, TransientSeatUserData<D>, D> for TransientSeatManagerState
+where
+    D: Dispatch<ExtTransientSeatV1, TransientSeatUserData<D>>,
+    D: TransientSeatHandler,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        _resource: &ExtTransientSeatV1,
+        request: ext_transient_seat_v1::Request,
+        _data: &TransientSeatUserData<D>,
+        _dh: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            // the seat is removed in `destroyed`, which also covers disconnecting clients
+            ext_transient_seat_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(
+        state: &mut D,
+        _client: ClientId,
+        resource: &ExtTransientSeatV1,
+        data: &TransientSeatUserData<D>,
+    ) {
+        let Some(seat) = data.seat.lock().unwrap().take() else {
+            return;
+        };
+        let Some(dh) = resource.handle().upgrade().map(DisplayHandle::from) else {
+            return;
+        };
+
+        state.transient_seat_destroyed(&seat);
+        state.seat_state().remove_wl_seat(&dh, &seat);
+    }
+}
+
+/// Macro to delegate implementation of the transient seat protocol to [`TransientSeatManagerState`].
+///
+/// You must also implement [`TransientSeatHandler`] to use this.
+#[macro_export]
+macro_rules! delegate_transient_seat {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        $crate::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::ext::transient_seat::v1::server::ext_transient_seat_manager_v1::ExtTransientSeatManagerV1: $crate::wayland::transient_seat::TransientSeatManagerGlobalData
+        ] => $crate::wayland::transient_seat::TransientSeatManagerState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::ext::transient_seat::v1::server::ext_transient_seat_manager_v1::ExtTransientSeatManagerV1: ()
+        ] => $crate::wayland::transient_seat::TransientSeatManagerState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::ext::transient_seat::v1::server::ext_transient_seat_v1::ExtTransientSeatV1: $crate::wayland::transient_seat::TransientSeatUserData<$ty>
+        ] => $crate::wayland::transient_seat::TransientSeatManagerState);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use wayland_client::Proxy;
+    use wayland_protocols::ext::transient_seat::v1::client::ext_transient_seat_manager_v1::ExtTransientSeatManagerV1 as ClientManager;
+
+    use super::*;
+    use crate::wayland::test_utils::{ClientEvent, Fixture, TestState};
+
+    #[test]
+    fn ready_with_seat_global_name() {
+        let mut fixture = Fixture::new();
+        TransientSeatManagerState::new::<TestState, _>(&fixture.display.handle(), |_| true);
+        fixture.roundtrip();
+
+        let manager: ClientManager = fixture.bind(1);
+        let first = manager.create(&fixture.handle(), ());
+        let second = manager.create(&fixture.handle(), ());
+        fixture.roundtrip();
+
+        let names = fixture
+            .client
+            .events
+            .iter()
+            .map(|event| match event {
+                ClientEvent::TransientSeatReady(name) => *name,
+                event => panic!("unexpected event {event:?}"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(names.len(), 2);
+        for name in &names {
+            assert!(fixture
+                .client
+                .globals
+                .iter()
+                .any(|(global, interface, _)| global == name && interface == "wl_seat"));
+        }
+
+        let seats = |fixture: &Fixture| {
+            fixture
+                .state
+                .seat_state
+                .seats
+                .iter()
+                .map(|seat| seat.name().to_owned())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(seats(&fixture), ["transient-0", "transient-1"]);
+
+        // the name of a removed seat is reused
+        first.destroy();
+        fixture.roundtrip();
+        assert_eq!(seats(&fixture), ["transient-1"]);
+        manager.create(&fixture.handle(), ());
+        fixture.roundtrip();
+        assert_eq!(seats(&fixture), ["transient-1", "transient-0"]);
+        assert!(second.is_alive());
+    }
+}
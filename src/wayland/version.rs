@@ -0,0 +1,54 @@
+//! Versioning of wayland objects
+//!
+//! Clients bind every global at a version of their choice, which the objects created from it inherit.
+//! Events introduced in later versions of an interface must not be sent to objects bound at an older
+//! version. For every request and event the protocol bindings provide a `REQ_<NAME>_SINCE` or
+//! `EVT_<NAME>_SINCE` constant, which should be used together with [`VersionedResource`] instead of
+//! comparing against plain version numbers.
+//!
+//! ```no_run
+//! use smithay::reexports::wayland_server::protocol::wl_pointer::{self, WlPointer};
+//! use smithay::wayland::version::VersionedResource;
+//!
+//! # let pointer: WlPointer = todo!();
+//! // only sent to pointers bound at version 5 or later
+//! pointer.send_since(wl_pointer::EVT_FRAME_SINCE, WlPointer::frame);
+//! ```
+
+use wayland_server::Resource;
+
+/// Extension of [`Resource`] to gate requests and events on the version an object was bound at
+pub trait VersionedResource: Resource {
+    /// Returns whether the object was bound at least at version `since`
+    #[inline]
+    fn supports(&self, since: u32) -> bool {
+        self.version() >= since
+    }
+
+    /// Calls `send` with the object, if it was bound at least at version `since`
+    ///
+    /// Returns whether `send` was called.
+    #[inline]
+    fn send_since(&self, since: u32, send: impl FnOnce(&Self)) -> bool {
+        let supported = self.supports(since);
+        if supported {
+            send(self);
+        }
+        supported
+    }
+
+    /// Calls `send` with the object, if it was bound at a version older than `until`
+    ///
+    /// Used for events that were deprecated or replaced in version `until`.
+    /// Returns whether `send` was called.
+    #[inline]
+    fn send_before(&self, until: u32, send: impl FnOnce(&Self)) -> bool {
+        let supported = !self.supports(until);
+        if supported {
+            send(self);
+        }
+        supported
+    }
+}
+
+impl<R: Resource> VersionedResource for R {}
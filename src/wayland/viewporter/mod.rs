@@ -31,6 +31,7 @@
 //! #     fn commit(&mut self, surface: &WlSurface) {}
 //! # }
 //! # delegate_compositor!(State);
+//! # impl smithay::wayland::protocol_error::ProtocolErrorHandler for State {}
 //!
 //! // You're now ready to go!
 //! ```
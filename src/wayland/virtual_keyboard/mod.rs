@@ -18,6 +18,7 @@
 //! # struct State { seat_state: SeatState<Self> };
 //!
 //! delegate_seat!(State);
+//! # impl smithay::wayland::protocol_error::ProtocolErrorHandler for State {}
 //! // Delegate virtual keyboard handling for State to VirtualKeyboardManagerState.
 //! delegate_virtual_keyboard_manager!(State);
 //!
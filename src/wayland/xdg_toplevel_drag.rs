@@ -0,0 +1,328 @@
+//! XDG Toplevel Drag
+//!
+//! This protocol allows clients to attach a toplevel to a drag'n'drop operation, which is then moved
+//! with the cursor, e.g. to tear off tabs of browsers or terminals.
+//!
+//! In order to advertise the toplevel drag global call [`XdgToplevelDragState::new`] and delegate
+//! events to it with [`delegate_xdg_toplevel_drag`][crate::delegate_xdg_toplevel_drag].
+//!
+//! The client initiated drag'n'drop grab moves an attached toplevel by calling
+//! [`ClientDndGrabHandler::toplevel_drag_motion`](crate::wayland::selection::data_device::ClientDndGrabHandler::toplevel_drag_motion)
+//! with the location the geometry of the toplevel should be placed at. With the desktop abstractions
+//! this is done by forwarding it to
+//! [`Space::relocate_dragged_toplevel`](crate::desktop::Space::relocate_dragged_toplevel).
+//! Toplevels attached before they are mapped should be mapped at the cursor location minus the offset
+//! passed to [`XdgToplevelDragHandler::toplevel_attached`].
+//!
+//! ```
+//! use smithay::wayland::xdg_toplevel_drag::{XdgToplevelDragState, XdgToplevelDragHandler};
+//! use smithay::delegate_xdg_toplevel_drag;
+//! # use smithay::wayland::shell::xdg::{XdgShellState, XdgShellHandler, ToplevelSurface, PopupSurface, PositionerState};
+//! # use smithay::reexports::wayland_server::protocol::wl_seat;
+//! # use smithay::utils::Serial;
+//!
+//! # struct State;
+//! # impl XdgShellHandler for State {
+//! #     fn xdg_shell_state(&mut self) -> &mut XdgShellState { unimplemented!() }
+//! #     fn new_toplevel(&mut self, surface: ToplevelSurface) { unimplemented!() }
+//! #     fn new_popup(&mut self, surface: PopupSurface, positioner: PositionerState) { unimplemented!() }
+//! #     fn grab(&mut self, surface: PopupSurface, seat: wl_seat::WlSeat, serial: Serial) { unimplemented!() }
+//! #     fn reposition_request(&mut self, surface: PopupSurface, positioner: PositionerState, token: u32) { unimplemented!() }
+//! # }
+//! # let mut display = wayland_server::Display::<State>::new().unwrap();
+//! XdgToplevelDragState::new::<State>(&display.handle());
+//!
+//! // provide the necessary trait implementations
+//! impl XdgToplevelDragHandler for State {}
+//!
+//! delegate_xdg_toplevel_drag!(State);
+//! ```
+
+use std::sync::Mutex;
+
+use wayland_protocols::xdg::toplevel_drag::v1::server::{
+    xdg_toplevel_drag_manager_v1::{self, XdgToplevelDragManagerV1},
+    xdg_toplevel_drag_v1::{self, XdgToplevelDragV1},
+};
+use wayland_server::{
+    backend::GlobalId,
+    protocol::{wl_data_source::WlDataSource, wl_surface::WlSurface},
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource,
+};
+
+use crate::{
+    utils::{HookId, Logical, Point},
+    wayland::{
+        compositor::{self, BufferAssignment, SurfaceAttributes},
+        selection::data_device::DataSourceUserData,
+        shell::xdg::{ToplevelSurface, XdgShellHandler},
+    },
+};
+
+/// Handler for xdg toplevel drag requests
+#[allow(unused_variables)]
+pub trait XdgToplevelDragHandler:
+    XdgShellHandler
+    + GlobalDispatch<XdgToplevelDragManagerV1, ()>
+    + Dispatch<XdgToplevelDragManagerV1, ()>
+    + Dispatch<XdgToplevelDragV1, XdgToplevelDragUserData>
+    + 'static
+{
+    /// A toplevel was attached to a drag'n'drop operation
+    ///
+    /// `offset` is the position of the cursor relative to the geometry of the toplevel.
+    /// Toplevels, which are not mapped yet, should be mapped at this offset from the cursor.
+    fn toplevel_attached(&mut self, toplevel: ToplevelSurface, offset: Point<i32, Logical>) {}
+
+    /// A toplevel was detached from a drag'n'drop operation, because it was unmapped
+    fn toplevel_detached(&mut self, toplevel: ToplevelSurface) {}
+}
+
+/// State of the xdg toplevel drag
+#[derive(Debug)]
+pub struct XdgToplevelDragState {
+    global_id: GlobalId,
+}
+
+impl XdgToplevelDragState {
+    /// Register new [XdgToplevelDragManagerV1] global
+    pub fn new<D: XdgToplevelDragHandler>(display: &DisplayHandle) -> Self {
+        let global_id = display.create_global::<D, XdgToplevelDragManagerV1, ()>(1, ());
+        Self { global_id }
+    }
+
+    /// [XdgToplevelDragManagerV1] GlobalId getter
+    pub fn global(&self) -> GlobalId {
+        self.global_id.clone()
+    }
+}
+
+/// User data of [XdgToplevelDragV1]
+#[derive(Debug)]
+pub struct XdgToplevelDragUserData {
+    manager: XdgToplevelDragManagerV1,
+    inner: Mutex<ToplevelDragInner>,
+}
+
+#[derive(Debug, Default)]
+struct ToplevelDragInner {
+    attached: Option<AttachedToplevel>,
+    dragging: bool,
+}
+
+#[derive(Debug)]
+struct AttachedToplevel {
+    toplevel: ToplevelSurface,
+    offset: Point<i32, Logical>,
+    unmap_hook: HookId,
+}
+
+impl XdgToplevelDragUserData {
+    fn detach(&self) -> Option<ToplevelSurface> {
+        let attached = self.inner.lock().unwrap().attached.take()?;
+        compositor::remove_post_commit_hook(attached.toplevel.wl_surface(), attached.unmap_hook);
+        Some(attached.toplevel)
+    }
+}
+
+fn with_toplevel_drag<T>(source: &WlDataSource, f: impl FnOnce(&XdgToplevelDragUserData) -> T) -> Option<T> {
+    let drag = source
+        .data::<DataSourceUserData>()?
+        .toplevel_drag
+        .lock()
+        .unwrap()
+        .clone()?;
+    let data = drag.data::<XdgToplevelDragUserData>()?;
+    Some(f(data))
+}
+
+/// Returns the toplevel attached to a drag'n'drop source and its offset from the cursor
+pub fn attached_toplevel(source: &WlDataSource) -> Option<(ToplevelSurface, Point<i32, Logical>)> {
+    with_toplevel_drag(source, |data| {
+        let inner = data.inner.lock().unwrap();
+        inner
+            .attached
+            .as_ref()
+            .filter(|attached| attached.toplevel.alive())
+            .map(|attached| (attached.toplevel.clone(), attached.offset))
+    })
+    .flatten()
+}
+
+pub(crate) fn set_dragging(source: &WlDataSource, dragging: bool) {
+    with_toplevel_drag(source, |data| {
+        data.inner.lock().unwrap().dragging = dragging;
+    });
+}
+
+// Unmapped toplevels are detached by their commit hook, so only destroyed ones have to be skipped
+fn has_valid_toplevel(data: &XdgToplevelDragUserData) -> bool {
+    data.inner
+        .lock()
+        .unwrap()
+        .attached
+        .as_ref()
+        .is_some_and(|attached| attached.toplevel.alive())
+}
+
+fn unmap_hook<D: XdgToplevelDragHandler>(
+    drag: XdgToplevelDragV1,
+) -> impl Fn(&mut D, &DisplayHandle, &WlSurface) {
+    move |state, _, surface| {
+        let unmapped = compositor::with_states(surface, |states| {
+            matches!(
+                states.cached_state.get::<SurfaceAttributes>().current().buffer,
+                Some(BufferAssignment::Removed)
+            )
+        });
+        if !unmapped {
+            return;
+        }
+        if let Some(toplevel) = drag
+            .data::<XdgToplevelDragUserData>()
+            .and_then(|data| data.detach())
+        {
+            state.toplevel_detached(toplevel);
+        }
+    }
+}
+
+impl<D: XdgToplevelDragHandler> GlobalDispatch<XdgToplevelDragManagerV1, (), D> for XdgToplevelDragState {
+    fn bind(
+        _state: &mut D,
+        _handle: &DisplayHandle,
+        _client: &Client,
+        resource: New<XdgToplevelDragManagerV1>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        data_init.init(resource, ());
+    }
+}
+
+impl<D: XdgToplevelDragHandler> Dispatch<XdgToplevelDragManagerV1, (), D> for XdgToplevelDragState {
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        resource: &XdgToplevelDragManagerV1,
+        request: xdg_toplevel_drag_manager_v1::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            xdg_toplevel_drag_manager_v1::Request::GetXdgToplevelDrag { id, data_source } => {
+                let Some(source_data) = data_source.data::<DataSourceUserData>() else {
+                    return;
+                };
+                let mut toplevel_drag = source_data.toplevel_drag.lock().unwrap();
+                if toplevel_drag.as_ref().is_some_and(|drag| drag.is_alive()) {
+                    resource.post_error(
+                        xdg_toplevel_drag_manager_v1::Error::InvalidSource,
+                        "data_source already used for toplevel drag",
+                    );
+                    return;
+                }
+
+                let drag = data_init.init(
+                    id,
+                    XdgToplevelDragUserData {
+                        manager: resource.clone(),
+                        inner: Default::default(),
+                    },
+                );
+                *toplevel_drag = Some(drag);
+            }
+            xdg_toplevel_drag_manager_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D: XdgToplevelDragHandler> Dispatch<XdgToplevelDragV1, XdgToplevelDragUserData, D>
+    for XdgToplevelDragState
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        resource: &XdgToplevelDragV1,
+        request: xdg_toplevel_drag_v1::Request,
+        data: &XdgToplevelDragUserData,
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            xdg_toplevel_drag_v1::Request::Attach {
+                toplevel,
+                x_offset,
+                y_offset,
+            } => {
+                if has_valid_toplevel(data) {
+                    resource.post_error(
+                        xdg_toplevel_drag_v1::Error::ToplevelAttached,
+                        "valid toplevel already attached",
+                    );
+                    return;
+                }
+                let Some(toplevel) = state.xdg_shell_state().get_toplevel(&toplevel) else {
+                    return;
+                };
+                data.detach();
+
+                let offset = Point::from((x_offset, y_offset));
+                let unmap_hook = compositor::add_post_commit_hook::<D, _>(
+                    toplevel.wl_surface(),
+                    unmap_hook(resource.clone()),
+                );
+                data.inner.lock().unwrap().attached = Some(AttachedToplevel {
+                    toplevel: toplevel.clone(),
+                    offset,
+                    unmap_hook,
+                });
+                state.toplevel_attached(toplevel, offset);
+            }
+            xdg_toplevel_drag_v1::Request::Destroy => {
+                if data.inner.lock().unwrap().dragging {
+                    resource.post_error(xdg_toplevel_drag_v1::Error::OngoingDrag, "drag has not ended");
+                    return;
+                }
+                data.detach();
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// Posts an `invalid_source` error if the source of a toplevel drag is used for anything
+/// but drag'n'drop
+///
+/// Returns `true` if the error was posted.
+pub(crate) fn reject_non_dnd_source(source: &WlDataSource) -> bool {
+    with_toplevel_drag(source, |data| {
+        data.manager.post_error(
+            xdg_toplevel_drag_manager_v1::Error::InvalidSource,
+            "data_source used for toplevel drag",
+        );
+    })
+    .is_some()
+}
+
+/// Macro to delegate implementation of the xdg toplevel drag to [`XdgToplevelDragState`].
+///
+/// You must also implement [`XdgToplevelDragHandler`] to use this.
+#[macro_export]
+macro_rules! delegate_xdg_toplevel_drag {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        $crate::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::xdg::toplevel_drag::v1::server::xdg_toplevel_drag_manager_v1::XdgToplevelDragManagerV1: ()
+        ] => $crate::wayland::xdg_toplevel_drag::XdgToplevelDragState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::xdg::toplevel_drag::v1::server::xdg_toplevel_drag_manager_v1::XdgToplevelDragManagerV1: ()
+        ] => $crate::wayland::xdg_toplevel_drag::XdgToplevelDragState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::xdg::toplevel_drag::v1::server::xdg_toplevel_drag_v1::XdgToplevelDragV1: $crate::wayland::xdg_toplevel_drag::XdgToplevelDragUserData
+        ] => $crate::wayland::xdg_toplevel_drag::XdgToplevelDragState);
+    };
+}
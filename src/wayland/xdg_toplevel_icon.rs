@@ -4,7 +4,8 @@
 //!
 //! In order to advertise toplevel icon global call [XdgToplevelIconManager::new] and delegate
 //! events to it with [`delegate_xdg_toplevel_icon`][crate::delegate_xdg_toplevel_icon].
-//! Currently attached icon is available in double-buffered [ToplevelIconCachedState]
+//! Currently attached icon is available in double-buffered [ToplevelIconCachedState],
+//! which also provides [ToplevelIconCachedState::import_texture] to render pixel based icons.
 
 use std::{
     collections::HashSet,
@@ -26,7 +27,8 @@ use wayland_server::{
 };
 
 use crate::{
-    utils::HookId,
+    backend::renderer::ImportMemWl,
+    utils::{HookId, Rectangle},
     wayland::{
         compositor::{self, Cacheable},
         shell::xdg::XdgShellSurfaceUserData,
@@ -91,6 +93,57 @@ impl ToplevelIconCachedState {
         };
         &data.buffers
     }
+
+    /// Picks the icon buffer best suited to be displayed at `size` logical pixels on an output with `scale`
+    ///
+    /// Prefers an exact match of buffer size and scale, then the smallest buffer at least as large as
+    /// the requested size in buffer pixels, and otherwise the largest buffer available.
+    pub fn best_buffer(&self, size: i32, scale: i32) -> Option<&WlBuffer> {
+        let target = size * scale;
+        let buffers = self
+            .buffers()
+            .iter()
+            .filter_map(|(buffer, buffer_scale)| Some((buffer, *buffer_scale, icon_buffer_size(buffer)?)));
+
+        let mut smallest_larger: Option<(&WlBuffer, i32)> = None;
+        let mut largest: Option<(&WlBuffer, i32)> = None;
+        for (buffer, buffer_scale, buffer_size) in buffers {
+            if buffer_scale == scale && buffer_size == target {
+                return Some(buffer);
+            }
+            if buffer_size >= target && smallest_larger.map_or(true, |(_, size)| buffer_size < size) {
+                smallest_larger = Some((buffer, buffer_size));
+            }
+            if largest.map_or(true, |(_, size)| buffer_size > size) {
+                largest = Some((buffer, buffer_size));
+            }
+        }
+        smallest_larger.or(largest).map(|(buffer, _)| buffer)
+    }
+
+    /// Imports the [best buffer](Self::best_buffer) for the given size and scale into a renderer texture
+    ///
+    /// Returns `Ok(None)` if no pixel based icon is set. Icon buffers are immutable, so the texture
+    /// can be cached until the icon changes.
+    pub fn import_texture<R: ImportMemWl>(
+        &self,
+        renderer: &mut R,
+        size: i32,
+        scale: i32,
+    ) -> Result<Option<R::TextureId>, R::Error> {
+        let Some(buffer) = self.best_buffer(size, scale) else {
+            return Ok(None);
+        };
+        let Some(buffer_size) = icon_buffer_size(buffer) else {
+            return Ok(None);
+        };
+        let damage = Rectangle::from_size((buffer_size, buffer_size).into());
+        renderer.import_shm_buffer(buffer, None, &[damage]).map(Some)
+    }
+}
+
+fn icon_buffer_size(buffer: &WlBuffer) -> Option<i32> {
+    buffer.data::<ShmBufferUserData>().map(|shm| shm.data.width)
 }
 
 impl Cacheable for ToplevelIconCachedState {
@@ -0,0 +1,184 @@
+//! XDG Toplevel Tag
+//!
+//! This protocol allows clients to set a tag and a description for their toplevels.
+//! Unlike the title, the tag identifies a toplevel across restarts of the application,
+//! so compositors can use it to persist window properties or to match window rules.
+//!
+//! In order to advertise toplevel tag global call [`XdgToplevelTagState::new`] and delegate
+//! events to it with [`delegate_xdg_toplevel_tag`][crate::delegate_xdg_toplevel_tag].
+//! The tag and description are available in the
+//! [`XdgToplevelSurfaceRoleAttributes`](crate::wayland::shell::xdg::XdgToplevelSurfaceRoleAttributes)
+//! of the toplevel.
+//!
+//! ```no_run
+//! use smithay::wayland::xdg_toplevel_tag::{XdgToplevelTagState, XdgToplevelTagHandler};
+//! use smithay::wayland::shell::xdg::{ToplevelSurface, XdgShellHandler, XdgShellState, PopupSurface, PositionerState};
+//! use smithay::delegate_xdg_toplevel_tag;
+//! # use smithay::reexports::wayland_server::protocol::wl_seat;
+//! # use smithay::utils::Serial;
+//!
+//! # struct State;
+//! # let mut display = wayland_server::Display::<State>::new().unwrap();
+//! XdgToplevelTagState::new::<State>(
+//!     &display.handle(),
+//! );
+//!
+//! // provide the necessary trait implementations
+//! impl XdgToplevelTagHandler for State {
+//!     fn tag_changed(&mut self, toplevel: ToplevelSurface) {
+//!         // apply window rules for the tag
+//!     }
+//! }
+//! # impl XdgShellHandler for State {
+//! #     fn xdg_shell_state(&mut self) -> &mut XdgShellState { unimplemented!() }
+//! #     fn new_toplevel(&mut self, surface: ToplevelSurface) { unimplemented!() }
+//! #     fn new_popup(&mut self, surface: PopupSurface, positioner: PositionerState) { unimplemented!() }
+//! #     fn grab(&mut self, surface: PopupSurface, seat: wl_seat::WlSeat, serial: Serial) { unimplemented!() }
+//! #     fn reposition_request(&mut self, surface: PopupSurface, positioner: PositionerState, token: u32) { unimplemented!() }
+//! # }
+//! # impl smithay::input::SeatHandler for State {
+//! #     type KeyboardFocus = wayland_server::protocol::wl_surface::WlSurface;
+//! #     type PointerFocus = wayland_server::protocol::wl_surface::WlSurface;
+//! #     type TouchFocus = wayland_server::protocol::wl_surface::WlSurface;
+//! #     fn seat_state(&mut self) -> &mut smithay::input::SeatState<Self> { unimplemented!() }
+//! # }
+//! # smithay::delegate_xdg_shell!(State);
+//! # impl smithay::wayland::protocol_error::ProtocolErrorHandler for State {}
+//!
+//! delegate_xdg_toplevel_tag!(State);
+//! ```
+
+use wayland_protocols::xdg::toplevel_tag::v1::server::xdg_toplevel_tag_manager_v1::{
+    self, XdgToplevelTagManagerV1,
+};
+use wayland_server::{backend::GlobalId, Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New};
+
+use crate::wayland::{
+    compositor,
+    shell::xdg::{
+        ToplevelSurface, XdgShellHandler, XdgToplevelSurfaceData, XdgToplevelSurfaceRoleAttributes,
+    },
+};
+
+/// Handler for xdg toplevel tag requests
+pub trait XdgToplevelTagHandler:
+    XdgShellHandler
+    + GlobalDispatch<XdgToplevelTagManagerV1, ()>
+    + Dispatch<XdgToplevelTagManagerV1, ()>
+    + 'static
+{
+    /// The client changed the tag of a toplevel
+    fn tag_changed(&mut self, toplevel: ToplevelSurface) {
+        let _ = toplevel;
+    }
+
+    /// The client changed the description of a toplevel
+    fn description_changed(&mut self, toplevel: ToplevelSurface) {
+        let _ = toplevel;
+    }
+}
+
+/// State of the xdg toplevel tag global
+#[derive(Debug)]
+pub struct XdgToplevelTagState {
+    global_id: GlobalId,
+}
+
+impl XdgToplevelTagState {
+    /// Register new [XdgToplevelTagManagerV1] global
+    pub fn new<D: XdgToplevelTagHandler>(display: &DisplayHandle) -> Self {
+        let global_id = display.create_global::<D, XdgToplevelTagManagerV1, ()>(1, ());
+        Self { global_id }
+    }
+
+    /// [XdgToplevelTagManagerV1] GlobalId getter
+    pub fn global(&self) -> GlobalId {
+        self.global_id.clone()
+    }
+}
+
+impl<D: XdgToplevelTagHandler> GlobalDispatch<XdgToplevelTagManagerV1, (), D> for XdgToplevelTagState {
+    fn bind(
+        _state: &mut D,
+        _handle: &DisplayHandle,
+        _client: &Client,
+        resource: New<XdgToplevelTagManagerV1>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        data_init.init(resource, ());
+    }
+}
+
+impl<D: XdgToplevelTagHandler> Dispatch<XdgToplevelTagManagerV1, (), D> for XdgToplevelTagState {
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        _resource: &XdgToplevelTagManagerV1,
+        request: xdg_toplevel_tag_manager_v1::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            xdg_toplevel_tag_manager_v1::Request::SetToplevelTag { toplevel, tag } => {
+                let Some(toplevel) = state.xdg_shell_state().get_toplevel(&toplevel) else {
+                    return;
+                };
+                if with_role(&toplevel, |role| replace(&mut role.tag, tag)) {
+                    state.tag_changed(toplevel);
+                }
+            }
+            xdg_toplevel_tag_manager_v1::Request::SetToplevelDescription {
+                toplevel,
+                description,
+            } => {
+                let Some(toplevel) = state.xdg_shell_state().get_toplevel(&toplevel) else {
+                    return;
+                };
+                if with_role(&toplevel, |role| replace(&mut role.tag_description, description)) {
+                    state.description_changed(toplevel);
+                }
+            }
+            xdg_toplevel_tag_manager_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+fn with_role<T>(toplevel: &ToplevelSurface, f: impl FnOnce(&mut XdgToplevelSurfaceRoleAttributes) -> T) -> T {
+    compositor::with_states(toplevel.wl_surface(), |states| {
+        f(&mut states
+            .data_map
+            .get::<XdgToplevelSurfaceData>()
+            .unwrap()
+            .lock()
+            .unwrap())
+    })
+}
+
+/// Returns true if changed
+fn replace(field: &mut Option<String>, value: String) -> bool {
+    if field.as_ref() == Some(&value) {
+        false
+    } else {
+        *field = Some(value);
+        true
+    }
+}
+
+/// Macro to delegate implementation of the xdg toplevel tag to [`XdgToplevelTagState`].
+///
+/// You must also implement [`XdgToplevelTagHandler`] to use this.
+#[macro_export]
+macro_rules! delegate_xdg_toplevel_tag {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        $crate::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::xdg::toplevel_tag::v1::server::xdg_toplevel_tag_manager_v1::XdgToplevelTagManagerV1: ()
+        ] => $crate::wayland::xdg_toplevel_tag::XdgToplevelTagState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::xdg::toplevel_tag::v1::server::xdg_toplevel_tag_manager_v1::XdgToplevelTagManagerV1: ()
+        ] => $crate::wayland::xdg_toplevel_tag::XdgToplevelTagState);
+    };
+}
@@ -60,6 +60,18 @@ const MANAGER_VERSION: u32 = 1;
 
 /// Handler for xwayland keyboard grab protocol
 pub trait XWaylandKeyboardGrabHandler: SeatHandler {
+    /// Decides whether XWayland may grab the keyboard of `seat` for `surface`
+    ///
+    /// This is the place to implement the policy of the compositor, e.g. to only allow
+    /// grabs for the currently focused window or for windows the user approved before.
+    /// If this returns `false`, the request is ignored.
+    ///
+    /// The default implementation allows all grabs.
+    fn allow_grab(&mut self, surface: &wl_surface::WlSurface, seat: &Seat<Self>) -> bool {
+        let _ = (surface, seat);
+        true
+    }
+
     /// XWayland client has requested a keyboard grab for `surface` on `seat`
     ///
     /// The default implementation calls `KeyboardHandle::set_grab` if `seat`
@@ -209,12 +221,18 @@ where
         match request {
             zwp_xwayland_keyboard_grab_manager_v1::Request::GrabKeyboard { id, surface, seat } => {
                 let grab = data_init.init(id, ());
+                // the seat may have been removed in the meantime
+                let Some(seat) = Seat::from_resource(&seat) else {
+                    return;
+                };
+                if !state.allow_grab(&surface, &seat) {
+                    return;
+                }
                 if let Some(focus) = state.keyboard_focus_for_xsurface(&surface) {
                     let grab = XWaylandKeyboardGrab {
                         grab,
                         start_data: keyboard::GrabStartData { focus: Some(focus) },
                     };
-                    let seat = Seat::from_resource(&seat).unwrap();
                     state.grab(surface, seat, grab);
                 }
             }
@@ -57,6 +57,7 @@
 //!
 //! // implement Dispatch for your state.
 //! delegate_xwayland_shell!(State);
+//! # impl smithay::wayland::protocol_error::ProtocolErrorHandler for State {}
 //! ```
 
 use std::collections::HashMap;
@@ -73,7 +74,10 @@ use wayland_server::{
 };
 
 use crate::{
-    wayland::compositor,
+    wayland::{
+        compositor,
+        protocol_error::{post_error, ProtocolErrorHandler, ProtocolErrorKind},
+    },
     xwayland::{xwm::XwmId, X11Surface, XWaylandClientData, XwmHandler},
 };
 
@@ -179,11 +183,11 @@ impl<D> Dispatch<XwaylandShellV1, (), D> for XWaylandShellState
 where
     D: Dispatch<XwaylandShellV1, ()>,
     D: Dispatch<XwaylandSurfaceV1, XWaylandSurfaceUserData>,
-    D: XWaylandShellHandler + XwmHandler,
+    D: XWaylandShellHandler + XwmHandler + ProtocolErrorHandler,
     D: 'static,
 {
     fn request(
-        _state: &mut D,
+        state: &mut D,
         _client: &Client,
         resource: &XwaylandShellV1,
         request: <XwaylandShellV1 as Resource>::Request,
@@ -194,7 +198,13 @@ where
         match request {
             xwayland_shell_v1::Request::GetXwaylandSurface { id, surface } => {
                 if compositor::give_role(&surface, XWAYLAND_SHELL_ROLE).is_err() {
-                    resource.post_error(xwayland_shell_v1::Error::Role, "Surface already has a role.");
+                    post_error(
+                        state,
+                        resource,
+                        ProtocolErrorKind::Role,
+                        xwayland_shell_v1::Error::Role,
+                        "Surface already has a role.",
+                    );
                     return;
                 }
 